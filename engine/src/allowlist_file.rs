@@ -0,0 +1,113 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{fmt::Display, path::Path};
+
+use autocxx_parser::ExternalAllowlist;
+
+/// Problems reading a file named in an `allowlist_file!` directive.
+#[derive(Debug)]
+pub enum AllowlistFileError {
+    /// The file couldn't be read.
+    Io(std::io::Error),
+    /// The file wasn't valid TOML.
+    Toml(toml::de::Error),
+    /// The file contained a key other than `generate`, `generate_pod`,
+    /// `generate_ns` or `block`.
+    UnexpectedKey(String),
+    /// A key's value wasn't a list of strings.
+    NotAListOfStrings(String),
+}
+
+impl Display for AllowlistFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AllowlistFileError::Io(err) => write!(f, "{}", err)?,
+            AllowlistFileError::Toml(err) => write!(f, "{}", err)?,
+            AllowlistFileError::UnexpectedKey(key) => write!(
+                f,
+                "unexpected key '{}'; expected generate, generate_pod, generate_ns or block",
+                key
+            )?,
+            AllowlistFileError::NotAListOfStrings(key) => {
+                write!(f, "'{}' should be a list of strings", key)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read an allowlist file of the form
+/// ```toml
+/// generate = [ "Foo", "ns::bar" ]
+/// generate_pod = [ "Baz" ]
+/// generate_ns = [ "other_ns" ]
+/// block = [ "Qux" ]
+/// ```
+/// where every key is optional.
+pub(crate) fn read_allowlist_file(path: &Path) -> Result<ExternalAllowlist, AllowlistFileError> {
+    let contents = std::fs::read_to_string(path).map_err(AllowlistFileError::Io)?;
+    parse_allowlist(&contents)
+}
+
+fn parse_allowlist(contents: &str) -> Result<ExternalAllowlist, AllowlistFileError> {
+    let table: toml::value::Table = toml::from_str(contents).map_err(AllowlistFileError::Toml)?;
+    let mut allowlist = ExternalAllowlist::default();
+    for (key, value) in table {
+        let list = match key.as_str() {
+            "generate" => &mut allowlist.generate,
+            "generate_pod" => &mut allowlist.generate_pod,
+            "generate_ns" => &mut allowlist.generate_ns,
+            "block" => &mut allowlist.block,
+            _ => return Err(AllowlistFileError::UnexpectedKey(key)),
+        };
+        let items = value
+            .as_array()
+            .and_then(|items| {
+                items
+                    .iter()
+                    .map(|item| item.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| AllowlistFileError::NotAListOfStrings(key.clone()))?;
+        list.extend(items);
+    }
+    Ok(allowlist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_allowlist, AllowlistFileError};
+
+    #[test]
+    fn test_parse_allowlist() {
+        let allowlist = parse_allowlist(
+            r#"
+            generate = [ "Foo", "ns::bar" ]
+            generate_pod = [ "Baz" ]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(allowlist.generate, ["Foo", "ns::bar"]);
+        assert_eq!(allowlist.generate_pod, ["Baz"]);
+        assert!(allowlist.generate_ns.is_empty());
+        assert!(allowlist.block.is_empty());
+    }
+
+    #[test]
+    fn test_parse_allowlist_rejects_unknown_keys() {
+        assert!(matches!(
+            parse_allowlist(r#"generate_all = [ "Foo" ]"#),
+            Err(AllowlistFileError::UnexpectedKey(_))
+        ));
+        assert!(matches!(
+            parse_allowlist(r#"generate = "Foo""#),
+            Err(AllowlistFileError::NotAListOfStrings(_))
+        ));
+    }
+}
@@ -9,11 +9,15 @@
 use autocxx_parser::file_locations::FileLocationStrategy;
 use proc_macro2::TokenStream;
 
-use crate::{strip_system_headers, CppCodegenOptions, ParseError, RebuildDependencyRecorder};
+use crate::{
+    bindgen_callbacks::ParseCallbacks, module_clang_args, strip_system_headers, CppCodegenOptions,
+    ModuleImport, ParseError, RebuildDependencyRecorder,
+};
 use std::ffi::OsString;
 use std::io::Write;
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::{ffi::OsStr, io, process};
 use std::{fmt::Display, fs::File};
 
@@ -51,6 +55,40 @@ impl Display for BuilderError {
 #[cfg_attr(feature = "nightly", doc(cfg(feature = "build")))]
 pub type BuilderBuild = cc::Build;
 
+/// A sanitizer with which the generated C++ can be compiled. See
+/// [`Builder::sanitizer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "nightly", doc(cfg(feature = "build")))]
+pub enum Sanitizer {
+    /// AddressSanitizer, equivalent to Rust's `-Zsanitizer=address`.
+    Address,
+    /// MemorySanitizer, equivalent to Rust's `-Zsanitizer=memory`.
+    Memory,
+    /// UndefinedBehaviorSanitizer, which has no Rust equivalent.
+    UndefinedBehavior,
+}
+
+impl Sanitizer {
+    /// The name used in C++ compilers' `-fsanitize=` flag.
+    fn cpp_name(&self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+            Sanitizer::Memory => "memory",
+            Sanitizer::UndefinedBehavior => "undefined",
+        }
+    }
+
+    /// The sanitizer named in Rust's `-Zsanitizer=` flag, if it's one we
+    /// support.
+    fn from_rust_name(name: &str) -> Option<Self> {
+        match name {
+            "address" => Some(Sanitizer::Address),
+            "memory" => Some(Sanitizer::Memory),
+            _ => None,
+        }
+    }
+}
+
 /// For test purposes only, a [`cc::Build`] and lists of Rust and C++
 /// files generated.
 #[cfg_attr(feature = "nightly", doc(cfg(feature = "build")))]
@@ -84,7 +122,14 @@ pub struct Builder<'a, BuilderContext> {
     dependency_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
     custom_gendir: Option<PathBuf>,
     auto_allowlist: bool,
+    cuda_compatibility: bool,
+    sanitizers: Vec<Sanitizer>,
+    match_rust_sanitizers: bool,
     cpp_codegen_options: CppCodegenOptions<'a>,
+    #[cfg(feature = "manifest")]
+    manifest_file: Option<PathBuf>,
+    snapshot_file: Option<PathBuf>,
+    rust_api_header: Option<PathBuf>,
     // This member is to ensure that this type is parameterized
     // by a BuilderContext. The goal is to balance three needs:
     // (1) have most of the functionality over in autocxx_engine,
@@ -112,7 +157,14 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
             dependency_recorder: CTX::get_dependency_recorder(),
             custom_gendir: None,
             auto_allowlist: false,
+            cuda_compatibility: false,
+            sanitizers: Vec::new(),
+            match_rust_sanitizers: false,
             cpp_codegen_options: CppCodegenOptions::default(),
+            #[cfg(feature = "manifest")]
+            manifest_file: None,
+            snapshot_file: None,
+            rust_api_header: None,
             ctx: PhantomData,
         }
     }
@@ -129,6 +181,50 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
         self
     }
 
+    /// Specify extra arguments for clang which apply only to the given
+    /// header, named as in its `#include` within `include_cpp!`. This is
+    /// useful when headers need conflicting macro definitions. Since all
+    /// the headers are processed together, only `-D` and `-U` are supported;
+    /// the same definitions are applied wherever the generated C++ includes
+    /// that header.
+    pub fn header_clang_args(mut self, header: &str, args: &[&str]) -> Self {
+        self.cpp_codegen_options
+            .header_clang_args
+            .entry(header.to_string())
+            .or_default()
+            .extend(args.iter().map(|s| s.to_string()));
+        self
+    }
+
+    /// Import a C++20 named module, as if by `import name;`, both when
+    /// autocxx reads the headers and in the generated C++. `bmi` is the
+    /// module's prebuilt interface (a `.pcm` file) which clang should use.
+    /// Modules are imported before any of the headers are included, and
+    /// everything is compiled as C++20 unless [`Builder::extra_clang_args`]
+    /// or the resulting [`cc::Build`] say otherwise. Only clang can
+    /// consume these prebuilt interfaces, so the C++ must be built with it.
+    pub fn import_module(mut self, name: &str, bmi: impl AsRef<Path>) -> Self {
+        self.cpp_codegen_options
+            .module_imports
+            .push(ModuleImport::Module {
+                name: name.to_string(),
+                bmi: bmi.as_ref().to_path_buf(),
+            });
+        self
+    }
+
+    /// Import a C++20 header unit, as if by `import "header";`, rather than
+    /// including it textually. See [`Builder::import_module`] for details.
+    pub fn import_header_unit(mut self, header: &str, bmi: impl AsRef<Path>) -> Self {
+        self.cpp_codegen_options
+            .module_imports
+            .push(ModuleImport::HeaderUnit {
+                header: header.to_string(),
+                bmi: bmi.as_ref().to_path_buf(),
+            });
+        self
+    }
+
     /// Update C++ code generation options. See [`CppCodegenOptions`] for details.
     pub fn cpp_codegen_options<F>(mut self, modifier: F) -> Self
     where
@@ -156,6 +252,97 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
         self
     }
 
+    /// Accept headers written for CUDA, such that their host-callable API
+    /// can be used from Rust. The CUDA declaration specifiers (`__host__`,
+    /// `__device__`, `__global__` etc.) are defined away both when autocxx
+    /// reads the headers and when the resulting [`cc::Build`] compiles them,
+    /// and kernels (`__global__` functions) are skipped since they can't be
+    /// called like normal functions. `__CUDACC__` is not defined, so headers
+    /// see the same thing as any other host compiler would. Functions which
+    /// are only `__device__` can't be told apart from `__host__ __device__`
+    /// ones, so they should be guarded by `#ifdef __CUDACC__` or [`block`]ed.
+    ///
+    /// [`block`]: https://docs.rs/autocxx/latest/autocxx/macro.block.html
+    pub fn cuda_compatibility(mut self, do_it: bool) -> Self {
+        self.cuda_compatibility = do_it;
+        self
+    }
+
+    /// Compile the generated C++ with the given sanitizer, and link its
+    /// runtime into any binaries or tests built by this crate unless Rust
+    /// is also using it, in which case rustc links it. The resulting
+    /// [`cc::Build`] is configured likewise, so anything else you compile
+    /// with it is also instrumented. This needs a C++ compiler which
+    /// supports `-fsanitize`, such as clang or gcc.
+    pub fn sanitizer(mut self, sanitizer: Sanitizer) -> Self {
+        if !self.sanitizers.contains(&sanitizer) {
+            self.sanitizers.push(sanitizer);
+        }
+        self
+    }
+
+    /// Compile the generated C++ with whichever of
+    /// [`Sanitizer::Address`] and [`Sanitizer::Memory`] are enabled for
+    /// Rust using `-Zsanitizer` in `RUSTFLAGS`, so that the C++ and Rust
+    /// are instrumented in the same way. See [`Builder::sanitizer`].
+    pub fn match_rust_sanitizers(mut self, do_it: bool) -> Self {
+        self.match_rust_sanitizers = do_it;
+        self
+    }
+
+    /// Compile the generated C++ with instrumentation for LLVM's
+    /// source-based code coverage, which is what Rust's
+    /// `-Cinstrument-coverage` uses, so that one report covers both
+    /// languages. The resulting [`cc::Build`] is configured likewise. Each
+    /// generated wrapper function is annotated with the Rust function which
+    /// calls it. The profiling runtime is linked into any binaries or tests
+    /// built by this crate unless Rust is also being instrumented, in which
+    /// case rustc links it. This needs the C++ to be built with clang.
+    pub fn coverage(mut self, do_it: bool) -> Self {
+        self.cpp_codegen_options.coverage = do_it;
+        self
+    }
+
+    /// Sort the `#include`s in `include_cpp!` by name, so that reordering
+    /// them doesn't change the generated code. Only use this if your
+    /// headers don't depend on the order in which they're included.
+    pub fn sort_includes(mut self, do_it: bool) -> Self {
+        self.cpp_codegen_options.sort_inclusions = do_it;
+        self
+    }
+
+    /// Ask bindgen to ignore C++ items matching this regular expression,
+    /// as well as those which autocxx itself ignores. This is a way to
+    /// work around items which bindgen can't handle. Can be called more
+    /// than once.
+    pub fn bindgen_blocklist(mut self, regex: impl Into<String>) -> Self {
+        self.cpp_codegen_options
+            .bindgen_blocklist
+            .push(regex.into());
+        self
+    }
+
+    /// Supply callbacks for bindgen to make as it parses the headers, for
+    /// example to ignore macros whose names collide. Callbacks which would
+    /// rename items aren't called, since autocxx relies on the names
+    /// matching those in C++. The same callbacks are used for every
+    /// `include_cpp!`.
+    pub fn bindgen_callbacks(mut self, callbacks: impl ParseCallbacks + 'static) -> Self {
+        self.cpp_codegen_options.bindgen_callbacks = Some(Rc::new(callbacks));
+        self
+    }
+
+    /// Split the C++ functions which autocxx generates for each
+    /// `include_cpp!` between this many files, of roughly equal size, so
+    /// that they can be compiled in parallel. This helps for very large
+    /// bindings, especially alongside `bridge_shards!`. The files are all
+    /// added to the resulting [`cc::Build`]; to have it compile them in
+    /// parallel, enable the `parallel` feature of the `cc` crate.
+    pub fn cpp_shards(mut self, shards: usize) -> Self {
+        self.cpp_codegen_options.cpp_shards = shards;
+        self
+    }
+
     /// Whether to suppress inclusion of system headers (`memory`, `string` etc.)
     /// from generated C++ bindings code. This should not normally be used,
     /// but can occasionally be useful if you're reducing a test case and you
@@ -167,7 +354,11 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
     }
 
     /// An annotation optionally to include on each C++ function.
-    /// For example to export the symbol from a library.
+    /// For example to export the symbol from a library. This is also applied
+    /// to the C++ classes which autocxx generates for subclasses, so that
+    /// their methods are exported too - for instance, pass
+    /// `__declspec(dllexport)` (or a macro expanding to it) when building a
+    /// Windows DLL.
     pub fn cxx_impl_annotations(mut self, cxx_impl_annotations: Option<String>) -> Self {
         self.cpp_codegen_options.cxx_impl_annotations = cxx_impl_annotations;
         self
@@ -180,6 +371,39 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
         self
     }
 
+    /// Write a JSON manifest describing every item for which bindings were
+    /// generated (or which couldn't be generated, and why) to this file.
+    /// The manifest is an object with an entry for each `include_cpp!` mod.
+    #[cfg(feature = "manifest")]
+    pub fn manifest_file(mut self, manifest_file: PathBuf) -> Self {
+        self.manifest_file = Some(manifest_file);
+        self
+    }
+
+    /// Write a snapshot of all the generated Rust and C++ to this file. The
+    /// snapshot is the same every time, so long as the headers, the
+    /// `include_cpp!` directives and the version of autocxx are too. Check
+    /// it into source control to see how changes to any of them affect
+    /// your bindings, or to fail CI if the bindings change unexpectedly.
+    /// The snapshot lists items in a canonical order and so isn't itself
+    /// valid Rust; the generated code is unaffected.
+    pub fn snapshot_file(mut self, snapshot_file: PathBuf) -> Self {
+        self.snapshot_file = Some(snapshot_file);
+        self
+    }
+
+    /// Write a C++ header declaring the Rust types and functions which are
+    /// made available to C++ using `extern_rust_type` and
+    /// `extern_rust_function`. Unlike the headers generated by `cxx`, this
+    /// is designed to be installed or checked in for use by other C++
+    /// code: it has an include guard, a comment describing each item, and
+    /// puts the items in a namespace named after the `include_cpp!` mod.
+    /// It includes `cxxgen.h`, which must still be on the include path.
+    pub fn rust_api_header(mut self, rust_api_header: PathBuf) -> Self {
+        self.rust_api_header = Some(rust_api_header);
+        self
+    }
+
     /// Build autocxx C++ files and return a cc::Build you can use to build
     /// more from a build.rs file.
     pub fn build(self) -> Result<BuilderBuild, BuilderError> {
@@ -189,9 +413,17 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
     /// For use in tests only, this does the build and returns additional information
     /// about the files generated which can subsequently be examined for correctness.
     pub fn build_listing_files(self) -> Result<BuilderSuccess, BuilderError> {
-        let clang_args = &self
-            .extra_clang_args
+        let cuda_clang_args: Vec<_> = if self.cuda_compatibility {
+            CUDA_COMPATIBILITY_DEFINES
+                .iter()
+                .map(|(name, value)| format!("-D{}={}", name, value))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let clang_args = &cuda_clang_args
             .iter()
+            .chain(self.extra_clang_args.iter())
             .map(|s| &s[..])
             .collect::<Vec<_>>();
         rust_version_check();
@@ -232,6 +464,43 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
         let mut counter = 0;
         let mut builder = cc::Build::new();
         builder.cpp(true);
+        if self.cuda_compatibility {
+            for (name, value) in CUDA_COMPATIBILITY_DEFINES {
+                builder.define(name, Some(*value));
+            }
+        }
+        for arg in module_clang_args(&self.cpp_codegen_options.module_imports) {
+            builder.flag(&arg);
+        }
+        let rustflags = rustflags();
+        let rust_sanitizers = sanitizers_in_rustflags(&rustflags);
+        let mut sanitizers = self.sanitizers;
+        if self.match_rust_sanitizers {
+            for sanitizer in &rust_sanitizers {
+                if !sanitizers.contains(sanitizer) {
+                    sanitizers.push(*sanitizer);
+                }
+            }
+        }
+        for sanitizer in &sanitizers {
+            builder.flag(&format!("-fsanitize={}", sanitizer.cpp_name()));
+            // rustc links the runtime for the sanitizers it's using.
+            // Otherwise, asking the linker driver for the sanitizer
+            // makes it link the runtime.
+            if !rust_sanitizers.contains(sanitizer) {
+                println!("cargo:rustc-link-arg=-fsanitize={}", sanitizer.cpp_name());
+            }
+        }
+        if !sanitizers.is_empty() {
+            builder.flag("-fno-omit-frame-pointer");
+        }
+        if self.cpp_codegen_options.coverage {
+            builder.flag("-fprofile-instr-generate");
+            builder.flag("-fcoverage-mapping");
+            if !rust_instruments_coverage(&rustflags) {
+                println!("cargo:rustc-link-arg=-fprofile-instr-generate");
+            }
+        }
         let mut generated_rs = Vec::new();
         let mut generated_cpp = Vec::new();
         builder.includes(parsed_file.include_dirs());
@@ -260,6 +529,48 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
                 rs,
             )?);
         }
+        #[cfg(feature = "manifest")]
+        if let Some(manifest_file) = &self.manifest_file {
+            let manifest: serde_json::Map<_, _> = parsed_file
+                .get_rs_buildables()
+                .map(|include_cpp| {
+                    (
+                        include_cpp.get_mod_name(),
+                        crate::generated_items_to_json(include_cpp.generated_items()),
+                    )
+                })
+                .collect();
+            let manifest = serde_json::to_vec_pretty(&manifest).unwrap();
+            try_write_to_file(manifest_file, &manifest)
+                .map_err(|e| BuilderError::FileWriteFail(e, manifest_file.clone()))?;
+        }
+        if let Some(snapshot_file) = &self.snapshot_file {
+            let snapshot = parsed_file
+                .get_rs_buildables()
+                .map(|include_cpp| include_cpp.generate_snapshot(&self.cpp_codegen_options))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(BuilderError::InvalidCxx)?
+                .join("\n");
+            try_write_to_file(snapshot_file, snapshot.as_bytes())
+                .map_err(|e| BuilderError::FileWriteFail(e, snapshot_file.clone()))?;
+        }
+        if let Some(rust_api_header) = &self.rust_api_header {
+            let sections: Vec<_> = parsed_file
+                .get_rs_buildables()
+                .filter_map(|include_cpp| include_cpp.generate_rust_api_section())
+                .collect();
+            let header_name = rust_api_header
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let header = crate::rust_api_header::render_rust_api_header(
+                &header_name,
+                &sections,
+                &self.cpp_codegen_options,
+            );
+            try_write_to_file(rust_api_header, header.as_bytes())
+                .map_err(|e| BuilderError::FileWriteFail(e, rust_api_header.clone()))?;
+        }
         if counter == 0 {
             Err(BuilderError::NoIncludeCxxMacrosFound)
         } else {
@@ -281,6 +592,69 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
     }
 }
 
+/// Definitions which give a host compiler's view of CUDA headers. Giving
+/// kernels internal linkage means bindgen won't generate bindings for them.
+const CUDA_COMPATIBILITY_DEFINES: &[(&str, &str)] = &[
+    ("__host__", ""),
+    ("__device__", ""),
+    ("__global__", "static"),
+    ("__shared__", "static"),
+    ("__constant__", "static"),
+    ("__managed__", "static"),
+    ("__forceinline__", "inline"),
+    ("__noinline__", ""),
+    ("__launch_bounds__(...)", ""),
+];
+
+/// The flags which cargo passes to rustc.
+fn rustflags() -> Vec<String> {
+    match std::env::var("CARGO_ENCODED_RUSTFLAGS") {
+        Ok(flags) => flags.split('\x1f').map(str::to_string).collect(),
+        Err(_) => std::env::var("RUSTFLAGS")
+            .map(|flags| flags.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// The `-C` and `-Z` options in some rustc flags. Both `-Cfoo` and
+/// `-C foo` are allowed.
+fn codegen_options(flags: &[String]) -> Vec<&str> {
+    let mut options = Vec::new();
+    let mut flags = flags.iter().map(String::as_str);
+    while let Some(flag) = flags.next() {
+        match flag {
+            "-C" | "-Z" => options.extend(flags.next()),
+            _ => options.extend(flag.strip_prefix("-C").or_else(|| flag.strip_prefix("-Z"))),
+        }
+    }
+    options
+}
+
+/// The sanitizers enabled by `-Zsanitizer` in some rustc flags.
+fn sanitizers_in_rustflags(flags: &[String]) -> Vec<Sanitizer> {
+    let mut sanitizers = Vec::new();
+    for option in codegen_options(flags) {
+        if let Some(names) = option.strip_prefix("sanitizer=") {
+            for sanitizer in names.split(',').filter_map(Sanitizer::from_rust_name) {
+                if !sanitizers.contains(&sanitizer) {
+                    sanitizers.push(sanitizer);
+                }
+            }
+        }
+    }
+    sanitizers
+}
+
+/// Whether some rustc flags turn on coverage instrumentation.
+fn rust_instruments_coverage(flags: &[String]) -> bool {
+    codegen_options(flags).into_iter().any(|option| {
+        matches!(
+            option,
+            "instrument-coverage" | "instrument-coverage=yes" | "instrument-coverage=all"
+        )
+    })
+}
+
 fn ensure_created(dir: &Path) -> Result<(), BuilderError> {
     std::fs::create_dir_all(dir)
         .map_err(|e| BuilderError::UnableToCreateDirectory(e, dir.to_path_buf()))
@@ -322,3 +696,37 @@ fn rust_version_check() {
         panic!("Rust 1.54 or later is required.")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{rust_instruments_coverage, sanitizers_in_rustflags, Sanitizer};
+
+    #[test]
+    fn test_sanitizers_in_rustflags() {
+        let flags =
+            |flags: &[&str]| -> Vec<String> { flags.iter().map(|f| f.to_string()).collect() };
+        assert_eq!(
+            sanitizers_in_rustflags(&flags(&["-Zsanitizer=address", "-Cdebuginfo=2"])),
+            vec![Sanitizer::Address]
+        );
+        assert_eq!(
+            sanitizers_in_rustflags(&flags(&["-Z", "sanitizer=memory,leak"])),
+            vec![Sanitizer::Memory]
+        );
+        assert!(sanitizers_in_rustflags(&flags(&["-Zsanitizer=thread", "-Zbuild-std"])).is_empty());
+    }
+
+    #[test]
+    fn test_rust_instruments_coverage() {
+        let flags =
+            |flags: &[&str]| -> Vec<String> { flags.iter().map(|f| f.to_string()).collect() };
+        assert!(rust_instruments_coverage(&flags(&[
+            "-Cinstrument-coverage"
+        ])));
+        assert!(rust_instruments_coverage(&flags(&[
+            "-C",
+            "instrument-coverage"
+        ])));
+        assert!(!rust_instruments_coverage(&flags(&["-Copt-level=2"])));
+    }
+}
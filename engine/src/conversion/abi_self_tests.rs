@@ -0,0 +1,205 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for `abi_self_test!`, which generates tests that Rust and C++
+//! agree on the layout of POD types. For each type whose fields are all
+//! numbers or `bool`s, we generate C++ shims to read each field, to copy
+//! the whole value and to report its size, and a Rust test which calls
+//! them. Both the C++ and Rust code generators need to agree on what those
+//! shims are.
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{Fields, Ident, Type, Visibility};
+
+use super::{
+    analysis::fun::FnPhase,
+    api::{Api, TypeKind},
+    apivec::ApiVec,
+};
+use crate::types::{make_ident, QualifiedName};
+
+/// The Rust types of fields which we know how to test. bindgen refers to
+/// C integer types like `int` by their `std::os::raw` names.
+const TESTABLE_FIELD_TYPES: &[&str] = &[
+    "bool",
+    "i8",
+    "u8",
+    "i16",
+    "u16",
+    "i32",
+    "u32",
+    "i64",
+    "u64",
+    "isize",
+    "usize",
+    "f32",
+    "f64",
+    "c_char",
+    "c_schar",
+    "c_uchar",
+    "c_short",
+    "c_ushort",
+    "c_int",
+    "c_uint",
+    "c_long",
+    "c_ulong",
+    "c_longlong",
+    "c_ulonglong",
+    "c_float",
+    "c_double",
+];
+
+/// A POD type for which we generate a layout test.
+pub(crate) struct AbiTestedType<'a> {
+    pub(crate) name: &'a QualifiedName,
+    pub(crate) fields: Vec<AbiTestedField<'a>>,
+}
+
+/// A field of an [`AbiTestedType`].
+pub(crate) struct AbiTestedField<'a> {
+    /// The name of the field in the bindgen-generated struct.
+    pub(crate) rust_name: &'a Ident,
+    /// The name of the field in C++. This differs from the Rust name if
+    /// the C++ name is a Rust keyword.
+    pub(crate) cpp_name: String,
+    pub(crate) ty: &'a Type,
+}
+
+impl<'a> AbiTestedField<'a> {
+    /// Whether this is a `bool`, which can't be made using `as`.
+    pub(crate) fn is_bool(&self) -> bool {
+        self.type_name() == "bool"
+    }
+
+    /// Whether this is a floating point number.
+    pub(crate) fn is_float(&self) -> bool {
+        matches!(
+            self.type_name().as_str(),
+            "f32" | "f64" | "c_float" | "c_double"
+        )
+    }
+
+    fn type_name(&self) -> String {
+        match self.ty {
+            Type::Path(typ) => typ
+                .path
+                .segments
+                .last()
+                .map(|seg| seg.ident.to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+}
+
+impl<'a> AbiTestedType<'a> {
+    /// The name of the shim which reads a field, given its index.
+    pub(crate) fn field_fn_name(&self) -> Ident {
+        self.shim_name("field")
+    }
+
+    /// The name of the shim which returns a copy by value.
+    pub(crate) fn roundtrip_fn_name(&self) -> Ident {
+        self.shim_name("roundtrip")
+    }
+
+    /// The name of the shim which returns the size of the type.
+    pub(crate) fn size_fn_name(&self) -> Ident {
+        self.shim_name("size")
+    }
+
+    fn shim_name(&self, operation: &str) -> Ident {
+        make_ident(format!(
+            "{}_autocxx_abi_{}",
+            self.name.get_final_item(),
+            operation
+        ))
+    }
+}
+
+/// Find the POD types which we can test, if the user asked for that with
+/// `abi_self_test!`. We skip any type with a field which is private, hidden
+/// by `volatile_field!`, or of a type other than a number or `bool`, and
+/// any type subject to a cfg, which might not exist.
+pub(crate) fn find_abi_tested_types<'a>(
+    apis: &'a ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> Vec<AbiTestedType<'a>> {
+    if !config.abi_self_test() {
+        return Vec::new();
+    }
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Struct {
+                name,
+                details,
+                analysis,
+            } if matches!(analysis.pod.kind, TypeKind::Pod)
+                && !analysis.pod.is_generic
+                && config.cfg_for(&name.name.to_cpp_name()).is_none()
+                && config
+                    .volatile_fields(&name.name.to_cpp_name())
+                    .next()
+                    .is_none() =>
+            {
+                let fields = match &details.item.fields {
+                    Fields::Named(fields) if !fields.named.is_empty() => fields,
+                    _ => return None,
+                };
+                let fields = fields
+                    .named
+                    .iter()
+                    .map(|field| {
+                        let rust_name = field.ident.as_ref()?;
+                        // This also excludes bindgen's padding and bitfields.
+                        if rust_name.to_string().starts_with('_')
+                            || !matches!(field.vis, Visibility::Public(_))
+                            || !is_testable_field_type(&field.ty)
+                        {
+                            return None;
+                        }
+                        Some(AbiTestedField {
+                            rust_name,
+                            cpp_name: cpp_field_name(rust_name),
+                            ty: &field.ty,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(AbiTestedType {
+                    name: &name.name,
+                    fields,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_testable_field_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(typ) if typ.qself.is_none() => typ
+            .path
+            .segments
+            .last()
+            .map(|seg| {
+                seg.arguments.is_empty()
+                    && TESTABLE_FIELD_TYPES.contains(&seg.ident.to_string().as_str())
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// bindgen appends an underscore to fields whose names are Rust keywords.
+fn cpp_field_name(rust_name: &Ident) -> String {
+    let rust_name = rust_name.to_string();
+    match rust_name.strip_suffix('_') {
+        Some(stripped) if syn::parse_str::<Ident>(stripped).is_err() => stripped.to_string(),
+        _ => rust_name,
+    }
+}
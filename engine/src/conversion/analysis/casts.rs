@@ -53,9 +53,11 @@ fn create_casts<'a>(
 ) -> impl Iterator<Item = Api<PodPhase>> + 'a {
     // Create casts only to base classes which are on the allowlist
     // because otherwise we won't know for sure whether they're abstract or not.
+    // Sort them so that our output doesn't depend on hash ordering.
     analysis
         .castable_bases
         .iter()
+        .sorted()
         .flat_map(move |base| cast_types().map(|mutable| create_cast(name, base, mutable)))
 }
 
@@ -113,6 +115,8 @@ fn create_cast(from: &QualifiedName, to: &QualifiedName, mutable: CastMutability
             }),
             synthetic_cpp: Some((CppFunctionBody::Cast, CppFunctionKind::Function)),
             is_deleted: false,
+            calling_convention: None,
+            link_name: None,
             provenance: Provenance::SynthesizedOther,
         }),
         analysis: (),
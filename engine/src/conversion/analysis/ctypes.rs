@@ -8,6 +8,8 @@
 
 use std::collections::HashMap;
 
+use itertools::Itertools;
+
 use syn::Ident;
 
 use crate::conversion::api::ApiName;
@@ -18,16 +20,22 @@ use crate::{conversion::api::Api, known_types::known_types, types::QualifiedName
 use super::deps::HasDependencies;
 use super::fun::FnPhase;
 
-/// Spot any variable-length C types (e.g. unsigned long)
+/// Spot any variable-length C types (e.g. unsigned long), SIMD vector
+/// types (e.g. __m128), or types registered by a [crate::KnownTypeExtension],
 /// used in the [Api]s and append those as extra APIs.
 pub(crate) fn append_ctype_information(apis: &mut ApiVec<FnPhase>) {
     let ctypes: HashMap<Ident, QualifiedName> = apis
         .iter()
         .flat_map(|api| api.deps())
-        .filter(|ty| known_types().is_ctype(ty))
+        .filter(|ty| {
+            known_types().is_ctype(ty)
+                || known_types().is_simd_type(ty)
+                || known_types().is_external_type(ty)
+        })
         .map(|ty| (ty.get_final_ident(), ty.clone()))
         .collect();
-    for (id, typename) in ctypes {
+    // Sort them, so that the generated code is the same every time.
+    for (id, typename) in ctypes.into_iter().sorted_by_key(|(id, _)| id.to_string()) {
         apis.push(Api::CType {
             name: ApiName::new(&Namespace::new(), id),
             typename,
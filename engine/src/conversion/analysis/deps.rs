@@ -52,6 +52,8 @@ impl HasDependencies for Api<FnPrePhase1> {
                 superclass,
             } => Box::new(std::iter::once(superclass)),
             Api::RustSubclassFn { details, .. } => Box::new(details.dependencies.iter()),
+            Api::FnPtrTypedef { deps, .. } => Box::new(deps.iter()),
+            Api::Const { impl_for, .. } => Box::new(impl_for.iter()),
             _ => Box::new(std::iter::empty()),
         }
     }
@@ -104,6 +106,8 @@ impl HasDependencies for Api<FnPhase> {
                 superclass,
             } => Box::new(std::iter::once(superclass)),
             Api::RustSubclassFn { details, .. } => Box::new(details.dependencies.iter()),
+            Api::FnPtrTypedef { deps, .. } => Box::new(deps.iter()),
+            Api::Const { impl_for, .. } => Box::new(impl_for.iter()),
             _ => Box::new(std::iter::empty()),
         }
     }
@@ -0,0 +1,180 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Code to create methods to get and set the fields of non-POD types,
+//! as requested by `field_accessors!`. Rust can't see the fields of such
+//! types, so we do it in C++.
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{
+    parse_quote, punctuated::Punctuated, token::Comma, Field, FnArg, Ident, ItemStruct, ReturnType,
+    Type, Visibility,
+};
+
+use crate::{
+    conversion::{
+        api::{
+            Api, ApiName, CppVisibility, FuncToConvert, Provenance, References, TypeKind,
+            Virtualness,
+        },
+        apivec::ApiVec,
+    },
+    types::{cpp_name_for_rust_ident, make_ident, QualifiedName},
+};
+
+use super::{
+    fun::function_wrapper::{CppFunctionBody, CppFunctionKind},
+    pod::PodPhase,
+};
+
+pub(crate) fn add_field_accessors(
+    apis: ApiVec<PodPhase>,
+    config: &IncludeCppConfig,
+) -> ApiVec<PodPhase> {
+    apis.into_iter()
+        .flat_map(|api| {
+            let mut resultant_apis = match api {
+                Api::Struct {
+                    ref name,
+                    ref details,
+                    ref analysis,
+                } if matches!(analysis.kind, TypeKind::NonPod)
+                    && !analysis.is_generic
+                    && config.field_accessors_for(&name.name.to_cpp_name()) =>
+                {
+                    create_field_accessors(&name.name, &details.item)
+                }
+                _ => Vec::new(),
+            };
+            resultant_apis.push(api);
+            resultant_apis.into_iter()
+        })
+        .collect()
+}
+
+fn create_field_accessors(ty: &QualifiedName, s: &ItemStruct) -> Vec<Api<PodPhase>> {
+    s.fields
+        .iter()
+        .filter(|f| is_accessible_field(f))
+        .flat_map(|f| {
+            let rust_name = f.ident.as_ref().unwrap();
+            let cpp_name = cpp_field_name(rust_name);
+            [
+                create_getter(ty, rust_name, cpp_name.clone(), &f.ty),
+                create_setter(ty, rust_name, cpp_name, &f.ty),
+            ]
+        })
+        .collect()
+}
+
+/// Whether we can make accessors for this field. bindgen makes private
+/// fields private in Rust too, and adds fields of its own, such as
+/// `_base` and `vtable_`, which don't exist in C++. Pointers are skipped
+/// because bindgen represents reference fields as pointers, and we
+/// couldn't return a reference to one of those.
+fn is_accessible_field(f: &Field) -> bool {
+    matches!(f.vis, Visibility::Public(_))
+        && !matches!(f.ty, Type::Ptr(_) | Type::Array(_))
+        && f.ident
+            .as_ref()
+            .map(|id| {
+                let id = id.to_string();
+                !id.starts_with('_') && id != "vtable_"
+            })
+            .unwrap_or_default()
+}
+
+/// bindgen appends an underscore to fields whose names are Rust keywords.
+fn cpp_field_name(rust_name: &Ident) -> Ident {
+    make_ident(cpp_name_for_rust_ident(&rust_name.to_string()))
+}
+
+fn create_getter(
+    ty: &QualifiedName,
+    rust_name: &Ident,
+    cpp_name: Ident,
+    field_ty: &Type,
+) -> Api<PodPhase> {
+    let typ = ty.to_type_path();
+    create_accessor(
+        ty,
+        rust_name.clone(),
+        "getter",
+        parse_quote! { this: *const #typ },
+        parse_quote! { -> *const #field_ty },
+        References::new_with_this_and_return_as_reference(),
+        CppFunctionBody::FieldGetter(cpp_name),
+    )
+}
+
+fn create_setter(
+    ty: &QualifiedName,
+    rust_name: &Ident,
+    cpp_name: Ident,
+    field_ty: &Type,
+) -> Api<PodPhase> {
+    let typ = ty.to_type_path();
+    create_accessor(
+        ty,
+        make_ident(format!("set_{}", rust_name)),
+        "setter",
+        parse_quote! { this: *mut #typ, value: #field_ty },
+        ReturnType::Default,
+        References {
+            ref_params: [make_ident("this")].into_iter().collect(),
+            ..Default::default()
+        },
+        CppFunctionBody::FieldSetter(cpp_name),
+    )
+}
+
+fn create_accessor(
+    ty: &QualifiedName,
+    ident: Ident,
+    label: &str,
+    inputs: Punctuated<FnArg, Comma>,
+    output: ReturnType,
+    references: References,
+    body: CppFunctionBody,
+) -> Api<PodPhase> {
+    // This name just needs to be unique; the method is called `ident`.
+    let name = QualifiedName::new(
+        ty.get_namespace(),
+        make_ident(format!(
+            "{}_{}_autocxx_field_{}",
+            ty.get_final_item(),
+            ident,
+            label
+        )),
+    );
+    Api::Function {
+        name: ApiName::new_from_qualified_name(name),
+        fun: Box::new(FuncToConvert {
+            ident,
+            doc_attr: None,
+            inputs,
+            output,
+            vis: parse_quote! { pub },
+            virtualness: Virtualness::None,
+            cpp_vis: CppVisibility::Public,
+            special_member: None,
+            unused_template_param: false,
+            references,
+            original_name: None,
+            self_ty: Some(ty.clone()),
+            synthesized_this_type: None,
+            add_to_trait: None,
+            synthetic_cpp: Some((body, CppFunctionKind::Method)),
+            is_deleted: false,
+            calling_convention: None,
+            link_name: None,
+            provenance: Provenance::SynthesizedOther,
+        }),
+        analysis: (),
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks for function signatures which neither cxx nor our C++ wrappers
+//! can represent. We skip such functions up front, saying which construct
+//! was the problem, rather than generating code which fails to compile
+//! later on.
+
+use syn::{Expr, ExprLit, FnArg, GenericArgument, Lit, Pat, PathArguments, ReturnType, Type};
+
+use crate::conversion::api::FuncToConvert;
+
+/// The name bindgen gives to the type in which it stores bitfields.
+const BITFIELD_UNIT: &str = "__BindgenBitfieldUnit";
+
+/// Describe the first construct in this function's signature, as given to
+/// us by bindgen, which can't cross the cxx bridge.
+pub(super) fn find_unrepresentable_abi(fun: &FuncToConvert) -> Option<String> {
+    if let ReturnType::Type(_, ty) = &fun.output {
+        match ty.as_ref() {
+            Type::Array(_) => return Some("returns a C array by value".into()),
+            Type::Ptr(ptr) if matches!(*ptr.elem, Type::Array(_)) => {
+                return Some("returns a pointer or reference to a C array".into())
+            }
+            ty if refers_to_bitfield(ty) => return Some("returns a bitfield".into()),
+            _ => {}
+        }
+    }
+    fun.inputs.iter().find_map(|input| {
+        let pt = match input {
+            FnArg::Typed(pt) => pt,
+            FnArg::Receiver(_) => return None,
+        };
+        let param = match pt.pat.as_ref() {
+            Pat::Ident(pp) => pp.ident.to_string(),
+            _ => "<unnamed>".into(),
+        };
+        match pt.ty.as_ref() {
+            Type::Array(_) => Some(format!("takes a C array by value as parameter {}", param)),
+            Type::Ptr(ptr) if is_array_of_unknown_bound(&ptr.elem) => Some(format!(
+                "takes a pointer or reference to a C array of unknown bound as parameter {}",
+                param
+            )),
+            ty if refers_to_bitfield(ty) => {
+                Some(format!("takes a bitfield as parameter {}", param))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// bindgen represents `T[]` as an array of length zero.
+fn is_array_of_unknown_bound(ty: &Type) -> bool {
+    match ty {
+        Type::Array(arr) => {
+            let len_is_zero = match &arr.len {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(len), ..
+                }) => len.base10_digits() == "0",
+                _ => false,
+            };
+            len_is_zero || is_array_of_unknown_bound(&arr.elem)
+        }
+        _ => false,
+    }
+}
+
+fn refers_to_bitfield(ty: &Type) -> bool {
+    match ty {
+        Type::Ptr(ptr) => refers_to_bitfield(&ptr.elem),
+        Type::Reference(r) => refers_to_bitfield(&r.elem),
+        Type::Array(arr) => refers_to_bitfield(&arr.elem),
+        Type::Path(typ) => typ.path.segments.iter().any(|seg| {
+            seg.ident.to_string().starts_with(BITFIELD_UNIT)
+                || match &seg.arguments {
+                    PathArguments::AngleBracketed(ab) => ab.args.iter().any(|arg| match arg {
+                        GenericArgument::Type(ty) => refers_to_bitfield(ty),
+                        _ => false,
+                    }),
+                    _ => false,
+                }
+        }),
+        _ => false,
+    }
+}
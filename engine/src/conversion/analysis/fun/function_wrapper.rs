@@ -7,11 +7,28 @@
 // except according to those terms.
 
 use crate::{
-    conversion::api::SubclassName,
+    conversion::{api::SubclassName, last_errors::LastError},
     types::{Namespace, QualifiedName},
 };
 use syn::{parse_quote, Ident, Type};
 
+/// The longest slice we can pass to a function taking a
+/// `std::initializer_list`, which we have to build from a braced list of a
+/// fixed number of elements.
+pub(crate) const MAX_INITIALIZER_LIST_LEN: usize = 16;
+
+/// The shape of a C++ array passed by reference, such as `int (&)[3][4]`,
+/// or as a pointer to its rows, such as the `int (*)[4]` to which a
+/// parameter `int m[3][4]` decays. cxx can't represent arrays, so we pass a
+/// pointer to the first element across the bridge instead.
+#[derive(Clone, Debug)]
+pub(crate) struct ArrayShape {
+    /// The length of each dimension of the array pointed to, outermost first.
+    pub(crate) dims: Vec<usize>,
+    /// Whether this is a reference to the array, rather than a pointer.
+    pub(crate) by_reference: bool,
+}
+
 #[derive(Clone, Debug)]
 pub(crate) enum CppConversionType {
     None,
@@ -20,6 +37,29 @@ pub(crate) enum CppConversionType {
     FromPtrToValue,
     FromValueToUniquePtr,
     FromPtrToMove,
+    /// From a `rust::Str` or `rust::Slice` to the named C++ view type,
+    /// such as `absl::string_view`.
+    FromRustViewToCppView(String),
+    /// From the named C++ view type to a `rust::Str` or `rust::Slice`.
+    FromCppViewToRustView(String),
+    /// From a `rust::Slice` to a `std::initializer_list`. This can't be done
+    /// by a simple expression, so the call is instead made from within a
+    /// helper which builds the list.
+    FromSliceToInitializerList,
+    /// From a `std::initializer_list` to a `rust::Slice`.
+    FromInitializerListToSlice,
+    /// From a pointer to the first element of an array to a reference or
+    /// pointer to the array itself.
+    FromPtrToArray(ArrayShape),
+    /// From a reference or pointer to an array to a pointer to its first
+    /// element.
+    FromArrayToPtr(ArrayShape),
+    /// From a `std::unique_ptr<T[]>` to the raw pointer it held, which
+    /// the Rust wrapper takes ownership of.
+    FromUniqueArrayToPtr,
+    /// From a `const` reference to a POD type to a copy of it, as
+    /// requested by `copy_pod_returns!`.
+    FromReferenceToValue,
 }
 
 impl CppConversionType {
@@ -33,6 +73,24 @@ impl CppConversionType {
                 CppConversionType::FromValueToUniquePtr
             }
             CppConversionType::FromValueToUniquePtr => CppConversionType::FromUniquePtrToValue,
+            CppConversionType::FromRustViewToCppView(cpp_view) => {
+                CppConversionType::FromCppViewToRustView(cpp_view.clone())
+            }
+            CppConversionType::FromCppViewToRustView(cpp_view) => {
+                CppConversionType::FromRustViewToCppView(cpp_view.clone())
+            }
+            CppConversionType::FromSliceToInitializerList => {
+                CppConversionType::FromInitializerListToSlice
+            }
+            CppConversionType::FromInitializerListToSlice => {
+                CppConversionType::FromSliceToInitializerList
+            }
+            CppConversionType::FromPtrToArray(shape) => {
+                CppConversionType::FromArrayToPtr(shape.clone())
+            }
+            CppConversionType::FromArrayToPtr(shape) => {
+                CppConversionType::FromPtrToArray(shape.clone())
+            }
             _ => panic!("Did not expect to have to invert this conversion"),
         }
     }
@@ -47,6 +105,17 @@ pub(crate) enum RustConversionType {
     FromPinMoveRefToPtr,
     FromTypeToPtr,
     FromValueParamToPtr,
+    FromOutParamToPtr,
+    FromCStrToPtr,
+    FromSliceToPtr,
+    /// The length of the slice passed as the given parameter.
+    FromSliceToLen(Ident),
+    /// A slice which will become a `std::initializer_list`, so mustn't be
+    /// longer than [`MAX_INITIALIZER_LIST_LEN`].
+    FromSliceToInitializerList,
+    /// A Rust array reference, or pointer to an array, from which we pass a
+    /// pointer to the first element.
+    FromArrayToPtr(ArrayShape),
 }
 
 impl RustConversionType {
@@ -93,6 +162,64 @@ impl TypeConversionPolicy {
         }
     }
 
+    /// A C++ view type such as `absl::string_view`, passed from Rust as
+    /// the equivalent reference type `ty`.
+    pub(crate) fn new_from_rust_view(ty: Type, cpp_view: String) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromRustViewToCppView(cpp_view),
+            rust_conversion: RustConversionType::None,
+        }
+    }
+
+    /// A C++ view type such as `absl::string_view`, returned to Rust as
+    /// the equivalent reference type `ty`.
+    pub(crate) fn new_to_rust_view(ty: Type, cpp_view: String) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromCppViewToRustView(cpp_view),
+            rust_conversion: RustConversionType::None,
+        }
+    }
+
+    /// A `const` reference to the POD type `ty`, returned by value.
+    pub(crate) fn new_from_reference_to_value(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromReferenceToValue,
+            rust_conversion: RustConversionType::None,
+        }
+    }
+
+    /// A `std::initializer_list`, passed from Rust as the slice `ty`.
+    pub(crate) fn new_from_slice_to_initializer_list(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromSliceToInitializerList,
+            rust_conversion: RustConversionType::FromSliceToInitializerList,
+        }
+    }
+
+    /// A reference or pointer to a C++ array of the given shape, passed
+    /// through the bridge as `ty`, a pointer to its first element.
+    pub(crate) fn new_from_array(ty: Type, shape: ArrayShape) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromPtrToArray(shape.clone()),
+            rust_conversion: RustConversionType::FromArrayToPtr(shape),
+        }
+    }
+
+    /// A returned `std::unique_ptr<T[]>`, passed through the bridge as
+    /// `ty`, a pointer to its first element.
+    pub(crate) fn new_from_unique_array(ty: Type) -> Self {
+        TypeConversionPolicy {
+            unwrapped_type: ty,
+            cpp_conversion: CppConversionType::FromUniqueArrayToPtr,
+            rust_conversion: RustConversionType::None,
+        }
+    }
+
     pub(crate) fn cpp_work_needed(&self) -> bool {
         !matches!(self.cpp_conversion, CppConversionType::None)
     }
@@ -156,8 +283,25 @@ pub(crate) enum CppFunctionBody {
     ConstructSuperclass(String),
     Cast,
     Destructor(Namespace, Ident),
+    AssignmentOperator,
     AllocUninitialized(QualifiedName),
     FreeUninitialized(QualifiedName),
+    /// Returns the address of the named global variable.
+    AddressOfGlobal(QualifiedName),
+    /// Returns a reference to the named field of the receiver.
+    FieldGetter(Ident),
+    /// Assigns the argument to the named field of the receiver.
+    FieldSetter(Ident),
+    /// Calls the function with the given symbol name, looked up at runtime
+    /// in the library named in `dynamic_library!`.
+    DynamicCall(String),
+    /// Calls the function passed in as the first parameter. This is the
+    /// body of a wrapper shared by functions which have the same argument
+    /// and return conversions.
+    CallCallee,
+    /// Passes the arguments unconverted to the named shared wrapper,
+    /// along with the given lambda which calls the original function.
+    CallSharedWrapper(String, String),
 }
 
 #[derive(Clone)]
@@ -169,6 +313,35 @@ pub(crate) enum CppFunctionKind {
     SynthesizedConstructor,
 }
 
+/// A calling convention other than the platform default, as is common
+/// for 32-bit Windows APIs. bindgen tells us about these using the ABI
+/// of the `extern` block containing each function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CallingConvention {
+    Stdcall,
+    Fastcall,
+    Vectorcall,
+}
+
+impl CallingConvention {
+    pub(crate) fn from_abi(abi: &syn::Abi) -> Option<Self> {
+        match abi.name.as_ref().map(|name| name.value()).as_deref() {
+            Some("stdcall") => Some(Self::Stdcall),
+            Some("fastcall") => Some(Self::Fastcall),
+            Some("vectorcall") => Some(Self::Vectorcall),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn cpp_keyword(&self) -> &'static str {
+        match self {
+            Self::Stdcall => "__stdcall",
+            Self::Fastcall => "__fastcall",
+            Self::Vectorcall => "__vectorcall",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct CppFunction {
     pub(crate) payload: CppFunctionBody,
@@ -179,4 +352,10 @@ pub(crate) struct CppFunction {
     pub(crate) kind: CppFunctionKind,
     pub(crate) pass_obs_field: bool,
     pub(crate) qualification: Option<QualifiedName>,
+    /// Only needed where our C++ must match some existing declaration,
+    /// i.e. when overriding virtual methods in subclasses.
+    pub(crate) calling_convention: Option<CallingConvention>,
+    /// If the function reports errors errno-style (see `last_error!`), how
+    /// to capture the error straight after the call.
+    pub(crate) last_error: Option<LastError>,
 }
@@ -6,16 +6,18 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+mod abi;
 mod bridge_name_tracker;
 pub(crate) mod function_wrapper;
 mod implicit_constructors;
 mod overload_tracker;
+mod rust_naming;
 mod subclass;
 
 use crate::{
     conversion::{
         analysis::{
-            fun::function_wrapper::{CppConversionType, CppFunctionKind},
+            fun::function_wrapper::{ArrayShape, CppConversionType, CppFunctionKind},
             type_converter::{self, add_analysis, TypeConversionContext, TypeConverter},
         },
         api::{
@@ -27,35 +29,46 @@ use crate::{
         convert_error::ErrorContext,
         convert_error::{ConvertErrorWithContext, ErrorContextType},
         error_reporter::{convert_apis, report_any_error},
+        out_of_line::{function_key, FunctionSymbols},
     },
-    known_types::known_types,
+    known_types::{known_types, ViewKind},
     types::validate_ident_ok_for_rust,
 };
 use std::collections::{HashMap, HashSet};
 
-use autocxx_parser::{IncludeCppConfig, UnsafePolicy};
+use autocxx_parser::{
+    ConstructorNaming, IncludeCppConfig, NamingPolicy, UniqueArrayLength, UnsafePolicy,
+};
 use function_wrapper::{CppFunction, CppFunctionBody, TypeConversionPolicy};
 use itertools::Itertools;
 use proc_macro2::Span;
 use quote::quote;
 use syn::{
-    parse_quote, punctuated::Punctuated, token::Comma, FnArg, Ident, Pat, ReturnType, Type,
-    TypePtr, Visibility,
+    parse_quote, punctuated::Punctuated, token::Comma, FnArg, GenericArgument, Ident, Pat,
+    PathArguments, ReturnType, Type, TypePtr, TypeReference, Visibility,
 };
 
 use crate::{
     conversion::{
         api::{AnalysisPhase, Api, TypeKind},
+        last_errors::LastError,
+        member_pointers::{data_member_pointer_class, DataMemberPointer},
+        refcounted::RefcountedReturn,
+        result_types::{result_type_kind, ResultReturn, ResultReturnKind, ResultTypeKind},
+        smart_pointers::{smart_pointer_target, SmartPointerReturn},
+        unique_arrays::{unique_array_element, UniqueArrayLen, UniqueArrayReturn},
         ConvertError,
     },
     types::{make_ident, validate_ident_ok_for_cxx, Namespace, QualifiedName},
 };
 
 use self::{
+    abi::find_unrepresentable_abi,
     bridge_name_tracker::BridgeNameTracker,
     function_wrapper::RustConversionType,
     implicit_constructors::{find_constructors_present, ItemsFound},
     overload_tracker::OverloadTracker,
+    rust_naming::{split_accessor_name, to_snake_case, AccessorKind},
     subclass::{
         create_subclass_constructor, create_subclass_fn_wrapper, create_subclass_function,
         create_subclass_trait_item,
@@ -68,6 +81,22 @@ use super::{
     type_converter::Annotated,
 };
 
+/// Members which Qt's `Q_OBJECT` macro declares for the use of moc-generated
+/// code. These are hidden if the user asks for `qt_mode!`.
+const MOC_GENERATED_MEMBERS: &[&str] = &[
+    "metaObject",
+    "qt_metacast",
+    "qt_metacall",
+    "qt_static_metacall",
+    "tr",
+    "trUtf8",
+];
+
+/// The Rust primitive integer types which can be used as a length.
+const INTEGER_TYPES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize",
+];
+
 #[derive(Clone, Debug)]
 pub(crate) enum ReceiverMutability {
     Const,
@@ -154,6 +183,10 @@ pub(crate) struct FnAnalysis {
     pub(crate) kind: FnKind,
     pub(crate) ret_type: ReturnType,
     pub(crate) param_details: Vec<ArgumentAnalysis>,
+    /// If this returns a reference whose lifetime Rust's elision rules
+    /// wouldn't tie to the right parameter, the index within
+    /// `param_details` of the parameter it borrows from.
+    pub(crate) return_borrows_from: Option<usize>,
     pub(crate) ret_conversion: Option<TypeConversionPolicy>,
     pub(crate) requires_unsafe: UnsafetyNeeded,
     pub(crate) vis: Visibility,
@@ -169,6 +202,41 @@ pub(crate) struct FnAnalysis {
     pub(crate) externally_callable: bool,
     /// Whether we need to generate a Rust-side calling function
     pub(crate) rust_wrapper_needed: bool,
+    /// If this function returns an error code which should be turned into
+    /// a `Result` (see `error_code!`), the enum variant meaning success.
+    pub(crate) error_code_success: Option<Ident>,
+    /// Whether the Rust wrapper should convert the returned
+    /// `UniquePtr<CxxString>` into a `String` (see `string_returns!`).
+    pub(crate) string_return: bool,
+    /// If this returns `absl::Status`, `absl::StatusOr<T>` or a
+    /// `result_type!`, which the Rust wrapper should convert into a
+    /// `Result`, how to do so.
+    pub(crate) result_return: Option<ResultReturn>,
+    /// If this returns a `smart_pointer!`, which the Rust wrapper should
+    /// turn into an `autocxx::SmartPtr`, how to do so.
+    pub(crate) smart_pointer_return: Option<SmartPointerReturn>,
+    /// If this returns a `std::unique_ptr<T[]>`, which the Rust wrapper
+    /// should turn into an `autocxx::UniqueArray`, how to do so.
+    pub(crate) unique_array_return: Option<UniqueArrayReturn>,
+    /// If this returns a pointer to a `refcounted!` class, which the Rust
+    /// wrapper should turn into an `autocxx::RcHandle`, how to do so.
+    pub(crate) refcounted_return: Option<RefcountedReturn>,
+    /// If this is named in a `last_error!` directive, how its C++ wrapper
+    /// captures the error, which its Rust wrapper returns in a `Result`.
+    pub(crate) last_error: Option<LastError>,
+    /// Whether to generate an `_async` variant of the Rust wrapper, which
+    /// makes the call on a thread suitable for blocking work.
+    pub(crate) async_wrapper: bool,
+    /// Pointers to data members which this takes or returns, each of which
+    /// gets a shim to apply it to an object.
+    pub(crate) data_member_pointers: Vec<DataMemberPointer>,
+    /// If this is a getter or setter renamed by `pair_accessors!`, the Rust
+    /// name of the other half of the pair.
+    pub(crate) paired_accessor: Option<String>,
+    /// If this was renamed because its Rust name would otherwise have
+    /// been the same as that of another function in the same namespace or
+    /// type, the name it would have had.
+    pub(crate) renamed_from: Option<String>,
 }
 
 #[derive(Clone)]
@@ -237,6 +305,7 @@ pub(crate) struct FnPhase;
 /// Indicates which kinds of public constructors are known to exist for a type.
 #[derive(Debug, Default, Copy, Clone)]
 pub(crate) struct PublicConstructors {
+    pub(crate) copy_constructor: bool,
     pub(crate) move_constructor: bool,
     pub(crate) destructor: bool,
 }
@@ -244,6 +313,7 @@ pub(crate) struct PublicConstructors {
 impl PublicConstructors {
     fn from_items_found(items_found: &ItemsFound) -> Self {
         Self {
+            copy_constructor: items_found.const_copy_constructor.callable_any(),
             move_constructor: items_found.move_constructor.callable_any(),
             destructor: items_found.destructor.callable_any(),
         }
@@ -271,12 +341,18 @@ pub(crate) struct FnAnalyzer<'a> {
     type_converter: TypeConverter<'a>,
     bridge_name_tracker: BridgeNameTracker,
     pod_safe_types: HashSet<QualifiedName>,
+    pod_types_with_pointers: HashSet<QualifiedName>,
+    pod_sizes: HashMap<QualifiedName, usize>,
     config: &'a IncludeCppConfig,
     overload_trackers_by_mod: HashMap<Namespace, OverloadTracker>,
     subclasses_by_superclass: HashMap<QualifiedName, Vec<SubclassName>>,
     nested_type_name_map: HashMap<QualifiedName, String>,
     generic_types: HashSet<QualifiedName>,
     existing_superclass_trait_api_names: HashSet<QualifiedName>,
+    method_names_by_type: HashMap<QualifiedName, HashSet<String>>,
+    ambiguous_constructor_suffixes: HashSet<(QualifiedName, String)>,
+    last_error_fetchers: HashMap<String, (Namespace, Box<Type>)>,
+    function_symbols: FunctionSymbols,
 }
 
 impl<'a> FnAnalyzer<'a> {
@@ -284,6 +360,7 @@ impl<'a> FnAnalyzer<'a> {
         apis: ApiVec<PodPhase>,
         unsafe_policy: UnsafePolicy,
         config: &'a IncludeCppConfig,
+        function_symbols: FunctionSymbols,
     ) -> ApiVec<FnPrePhase2> {
         let mut me = Self {
             unsafe_policy,
@@ -293,10 +370,18 @@ impl<'a> FnAnalyzer<'a> {
             config,
             overload_trackers_by_mod: HashMap::new(),
             pod_safe_types: Self::build_pod_safe_type_set(&apis),
+            pod_types_with_pointers: Self::build_pod_types_with_pointers_set(&apis),
+            pod_sizes: Self::build_pod_size_map(&apis),
             subclasses_by_superclass: subclass::subclasses_by_superclass(&apis),
             nested_type_name_map: Self::build_nested_type_map(&apis),
             generic_types: Self::build_generic_type_set(&apis),
             existing_superclass_trait_api_names: HashSet::new(),
+            method_names_by_type: Self::build_method_name_map(&apis),
+            ambiguous_constructor_suffixes: Self::build_ambiguous_constructor_suffix_set(
+                &apis, config,
+            ),
+            last_error_fetchers: Self::build_last_error_fetcher_map(&apis, config),
+            function_symbols,
         };
         let mut results = ApiVec::new();
         convert_apis(
@@ -313,6 +398,25 @@ impl<'a> FnAnalyzer<'a> {
         results
     }
 
+    /// Whether a function is defined out-of-line, so that we can expect
+    /// to find it in a shared library. If we don't know, we assume so.
+    fn is_out_of_line(&self, fun: &FuncToConvert) -> bool {
+        self.function_symbols
+            .out_of_line
+            .as_ref()
+            .map_or(true, |functions| {
+                functions.contains(&function_key(fun.link_name.as_deref(), &fun.ident))
+            })
+    }
+
+    /// Whether a function is defined out-of-line but not exported from its
+    /// library, according to `export_macro!`.
+    fn is_hidden(&self, fun: &FuncToConvert) -> bool {
+        self.function_symbols
+            .hidden
+            .contains(&function_key(fun.link_name.as_deref(), &fun.ident))
+    }
+
     fn build_pod_safe_type_set(apis: &ApiVec<PodPhase>) -> HashSet<QualifiedName> {
         apis.iter()
             .filter_map(|api| match api {
@@ -324,7 +428,7 @@ impl<'a> FnAnalyzer<'a> {
                         },
                     ..
                 } => Some(api.name().clone()),
-                Api::Enum { .. } => Some(api.name().clone()),
+                Api::Enum { .. } | Api::FnPtrTypedef { .. } => Some(api.name().clone()),
                 _ => None,
             })
             .chain(
@@ -343,6 +447,124 @@ impl<'a> FnAnalyzer<'a> {
             .collect()
     }
 
+    /// POD types with raw pointer fields, directly or within other POD
+    /// fields. These aren't `Send`, so can't be passed to or returned from
+    /// `_async` wrappers.
+    fn build_pod_types_with_pointers_set(apis: &ApiVec<PodPhase>) -> HashSet<QualifiedName> {
+        let field_types: Vec<(&QualifiedName, Vec<&Type>)> = apis
+            .iter()
+            .filter_map(|api| match api {
+                Api::Struct {
+                    name,
+                    analysis:
+                        PodAnalysis {
+                            kind: TypeKind::Pod,
+                            field_info,
+                            ..
+                        },
+                    ..
+                } => Some((
+                    &name.name,
+                    field_info.iter().map(|field| &field.ty).collect(),
+                )),
+                _ => None,
+            })
+            .collect();
+        let mut results = HashSet::new();
+        // Keep going until we've found every type containing another.
+        loop {
+            let before = results.len();
+            for (name, field_types) in &field_types {
+                if field_types
+                    .iter()
+                    .any(|ty| type_contains_pointer(ty, &results))
+                {
+                    results.insert((*name).clone());
+                }
+            }
+            if results.len() == before {
+                break;
+            }
+        }
+        results
+    }
+
+    /// The size of each POD struct whose layout we know, used to decide
+    /// whether to copy it for `copy_pod_returns!`.
+    fn build_pod_size_map(apis: &ApiVec<PodPhase>) -> HashMap<QualifiedName, usize> {
+        apis.iter()
+            .filter_map(|api| match api {
+                Api::Struct {
+                    name,
+                    details,
+                    analysis:
+                        PodAnalysis {
+                            kind: TypeKind::Pod,
+                            ..
+                        },
+                } => details
+                    .layout
+                    .as_ref()
+                    .map(|layout| (name.name.clone(), layout.size)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The C++ names of the methods of each type, used to spot
+    /// getter/setter pairs.
+    fn build_method_name_map(apis: &ApiVec<PodPhase>) -> HashMap<QualifiedName, HashSet<String>> {
+        let mut results: HashMap<QualifiedName, HashSet<String>> = HashMap::new();
+        for api in apis.iter() {
+            if let Api::Function { name, fun, .. } = api {
+                if let (Some(self_ty), Some(cpp_name)) =
+                    (self_type_of(fun), name.cpp_name_if_present())
+                {
+                    results.entry(self_ty).or_default().insert(cpp_name.clone());
+                }
+            }
+        }
+        results
+    }
+
+    /// The suffixes which [`Self::constructor_suffix_from_params`] would
+    /// give more than one constructor of the same type.
+    fn build_ambiguous_constructor_suffix_set(
+        apis: &ApiVec<PodPhase>,
+        config: &IncludeCppConfig,
+    ) -> HashSet<(QualifiedName, String)> {
+        if config.constructor_naming() != ConstructorNaming::ByParameters {
+            return HashSet::new();
+        }
+        let mut counts: HashMap<(QualifiedName, String), usize> = HashMap::new();
+        for api in apis.iter() {
+            if let Api::Function { fun, .. } = api {
+                if !matches!(
+                    fun.special_member,
+                    Some(SpecialMemberKind::DefaultConstructor | SpecialMemberKind::Constructor)
+                ) {
+                    continue;
+                }
+                let params = fun.inputs.iter().filter_map(|arg| match arg {
+                    FnArg::Typed(pt) if !matches!(pt.pat.as_ref(), Pat::Ident(pp) if pp.ident == "this") => {
+                        Some(pt.pat.as_ref())
+                    }
+                    _ => None,
+                });
+                if let (Some(self_ty), Some(suffix)) =
+                    (self_type_of(fun), suffix_from_param_names(params))
+                {
+                    *counts.entry((self_ty, suffix)).or_default() += 1;
+                }
+            }
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(key, _)| key)
+            .collect()
+    }
+
     fn build_generic_type_set(apis: &ApiVec<PodPhase>) -> HashSet<QualifiedName> {
         apis.iter()
             .filter_map(|api| match api {
@@ -377,6 +599,31 @@ impl<'a> FnAnalyzer<'a> {
             .collect()
     }
 
+    /// The namespace and raw return type of each free function named as
+    /// the error fetcher in a `last_error!` directive, keyed by its
+    /// qualified C++ name. Only functions taking no parameters qualify.
+    fn build_last_error_fetcher_map(
+        apis: &ApiVec<PodPhase>,
+        config: &IncludeCppConfig,
+    ) -> HashMap<String, (Namespace, Box<Type>)> {
+        apis.iter()
+            .filter_map(|api| match api {
+                Api::Function { name, fun, .. }
+                    if fun.inputs.is_empty() && fun.self_ty.is_none() =>
+                {
+                    let cpp_name = name.qualified_cpp_name();
+                    match &fun.output {
+                        ReturnType::Type(_, ty) if config.is_last_error_fetcher(&cpp_name) => {
+                            Some((cpp_name, (name.name.get_namespace().clone(), ty.clone())))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     fn convert_boxed_type(
         &mut self,
         ty: Box<Type>,
@@ -391,6 +638,65 @@ impl<'a> FnAnalyzer<'a> {
         Ok(annotated)
     }
 
+    /// bindgen gives a member pointer the type of a plain pointer to the
+    /// member, so instead we represent it by an opaque type which C++
+    /// defines as the real member pointer type.
+    fn convert_member_pointer(&mut self, cpp_definition: &str) -> Annotated<Box<Type>> {
+        let mut annotated = self.type_converter.convert_member_pointer(cpp_definition);
+        self.extra_apis.append(&mut annotated.extra_apis);
+        annotated
+    }
+
+    /// For each pointer to a data member which this function takes or
+    /// returns, work out what we need in order to apply it to an object.
+    fn analyze_data_member_pointers(
+        &mut self,
+        fun: &FuncToConvert,
+        ns: &Namespace,
+    ) -> Vec<DataMemberPointer> {
+        let params = fun.inputs.iter().filter_map(|arg| match arg {
+            FnArg::Typed(pt) => match pt.pat.as_ref() {
+                Pat::Ident(pp) => fun
+                    .references
+                    .member_pointer_params
+                    .get(&pp.ident)
+                    .map(|cpp_definition| (cpp_definition, pt.ty.as_ref())),
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        });
+        let ret = match (&fun.references.member_pointer_return, &fun.output) {
+            (Some(cpp_definition), ReturnType::Type(_, ty)) => Some((cpp_definition, ty.as_ref())),
+            _ => None,
+        };
+        params
+            .chain(ret)
+            .filter_map(|(cpp_definition, ty)| {
+                let class = data_member_pointer_class(cpp_definition)?;
+                // bindgen thinks this is a pointer to the member.
+                let field = match ty {
+                    Type::Ptr(ptr) => ptr.elem.clone(),
+                    _ => return None,
+                };
+                let mut field = self
+                    .type_converter
+                    .convert_boxed_type(field, ns, &TypeConversionContext::CxxInnerType)
+                    .ok()?;
+                self.extra_apis.append(&mut field.extra_apis);
+                let (token, api) = self
+                    .type_converter
+                    .get_member_pointer_typename(cpp_definition);
+                self.extra_apis.extend(api.into_iter());
+                Some(DataMemberPointer {
+                    token,
+                    class,
+                    field: *field.ty,
+                    field_deps: field.types_encountered,
+                })
+            })
+            .collect()
+    }
+
     fn get_cxx_bridge_name(
         &mut self,
         type_name: Option<&str>,
@@ -508,6 +814,18 @@ impl<'a> FnAnalyzer<'a> {
                 // Create a make_unique too
                 self.create_make_unique(fun, initial_name, &mut results);
 
+                // A subclass constructor passes its parameters straight to
+                // the superclass constructor's initializer, where we've no
+                // way to build a std::initializer_list.
+                if analysis.param_details.iter().any(|pd| {
+                    matches!(
+                        pd.conversion.cpp_conversion,
+                        CppConversionType::FromSliceToInitializerList
+                    )
+                }) {
+                    continue;
+                }
+
                 for sub in self.subclasses_by_superclass(sup) {
                     // Create a subclass constructor. This is a synthesized function
                     // which didn't exist in the original C++.
@@ -545,14 +863,27 @@ impl<'a> FnAnalyzer<'a> {
             self.analyze_foreign_fn(name, &fun, TypeConversionSophistication::Regular, None);
         let mut results = ApiVec::new();
 
-        // Consider whether we need to synthesize subclass items.
-        if let FnKind::Method {
-            impl_for: sup,
-            method_kind:
-                MethodKind::Virtual(receiver_mutability) | MethodKind::PureVirtual(receiver_mutability),
-            ..
-        } = &analysis.kind
-        {
+        // Consider whether we need to synthesize subclass items. Subclasses
+        // can override virtual methods, and can call protected methods,
+        // which nobody else can.
+        let subclass_method = match &analysis.kind {
+            FnKind::Method {
+                impl_for,
+                method_kind:
+                    MethodKind::Virtual(receiver_mutability)
+                    | MethodKind::PureVirtual(receiver_mutability),
+                ..
+            } => Some((impl_for, receiver_mutability, true)),
+            FnKind::Method {
+                impl_for,
+                method_kind: MethodKind::Normal(receiver_mutability),
+                ..
+            } if matches!(fun.cpp_vis, CppVisibility::Protected) => {
+                Some((impl_for, receiver_mutability, false))
+            }
+            _ => None,
+        };
+        if let Some((sup, receiver_mutability, is_virtual)) = subclass_method {
             let (simpler_analysis, _) = self.analyze_foreign_fn(
                 name.clone(),
                 &fun,
@@ -597,7 +928,8 @@ impl<'a> FnAnalyzer<'a> {
                 }
 
                 // Create the Rust API representing the subclass implementation (allowing calls
-                // from C++ -> Rust)
+                // from C++ -> Rust). For non-virtual methods there's no such implementation,
+                // but this still tells the C++ codegen to make the method calling the superclass.
                 results.push(create_subclass_function(
                     // RustSubclassFn
                     &sub,
@@ -606,6 +938,8 @@ impl<'a> FnAnalyzer<'a> {
                     receiver_mutability,
                     sup,
                     subclass_fn_deps,
+                    fun.calling_convention,
+                    is_virtual,
                 ));
 
                 // Create the trait item for the <superclass>_methods and <superclass>_supers
@@ -623,6 +957,7 @@ impl<'a> FnAnalyzer<'a> {
                         receiver_mutability,
                         sup.clone(),
                         is_pure_virtual,
+                        is_virtual,
                     ));
                 }
             }
@@ -697,6 +1032,7 @@ impl<'a> FnAnalyzer<'a> {
         predetermined_rust_name: Option<String>,
     ) -> (FnAnalysis, ApiName) {
         let mut cpp_name = name.cpp_name_if_present().cloned();
+        let mut paired_accessor = None;
         let ns = name.name.get_namespace();
 
         // Let's gather some pre-wisdom about the name of the function.
@@ -778,6 +1114,13 @@ impl<'a> FnAnalyzer<'a> {
             }
         };
 
+        // Copy and move assignment operators become `assign` and
+        // `assign_move` methods. Other flavors of `operator=` are ignored below.
+        let assignment_name = self_ty
+            .as_ref()
+            .and_then(|self_ty| assignment_operator_name(fun, self_ty, &param_details));
+        let is_move_assignment = assignment_name == Some("assign_move");
+
         // Part two, work out if this is a function, or method, or whatever.
         // First determine if this is actually a trait implementation.
         let trait_details = self.trait_creation_details_for_synthetic_function(
@@ -908,13 +1251,20 @@ impl<'a> FnAnalyzer<'a> {
                     let constructor_suffix = rust_name
                         .strip_prefix(nested_type_ident)
                         .or_else(|| rust_name.strip_prefix("new"))
-                        .unwrap();
+                        .unwrap_or_default();
+                    let constructor_suffix = self
+                        .constructor_suffix_from_params(&self_ty, &param_details)
+                        .unwrap_or_else(|| constructor_suffix.to_string());
                     rust_name = format!("make_unique{}", constructor_suffix);
                     // Strip off the 'this' arg.
                     params = params.into_iter().skip(1).collect();
                     param_details.remove(0);
                     MethodKind::MakeUnique
-                } else if let Some(constructor_suffix) = rust_name.strip_prefix(nested_type_ident) {
+                } else if matches!(
+                    fun.special_member,
+                    Some(SpecialMemberKind::DefaultConstructor | SpecialMemberKind::Constructor)
+                ) || rust_name == nested_type_ident
+                {
                     // It's a constructor. bindgen generates
                     // fn Type(this: *mut Type, ...args)
                     // We want
@@ -926,6 +1276,18 @@ impl<'a> FnAnalyzer<'a> {
                     // fn make_unique(...args) -> UniquePtr<Type>
                     // If there are multiple constructors, bindgen generates
                     // new, new1, new2 etc. and we'll keep those suffixes.
+                    // We know this is a constructor from bindgen's
+                    // annotations or 'impl' blocks, or because it has the
+                    // same name as the type, not from its name alone: a
+                    // method might happen to start with the type name.
+                    let constructor_suffix = self
+                        .constructor_suffix_from_params(&self_ty, &param_details)
+                        .unwrap_or_else(|| {
+                            rust_name
+                                .strip_prefix(nested_type_ident)
+                                .unwrap_or_default()
+                                .to_string()
+                        });
                     rust_name = format!("new{}", constructor_suffix);
                     MethodKind::Constructor {
                         is_default: matches!(
@@ -933,15 +1295,43 @@ impl<'a> FnAnalyzer<'a> {
                             Some(SpecialMemberKind::DefaultConstructor)
                         ),
                     }
-                } else if is_static_method {
-                    MethodKind::Static
                 } else {
-                    let receiver_mutability =
-                        receiver_mutability.expect("Failed to find receiver details");
-                    match fun.virtualness {
-                        Virtualness::None => MethodKind::Normal(receiver_mutability),
-                        Virtualness::Virtual => MethodKind::Virtual(receiver_mutability),
-                        Virtualness::PureVirtual => MethodKind::PureVirtual(receiver_mutability),
+                    // An ordinary method, which the user may have asked us to
+                    // rename to be more Rustic. The C++ name must be retained
+                    // so that we can still call it.
+                    if let Some(assignment_name) = assignment_name {
+                        // We always call assignment operators via a C++
+                        // wrapper, so there's no C++ name to retain.
+                        cpp_name = None;
+                        rust_name = assignment_name.to_string();
+                    } else if self.config.pair_accessors() {
+                        if let Some((accessor_name, partner_name)) =
+                            self.paired_accessor_names(&self_ty, &rust_name, fun, &param_details)
+                        {
+                            cpp_name.get_or_insert_with(|| rust_name.clone());
+                            rust_name = accessor_name;
+                            paired_accessor = Some(partner_name);
+                        }
+                    }
+                    if self.config.naming_policy() == NamingPolicy::SnakeCase {
+                        let snake_name = to_snake_case(&rust_name);
+                        if snake_name != rust_name {
+                            cpp_name.get_or_insert_with(|| rust_name.clone());
+                            rust_name = snake_name;
+                        }
+                    }
+                    if is_static_method {
+                        MethodKind::Static
+                    } else {
+                        let receiver_mutability =
+                            receiver_mutability.expect("Failed to find receiver details");
+                        match fun.virtualness {
+                            Virtualness::None => MethodKind::Normal(receiver_mutability),
+                            Virtualness::Virtual => MethodKind::Virtual(receiver_mutability),
+                            Virtualness::PureVirtual => {
+                                MethodKind::PureVirtual(receiver_mutability)
+                            }
+                        }
                     }
                 };
                 // Disambiguate overloads.
@@ -1066,9 +1456,66 @@ impl<'a> FnAnalyzer<'a> {
                 )
                 .unwrap_or_else(&mut set_ignore_reason);
             }
+            FnKind::Method { .. } if is_move_assignment => {
+                self.reanalyze_parameter(
+                    1,
+                    fun,
+                    ns,
+                    &rust_name,
+                    &mut params,
+                    &mut param_details,
+                    Some(RustConversionType::FromPinMoveRefToPtr),
+                    sophistication,
+                )
+                .unwrap_or_else(&mut set_ignore_reason);
+                // The MoveRef guarantees the pointer is valid, so this
+                // needn't make the method unsafe.
+                param_details[1].requires_unsafe = UnsafetyNeeded::JustBridge;
+            }
             _ => {}
         }
 
+        // POD types are `Unpin`, so if the user asked, don't make callers
+        // pin them to call mutable methods. Those take `&mut self` instead,
+        // whereas mutable methods of non-POD types always take
+        // `self: Pin<&mut Self>`.
+        if let FnKind::Method {
+            impl_for,
+            method_kind: MethodKind::Normal(ReceiverMutability::Mutable),
+        } = &kind
+        {
+            if self.config.unpin_pod_methods() && self.pod_safe_types.contains(impl_for) {
+                Self::unpin_receiver(&mut params, &mut param_details);
+            }
+        }
+
+        // The user may have asked for one of the pointer parameters to be
+        // treated as an extra return value.
+        let cpp_call_name = cpp_name.clone().unwrap_or_else(|| fun.ident.to_string());
+        let out_param_fn_name = match &kind {
+            FnKind::Method { impl_for, .. } | FnKind::TraitMethod { impl_for, .. } => {
+                format!("{}::{}", impl_for.to_cpp_name(), cpp_call_name)
+            }
+            FnKind::Function => QualifiedName::new(ns, make_ident(&cpp_call_name)).to_cpp_name(),
+        };
+        if self.config.qt_mode()
+            && matches!(kind, FnKind::Method { .. })
+            && MOC_GENERATED_MEMBERS.contains(&cpp_call_name.as_str())
+        {
+            set_ignore_reason(ConvertError::MocGeneratedMember);
+        }
+        if let Some(idx) = self.config.out_param_for(&out_param_fn_name) {
+            self.apply_out_param(idx, &kind, &mut param_details)
+                .unwrap_or_else(&mut set_ignore_reason);
+        }
+        for (ptr_idx, len_idx) in self.config.slice_params_for(&out_param_fn_name) {
+            self.apply_slice_params(ptr_idx, len_idx, &kind, &mut param_details)
+                .unwrap_or_else(&mut set_ignore_reason);
+        }
+        if self.config.c_string_params() {
+            Self::apply_c_string_params(&kind, &mut param_details);
+        }
+
         let requires_unsafe = self.should_be_unsafe(&param_details, &kind);
 
         // Now we can add context to the error, check for a variety of error
@@ -1083,16 +1530,42 @@ impl<'a> FnAnalyzer<'a> {
             CppVisibility::Protected => false,
             CppVisibility::Public => true,
         };
+        // Objects of refcounted! classes must be owned by their reference
+        // count, not by a UniquePtr.
+        let constructs_refcounted = match &kind {
+            FnKind::Method {
+                impl_for,
+                method_kind: MethodKind::Constructor { .. } | MethodKind::MakeUnique,
+                ..
+            } => self
+                .config
+                .refcounted_functions(&impl_for.to_cpp_name())
+                .is_some(),
+            _ => false,
+        };
         if matches!(
             fun.special_member,
             Some(SpecialMemberKind::AssignmentOperator)
-        ) {
+        ) && assignment_name.is_none()
+        {
             set_ignore_reason(ConvertError::AssignmentOperator)
         } else if fun.references.rvalue_ref_return {
             set_ignore_reason(ConvertError::RValueReturn)
+        } else if fun.references.rvalue_ref_this && !matches!(fun.virtualness, Virtualness::None) {
+            set_ignore_reason(ConvertError::RValueQualifiedVirtual)
         } else if fun.is_deleted {
             set_ignore_reason(ConvertError::Deleted)
+        } else if self.is_hidden(fun) && !self.config.force_wrapper_for(&out_param_fn_name) {
+            // Our C++ wrapper functions are inline in the generated header,
+            // so if that's built into the same library as this function,
+            // they can still call it.
+            set_ignore_reason(ConvertError::HiddenVisibility)
+        } else if constructs_refcounted {
+            set_ignore_reason(ConvertError::RefcountedConstructor)
+        } else if let Some(construct) = find_unrepresentable_abi(fun) {
+            set_ignore_reason(ConvertError::UnrepresentableAbi(construct))
         } else if !fun.references.rvalue_ref_params.is_empty()
+            && !is_move_assignment
             && !matches!(
                 kind,
                 FnKind::TraitMethod {
@@ -1160,6 +1633,7 @@ impl<'a> FnAnalyzer<'a> {
 
         // Analyze the return type, just as we previously did for the
         // parameters.
+        let mut unique_array_return = None;
         let mut return_analysis = if let FnKind::Method {
             ref impl_for,
             method_kind: MethodKind::MakeUnique,
@@ -1177,8 +1651,47 @@ impl<'a> FnAnalyzer<'a> {
                 was_reference: false,
                 deps: std::iter::once(impl_for).cloned().collect(),
             }
+        } else if let Some(elem) = match &fun.output {
+            ReturnType::Type(_, ty) => unique_array_element(ty),
+            ReturnType::Default => None,
+        } {
+            self.analyze_unique_array_return(
+                elem,
+                &out_param_fn_name,
+                &kind,
+                &param_details,
+                &cxxbridge_name,
+                ns,
+            )
+            .map(|(analysis, unique_array)| {
+                unique_array_return = Some(unique_array);
+                analysis
+            })
+            .unwrap_or_else(|err| {
+                set_ignore_reason(err);
+                ReturnTypeAnalysis::default()
+            })
         } else {
+            // Virtual methods must keep returning a reference, since
+            // subclasses may override them.
+            let may_copy_pod_return =
+                matches!(sophistication, TypeConversionSophistication::Regular)
+                    && matches!(
+                        kind,
+                        FnKind::Function
+                            | FnKind::Method {
+                                method_kind: MethodKind::Normal(_) | MethodKind::Static,
+                                ..
+                            }
+                    );
             self.convert_return_type(&fun.output, ns, &fun.references)
+                .map(|analysis| {
+                    if may_copy_pod_return {
+                        self.copy_small_pod_return(analysis)
+                    } else {
+                        analysis
+                    }
+                })
                 .unwrap_or_else(|err| {
                     set_ignore_reason(err);
                     ReturnTypeAnalysis::default()
@@ -1187,14 +1700,119 @@ impl<'a> FnAnalyzer<'a> {
         let mut deps = params_deps;
         deps.extend(return_analysis.deps.drain());
 
-        let num_input_references = param_details.iter().filter(|pd| pd.was_reference).count();
-        if num_input_references != 1 && return_analysis.was_reference {
-            // cxx only allows functions to return a reference if they take exactly
-            // one reference as a parameter. Let's see...
-            set_ignore_reason(ConvertError::NotOneInputReference(rust_name.clone()));
+        let return_borrows_from = if return_analysis.was_reference && assignment_name.is_none() {
+            self.find_borrowed_param(&out_param_fn_name, &rust_name, &param_details)
+                .unwrap_or_else(|err| {
+                    set_ignore_reason(err);
+                    None
+                })
+        } else {
+            None
+        };
+        let (mut ret_type, ret_type_conversion) = if assignment_name.is_some() {
+            // operator= conventionally returns `*this`, which is of no use
+            // to Rust callers and would fall foul of cxx's reference rules.
+            (ReturnType::Default, None)
+        } else {
+            (return_analysis.rt, return_analysis.conversion)
+        };
+        let error_code_success = match (&kind, &ret_type) {
+            (FnKind::Function | FnKind::Method { .. }, ReturnType::Type(_, ty)) => {
+                match ty.as_ref() {
+                    Type::Path(typ) => self
+                        .config
+                        .error_code_success_value(&QualifiedName::from_type_path(typ).to_cpp_name())
+                        .map(make_ident),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let string_return = matches!(kind, FnKind::Function | FnKind::Method { .. })
+            && self.config.string_return_for(&out_param_fn_name)
+            && ret_type_conversion.as_ref().map_or(false, |conversion| {
+                matches!(
+                    conversion.cpp_conversion,
+                    CppConversionType::FromValueToUniquePtr
+                ) && match &conversion.unwrapped_type {
+                    Type::Path(typ) => {
+                        known_types().convertible_from_strs(&QualifiedName::from_type_path(typ))
+                    }
+                    _ => false,
+                }
+            });
+        let result_return = match (&kind, &fun.output, &ret_type_conversion) {
+            (
+                FnKind::Function | FnKind::Method { .. },
+                ReturnType::Type(_, raw_ret_type),
+                Some(conversion),
+            ) => self.analyze_result_return(raw_ret_type, conversion, ns, &mut deps),
+            _ => None,
+        };
+        let smart_pointer_return = match (&kind, &fun.output, &ret_type_conversion) {
+            (
+                FnKind::Function | FnKind::Method { .. },
+                ReturnType::Type(_, raw_ret_type),
+                Some(conversion),
+            ) if result_return.is_none() => {
+                self.analyze_smart_pointer_return(raw_ret_type, conversion, ns, &mut deps)
+            }
+            _ => None,
+        };
+        let refcounted_return = match (&kind, &ret_type) {
+            (FnKind::Function | FnKind::Method { .. }, ReturnType::Type(_, ty))
+                if result_return.is_none() =>
+            {
+                self.analyze_refcounted_return(ty)
+            }
+            _ => None,
+        };
+        let last_error = match self.config.last_error_for(&out_param_fn_name) {
+            Some((fetch, failure)) => {
+                match self.analyze_last_error(
+                    fetch,
+                    failure,
+                    &kind,
+                    &ret_type_conversion,
+                    &mut deps,
+                ) {
+                    Ok(last_error) => Some(last_error),
+                    Err(err) => {
+                        set_ignore_reason(err);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        let async_wrapper = self.config.async_wrapper_for(&out_param_fn_name);
+        if async_wrapper
+            && !(self.can_generate_async_wrapper(
+                &kind,
+                &param_details,
+                &ret_type_conversion,
+                string_return,
+            ) && result_return.is_none()
+                && smart_pointer_return.is_none()
+                && unique_array_return.is_none()
+                && refcounted_return.is_none()
+                && last_error.as_ref().map_or(true, |last_error| {
+                    self.is_send_by_value(&last_error.error_ty)
+                }))
+        {
+            set_ignore_reason(ConvertError::InvalidAsyncWrapper);
         }
-        let mut ret_type = return_analysis.rt;
-        let ret_type_conversion = return_analysis.conversion;
+        // If the user asked us to instrument calls, every function and method
+        // needs a Rust wrapper in which to do so. Methods then also need a C++
+        // wrapper, so that the Rust wrapper doesn't clash with the method
+        // which cxx generates.
+        let instrumented = self.config.instrument_calls()
+            && matches!(sophistication, TypeConversionSophistication::Regular)
+            && !matches!(
+                fun.provenance,
+                Provenance::SynthesizedSubclassConstructor(_)
+            )
+            && matches!(kind, FnKind::Function | FnKind::Method { .. });
 
         // Do we need to convert either parameters or return type?
         let param_conversion_needed = param_details.iter().any(|b| b.conversion.cpp_work_needed());
@@ -1205,6 +1823,25 @@ impl<'a> FnAnalyzer<'a> {
         let effective_cpp_name = cpp_name.as_ref().unwrap_or(&rust_name);
         let cpp_name_incompatible_with_cxx =
             validate_ident_ok_for_rust(effective_cpp_name).is_err();
+        // If the user asked us to look up functions in a shared library at
+        // runtime, each function and non-virtual method needs a C++ wrapper
+        // which calls it through a function pointer found by symbol name.
+        let dynamic_symbol = match kind {
+            FnKind::Function
+            | FnKind::Method {
+                method_kind: MethodKind::Normal(_) | MethodKind::Static,
+                ..
+            } if self.config.dynamic_library().is_some()
+                && matches!(sophistication, TypeConversionSophistication::Regular)
+                && matches!(fun.provenance, Provenance::Bindgen)
+                && fun.synthetic_cpp.is_none()
+                && assignment_name.is_none()
+                && self.is_out_of_line(fun) =>
+            {
+                dynamic_symbol(fun, &kind, effective_cpp_name)
+            }
+            _ => None,
+        };
         // If possible, we'll put knowledge of the C++ API directly into the cxx::bridge
         // mod. However, there are various circumstances where cxx can't work with the existing
         // C++ API and we need to create a C++ wrapper function which is more cxx-compliant.
@@ -1231,6 +1868,15 @@ impl<'a> FnAnalyzer<'a> {
             _ if ret_type_conversion_needed => true,
             _ if cpp_name_incompatible_with_cxx => true,
             _ if fun.synthetic_cpp.is_some() => true,
+            _ if assignment_name.is_some() => true,
+            FnKind::Method { .. } if instrumented => true,
+            _ if dynamic_symbol.is_some() => true,
+            _ if last_error.is_some() => true,
+            FnKind::Function | FnKind::Method { .. }
+                if self.config.force_wrapper_for(&out_param_fn_name) =>
+            {
+                true
+            }
             _ => false,
         };
 
@@ -1251,6 +1897,9 @@ impl<'a> FnAnalyzer<'a> {
                         method_kind: MethodKind::MakeUnique,
                         ..
                     } => (CppFunctionBody::MakeUnique, CppFunctionKind::Function),
+                    FnKind::Method { .. } if assignment_name.is_some() => {
+                        (CppFunctionBody::AssignmentOperator, CppFunctionKind::Method)
+                    }
                     FnKind::Method {
                         ref impl_for,
                         method_kind: MethodKind::Constructor { .. },
@@ -1294,6 +1943,13 @@ impl<'a> FnAnalyzer<'a> {
                     ),
                 },
             };
+            let payload = match (payload, dynamic_symbol) {
+                (
+                    CppFunctionBody::FunctionCall(..) | CppFunctionBody::StaticMethodCall(..),
+                    Some(symbol),
+                ) => CppFunctionBody::DynamicCall(symbol),
+                (payload, _) => payload,
+            };
             // Now modify the cxx::bridge entry we're going to make.
             if let Some(ref conversion) = ret_type_conversion {
                 let new_ret_type = conversion.unconverted_rust_type();
@@ -1335,6 +1991,8 @@ impl<'a> FnAnalyzer<'a> {
                 kind: cpp_function_kind,
                 pass_obs_field: false,
                 qualification: None,
+                calling_convention: None,
+                last_error: last_error.clone(),
             })
         } else {
             None
@@ -1348,6 +2006,18 @@ impl<'a> FnAnalyzer<'a> {
 
         let rust_wrapper_needed = match kind {
             FnKind::TraitMethod { .. } => true,
+            _ if error_code_success.is_some()
+                || string_return
+                || result_return.is_some()
+                || smart_pointer_return.is_some()
+                || unique_array_return.is_some()
+                || refcounted_return.is_some()
+                || last_error.is_some()
+                || async_wrapper
+                || instrumented =>
+            {
+                true
+            }
             FnKind::Method { .. } => any_param_needs_rust_conversion || cxxbridge_name != rust_name,
             _ => any_param_needs_rust_conversion,
         };
@@ -1364,6 +2034,7 @@ impl<'a> FnAnalyzer<'a> {
             _ => RustRenameStrategy::None,
         };
 
+        let data_member_pointers = self.analyze_data_member_pointers(fun, &ns);
         let analysis = FnAnalysis {
             cxxbridge_name: cxxbridge_name.clone(),
             rust_name: rust_name.clone(),
@@ -1373,6 +2044,7 @@ impl<'a> FnAnalyzer<'a> {
             kind,
             ret_type,
             param_details,
+            return_borrows_from,
             requires_unsafe,
             vis,
             cpp_wrapper,
@@ -1380,11 +2052,268 @@ impl<'a> FnAnalyzer<'a> {
             ignore_reason,
             externally_callable,
             rust_wrapper_needed,
+            error_code_success,
+            string_return,
+            result_return,
+            smart_pointer_return,
+            unique_array_return,
+            refcounted_return,
+            last_error,
+            async_wrapper,
+            data_member_pointers,
+            paired_accessor,
+            renamed_from: None,
         };
         let name = ApiName::new_with_cpp_name(ns, cxxbridge_name, cpp_name);
         (analysis, name)
     }
 
+    /// If the user asked for constructors to be named after their
+    /// parameters, returns a suffix such as `_with_width_and_height`.
+    /// Returns `None` for constructors without parameters, and where
+    /// another constructor has parameters of the same names, in which
+    /// case the constructors are numbered instead.
+    fn constructor_suffix_from_params(
+        &self,
+        self_ty: &QualifiedName,
+        param_details: &[ArgumentAnalysis],
+    ) -> Option<String> {
+        if self.config.constructor_naming() != ConstructorNaming::ByParameters {
+            return None;
+        }
+        let suffix = suffix_from_param_names(
+            param_details
+                .iter()
+                .filter(|pd| pd.self_type.is_none())
+                .map(|pd| &pd.name),
+        )?;
+        if self
+            .ambiguous_constructor_suffixes
+            .contains(&(self_ty.clone(), suffix.clone()))
+        {
+            None
+        } else {
+            Some(suffix)
+        }
+    }
+
+    /// If this method is a getter or setter, and the other half of the
+    /// pair exists, returns the Rust names for this method and for its
+    /// partner. Getters are named after the property (`foo`) and setters
+    /// have a `set_` prefix (`set_foo`).
+    fn paired_accessor_names(
+        &self,
+        self_ty: &QualifiedName,
+        method_name: &str,
+        fun: &FuncToConvert,
+        param_details: &[ArgumentAnalysis],
+    ) -> Option<(String, String)> {
+        let (kind, partner_cpp_name, property) = split_accessor_name(method_name)?;
+        if !self
+            .method_names_by_type
+            .get(self_ty)?
+            .contains(&partner_cpp_name)
+        {
+            return None;
+        }
+        let non_self_params = param_details
+            .iter()
+            .filter(|pd| pd.self_type.is_none())
+            .count();
+        let getter_name = property.clone();
+        let setter_name = format!("set_{}", property.trim_end_matches('_'));
+        match kind {
+            AccessorKind::Getter
+                if non_self_params == 0 && !matches!(fun.output, ReturnType::Default) =>
+            {
+                Some((getter_name, setter_name))
+            }
+            AccessorKind::Setter if non_self_params == 1 => Some((setter_name, getter_name)),
+            _ => None,
+        }
+    }
+
+    /// Works out which parameter a returned reference borrows from, if
+    /// Rust's lifetime elision rules wouldn't get it right: the parameter
+    /// named by any `borrows_from!` directive, otherwise `this`. If there's
+    /// no `this`, there must be exactly one reference parameter, which
+    /// elision handles.
+    fn find_borrowed_param(
+        &self,
+        fn_name: &str,
+        rust_name: &str,
+        param_details: &[ArgumentAnalysis],
+    ) -> Result<Option<usize>, ConvertError> {
+        if let Some(idx) = self.config.borrowed_param_for(fn_name) {
+            return param_details
+                .iter()
+                .enumerate()
+                .filter(|(_, pd)| pd.self_type.is_none())
+                .nth(idx)
+                .filter(|(_, pd)| pd.was_reference)
+                .map(|(pos, _)| Some(pos))
+                .ok_or(ConvertError::InvalidBorrowsFrom(idx));
+        }
+        let num_input_references = param_details.iter().filter(|pd| pd.was_reference).count();
+        if num_input_references == 1 {
+            return Ok(None);
+        }
+        param_details
+            .iter()
+            .position(|pd| pd.self_type.is_some())
+            .map(Some)
+            .ok_or_else(|| ConvertError::NotOneInputReference(rust_name.to_string()))
+    }
+
+    /// Marks the parameter at index `idx` (not counting any `this` parameter)
+    /// as an out parameter. Its pointer will be created and managed by the
+    /// Rust wrapper function, so it doesn't make the wrapper unsafe.
+    fn apply_out_param(
+        &self,
+        idx: usize,
+        kind: &FnKind,
+        param_details: &mut [ArgumentAnalysis],
+    ) -> Result<(), ConvertError> {
+        let supported_kind = matches!(
+            kind,
+            FnKind::Function
+                | FnKind::Method {
+                    method_kind: MethodKind::Normal(_) | MethodKind::Static,
+                    ..
+                }
+        );
+        let pd = param_details
+            .iter_mut()
+            .filter(|pd| pd.self_type.is_none())
+            .nth(idx)
+            .filter(|_| supported_kind)
+            .ok_or(ConvertError::InvalidOutParam(idx))?;
+        let pointee_is_pod = match &pd.conversion.unwrapped_type {
+            Type::Ptr(TypePtr {
+                elem,
+                mutability: Some(_),
+                ..
+            }) => match elem.as_ref() {
+                Type::Path(typ) => self
+                    .pod_safe_types
+                    .contains(&QualifiedName::from_type_path(typ)),
+                _ => false,
+            },
+            _ => false,
+        };
+        if !pointee_is_pod || pd.conversion.cpp_work_needed() || pd.conversion.rust_work_needed() {
+            return Err(ConvertError::InvalidOutParam(idx));
+        }
+        pd.conversion.rust_conversion = RustConversionType::FromOutParamToPtr;
+        pd.requires_unsafe = UnsafetyNeeded::JustBridge;
+        Ok(())
+    }
+
+    /// Combines the pointer parameter at index `ptr_idx` and the length
+    /// parameter at index `len_idx` (neither counting any `this` parameter)
+    /// into a single slice parameter. The wrapper derives both from the
+    /// slice, so neither makes the wrapper unsafe.
+    fn apply_slice_params(
+        &self,
+        ptr_idx: usize,
+        len_idx: usize,
+        kind: &FnKind,
+        param_details: &mut [ArgumentAnalysis],
+    ) -> Result<(), ConvertError> {
+        let supported_kind = matches!(
+            kind,
+            FnKind::Function
+                | FnKind::Method {
+                    method_kind: MethodKind::Normal(_) | MethodKind::Static,
+                    ..
+                }
+        );
+        let err = ConvertError::InvalidSliceParams(ptr_idx, len_idx);
+        if !supported_kind {
+            return Err(err);
+        }
+        let mut params: Vec<_> = param_details
+            .iter_mut()
+            .filter(|pd| pd.self_type.is_none())
+            .collect();
+        if ptr_idx >= params.len() || len_idx >= params.len() {
+            return Err(err);
+        }
+        let pointee_is_pod = match &params[ptr_idx].conversion.unwrapped_type {
+            Type::Ptr(TypePtr { elem, .. }) => match elem.as_ref() {
+                Type::Path(typ) => self
+                    .pod_safe_types
+                    .contains(&QualifiedName::from_type_path(typ)),
+                _ => false,
+            },
+            _ => false,
+        };
+        let len_is_integer = match &params[len_idx].conversion.unwrapped_type {
+            Type::Path(typ) => {
+                let tn = QualifiedName::from_type_path(typ);
+                known_types().is_ctype(&tn) || INTEGER_TYPES.contains(&tn.to_cpp_name().as_str())
+            }
+            _ => false,
+        };
+        let unconverted = |pd: &ArgumentAnalysis| {
+            !pd.conversion.cpp_work_needed() && !pd.conversion.rust_work_needed()
+        };
+        let slice_name = match &params[ptr_idx].name {
+            Pat::Ident(pi) => pi.ident.clone(),
+            _ => return Err(err),
+        };
+        if !pointee_is_pod
+            || !len_is_integer
+            || !unconverted(&params[ptr_idx])
+            || !unconverted(&params[len_idx])
+        {
+            return Err(err);
+        }
+        params[ptr_idx].conversion.rust_conversion = RustConversionType::FromSliceToPtr;
+        params[ptr_idx].requires_unsafe = UnsafetyNeeded::JustBridge;
+        params[len_idx].conversion.rust_conversion = RustConversionType::FromSliceToLen(slice_name);
+        params[len_idx].requires_unsafe = UnsafetyNeeded::JustBridge;
+        Ok(())
+    }
+
+    /// Allows any `const char*` parameters to be passed as a `&CStr`, which
+    /// guarantees a valid NUL-terminated string and so doesn't make the
+    /// wrapper unsafe.
+    fn apply_c_string_params(kind: &FnKind, param_details: &mut [ArgumentAnalysis]) {
+        let supported_kind = matches!(
+            kind,
+            FnKind::Function
+                | FnKind::Method {
+                    method_kind: MethodKind::Normal(_) | MethodKind::Static,
+                    ..
+                }
+        );
+        if !supported_kind {
+            return;
+        }
+        let c_char = QualifiedName::new_from_cpp_name("std::os::raw::c_char");
+        for pd in param_details.iter_mut().filter(|pd| pd.self_type.is_none()) {
+            let is_const_char_ptr = match &pd.conversion.unwrapped_type {
+                Type::Ptr(TypePtr {
+                    elem,
+                    mutability: None,
+                    ..
+                }) => match elem.as_ref() {
+                    Type::Path(typ) => QualifiedName::from_type_path(typ) == c_char,
+                    _ => false,
+                },
+                _ => false,
+            };
+            if is_const_char_ptr
+                && !pd.conversion.cpp_work_needed()
+                && !pd.conversion.rust_work_needed()
+            {
+                pd.conversion.rust_conversion = RustConversionType::FromCStrToPtr;
+                pd.requires_unsafe = UnsafetyNeeded::JustBridge;
+            }
+        }
+    }
+
     /// Applies a specific `force_rust_conversion` to the parameter at index
     /// `param_idx`. Modifies `param_details` and `params` in place.
     #[allow(clippy::too_many_arguments)] // it's true, but sticking with it for now
@@ -1425,6 +2354,33 @@ impl<'a> FnAnalyzer<'a> {
         })
     }
 
+    /// Turns a `self: Pin<&mut T>` receiver into `self: &mut T`.
+    fn unpin_receiver(
+        params: &mut Punctuated<FnArg, Comma>,
+        param_details: &mut [ArgumentAnalysis],
+    ) {
+        let idx = match param_details.iter().position(|pd| pd.self_type.is_some()) {
+            Some(idx) => idx,
+            None => return,
+        };
+        let unpinned = match &param_details[idx].conversion.unwrapped_type {
+            Type::Path(typ) => match typ.path.segments.last().map(|seg| &seg.arguments) {
+                Some(PathArguments::AngleBracketed(ab)) => match ab.args.first() {
+                    Some(GenericArgument::Type(reference @ Type::Reference(_))) => {
+                        reference.clone()
+                    }
+                    _ => return,
+                },
+                _ => return,
+            },
+            _ => return,
+        };
+        if let Some(FnArg::Typed(pt)) = params.iter_mut().nth(idx) {
+            pt.ty = Box::new(unpinned.clone());
+        }
+        param_details[idx].conversion.unwrapped_type = unpinned;
+    }
+
     fn get_overload_name(&mut self, ns: &Namespace, type_ident: &str, rust_name: String) -> String {
         let overload_tracker = self.overload_trackers_by_mod.entry(ns.clone()).or_default();
         overload_tracker.get_method_real_name(type_ident, rust_name)
@@ -1570,6 +2526,7 @@ impl<'a> FnAnalyzer<'a> {
                 let old_pat = *pt.pat;
                 let mut treat_as_reference = false;
                 let mut treat_as_rvalue_reference = false;
+                let mut consumes_this = false;
                 let new_pat = match old_pat {
                     syn::Pat::Ident(mut pp) if pp.ident == "this" => {
                         let this_type = match pt.ty.as_ref() {
@@ -1610,6 +2567,7 @@ impl<'a> FnAnalyzer<'a> {
                         if treat_this_as_reference {
                             pp.ident = Ident::new("self", pp.ident.span());
                             treat_as_reference = true;
+                            consumes_this = references.rvalue_ref_this;
                         }
                         syn::Pat::Ident(pp)
                     }
@@ -1622,29 +2580,76 @@ impl<'a> FnAnalyzer<'a> {
                     }
                     _ => old_pat,
                 };
-                let annotated_type = self.convert_boxed_type(pt.ty, ns, treat_as_reference)?;
+                let member_pointer = match &new_pat {
+                    Pat::Ident(pp) => references.member_pointer_params.get(&pp.ident),
+                    _ => None,
+                };
+                let annotated_type = match member_pointer {
+                    Some(cpp_definition) => self.convert_member_pointer(cpp_definition),
+                    None => self.convert_boxed_type(pt.ty, ns, treat_as_reference)?,
+                };
                 let new_ty = annotated_type.ty;
                 let subclass_holder = match &annotated_type.kind {
                     type_converter::TypeKind::SubclassHolder(holder) => Some(holder),
                     _ => None,
                 };
-                let conversion = self.argument_conversion_details(
-                    &new_ty,
-                    &subclass_holder.cloned(),
-                    treat_as_rvalue_reference,
-                    force_rust_conversion,
-                    sophistication,
-                );
+                let mut conversion = match &annotated_type.kind {
+                    type_converter::TypeKind::View(view_name)
+                        if known_types().view_kind(view_name)
+                            == Some(ViewKind::InitializerList) =>
+                    {
+                        TypeConversionPolicy::new_from_slice_to_initializer_list(
+                            new_ty.as_ref().clone(),
+                        )
+                    }
+                    type_converter::TypeKind::View(view_name) => {
+                        TypeConversionPolicy::new_from_rust_view(
+                            new_ty.as_ref().clone(),
+                            view_name.to_cpp_name(),
+                        )
+                    }
+                    type_converter::TypeKind::Array(shape) => {
+                        // Rust code will see the elements, so they must be
+                        // safe to hold by value.
+                        let elem_is_pod = match new_ty.as_ref() {
+                            Type::Ptr(TypePtr { elem, .. }) => match elem.as_ref() {
+                                Type::Path(typ) => self
+                                    .pod_safe_types
+                                    .contains(&QualifiedName::from_type_path(typ)),
+                                _ => false,
+                            },
+                            _ => false,
+                        };
+                        if !elem_is_pod {
+                            return Err(ConvertError::ArrayOfNonPodType);
+                        }
+                        TypeConversionPolicy::new_from_array(new_ty.as_ref().clone(), shape.clone())
+                    }
+                    _ => self.argument_conversion_details(
+                        &new_ty,
+                        &subclass_holder.cloned(),
+                        treat_as_rvalue_reference,
+                        force_rust_conversion,
+                        sophistication,
+                    ),
+                };
+                if consumes_this {
+                    // A `&&`-qualified method has to be called on an rvalue,
+                    // so the C++ wrapper moves from the receiver. Rust still
+                    // passes it by reference, leaving it moved-from.
+                    conversion.cpp_conversion = CppConversionType::Move;
+                }
                 pt.pat = Box::new(new_pat.clone());
                 pt.ty = new_ty;
-                let requires_unsafe =
-                    if matches!(annotated_type.kind, type_converter::TypeKind::Pointer) {
+                let requires_unsafe = match &annotated_type.kind {
+                    type_converter::TypeKind::Pointer => UnsafetyNeeded::Always,
+                    type_converter::TypeKind::Array(shape) if !shape.by_reference => {
                         UnsafetyNeeded::Always
-                    } else if conversion.bridge_unsafe_needed() {
-                        UnsafetyNeeded::JustBridge
-                    } else {
-                        UnsafetyNeeded::None
-                    };
+                    }
+                    type_converter::TypeKind::Array(_) => UnsafetyNeeded::JustBridge,
+                    _ if conversion.bridge_unsafe_needed() => UnsafetyNeeded::JustBridge,
+                    _ => UnsafetyNeeded::None,
+                };
                 (
                     FnArg::Typed(pt),
                     ArgumentAnalysis {
@@ -1655,13 +2660,20 @@ impl<'a> FnAnalyzer<'a> {
                             annotated_type.kind,
                             type_converter::TypeKind::Reference
                                 | type_converter::TypeKind::MutableReference
+                                | type_converter::TypeKind::View(_)
+                                | type_converter::TypeKind::Array(ArrayShape {
+                                    by_reference: true,
+                                    ..
+                                })
                         ),
                         deps: annotated_type.types_encountered,
                         requires_unsafe,
                     },
                 )
             }
-            _ => panic!("Did not expect FnArg::Receiver to be generated by bindgen"),
+            // bindgen represents `this` as an explicit parameter, so
+            // we don't expect this, but if it happens we can't handle it.
+            FnArg::Receiver(_) => return Err(ConvertError::UnsupportedReceiver),
         })
     }
 
@@ -1755,6 +2767,310 @@ impl<'a> FnAnalyzer<'a> {
         }
     }
 
+    /// If a function returns `absl::Status`, `absl::StatusOr<T>` or a
+    /// `result_type!` by value, work out how its Rust wrapper can return a
+    /// `Result` instead.
+    fn analyze_result_return(
+        &mut self,
+        raw_ret_type: &Type,
+        ret_type_conversion: &TypeConversionPolicy,
+        ns: &Namespace,
+        deps: &mut HashSet<QualifiedName>,
+    ) -> Option<ResultReturn> {
+        if !matches!(
+            ret_type_conversion.cpp_conversion,
+            CppConversionType::FromValueToUniquePtr
+        ) {
+            return None;
+        }
+        let result_type = match &ret_type_conversion.unwrapped_type {
+            Type::Path(typ) => QualifiedName::from_type_path(typ),
+            _ => return None,
+        };
+        let config = self.config;
+        let (value, kind) = match result_type_kind(raw_ret_type, config)? {
+            ResultTypeKind::Status => (None, ResultReturnKind::Abseil),
+            ResultTypeKind::StatusOr(payload) => (
+                Some(self.analyze_result_payload(payload, ns, deps)?),
+                ResultReturnKind::Abseil,
+            ),
+            ResultTypeKind::Custom {
+                value,
+                error,
+                value_accessor,
+                error_accessor,
+            } => {
+                let value = match value {
+                    Some(value) => Some(self.analyze_result_payload(value, ns, deps)?),
+                    None => None,
+                };
+                let error = self.analyze_result_payload(error, ns, deps)?;
+                (
+                    value,
+                    ResultReturnKind::Custom {
+                        error,
+                        value_accessor: value_accessor.to_string(),
+                        error_accessor: error_accessor.to_string(),
+                    },
+                )
+            }
+        };
+        Some(ResultReturn {
+            result_type,
+            value,
+            kind,
+        })
+    }
+
+    /// If a function returns a `smart_pointer!` by value, work out how its
+    /// Rust wrapper can return an `autocxx::SmartPtr` instead.
+    fn analyze_smart_pointer_return(
+        &mut self,
+        raw_ret_type: &Type,
+        ret_type_conversion: &TypeConversionPolicy,
+        ns: &Namespace,
+        deps: &mut HashSet<QualifiedName>,
+    ) -> Option<SmartPointerReturn> {
+        if !matches!(
+            ret_type_conversion.cpp_conversion,
+            CppConversionType::FromValueToUniquePtr
+        ) {
+            return None;
+        }
+        let ptr_type = ret_type_conversion.unwrapped_type.clone();
+        let ptr_name = match &ptr_type {
+            Type::Path(typ) => QualifiedName::from_type_path(typ),
+            _ => return None,
+        };
+        let config = self.config;
+        let (target, deref_accessor) = smart_pointer_target(raw_ret_type, config)?;
+        let annotated = self
+            .convert_boxed_type(Box::new(target.clone()), ns, false)
+            .ok()?;
+        if !matches!(annotated.ty.as_ref(), Type::Path(_)) {
+            return None;
+        }
+        deps.extend(annotated.types_encountered);
+        Some(SmartPointerReturn {
+            ptr_name,
+            ptr_type,
+            target: *annotated.ty,
+            deref_accessor: deref_accessor.to_string(),
+        })
+    }
+
+    /// If a function returns a pointer to a class named in a `refcounted!`
+    /// directive, work out how its Rust wrapper can return an
+    /// `autocxx::RcHandle` instead. The handle takes over the reference
+    /// which the function returned.
+    fn analyze_refcounted_return(&self, ret_type: &Type) -> Option<RefcountedReturn> {
+        let ptr = match ret_type {
+            Type::Ptr(ptr) => ptr,
+            _ => return None,
+        };
+        let typ = match ptr.elem.as_ref() {
+            Type::Path(typ) => typ,
+            _ => return None,
+        };
+        let name = QualifiedName::from_type_path(typ);
+        let (addref, release) = self.config.refcounted_functions(&name.to_cpp_name())?;
+        Some(RefcountedReturn {
+            name,
+            ty: Type::Path(typ.clone()),
+            is_const: ptr.const_token.is_some(),
+            addref: addref.to_string(),
+            release: release.to_string(),
+        })
+    }
+
+    /// For a function named in a `last_error!` directive, work out how its
+    /// C++ wrapper can capture the error. The function's return value is
+    /// compared against the failure value in C++, so it must cross the
+    /// bridge unchanged, and the error must be of a type which can be
+    /// passed back by pointer.
+    fn analyze_last_error(
+        &mut self,
+        fetch: &str,
+        failure: &str,
+        kind: &FnKind,
+        ret_type_conversion: &Option<TypeConversionPolicy>,
+        deps: &mut HashSet<QualifiedName>,
+    ) -> Result<LastError, ConvertError> {
+        let err = || ConvertError::InvalidLastError(fetch.to_string());
+        let returns_unchanged = matches!(
+            kind,
+            FnKind::Function
+                | FnKind::Method {
+                    method_kind: MethodKind::Normal(_) | MethodKind::Static,
+                    ..
+                }
+        ) && ret_type_conversion.as_ref().map_or(false, |conversion| {
+            matches!(conversion.cpp_conversion, CppConversionType::None)
+        });
+        if !returns_unchanged {
+            return Err(err());
+        }
+        let (ns, raw_error_ty) = self
+            .last_error_fetchers
+            .get(fetch)
+            .cloned()
+            .ok_or_else(err)?;
+        let annotated = self
+            .convert_boxed_type(raw_error_ty, &ns, false)
+            .map_err(|_| err())?;
+        if !matches!(annotated.ty.as_ref(), Type::Path(_)) {
+            return Err(err());
+        }
+        deps.extend(annotated.types_encountered);
+        Ok(LastError {
+            fetch_fn: QualifiedName::new_from_cpp_name(fetch),
+            failure: failure.to_string(),
+            error_ty: *annotated.ty,
+        })
+    }
+
+    /// Whether a function named in an `async_wrapper!` directive can have
+    /// an `_async` variant. It must be a free function, and everything
+    /// passed to it or returned from it must be able to move to the thread
+    /// on which the call is made.
+    fn can_generate_async_wrapper(
+        &self,
+        kind: &FnKind,
+        param_details: &[ArgumentAnalysis],
+        ret_type_conversion: &Option<TypeConversionPolicy>,
+        string_return: bool,
+    ) -> bool {
+        let is_sendable = |conversion: &TypeConversionPolicy| {
+            matches!(conversion.cpp_conversion, CppConversionType::None)
+                && matches!(conversion.rust_conversion, RustConversionType::None)
+                && self.is_send_by_value(&conversion.unwrapped_type)
+        };
+        matches!(kind, FnKind::Function)
+            && param_details.iter().all(|pd| is_sendable(&pd.conversion))
+            && (string_return || ret_type_conversion.as_ref().map_or(true, is_sendable))
+    }
+
+    fn is_send_by_value(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Path(typ) => {
+                let tn = QualifiedName::from_type_path(typ);
+                if known_types().is_known_type(&tn) {
+                    known_types().is_send_by_value(&tn)
+                } else {
+                    self.pod_safe_types.contains(&tn) && !self.pod_types_with_pointers.contains(&tn)
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// If a function returns a `std::unique_ptr<T[]>`, work out how its Rust
+    /// wrapper can return an `autocxx::UniqueArray` instead, using the
+    /// length given by a `unique_array!` directive. The C++ wrapper releases
+    /// the raw pointer, which is what crosses the bridge.
+    fn analyze_unique_array_return(
+        &mut self,
+        elem: &Type,
+        fn_name: &str,
+        kind: &FnKind,
+        param_details: &[ArgumentAnalysis],
+        cxxbridge_name: &Ident,
+        ns: &Namespace,
+    ) -> Result<(ReturnTypeAnalysis, UniqueArrayReturn), ConvertError> {
+        let len = self
+            .config
+            .unique_array_length_for(fn_name)
+            .ok_or(ConvertError::UniqueArrayWithoutLength)?;
+        let err = ConvertError::InvalidUniqueArray;
+        let annotated = self
+            .convert_boxed_type(Box::new(elem.clone()), ns, false)
+            .map_err(|_| err.clone())?;
+        let elem_is_pod = match annotated.ty.as_ref() {
+            Type::Path(typ) => self
+                .pod_safe_types
+                .contains(&QualifiedName::from_type_path(typ)),
+            _ => false,
+        };
+        if !elem_is_pod {
+            return Err(err);
+        }
+        let len = match (len, kind) {
+            (
+                UniqueArrayLength::Param(idx),
+                FnKind::Function
+                | FnKind::Method {
+                    method_kind: MethodKind::Normal(_) | MethodKind::Static,
+                    ..
+                },
+            ) => {
+                let pd = param_details
+                    .iter()
+                    .filter(|pd| pd.self_type.is_none())
+                    .nth(*idx)
+                    .ok_or_else(|| err.clone())?;
+                let (name, is_ctype) = match (&pd.name, &pd.conversion.unwrapped_type) {
+                    (Pat::Ident(pi), Type::Path(typ)) => {
+                        let tn = QualifiedName::from_type_path(typ);
+                        let is_ctype = known_types().is_ctype(&tn);
+                        if !is_ctype && !INTEGER_TYPES.contains(&tn.to_cpp_name().as_str()) {
+                            return Err(err);
+                        }
+                        (pi.ident.clone(), is_ctype)
+                    }
+                    _ => return Err(err),
+                };
+                UniqueArrayLen::Param { name, is_ctype }
+            }
+            (
+                UniqueArrayLength::Function(len_fn),
+                FnKind::Method {
+                    method_kind: MethodKind::Normal(_),
+                    ..
+                },
+            ) => UniqueArrayLen::Method(make_ident(len_fn)),
+            (
+                UniqueArrayLength::Function(len_fn),
+                FnKind::Method {
+                    method_kind: MethodKind::Static,
+                    ..
+                },
+            ) => UniqueArrayLen::StaticMethod(make_ident(len_fn)),
+            (UniqueArrayLength::Function(len_fn), FnKind::Function) => {
+                UniqueArrayLen::Function(make_ident(len_fn))
+            }
+            _ => return Err(err),
+        };
+        let elem = *annotated.ty;
+        let ptr_type: Type = parse_quote! { *mut #elem };
+        Ok((
+            ReturnTypeAnalysis {
+                rt: parse_quote! { -> #ptr_type },
+                conversion: Some(TypeConversionPolicy::new_from_unique_array(ptr_type)),
+                was_reference: false,
+                deps: annotated.types_encountered,
+            },
+            UniqueArrayReturn::new(elem, len, cxxbridge_name),
+        ))
+    }
+
+    /// Work out how to return the value or error held by a type which we're
+    /// turning into a `Result`.
+    fn analyze_result_payload(
+        &mut self,
+        payload: &Type,
+        ns: &Namespace,
+        deps: &mut HashSet<QualifiedName>,
+    ) -> Option<TypeConversionPolicy> {
+        let annotated = self
+            .convert_boxed_type(Box::new(payload.clone()), ns, false)
+            .ok()?;
+        if !matches!(annotated.ty.as_ref(), Type::Path(_)) {
+            return None;
+        }
+        deps.extend(annotated.types_encountered);
+        Some(self.return_type_conversion_details(&annotated.ty))
+    }
+
     fn convert_return_type(
         &mut self,
         rt: &ReturnType,
@@ -1769,12 +3085,33 @@ impl<'a> FnAnalyzer<'a> {
                 deps: HashSet::new(),
             },
             ReturnType::Type(rarrow, boxed_type) => {
-                // TODO remove the below clone
-                let annotated_type =
-                    self.convert_boxed_type(boxed_type.clone(), ns, references.ref_return)?;
+                let annotated_type = match &references.member_pointer_return {
+                    Some(cpp_definition) => self.convert_member_pointer(cpp_definition),
+                    // TODO remove the below clone
+                    None => {
+                        self.convert_boxed_type(boxed_type.clone(), ns, references.ref_return)?
+                    }
+                };
                 let boxed_type = annotated_type.ty;
                 let was_reference = matches!(boxed_type.as_ref(), Type::Reference(_));
-                let conversion = self.return_type_conversion_details(boxed_type.as_ref());
+                let conversion = match &annotated_type.kind {
+                    // The list would refer to a temporary array which no
+                    // longer exists by the time we return.
+                    type_converter::TypeKind::View(view_name)
+                        if known_types().view_kind(view_name)
+                            == Some(ViewKind::InitializerList) =>
+                    {
+                        return Err(ConvertError::InitializerListReturn)
+                    }
+                    type_converter::TypeKind::Array(_) => return Err(ConvertError::ArrayReturn),
+                    type_converter::TypeKind::View(view_name) => {
+                        TypeConversionPolicy::new_to_rust_view(
+                            boxed_type.as_ref().clone(),
+                            view_name.to_cpp_name(),
+                        )
+                    }
+                    _ => self.return_type_conversion_details(boxed_type.as_ref()),
+                };
                 ReturnTypeAnalysis {
                     rt: ReturnType::Type(*rarrow, boxed_type),
                     conversion: Some(conversion),
@@ -1786,6 +3123,42 @@ impl<'a> FnAnalyzer<'a> {
         Ok(result)
     }
 
+    /// If the user asked with `copy_pod_returns!`, return small POD types
+    /// by value rather than by `const` reference.
+    fn copy_small_pod_return(&self, analysis: ReturnTypeAnalysis) -> ReturnTypeAnalysis {
+        let max_size = match self.config.copy_pod_returns_max_size() {
+            Some(max_size) => max_size,
+            None => return analysis,
+        };
+        let pod_type = match &analysis.rt {
+            ReturnType::Type(_, ty) => match ty.as_ref() {
+                Type::Reference(TypeReference {
+                    mutability: None,
+                    elem,
+                    ..
+                }) => match elem.as_ref() {
+                    Type::Path(typ)
+                        if self
+                            .pod_sizes
+                            .get(&QualifiedName::from_type_path(typ))
+                            .map_or(false, |size| *size <= max_size) =>
+                    {
+                        elem.as_ref().clone()
+                    }
+                    _ => return analysis,
+                },
+                _ => return analysis,
+            },
+            ReturnType::Default => return analysis,
+        };
+        ReturnTypeAnalysis {
+            rt: parse_quote! { -> #pod_type },
+            conversion: Some(TypeConversionPolicy::new_from_reference_to_value(pod_type)),
+            was_reference: false,
+            deps: analysis.deps,
+        }
+    }
+
     /// If a type has explicit constructors, bindgen will generate corresponding
     /// constructor functions, which we'll have already converted to make_unique methods.
     /// C++ mandates the synthesis of certain implicit constructors, to which we
@@ -1800,7 +3173,12 @@ impl<'a> FnAnalyzer<'a> {
     /// for further analysis phases.
     fn add_constructors_present(&mut self, mut apis: ApiVec<FnPrePhase1>) -> ApiVec<FnPrePhase2> {
         let all_items_found = find_constructors_present(&apis);
-        for (self_ty, items_found) in all_items_found.iter() {
+        // Sort these so that the special members we synthesize, and so
+        // the names we give them, don't depend on hash ordering.
+        for (self_ty, items_found) in all_items_found
+            .iter()
+            .sorted_by_key(|(self_ty, _)| *self_ty)
+        {
             if self.config.exclude_impls {
                 // Remember that `find_constructors_present` mutates `apis`, so we always have to
                 // call that, even if we don't do anything with the return value. This is kind of
@@ -1909,8 +3287,8 @@ impl<'a> FnAnalyzer<'a> {
             None => self_ty.name.get_final_ident(),
         };
         let cpp_name = if matches!(special_member, SpecialMemberKind::DefaultConstructor) {
-            // Constructors (other than move or copy) are identified in `analyze_foreign_fn` by
-            // being suffixed with the cpp_name, so we have to produce that.
+            // `analyze_foreign_fn` makes the name of a constructor (other than move or
+            // copy) by replacing the type name with `new`, so we have to produce that.
             self.nested_type_name_map
                 .get(&self_ty.name)
                 .cloned()
@@ -1942,6 +3320,8 @@ impl<'a> FnAnalyzer<'a> {
                         original_name: None,
                         synthesized_this_type: None,
                         is_deleted: false,
+                        calling_convention: None,
+                        link_name: None,
                         add_to_trait: None,
                         synthetic_cpp: None,
                         provenance: Provenance::SynthesizedOther,
@@ -1955,10 +3335,107 @@ impl<'a> FnAnalyzer<'a> {
     }
 }
 
+/// Whether a field of this type is, or contains, a raw pointer, given the
+/// POD types already known to contain them.
+fn type_contains_pointer(ty: &Type, pod_types_with_pointers: &HashSet<QualifiedName>) -> bool {
+    match ty {
+        Type::Ptr(_) => true,
+        Type::Array(arr) => type_contains_pointer(&arr.elem, pod_types_with_pointers),
+        Type::Path(typ) => pod_types_with_pointers.contains(&QualifiedName::from_type_path(typ)),
+        _ => false,
+    }
+}
+
+/// The type of which this function is a method, if any.
+fn self_type_of(fun: &FuncToConvert) -> Option<QualifiedName> {
+    fun.self_ty.clone().or_else(|| {
+        fun.inputs.iter().find_map(|arg| match arg {
+            FnArg::Typed(pt) => match (pt.pat.as_ref(), pt.ty.as_ref()) {
+                (Pat::Ident(pp), Type::Ptr(TypePtr { elem, .. })) if pp.ident == "this" => {
+                    match elem.as_ref() {
+                        Type::Path(typ) => Some(QualifiedName::from_type_path(typ)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+    })
+}
+
+/// A constructor name suffix such as `_with_width_and_height` for the
+/// given parameters, or `None` if there are none.
+fn suffix_from_param_names<'a>(params: impl Iterator<Item = &'a Pat>) -> Option<String> {
+    let param_names = params
+        .map(|pat| match pat {
+            Pat::Ident(pp) => Some(
+                to_snake_case(&pp.ident.to_string())
+                    .trim_end_matches('_')
+                    .to_string(),
+            ),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if param_names.is_empty() {
+        None
+    } else {
+        Some(format!("_with_{}", param_names.join("_and_")))
+    }
+}
+
+/// The symbol by which a function can be found in a shared library using
+/// `dlsym`. Only the Itanium C++ ABI is supported, in which a non-virtual
+/// method can be called as a function taking `this` as its first parameter.
+fn dynamic_symbol(fun: &FuncToConvert, kind: &FnKind, cpp_name: &str) -> Option<String> {
+    match &fun.link_name {
+        // Mach-O symbols have an extra leading underscore, which dlsym
+        // adds for us.
+        Some(link_name) if link_name.starts_with("__Z") => Some(link_name[1..].to_string()),
+        Some(link_name) if link_name.starts_with("_Z") => Some(link_name.clone()),
+        Some(_) => None,
+        // An extern "C" function.
+        None if matches!(kind, FnKind::Function) => Some(cpp_name.to_string()),
+        None => None,
+    }
+}
+
 fn error_context_for_method(self_ty: &QualifiedName, rust_name: &str) -> ErrorContext {
     ErrorContext::new_for_method(self_ty.get_final_ident(), make_ident(rust_name))
 }
 
+/// If this is a copy or move assignment operator for `self_ty`, returns the
+/// name of the Rust method we should generate for it.
+fn assignment_operator_name(
+    fun: &FuncToConvert,
+    self_ty: &QualifiedName,
+    param_details: &[ArgumentAnalysis],
+) -> Option<&'static str> {
+    if !matches!(
+        fun.special_member,
+        Some(SpecialMemberKind::AssignmentOperator)
+    ) || param_details.len() != 2
+    {
+        return None;
+    }
+    let is_move = !fun.references.rvalue_ref_params.is_empty();
+    let other_ty = match &param_details[1].conversion.unwrapped_type {
+        Type::Reference(TypeReference {
+            elem,
+            mutability: None,
+            ..
+        }) if !is_move => elem,
+        Type::Ptr(TypePtr { elem, .. }) if is_move => elem,
+        _ => return None,
+    };
+    match other_ty.as_ref() {
+        Type::Path(typ) if QualifiedName::from_type_path(typ) == *self_ty => {
+            Some(if is_move { "assign_move" } else { "assign" })
+        }
+        _ => None,
+    }
+}
+
 impl Api<FnPhase> {
     pub(crate) fn name_for_allowlist(&self) -> QualifiedName {
         match &self {
@@ -1970,6 +3447,10 @@ impl Api<FnPhase> {
                 }
             },
             Api::RustSubclassFn { subclass, .. } => subclass.0.name.clone(),
+            Api::Const {
+                impl_for: Some(impl_for),
+                ..
+            } => impl_for.clone(),
             Api::IgnoredItem {
                 name,
                 ctx: Some(ctx),
@@ -0,0 +1,104 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::types::rust_name_for_cpp_ident;
+
+/// Convert a C++ `camelCase` or `PascalCase` name into Rust `snake_case`.
+/// Runs of capitals are treated as a single word, so `getHTTPResponse`
+/// becomes `get_http_response`. Leading and trailing underscores are
+/// retained. If the result would be a Rust keyword, an underscore is
+/// appended, just as bindgen does.
+pub(crate) fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut output = String::with_capacity(name.len() + 4);
+    for (idx, ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() && idx > 0 {
+            let prev = chars[idx - 1];
+            let next_is_lower = chars.get(idx + 1).map_or(false, |n| n.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                output.push('_');
+            }
+        }
+        output.extend(ch.to_lowercase());
+    }
+    rust_name_for_cpp_ident(&output)
+}
+
+/// Whether a method looks like a getter or a setter.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum AccessorKind {
+    Getter,
+    Setter,
+}
+
+/// If this C++ method name looks like a getter or setter (e.g. `getFoo`,
+/// `SetFoo` or `get_foo`), returns which it is, the C++ name of the method
+/// which would form the other half of the pair, and the `snake_case` name
+/// of the property.
+pub(crate) fn split_accessor_name(name: &str) -> Option<(AccessorKind, String, String)> {
+    let (kind, partner_prefix) = match name.get(..3)? {
+        "get" => (AccessorKind::Getter, "set"),
+        "Get" => (AccessorKind::Getter, "Set"),
+        "set" => (AccessorKind::Setter, "get"),
+        "Set" => (AccessorKind::Setter, "Get"),
+        _ => return None,
+    };
+    let rest = &name[3..];
+    // The property must start a new word: `settle` isn't a setter.
+    let property = match rest.strip_prefix('_') {
+        Some(property) => property,
+        None if rest.starts_with(|c: char| c.is_uppercase()) => rest,
+        None => return None,
+    };
+    if property.is_empty() {
+        return None;
+    }
+    Some((
+        kind,
+        format!("{}{}", partner_prefix, rest),
+        to_snake_case(property),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_accessor_name, to_snake_case, AccessorKind};
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(to_snake_case("getValue"), "get_value");
+        assert_eq!(to_snake_case("GetValue"), "get_value");
+        assert_eq!(to_snake_case("get_value"), "get_value");
+        assert_eq!(to_snake_case("getHTTPResponse"), "get_http_response");
+        assert_eq!(to_snake_case("toUTF8"), "to_utf8");
+        assert_eq!(to_snake_case("move_"), "move_");
+        assert_eq!(to_snake_case("Type"), "type_");
+    }
+
+    #[test]
+    fn test_split_accessor_name() {
+        assert_eq!(
+            split_accessor_name("getFooBar"),
+            Some((AccessorKind::Getter, "setFooBar".into(), "foo_bar".into()))
+        );
+        assert_eq!(
+            split_accessor_name("Set_foo"),
+            Some((AccessorKind::Setter, "Get_foo".into(), "foo".into()))
+        );
+        assert_eq!(
+            split_accessor_name("getType"),
+            Some((AccessorKind::Getter, "setType".into(), "type_".into()))
+        );
+        assert_eq!(split_accessor_name("settle"), None);
+        assert_eq!(split_accessor_name("get"), None);
+        assert_eq!(split_accessor_name("count"), None);
+    }
+}
@@ -20,7 +20,7 @@ use crate::conversion::apivec::ApiVec;
 use crate::{
     conversion::{
         analysis::fun::function_wrapper::{
-            CppFunction, CppFunctionBody, CppFunctionKind, TypeConversionPolicy,
+            CallingConvention, CppFunction, CppFunctionBody, CppFunctionKind, TypeConversionPolicy,
         },
         api::{Api, ApiName},
     },
@@ -67,6 +67,9 @@ pub(super) fn create_subclass_fn_wrapper(
         references: fun.references.clone(),
         add_to_trait: fun.add_to_trait.clone(),
         is_deleted: fun.is_deleted,
+        calling_convention: None,
+        last_error: None,
+        link_name: None,
         synthetic_cpp: None,
         provenance: Provenance::SynthesizedOther,
     })
@@ -78,6 +81,7 @@ pub(super) fn create_subclass_trait_item(
     receiver_mutability: &ReceiverMutability,
     receiver: QualifiedName,
     is_pure_virtual: bool,
+    is_virtual: bool,
 ) -> Api<FnPrePhase1> {
     let param_names = analysis
         .param_details
@@ -94,6 +98,7 @@ pub(super) fn create_subclass_trait_item(
             receiver_mutability: receiver_mutability.clone(),
             requires_unsafe: UnsafetyNeeded::from_param_details(&analysis.param_details, false),
             is_pure_virtual,
+            is_virtual,
             receiver,
         },
     }
@@ -106,6 +111,8 @@ pub(super) fn create_subclass_function(
     receiver_mutability: &ReceiverMutability,
     superclass: &QualifiedName,
     dependencies: Vec<QualifiedName>,
+    calling_convention: Option<CallingConvention>,
+    is_virtual: bool,
 ) -> Api<FnPrePhase1> {
     let cpp = sub.cpp();
     let holder_name = sub.holder();
@@ -146,6 +153,7 @@ pub(super) fn create_subclass_function(
                 kind,
                 pass_obs_field: true,
                 qualification: Some(cpp),
+                calling_convention,
             },
             superclass: superclass.clone(),
             receiver_mutability: receiver_mutability.clone(),
@@ -158,6 +166,7 @@ pub(super) fn create_subclass_function(
                     ..
                 }
             ),
+            is_virtual,
         }),
     }
 }
@@ -190,6 +199,8 @@ pub(super) fn create_subclass_constructor(
         pass_obs_field: false,
         qualification: Some(cpp.clone()),
         original_cpp_name: cpp.to_cpp_name(),
+        calling_convention: None,
+        last_error: None,
     };
     let subclass_constructor_details = Box::new(SubclassConstructorDetails {
         subclass: sub.clone(),
@@ -235,6 +246,9 @@ pub(super) fn create_subclass_constructor(
         self_ty: Some(cpp),
         add_to_trait: None,
         is_deleted: fun.is_deleted,
+        calling_convention: None,
+        last_error: None,
+        link_name: None,
         synthetic_cpp: None,
         provenance: Provenance::SynthesizedSubclassConstructor(subclass_constructor_details),
     });
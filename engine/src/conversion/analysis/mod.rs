@@ -13,6 +13,7 @@ pub(crate) mod constructor_deps;
 pub(crate) mod ctypes;
 pub(crate) mod deps;
 mod depth_first;
+pub(crate) mod field_accessors;
 pub(crate) mod fun;
 pub(crate) mod gc;
 mod name_check;
@@ -21,4 +22,4 @@ pub(crate) mod remove_ignored;
 pub(crate) mod tdef;
 mod type_converter;
 
-pub(crate) use name_check::check_names;
+pub(crate) use name_check::{check_names, check_prefix_stripped_names, resolve_fn_name_collisions};
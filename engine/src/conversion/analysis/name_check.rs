@@ -6,8 +6,9 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use autocxx_parser::IncludeCppConfig;
 use syn::Ident;
 
 use crate::{
@@ -17,10 +18,10 @@ use crate::{
         error_reporter::convert_item_apis,
         ConvertError,
     },
-    types::{validate_ident_ok_for_cxx, QualifiedName},
+    types::{make_ident, validate_ident_ok_for_cxx, Namespace, QualifiedName},
 };
 
-use super::fun::FnPhase;
+use super::fun::{FnAnalysis, FnKind, FnPhase, MethodKind, RustRenameStrategy};
 
 /// Do some final checks that the names we've come up with can be represented
 /// within cxx.
@@ -34,7 +35,9 @@ pub(crate) fn check_names(apis: ApiVec<FnPhase>) -> ApiVec<FnPhase> {
     let mut intermediate = ApiVec::new();
     convert_item_apis(apis, &mut intermediate, |api| match api {
         Api::Typedef { ref name, .. }
+        | Api::FnPtrTypedef { ref name, .. }
         | Api::ForwardDeclaration { ref name, .. }
+        | Api::ExternCppType { ref name, .. }
         | Api::Const { ref name, .. }
         | Api::Enum { ref name, .. }
         | Api::Struct { ref name, .. } => {
@@ -100,6 +103,183 @@ pub(crate) fn check_names(apis: ApiVec<FnPhase>) -> ApiVec<FnPhase> {
     results
 }
 
+/// Where the Rust name of a function must be unique.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum NameScope {
+    /// The methods of a type.
+    Type(QualifiedName),
+    /// The free functions within a namespace.
+    Namespace(Namespace),
+}
+
+fn name_scope(api: &Api<FnPhase>) -> Option<NameScope> {
+    match api {
+        Api::Function { analysis, .. }
+            if analysis.ignore_reason.is_ok() && analysis.externally_callable =>
+        {
+            match &analysis.kind {
+                FnKind::Method { impl_for, .. } => Some(NameScope::Type(impl_for.clone())),
+                FnKind::Function => Some(NameScope::Namespace(api.name().get_namespace().clone())),
+                FnKind::TraitMethod { .. } => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Rename any functions or methods whose Rust names would otherwise be the
+/// same as that of another function in the same namespace, or another
+/// method of the same type. This can happen when the suffix which we add
+/// to overloads produces the name of some other function: for instance
+/// if a type has a method `get1()` as well as `get(int)` and `get(double)`.
+/// The first such function to be declared keeps its name (preferring
+/// virtual methods, since subclasses refer to those by name) and the
+/// others get the next free numeric suffix, here `get2`. Each renamed
+/// function records its original name, which ends up in its documentation
+/// and in the [`crate::GeneratedItem`] describing it.
+pub(crate) fn resolve_fn_name_collisions(apis: ApiVec<FnPhase>) -> ApiVec<FnPhase> {
+    let mut apis: Vec<_> = apis.into_iter().collect();
+    let mut claimants: HashMap<(NameScope, String), Vec<usize>> = HashMap::new();
+    for (idx, api) in apis.iter().enumerate() {
+        if let (Some(scope), Api::Function { analysis, .. }) = (name_scope(api), api) {
+            claimants
+                .entry((scope, analysis.rust_name.clone()))
+                .or_default()
+                .push(idx);
+        }
+    }
+    let mut taken: HashSet<(NameScope, String)> = claimants.keys().cloned().collect();
+    let mut collisions: Vec<_> = claimants
+        .into_iter()
+        .filter(|(_, idxs)| idxs.len() > 1)
+        .collect();
+    collisions.sort_by_key(|(_, idxs)| idxs[0]);
+    for ((scope, name), mut idxs) in collisions {
+        idxs.sort_by_key(|idx| !is_virtual(&apis[*idx]));
+        let stem = name.trim_end_matches(|c: char| c.is_ascii_digit());
+        for idx in idxs.into_iter().skip(1) {
+            let new_name = (1..)
+                .map(|n| format!("{}{}", stem, n))
+                .find(|candidate| !taken.contains(&(scope.clone(), candidate.clone())))
+                .unwrap();
+            taken.insert((scope.clone(), new_name.clone()));
+            if let Api::Function { analysis, .. } = &mut apis[idx] {
+                log::info!(
+                    "Renaming {} to {} to avoid a clash with another function",
+                    name,
+                    new_name
+                );
+                rename_fn(analysis, new_name);
+            }
+        }
+    }
+    let mut results = ApiVec::new();
+    results.extend(apis.into_iter());
+    results
+}
+
+fn is_virtual(api: &Api<FnPhase>) -> bool {
+    matches!(
+        api,
+        Api::Function {
+            analysis: FnAnalysis {
+                kind: FnKind::Method {
+                    method_kind: MethodKind::Virtual(_) | MethodKind::PureVirtual(_),
+                    ..
+                },
+                ..
+            },
+            ..
+        }
+    )
+}
+
+/// Give a function a new Rust name, adjusting how we expose it to match.
+fn rename_fn(analysis: &mut FnAnalysis, new_name: String) {
+    analysis.renamed_from = Some(std::mem::replace(&mut analysis.rust_name, new_name));
+    let differs_from_cxxbridge_name = analysis.cxxbridge_name != analysis.rust_name;
+    if matches!(analysis.kind, FnKind::Method { .. }) {
+        // A method can only be renamed by a Rust wrapper.
+        analysis.rust_wrapper_needed |= differs_from_cxxbridge_name;
+    }
+    analysis.rust_rename_strategy = if analysis.rust_wrapper_needed {
+        RustRenameStrategy::RenameUsingWrapperFunction
+    } else if differs_from_cxxbridge_name {
+        RustRenameStrategy::RenameInOutputMod(make_ident(&analysis.rust_name))
+    } else {
+        RustRenameStrategy::None
+    };
+}
+
+/// The name under which this API will be exposed in its namespace
+/// within the output mod, before any `strip_prefix!` directives are applied.
+/// Returns `None` for items which aren't exposed directly, such as methods.
+fn exposed_name(api: &Api<FnPhase>) -> Option<String> {
+    match api {
+        Api::Typedef { name, .. }
+        | Api::FnPtrTypedef { name, .. }
+        | Api::ForwardDeclaration { name, .. }
+        | Api::ExternCppType { name, .. }
+        | Api::ConcreteType { name, .. }
+        | Api::Const {
+            name,
+            impl_for: None,
+            ..
+        }
+        | Api::Enum { name, .. }
+        | Api::Struct { name, .. } => Some(name.name.get_final_item().to_string()),
+        Api::Function { analysis, .. }
+            if matches!(analysis.kind, FnKind::Function)
+                && analysis.ignore_reason.is_ok()
+                && analysis.externally_callable =>
+        {
+            Some(analysis.rust_name.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Check that any names changed by `strip_prefix!` directives don't collide
+/// with each other, or with other names in the same namespace. Any item
+/// whose stripped name collides is rejected.
+pub(crate) fn check_prefix_stripped_names(
+    apis: ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> ApiVec<FnPhase> {
+    let mut names_found: HashMap<QualifiedName, Vec<QualifiedName>> = HashMap::new();
+    for api in apis.iter() {
+        if let Some(exposed) = exposed_name(api) {
+            let final_name = config.strip_prefix(&exposed).unwrap_or(&exposed);
+            let final_name = QualifiedName::new(api.name().get_namespace(), make_ident(final_name));
+            names_found
+                .entry(final_name)
+                .or_default()
+                .push(api.name().clone());
+        }
+    }
+    let mut results = ApiVec::new();
+    convert_item_apis(apis, &mut results, |api| {
+        if let Some(exposed) = exposed_name(&api) {
+            if let Some(stripped) = config.strip_prefix(&exposed) {
+                let final_name =
+                    QualifiedName::new(api.name().get_namespace(), make_ident(stripped));
+                if let Some(other) = names_found
+                    .get(&final_name)
+                    .and_then(|names| names.iter().find(|n| *n != api.name()))
+                {
+                    return Err(ConvertError::PrefixStrippingCollision(
+                        api.name().clone(),
+                        other.clone(),
+                        stripped.to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(Box::new(std::iter::once(api)))
+    });
+    results
+}
+
 fn validate_all_segments_ok_for_cxx(
     items: impl Iterator<Item = String>,
 ) -> Result<(), ConvertError> {
@@ -11,12 +11,12 @@ use crate::{conversion::ConvertError, known_types::known_types};
 use crate::{
     conversion::{
         analysis::tdef::TypedefPhase,
-        api::{Api, TypedefKind},
+        api::{Api, SpecialMemberKind, TypedefKind},
     },
     types::{Namespace, QualifiedName},
 };
 use autocxx_parser::IncludeCppConfig;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use syn::{ItemStruct, Type};
 
 #[derive(Clone)]
@@ -27,10 +27,17 @@ enum PodState {
     IsAlias(QualifiedName),
 }
 
+/// A field of a struct, whose type must be POD if the struct is to be.
+#[derive(Clone)]
+struct FieldDependency {
+    field: String,
+    ty: QualifiedName,
+}
+
 #[derive(Clone)]
 struct StructDetails {
     state: PodState,
-    dependent_structs: Vec<QualifiedName>,
+    dependent_structs: Vec<FieldDependency>,
 }
 
 impl StructDetails {
@@ -51,6 +58,9 @@ impl StructDetails {
 pub struct ByValueChecker {
     // Mapping from type name to whether it is safe to be POD
     results: HashMap<QualifiedName, StructDetails>,
+    // Types which we made POD because of `auto_pod!` rather than because
+    // they (or a type containing them) were named in `generate_pod!`.
+    automatic: HashSet<QualifiedName>,
 }
 
 impl ByValueChecker {
@@ -64,7 +74,10 @@ impl ByValueChecker {
             };
             results.insert(tn.clone(), StructDetails::new(safety));
         }
-        ByValueChecker { results }
+        ByValueChecker {
+            results,
+            automatic: HashSet::new(),
+        }
     }
 
     /// Scan APIs to work out which are by-value safe. Constructs a [ByValueChecker]
@@ -110,7 +123,7 @@ impl ByValueChecker {
                 Api::Struct { details, .. } => {
                     byvalue_checker.ingest_struct(&details.item, api.name().get_namespace())
                 }
-                Api::Enum { .. } => {
+                Api::Enum { .. } | Api::FnPtrTypedef { .. } => {
                     byvalue_checker
                         .results
                         .insert(api.name().clone(), StructDetails::new(PodState::IsPod));
@@ -126,26 +139,151 @@ impl ByValueChecker {
         byvalue_checker
             .satisfy_requests(pod_requests)
             .map_err(ConvertError::UnsafePodType)?;
+        if config.auto_pod() {
+            byvalue_checker.satisfy_automatic_requests(apis, config);
+        }
         Ok(byvalue_checker)
     }
 
+    /// Make POD every struct which can safely be, other than those named
+    /// in `non_pod!`. Field types alone don't tell us whether a type is
+    /// trivially copyable, so we also rule out any type which declares a
+    /// destructor, copy or move constructor, or assignment operator.
+    fn satisfy_automatic_requests(
+        &mut self,
+        apis: &ApiVec<TypedefPhase>,
+        config: &IncludeCppConfig,
+    ) {
+        let mut exclusions = Vec::new();
+        let mut candidates = Vec::new();
+        for api in apis.iter() {
+            match api {
+                Api::Function { fun, .. } => {
+                    if let (
+                        Some(self_ty),
+                        Some(
+                            SpecialMemberKind::Destructor
+                            | SpecialMemberKind::CopyConstructor
+                            | SpecialMemberKind::MoveConstructor
+                            | SpecialMemberKind::AssignmentOperator,
+                        ),
+                    ) = (&fun.self_ty, &fun.special_member)
+                    {
+                        exclusions.push((self_ty.clone(), format!("Type {} could not be POD because it has a user-declared destructor, copy or move constructor, or assignment operator", self_ty)));
+                    }
+                }
+                Api::Struct { name, details, .. } => {
+                    if details.has_rvalue_reference_fields {
+                        exclusions.push((
+                            name.name.clone(),
+                            format!(
+                                "Type {} could not be POD because it has rvalue reference fields",
+                                name.name
+                            ),
+                        ));
+                    } else if details.item.generics.params.is_empty() {
+                        candidates.push(name.name.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        for non_pod in candidates
+            .iter()
+            .filter(|ty| config.is_non_pod(&ty.to_cpp_name()))
+        {
+            exclusions.push((
+                non_pod.clone(),
+                format!(
+                    "Type {} could not be POD because it's named in non_pod!",
+                    non_pod
+                ),
+            ));
+        }
+        // Rule these out before considering any candidates, so that they're
+        // not made POD as a field of some other type either.
+        for (ty_id, reason) in exclusions {
+            if let Some(deets) = self.results.get_mut(&ty_id) {
+                if matches!(deets.state, PodState::SafeToBePod) {
+                    deets.state = PodState::UnsafeToBePod(reason);
+                }
+            }
+        }
+        candidates.sort();
+        for ty_id in candidates {
+            if self.is_pod(&ty_id) {
+                continue;
+            }
+            match self.check_pod_safety(&ty_id, &mut HashSet::new()) {
+                Ok(()) => {
+                    log::info!("Making {} POD automatically", ty_id);
+                    let before: HashSet<_> = self.pod_types().cloned().collect();
+                    self.satisfy_requests(vec![ty_id])
+                        .expect("type was checked to be safe to be POD");
+                    let newly_pod: Vec<_> = self
+                        .pod_types()
+                        .filter(|ty| !before.contains(ty))
+                        .cloned()
+                        .collect();
+                    self.automatic.extend(newly_pod);
+                }
+                Err(reason) => log::info!("Not making {} POD automatically: {}", ty_id, reason),
+            }
+        }
+    }
+
+    /// Works out whether `satisfy_requests` would succeed for this type,
+    /// without making any changes.
+    fn check_pod_safety(
+        &self,
+        ty_id: &QualifiedName,
+        visited: &mut HashSet<QualifiedName>,
+    ) -> Result<(), String> {
+        if !visited.insert(ty_id.clone()) {
+            return Ok(());
+        }
+        match self.results.get(ty_id) {
+            None => Err(format!(
+                "Unable to make {} POD because we never saw a struct definition",
+                ty_id
+            )),
+            Some(deets) => match &deets.state {
+                PodState::UnsafeToBePod(reason) => Err(reason.clone()),
+                PodState::IsPod => Ok(()),
+                PodState::SafeToBePod => deets.dependent_structs.iter().try_for_each(|dep| {
+                    self.check_pod_safety(&dep.ty, visited).map_err(|reason| {
+                        Self::explain_chain(&[Self::describe_field(ty_id, dep)], reason)
+                    })
+                }),
+                PodState::IsAlias(target) => self.check_pod_safety(target, visited),
+            },
+        }
+    }
+
+    fn pod_types(&self) -> impl Iterator<Item = &QualifiedName> {
+        self.results
+            .iter()
+            .filter(|(_, deets)| matches!(deets.state, PodState::IsPod))
+            .map(|(ty_id, _)| ty_id)
+    }
+
     fn ingest_struct(&mut self, def: &ItemStruct, ns: &Namespace) {
         // For this struct, work out whether it _could_ be safe as a POD.
         let tyname = QualifiedName::new(ns, def.ident.clone());
         let mut field_safety_problem = PodState::SafeToBePod;
         let fieldlist = Self::get_field_types(def);
-        for ty_id in &fieldlist {
-            match self.results.get(ty_id) {
+        for dep in &fieldlist {
+            match self.results.get(&dep.ty) {
                 None => {
                     field_safety_problem = PodState::UnsafeToBePod(format!(
-                        "Type {} could not be POD because its dependent type {} isn't known",
-                        tyname, ty_id
+                        "Type {} could not be POD because its field `{}` has type {}, which isn't known",
+                        tyname, dep.field, dep.ty
                     ));
                     break;
                 }
                 Some(deets) => {
                     if let PodState::UnsafeToBePod(reason) = &deets.state {
-                        let new_reason = format!("Type {} could not be POD because its dependent type {} isn't safe to be POD. Because: {}", tyname, ty_id, reason);
+                        let new_reason = format!("Type {} could not be POD because its field `{}` has type {}, which isn't safe to be POD. Because: {}", tyname, dep.field, dep.ty, reason);
                         field_safety_problem = PodState::UnsafeToBePod(new_reason);
                         break;
                     }
@@ -172,24 +310,41 @@ impl ByValueChecker {
         );
     }
 
-    fn satisfy_requests(&mut self, mut requests: Vec<QualifiedName>) -> Result<(), String> {
-        while !requests.is_empty() {
-            let ty_id = requests.remove(requests.len() - 1);
+    /// Make the requested types POD, along with the types of all their
+    /// fields. If that's not possible, the error explains which field, of
+    /// which nested type, is to blame.
+    fn satisfy_requests(&mut self, requests: Vec<QualifiedName>) -> Result<(), String> {
+        // Each request is accompanied by the chain of fields by which we
+        // reached it from one of the original requests.
+        let mut requests: Vec<(QualifiedName, Vec<String>)> = requests
+            .into_iter()
+            .map(|ty_id| (ty_id, Vec::new()))
+            .collect();
+        while let Some((ty_id, chain)) = requests.pop() {
             let deets = self.results.get_mut(&ty_id);
             let mut alias_to_consider = None;
             match deets {
                 None => {
-                    return Err(format!(
-                        "Unable to make {} POD because we never saw a struct definition",
-                        ty_id
+                    return Err(Self::explain_chain(
+                        &chain,
+                        format!(
+                            "Unable to make {} POD because we never saw a struct definition",
+                            ty_id
+                        ),
                     ))
                 }
                 Some(deets) => match &deets.state {
-                    PodState::UnsafeToBePod(error_msg) => return Err(error_msg.clone()),
+                    PodState::UnsafeToBePod(error_msg) => {
+                        return Err(Self::explain_chain(&chain, error_msg.clone()))
+                    }
                     PodState::IsPod => {}
                     PodState::SafeToBePod => {
                         deets.state = PodState::IsPod;
-                        requests.extend_from_slice(&deets.dependent_structs);
+                        requests.extend(deets.dependent_structs.iter().map(|dep| {
+                            let mut chain = chain.clone();
+                            chain.push(Self::describe_field(&ty_id, dep));
+                            (dep.ty.clone(), chain)
+                        }));
                     }
                     PodState::IsAlias(target_type) => {
                         alias_to_consider = Some(target_type.clone());
@@ -199,7 +354,12 @@ impl ByValueChecker {
             // Do the following outside the match to avoid borrow checker violation.
             if let Some(alias) = alias_to_consider {
                 match self.results.get(&alias) {
-                    None => requests.extend_from_slice(&[alias, ty_id]), // try again after resolving alias target
+                    None => {
+                        // try again after resolving alias target
+                        let mut alias_chain = chain.clone();
+                        alias_chain.push(format!("{} is an alias of {}", ty_id, alias));
+                        requests.extend([(ty_id, chain), (alias, alias_chain)])
+                    }
                     Some(alias_target_deets) => {
                         self.results.get_mut(&ty_id).unwrap().state =
                             alias_target_deets.state.clone();
@@ -210,6 +370,20 @@ impl ByValueChecker {
         Ok(())
     }
 
+    /// Prefix an explanation of why a type can't be POD with the chain of
+    /// fields by which we reached that type.
+    fn explain_chain(chain: &[String], reason: String) -> String {
+        if chain.is_empty() {
+            reason
+        } else {
+            format!("{}; {}", chain.join("; "), reason)
+        }
+    }
+
+    fn describe_field(owner: &QualifiedName, dep: &FieldDependency) -> String {
+        format!("{} has field `{}` of type {}", owner, dep.field, dep.ty)
+    }
+
     /// Return whether a given type is POD (i.e. can be represented by value in Rust) or not.
     /// Unless we've got a definite record that it _is_, we return false.
     /// Some types won't be in our `results` map. For example: (a) AutocxxConcrete types
@@ -225,12 +399,24 @@ impl ByValueChecker {
         )
     }
 
-    fn get_field_types(def: &ItemStruct) -> Vec<QualifiedName> {
+    /// Return whether a type is POD only because of `auto_pod!`.
+    pub fn is_automatically_pod(&self, ty_id: &QualifiedName) -> bool {
+        self.automatic.contains(ty_id)
+    }
+
+    fn get_field_types(def: &ItemStruct) -> Vec<FieldDependency> {
         let mut results = Vec::new();
-        for f in &def.fields {
+        for (i, f) in def.fields.iter().enumerate() {
             let fty = &f.ty;
             if let Type::Path(p) = fty {
-                results.push(QualifiedName::from_type_path(p));
+                results.push(FieldDependency {
+                    field: f
+                        .ident
+                        .as_ref()
+                        .map(|id| id.to_string())
+                        .unwrap_or_else(|| i.to_string()),
+                    ty: QualifiedName::from_type_path(p),
+                });
             }
             // TODO handle anything else which bindgen might spit out, e.g. arrays?
         }
@@ -329,4 +515,27 @@ mod tests {
         bvc.ingest_struct(&t, &Namespace::new());
         assert!(bvc.satisfy_requests(vec![t_id]).is_err());
     }
+
+    #[test]
+    fn test_nested_cxxstring_explained() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemStruct = parse_quote! {
+            struct Foo {
+                a: i32,
+                name: CxxString,
+            }
+        };
+        bvc.ingest_struct(&t, &Namespace::new());
+        let t: ItemStruct = parse_quote! {
+            struct Bar {
+                foo: Foo,
+                b: i64,
+            }
+        };
+        let t_id = ty_from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        let err = bvc.satisfy_requests(vec![t_id]).unwrap_err();
+        assert!(err.contains("field `foo` has type Foo"));
+        assert!(err.contains("field `name` has type CxxString"));
+    }
 }
@@ -12,7 +12,7 @@ use std::collections::{HashMap, HashSet};
 
 use autocxx_parser::IncludeCppConfig;
 use byvalue_checker::ByValueChecker;
-use syn::{ItemEnum, ItemStruct, Type, Visibility};
+use syn::{parse_quote, ItemEnum, ItemStruct, Type, Visibility};
 
 use crate::{
     conversion::{
@@ -143,15 +143,27 @@ fn analyze_struct(
     let bases = get_bases(&details.item);
     let mut field_deps = HashSet::new();
     let mut field_info = Vec::new();
+    let opaque_pointers = config.opaque_pod_pointers() && byvalue_checker.is_pod(&name.name);
     let field_conversion_errors = get_struct_field_types(
         type_converter,
         name.name.get_namespace(),
-        &details.item,
+        &mut details.item,
+        opaque_pointers,
         &mut field_deps,
         &mut field_info,
         extra_apis,
     );
-    let type_kind = if byvalue_checker.is_pod(&name.name) {
+    let type_kind = if byvalue_checker.is_automatically_pod(&name.name)
+        && !field_conversion_errors.is_empty()
+    {
+        // Nobody asked for this to be POD, so rather than rejecting the
+        // type, fall back to treating it as opaque.
+        log::info!(
+            "Not making {} POD automatically because its fields can't all be represented in Rust",
+            name.name
+        );
+        TypeKind::NonPod
+    } else if byvalue_checker.is_pod(&name.name) {
         // It's POD so any errors encountered parsing its fields are important.
         // Let's not allow anything to be POD if it's got rvalue reference fields.
         if details.has_rvalue_reference_fields {
@@ -174,7 +186,10 @@ fn analyze_struct(
         .iter()
         .filter(|(_, is_public)| **is_public)
         .map(|(base, _)| base)
-        .filter(|base| config.is_on_allowlist(&base.to_cpp_name()))
+        .filter(|base| {
+            config.is_on_allowlist(&base.to_cpp_name())
+                && !config.is_on_blocklist(&base.to_cpp_name())
+        })
         .cloned()
         .collect();
     let is_generic = !details.item.generics.params.is_empty();
@@ -195,16 +210,28 @@ fn analyze_struct(
 fn get_struct_field_types(
     type_converter: &mut TypeConverter,
     ns: &Namespace,
-    s: &ItemStruct,
+    s: &mut ItemStruct,
+    opaque_pointers: bool,
     field_deps: &mut HashSet<QualifiedName>,
     field_info: &mut Vec<FieldInfo>,
     extra_apis: &mut ApiVec<NullPhase>,
 ) -> Vec<ConvertError> {
     let mut convert_errors = Vec::new();
-    for f in &s.fields {
+    for f in s.fields.iter_mut() {
         let annotated =
             type_converter.convert_type(f.ty.clone(), ns, &TypeConversionContext::CxxInnerType);
         match annotated {
+            Err(_) if opaque_pointers && matches!(f.ty, Type::Ptr(_)) => {
+                // We can't represent the pointee, but a void pointer has the
+                // same layout, so the type can still be POD.
+                if let Type::Ptr(ptr) = &mut f.ty {
+                    *ptr.elem = parse_quote! { ::std::os::raw::c_void };
+                }
+                field_info.push(FieldInfo {
+                    ty: f.ty.clone(),
+                    type_kind: type_converter::TypeKind::Pointer,
+                });
+            }
             Ok(mut r) => {
                 extra_apis.append(&mut r.extra_apis);
                 // Skip base classes represented as fields. Anything which wants to include bases can chain
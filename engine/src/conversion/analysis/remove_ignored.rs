@@ -8,6 +8,8 @@
 
 use std::collections::HashSet;
 
+use autocxx_parser::IncludeCppConfig;
+
 use super::deps::HasDependencies;
 use super::fun::{FnAnalysis, FnKind, FnPhase};
 use crate::conversion::apivec::ApiVec;
@@ -16,9 +18,13 @@ use crate::{conversion::api::Api, known_types};
 
 /// Remove any APIs which depend on other items which have been ignored.
 /// We also eliminate any APIs that depend on some type that we just don't
-/// know about at all. In either case, we don't simply remove the type, but instead
-/// replace it with an error marker.
-pub(crate) fn filter_apis_by_ignored_dependents(mut apis: ApiVec<FnPhase>) -> ApiVec<FnPhase> {
+/// know about at all, which includes types on the blocklist. In either case,
+/// we don't simply remove the type, but instead replace it with an error
+/// marker.
+pub(crate) fn filter_apis_by_ignored_dependents(
+    mut apis: ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> ApiVec<FnPhase> {
     let (ignored_items, valid_items): (Vec<&Api<_>>, Vec<&Api<_>>) = apis
         .iter()
         .partition(|api| matches!(api, Api::IgnoredItem { .. }));
@@ -46,12 +52,22 @@ pub(crate) fn filter_apis_by_ignored_dependents(mut apis: ApiVec<FnPhase>) -> Ap
                     ignored_items.insert(api.name().clone());
                     create_ignore_item(api, ConvertError::IgnoredDependent(ignored_dependents))
                 } else {
-                    let mut missing_deps = api.deps().filter(|dep| {
-                        !valid_types.contains(dep) && !known_types().is_known_type(dep)
-                    });
-                    let first = missing_deps.next();
-                    std::mem::drop(missing_deps);
-                    if let Some(missing_dep) = first.cloned() {
+                    let missing_deps: Vec<_> = api
+                        .deps()
+                        .filter(|dep| {
+                            !valid_types.contains(dep) && !known_types().is_known_type(dep)
+                        })
+                        .cloned()
+                        .collect();
+                    // If a type is missing because it was blocked, say so,
+                    // since that's something the user can do something
+                    // about.
+                    let blocked_dep = missing_deps
+                        .iter()
+                        .find(|dep| config.is_on_blocklist(&dep.to_cpp_name()));
+                    if let Some(blocked_dep) = blocked_dep {
+                        create_ignore_item(api, ConvertError::Blocked(blocked_dep.clone()))
+                    } else if let Some(missing_dep) = missing_deps.into_iter().next() {
                         create_ignore_item(api, ConvertError::UnknownDependentType(missing_dep))
                     } else {
                         api
@@ -6,21 +6,23 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use autocxx_parser::IncludeCppConfig;
-use syn::ItemType;
+use syn::{GenericArgument, Ident, ItemType, PathArguments, ReturnType, Type, TypeBareFn};
 
 use crate::{
     conversion::{
         analysis::type_converter::{add_analysis, Annotated, TypeConversionContext, TypeConverter},
         api::{AnalysisPhase, Api, ApiName, NullPhase, TypedefKind},
         apivec::ApiVec,
+        codegen_cpp::type_to_cpp::type_to_cpp,
         convert_error::{ConvertErrorWithContext, ErrorContext},
         error_reporter::convert_apis,
         parse::BindgenSemanticAttributes,
         ConvertError,
     },
+    known_types::known_types,
     types::QualifiedName,
 };
 
@@ -45,6 +47,7 @@ pub(crate) fn convert_typedef_targets(
     apis: ApiVec<NullPhase>,
 ) -> ApiVec<TypedefPhase> {
     let mut type_converter = TypeConverter::new(config, &apis);
+    let alias_templates = find_alias_templates(&apis);
     let mut extra_apis = ApiVec::new();
     let mut results = ApiVec::new();
     convert_apis(
@@ -55,6 +58,9 @@ pub(crate) fn convert_typedef_targets(
         Api::enum_unchanged,
         |name, item, old_tyname, _| {
             Ok(Box::new(std::iter::once(match item {
+                TypedefKind::Type(ity) if is_concrete(config, &name) => {
+                    get_concrete_type(name, ity, &alias_templates)?
+                }
                 TypedefKind::Type(ity) => get_replacement_typedef(
                     name,
                     ity,
@@ -88,12 +94,37 @@ fn get_replacement_typedef(
     let mut converted_type = ity.clone();
     let metadata = BindgenSemanticAttributes::new_retaining_others(&mut converted_type.attrs);
     metadata.check_for_fatal_attrs(&ity.ident)?;
+    if let Some(fn_ptr) = function_pointer_target(&ity.ty) {
+        if let Some(deps) = function_pointer_deps(fn_ptr, &name, type_converter) {
+            return Ok(Api::FnPtrTypedef {
+                name,
+                item: converted_type,
+                deps,
+            });
+        }
+    }
     let type_conversion_results = type_converter.convert_type(
         (*ity.ty).clone(),
         name.name.get_namespace(),
         &TypeConversionContext::CxxInnerType,
     );
     match type_conversion_results {
+        Err(err) if !ity.generics.params.is_empty() => Err(ConvertErrorWithContext(
+            err,
+            Some(ErrorContext::new_for_item(name.name.get_final_ident())),
+        )),
+        Err(err) if is_unrepresentable_target(&err) => {
+            // We can't express the target of this typedef in Rust, but that's
+            // no reason to give up on every function which mentions it.
+            // C++ knows the typedef's name perfectly well, so we can treat
+            // it as an opaque type which Rust can hold by reference.
+            log::info!(
+                "Representing typedef {} opaquely because its target couldn't be converted: {}",
+                name.name,
+                err
+            );
+            Ok(Api::ForwardDeclaration { name })
+        }
         Err(err) => Err(ConvertErrorWithContext(
             err,
             Some(ErrorContext::new_for_item(name.name.get_final_ident())),
@@ -120,3 +151,222 @@ fn get_replacement_typedef(
         }
     }
 }
+
+/// Whether this error means merely that Rust can't express the target of a
+/// typedef, in which case the typedef can still be used opaquely. Other
+/// errors, such as the target having been blocked, must stand.
+fn is_unrepresentable_target(err: &ConvertError) -> bool {
+    matches!(
+        err,
+        ConvertError::UnsupportedType(_)
+            | ConvertError::UnsupportedBuiltInType(_)
+            | ConvertError::TemplatedTypeContainingNonPathArg(_)
+            | ConvertError::InvalidPointee
+            | ConvertError::UnknownType(_)
+    )
+}
+
+/// Whether this is the typedef we added to bindgen's input for a
+/// `concrete!` directive.
+fn is_concrete(config: &IncludeCppConfig, name: &ApiName) -> bool {
+    name.name.get_namespace().is_empty()
+        && config
+            .concretes()
+            .any(|(_, rust_id)| *rust_id == name.name.get_final_item())
+}
+
+/// The instantiation named by a `concrete!` directive. If it's an
+/// instantiation of an alias template, C++ needs to see the underlying
+/// template instead, so we resolve it.
+fn get_concrete_type(
+    name: ApiName,
+    ity: ItemType,
+    alias_templates: &HashMap<QualifiedName, ItemType>,
+) -> Result<Api<TypedefPhase>, ConvertErrorWithContext> {
+    let err_context = |err| {
+        ConvertErrorWithContext(
+            err,
+            Some(ErrorContext::new_for_item(name.name.get_final_ident())),
+        )
+    };
+    let rs_definition =
+        expand_alias_templates(&ity.ty, alias_templates, &HashSet::new()).map_err(err_context)?;
+    let cpp_definition = type_to_cpp(&rs_definition, &HashMap::new()).map_err(err_context)?;
+    Ok(Api::ConcreteType {
+        name,
+        rs_definition: Some(Box::new(rs_definition)),
+        cpp_definition,
+    })
+}
+
+/// Alias templates, i.e. typedefs with generic parameters, by name.
+fn find_alias_templates(apis: &ApiVec<NullPhase>) -> HashMap<QualifiedName, ItemType> {
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Typedef {
+                name,
+                item: TypedefKind::Type(ity),
+                ..
+            } if !ity.generics.params.is_empty() => Some((name.name.clone(), ity.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Replaces any instantiation of an alias template within this type by
+/// the type which it aliases, with the template arguments substituted for
+/// its parameters.
+fn expand_alias_templates(
+    ty: &Type,
+    alias_templates: &HashMap<QualifiedName, ItemType>,
+    encountered: &HashSet<QualifiedName>,
+) -> Result<Type, ConvertError> {
+    let mut typ = match ty {
+        Type::Path(typ) => typ.clone(),
+        _ => return Ok(ty.clone()),
+    };
+    let mut args = Vec::new();
+    if let Some(PathArguments::AngleBracketed(ab)) =
+        typ.path.segments.last_mut().map(|seg| &mut seg.arguments)
+    {
+        for arg in ab.args.iter_mut() {
+            if let GenericArgument::Type(arg_ty) = arg {
+                *arg_ty = expand_alias_templates(arg_ty, alias_templates, encountered)?;
+                args.push(arg_ty.clone());
+            }
+        }
+    }
+    let tn = QualifiedName::from_type_path(&typ);
+    let alias = match alias_templates.get(&tn) {
+        None => return Ok(Type::Path(typ)),
+        Some(alias) => alias,
+    };
+    if encountered.contains(&tn) {
+        return Err(ConvertError::InfinitelyRecursiveTypedef(tn));
+    }
+    let substitutions: HashMap<&Ident, Type> = alias
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .zip(args)
+        .collect();
+    let mut encountered = encountered.clone();
+    encountered.insert(tn);
+    expand_alias_templates(
+        &substitute_params(&alias.ty, &substitutions),
+        alias_templates,
+        &encountered,
+    )
+}
+
+fn substitute_params(ty: &Type, substitutions: &HashMap<&Ident, Type>) -> Type {
+    match ty {
+        Type::Path(typ) if typ.qself.is_none() && typ.path.segments.len() == 1 => {
+            match substitutions.get(&typ.path.segments[0].ident) {
+                Some(arg) if typ.path.segments[0].arguments.is_empty() => arg.clone(),
+                _ => substitute_params_in_path(typ.clone(), substitutions),
+            }
+        }
+        Type::Path(typ) => substitute_params_in_path(typ.clone(), substitutions),
+        Type::Ptr(ptr) => {
+            let mut ptr = ptr.clone();
+            ptr.elem = Box::new(substitute_params(&ptr.elem, substitutions));
+            Type::Ptr(ptr)
+        }
+        Type::Reference(reference) => {
+            let mut reference = reference.clone();
+            reference.elem = Box::new(substitute_params(&reference.elem, substitutions));
+            Type::Reference(reference)
+        }
+        _ => ty.clone(),
+    }
+}
+
+fn substitute_params_in_path(
+    mut typ: syn::TypePath,
+    substitutions: &HashMap<&Ident, Type>,
+) -> Type {
+    for seg in typ.path.segments.iter_mut() {
+        if let PathArguments::AngleBracketed(ab) = &mut seg.arguments {
+            for arg in ab.args.iter_mut() {
+                if let GenericArgument::Type(arg_ty) = arg {
+                    *arg_ty = substitute_params(arg_ty, substitutions);
+                }
+            }
+        }
+    }
+    Type::Path(typ)
+}
+
+/// bindgen represents a function pointer as
+/// `Option<unsafe extern "C" fn(..)>`, since it may be null.
+fn function_pointer_target(ty: &Type) -> Option<&TypeBareFn> {
+    let typ = match ty {
+        Type::Path(typ) => typ,
+        _ => return None,
+    };
+    let last_seg = typ.path.segments.last()?;
+    if last_seg.ident != "Option" {
+        return None;
+    }
+    match &last_seg.arguments {
+        PathArguments::AngleBracketed(ab) => match ab.args.first() {
+            Some(GenericArgument::Type(Type::BareFn(fn_ptr))) if fn_ptr.variadic.is_none() => {
+                Some(fn_ptr)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The types mentioned by a function pointer, so long as we can represent
+/// all of them. We leave them as bindgen gave them to us, since they're
+/// only ever passed to and from C++ through the function pointer itself,
+/// so we can't accept any which we'd otherwise replace (such as
+/// `std::string`) or need to instantiate.
+fn function_pointer_deps(
+    fn_ptr: &TypeBareFn,
+    name: &ApiName,
+    type_converter: &mut TypeConverter,
+) -> Option<HashSet<QualifiedName>> {
+    let ret = match &fn_ptr.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ty) => Some(ty.as_ref()),
+    };
+    let mut deps = HashSet::new();
+    for ty in fn_ptr.inputs.iter().map(|arg| &arg.ty).chain(ret) {
+        match type_converter.convert_type(
+            ty.clone(),
+            name.name.get_namespace(),
+            &TypeConversionContext::CxxInnerType,
+        ) {
+            Ok(annotated)
+                if annotated.extra_apis.is_empty()
+                    && annotated.types_encountered.iter().all(|dep| {
+                        dep.get_namespace().is_empty()
+                            || !known_types().is_known_type(dep)
+                            || known_types().is_ctype(dep)
+                    }) =>
+            {
+                deps.extend(annotated.types_encountered)
+            }
+            Ok(_) => {
+                log::info!(
+                    "Representing function pointer typedef {} opaquely because of the types it mentions",
+                    name.name
+                );
+                return None;
+            }
+            Err(err) => {
+                log::info!(
+                    "Representing function pointer typedef {} opaquely: {}",
+                    name.name,
+                    err
+                );
+                return None;
+            }
+        }
+    }
+    Some(deps)
+}
@@ -13,7 +13,7 @@ use crate::{
         codegen_cpp::type_to_cpp::type_to_cpp,
         ConvertError,
     },
-    known_types::{known_types, CxxGenericType},
+    known_types::{known_types, CxxGenericType, ViewKind},
     types::{make_ident, Namespace, QualifiedName},
 };
 use autocxx_parser::IncludeCppConfig;
@@ -22,11 +22,11 @@ use proc_macro2::Ident;
 use quote::ToTokens;
 use std::collections::{HashMap, HashSet};
 use syn::{
-    parse_quote, punctuated::Punctuated, GenericArgument, PathArguments, PathSegment, Type,
-    TypePath, TypePtr,
+    parse_quote, punctuated::Punctuated, Expr, ExprLit, GenericArgument, Lit, PathArguments,
+    PathSegment, Type, TypePath, TypePtr,
 };
 
-use super::tdef::TypedefAnalysis;
+use super::{fun::function_wrapper::ArrayShape, tdef::TypedefAnalysis};
 
 /// Certain kinds of type may require special handling by callers.
 #[derive(Debug)]
@@ -36,6 +36,12 @@ pub(crate) enum TypeKind {
     SubclassHolder(Ident),
     Reference,
     MutableReference,
+    /// A C++ view type such as `absl::string_view`, which is passed by value
+    /// in C++ but has become a reference in Rust.
+    View(QualifiedName),
+    /// A reference or pointer to a C++ array, which has become a pointer to
+    /// its first element.
+    Array(ArrayShape),
 }
 
 /// Results of some type conversion, annotated with a list of every type encountered,
@@ -185,6 +191,14 @@ impl<'a> TypeConverter<'a> {
                     TypeKind::Reference,
                 )
             }
+            // Arrays can't be represented in cxx, so only do this for the
+            // parameters and return values of functions, which we can wrap.
+            Type::Ptr(ptr)
+                if matches!(*ptr.elem, Type::Array(_))
+                    && matches!(ctx, TypeConversionContext::CxxOuterType { .. }) =>
+            {
+                self.convert_array_ptr(ptr, ns, ctx.convert_ptrs_to_references())?
+            }
             Type::Ptr(ptr) if ctx.convert_ptrs_to_references() => {
                 self.convert_ptr_to_reference(ptr, ns)?
             }
@@ -241,12 +255,15 @@ impl<'a> TypeConverter<'a> {
         if self.config.is_on_blocklist(&original_tn.to_cpp_name()) {
             return Err(ConvertError::Blocked(original_tn));
         }
+        let original_view = known_types()
+            .view_kind(&original_tn)
+            .map(|view_kind| (original_tn.clone(), view_kind));
         let mut deps = HashSet::new();
 
         // Now convert this type itself.
         deps.insert(original_tn.clone());
         // First let's see if this is a typedef.
-        let (typ, tn) = match self.resolve_typedef(&original_tn)? {
+        let (mut typ, tn) = match self.resolve_typedef(&original_tn)? {
             None => (typ, original_tn),
             Some(Type::Path(resolved_tp)) => {
                 let resolved_tn = QualifiedName::from_type_path(resolved_tp);
@@ -271,6 +288,34 @@ impl<'a> TypeConverter<'a> {
             }
         };
 
+        // Views onto borrowed data become references, so long as they're
+        // being passed by value.
+        if matches!(ctx, TypeConversionContext::CxxOuterType { .. }) {
+            let view = original_view.or_else(|| {
+                known_types()
+                    .view_kind(&tn)
+                    .map(|view_kind| (tn.clone(), view_kind))
+            });
+            if let Some((view_name, view_kind)) = view {
+                return self.convert_view(typ, view_name, view_kind, ns);
+            }
+        }
+
+        // A `std::unique_ptr` with the default deleter is a `cxx::UniquePtr`.
+        // One with a custom deleter becomes an opaque type, which C++
+        // destroys by calling the deleter.
+        if known_types().is_unique_ptr(&tn) && Self::strip_default_deleter(&mut typ) {
+            let (new_tn, api) = self.get_templated_typename(&Type::Path(typ))?;
+            deps.remove(&tn);
+            deps.insert(new_tn.clone());
+            return Ok(Annotated::new(
+                Type::Path(new_tn.to_type_path()),
+                deps,
+                api.into_iter().collect(),
+                TypeKind::Regular,
+            ));
+        }
+
         // Now let's see if it's a known type.
         // (We may entirely reject some types at this point too.)
         let mut typ = match known_types().consider_substitution(&tn) {
@@ -293,6 +338,7 @@ impl<'a> TypeConverter<'a> {
         if let Some(last_seg) = Self::get_generic_args(&mut typ) {
             let generic_behavior = known_types().cxx_generic_behavior(&tn);
             let forward_declarations_ok = generic_behavior == CxxGenericType::Rust
+                || known_types().permits_incomplete_type_argument(&tn)
                 || ctx.allow_instantiation_of_forward_declaration();
             if generic_behavior != CxxGenericType::Not {
                 // this is a type of generic understood by cxx (e.g. CxxVector)
@@ -322,6 +368,68 @@ impl<'a> TypeConverter<'a> {
         Ok(Annotated::new(Type::Path(typ), deps, extra_apis, kind))
     }
 
+    fn convert_view(
+        &mut self,
+        typ: TypePath,
+        view_name: QualifiedName,
+        view_kind: ViewKind,
+        ns: &Namespace,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        match view_kind {
+            ViewKind::Str => Ok(Annotated::new(
+                parse_quote! { &str },
+                HashSet::new(),
+                ApiVec::new(),
+                TypeKind::View(view_name),
+            )),
+            ViewKind::Slice | ViewKind::InitializerList => {
+                let elem = match &typ.path.segments.last().unwrap().arguments {
+                    PathArguments::AngleBracketed(ab) if ab.args.len() == 1 => {
+                        match ab.args.first() {
+                            Some(GenericArgument::Type(elem @ Type::Path(_))) => Some(elem.clone()),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                let elem = elem.ok_or_else(|| {
+                    ConvertError::TemplatedTypeContainingNonPathArg(QualifiedName::from_type_path(
+                        &typ,
+                    ))
+                })?;
+                let elem = self.convert_type(elem, ns, &TypeConversionContext::CxxInnerType)?;
+                let elem_ty = elem.ty;
+                Ok(Annotated::new(
+                    parse_quote! { &[#elem_ty] },
+                    elem.types_encountered,
+                    elem.extra_apis,
+                    TypeKind::View(view_name),
+                ))
+            }
+        }
+    }
+
+    /// Removes any `std::default_delete` deleter from the arguments of a
+    /// `std::unique_ptr`. Returns whether there's a custom deleter instead,
+    /// which is left in place.
+    fn strip_default_deleter(typ: &mut TypePath) -> bool {
+        let args = match typ.path.segments.last_mut().map(|seg| &mut seg.arguments) {
+            Some(PathArguments::AngleBracketed(ab)) => &mut ab.args,
+            _ => return false,
+        };
+        let is_default = match args.iter().nth(1) {
+            None => return false,
+            Some(GenericArgument::Type(Type::Path(deleter))) => {
+                known_types().is_default_deleter(&QualifiedName::from_type_path(deleter))
+            }
+            Some(_) => false,
+        };
+        if is_default {
+            *args = args.iter().take(1).cloned().collect();
+        }
+        !is_default
+    }
+
     fn get_generic_args(typ: &mut TypePath) -> Option<&mut PathSegment> {
         match typ.path.segments.last_mut() {
             Some(s) if !s.arguments.is_empty() => Some(s),
@@ -373,6 +481,10 @@ impl<'a> TypeConverter<'a> {
                     if encountered.contains(&new_tn) {
                         return Err(ConvertError::InfinitelyRecursiveTypedef(tn.clone()));
                     }
+                    // A typedef doesn't make a blocked type usable.
+                    if self.config.is_on_blocklist(&new_tn.to_cpp_name()) {
+                        return Err(ConvertError::Blocked(new_tn));
+                    }
                     encountered.insert(new_tn.clone());
                     tn = new_tn;
                 }
@@ -409,22 +521,95 @@ impl<'a> TypeConverter<'a> {
         Ok(outer)
     }
 
+    fn convert_array_ptr(
+        &mut self,
+        ptr: TypePtr,
+        ns: &Namespace,
+        by_reference: bool,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        let mut dims = Vec::new();
+        let mut elem = *ptr.elem;
+        while let Type::Array(arr) = elem {
+            let len = match &arr.len {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(len), ..
+                }) => len.base10_parse().ok(),
+                _ => None,
+            };
+            dims.push(
+                len.ok_or_else(|| {
+                    ConvertError::UnsupportedType(arr.to_token_stream().to_string())
+                })?,
+            );
+            elem = *arr.elem;
+        }
+        if !matches!(elem, Type::Path(_)) {
+            return Err(ConvertError::InvalidPointee);
+        }
+        let elem = self.convert_type(elem, ns, &TypeConversionContext::CxxInnerType)?;
+        let mutability = ptr.mutability;
+        let const_token = ptr.const_token;
+        let mut outer = elem.map(|elem| {
+            Type::Ptr(parse_quote! {
+                * #const_token #mutability #elem
+            })
+        });
+        outer.kind = TypeKind::Array(ArrayShape { dims, by_reference });
+        Ok(outer)
+    }
+
     fn get_templated_typename(
         &mut self,
         rs_definition: &Type,
     ) -> Result<(QualifiedName, Option<UnanalyzedApi>), ConvertError> {
-        let count = self.concrete_templates.len();
         // We just use this as a hash key, essentially.
         // TODO: Once we've completed the TypeConverter refactoring (see #220),
         // pass in an actual original_name_map here.
         let cpp_definition = type_to_cpp(rs_definition, &HashMap::new())?;
+        Ok(self.get_concrete_typename(
+            cpp_definition,
+            Some(Box::new(rs_definition.clone())),
+            "AutocxxConcrete",
+        ))
+    }
+
+    /// The opaque type which represents a member pointer type, such as
+    /// `int Foo::*`, as spelled in C++. bindgen tells us only the type
+    /// of the member, so we can't generate this from a Rust type.
+    pub(crate) fn get_member_pointer_typename(
+        &mut self,
+        cpp_definition: &str,
+    ) -> (QualifiedName, Option<UnanalyzedApi>) {
+        self.get_concrete_typename(cpp_definition.to_string(), None, "AutocxxMemberPointer")
+    }
+
+    /// A member pointer type, as spelled in C++, becomes the opaque type
+    /// which represents it.
+    pub(crate) fn convert_member_pointer(&mut self, cpp_definition: &str) -> Annotated<Box<Type>> {
+        let (token, api) = self.get_member_pointer_typename(cpp_definition);
+        Annotated::new(
+            Box::new(Type::Path(token.to_type_path())),
+            [token].into_iter().collect(),
+            api.into_iter().collect(),
+            TypeKind::Regular,
+        )
+    }
+
+    fn get_concrete_typename(
+        &mut self,
+        cpp_definition: String,
+        rs_definition: Option<Box<Type>>,
+        suffix: &str,
+    ) -> (QualifiedName, Option<UnanalyzedApi>) {
+        let count = self.concrete_templates.len();
         let e = self.concrete_templates.get(&cpp_definition);
         match e {
-            Some(tn) => Ok((tn.clone(), None)),
+            Some(tn) => (tn.clone(), None),
             None => {
                 let synthetic_ident = format!(
-                    "{}_AutocxxConcrete",
-                    cpp_definition.replace(|c: char| !(c.is_ascii_alphanumeric() || c == '_'), "_")
+                    "{}_{}",
+                    cpp_definition.replace(|c: char| !(c.is_ascii_alphanumeric() || c == '_'), "_"),
+                    suffix
                 );
                 // Remove runs of multiple _s. Trying to avoid a dependency on
                 // regex.
@@ -441,16 +626,16 @@ impl<'a> TypeConverter<'a> {
                     .find(|s| s == &synthetic_ident)
                 {
                     None => synthetic_ident,
-                    Some(_) => format!("AutocxxConcrete{}", count),
+                    Some(_) => format!("{}{}", suffix, count),
                 };
                 let api = UnanalyzedApi::ConcreteType {
                     name: ApiName::new_in_root_namespace(make_ident(&synthetic_ident)),
-                    rs_definition: Box::new(rs_definition.clone()),
+                    rs_definition,
                     cpp_definition: cpp_definition.clone(),
                 };
                 self.concrete_templates
                     .insert(cpp_definition, api.name().clone());
-                Ok((api.name().clone(), Some(api)))
+                (api.name().clone(), Some(api))
             }
         }
     }
@@ -526,6 +711,13 @@ impl<'a> TypeConverter<'a> {
     {
         apis.iter()
             .filter_map(|api| match &api {
+                // A `use` may refer to a type in any namespace, so we
+                // record its target by its full name.
+                Api::Typedef {
+                    item: TypedefKind::Use(_),
+                    old_tyname: Some(old_tyname),
+                    ..
+                } => Some((api.name().clone(), Type::Path(old_tyname.to_type_path()))),
                 Api::Typedef { analysis, .. } => analysis
                     .get_target()
                     .cloned()
@@ -604,10 +796,12 @@ pub(crate) fn find_types<A: AnalysisPhase>(apis: &ApiVec<A>) -> HashSet<Qualifie
             Api::ForwardDeclaration { .. }
             | Api::ConcreteType { .. }
             | Api::Typedef { .. }
+            | Api::FnPtrTypedef { .. }
             | Api::Enum { .. }
             | Api::Struct { .. }
             | Api::Subclass { .. }
-            | Api::RustType { .. } => Some(api.name()),
+            | Api::RustType { .. }
+            | Api::ExternCppType { .. } => Some(api.name()),
             Api::StringConstructor { .. }
             | Api::Function { .. }
             | Api::Const { .. }
@@ -6,7 +6,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::types::{make_ident, Namespace, QualifiedName};
 use autocxx_parser::RustPath;
@@ -22,7 +22,7 @@ use syn::{
 
 use super::{
     analysis::fun::{
-        function_wrapper::{CppFunction, CppFunctionBody, CppFunctionKind},
+        function_wrapper::{CallingConvention, CppFunction, CppFunctionBody, CppFunctionKind},
         ReceiverMutability,
     },
     convert_error::{ConvertErrorWithContext, ErrorContext},
@@ -53,6 +53,8 @@ pub(crate) struct StructDetails {
     pub(crate) vis: CppVisibility,
     pub(crate) item: ItemStruct,
     pub(crate) layout: Option<Layout>,
+    /// The offset in bytes of each field, as seen by bindgen.
+    pub(crate) field_offsets: Vec<(Ident, usize)>,
     pub(crate) has_rvalue_reference_fields: bool,
 }
 
@@ -63,8 +65,6 @@ pub(crate) struct Layout {
     pub(crate) size: usize,
     /// The alignment (in bytes) of this layout.
     pub(crate) align: usize,
-    /// Whether this layout's members are packed or not.
-    pub(crate) packed: bool,
 }
 
 impl Parse for Layout {
@@ -73,11 +73,12 @@ impl Parse for Layout {
         input.parse::<syn::token::Comma>()?;
         let align: LitInt = input.parse()?;
         input.parse::<syn::token::Comma>()?;
-        let packed: LitBool = input.parse()?;
+        // Whether the members are packed. The size and alignment already
+        // reflect that, which is all we need.
+        input.parse::<LitBool>()?;
         Ok(Layout {
             size: size.base10_parse().unwrap(),
             align: align.base10_parse().unwrap(),
-            packed: packed.value(),
         })
     }
 }
@@ -131,6 +132,9 @@ pub(crate) struct SuperclassMethod {
     pub(crate) receiver_mutability: ReceiverMutability,
     pub(crate) requires_unsafe: UnsafetyNeeded,
     pub(crate) is_pure_virtual: bool,
+    /// Whether subclasses can override this method. If not, it's a protected
+    /// method which subclasses can only call.
+    pub(crate) is_virtual: bool,
 }
 
 /// Information about references (as opposed to pointers) to be found
@@ -142,6 +146,15 @@ pub(crate) struct References {
     pub(crate) ref_params: HashSet<Ident>,
     pub(crate) ref_return: bool,
     pub(crate) rvalue_ref_return: bool,
+    /// Whether this is a method which may only be called on an rvalue,
+    /// i.e. it's `&&`-qualified, so it may consume `*this`.
+    pub(crate) rvalue_ref_this: bool,
+    /// Parameters which are really member pointers, with the C++ spelling
+    /// of each member pointer type. bindgen gives them the type of a
+    /// plain pointer to the member.
+    pub(crate) member_pointer_params: HashMap<Ident, String>,
+    /// The C++ spelling of the return type, if it's a member pointer.
+    pub(crate) member_pointer_return: Option<String>,
 }
 
 impl References {
@@ -196,6 +209,10 @@ impl std::hash::Hash for TraitImplSignature {
 #[derive(Clone, Debug)]
 pub(crate) enum SpecialMemberKind {
     DefaultConstructor,
+    /// Any other constructor, apart from copy and move constructors.
+    /// bindgen doesn't annotate these, so we find them from the 'impl'
+    /// blocks it generates.
+    Constructor,
     CopyConstructor,
     MoveConstructor,
     Destructor,
@@ -247,6 +264,10 @@ pub(crate) struct FuncToConvert {
     /// C++ and instead we're synthesizing it.
     pub(crate) synthetic_cpp: Option<(CppFunctionBody, CppFunctionKind)>,
     pub(crate) is_deleted: bool,
+    pub(crate) calling_convention: Option<CallingConvention>,
+    /// The symbol name given to us by bindgen, if it differs from the
+    /// function name, without any prefix telling rustc not to mangle it.
+    pub(crate) link_name: Option<String>,
 }
 
 /// Layers of analysis which may be applied to decorate each API.
@@ -313,9 +334,9 @@ impl ApiName {
     pub(crate) fn qualified_cpp_name(&self) -> String {
         let cpp_name = self.cpp_name();
         self.name
-            .ns_segment_iter()
-            .cloned()
-            .chain(std::iter::once(cpp_name))
+            .get_namespace()
+            .cpp_iter()
+            .chain(std::iter::once(cpp_name.as_str()))
             .join("::")
     }
 
@@ -417,10 +438,13 @@ pub(crate) enum Api<T: AnalysisPhase> {
     /// A forward declared type for which no definition is available.
     ForwardDeclaration { name: ApiName },
     /// A synthetic type we've manufactured in order to
-    /// concretize some templated C++ type.
+    /// concretize some templated C++ type, or to represent some other
+    /// C++ type which cxx can't, such as a member pointer.
     ConcreteType {
         name: ApiName,
-        rs_definition: Box<Type>,
+        /// The templated type, or `None` if there's no Rust equivalent and
+        /// `cpp_definition` is exactly the C++ type, as for a member pointer.
+        rs_definition: Option<Box<Type>>,
         cpp_definition: String,
     },
     /// A simple note that we want to make a constructor for
@@ -436,6 +460,9 @@ pub(crate) enum Api<T: AnalysisPhase> {
     Const {
         name: ApiName,
         const_item: ItemConst,
+        /// For an associated constant, the type in whose `impl` block
+        /// bindgen declared it.
+        impl_for: Option<QualifiedName>,
     },
     /// A typedef found in the bindgen output which we wish
     /// to pass on in our output
@@ -445,6 +472,14 @@ pub(crate) enum Api<T: AnalysisPhase> {
         old_tyname: Option<QualifiedName>,
         analysis: T::TypedefAnalysis,
     },
+    /// A typedef of a function pointer type, e.g.
+    /// `typedef void (*Callback)(int)`. `deps` are any types mentioned in
+    /// its parameters or return type.
+    FnPtrTypedef {
+        name: ApiName,
+        item: ItemType,
+        deps: HashSet<QualifiedName>,
+    },
     /// An enum encountered in the
     /// `bindgen` output.
     Enum { name: ApiName, item: ItemEnum },
@@ -471,6 +506,9 @@ pub(crate) enum Api<T: AnalysisPhase> {
     },
     /// A Rust type which is not a C++ type.
     RustType { name: ApiName, path: RustPath },
+    /// A C++ type for which some other `include_cpp!` generated the Rust
+    /// type, found at this path.
+    ExternCppType { name: ApiName, path: RustPath },
     /// A function for the 'extern Rust' block which is not a C++ type.
     RustFn {
         name: ApiName,
@@ -506,6 +544,8 @@ pub(crate) struct RustSubclassFnDetails {
     pub(crate) dependencies: Vec<QualifiedName>,
     pub(crate) requires_unsafe: UnsafetyNeeded,
     pub(crate) is_pure_virtual: bool,
+    /// See [`SuperclassMethod::is_virtual`].
+    pub(crate) is_virtual: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -524,11 +564,13 @@ impl<T: AnalysisPhase> Api<T> {
             Api::Function { name, .. } => name,
             Api::Const { name, .. } => name,
             Api::Typedef { name, .. } => name,
+            Api::FnPtrTypedef { name, .. } => name,
             Api::Enum { name, .. } => name,
             Api::Struct { name, .. } => name,
             Api::CType { name, .. } => name,
             Api::IgnoredItem { name, .. } => name,
             Api::RustType { name, .. } => name,
+            Api::ExternCppType { name, .. } => name,
             Api::RustFn { name, .. } => name,
             Api::RustSubclassFn { name, .. } => name,
             Api::Subclass { name, .. } => &name.0,
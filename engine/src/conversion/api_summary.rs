@@ -0,0 +1,371 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use quote::ToTokens;
+
+use super::{
+    analysis::fun::{FnKind, FnPhase},
+    api::{Api, TypeKind},
+    apivec::ApiVec,
+};
+use crate::types::{cpp_name_for_rust_ident, QualifiedName};
+
+/// The broad category of an item for which autocxx generated (or
+/// declined to generate) bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeneratedItemKind {
+    /// A plain-old-data type, which can be held by value in Rust.
+    PodType,
+    /// A type which can only be held in Rust by reference, `UniquePtr`
+    /// or similar.
+    NonPodType,
+    /// A type which has pure virtual members and so can't be instantiated.
+    AbstractType,
+    /// A type which was declared but never defined, or a typedef whose
+    /// target couldn't be represented in Rust. Either way, it's opaque.
+    ForwardDeclaration,
+    /// A concrete instantiation of some C++ template.
+    ConcreteType,
+    /// A type generated by another `include_cpp!`, named in an
+    /// `extern_cpp_ns!` directive, which we merely refer to.
+    ExternCppType,
+    /// An enum.
+    Enum,
+    /// A typedef or `using` alias.
+    Typedef,
+    /// A constant.
+    Const,
+    /// A free function.
+    Function,
+    /// A method on some C++ type.
+    Method,
+    /// An implementation of some Rust trait (e.g. `MoveNew`) on a C++ type.
+    TraitMethod,
+    /// Some other utility which autocxx generated, such as `make_string`.
+    Utility,
+    /// A Rust type exposed to C++.
+    RustType,
+    /// A Rust function exposed to C++.
+    RustFunction,
+    /// A Rust subclass of a C++ class, or one of its supporting items.
+    Subclass,
+    /// Something which autocxx could not generate. See
+    /// [`GeneratedItem::ignored_reason`].
+    Ignored,
+}
+
+/// A structured description of one item within the bindings generated
+/// by [`crate::IncludeCppEngine`]. This is intended for tools which
+/// consume autocxx programmatically and want to know what was generated
+/// without inspecting the Rust or C++ output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedItem {
+    /// The name by which this item is known in Rust, including any
+    /// namespace, e.g. `a::b::Foo`.
+    pub rust_name: String,
+    /// The name by which this item is known in C++, if it differs from
+    /// the final segment of `rust_name`.
+    pub cpp_name: Option<String>,
+    /// What sort of item this is.
+    pub kind: GeneratedItemKind,
+    /// For methods, trait methods and associated constants, the Rust name
+    /// of the type on which they're implemented.
+    pub impl_for: Option<String>,
+    /// For ignored items, an explanation of why autocxx couldn't generate
+    /// bindings.
+    pub ignored_reason: Option<String>,
+    /// For functions and methods, the signature with which they're declared
+    /// in the `cxx::bridge`. This may differ from the signature of any Rust
+    /// wrapper function which autocxx generates.
+    pub signature: Option<String>,
+    /// For functions and methods, whether autocxx generates a C++ wrapper
+    /// function, for instance to convert parameters or return values which
+    /// `cxx` can't pass directly.
+    pub has_cpp_wrapper: bool,
+    /// For functions and methods, whether autocxx generates a Rust function
+    /// which wraps the `cxx::bridge` declaration, for instance to accept
+    /// anything convertible to a C++ string.
+    pub has_rust_wrapper: bool,
+    /// Any C++ identifiers within this item's name which are Rust keywords
+    /// (e.g. a namespace called `async` or a function called `move`), and
+    /// so were renamed in Rust by appending an underscore.
+    pub keyword_renames: Vec<String>,
+    /// For functions and methods which autocxx renamed because their Rust
+    /// name would otherwise have been the same as another's, the name they
+    /// would otherwise have had.
+    pub renamed_from: Option<String>,
+}
+
+#[cfg(feature = "manifest")]
+impl GeneratedItem {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "rust_name": self.rust_name,
+            "cpp_name": self.cpp_name,
+            "kind": format!("{:?}", self.kind),
+            "impl_for": self.impl_for,
+            "ignored_reason": self.ignored_reason,
+            "signature": self.signature,
+            "has_cpp_wrapper": self.has_cpp_wrapper,
+            "has_rust_wrapper": self.has_rust_wrapper,
+            "keyword_renames": self.keyword_renames,
+            "renamed_from": self.renamed_from,
+        })
+    }
+}
+
+/// Describes a set of generated items as JSON, suitable for diffing between
+/// versions of a library or for driving other tools.
+#[cfg(feature = "manifest")]
+pub fn generated_items_to_json(items: &[GeneratedItem]) -> serde_json::Value {
+    serde_json::Value::Array(items.iter().map(GeneratedItem::to_json).collect())
+}
+
+/// How one item differs between two sets of generated bindings, for
+/// instance those for two versions of the same C++ library. See
+/// [`diff_generated_items`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeneratedItemChange {
+    /// The item exists only in the new bindings.
+    Added(GeneratedItem),
+    /// The item exists only in the old bindings.
+    Removed(GeneratedItem),
+    /// The item exists in both, but its kind, signature or C++ name differ,
+    /// or it became (or stopped being) ignored.
+    Changed {
+        old: GeneratedItem,
+        new: GeneratedItem,
+    },
+}
+
+impl GeneratedItemChange {
+    /// The Rust name of the item which changed.
+    pub fn rust_name(&self) -> &str {
+        match self {
+            GeneratedItemChange::Added(item)
+            | GeneratedItemChange::Removed(item)
+            | GeneratedItemChange::Changed { new: item, .. } => &item.rust_name,
+        }
+    }
+}
+
+/// Compare the items generated for two versions of some headers, as returned
+/// by [`crate::IncludeCppEngine::analyze`], matching up items by their
+/// Rust names. The changes are sorted by name. Only differences which would
+/// affect users of the bindings are reported, so a function which merely
+/// gains or loses a C++ wrapper is considered unchanged.
+pub fn diff_generated_items(
+    old: &[GeneratedItem],
+    new: &[GeneratedItem],
+) -> Vec<GeneratedItemChange> {
+    let old_by_name: HashMap<_, _> = old.iter().map(|item| (&item.rust_name, item)).collect();
+    let new_by_name: HashMap<_, _> = new.iter().map(|item| (&item.rust_name, item)).collect();
+    let mut changes: Vec<_> = old
+        .iter()
+        .filter(|item| !new_by_name.contains_key(&item.rust_name))
+        .map(|item| GeneratedItemChange::Removed(item.clone()))
+        .chain(
+            new.iter()
+                .filter_map(|item| match old_by_name.get(&item.rust_name) {
+                    None => Some(GeneratedItemChange::Added(item.clone())),
+                    Some(old_item) if !same_api(old_item, item) => {
+                        Some(GeneratedItemChange::Changed {
+                            old: (*old_item).clone(),
+                            new: item.clone(),
+                        })
+                    }
+                    Some(_) => None,
+                }),
+        )
+        .collect();
+    changes.sort_by(|a, b| a.rust_name().cmp(b.rust_name()));
+    changes
+}
+
+fn same_api(a: &GeneratedItem, b: &GeneratedItem) -> bool {
+    a.kind == b.kind
+        && a.cpp_name == b.cpp_name
+        && a.impl_for == b.impl_for
+        && a.signature == b.signature
+        && a.ignored_reason.is_some() == b.ignored_reason.is_some()
+}
+
+/// Build a description of each of the APIs which survived analysis.
+pub(crate) fn summarize_apis(apis: &ApiVec<FnPhase>) -> Vec<GeneratedItem> {
+    let mut items: Vec<_> = apis.iter().map(summarize_api).collect();
+    items.sort_by(|a, b| a.rust_name.cmp(&b.rust_name));
+    items
+}
+
+fn summarize_api(api: &Api<FnPhase>) -> GeneratedItem {
+    let mut impl_for = None;
+    let mut ignored_reason = None;
+    let mut signature = None;
+    let mut has_cpp_wrapper = false;
+    let mut has_rust_wrapper = false;
+    let mut rust_fn_name = None;
+    let mut renamed_from = None;
+    let kind = match api {
+        Api::ForwardDeclaration { .. } => GeneratedItemKind::ForwardDeclaration,
+        Api::ConcreteType { .. } => GeneratedItemKind::ConcreteType,
+        Api::StringConstructor { .. } => GeneratedItemKind::Utility,
+        Api::Function { analysis, .. } => {
+            signature = Some(
+                format!(
+                    "fn {}({}) {}",
+                    analysis.rust_name,
+                    analysis.params.to_token_stream(),
+                    analysis.ret_type.to_token_stream()
+                )
+                .trim_end()
+                .to_string(),
+            );
+            has_cpp_wrapper = analysis.cpp_wrapper.is_some();
+            has_rust_wrapper = analysis.rust_wrapper_needed;
+            rust_fn_name = Some(analysis.rust_name.clone());
+            renamed_from = analysis.renamed_from.clone();
+            match &analysis.kind {
+                FnKind::Function => GeneratedItemKind::Function,
+                FnKind::Method { impl_for: ty, .. } => {
+                    impl_for = Some(ty.to_string());
+                    GeneratedItemKind::Method
+                }
+                FnKind::TraitMethod { impl_for: ty, .. } => {
+                    impl_for = Some(ty.to_string());
+                    GeneratedItemKind::TraitMethod
+                }
+            }
+        }
+        Api::Const { impl_for: ty, .. } => {
+            impl_for = ty.as_ref().map(QualifiedName::to_string);
+            GeneratedItemKind::Const
+        }
+        Api::Typedef { .. } | Api::FnPtrTypedef { .. } => GeneratedItemKind::Typedef,
+        Api::Enum { .. } => GeneratedItemKind::Enum,
+        Api::ExternCppType { .. } => GeneratedItemKind::ExternCppType,
+        Api::Struct { analysis, .. } => match analysis.pod.kind {
+            TypeKind::Pod => GeneratedItemKind::PodType,
+            TypeKind::NonPod => GeneratedItemKind::NonPodType,
+            TypeKind::Abstract => GeneratedItemKind::AbstractType,
+        },
+        Api::CType { .. } => GeneratedItemKind::Utility,
+        Api::IgnoredItem { err, .. } => {
+            ignored_reason = Some(err.to_string());
+            GeneratedItemKind::Ignored
+        }
+        Api::RustType { .. } => GeneratedItemKind::RustType,
+        Api::RustFn { .. } => GeneratedItemKind::RustFunction,
+        Api::RustSubclassFn { .. } | Api::Subclass { .. } | Api::SubclassTraitItem { .. } => {
+            GeneratedItemKind::Subclass
+        }
+    };
+    let keyword_renames = api
+        .name()
+        .segment_iter()
+        .chain(rust_fn_name)
+        .filter_map(|seg| {
+            let cpp_seg = cpp_name_for_rust_ident(&seg);
+            (cpp_seg != seg).then(|| cpp_seg.to_string())
+        })
+        .unique()
+        .collect();
+    GeneratedItem {
+        rust_name: api.name().to_string(),
+        cpp_name: api.cpp_name().clone(),
+        kind,
+        impl_for,
+        ignored_reason,
+        signature,
+        has_cpp_wrapper,
+        has_rust_wrapper,
+        keyword_renames,
+        renamed_from,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_generated_items, GeneratedItem, GeneratedItemChange, GeneratedItemKind};
+
+    fn function(rust_name: &str, signature: &str) -> GeneratedItem {
+        GeneratedItem {
+            rust_name: rust_name.into(),
+            cpp_name: None,
+            kind: GeneratedItemKind::Function,
+            impl_for: None,
+            ignored_reason: None,
+            signature: Some(signature.into()),
+            has_cpp_wrapper: false,
+            has_rust_wrapper: false,
+            keyword_renames: Vec::new(),
+            renamed_from: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_generated_items() {
+        let kept = function("a::kept", "fn kept()");
+        let old = vec![
+            kept.clone(),
+            function("a::changed", "fn changed(x: u32)"),
+            function("a::removed", "fn removed()"),
+        ];
+        let mut rewrapped = kept.clone();
+        rewrapped.has_cpp_wrapper = true;
+        let new = vec![
+            function("a::added", "fn added()"),
+            function("a::changed", "fn changed(x: u64)"),
+            rewrapped,
+        ];
+        let changes = diff_generated_items(&old, &new);
+        assert_eq!(changes.len(), 3);
+        assert!(
+            matches!(&changes[0], GeneratedItemChange::Added(item) if item.rust_name == "a::added")
+        );
+        match &changes[1] {
+            GeneratedItemChange::Changed { old, new } => {
+                assert_eq!(old.signature.as_deref(), Some("fn changed(x: u32)"));
+                assert_eq!(new.signature.as_deref(), Some("fn changed(x: u64)"));
+            }
+            _ => panic!("Expected a change"),
+        }
+        assert!(
+            matches!(&changes[2], GeneratedItemChange::Removed(item) if item.rust_name == "a::removed")
+        );
+    }
+
+    #[cfg(feature = "manifest")]
+    #[test]
+    fn test_manifest_json() {
+        use super::generated_items_to_json;
+
+        let items = vec![GeneratedItem {
+            rust_name: "a::Foo".into(),
+            cpp_name: None,
+            kind: GeneratedItemKind::Method,
+            impl_for: Some("a::Bar".into()),
+            ignored_reason: None,
+            signature: Some("fn Foo(self: &Bar) -> u32".into()),
+            has_cpp_wrapper: false,
+            has_rust_wrapper: true,
+            keyword_renames: vec!["type".into()],
+            renamed_from: Some("Foo1".into()),
+        }];
+        let json = generated_items_to_json(&items);
+        assert_eq!(json[0]["rust_name"], "a::Foo");
+        assert_eq!(json[0]["kind"], "Method");
+        assert_eq!(json[0]["impl_for"], "a::Bar");
+        assert!(json[0]["cpp_name"].is_null());
+        assert_eq!(json[0]["has_rust_wrapper"], true);
+        assert_eq!(json[0]["keyword_renames"][0], "type");
+        assert_eq!(json[0]["renamed_from"], "Foo1");
+    }
+}
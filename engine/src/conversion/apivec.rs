@@ -101,6 +101,11 @@ impl<P: AnalysisPhase> ApiVec<P> {
         self.apis.into_iter()
     }
 
+    /// Order the APIs by namespace and then name.
+    pub(crate) fn sort_by_name(&mut self) {
+        self.apis.sort_by(|a, b| a.name().cmp(b.name()));
+    }
+
     pub(crate) fn is_empty(&self) -> bool {
         self.apis.is_empty()
     }
@@ -0,0 +1,39 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use indoc::indoc;
+
+/// This is logic to find a function in a shared library at runtime, as
+/// requested by `dynamic_library!`. We leave the library loaded for the
+/// life of the process, since functions from it may be called at any
+/// time. Failure is reported by throwing, so that it's an `Err` for any
+/// function which can return a `Result`, and otherwise terminates with a
+/// message naming the missing library or symbol.
+pub(super) static DYNAMIC_LIBRARY_PRELUDE: &str = indoc! {"
+    #include <dlfcn.h>
+    #include <stdexcept>
+    #include <string>
+    #ifndef AUTOCXX_DYNAMIC_LIBRARY_PRELUDE
+    #define AUTOCXX_DYNAMIC_LIBRARY_PRELUDE
+    // Mechanics to look up functions in a shared library
+    inline void *autocxx_dl_resolve(const char *library, const char *symbol) {
+      void *handle = dlopen(library, RTLD_NOW | RTLD_LOCAL);
+      if (!handle) {
+        const char *error = dlerror();
+        throw std::runtime_error(std::string(\"autocxx: unable to load \") + library +
+                                 \": \" + (error ? error : \"unknown error\"));
+      }
+      void *sym = dlsym(handle, symbol);
+      if (!sym) {
+        throw std::runtime_error(std::string(\"autocxx: symbol \") + symbol +
+                                 \" not found in \" + library);
+      }
+      return sym;
+    }
+    #endif // AUTOCXX_DYNAMIC_LIBRARY_PRELUDE
+"};
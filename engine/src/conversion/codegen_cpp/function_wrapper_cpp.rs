@@ -7,11 +7,14 @@
 // except according to those terms.
 
 use crate::conversion::{
-    analysis::fun::function_wrapper::{CppConversionType, TypeConversionPolicy},
+    analysis::fun::function_wrapper::{ArrayShape, CppConversionType, TypeConversionPolicy},
     ConvertError,
 };
 
 use super::type_to_cpp::{type_to_cpp, CppNameMap};
+use itertools::Itertools;
+use quote::ToTokens;
+use syn::{Type, TypePtr, TypeReference, TypeSlice};
 
 impl TypeConversionPolicy {
     pub(super) fn unconverted_type(
@@ -23,6 +26,16 @@ impl TypeConversionPolicy {
             CppConversionType::FromPtrToValue => {
                 Ok(format!("{}*", self.unwrapped_type_as_string(cpp_name_map)?))
             }
+            CppConversionType::FromRustViewToCppView(_) => self.rust_view_type(cpp_name_map),
+            CppConversionType::FromCppViewToRustView(ref cpp_view) => {
+                self.cpp_view_type(cpp_view, cpp_name_map)
+            }
+            CppConversionType::FromSliceToInitializerList => self.rust_view_type(cpp_name_map),
+            CppConversionType::FromInitializerListToSlice => {
+                self.initializer_list_type(cpp_name_map)
+            }
+            CppConversionType::FromArrayToPtr(ref shape) => self.array_type(shape, cpp_name_map),
+            CppConversionType::FromUniqueArrayToPtr => self.unique_array_type(cpp_name_map),
             _ => self.unwrapped_type_as_string(cpp_name_map),
         }
     }
@@ -30,6 +43,15 @@ impl TypeConversionPolicy {
     pub(super) fn converted_type(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
         match self.cpp_conversion {
             CppConversionType::FromValueToUniquePtr => self.unique_ptr_wrapped_type(cpp_name_map),
+            CppConversionType::FromRustViewToCppView(ref cpp_view) => {
+                self.cpp_view_type(cpp_view, cpp_name_map)
+            }
+            CppConversionType::FromCppViewToRustView(_) => self.rust_view_type(cpp_name_map),
+            CppConversionType::FromSliceToInitializerList => {
+                self.initializer_list_type(cpp_name_map)
+            }
+            CppConversionType::FromInitializerListToSlice => self.rust_view_type(cpp_name_map),
+            CppConversionType::FromPtrToArray(ref shape) => self.array_type(shape, cpp_name_map),
             _ => self.unwrapped_type_as_string(cpp_name_map),
         }
     }
@@ -48,6 +70,107 @@ impl TypeConversionPolicy {
         ))
     }
 
+    /// The `std::unique_ptr<T[]>` for a pointer to `T`.
+    fn unique_array_type(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
+        match &self.unwrapped_type {
+            Type::Ptr(TypePtr { elem, .. }) => Ok(format!(
+                "std::unique_ptr<{}[]>",
+                type_to_cpp(elem, cpp_name_map)?
+            )),
+            _ => Err(ConvertError::UnsupportedType(
+                self.unwrapped_type.to_token_stream().to_string(),
+            )),
+        }
+    }
+
+    /// For a view type, the type of the element viewed, if it's a slice.
+    fn view_element_type(&self, cpp_name_map: &CppNameMap) -> Result<Option<String>, ConvertError> {
+        match &self.unwrapped_type {
+            Type::Reference(TypeReference { elem, .. }) => match elem.as_ref() {
+                Type::Slice(TypeSlice { elem, .. }) => Ok(Some(type_to_cpp(elem, cpp_name_map)?)),
+                _ => Ok(None),
+            },
+            _ => Err(ConvertError::UnsupportedType(
+                self.unwrapped_type.to_token_stream().to_string(),
+            )),
+        }
+    }
+
+    /// The cxx type which represents a view in Rust: `rust::Str` or
+    /// `rust::Slice<const T>`.
+    fn rust_view_type(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
+        Ok(match self.view_element_type(cpp_name_map)? {
+            Some(elem) => format!("rust::Slice<const {}>", elem),
+            None => "rust::Str".to_string(),
+        })
+    }
+
+    /// The C++ view type, such as `absl::string_view` or `absl::Span<const T>`.
+    fn cpp_view_type(
+        &self,
+        cpp_view: &str,
+        cpp_name_map: &CppNameMap,
+    ) -> Result<String, ConvertError> {
+        Ok(match self.view_element_type(cpp_name_map)? {
+            Some(elem) => format!("{}<const {}>", cpp_view, elem),
+            None => cpp_view.to_string(),
+        })
+    }
+
+    /// The `std::initializer_list<T>` for a slice of `T`.
+    pub(super) fn initializer_list_type(
+        &self,
+        cpp_name_map: &CppNameMap,
+    ) -> Result<String, ConvertError> {
+        match self.view_element_type(cpp_name_map)? {
+            Some(elem) => Ok(format!("std::initializer_list<{}>", elem)),
+            None => Err(ConvertError::UnsupportedType(
+                self.unwrapped_type.to_token_stream().to_string(),
+            )),
+        }
+    }
+
+    /// For a pointer to the first element of an array, the reference or
+    /// pointer to the array itself, e.g. `int (&)[3][4]`. We spell this
+    /// using `<type_traits>` so that, like other types, it can be followed
+    /// by a variable name.
+    fn array_type(
+        &self,
+        shape: &ArrayShape,
+        cpp_name_map: &CppNameMap,
+    ) -> Result<String, ConvertError> {
+        Ok(format!(
+            "std::add_{}_t<{}>",
+            if shape.by_reference {
+                "lvalue_reference"
+            } else {
+                "pointer"
+            },
+            self.array_elements_type(shape, cpp_name_map)?
+        ))
+    }
+
+    /// The array type itself, e.g. `int[3][4]`.
+    fn array_elements_type(
+        &self,
+        shape: &ArrayShape,
+        cpp_name_map: &CppNameMap,
+    ) -> Result<String, ConvertError> {
+        match &self.unwrapped_type {
+            Type::Ptr(TypePtr {
+                elem, mutability, ..
+            }) => Ok(format!(
+                "{}{}{}",
+                if mutability.is_some() { "" } else { "const " },
+                type_to_cpp(elem, cpp_name_map)?,
+                shape.dims.iter().map(|len| format!("[{}]", len)).join("")
+            )),
+            _ => Err(ConvertError::UnsupportedType(
+                self.unwrapped_type.to_token_stream().to_string(),
+            )),
+        }
+    }
+
     pub(super) fn cpp_conversion(
         &self,
         var_name: &str,
@@ -57,7 +180,11 @@ impl TypeConversionPolicy {
         // If is_return we want to avoid unnecessary std::moves because they
         // make RVO less effective
         Ok(match self.cpp_conversion {
-            CppConversionType::None => var_name.to_string(),
+            // The wrapper returns the value type, so returning the
+            // reference copies it.
+            CppConversionType::None | CppConversionType::FromReferenceToValue => {
+                var_name.to_string()
+            }
             CppConversionType::Move => {
                 format!("std::move({})", var_name)
             }
@@ -77,6 +204,40 @@ impl TypeConversionPolicy {
                     format!("std::move({})", dereference)
                 }
             }
+            CppConversionType::FromRustViewToCppView(_)
+            | CppConversionType::FromCppViewToRustView(_) => {
+                // Both sides are constructed from a pointer and a length. Use
+                // a lambda so that we evaluate `var_name` just once, since it
+                // may be a function call.
+                format!(
+                    "[]({} v) {{ return {}(v.data(), v.size()); }}({})",
+                    self.unconverted_type(cpp_name_map)?,
+                    self.converted_type(cpp_name_map)?,
+                    var_name
+                )
+            }
+            // The caller wraps the call in a helper which builds a list named
+            // like this; see `generate_cpp_function_inner`.
+            CppConversionType::FromSliceToInitializerList => format!("{}_list", var_name),
+            CppConversionType::FromPtrToArray(ref shape) => format!(
+                "{}reinterpret_cast<std::add_pointer_t<{}>>({})",
+                if shape.by_reference { "*" } else { "" },
+                self.array_elements_type(shape, cpp_name_map)?,
+                var_name
+            ),
+            CppConversionType::FromArrayToPtr(ref shape) => format!(
+                "reinterpret_cast<{}>({}{})",
+                self.unwrapped_type_as_string(cpp_name_map)?,
+                if shape.by_reference { "&" } else { "" },
+                var_name
+            ),
+            CppConversionType::FromUniqueArrayToPtr => format!("{}.release()", var_name),
+            CppConversionType::FromInitializerListToSlice => format!(
+                "[]({} v) {{ return {}(v.begin(), v.size()); }}({})",
+                self.unconverted_type(cpp_name_map)?,
+                self.converted_type(cpp_name_map)?,
+                var_name
+            ),
         })
     }
 }
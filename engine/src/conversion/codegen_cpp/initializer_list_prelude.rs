@@ -0,0 +1,42 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use indoc::formatdoc;
+use itertools::Itertools;
+
+use crate::conversion::analysis::fun::function_wrapper::MAX_INITIALIZER_LIST_LEN;
+
+/// This is logic to call a function with a `std::initializer_list` holding
+/// copies of the elements of a slice. Such a list can only be constructed
+/// from a braced list, so we switch between a braced list of each length
+/// up to `MAX_INITIALIZER_LIST_LEN`. The Rust side checks the length before
+/// we get here.
+pub(super) fn initializer_list_prelude() -> String {
+    let cases = (0..=MAX_INITIALIZER_LIST_LEN)
+        .map(|len| {
+            let items = (0..len).map(|idx| format!("items[{}]", idx)).join(", ");
+            format!("  case {}:\n    return f({{{}}});", len, items)
+        })
+        .join("\n");
+    formatdoc! {"
+        #include <cstddef>
+        #include <cstdlib>
+        #include <initializer_list>
+        #ifndef AUTOCXX_INITIALIZER_LIST_PRELUDE
+        #define AUTOCXX_INITIALIZER_LIST_PRELUDE
+        // Mechanics to build a std::initializer_list from a slice
+        template <typename T, typename F>
+        decltype(auto) autocxx_with_initializer_list(const T *items, std::size_t len, F f) {{
+          switch (len) {{
+        {cases}
+          }}
+          std::abort();
+        }}
+        #endif // AUTOCXX_INITIALIZER_LIST_PRELUDE
+    ", cases = cases}
+}
@@ -6,18 +6,23 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+mod dynamic_library_prelude;
 mod function_wrapper_cpp;
+mod initializer_list_prelude;
 mod new_and_delete_prelude;
 pub(crate) mod type_to_cpp;
 
 use crate::{
-    conversion::analysis::fun::{function_wrapper::CppFunctionKind, FnAnalysis},
+    conversion::analysis::fun::{function_wrapper::CppFunctionKind, FnAnalysis, FnKind},
     types::{make_ident, QualifiedName},
     CppCodegenOptions, CppFilePair,
 };
 use autocxx_parser::IncludeCppConfig;
+use indoc::formatdoc;
 use itertools::Itertools;
+use quote::quote;
 use std::collections::{HashMap, HashSet};
+use syn::Ident;
 use type_to_cpp::{original_name_map_from_apis, type_to_cpp, CppNameMap};
 
 use self::type_to_cpp::{
@@ -25,15 +30,33 @@ use self::type_to_cpp::{
 };
 
 use super::{
+    abi_self_tests::find_abi_tested_types,
     analysis::{
         fun::{
-            function_wrapper::{CppFunction, CppFunctionBody},
+            function_wrapper::{
+                CppConversionType, CppFunction, CppFunctionBody, TypeConversionPolicy,
+            },
             FnPhase, PodAndDepAnalysis,
         },
         pod::PodAnalysis,
     },
-    api::{Api, Provenance, SubclassName, TypeKind},
+    api::{Api, Layout, Provenance, SubclassName, TypeKind},
     apivec::ApiVec,
+    dense_matrices::find_dense_matrices,
+    iterators::find_iterable_types,
+    last_errors::{find_last_error_fetchers, LastError},
+    member_pointers::find_data_member_pointers,
+    qt_signals::{find_qt_signals, qt_disconnect_fn_name},
+    refcounted::find_refcounted_types,
+    result_types::{find_result_types, ResultReturnKind},
+    shared_ptrs::{
+        find_shared_from_this_types, find_shared_ptr_upcasts, shared_from_this_fn_name,
+        upcast_fn_name,
+    },
+    smart_pointers::find_smart_pointer_types,
+    stable_hash::stable_hash,
+    unique_arrays::find_unique_array_returns,
+    vectors::{find_vector_element_types, vector_get_copy_fn_name, vector_push_fn_name},
     ConvertError,
 };
 
@@ -43,6 +66,8 @@ enum Header {
     CxxH,
     CxxgenH,
     NewDeletePrelude,
+    InitializerListPrelude,
+    DynamicLibraryPrelude,
 }
 
 impl Header {
@@ -62,6 +87,10 @@ impl Header {
                 format!("#include \"{}cxxgen.h\"", prefix)
             }
             Header::NewDeletePrelude => new_and_delete_prelude::NEW_AND_DELETE_PRELUDE.to_string(),
+            Header::InitializerListPrelude => initializer_list_prelude::initializer_list_prelude(),
+            Header::DynamicLibraryPrelude => {
+                dynamic_library_prelude::DYNAMIC_LIBRARY_PRELUDE.to_string()
+            }
         }
     }
 
@@ -96,11 +125,15 @@ pub(crate) struct CppCodeGenerator<'a> {
     original_name_map: CppNameMap,
     config: &'a IncludeCppConfig,
     cpp_codegen_options: &'a CppCodegenOptions<'a>,
+    /// The names of the shared wrappers we've generated so far; see
+    /// [`Self::generate_shared_wrapper`].
+    shared_wrappers: HashSet<String>,
 }
 
 struct SubclassFunction<'a> {
     fun: &'a CppFunction,
     is_pure_virtual: bool,
+    is_virtual: bool,
 }
 
 impl<'a> CppCodeGenerator<'a> {
@@ -109,7 +142,7 @@ impl<'a> CppCodeGenerator<'a> {
         apis: &ApiVec<FnPhase>,
         config: &'a IncludeCppConfig,
         cpp_codegen_options: &CppCodegenOptions,
-    ) -> Result<Option<CppFilePair>, ConvertError> {
+    ) -> Result<Vec<CppFilePair>, ConvertError> {
         let mut gen = CppCodeGenerator::new(
             inclusions,
             original_name_map_from_apis(apis),
@@ -119,6 +152,18 @@ impl<'a> CppCodeGenerator<'a> {
         // The 'filter' on the following line is designed to ensure we don't accidentally
         // end up out of sync with needs_cpp_codegen
         gen.add_needs(apis.iter().filter(|api| api.needs_cpp_codegen()))?;
+        gen.generate_qt_signals(apis)?;
+        gen.generate_result_shims(apis)?;
+        gen.generate_smart_pointer_shims(apis)?;
+        gen.generate_unique_array_shims(apis)?;
+        gen.generate_refcounted_shims(apis);
+        gen.generate_last_error_shims(apis);
+        gen.generate_shared_ptr_shims(apis);
+        gen.generate_vector_shims(apis);
+        gen.generate_dense_matrices(apis);
+        gen.generate_iterator_shims(apis);
+        gen.generate_member_pointer_shims(apis)?;
+        gen.generate_abi_self_test_shims(apis);
         Ok(gen.generate())
     }
 
@@ -134,6 +179,7 @@ impl<'a> CppCodeGenerator<'a> {
             original_name_map,
             config,
             cpp_codegen_options,
+            shared_wrappers: HashSet::new(),
         }
     }
 
@@ -149,8 +195,9 @@ impl<'a> CppCodeGenerator<'a> {
             match &api {
                 Api::StringConstructor { .. } => self.generate_string_constructor(),
                 Api::Function {
+                    name,
                     analysis:
-                        FnAnalysis {
+                        analysis @ FnAnalysis {
                             cpp_wrapper: Some(cpp_wrapper),
                             ignore_reason: Ok(_),
                             externally_callable: true,
@@ -165,12 +212,24 @@ impl<'a> CppCodeGenerator<'a> {
                             .or_default()
                             .push(&details.cpp_impl);
                     }
-                    self.generate_cpp_function(cpp_wrapper)?
+                    let rust_caller = self
+                        .cpp_codegen_options
+                        .coverage
+                        .then(|| self.rust_caller_name(&name.name, analysis));
+                    self.generate_cpp_function(cpp_wrapper, rust_caller)?
                 }
-                Api::ConcreteType { rs_definition, .. } => self.generate_typedef(
+                Api::ConcreteType {
+                    rs_definition: Some(rs_definition),
+                    ..
+                } => self.generate_typedef(
                     api.name(),
                     type_to_cpp(rs_definition, &self.original_name_map)?,
                 ),
+                Api::ConcreteType {
+                    rs_definition: None,
+                    cpp_definition,
+                    ..
+                } => self.generate_type_alias(api.name(), cpp_definition),
                 Api::CType { typename, .. } => self.generate_ctype_typedef(typename),
                 Api::Subclass { .. } => deferred_apis.push(api),
                 Api::RustSubclassFn {
@@ -182,10 +241,12 @@ impl<'a> CppCodeGenerator<'a> {
                         .push(SubclassFunction {
                             fun: &details.cpp_impl,
                             is_pure_virtual: details.is_pure_virtual,
+                            is_virtual: details.is_virtual,
                         });
                 }
                 Api::Struct {
                     name,
+                    details,
                     analysis:
                         PodAndDepAnalysis {
                             pod:
@@ -198,6 +259,13 @@ impl<'a> CppCodeGenerator<'a> {
                     ..
                 } => {
                     self.generate_pod_assertion(name.qualified_cpp_name());
+                    if let Some(layout) = &details.layout {
+                        self.generate_pod_layout_assertion(
+                            name.qualified_cpp_name(),
+                            layout,
+                            &details.field_offsets,
+                        );
+                    }
                 }
                 _ => panic!("Should have filtered on needs_cpp_codegen"),
             }
@@ -217,9 +285,15 @@ impl<'a> CppCodeGenerator<'a> {
         Ok(())
     }
 
-    fn generate(&self) -> Option<CppFilePair> {
-        if self.additional_functions.is_empty() {
-            None
+    fn generate(&self) -> Vec<CppFilePair> {
+        let has_wrapped_inclusions = self
+            .config
+            .inclusions
+            .iter()
+            .any(|inc| self.cpp_codegen_options.header_clang_args.contains_key(inc));
+        let has_module_imports = !self.cpp_codegen_options.module_imports.is_empty();
+        if self.additional_functions.is_empty() && !has_wrapped_inclusions && !has_module_imports {
+            Vec::new()
         } else {
             let headers = self.collect_headers(|additional_need| &additional_need.headers);
             let cpp_headers = self.collect_headers(|additional_need| &additional_need.cpp_headers);
@@ -234,27 +308,66 @@ impl<'a> CppCodeGenerator<'a> {
                 .cpp_codegen_options
                 .header_namer
                 .name_header(self.config.get_mod_name().to_string());
-            let implementation = if self
-                .additional_functions
-                .iter()
-                .any(|x| x.definition.is_some())
-            {
-                let definitions = self.concat_additional_items(|x| x.definition.as_ref());
+            let mut shards = self.shard_definitions().into_iter();
+            let make_implementation = |definitions: Vec<&String>| {
                 let definitions = format!(
-                    "#include \"{}\"\n{}\n{}",
-                    header_name, cpp_headers, definitions
+                    "#include \"{}\"\n{}\n{}\n",
+                    header_name,
+                    cpp_headers,
+                    definitions.into_iter().join("\n")
                 );
                 log::info!("Additional C++ defs:\n{}", definitions);
-                Some(definitions.into_bytes())
-            } else {
-                None
+                definitions.into_bytes()
             };
-            Some(CppFilePair {
+            let mut files = vec![CppFilePair {
                 header: declarations.into_bytes(),
-                implementation,
-                header_name,
-            })
+                implementation: shards.next().map(&make_implementation),
+                header_name: header_name.clone(),
+            }];
+            // Each further shard needs a header too, if only so that the
+            // build system has something to name it by.
+            for (idx, shard) in shards.enumerate() {
+                let shard_header_name = self.cpp_codegen_options.header_namer.name_header(format!(
+                    "{}_shard{}",
+                    self.config.get_mod_name(),
+                    idx + 1
+                ));
+                files.push(CppFilePair {
+                    header: format!("#include \"{}\"\n", header_name).into_bytes(),
+                    implementation: Some(make_implementation(shard)),
+                    header_name: shard_header_name,
+                });
+            }
+            files
+        }
+    }
+
+    /// Split the out-of-line definitions between the requested number of
+    /// implementation files, so that they can be compiled in parallel. Each
+    /// definition goes in whichever file is smallest so far, which keeps
+    /// the files of similar sizes. Returns nothing if there are no
+    /// definitions, and never returns an empty file.
+    fn shard_definitions(&self) -> Vec<Vec<&String>> {
+        let definitions: Vec<_> = self
+            .additional_functions
+            .iter()
+            .filter_map(|x| x.definition.as_ref())
+            .collect();
+        let shard_count = self
+            .cpp_codegen_options
+            .cpp_shards
+            .clamp(1, definitions.len().max(1));
+        let mut shards: Vec<(usize, Vec<&String>)> = vec![(0, Vec::new()); shard_count];
+        for definition in definitions {
+            let smallest = shards.iter_mut().min_by_key(|(size, _)| *size).unwrap();
+            smallest.0 += definition.len();
+            smallest.1.push(definition);
         }
+        shards
+            .into_iter()
+            .map(|(_, shard)| shard)
+            .filter(|shard| !shard.is_empty())
+            .collect()
     }
 
     fn collect_headers<F>(&self, filter: F) -> String
@@ -270,6 +383,7 @@ impl<'a> CppCodeGenerator<'a> {
         cpp_headers
             .iter()
             .map(|x| x.include_stmt(self.cpp_codegen_options))
+            .sorted()
             .join("\n")
     }
 
@@ -304,6 +418,41 @@ impl<'a> CppCodeGenerator<'a> {
         })
     }
 
+    fn generate_pod_layout_assertion(
+        &mut self,
+        name: String,
+        layout: &Layout,
+        field_offsets: &[(Ident, usize)],
+    ) {
+        // The Rust definition of a POD type is generated from bindgen's view
+        // of its layout. If the C++ compiler used for the real build disagrees
+        // (e.g. due to differing #defines or packing flags), Rust code would
+        // silently corrupt these objects, so check it at build time. These
+        // go in the .cc file rather than the header, so they're checked once.
+        let definition = std::iter::once(format!(
+            "static_assert(sizeof({}) == {}, \"autocxx: size of {} differs from that seen by bindgen\");\nstatic_assert(alignof({}) == {}, \"autocxx: alignment of {} differs from that seen by bindgen\");",
+            name, layout.size, name, name, layout.align, name
+        ))
+        .chain(field_offsets.iter().map(|(field, offset)| {
+            format!(
+                "static_assert(offsetof({}, {}) == {}, \"autocxx: offset of {}::{} differs from that seen by bindgen\");",
+                name, field, offset, name, field
+            )
+        }))
+        .join("\n");
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: None,
+            declaration: None,
+            definition: Some(definition),
+            headers: Vec::new(),
+            cpp_headers: if field_offsets.is_empty() {
+                Vec::new()
+            } else {
+                vec![Header::System("cstddef")]
+            },
+        })
+    }
+
     fn generate_string_constructor(&mut self) {
         let makestring_name = self.config.get_makestring_name();
         let declaration = Some(format!("inline std::unique_ptr<std::string> {}(::rust::Str str) {{ return std::make_unique<std::string>(std::string(str)); }}", makestring_name));
@@ -320,16 +469,718 @@ impl<'a> CppCodeGenerator<'a> {
         })
     }
 
-    fn generate_cpp_function(&mut self, details: &CppFunction) -> Result<(), ConvertError> {
-        self.additional_functions
-            .push(self.generate_cpp_function_inner(
-                details,
+    /// Generates the means to connect a `rust::Fn` to each signal named in a
+    /// `qt_signal!` directive. The signal's arguments are passed to Rust as
+    /// an array of pointers, whose first element is unused so that it's
+    /// never empty.
+    fn generate_qt_signals(&mut self, apis: &ApiVec<FnPhase>) -> Result<(), ConvertError> {
+        let signals: Vec<_> = find_qt_signals(apis, self.config)
+            .into_iter()
+            .flatten()
+            .collect();
+        if signals.is_empty() {
+            return Ok(());
+        }
+        let disconnect_fn_name = qt_disconnect_fn_name();
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: None,
+            declaration: Some(formatdoc! {"
+                template <typename Sender, typename Class, typename... Args>
+                size_t autocxx_qt_connect(Sender& sender, void (Class::*signal)(Args...), ::rust::Fn<void(size_t, size_t)> handler, size_t context) {{
+                    auto connection = QObject::connect(&sender, signal, [handler, context](Args... args) {{
+                        const void* argv[] = {{ nullptr, static_cast<const void*>(&args)... }};
+                        handler(context, reinterpret_cast<size_t>(argv));
+                    }});
+                    return reinterpret_cast<size_t>(new QMetaObject::Connection(connection));
+                }}
+                inline void {}(size_t connection) {{
+                    auto c = reinterpret_cast<QMetaObject::Connection*>(connection);
+                    QObject::disconnect(*c);
+                    delete c;
+                }}
+            ", disconnect_fn_name}),
+            definition: None,
+            headers: vec![Header::System("cstddef"), Header::CxxH],
+            cpp_headers: Vec::new(),
+        });
+        for signal in signals {
+            let class =
+                namespaced_name_using_original_name_map(signal.class, &self.original_name_map);
+            let param_types = signal
+                .param_types
+                .iter()
+                .map(|ty| type_to_cpp(ty, &self.original_name_map))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(", ");
+            let declaration = format!(
+                "inline size_t {}({}& sender, ::rust::Fn<void(size_t, size_t)> handler, size_t context) {{ return autocxx_qt_connect(sender, static_cast<void ({}::*)({})>(&{}::{}), handler, context); }}",
+                signal.connect_fn_name, class, class, param_types, class, signal.cpp_name
+            );
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: vec![Header::System("cstddef"), Header::CxxH],
+                cpp_headers: Vec::new(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Generates the shims which the Rust wrapper for each function returning
+    /// `absl::Status`, `absl::StatusOr<T>` or a `result_type!` uses to
+    /// convert it into a `Result`.
+    fn generate_result_shims(&mut self, apis: &ApiVec<FnPhase>) -> Result<(), ConvertError> {
+        for result_return in find_result_types(apis) {
+            let ty = namespaced_name_using_original_name_map(
+                &result_return.result_type,
+                &self.original_name_map,
+            );
+            let (has_value, value_accessor) = match &result_return.kind {
+                ResultReturnKind::Abseil => ("ok", "value"),
+                ResultReturnKind::Custom { value_accessor, .. } => {
+                    ("has_value", value_accessor.as_str())
+                }
+            };
+            let mut declaration = format!(
+                "inline bool {}(const {}& result) {{ return result.{}(); }}\n",
+                result_return.ok_fn_name(),
+                ty,
+                has_value
+            );
+            if let Some(value) = &result_return.value {
+                declaration.push_str(&self.generate_result_accessor(
+                    &result_return.value_fn_name(),
+                    &ty,
+                    value,
+                    value_accessor,
+                )?);
+            }
+            match &result_return.kind {
+                ResultReturnKind::Abseil => {
+                    let status = if result_return.value.is_some() {
+                        "result.status()"
+                    } else {
+                        "result"
+                    };
+                    declaration.push_str(&formatdoc! {"
+                        inline int32_t {code}(const {ty}& result) {{ return static_cast<int32_t>({status}.code()); }}
+                        inline rust::String {message}(const {ty}& result) {{ return rust::String(std::string({status}.message())); }}
+                        ",
+                        code = result_return.code_fn_name(),
+                        message = result_return.message_fn_name(),
+                        ty = ty,
+                        status = status,
+                    });
+                }
+                ResultReturnKind::Custom {
+                    error,
+                    error_accessor,
+                    ..
+                } => {
+                    declaration.push_str(&self.generate_result_accessor(
+                        &result_return.error_fn_name(),
+                        &ty,
+                        error,
+                        error_accessor,
+                    )?);
+                }
+            }
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: vec![
+                    Header::System("cstdint"),
+                    Header::System("memory"),
+                    Header::System("string"),
+                    Header::CxxH,
+                ],
+                cpp_headers: Vec::new(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Generates the shims which an `autocxx::SmartPtr` uses to dereference
+    /// and copy each `smart_pointer!` type returned by some function.
+    fn generate_smart_pointer_shims(&mut self, apis: &ApiVec<FnPhase>) -> Result<(), ConvertError> {
+        for smart_pointer in find_smart_pointer_types(apis) {
+            let ty = namespaced_name_using_original_name_map(
+                &smart_pointer.ptr_name,
+                &self.original_name_map,
+            );
+            let declaration = formatdoc! {"
+                inline const {target}* {get}(const {ty}& ptr) {{ return ptr.{deref}(); }}
+                inline std::unique_ptr<{ty}> {clone}(const {ty}& ptr) {{ return std::make_unique<{ty}>(ptr); }}
+                ",
+                target = type_to_cpp(&smart_pointer.target, &self.original_name_map)?,
+                get = smart_pointer.get_fn_name(),
+                deref = smart_pointer.deref_accessor,
+                clone = smart_pointer.clone_fn_name(),
+                ty = ty,
+            };
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: vec![Header::System("memory")],
+                cpp_headers: Vec::new(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Generates the shims which an `autocxx::RcHandle` uses to add and
+    /// release references to each `refcounted!` class.
+    fn generate_refcounted_shims(&mut self, apis: &ApiVec<FnPhase>) {
+        for refcounted in find_refcounted_types(apis) {
+            let ty =
+                namespaced_name_using_original_name_map(&refcounted.name, &self.original_name_map);
+            let declaration = formatdoc! {"
+                inline void {addref_shim}({ty}* obj) {{ {addref}(obj); }}
+                inline void {release_shim}({ty}* obj) {{ {release}(obj); }}
+                ",
+                addref_shim = refcounted.addref_fn_name(),
+                release_shim = refcounted.release_fn_name(),
+                addref = refcounted.addref,
+                release = refcounted.release,
+                ty = ty,
+            };
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: Vec::new(),
+                cpp_headers: Vec::new(),
+            });
+        }
+    }
+
+    /// Generates the thread-local slot in which the error from each
+    /// `last_error!` fetcher is captured, and the shim through which the
+    /// Rust wrapper takes it.
+    fn generate_last_error_shims(&mut self, apis: &ApiVec<FnPhase>) {
+        for last_error in find_last_error_fetchers(apis) {
+            let fetch = last_error.fetch_fn.to_cpp_name();
+            let slot = last_error.slot_fn_name();
+            let type_definition = formatdoc! {"
+                struct {slot}_t {{ bool failed; decltype({fetch}()) error; }};
+                inline {slot}_t& {slot}() {{ static thread_local {slot}_t slot; return slot; }}
+                ",
+                slot = slot,
+                fetch = fetch,
+            };
+            let declaration = format!(
+                "inline bool {take}(decltype({fetch}())* error) {{ auto& slot = {slot}(); if (slot.failed) {{ *error = slot.error; }} return slot.failed; }}",
+                take = last_error.take_fn_name(),
+                fetch = fetch,
+                slot = slot,
+            );
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: Some(type_definition),
+                declaration: Some(declaration),
+                definition: None,
+                headers: Vec::new(),
+                cpp_headers: Vec::new(),
+            });
+        }
+    }
+
+    /// Makes a call to a function named in a `last_error!` directive,
+    /// fetching its error into the thread-local slot straight away if it
+    /// failed, before anything else can overwrite it.
+    fn capture_last_error(last_error: &LastError, call: &str) -> String {
+        format!(
+            "decltype(auto) autocxx_result = {call}; auto& autocxx_slot = {slot}(); autocxx_slot.failed = autocxx_result == ({failure}); if (autocxx_slot.failed) {{ autocxx_slot.error = {fetch}(); }} return autocxx_result",
+            call = call,
+            slot = last_error.slot_fn_name(),
+            failure = last_error.failure,
+            fetch = last_error.fetch_fn.to_cpp_name(),
+        )
+    }
+
+    /// Generates the shims which an `autocxx::UniqueArray` uses to free the
+    /// array returned by each function returning a `std::unique_ptr<T[]>`.
+    fn generate_unique_array_shims(&mut self, apis: &ApiVec<FnPhase>) -> Result<(), ConvertError> {
+        for unique_array in find_unique_array_returns(apis) {
+            let declaration = format!(
+                "inline void {}({}* ptr) {{ delete[] ptr; }}",
+                unique_array.delete_fn_name,
+                type_to_cpp(&unique_array.elem, &self.original_name_map)?
+            );
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: Vec::new(),
+                cpp_headers: Vec::new(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Generates the shims which call `shared_from_this` on each class
+    /// deriving from `std::enable_shared_from_this`, and which upcast a
+    /// `std::shared_ptr` of each class to one of each of its bases.
+    /// `shared_from_this` may return a pointer to const, but Rust can only
+    /// get a shared reference from a `cxx::SharedPtr` anyway.
+    fn generate_shared_ptr_shims(&mut self, apis: &ApiVec<FnPhase>) {
+        let mut declarations = Vec::new();
+        for ty in find_shared_from_this_types(apis, self.config) {
+            declarations.push(formatdoc! {"
+                inline std::shared_ptr<{ty}> {name}(const {ty}& obj) {{
+                    return std::const_pointer_cast<{ty}>(obj.shared_from_this());
+                }}
+                ",
+                ty = self.namespaced_name(ty),
+                name = shared_from_this_fn_name(ty),
+            });
+        }
+        for (derived, base) in find_shared_ptr_upcasts(apis, self.config) {
+            declarations.push(formatdoc! {"
+                inline std::shared_ptr<{base}> {name}(const std::shared_ptr<{derived}>& ptr) {{
+                    return ptr;
+                }}
+                ",
+                base = self.namespaced_name(base),
+                derived = self.namespaced_name(derived),
+                name = upcast_fn_name(derived, base),
+            });
+        }
+        for declaration in declarations {
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: vec![Header::System("memory")],
+                cpp_headers: Vec::new(),
+            });
+        }
+    }
+
+    /// Generates the shims which copy elements out of, and move them into,
+    /// each `std::vector` of a non-POD class.
+    fn generate_vector_shims(&mut self, apis: &ApiVec<FnPhase>) {
+        for ty in find_vector_element_types(apis, self.config) {
+            let declaration = formatdoc! {"
+                inline std::unique_ptr<{ty}> {get_copy}(const std::vector<{ty}>& vec, size_t pos) {{
+                    if (pos >= vec.size()) {{
+                        return nullptr;
+                    }}
+                    return std::make_unique<{ty}>(vec[pos]);
+                }}
+                inline void {push}(std::vector<{ty}>& vec, std::unique_ptr<{ty}> value) {{
+                    vec.push_back(std::move(*value));
+                }}
+                ",
+                ty = self.namespaced_name(ty),
+                get_copy = vector_get_copy_fn_name(ty),
+                push = vector_push_fn_name(ty),
+            };
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: vec![
+                    Header::System("cstddef"),
+                    Header::System("memory"),
+                    Header::System("utility"),
+                    Header::System("vector"),
+                ],
+                cpp_headers: Vec::new(),
+            });
+        }
+    }
+
+    /// Generates the shims which convert each type named in a
+    /// `dense_matrix!` directive to and from slices. These rely only on the
+    /// `Scalar` typedef and `resize`, `data`, `size`, `rows` and `cols`
+    /// methods which Eigen's dense types provide.
+    fn generate_dense_matrices(&mut self, apis: &ApiVec<FnPhase>) {
+        for matrix in find_dense_matrices(apis, self.config) {
+            let declaration = formatdoc! {"
+                inline std::unique_ptr<{ty}> {from_slice}(rust::Slice<const {ty}::Scalar> data, size_t rows, size_t cols) {{
+                    auto matrix = std::make_unique<{ty}>();
+                    matrix->resize(rows, cols);
+                    if (static_cast<size_t>(matrix->rows()) != rows || static_cast<size_t>(matrix->cols()) != cols) {{
+                        return nullptr;
+                    }}
+                    std::copy(data.begin(), data.end(), matrix->data());
+                    return matrix;
+                }}
+                inline rust::Slice<const {ty}::Scalar> {data}(const {ty}& matrix) {{ return rust::Slice<const {ty}::Scalar>(matrix.data(), static_cast<size_t>(matrix.size())); }}
+                inline rust::Slice<{ty}::Scalar> {data_mut}({ty}& matrix) {{ return rust::Slice<{ty}::Scalar>(matrix.data(), static_cast<size_t>(matrix.size())); }}
+                inline size_t {rows}(const {ty}& matrix) {{ return static_cast<size_t>(matrix.rows()); }}
+                inline size_t {cols}(const {ty}& matrix) {{ return static_cast<size_t>(matrix.cols()); }}
+                ",
+                ty = matrix.cpp_name,
+                from_slice = matrix.from_slice_fn_name(),
+                data = matrix.data_fn_name(),
+                data_mut = matrix.data_mut_fn_name(),
+                rows = matrix.rows_fn_name(),
+                cols = matrix.cols_fn_name(),
+            };
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: vec![
+                    Header::System("algorithm"),
+                    Header::System("cstddef"),
+                    Header::System("memory"),
+                    Header::CxxH,
+                ],
+                cpp_headers: Vec::new(),
+            });
+        }
+    }
+
+    /// Generates the shims which iterate over each type with `begin()` and
+    /// `end()` methods. The struct holding the iterators is allocated on
+    /// the heap, so that Rust needn't know its size.
+    fn generate_iterator_shims(&mut self, apis: &ApiVec<FnPhase>) {
+        for iterable in find_iterable_types(apis) {
+            let ty =
+                namespaced_name_using_original_name_map(iterable.name, &self.original_name_map);
+            let iter_ty = iterable.iterator_type_name();
+            let type_definition = formatdoc! {"
+                struct {iter_ty} {{
+                    decltype(std::declval<const {ty}&>().begin()) current;
+                    decltype(std::declval<const {ty}&>().end()) end;
+                }};",
+                iter_ty = iter_ty,
+                ty = ty,
+            };
+            let declaration = formatdoc! {"
+                inline std::unique_ptr<{iter_ty}> {begin}(const {ty}& obj) {{
+                    return std::unique_ptr<{iter_ty}>(new {iter_ty}{{obj.begin(), obj.end()}});
+                }}
+                inline decltype(std::declval<const {ty}&>().begin()) {next}({iter_ty}& iter) {{
+                    if (iter.current == iter.end) {{
+                        return nullptr;
+                    }}
+                    auto element = &*iter.current;
+                    ++iter.current;
+                    return element;
+                }}
+                ",
+                iter_ty = iter_ty,
+                ty = ty,
+                begin = iterable.begin_fn_name(),
+                next = iterable.next_fn_name(),
+            };
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: Some(type_definition),
+                declaration: Some(declaration),
+                definition: None,
+                headers: vec![Header::System("memory"), Header::System("utility")],
+                cpp_headers: Vec::new(),
+            });
+        }
+    }
+
+    /// Generates the shims which apply each pointer to a data member to an
+    /// object.
+    fn generate_member_pointer_shims(
+        &mut self,
+        apis: &ApiVec<FnPhase>,
+    ) -> Result<(), ConvertError> {
+        for member_pointer in find_data_member_pointers(apis) {
+            let class = namespaced_name_using_original_name_map(
+                &member_pointer.class,
+                &self.original_name_map,
+            );
+            let field = type_to_cpp(&member_pointer.field, &self.original_name_map)?;
+            let declaration = format!(
+                "inline const {field}* {apply}(const {token}& member, const {class}* obj) {{ return &(obj->*member); }}",
+                field = field,
+                apply = member_pointer.apply_fn_name(),
+                token = member_pointer.token.get_final_item(),
+                class = class,
+            );
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: Vec::new(),
+                cpp_headers: Vec::new(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Generates the shims which the tests requested by `abi_self_test!`
+    /// use to read each field of a POD type, to copy it and to find its
+    /// size.
+    fn generate_abi_self_test_shims(&mut self, apis: &ApiVec<FnPhase>) {
+        for tested in find_abi_tested_types(apis, self.config) {
+            let ty = namespaced_name_using_original_name_map(tested.name, &self.original_name_map);
+            let cases = tested
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(idx, field)| {
+                    format!(
+                        "case {}: return static_cast<double>(obj.{});",
+                        idx, field.cpp_name
+                    )
+                })
+                .join(" ");
+            let declaration = formatdoc! {"
+                inline double {field}(const {ty}& obj, uint32_t field) {{
+                    switch (field) {{ {cases} default: return -1.0; }}
+                }}
+                inline {ty} {roundtrip}(const {ty}& obj) {{ return obj; }}
+                inline size_t {size}() {{ return sizeof({ty}); }}
+                ",
+                field = tested.field_fn_name(),
+                roundtrip = tested.roundtrip_fn_name(),
+                size = tested.size_fn_name(),
+                cases = cases,
+                ty = ty,
+            };
+            self.additional_functions.push(AdditionalFunction {
+                type_definition: None,
+                declaration: Some(declaration),
+                definition: None,
+                headers: vec![Header::System("cstddef"), Header::System("cstdint")],
+                cpp_headers: Vec::new(),
+            });
+        }
+    }
+
+    /// Generates a shim which moves the value or error out of a result type
+    /// using the given accessor method.
+    fn generate_result_accessor(
+        &self,
+        fn_name: &Ident,
+        result_ty: &str,
+        payload: &TypeConversionPolicy,
+        accessor: &str,
+    ) -> Result<String, ConvertError> {
+        let payload_ty = type_to_cpp(&payload.unwrapped_type, &self.original_name_map)?;
+        let payload_val = format!("std::move(result).{}()", accessor);
+        let (ret_ty, ret_val) = match payload.cpp_conversion {
+            CppConversionType::FromValueToUniquePtr => (
+                format!("std::unique_ptr<{}>", payload_ty),
+                format!("std::make_unique<{}>({})", payload_ty, payload_val),
+            ),
+            _ => (payload_ty, payload_val),
+        };
+        Ok(format!(
+            "inline {} {}({}& result) {{ return {}; }}\n",
+            ret_ty, fn_name, result_ty, ret_val
+        ))
+    }
+
+    /// The path by which Rust code calls the function for which we're
+    /// generating a wrapper, e.g. `ffi::ns::Foo::bar`.
+    fn rust_caller_name(&self, name: &QualifiedName, analysis: &FnAnalysis) -> String {
+        let mod_name = self.config.get_mod_name();
+        let rust_name = &analysis.rust_name;
+        match &analysis.kind {
+            FnKind::Function => format!(
+                "{}::{}",
+                mod_name,
+                QualifiedName::new(name.get_namespace(), make_ident(rust_name))
+            ),
+            FnKind::Method { impl_for, .. } => {
+                format!("{}::{}::{}", mod_name, impl_for, rust_name)
+            }
+            FnKind::TraitMethod {
+                impl_for, details, ..
+            } => {
+                let trait_signature = &details.trt.trait_signature;
+                format!(
+                    "<{}::{} as {}>::{}",
+                    mod_name,
+                    impl_for,
+                    quote! { #trait_signature }.to_string().replace(' ', ""),
+                    details.method_name
+                )
+            }
+        }
+    }
+
+    fn generate_cpp_function(
+        &mut self,
+        details: &CppFunction,
+        rust_caller: Option<String>,
+    ) -> Result<(), ConvertError> {
+        // If the C++ is split between several files, the wrappers are
+        // defined out of line so that they can go in any of them.
+        let out_of_line = self.cpp_codegen_options.cpp_shards > 1;
+        let thunk;
+        let details = if self.can_share_wrapper(details) {
+            thunk = CppFunction {
+                payload: CppFunctionBody::CallSharedWrapper(
+                    self.generate_shared_wrapper(details)?,
+                    self.callee_lambda(details)?,
+                ),
+                ..details.clone()
+            };
+            &thunk
+        } else {
+            details
+        };
+        let mut additional_function = self.generate_cpp_function_inner(
+            details,
+            false,
+            ConversionDirection::RustCallsCpp,
+            out_of_line,
+            None,
+        )?;
+        // Coverage reports show the generated C++, so point out where it's
+        // called from.
+        if let Some(rust_caller) = rust_caller {
+            let comment = format!("// Called from Rust by {}\n", rust_caller);
+            for code in [
+                &mut additional_function.declaration,
+                &mut additional_function.definition,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                code.insert_str(0, &comment);
+            }
+        }
+        self.additional_functions.push(additional_function);
+        Ok(())
+    }
+
+    /// Whether a wrapper can call the original function through a wrapper
+    /// shared with others which have the same argument and return
+    /// conversions, such as many methods each taking a `std::string`. The
+    /// conversions are then generated just once, and each function's own
+    /// wrapper merely passes the shared one a lambda calling the original.
+    /// That's not done when building for coverage, so that each wrapper's
+    /// use is counted separately.
+    fn can_share_wrapper(&self, details: &CppFunction) -> bool {
+        !self.cpp_codegen_options.coverage
+            && matches!(
+                details.payload,
+                CppFunctionBody::FunctionCall(..) | CppFunctionBody::StaticMethodCall(..)
+            )
+            && matches!(
+                details.kind,
+                CppFunctionKind::Function | CppFunctionKind::Method
+            )
+            && !details.pass_obs_field
+            && details.qualification.is_none()
+            && details.calling_convention.is_none()
+            && details.last_error.is_none()
+            && details
+                .argument_conversion
+                .iter()
+                .chain(details.return_conversion.iter())
+                .all(|conv| {
+                    !matches!(
+                        conv.cpp_conversion,
+                        CppConversionType::FromSliceToInitializerList
+                            | CppConversionType::FromPtrToArray(_)
+                            | CppConversionType::FromArrayToPtr(_)
+                    )
+                })
+            && details
+                .argument_conversion
+                .iter()
+                .chain(details.return_conversion.iter())
+                .any(|conv| !matches!(conv.cpp_conversion, CppConversionType::None))
+    }
+
+    /// Generates, unless we already have, the wrapper which does the
+    /// conversions for `details` and calls whatever function it's passed,
+    /// returning its name. The name is derived from the wrapper's
+    /// contents, so where several `include_cpp!`s have the same one, they
+    /// share a single inline definition.
+    fn generate_shared_wrapper(&mut self, details: &CppFunction) -> Result<String, ConvertError> {
+        let shared = CppFunction {
+            payload: CppFunctionBody::CallCallee,
+            kind: CppFunctionKind::Function,
+            ..details.clone()
+        };
+        let unnamed = self.generate_cpp_function_inner(
+            &shared,
+            false,
+            ConversionDirection::RustCallsCpp,
+            false,
+            Some(""),
+        )?;
+        // Other include_cpp!s, perhaps built by a different autocxx binary,
+        // must pick the same name for the same wrapper, so we use a hash
+        // with a fixed algorithm.
+        let name = format!(
+            "autocxx_shared_wrapper_{:016x}",
+            stable_hash(unnamed.declaration.as_deref().unwrap_or_default())
+        );
+        if self.shared_wrappers.insert(name.clone()) {
+            let additional_function = self.generate_cpp_function_inner(
+                &shared,
                 false,
                 ConversionDirection::RustCallsCpp,
                 false,
-                None,
-            )?);
-        Ok(())
+                Some(&name),
+            )?;
+            self.additional_functions.push(additional_function);
+        }
+        Ok(name)
+    }
+
+    /// A captureless lambda which calls the original function with its
+    /// unconverted arguments, and so converts to the function pointer
+    /// taken by a shared wrapper. For a method, the receiver is the first
+    /// parameter.
+    fn callee_lambda(&self, details: &CppFunction) -> Result<String, ConvertError> {
+        let params: Result<Vec<_>, ConvertError> = details
+            .argument_conversion
+            .iter()
+            .enumerate()
+            .map(|(counter, conv)| {
+                Ok(format!(
+                    "{} arg{}",
+                    conv.converted_type(&self.original_name_map)?,
+                    counter
+                ))
+            })
+            .collect();
+        let ret_type = match &details.return_conversion {
+            Some(ret) => ret.unconverted_type(&self.original_name_map)?,
+            None => "void".to_string(),
+        };
+        let mut args = (0..details.argument_conversion.len())
+            .map(|counter| format!("std::forward<decltype(arg{0})>(arg{0})", counter));
+        let call = match &details.payload {
+            CppFunctionBody::FunctionCall(_, id)
+                if matches!(details.kind, CppFunctionKind::Method) =>
+            {
+                args.next();
+                format!("arg0.{}({})", id, args.join(", "))
+            }
+            CppFunctionBody::FunctionCall(ns, id) => format!(
+                "{}({})",
+                ns.cpp_iter()
+                    .map(str::to_string)
+                    .chain(std::iter::once(id.to_string()))
+                    .join("::"),
+                args.join(", ")
+            ),
+            CppFunctionBody::StaticMethodCall(ns, ty_id, fn_id) => format!(
+                "{}({})",
+                ns.cpp_iter()
+                    .map(str::to_string)
+                    .chain([ty_id.to_string(), fn_id.to_string()])
+                    .join("::"),
+                args.join(", ")
+            ),
+            _ => panic!("Shared wrappers only call functions and methods"),
+        };
+        Ok(format!(
+            "[]({}) -> {} {{ return {}; }}",
+            params?.join(", "),
+            ret_type,
+            call
+        ))
     }
 
     fn generate_cpp_function_inner(
@@ -337,7 +1188,7 @@ impl<'a> CppCodeGenerator<'a> {
         details: &CppFunction,
         avoid_this: bool,
         conversion_direction: ConversionDirection,
-        requires_rust_declarations: bool,
+        out_of_line: bool,
         force_name: Option<&str>,
     ) -> Result<AdditionalFunction, ConvertError> {
         // Even if the original function call is in a namespace,
@@ -391,7 +1242,11 @@ impl<'a> CppCodeGenerator<'a> {
                 ))
             })
             .collect();
-        let args = args?.join(", ");
+        let mut args = args?;
+        if matches!(details.payload, CppFunctionBody::CallCallee) {
+            args.insert(0, self.function_pointer_type(details, "autocxx_callee")?);
+        }
+        let args = args.join(", ");
         let default_return = match details.kind {
             CppFunctionKind::SynthesizedConstructor => "",
             _ => "void",
@@ -411,21 +1266,34 @@ impl<'a> CppCodeGenerator<'a> {
             CppFunctionKind::ConstMethod => " const",
             _ => "",
         };
-        let declaration = format!("{} {}({}){}", ret_type, name, args, constness);
+        let calling_convention = details
+            .calling_convention
+            .map(|cc| format!("{} ", cc.cpp_keyword()))
+            .unwrap_or_default();
+        let declaration = format!(
+            "{} {}{}({}){}",
+            ret_type, calling_convention, name, args, constness
+        );
         let qualification = if let Some(qualification) = &details.qualification {
             format!("{}::", qualification.to_cpp_name())
         } else {
             "".to_string()
         };
         let qualified_declaration = format!(
-            "{} {}{}({}){}",
-            ret_type, qualification, name, args, constness
+            "{} {}{}{}({}){}",
+            ret_type, calling_convention, qualification, name, args, constness
         );
         let arg_list: Result<Vec<_>, _> = details
             .argument_conversion
             .iter()
             .enumerate()
             .map(|(counter, conv)| match conversion_direction {
+                // The shared wrapper does the conversions.
+                ConversionDirection::RustCallsCpp
+                    if matches!(details.payload, CppFunctionBody::CallSharedWrapper(..)) =>
+                {
+                    Ok(get_arg_name(counter))
+                }
                 ConversionDirection::RustCallsCpp => {
                     conv.cpp_conversion(&get_arg_name(counter), &self.original_name_map, false)
                 }
@@ -477,8 +1345,8 @@ impl<'a> CppCodeGenerator<'a> {
                 ),
                 None => {
                     let underlying_function_call = ns
-                        .into_iter()
-                        .cloned()
+                        .cpp_iter()
+                        .map(str::to_string)
                         .chain(std::iter::once(id.to_string()))
                         .join("::");
                     (
@@ -488,10 +1356,15 @@ impl<'a> CppCodeGenerator<'a> {
                     )
                 }
             },
+            CppFunctionBody::AssignmentOperator => (
+                format!("{} = {}", receiver.unwrap(), arg_list),
+                "".to_string(),
+                false,
+            ),
             CppFunctionBody::StaticMethodCall(ns, ty_id, fn_id) => {
                 let underlying_function_call = ns
-                    .into_iter()
-                    .cloned()
+                    .cpp_iter()
+                    .map(str::to_string)
                     .chain([ty_id.to_string(), fn_id.to_string()].iter().cloned())
                     .join("::");
                 (
@@ -509,30 +1382,108 @@ impl<'a> CppCodeGenerator<'a> {
                     true,
                 )
             }
+            CppFunctionBody::AddressOfGlobal(name) => {
+                (format!("&{}", name.to_cpp_name()), "".to_string(), false)
+            }
+            CppFunctionBody::FieldGetter(field) => (
+                format!("{}.{}", receiver.unwrap(), field),
+                "".to_string(),
+                false,
+            ),
+            CppFunctionBody::FieldSetter(field) => (
+                format!("{}.{} = {}", receiver.unwrap(), field, arg_list),
+                "".to_string(),
+                false,
+            ),
             CppFunctionBody::FreeUninitialized(ty) => (
                 format!("delete_appropriately<{}>(arg0);", self.namespaced_name(ty)),
                 "".to_string(),
                 true,
             ),
+            CppFunctionBody::DynamicCall(symbol) => {
+                // The lookup happens on the first call, within the
+                // initializer of a function-local static.
+                let all_args = receiver
+                    .into_iter()
+                    .chain(std::iter::once(arg_list))
+                    .filter(|arg| !arg.is_empty())
+                    .join(", ");
+                (
+                    format!(
+                        "([] {{ static const auto autocxx_fn = reinterpret_cast<{}>(autocxx_dl_resolve({:?}, {:?})); return autocxx_fn; }}())({})",
+                        self.function_pointer_type(details, "")?,
+                        self.config.dynamic_library().unwrap_or_default(),
+                        symbol,
+                        all_args
+                    ),
+                    "".to_string(),
+                    false,
+                )
+            }
+            CppFunctionBody::CallCallee => (
+                format!("autocxx_callee({})", arg_list),
+                "".to_string(),
+                false,
+            ),
+            CppFunctionBody::CallSharedWrapper(shared, callee) => {
+                let all_args = std::iter::once(callee.clone())
+                    .chain(receiver)
+                    .chain(std::iter::once(arg_list))
+                    .filter(|arg| !arg.is_empty())
+                    .join(", ");
+                (format!("{}({})", shared, all_args), "".to_string(), false)
+            }
         };
         if let Some(ret) = &details.return_conversion {
-            underlying_function_call = format!(
-                "return {}",
-                match conversion_direction {
-                    ConversionDirection::RustCallsCpp => ret.cpp_conversion(
-                        &underlying_function_call,
-                        &self.original_name_map,
-                        true
-                    )?,
-                    ConversionDirection::CppCallsCpp => underlying_function_call,
-                    ConversionDirection::CppCallsRust => ret.inverse().cpp_conversion(
-                        &underlying_function_call,
-                        &self.original_name_map,
-                        true
-                    )?,
+            let converted_call = match conversion_direction {
+                ConversionDirection::RustCallsCpp
+                    if matches!(details.payload, CppFunctionBody::CallSharedWrapper(..)) =>
+                {
+                    underlying_function_call
                 }
-            );
+                ConversionDirection::RustCallsCpp => {
+                    ret.cpp_conversion(&underlying_function_call, &self.original_name_map, true)?
+                }
+                ConversionDirection::CppCallsCpp => underlying_function_call,
+                ConversionDirection::CppCallsRust => ret.inverse().cpp_conversion(
+                    &underlying_function_call,
+                    &self.original_name_map,
+                    true,
+                )?,
+            };
+            underlying_function_call = match (&details.last_error, &conversion_direction) {
+                (Some(last_error), ConversionDirection::RustCallsCpp) => {
+                    Self::capture_last_error(last_error, &converted_call)
+                }
+                _ => format!("return {}", converted_call),
+            };
         };
+        // A std::initializer_list can only be built from a braced list of
+        // elements, so we make the call from within a helper which builds one
+        // from each slice.
+        let mut need_initializer_lists = false;
+        if matches!(conversion_direction, ConversionDirection::RustCallsCpp) {
+            for (counter, conv) in details.argument_conversion.iter().enumerate() {
+                if matches!(
+                    conv.cpp_conversion,
+                    CppConversionType::FromSliceToInitializerList
+                ) {
+                    let arg_name = get_arg_name(counter);
+                    underlying_function_call = format!(
+                        "{}autocxx_with_initializer_list({arg}.data(), {arg}.size(), [&]({} {arg}_list) -> decltype(auto) {{ {}; }})",
+                        if details.return_conversion.is_some() {
+                            "return "
+                        } else {
+                            ""
+                        },
+                        conv.initializer_list_type(&self.original_name_map)?,
+                        underlying_function_call,
+                        arg = arg_name
+                    );
+                    need_initializer_lists = true;
+                }
+            }
+        }
         if !underlying_function_call.is_empty() {
             underlying_function_call = format!("{};", underlying_function_call);
         }
@@ -549,7 +1500,7 @@ impl<'a> CppCodeGenerator<'a> {
             };
         let definition_after_sig =
             format!("{} {{ {} }}", field_assignments, underlying_function_call,);
-        let (declaration, definition) = if requires_rust_declarations {
+        let (declaration, definition) = if out_of_line {
             (
                 Some(format!("{};", declaration)),
                 Some(format!(
@@ -567,6 +1518,23 @@ impl<'a> CppCodeGenerator<'a> {
         if need_allocators {
             headers.push(Header::NewDeletePrelude);
         }
+        if need_initializer_lists {
+            headers.push(Header::InitializerListPrelude);
+        }
+        if matches!(details.payload, CppFunctionBody::DynamicCall(_)) {
+            headers.push(Header::DynamicLibraryPrelude);
+        }
+        if matches!(details.payload, CppFunctionBody::CallSharedWrapper(..)) {
+            headers.push(Header::System("utility"));
+        }
+        if details.argument_conversion.iter().any(|conv| {
+            matches!(
+                conv.cpp_conversion,
+                CppConversionType::FromPtrToArray(_) | CppConversionType::FromArrayToPtr(_)
+            )
+        }) {
+            headers.push(Header::System("type_traits"));
+        }
         Ok(AdditionalFunction {
             type_definition: None,
             declaration,
@@ -576,6 +1544,26 @@ impl<'a> CppCodeGenerator<'a> {
         })
     }
 
+    /// The type of a pointer to the original function which a wrapper
+    /// calls, declaring `name` if it's not empty. For a method, `this`
+    /// becomes the first parameter.
+    fn function_pointer_type(
+        &self,
+        details: &CppFunction,
+        name: &str,
+    ) -> Result<String, ConvertError> {
+        let ret_type = match &details.return_conversion {
+            Some(ret) => ret.unconverted_type(&self.original_name_map)?,
+            None => "void".to_string(),
+        };
+        let params: Result<Vec<_>, _> = details
+            .argument_conversion
+            .iter()
+            .map(|conv| conv.converted_type(&self.original_name_map))
+            .collect();
+        Ok(format!("{}(*{})({})", ret_type, name, params?.join(", ")))
+    }
+
     fn namespaced_name(&self, name: &QualifiedName) -> String {
         namespaced_name_using_original_name_map(name, &self.original_name_map)
     }
@@ -596,6 +1584,19 @@ impl<'a> CppCodeGenerator<'a> {
         })
     }
 
+    /// Like [`Self::generate_typedef`], but `using` works for any type,
+    /// including member function pointers, whose name would otherwise have
+    /// to go in the middle of the definition.
+    fn generate_type_alias(&mut self, tn: &QualifiedName, definition: &str) {
+        self.additional_functions.push(AdditionalFunction {
+            type_definition: Some(format!("using {} = {};", tn.get_final_item(), definition)),
+            declaration: None,
+            definition: None,
+            headers: Vec::new(),
+            cpp_headers: Vec::new(),
+        })
+    }
+
     fn generate_subclass(
         &mut self,
         superclass: &QualifiedName,
@@ -613,16 +1614,19 @@ impl<'a> CppCodeGenerator<'a> {
         });
         let mut method_decls = Vec::new();
         for method in methods {
-            // First the method which calls from C++ to Rust
-            let mut fn_impl = self.generate_cpp_function_inner(
-                method.fun,
-                true,
-                ConversionDirection::CppCallsRust,
-                true,
-                Some(&method.fun.original_cpp_name),
-            )?;
-            method_decls.push(fn_impl.declaration.take().unwrap());
-            self.additional_functions.push(fn_impl);
+            // First the method which calls from C++ to Rust, if Rust can
+            // override this method.
+            if method.is_virtual {
+                let mut fn_impl = self.generate_cpp_function_inner(
+                    method.fun,
+                    true,
+                    ConversionDirection::CppCallsRust,
+                    true,
+                    Some(&method.fun.original_cpp_name),
+                )?;
+                method_decls.push(fn_impl.declaration.take().unwrap());
+                self.additional_functions.push(fn_impl);
+            }
             // And now the function to be called from Rust for default implementation (calls superclass in C++)
             if !method.is_pure_virtual {
                 let mut super_method = method.fun.clone();
@@ -672,9 +1676,19 @@ impl<'a> CppCodeGenerator<'a> {
             constructor_decls.push(decl);
             self.additional_functions.push(fn_impl);
         }
+        // Subclass methods are defined out of line in our .cc file, so they may
+        // need the same annotations as cxx gives its functions - for instance
+        // `__declspec(dllexport)` when building a Windows DLL.
+        let class_annotation = self
+            .cpp_codegen_options
+            .cxx_impl_annotations
+            .as_ref()
+            .map(|annotation| format!("{} ", annotation))
+            .unwrap_or_default();
         self.additional_functions.push(AdditionalFunction {
             type_definition: Some(format!(
-                "class {} : {}\n{{\npublic:\n{}\n{}\nvoid {}() const;\nprivate:rust::Box<{}> obs;\nvoid really_remove_ownership();\n\n}};",
+                "class {}{} : {}\n{{\npublic:\n{}\n{}\nvoid {}() const;\nprivate:rust::Box<{}> obs;\nvoid really_remove_ownership();\n\n}};",
+                class_annotation,
                 subclass.cpp(),
                 superclass.to_cpp_name(),
                 constructor_decls.join("\n"),
@@ -8,6 +8,7 @@
 
 use crate::{
     conversion::{apivec::ApiVec, AnalysisPhase, ConvertError},
+    known_types::known_types,
     types::QualifiedName,
 };
 use itertools::Itertools;
@@ -38,8 +39,8 @@ pub(crate) fn namespaced_name_using_original_name_map(
     if let Some(cpp_name) = original_name_map.get(qual_name) {
         qual_name
             .get_namespace()
-            .iter()
-            .chain(once(cpp_name))
+            .cpp_iter()
+            .chain(once(cpp_name.as_str()))
             .join("::")
     } else {
         qual_name.to_cpp_name()
@@ -87,6 +88,7 @@ pub(crate) fn type_to_cpp(ty: &Type, cpp_name_map: &CppNameMap) -> Result<String
                     let results: Result<Vec<_>, _> = ab
                         .args
                         .iter()
+                        .filter(|x| !is_default_deleter(x))
                         .map(|x| match x {
                             syn::GenericArgument::Type(gat) => type_to_cpp(gat, cpp_name_map),
                             _ => Ok("".to_string()),
@@ -129,6 +131,18 @@ pub(crate) fn type_to_cpp(ty: &Type, cpp_name_map: &CppNameMap) -> Result<String
     }
 }
 
+/// `std::default_delete` may appear as a template argument of a
+/// `std::unique_ptr` which came straight from bindgen, but it's the default
+/// and bindgen doesn't tell us its own template argument, so leave it out.
+fn is_default_deleter(arg: &syn::GenericArgument) -> bool {
+    match arg {
+        syn::GenericArgument::Type(Type::Path(typ)) => {
+            known_types().is_default_deleter(&QualifiedName::from_type_path(typ))
+        }
+        _ => false,
+    }
+}
+
 fn get_mut_string(mutability: &Option<Token![mut]>) -> &'static str {
     match mutability {
         None => "const ",
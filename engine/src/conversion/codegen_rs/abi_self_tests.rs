@@ -0,0 +1,146 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tests of the layout of POD types, as requested by `abi_self_test!`.
+//! The C++ side of this is in `codegen_cpp`.
+
+use autocxx_parser::IncludeCppConfig;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, ForeignItem, LitFloat, LitInt};
+
+use super::RsCodegenResult;
+use crate::{
+    conversion::{
+        abi_self_tests::{find_abi_tested_types, AbiTestedField},
+        analysis::fun::FnPhase,
+        apivec::ApiVec,
+    },
+    types::{make_ident, QualifiedName},
+};
+
+/// Generate a `#[cfg(test)]` test for each POD type we can test, along
+/// with the `cxx::bridge` declarations of the shims it calls. Returns
+/// whether there were any, in which case we'll need to include the C++
+/// which we generate for them.
+pub(super) fn generate_abi_self_tests(
+    apis: &ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> (Vec<(QualifiedName, RsCodegenResult)>, bool) {
+    let results: Vec<_> = find_abi_tested_types(apis, config)
+        .into_iter()
+        .map(|tested| {
+            let ty = tested.name.get_final_ident();
+            let fulltypath = tested.name.get_bindgen_path_idents();
+            let cpp_name = tested.name.to_cpp_name();
+            let field_fn_name = tested.field_fn_name();
+            let roundtrip_fn_name = tested.roundtrip_fn_name();
+            let size_fn_name = tested.size_fn_name();
+            let test_name = make_ident(format!("autocxx_abi_self_test_{}", ty));
+            let extern_c_mod_items: Vec<ForeignItem> = vec![
+                parse_quote! {
+                    fn #field_fn_name(obj: &#ty, field: u32) -> f64;
+                },
+                parse_quote! {
+                    fn #roundtrip_fn_name(obj: &#ty) -> #ty;
+                },
+                parse_quote! {
+                    fn #size_fn_name() -> usize;
+                },
+            ];
+            let (inits, checks): (Vec<_>, Vec<_>) = tested
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(idx, field)| {
+                    let (value, expected) = field_value(idx, field);
+                    let rust_name = field.rust_name;
+                    let idx = idx as u32;
+                    let field_cpp_name = format!("{}::{}", cpp_name, field.cpp_name);
+                    (
+                        quote! { #rust_name: #value },
+                        // The braces copy the field, in case the struct is
+                        // packed and we can't take a reference to it.
+                        quote! {
+                            assert_eq!(
+                                cxxbridge::#field_fn_name(obj, #idx),
+                                #expected,
+                                "C++ read the wrong value of {} {}",
+                                #field_cpp_name,
+                                when
+                            );
+                            assert_eq!(
+                                { obj.#rust_name },
+                                #value,
+                                "Rust read the wrong value of {} {}",
+                                #field_cpp_name,
+                                when
+                            );
+                        },
+                    )
+                })
+                .unzip();
+            let global_items = vec![parse_quote! {
+                #[cfg(test)]
+                #[test]
+                #[allow(non_snake_case)]
+                fn #test_name() {
+                    assert_eq!(
+                        cxxbridge::#size_fn_name(),
+                        ::std::mem::size_of::<#(#fulltypath)::*>(),
+                        "Rust and C++ disagree about the size of {}",
+                        #cpp_name
+                    );
+                    let obj = #(#fulltypath)::* {
+                        #(#inits),*
+                    };
+                    let check = |obj: &#(#fulltypath)::*, when: &str| {
+                        #(#checks)*
+                    };
+                    check(&obj, "passed to C++");
+                    check(&cxxbridge::#roundtrip_fn_name(&obj), "returned from C++");
+                }
+            }];
+            (
+                QualifiedName::new(tested.name.get_namespace(), test_name),
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    global_items,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+    let any = !results.is_empty();
+    (results, any)
+}
+
+/// A distinct value for each field, so that we notice if two are swapped,
+/// along with the value which C++ should report for it. Every one is small
+/// enough to be represented exactly by an `f64`, and by the smallest of
+/// the types we test.
+fn field_value(idx: usize, field: &AbiTestedField) -> (TokenStream, TokenStream) {
+    let ty = field.ty;
+    let value = (idx % 100) + 1;
+    if field.is_bool() {
+        let value = idx % 2 == 0;
+        let expected = if value {
+            quote! { 1.0 }
+        } else {
+            quote! { 0.0 }
+        };
+        (quote! { #value }, expected)
+    } else if field.is_float() {
+        let value = LitFloat::new(&format!("{}.5", value), proc_macro2::Span::call_site());
+        (quote! { #value as #ty }, quote! { #value })
+    } else {
+        let expected = LitFloat::new(&format!("{}.0", value), proc_macro2::Span::call_site());
+        let value = LitInt::new(&value.to_string(), proc_macro2::Span::call_site());
+        (quote! { #value as #ty }, quote! { #expected })
+    }
+}
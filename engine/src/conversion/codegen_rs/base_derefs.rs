@@ -0,0 +1,81 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `Deref` implementations which let the const methods (and, for POD
+//! types, the fields) of a base class be used from a derived class.
+
+use std::collections::BTreeMap;
+
+use syn::parse_quote;
+
+use super::RsCodegenResult;
+use crate::{
+    conversion::{
+        analysis::fun::{FnKind, FnPhase, TraitMethodKind},
+        api::{Api, CastMutability, TraitSynthesis},
+        apivec::ApiVec,
+    },
+    types::{make_ident, QualifiedName},
+};
+
+/// For each type with a single base class to which we can cast it,
+/// implement `Deref` using that cast. A type can only `Deref` to one
+/// other type, so for types with multiple bases, users must pick one
+/// using `AsRef`.
+pub(super) fn generate_base_derefs(
+    apis: &ApiVec<FnPhase>,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    // A BTreeMap, so that we generate these in the same order each time.
+    let mut bases_by_type: BTreeMap<&QualifiedName, Vec<&QualifiedName>> = BTreeMap::new();
+    for api in apis.iter() {
+        if let Api::Function { fun, analysis, .. } = api {
+            if let (
+                FnKind::TraitMethod {
+                    kind: TraitMethodKind::Cast,
+                    impl_for,
+                    ..
+                },
+                Some(TraitSynthesis::Cast {
+                    to_type,
+                    mutable: CastMutability::ConstToConst,
+                }),
+            ) = (&analysis.kind, &fun.add_to_trait)
+            {
+                bases_by_type.entry(impl_for).or_default().push(to_type);
+            }
+        }
+    }
+    bases_by_type
+        .into_iter()
+        .filter_map(|(derived, bases)| match bases.as_slice() {
+            [base] => Some((derived, *base)),
+            _ => None,
+        })
+        .map(|(derived, base)| {
+            let derived_path = derived.to_type_path();
+            let base_path = base.to_type_path();
+            (
+                QualifiedName::new(
+                    derived.get_namespace(),
+                    make_ident(format!("{}_deref", derived.get_final_item())),
+                ),
+                RsCodegenResult {
+                    bindgen_mod_items: vec![parse_quote! {
+                        impl ::std::ops::Deref for #derived_path {
+                            type Target = #base_path;
+                            fn deref(&self) -> &Self::Target {
+                                <Self as AsRef<#base_path>>::as_ref(self)
+                            }
+                        }
+                    }],
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
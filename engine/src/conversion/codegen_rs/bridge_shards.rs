@@ -0,0 +1,210 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Spreading the contents of the `cxx::bridge` across several mods,
+//! as requested by `bridge_shards!`. Each shard is expanded separately
+//! by cxx, which keeps huge bindings tractable.
+//!
+//! Only APIs consisting purely of extern "C++" functions are moved out of
+//! the first shard. Types stay in the first shard, and any other shard
+//! which needs one redeclares it as an alias. That's only possible for
+//! types which are themselves already aliases (e.g. of bindgen types):
+//! a function mentioning a type which cxx itself defines stays in the
+//! first shard, as do extern "Rust" items and explicit `impl`s.
+
+use std::collections::{BTreeMap, HashMap};
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{FnArg, ForeignItem, Ident, Item, Pat};
+
+use crate::{conversion::stable_hash::stable_hash, types::QualifiedName};
+
+/// The items destined for a single `#[cxx::bridge]` mod.
+#[derive(Default)]
+pub(super) struct BridgeShard {
+    pub(super) bridge_items: Vec<Item>,
+    pub(super) extern_c_mod_items: Vec<ForeignItem>,
+    pub(super) extern_rust_mod_items: Vec<ForeignItem>,
+}
+
+impl BridgeShard {
+    fn append(&mut self, mut other: BridgeShard) {
+        self.bridge_items.append(&mut other.bridge_items);
+        self.extern_c_mod_items
+            .append(&mut other.extern_c_mod_items);
+        self.extern_rust_mod_items
+            .append(&mut other.extern_rust_mod_items);
+    }
+
+    fn is_functions_only(&self) -> bool {
+        self.bridge_items.is_empty()
+            && self.extern_rust_mod_items.is_empty()
+            && self
+                .extern_c_mod_items
+                .iter()
+                .all(|item| matches!(item, ForeignItem::Fn(_)))
+    }
+
+    /// Free functions (as opposed to methods) declared in this shard.
+    /// These are the only things which cxx makes available directly
+    /// within the bridge mod.
+    pub(super) fn free_functions(&self) -> impl Iterator<Item = &Ident> {
+        self.extern_c_mod_items
+            .iter()
+            .filter_map(|item| match item {
+                ForeignItem::Fn(f) if !has_receiver(f.sig.inputs.first()) => Some(&f.sig.ident),
+                _ => None,
+            })
+    }
+}
+
+fn has_receiver(first_arg: Option<&FnArg>) -> bool {
+    match first_arg {
+        Some(FnArg::Receiver(_)) => true,
+        Some(FnArg::Typed(pt)) => matches!(&*pt.pat, Pat::Ident(pi) if pi.ident == "self"),
+        None => false,
+    }
+}
+
+/// Divide the bridge contributions of each API into `shard_count` shards.
+pub(super) fn shard_bridge(
+    contributions: Vec<(QualifiedName, BridgeShard)>,
+    shard_count: usize,
+) -> Vec<BridgeShard> {
+    let mut shards: Vec<BridgeShard> = (0..shard_count).map(|_| BridgeShard::default()).collect();
+    if shard_count == 1 {
+        for (_, contribution) in contributions {
+            shards[0].append(contribution);
+        }
+        return shards;
+    }
+    // For each type declared to cxx, the declaration which other shards
+    // can use, if it's possible for them to refer to it at all.
+    let type_decls: HashMap<String, Option<ForeignItem>> = contributions
+        .iter()
+        .flat_map(|(_, contribution)| contribution.extern_c_mod_items.iter())
+        .filter_map(type_declaration)
+        .collect();
+    let mut needed_types: Vec<BTreeMap<String, ForeignItem>> = vec![BTreeMap::new(); shard_count];
+    for (name, contribution) in contributions {
+        let mut shard = 0;
+        if contribution.is_functions_only() {
+            let mut mentioned = HashMap::new();
+            for item in &contribution.extern_c_mod_items {
+                let mut idents = Vec::new();
+                find_idents(item.to_token_stream(), &mut idents);
+                for id in idents {
+                    if let Some(decl) = type_decls.get(&id) {
+                        mentioned.insert(id, decl.clone());
+                    }
+                }
+            }
+            if mentioned.values().all(Option::is_some) {
+                shard = shard_for(&name, shard_count);
+                needed_types[shard]
+                    .extend(mentioned.into_iter().map(|(id, decl)| (id, decl.unwrap())));
+            }
+        }
+        shards[shard].append(contribution);
+    }
+    for (shard, needed_types) in shards.iter_mut().zip(needed_types).skip(1) {
+        shard.extern_c_mod_items.extend(needed_types.into_values());
+    }
+    shards
+}
+
+fn shard_for(name: &QualifiedName, shard_count: usize) -> usize {
+    // The proc macro and build script must agree, even if they were built
+    // by different Rust toolchains, so we use a hash with a fixed algorithm.
+    (stable_hash(&name.to_string()) % shard_count as u64) as usize
+}
+
+/// If this item declares a type, its name and - if it's an alias which
+/// could equally be declared within another shard - that declaration.
+fn type_declaration(item: &ForeignItem) -> Option<(String, Option<ForeignItem>)> {
+    match item {
+        ForeignItem::Type(ty) => Some((ty.ident.to_string(), None)),
+        ForeignItem::Verbatim(ts) => {
+            let mut tokens = ts.clone().into_iter();
+            tokens.find(|tt| matches!(tt, TokenTree::Ident(id) if id == "type"))?;
+            let id = match tokens.next() {
+                Some(TokenTree::Ident(id)) => id,
+                _ => return None,
+            };
+            let is_alias = matches!(tokens.next(), Some(TokenTree::Punct(p)) if p.as_char() == '=');
+            Some((id.to_string(), is_alias.then(|| item.clone())))
+        }
+        _ => None,
+    }
+}
+
+fn find_idents(ts: TokenStream, idents: &mut Vec<String>) {
+    for tt in ts {
+        match tt {
+            TokenTree::Ident(id) => idents.push(id.to_string()),
+            TokenTree::Group(group) => find_idents(group.stream(), idents),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::{parse_quote, ForeignItem};
+
+    use super::{shard_bridge, BridgeShard};
+    use crate::types::QualifiedName;
+
+    fn contribution(name: &str, item: ForeignItem) -> (QualifiedName, BridgeShard) {
+        (
+            QualifiedName::new_from_cpp_name(name),
+            BridgeShard {
+                extern_c_mod_items: vec![item],
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_shard_bridge() {
+        let mut contributions = vec![
+            contribution(
+                "Foo",
+                ForeignItem::Verbatim(quote! { type Foo = super::bindgen::root::Foo; }),
+            ),
+            contribution("Opaque", ForeignItem::Verbatim(quote! { type Opaque; })),
+            contribution(
+                "takes_opaque",
+                parse_quote! { fn takes_opaque(o: &Opaque); },
+            ),
+        ];
+        for i in 0..20 {
+            let id = quote::format_ident!("takes_foo{}", i);
+            contributions.push(contribution(
+                &id.to_string(),
+                parse_quote! { fn #id(foo: &Foo); },
+            ));
+        }
+        let shards = shard_bridge(contributions, 4);
+        assert_eq!(shards.len(), 4);
+        // Types, and functions which use types we can't alias, stay put.
+        assert!(shards[0].free_functions().any(|id| id == "takes_opaque"));
+        let total: usize = shards.iter().map(|s| s.free_functions().count()).sum();
+        assert_eq!(total, 21);
+        // Other shards get their own alias for Foo.
+        for shard in shards.iter().skip(1) {
+            if shard.free_functions().next().is_some() {
+                assert!(shard.extern_c_mod_items.iter().any(|item| matches!(
+                    item, ForeignItem::Verbatim(ts) if ts.to_string().contains("type Foo")
+                )));
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Methods to convert dense matrices to and from slices, as requested by
+//! `dense_matrix!`. The C++ side of this is in `codegen_cpp`.
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{parse_quote, ForeignItem, ImplItem};
+
+use super::{ImplBlockDetails, RsCodegenResult};
+use crate::{
+    conversion::{analysis::fun::FnPhase, apivec::ApiVec, dense_matrices::find_dense_matrices},
+    types::{make_ident, QualifiedName},
+};
+
+/// Generate `from_slice`, `as_slice`, `as_mut_slice` and `dims` methods
+/// for each type named in a `dense_matrix!` directive, along with the
+/// `cxx::bridge` declarations they need. Returns whether there were any, in
+/// which case we'll need to include the C++ which we generate for them.
+pub(super) fn generate_dense_matrices(
+    apis: &ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> (Vec<(QualifiedName, RsCodegenResult)>, bool) {
+    let mut results = Vec::new();
+    for matrix in find_dense_matrices(apis, config) {
+        let ty = matrix.name.get_final_ident();
+        let element = &matrix.element;
+        let from_slice_fn_name = matrix.from_slice_fn_name();
+        let data_fn_name = matrix.data_fn_name();
+        let data_mut_fn_name = matrix.data_mut_fn_name();
+        let rows_fn_name = matrix.rows_fn_name();
+        let cols_fn_name = matrix.cols_fn_name();
+        let size_mismatch_msg =
+            format!("a {} can't have the requested dimensions", matrix.cpp_name);
+        let mut add_method =
+            |method_name: &str, extern_c_mod_items: Vec<ForeignItem>, item: ImplItem| {
+                results.push((
+                    QualifiedName::new(matrix.name.get_namespace(), make_ident(method_name)),
+                    RsCodegenResult {
+                        extern_c_mod_items,
+                        impl_entry: Some(Box::new(ImplBlockDetails {
+                            item,
+                            ty: ty.clone(),
+                        })),
+                        ..Default::default()
+                    },
+                ))
+            };
+        add_method(
+            "from_slice",
+            vec![parse_quote! {
+                fn #from_slice_fn_name(data: &[#element], rows: usize, cols: usize) -> UniquePtr<#ty>;
+            }],
+            parse_quote! {
+                /// Creates a matrix with the given dimensions, given its elements in
+                /// column-major order. Panics if there aren't `rows * cols` elements, or
+                /// if this type of matrix can't have those dimensions.
+                pub fn from_slice(data: &[#element], rows: usize, cols: usize) -> cxx::UniquePtr<Self> {
+                    assert_eq!(data.len(), rows * cols, "wrong number of elements for a {}x{} matrix", rows, cols);
+                    let matrix = cxxbridge::#from_slice_fn_name(data, rows, cols);
+                    assert!(!matrix.is_null(), #size_mismatch_msg);
+                    matrix
+                }
+            },
+        );
+        add_method(
+            "as_slice",
+            vec![parse_quote! {
+                fn #data_fn_name(matrix: &#ty) -> &[#element];
+            }],
+            parse_quote! {
+                /// The elements of this matrix, in column-major order.
+                pub fn as_slice(&self) -> &[#element] {
+                    cxxbridge::#data_fn_name(self)
+                }
+            },
+        );
+        add_method(
+            "as_mut_slice",
+            vec![parse_quote! {
+                fn #data_mut_fn_name(matrix: Pin<&mut #ty>) -> &mut [#element];
+            }],
+            parse_quote! {
+                /// The elements of this matrix, in column-major order.
+                pub fn as_mut_slice(self: ::std::pin::Pin<&mut Self>) -> &mut [#element] {
+                    cxxbridge::#data_mut_fn_name(self)
+                }
+            },
+        );
+        add_method(
+            "dims",
+            vec![
+                parse_quote! {
+                    fn #rows_fn_name(matrix: &#ty) -> usize;
+                },
+                parse_quote! {
+                    fn #cols_fn_name(matrix: &#ty) -> usize;
+                },
+            ],
+            parse_quote! {
+                /// The number of rows and columns in this matrix.
+                pub fn dims(&self) -> (usize, usize) {
+                    (cxxbridge::#rows_fn_name(self), cxxbridge::#cols_fn_name(self))
+                }
+            },
+        );
+    }
+    let any = !results.is_empty();
+    (results, any)
+}
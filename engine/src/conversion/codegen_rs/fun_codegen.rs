@@ -13,7 +13,7 @@ use syn::{
     parse_quote,
     punctuated::Punctuated,
     token::{Comma, Unsafe},
-    Attribute, FnArg, ForeignItem, Ident, ImplItem, Item, ReturnType,
+    Attribute, FnArg, ForeignItem, Ident, ImplItem, Item, Pat, ReturnType, Signature, Type,
 };
 
 use super::{
@@ -23,11 +23,16 @@ use super::{
 use crate::{
     conversion::{
         analysis::fun::{
-            ArgumentAnalysis, FnAnalysis, FnKind, MethodKind, RustRenameStrategy,
-            TraitMethodDetails,
+            function_wrapper::RustConversionType, ArgumentAnalysis, FnAnalysis, FnKind, MethodKind,
+            RustRenameStrategy, TraitMethodDetails,
         },
         api::UnsafetyNeeded,
         codegen_rs::lifetime::add_lifetime_to_all_params,
+        last_errors::LastError,
+        refcounted::RefcountedReturn,
+        result_types::{ResultReturn, ResultReturnKind},
+        smart_pointers::SmartPointerReturn,
+        unique_arrays::{UniqueArrayLen, UniqueArrayReturn},
     },
     types::{Namespace, QualifiedName},
 };
@@ -82,6 +87,7 @@ pub(super) fn gen_function(
     fun: FuncToConvert,
     analysis: FnAnalysis,
     cpp_call_name: String,
+    instrument_calls: bool,
 ) -> RsCodegenResult {
     if analysis.ignore_reason.is_err() || !analysis.externally_callable {
         return RsCodegenResult::default();
@@ -95,6 +101,23 @@ pub(super) fn gen_function(
     let vis = analysis.vis;
     let kind = analysis.kind;
     let doc_attr = fun.doc_attr;
+    // Link getters and setters which were paired up by `pair_accessors!`,
+    // and explain any renaming to avoid a name clash.
+    let wrapper_doc_attrs: Vec<Attribute> = doc_attr
+        .iter()
+        .cloned()
+        .chain(analysis.paired_accessor.iter().map(|partner| {
+            let see_also = format!(" See also [`Self::{}`].", partner);
+            parse_quote! { #[doc = #see_also] }
+        }))
+        .chain(analysis.renamed_from.iter().map(|old_name| {
+            let renamed = format!(
+                " Named `{}` in Rust because `{}` is the name of another function.",
+                rust_name, old_name
+            );
+            parse_quote! { #[doc = #renamed] }
+        }))
+        .collect();
 
     let mut cpp_name_attr = Vec::new();
     let mut impl_entry = None;
@@ -104,17 +127,42 @@ pub(super) fn gen_function(
         FnKind::TraitMethod { ref details, .. } => details.trait_call_is_unsafe,
         _ => false,
     };
+    // The name reported to `autocxx::instrumentation` hooks.
+    let instrumentation_name = if instrument_calls {
+        Some(match kind {
+            FnKind::Method { ref impl_for, .. } | FnKind::TraitMethod { ref impl_for, .. } => {
+                format!("{}::{}", impl_for.to_cpp_name(), cpp_call_name)
+            }
+            FnKind::Function if ns.is_empty() => cpp_call_name.clone(),
+            FnKind::Function => format!("{}::{}", ns, cpp_call_name),
+        })
+    } else {
+        None
+    };
     let fn_generator = FnGenerator {
         param_details: &param_details,
+        return_borrows_from: analysis.return_borrows_from,
         cxxbridge_name: &cxxbridge_name,
         rust_name,
         unsafety: &analysis.requires_unsafe,
         always_unsafe_due_to_trait_definition,
-        doc_attr: &doc_attr,
+        doc_attrs: &wrapper_doc_attrs,
+        error_code_success: &analysis.error_code_success,
+        string_return: analysis.string_return,
+        result_return: &analysis.result_return,
+        smart_pointer_return: &analysis.smart_pointer_return,
+        unique_array_return: &analysis.unique_array_return,
+        refcounted_return: &analysis.refcounted_return,
+        last_error: &analysis.last_error,
+        instrumentation_name: &instrumentation_name,
     };
     // In rare occasions, we might need to give an explicit lifetime.
-    let (lifetime_tokens, params, ret_type) =
-        add_explicit_lifetime_if_necessary(&param_details, params, &ret_type);
+    let (lifetime_tokens, params, ret_type) = add_explicit_lifetime_if_necessary(
+        &param_details,
+        analysis.return_borrows_from,
+        params,
+        &ret_type,
+    );
 
     if analysis.rust_wrapper_needed {
         match kind {
@@ -146,7 +194,14 @@ pub(super) fn gen_function(
             }
             _ => {
                 // Generate plain old function
-                bindgen_mod_items.push(fn_generator.generate_function_impl(&ret_type));
+                let function_impl = fn_generator.generate_function_impl(&ret_type);
+                if analysis.async_wrapper {
+                    if let Item::Fn(ref sync_fn) = function_impl {
+                        bindgen_mod_items
+                            .push(fn_generator.generate_async_function_impl(&sync_fn.sig));
+                    }
+                }
+                bindgen_mod_items.push(function_impl);
             }
         }
     }
@@ -186,7 +241,7 @@ pub(super) fn gen_function(
     let namespace_attr = if ns.is_empty() || wrapper_function_needed {
         Vec::new()
     } else {
-        let namespace_string = ns.to_string();
+        let namespace_string = ns.to_cpp_string();
         Attribute::parse_outer
             .parse2(quote!(
                 #[namespace = #namespace_string]
@@ -201,12 +256,21 @@ pub(super) fn gen_function(
         #doc_attr
         #vis #bridge_unsafety fn #cxxbridge_name #lifetime_tokens ( #params ) #ret_type;
     ));
+    let async_materialization = match kind {
+        FnKind::Function if analysis.async_wrapper => Some(Use::SpecificNameFromBindgen(
+            make_ident(format!("{}_async", rust_name)),
+        )),
+        _ => None,
+    };
     RsCodegenResult {
         extern_c_mod_items: vec![extern_c_mod_item],
         bindgen_mod_items,
         impl_entry,
         trait_impl_entry,
-        materializations: materialization.into_iter().collect(),
+        materializations: materialization
+            .into_iter()
+            .chain(async_materialization)
+            .collect(),
         ..Default::default()
     }
 }
@@ -215,11 +279,20 @@ pub(super) fn gen_function(
 #[derive(Clone)]
 struct FnGenerator<'a> {
     param_details: &'a [ArgumentAnalysis],
+    return_borrows_from: Option<usize>,
     cxxbridge_name: &'a Ident,
     rust_name: &'a str,
     unsafety: &'a UnsafetyNeeded,
     always_unsafe_due_to_trait_definition: bool,
-    doc_attr: &'a Option<Attribute>,
+    doc_attrs: &'a [Attribute],
+    error_code_success: &'a Option<Ident>,
+    string_return: bool,
+    result_return: &'a Option<ResultReturn>,
+    smart_pointer_return: &'a Option<SmartPointerReturn>,
+    unique_array_return: &'a Option<UniqueArrayReturn>,
+    refcounted_return: &'a Option<RefcountedReturn>,
+    last_error: &'a Option<LastError>,
+    instrumentation_name: &'a Option<String>,
 }
 
 impl<'a> FnGenerator<'a> {
@@ -238,10 +311,18 @@ impl<'a> FnGenerator<'a> {
             } else {
                 pd.name.clone()
             };
-            let param_mutability = pd.conversion.rust_conversion.requires_mutability();
-            wrapper_params.push(parse_quote!(
-                #param_mutability #wrapper_arg_name: #type_name
-            ));
+            // Out parameters are created within the wrapper function, and
+            // slice lengths are derived from the slice, so the caller
+            // doesn't pass them.
+            if !matches!(
+                pd.conversion.rust_conversion,
+                RustConversionType::FromOutParamToPtr | RustConversionType::FromSliceToLen(_)
+            ) {
+                let param_mutability = pd.conversion.rust_conversion.requires_mutability();
+                wrapper_params.push(parse_quote!(
+                    #param_mutability #wrapper_arg_name: #type_name
+                ));
+            }
             let (local_variable, actual_arg) = pd
                 .conversion
                 .rust_conversion(wrapper_arg_name, wrap_unsafe_calls);
@@ -259,20 +340,25 @@ impl<'a> FnGenerator<'a> {
         ret_type: &ReturnType,
     ) -> Box<ImplBlockDetails> {
         let (wrapper_params, local_variables, arg_list) = self.generate_arg_lists(avoid_self);
-        let (lifetime_tokens, wrapper_params, ret_type) =
-            add_explicit_lifetime_if_necessary(self.param_details, wrapper_params, ret_type);
+        let (lifetime_tokens, wrapper_params, ret_type) = add_explicit_lifetime_if_necessary(
+            self.param_details,
+            self.return_borrows_from,
+            wrapper_params,
+            ret_type,
+        );
         let rust_name = make_ident(self.rust_name);
         let unsafety = self.unsafety.wrapper_token();
-        let doc_attr = self.doc_attr;
+        let doc_attrs = self.doc_attrs;
         let cxxbridge_name = self.cxxbridge_name;
-        let call_body = self.wrap_call_with_unsafe(quote! {
+        let call_body = self.wrap_call(quote! {
             cxxbridge::#cxxbridge_name ( #(#arg_list),* )
         });
+        let (ret_type, call_body) = self.convert_return_value(&ret_type, call_body);
         Box::new(ImplBlockDetails {
             item: ImplItem::Method(parse_quote! {
-                #doc_attr
+                #(#doc_attrs)*
                 pub #unsafety fn #rust_name #lifetime_tokens ( #wrapper_params ) #ret_type {
-                    #(#local_variables),*
+                    #(#local_variables)*
                     #call_body
                 }
             }),
@@ -291,20 +377,24 @@ impl<'a> FnGenerator<'a> {
         if let Some(parameter_reordering) = &details.parameter_reordering {
             wrapper_params = Self::reorder_parameters(wrapper_params, parameter_reordering);
         }
-        let (lifetime_tokens, wrapper_params, ret_type) =
-            add_explicit_lifetime_if_necessary(self.param_details, wrapper_params, ret_type);
-        let doc_attr = self.doc_attr;
+        let (lifetime_tokens, wrapper_params, ret_type) = add_explicit_lifetime_if_necessary(
+            self.param_details,
+            self.return_borrows_from,
+            wrapper_params,
+            ret_type,
+        );
+        let doc_attrs = self.doc_attrs;
         let unsafety = self.unsafety.wrapper_token();
         let cxxbridge_name = self.cxxbridge_name;
         let key = details.trt.clone();
         let method_name = &details.method_name;
-        let call_body = self.wrap_call_with_unsafe(quote! {
+        let call_body = self.wrap_call(quote! {
             cxxbridge::#cxxbridge_name ( #(#arg_list),* )
         });
         let item = parse_quote! {
-            #doc_attr
+            #(#doc_attrs)*
             #unsafety fn #method_name #lifetime_tokens ( #wrapper_params ) #ret_type {
-                #(#local_variables),*
+                #(#local_variables)*
                 #call_body
             }
         };
@@ -316,6 +406,21 @@ impl<'a> FnGenerator<'a> {
             || self.always_unsafe_due_to_trait_definition
     }
 
+    /// Wraps a call into the cxx::bridge in any `unsafe` block and
+    /// instrumentation which are needed.
+    fn wrap_call(&self, call: TokenStream) -> TokenStream {
+        self.wrap_call_with_unsafe(self.instrument_call(call))
+    }
+
+    fn instrument_call(&self, call: TokenStream) -> TokenStream {
+        match self.instrumentation_name {
+            Some(name) => quote! {
+                autocxx::instrumentation::instrumented(#name, || #call)
+            },
+            None => call,
+        }
+    }
+
     fn wrap_call_with_unsafe(&self, call: TokenStream) -> TokenStream {
         if self.should_wrap_unsafe_calls() {
             quote! {
@@ -347,19 +452,22 @@ impl<'a> FnGenerator<'a> {
             (quote! {}, quote! {})
         };
         let cxxbridge_name = self.cxxbridge_name;
+        let call = self.instrument_call(quote! {
+            cxxbridge::#cxxbridge_name(#(#arg_list),* )
+        });
         let body = quote! {
-            #(#local_variables),*
+            #(#local_variables)*
             autocxx::moveit::new::by_raw(move |#ptr_arg_name| {
                 let #ptr_arg_name = #ptr_arg_name.get_unchecked_mut().as_mut_ptr();
-                cxxbridge::#cxxbridge_name(#(#arg_list),* )
+                #call
             })
         };
         let body = self.wrap_call_with_unsafe(body);
-        let doc_attr = self.doc_attr;
+        let doc_attrs = self.doc_attrs;
         let unsafety = self.unsafety.wrapper_token();
         Box::new(ImplBlockDetails {
             item: ImplItem::Method(parse_quote! {
-                #doc_attr
+                #(#doc_attrs)*
                 pub #unsafety fn #rust_name #lifetime_param ( #wrapper_params ) -> impl autocxx::moveit::new::New<Output=Self> #lifetime_addition {
                     #body
                 }
@@ -371,22 +479,357 @@ impl<'a> FnGenerator<'a> {
     /// Generate a function call wrapper
     fn generate_function_impl(&self, ret_type: &ReturnType) -> Item {
         let (wrapper_params, local_variables, arg_list) = self.generate_arg_lists(false);
+        let (lifetime_tokens, wrapper_params, ret_type) = add_explicit_lifetime_if_necessary(
+            self.param_details,
+            self.return_borrows_from,
+            wrapper_params,
+            ret_type,
+        );
         let rust_name = make_ident(self.rust_name);
-        let doc_attr = self.doc_attr;
+        let doc_attrs = self.doc_attrs;
         let unsafety = self.unsafety.wrapper_token();
         let cxxbridge_name = self.cxxbridge_name;
-        let body = self.wrap_call_with_unsafe(quote! {
+        let body = self.wrap_call(quote! {
             cxxbridge::#cxxbridge_name ( #(#arg_list),* )
         });
+        let (ret_type, body) = self.convert_return_value(&ret_type, body);
         Item::Fn(parse_quote! {
-            #doc_attr
-            pub #unsafety fn #rust_name ( #wrapper_params ) #ret_type {
-                #(#local_variables),*
+            #(#doc_attrs)*
+            pub #unsafety fn #rust_name #lifetime_tokens ( #wrapper_params ) #ret_type {
+                #(#local_variables)*
                 #body
             }
         })
     }
 
+    /// Generate `foo_async` alongside the plain function `foo`, for
+    /// `async_wrapper!`. It takes the same parameters, and returns a future
+    /// which resolves to the result of calling `foo` on a thread provided
+    /// by `autocxx::blocking`.
+    fn generate_async_function_impl(&self, sync_sig: &Signature) -> Item {
+        let sync_name = &sync_sig.ident;
+        let async_name = make_ident(format!("{}_async", self.rust_name));
+        let unsafety = &sync_sig.unsafety;
+        let wrapper_params = &sync_sig.inputs;
+        let arg_list = wrapper_params.iter().filter_map(|arg| match arg {
+            FnArg::Typed(pt) => match pt.pat.as_ref() {
+                Pat::Ident(pp) => Some(&pp.ident),
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        });
+        let output = match &sync_sig.output {
+            ReturnType::Type(_, ty) => quote! { #ty },
+            ReturnType::Default => quote! { () },
+        };
+        let doc = format!(
+            " Calls [`{}`] on a thread suitable for blocking work; see `autocxx::blocking`.",
+            sync_name
+        );
+        Item::Fn(parse_quote! {
+            #[doc = #doc]
+            pub #unsafety fn #async_name ( #wrapper_params ) -> impl ::std::future::Future<Output = #output> {
+                autocxx::blocking::spawn_blocking(move || #sync_name ( #(#arg_list),* ))
+            }
+        })
+    }
+
+    /// Alter the return type and body to account for any out parameter
+    /// (see `out_param!`), error code (see `error_code!`), string
+    /// return (see `string_returns!`), result type (`absl::Status` or
+    /// see `result_type!`), smart pointer (see `smart_pointer!`),
+    /// `std::unique_ptr<T[]>` (see `unique_array!`), pointer to a
+    /// reference-counted class (see `refcounted!`) or errno-style error
+    /// (see `last_error!`). An out parameter is
+    /// returned as well as (or, for functions returning `bool`, conditional
+    /// upon) the real return value. An error code
+    /// becomes a `Result`, whose `Ok` value is any out parameter. A returned
+    /// `std::string` is copied into a `String`. A result type becomes a
+    /// `Result`; for `absl::Status` or `absl::StatusOr<T>` its error is an
+    /// `autocxx::AbslStatus`. A smart pointer is wrapped in an
+    /// `autocxx::SmartPtr`, an array in an `autocxx::UniqueArray`, and a
+    /// reference-counted object in an `autocxx::RcHandle`. An errno-style
+    /// error, captured by the C++ wrapper, makes the return value a `Result`.
+    fn convert_return_value(
+        &self,
+        ret_type: &ReturnType,
+        call_body: TokenStream,
+    ) -> (ReturnType, TokenStream) {
+        let (ret_type, call_body) = if let Some(result_return) = self.result_return {
+            Self::convert_result_return(result_return, call_body)
+        } else if let Some(smart_pointer_return) = self.smart_pointer_return {
+            let ptr_type = &smart_pointer_return.ptr_type;
+            let target = &smart_pointer_return.target;
+            let get_fn_name = smart_pointer_return.get_fn_name();
+            let clone_fn_name = smart_pointer_return.clone_fn_name();
+            (
+                parse_quote! { -> autocxx::SmartPtr<#ptr_type, #target> },
+                quote! {
+                    autocxx::SmartPtr::new(
+                        #call_body,
+                        cxxbridge::#get_fn_name,
+                        cxxbridge::#clone_fn_name,
+                    )
+                },
+            )
+        } else if let Some(unique_array_return) = self.unique_array_return {
+            Self::convert_unique_array_return(unique_array_return, call_body)
+        } else if let Some(refcounted_return) = self.refcounted_return {
+            Self::convert_refcounted_return(refcounted_return, call_body)
+        } else if let Some(last_error) = self.last_error {
+            Self::convert_last_error_return(last_error, ret_type, call_body)
+        } else if self.string_return {
+            (
+                parse_quote! { -> String },
+                quote! {
+                    (#call_body).to_string_lossy().into_owned()
+                },
+            )
+        } else {
+            (ret_type.clone(), call_body)
+        };
+        let ret_type = &ret_type;
+        let out_param = self.param_details.iter().find(|pd| {
+            matches!(
+                pd.conversion.rust_conversion,
+                RustConversionType::FromOutParamToPtr
+            )
+        });
+        let (out_ty, out_val, extract_out_param) = match out_param {
+            None if self.error_code_success.is_none() => return (ret_type.clone(), call_body),
+            None => (quote! { () }, quote! { () }, quote! {}),
+            Some(out_param) => {
+                let out_var = &out_param.name;
+                let out_ty = out_param.conversion.out_param_type();
+                (
+                    quote! { #out_ty },
+                    quote! { #out_var },
+                    quote! {
+                        #[allow(unused_unsafe)]
+                        let #out_var = unsafe { #out_var.assume_init() };
+                    },
+                )
+            }
+        };
+        let ret_type_path = match ret_type {
+            ReturnType::Type(_, ty) => match ty.as_ref() {
+                Type::Path(tp) => Some(tp),
+                _ => None,
+            },
+            ReturnType::Default => None,
+        };
+        match (ret_type_path, self.error_code_success) {
+            (Some(error_ty), Some(success)) => {
+                let mut success_path = error_ty.clone();
+                success_path.path.segments.push(parse_quote! { #success });
+                (
+                    parse_quote! { -> Result<#out_ty, #error_ty> },
+                    quote! {
+                        let autocxx_ret = #call_body;
+                        #extract_out_param
+                        if matches!(autocxx_ret, #success_path) {
+                            Ok(#out_val)
+                        } else {
+                            Err(autocxx_ret)
+                        }
+                    },
+                )
+            }
+            _ if matches!(ret_type, ReturnType::Default) => (
+                parse_quote! { -> #out_ty },
+                quote! {
+                    #call_body;
+                    #extract_out_param
+                    #out_val
+                },
+            ),
+            (Some(tp), _) if tp.path.is_ident("bool") => (
+                parse_quote! { -> Option<#out_ty> },
+                quote! {
+                    let autocxx_ret = #call_body;
+                    #extract_out_param
+                    if autocxx_ret {
+                        Some(#out_val)
+                    } else {
+                        None
+                    }
+                },
+            ),
+            _ => {
+                let ty = match ret_type {
+                    ReturnType::Type(_, ty) => ty,
+                    ReturnType::Default => unreachable!(),
+                };
+                (
+                    parse_quote! { -> (#ty, #out_ty) },
+                    quote! {
+                        let autocxx_ret = #call_body;
+                        #extract_out_param
+                        (autocxx_ret, #out_val)
+                    },
+                )
+            }
+        }
+    }
+
+    /// Wraps a returned array in an `autocxx::UniqueArray`. We find the
+    /// length before making the call, since the call may consume the
+    /// receiver.
+    fn convert_unique_array_return(
+        unique_array_return: &UniqueArrayReturn,
+        call_body: TokenStream,
+    ) -> (ReturnType, TokenStream) {
+        let elem = &unique_array_return.elem;
+        let delete_fn_name = &unique_array_return.delete_fn_name;
+        let len = match &unique_array_return.len {
+            UniqueArrayLen::Param {
+                name,
+                is_ctype: true,
+            } => quote! { #name.0 },
+            UniqueArrayLen::Param { name, .. } => quote! { #name },
+            UniqueArrayLen::Method(len_fn) => quote! { self.#len_fn() },
+            UniqueArrayLen::StaticMethod(len_fn) => quote! { Self::#len_fn() },
+            UniqueArrayLen::Function(len_fn) => quote! { #len_fn() },
+        };
+        (
+            parse_quote! { -> autocxx::UniqueArray<#elem> },
+            quote! {
+                {
+                    let autocxx_len: usize = ::std::convert::TryInto::try_into(#len)
+                        .expect("array length out of range");
+                    let autocxx_ptr = #call_body;
+                    #[allow(unused_unsafe)]
+                    unsafe {
+                        autocxx::UniqueArray::from_raw(
+                            autocxx_ptr,
+                            autocxx_len,
+                            cxxbridge::#delete_fn_name,
+                        )
+                    }
+                }
+            },
+        )
+    }
+
+    fn convert_refcounted_return(
+        refcounted_return: &RefcountedReturn,
+        call_body: TokenStream,
+    ) -> (ReturnType, TokenStream) {
+        let ty = &refcounted_return.ty;
+        let addref_fn_name = refcounted_return.addref_fn_name();
+        let release_fn_name = refcounted_return.release_fn_name();
+        let cast = refcounted_return.is_const.then(|| quote! { as *mut #ty });
+        (
+            parse_quote! { -> autocxx::RcHandle<#ty> },
+            quote! {
+                {
+                    let autocxx_ptr = #call_body;
+                    #[allow(unused_unsafe)]
+                    unsafe {
+                        autocxx::RcHandle::from_raw(
+                            autocxx_ptr #cast,
+                            cxxbridge::#addref_fn_name,
+                            cxxbridge::#release_fn_name,
+                        )
+                    }
+                }
+            },
+        )
+    }
+
+    fn convert_last_error_return(
+        last_error: &LastError,
+        ret_type: &ReturnType,
+        call_body: TokenStream,
+    ) -> (ReturnType, TokenStream) {
+        let ok_ty = match ret_type {
+            ReturnType::Type(_, ty) => quote! { #ty },
+            ReturnType::Default => quote! { () },
+        };
+        let error_ty = &last_error.error_ty;
+        let take_fn_name = last_error.take_fn_name();
+        (
+            parse_quote! { -> ::std::result::Result<#ok_ty, #error_ty> },
+            quote! {
+                {
+                    let autocxx_result = #call_body;
+                    let mut autocxx_error = ::std::mem::MaybeUninit::uninit();
+                    #[allow(unused_unsafe)]
+                    let autocxx_error = unsafe {
+                        if cxxbridge::#take_fn_name(autocxx_error.as_mut_ptr()) {
+                            Some(autocxx_error.assume_init())
+                        } else {
+                            None
+                        }
+                    };
+                    match autocxx_error {
+                        Some(autocxx_error) => Err(autocxx_error),
+                        None => Ok(autocxx_result),
+                    }
+                }
+            },
+        )
+    }
+
+    fn convert_result_return(
+        result_return: &ResultReturn,
+        call_body: TokenStream,
+    ) -> (ReturnType, TokenStream) {
+        let ok_fn_name = result_return.ok_fn_name();
+        let (ok_ty, ok_val) = match &result_return.value {
+            Some(value) => {
+                let value_ty = value.unconverted_rust_type();
+                let value_fn_name = result_return.value_fn_name();
+                (
+                    quote! { #value_ty },
+                    quote! { cxxbridge::#value_fn_name(autocxx_result.pin_mut()) },
+                )
+            }
+            None => (quote! { () }, quote! { () }),
+        };
+        let (err_ty, err_val, moves_out_error) = match &result_return.kind {
+            ResultReturnKind::Abseil => {
+                let code_fn_name = result_return.code_fn_name();
+                let message_fn_name = result_return.message_fn_name();
+                (
+                    quote! { autocxx::AbslStatus },
+                    quote! {
+                        autocxx::AbslStatus::new(
+                            cxxbridge::#code_fn_name(&autocxx_result),
+                            cxxbridge::#message_fn_name(&autocxx_result),
+                        )
+                    },
+                    false,
+                )
+            }
+            ResultReturnKind::Custom { error, .. } => {
+                let error_ty = error.unconverted_rust_type();
+                let error_fn_name = result_return.error_fn_name();
+                (
+                    quote! { #error_ty },
+                    quote! { cxxbridge::#error_fn_name(autocxx_result.pin_mut()) },
+                    true,
+                )
+            }
+        };
+        let mutability = if result_return.value.is_some() || moves_out_error {
+            quote! { mut }
+        } else {
+            quote! {}
+        };
+        (
+            parse_quote! { -> Result<#ok_ty, #err_ty> },
+            quote! {
+                let #mutability autocxx_result = #call_body;
+                if cxxbridge::#ok_fn_name(&autocxx_result) {
+                    Ok(#ok_val)
+                } else {
+                    Err(#err_val)
+                }
+            },
+        )
+    }
+
     fn reorder_parameters(
         params: Punctuated<FnArg, Comma>,
         parameter_ordering: &[usize],
@@ -10,8 +10,11 @@ use proc_macro2::TokenStream;
 use syn::{Pat, Type, TypePtr};
 
 use crate::{
-    conversion::analysis::fun::function_wrapper::{RustConversionType, TypeConversionPolicy},
-    types::make_ident,
+    conversion::analysis::fun::function_wrapper::{
+        RustConversionType, TypeConversionPolicy, MAX_INITIALIZER_LIST_LEN,
+    },
+    known_types::known_types,
+    types::{make_ident, QualifiedName},
 };
 use quote::quote;
 use syn::parse_quote;
@@ -56,6 +59,47 @@ impl TypeConversionPolicy {
                 let ty = &self.unwrapped_type;
                 parse_quote! { impl autocxx::ValueParam<#ty> }
             }
+            RustConversionType::FromOutParamToPtr => self.out_param_type().clone(),
+            RustConversionType::FromCStrToPtr => parse_quote! { &::std::ffi::CStr },
+            RustConversionType::FromSliceToPtr => match &self.unwrapped_type {
+                Type::Ptr(TypePtr {
+                    elem,
+                    mutability: Some(_),
+                    ..
+                }) => parse_quote! { &mut [#elem] },
+                Type::Ptr(TypePtr { elem, .. }) => parse_quote! { &[#elem] },
+                _ => panic!("Not a ptr"),
+            },
+            // Never actually used, since the caller doesn't pass the length.
+            RustConversionType::FromSliceToLen(_) => self.converted_rust_type(),
+            RustConversionType::FromSliceToInitializerList => self.converted_rust_type(),
+            RustConversionType::FromArrayToPtr(ref shape) => {
+                let (elem, mutability) = match &self.unwrapped_type {
+                    Type::Ptr(TypePtr {
+                        elem, mutability, ..
+                    }) => (elem, mutability),
+                    _ => panic!("Not a ptr"),
+                };
+                let array = shape.dims.iter().rev().fold(
+                    elem.as_ref().clone(),
+                    |ty, len| parse_quote! { [#ty; #len] },
+                );
+                match (shape.by_reference, mutability) {
+                    (true, Some(_)) => parse_quote! { &mut #array },
+                    (true, None) => parse_quote! { &#array },
+                    (false, Some(_)) => parse_quote! { *mut #array },
+                    (false, None) => parse_quote! { *const #array },
+                }
+            }
+        }
+    }
+
+    /// For an out parameter, the type of the value which will be
+    /// returned from the Rust wrapper function.
+    pub(super) fn out_param_type(&self) -> &Type {
+        match &self.unwrapped_type {
+            Type::Ptr(TypePtr { elem, .. }) => &*elem,
+            _ => panic!("Not a ptr"),
         }
     }
 
@@ -119,7 +163,7 @@ impl TypeConversionPolicy {
                     Some(quote! {
                         let mut #space_var_name = autocxx::ValueParamHandler::default();
                         let mut #space_var_name = unsafe {
-                            std::pin::Pin::new_unchecked(&mut #space_var_name)
+                            ::std::pin::Pin::new_unchecked(&mut #space_var_name)
                         };
                         #call
                     }),
@@ -128,6 +172,67 @@ impl TypeConversionPolicy {
                     },
                 )
             }
+            RustConversionType::FromOutParamToPtr => {
+                let ty = self.out_param_type();
+                (
+                    Some(quote! {
+                        let mut #var = ::std::mem::MaybeUninit::<#ty>::zeroed();
+                    }),
+                    quote! {
+                        #var.as_mut_ptr()
+                    },
+                )
+            }
+            RustConversionType::FromCStrToPtr => (None, quote! { #var.as_ptr() }),
+            RustConversionType::FromSliceToPtr => match &self.unwrapped_type {
+                Type::Ptr(TypePtr {
+                    mutability: Some(_),
+                    ..
+                }) => (None, quote! { #var.as_mut_ptr() }),
+                _ => (None, quote! { #var.as_ptr() }),
+            },
+            RustConversionType::FromSliceToLen(ref slice) => {
+                let len = quote! {
+                    ::std::convert::TryInto::try_into(#slice.len())
+                        .expect("slice too long for C++ length parameter")
+                };
+                let ty = &self.unwrapped_type;
+                let is_ctype = match ty {
+                    Type::Path(typ) => known_types().is_ctype(&QualifiedName::from_type_path(typ)),
+                    _ => false,
+                };
+                if is_ctype {
+                    (None, quote! { #ty(#len) })
+                } else {
+                    (None, len)
+                }
+            }
+            RustConversionType::FromArrayToPtr(ref shape) => {
+                let is_mut = matches!(
+                    self.unwrapped_type,
+                    Type::Ptr(TypePtr {
+                        mutability: Some(_),
+                        ..
+                    })
+                );
+                match (shape.by_reference, is_mut) {
+                    (false, _) => (None, quote! { #var.cast() }),
+                    (true, true) => (None, quote! { #var.as_mut_ptr().cast() }),
+                    (true, false) => (None, quote! { #var.as_ptr().cast() }),
+                }
+            }
+            RustConversionType::FromSliceToInitializerList => {
+                let msg = format!(
+                    "can't pass more than {} elements as a std::initializer_list",
+                    MAX_INITIALIZER_LIST_LEN
+                );
+                (
+                    Some(quote! {
+                        assert!(#var.len() <= #MAX_INITIALIZER_LIST_LEN, #msg);
+                    }),
+                    quote! { #var },
+                )
+            }
         }
     }
 }
@@ -0,0 +1,71 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rust `iter` methods for types with `begin()` and `end()` methods. The
+//! C++ side of this is in `codegen_cpp`.
+
+use quote::quote;
+use syn::{parse_quote, ForeignItem};
+
+use super::{unqualify::unqualify_ret_type, ImplBlockDetails, RsCodegenResult};
+use crate::{
+    conversion::{analysis::fun::FnPhase, apivec::ApiVec, iterators::find_iterable_types},
+    types::{make_ident, QualifiedName},
+};
+
+/// Generate an `iter` method for each type we can iterate over, along with
+/// the `cxx::bridge` declarations it needs. Returns whether there were any,
+/// in which case we'll need to include the C++ which we generate for them.
+pub(super) fn generate_iterators(
+    apis: &ApiVec<FnPhase>,
+) -> (Vec<(QualifiedName, RsCodegenResult)>, bool) {
+    let results: Vec<_> = find_iterable_types(apis)
+        .into_iter()
+        .map(|iterable| {
+            let ty = iterable.name.get_final_ident();
+            let iter_ty = iterable.iterator_type_name();
+            let begin_fn_name = iterable.begin_fn_name();
+            let next_fn_name = iterable.next_fn_name();
+            let element = iterable.element();
+            let bridge_ret_type = unqualify_ret_type(iterable.iterator.clone());
+            (
+                QualifiedName::new(iterable.name.get_namespace(), make_ident("iter")),
+                RsCodegenResult {
+                    extern_c_mod_items: vec![
+                        ForeignItem::Verbatim(quote! {
+                            type #iter_ty;
+                        }),
+                        parse_quote! {
+                            fn #begin_fn_name(obj: &#ty) -> UniquePtr<#iter_ty>;
+                        },
+                        parse_quote! {
+                            unsafe fn #next_fn_name(iter: Pin<&mut #iter_ty>) #bridge_ret_type;
+                        },
+                    ],
+                    impl_entry: Some(Box::new(ImplBlockDetails {
+                        item: parse_quote! {
+                            /// Iterates over the elements from `begin()` to `end()`.
+                            pub fn iter(&self) -> impl Iterator<Item = &#element> + '_ {
+                                let mut iter = cxxbridge::#begin_fn_name(self);
+                                ::std::iter::from_fn(move || {
+                                    // Safety: the elements belong to `self`, which we borrow
+                                    // for as long as the iterator exists.
+                                    unsafe { cxxbridge::#next_fn_name(iter.pin_mut()).as_ref() }
+                                })
+                            }
+                        },
+                        ty,
+                    })),
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+    let any = !results.is_empty();
+    (results, any)
+}
@@ -0,0 +1,44 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `cxx::bridge` declarations for the shims which take the error captured
+//! after a call to a function named in a `last_error!` directive. The C++
+//! side of this is in `codegen_cpp`.
+
+use syn::{parse_quote, ForeignItem};
+
+use super::{unqualify::unqualify_params, RsCodegenResult};
+use crate::{
+    conversion::{analysis::fun::FnPhase, apivec::ApiVec, last_errors::find_last_error_fetchers},
+    types::QualifiedName,
+};
+
+/// Declare the shim which takes the error captured by each error-fetching
+/// function named in a `last_error!` directive.
+pub(super) fn generate_last_error_shims(
+    apis: &ApiVec<FnPhase>,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    find_last_error_fetchers(apis)
+        .into_iter()
+        .map(|last_error| {
+            let error_ty = &last_error.error_ty;
+            let take_fn_name = last_error.take_fn_name();
+            let params = unqualify_params(parse_quote! { error: *mut #error_ty });
+            let extern_c_mod_items: Vec<ForeignItem> = vec![parse_quote! {
+                unsafe fn #take_fn_name(#params) -> bool;
+            }];
+            (
+                QualifiedName::new(last_error.fetch_fn.get_namespace(), take_fn_name),
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
@@ -7,7 +7,7 @@
 // except according to those terms.
 use crate::conversion::analysis::fun::{ArgumentAnalysis, ReceiverMutability};
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{quote, ToTokens};
 use std::borrow::Cow;
 use syn::{
     parse_quote, punctuated::Punctuated, token::Comma, FnArg, GenericArgument, PatType, Path,
@@ -16,11 +16,14 @@ use syn::{
 
 /// Function which can add explicit lifetime parameters to function signatures
 /// where necessary, based on analysis of parameters and return types.
-/// This is necessary only in one case - where the parameter is a Pin<&mut T>
-/// and the return type is some kind of reference - because lifetime elision
-/// is not smart enough to see inside a Pin.
+/// This is necessary where the return type is some kind of reference and
+/// either the function has several reference parameters, so analysis has
+/// worked out which one it borrows from (`return_borrows_from`), or the
+/// parameter is a Pin<&mut T>, because lifetime elision is not smart enough
+/// to see inside a Pin.
 pub(crate) fn add_explicit_lifetime_if_necessary<'r>(
     param_details: &[ArgumentAnalysis],
+    return_borrows_from: Option<usize>,
     mut params: Punctuated<FnArg, Comma>,
     ret_type: &'r ReturnType,
 ) -> (
@@ -28,12 +31,17 @@ pub(crate) fn add_explicit_lifetime_if_necessary<'r>(
     Punctuated<FnArg, Comma>,
     Cow<'r, ReturnType>,
 ) {
-    let has_mutable_receiver = param_details
-        .iter()
-        .any(|pd| matches!(pd.self_type, Some((_, ReceiverMutability::Mutable))));
-    if !has_mutable_receiver {
-        return (None, params, Cow::Borrowed(ret_type));
-    }
+    let borrowed_param = return_borrows_from
+        .or_else(|| {
+            param_details
+                .iter()
+                .position(|pd| matches!(pd.self_type, Some((_, ReceiverMutability::Mutable))))
+        })
+        .map(|idx| &param_details[idx]);
+    let borrowed_param = match borrowed_param {
+        None => return (None, params, Cow::Borrowed(ret_type)),
+        Some(borrowed_param) => borrowed_param,
+    };
     let new_return_type = match ret_type {
         ReturnType::Type(rarrow, boxed_type) => match boxed_type.as_ref() {
             Type::Reference(rtr) => {
@@ -58,15 +66,26 @@ pub(crate) fn add_explicit_lifetime_if_necessary<'r>(
     match new_return_type {
         None => (None, params, Cow::Borrowed(ret_type)),
         Some(new_return_type) => {
-            for mut param in params.iter_mut() {
-                match &mut param {
-                    FnArg::Typed(PatType { ty, .. }) => match ty.as_mut() {
-                        Type::Path(TypePath {
-                            path: Path { segments, .. },
-                            ..
-                        }) => add_lifetime_to_pinned_reference(segments).unwrap(),
-                        _ => panic!("Expected Pin<T>"),
-                    },
+            let borrowed_name = borrowed_param.name.to_token_stream().to_string();
+            for param in params.iter_mut() {
+                match param {
+                    FnArg::Typed(PatType { pat, ty, .. }) => {
+                        // The receiver may have been renamed to `self`.
+                        let name = pat.to_token_stream().to_string();
+                        if name != borrowed_name
+                            && !(name == "self" && borrowed_param.self_type.is_some())
+                        {
+                            continue;
+                        }
+                        match ty.as_mut() {
+                            Type::Path(TypePath {
+                                path: Path { segments, .. },
+                                ..
+                            }) => add_lifetime_to_pinned_reference(segments).unwrap(),
+                            Type::Reference(tyr) => add_lifetime_to_reference(tyr),
+                            _ => panic!("Expected a reference or Pin<T>"),
+                        }
+                    }
                     _ => panic!("Unexpected fnarg"),
                 }
             }
@@ -0,0 +1,59 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rust `apply` methods for the token types representing pointers to
+//! data members. The C++ side of this is in `codegen_cpp`.
+
+use syn::parse_quote;
+
+use super::{unqualify::unqualify_ret_type, ImplBlockDetails, RsCodegenResult};
+use crate::{
+    conversion::{
+        analysis::fun::FnPhase, apivec::ApiVec, member_pointers::find_data_member_pointers,
+    },
+    types::QualifiedName,
+};
+
+/// Generate an `apply` method for each token type representing a pointer
+/// to a data member, along with the `cxx::bridge` declaration of the C++
+/// function which follows the pointer.
+pub(super) fn generate_member_pointer_shims(
+    apis: &ApiVec<FnPhase>,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    find_data_member_pointers(apis)
+        .into_iter()
+        .map(|member_pointer| {
+            let token = member_pointer.token.get_final_ident();
+            let class = member_pointer.class.get_final_ident();
+            let class_path = member_pointer.class.to_type_path();
+            let field = &member_pointer.field;
+            let apply_fn_name = member_pointer.apply_fn_name();
+            let bridge_ret_type = unqualify_ret_type(parse_quote! { -> *const #field });
+            (
+                QualifiedName::new(member_pointer.token.get_namespace(), apply_fn_name.clone()),
+                RsCodegenResult {
+                    extern_c_mod_items: vec![parse_quote! {
+                        unsafe fn #apply_fn_name(member: &#token, obj: *const #class) #bridge_ret_type;
+                    }],
+                    impl_entry: Some(Box::new(ImplBlockDetails {
+                        item: parse_quote! {
+                            /// Returns the member of `obj` to which this points.
+                            pub fn apply<'a>(&self, obj: &'a #class_path) -> &'a #field {
+                                // Safety: the member belongs to `obj`, which we borrow
+                                // for as long as the reference exists.
+                                unsafe { &*cxxbridge::#apply_fn_name(self, obj) }
+                            }
+                        },
+                        ty: token,
+                    })),
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
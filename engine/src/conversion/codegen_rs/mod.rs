@@ -6,44 +6,80 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+mod abi_self_tests;
+mod base_derefs;
+mod bridge_shards;
+mod dense_matrices;
 mod fun_codegen;
 mod function_wrapper_rs;
 mod impl_item_creator;
+mod iterators;
+mod last_errors;
 mod lifetime;
+mod member_pointers;
 mod namespace_organizer;
 mod non_pod_struct;
+mod observer_registration;
+mod pin_helpers;
+mod qt_signals;
+mod refcounted;
+mod registry;
+mod result_types;
+mod shared_ptrs;
+mod smart_pointers;
+mod unique_arrays;
 pub(crate) mod unqualify;
+mod vectors;
+mod volatile_fields;
 
 use std::collections::{HashMap, HashSet};
 
 use autocxx_parser::IncludeCppConfig;
 
-use itertools::Itertools;
 use proc_macro2::{Span, TokenStream};
 use syn::{
     parse_quote, punctuated::Punctuated, token::Comma, Attribute, Expr, FnArg, ForeignItem,
-    ForeignItemFn, Ident, ImplItem, Item, ItemForeignMod, ItemMod, TraitItem,
+    ForeignItemFn, Ident, ImplItem, Item, ItemEnum, ItemForeignMod, ItemMod, TraitItem, Type,
 };
 
 use crate::{
     conversion::{
         codegen_rs::{
-            non_pod_struct::{make_non_pod, new_non_pod_struct},
+            non_pod_struct::{enum_layout, make_non_pod, new_non_pod_struct},
             unqualify::{unqualify_params, unqualify_ret_type},
         },
         doc_attr::get_doc_attr,
     },
+    known_types::known_types,
     types::{make_ident, Namespace, QualifiedName},
 };
 use impl_item_creator::create_impl_items;
 
 use self::{
+    abi_self_tests::generate_abi_self_tests,
+    base_derefs::generate_base_derefs,
+    bridge_shards::{shard_bridge, BridgeShard},
+    dense_matrices::generate_dense_matrices,
     fun_codegen::gen_function,
+    iterators::generate_iterators,
+    last_errors::generate_last_error_shims,
+    member_pointers::generate_member_pointer_shims,
     namespace_organizer::{HasNs, NamespaceEntries},
+    observer_registration::generate_observer_registrations,
+    pin_helpers::generate_pin_helpers,
+    qt_signals::generate_qt_signals,
+    refcounted::generate_refcounted_shims,
+    registry::generate_registry,
+    result_types::generate_result_shims,
+    shared_ptrs::generate_shared_ptr_shims,
+    smart_pointers::generate_smart_pointer_shims,
+    unique_arrays::generate_unique_array_shims,
+    vectors::generate_vector_shims,
+    volatile_fields::generate_volatile_field_accessors,
 };
 
 use super::{
-    analysis::fun::{FnPhase, ReceiverMutability},
+    analysis::fun::{FnAnalysis, FnKind, FnPhase, MethodKind, ReceiverMutability},
     api::{AnalysisPhase, Api, SubclassName, TypeKind, TypedefKind},
     convert_error::ErrorContextType,
 };
@@ -131,6 +167,7 @@ fn get_string_items() -> Vec<Item> {
 /// existing lumps of code within the Api structures.
 pub(crate) struct RsCodeGenerator<'a> {
     include_list: &'a [String],
+    has_module_imports: bool,
     bindgen_mod: ItemMod,
     original_name_map: CppNameMap,
     config: &'a IncludeCppConfig,
@@ -142,12 +179,14 @@ impl<'a> RsCodeGenerator<'a> {
     pub(crate) fn generate_rs_code(
         all_apis: ApiVec<FnPhase>,
         include_list: &'a [String],
+        has_module_imports: bool,
         bindgen_mod: ItemMod,
         config: &'a IncludeCppConfig,
         header_name: Option<String>,
     ) -> Vec<Item> {
         let c = Self {
             include_list,
+            has_module_imports,
             bindgen_mod,
             original_name_map: original_name_map_from_apis(&all_apis),
             config,
@@ -163,72 +202,154 @@ impl<'a> RsCodeGenerator<'a> {
         let methods_by_superclass = self.accumulate_superclass_methods(&all_apis);
         let subclasses_with_a_single_trivial_constructor =
             find_trivially_constructed_subclasses(&all_apis);
+        let pod_types = find_pod_types(&all_apis);
+        let base_derefs = generate_base_derefs(&all_apis);
+        let pin_helpers = generate_pin_helpers(&all_apis);
+        let observer_registrations = generate_observer_registrations(&all_apis, self.config);
+        let (qt_signals, has_qt_signals) = generate_qt_signals(&all_apis, self.config);
+        let (refcounted_shims, has_refcounted_shims) = generate_refcounted_shims(&all_apis);
+        let (shared_ptr_shims, has_shared_ptr_shims) =
+            generate_shared_ptr_shims(&all_apis, self.config);
+        let (dense_matrices, has_dense_matrices) = generate_dense_matrices(&all_apis, self.config);
+        let (iterators, has_iterators) = generate_iterators(&all_apis);
+        // Shims whose C++ side is generated by codegen_cpp. If there are
+        // any, we'll need to include that C++.
+        let cpp_shims: Vec<_> = [
+            generate_member_pointer_shims(&all_apis),
+            generate_unique_array_shims(&all_apis),
+            generate_result_shims(&all_apis),
+            generate_smart_pointer_shims(&all_apis),
+            generate_vector_shims(&all_apis, self.config),
+            generate_last_error_shims(&all_apis),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let has_cpp_shims = !cpp_shims.is_empty();
+        let registry = generate_registry(&all_apis, self.config, &self.original_name_map);
+        let (abi_self_tests, has_abi_self_tests) = generate_abi_self_tests(&all_apis, self.config);
         // Now let's generate the Rust code.
-        let (rs_codegen_results_and_namespaces, additional_cpp_needs): (Vec<_>, Vec<_>) = all_apis
-            .into_iter()
-            .map(|api| {
-                let more_cpp_needed = api.needs_cpp_codegen();
-                let name = api.name().clone();
-                let gen = self.generate_rs_for_api(
-                    api,
-                    &methods_by_superclass,
-                    &subclasses_with_a_single_trivial_constructor,
-                );
-                ((name, gen), more_cpp_needed)
-            })
-            .unzip();
+        let (mut rs_codegen_results_and_namespaces, additional_cpp_needs): (Vec<_>, Vec<_>) =
+            all_apis
+                .into_iter()
+                .map(|api| {
+                    let more_cpp_needed = api.needs_cpp_codegen();
+                    let name = api.name().clone();
+                    let cfg = self
+                        .config
+                        .cfg_for(&api.name_for_allowlist().to_cpp_name())
+                        .map(|cfg| -> Attribute {
+                            parse_quote! { #[#cfg] }
+                        });
+                    let mut gen = self.generate_rs_for_api(
+                        api,
+                        &methods_by_superclass,
+                        &subclasses_with_a_single_trivial_constructor,
+                        &pod_types,
+                    );
+                    gen.cfg = cfg;
+                    ((name, gen), more_cpp_needed)
+                })
+                .unzip();
+        rs_codegen_results_and_namespaces.extend(base_derefs);
+        rs_codegen_results_and_namespaces.extend(pin_helpers);
+        rs_codegen_results_and_namespaces.extend(observer_registrations);
+        rs_codegen_results_and_namespaces.extend(qt_signals);
+        rs_codegen_results_and_namespaces.extend(refcounted_shims);
+        rs_codegen_results_and_namespaces.extend(shared_ptr_shims);
+        rs_codegen_results_and_namespaces.extend(dense_matrices);
+        rs_codegen_results_and_namespaces.extend(iterators);
+        rs_codegen_results_and_namespaces.extend(cpp_shims);
+        rs_codegen_results_and_namespaces.extend(registry);
+        rs_codegen_results_and_namespaces.extend(abi_self_tests);
         // First, the hierarchy of mods containing lots of 'use' statements
         // which is the final API exposed as 'ffi'.
         let mut use_statements =
-            Self::generate_final_use_statements(&rs_codegen_results_and_namespaces);
+            Self::generate_final_use_statements(&rs_codegen_results_and_namespaces, self.config);
         // And work out what we need for the bindgen mod.
         let bindgen_root_items =
             self.generate_final_bindgen_mods(&rs_codegen_results_and_namespaces);
         // Both of the above ('use' hierarchy and bindgen mod) are organized into
         // sub-mods by namespace. From here on, things are flat.
-        let (_, rs_codegen_results): (Vec<_>, Vec<_>) =
-            rs_codegen_results_and_namespaces.into_iter().unzip();
-        let (extern_c_mod_items, extern_rust_mod_items, all_items, bridge_items): (
-            Vec<_>,
-            Vec<_>,
-            Vec<_>,
-            Vec<_>,
-        ) = rs_codegen_results
+        let (bridge_contributions, all_items): (Vec<_>, Vec<_>) = rs_codegen_results_and_namespaces
             .into_iter()
-            .map(|api| {
+            .map(|(name, api)| {
                 (
-                    api.extern_c_mod_items,
-                    api.extern_rust_mod_items,
+                    (
+                        name,
+                        BridgeShard {
+                            bridge_items: api.bridge_items,
+                            extern_c_mod_items: api.extern_c_mod_items,
+                            extern_rust_mod_items: api.extern_rust_mod_items,
+                        },
+                    ),
                     api.global_items,
-                    api.bridge_items,
                 )
             })
-            .multiunzip();
-        // Items for the [cxx::bridge] mod...
-        let mut bridge_items: Vec<Item> = bridge_items.into_iter().flatten().collect();
-        // Things to include in the "extern "C"" mod passed within the cxx::bridge
-        let mut extern_c_mod_items: Vec<ForeignItem> =
-            extern_c_mod_items.into_iter().flatten().collect();
-        // The same for extern "Rust"
-        let mut extern_rust_mod_items = extern_rust_mod_items.into_iter().flatten().collect();
+            .unzip();
+        // Items for the [cxx::bridge] mod(s)...
+        let shards = shard_bridge(bridge_contributions, self.config.bridge_shards());
         // And a list of global items to include at the top level.
         let mut all_items: Vec<Item> = all_items.into_iter().flatten().collect();
         // And finally any C++ we need to generate. And by "we" I mean autocxx not cxx.
-        let has_additional_cpp_needs = additional_cpp_needs.into_iter().any(std::convert::identity);
-        extern_c_mod_items.extend(self.build_include_foreign_items(has_additional_cpp_needs));
-        // We will always create an extern "C" mod even if bindgen
-        // didn't generate one, e.g. because it only generated types.
-        // We still want cxx to know about those types.
-        let mut extern_c_mod: ItemForeignMod = parse_quote!(
-            extern "C++" {}
-        );
-        extern_c_mod.items.append(&mut extern_c_mod_items);
-        bridge_items.push(Self::make_foreign_mod_unsafe(extern_c_mod));
-        let mut extern_rust_mod: ItemForeignMod = parse_quote!(
-            extern "Rust" {}
-        );
-        extern_rust_mod.items.append(&mut extern_rust_mod_items);
-        bridge_items.push(Item::ForeignMod(extern_rust_mod));
+        let has_additional_cpp_needs = has_cpp_shims
+            || has_qt_signals
+            || has_refcounted_shims
+            || has_shared_ptr_shims
+            || has_dense_matrices
+            || has_iterators
+            || has_abi_self_tests
+            || additional_cpp_needs.into_iter().any(std::convert::identity);
+        let include_foreign_items = self.build_include_foreign_items(has_additional_cpp_needs);
+        let sharded = shards.len() > 1;
+        let mut facade_uses: Vec<Item> = Vec::new();
+        let mut bridge_mods: Vec<Item> = Vec::new();
+        for (idx, shard) in shards.into_iter().enumerate() {
+            let bridge_mod_name = if sharded {
+                make_ident(format!("cxxbridge{}", idx))
+            } else {
+                make_ident("cxxbridge")
+            };
+            if idx == 0 {
+                facade_uses.push(parse_quote! {
+                    pub use super::#bridge_mod_name::*;
+                });
+            } else {
+                let free_functions = shard.free_functions();
+                facade_uses.push(parse_quote! {
+                    pub use super::#bridge_mod_name::{#(#free_functions),*};
+                });
+            }
+            let BridgeShard {
+                mut bridge_items,
+                extern_c_mod_items,
+                extern_rust_mod_items,
+            } = shard;
+            // We will always create an extern "C" mod even if bindgen
+            // didn't generate one, e.g. because it only generated types.
+            // We still want cxx to know about those types.
+            let mut extern_c_mod: ItemForeignMod = parse_quote!(
+                extern "C++" {}
+            );
+            extern_c_mod.items.extend(extern_c_mod_items);
+            extern_c_mod
+                .items
+                .extend(include_foreign_items.iter().cloned());
+            bridge_items.push(Self::make_foreign_mod_unsafe(extern_c_mod));
+            if idx == 0 {
+                let mut extern_rust_mod: ItemForeignMod = parse_quote!(
+                    extern "Rust" {}
+                );
+                extern_rust_mod.items.extend(extern_rust_mod_items);
+                bridge_items.push(Item::ForeignMod(extern_rust_mod));
+            }
+            bridge_mods.push(Item::Mod(parse_quote! {
+                #[cxx::bridge]
+                mod #bridge_mod_name {
+                    #(#bridge_items)*
+                }
+            }));
+        }
         // The extensive use of parse_quote here could end up
         // being a performance bottleneck. If so, we might want
         // to set the 'contents' field of the ItemMod
@@ -242,12 +363,17 @@ impl<'a> RsCodeGenerator<'a> {
             })];
             all_items.push(Item::Mod(self.bindgen_mod));
         }
-        all_items.push(Item::Mod(parse_quote! {
-            #[cxx::bridge]
-            mod cxxbridge {
-                #(#bridge_items)*
-            }
-        }));
+        all_items.append(&mut bridge_mods);
+        if sharded {
+            // Everything else refers to items in the bridge as
+            // cxxbridge::foo, so gather them back together.
+            all_items.push(Item::Mod(parse_quote! {
+                #[allow(unused_imports)]
+                mod cxxbridge {
+                    #(#facade_uses)*
+                }
+            }));
+        }
 
         all_items.push(Item::Use(parse_quote! {
             #[allow(unused_imports)]
@@ -287,7 +413,12 @@ impl<'a> RsCodeGenerator<'a> {
     }
 
     fn build_include_foreign_items(&self, has_additional_cpp_needs: bool) -> Vec<ForeignItem> {
-        let extra_inclusion = if has_additional_cpp_needs {
+        // If any headers were left out of the include list, or modules
+        // need importing, the generated header is needed to do that instead.
+        let extra_inclusion = if has_additional_cpp_needs
+            || self.has_module_imports
+            || self.include_list.len() != self.config.inclusions.len()
+        {
             Some(self.header_name.clone().unwrap())
         } else {
             None
@@ -306,31 +437,44 @@ impl<'a> RsCodeGenerator<'a> {
     /// mod hierarchy according to C++ namespaces.
     fn generate_final_use_statements(
         input_items: &[(QualifiedName, RsCodegenResult)],
+        config: &IncludeCppConfig,
     ) -> Vec<Item> {
         let mut output_items = Vec::new();
         let ns_entries = NamespaceEntries::new(input_items);
-        Self::append_child_use_namespace(&ns_entries, &mut output_items);
+        Self::append_child_use_namespace(&ns_entries, &mut output_items, config);
         output_items
     }
 
     fn append_child_use_namespace(
         ns_entries: &NamespaceEntries<(QualifiedName, RsCodegenResult)>,
         output_items: &mut Vec<Item>,
+        config: &IncludeCppConfig,
     ) {
+        // Any strip_prefix! directives are applied only here, as we
+        // expose items to the user. Internally, everything retains its
+        // original name.
+        let strip = |id: &Ident| config.strip_prefix(&id.to_string()).map(make_ident);
         for (name, codegen) in ns_entries.entries() {
             output_items.extend(codegen.materializations.iter().map(|materialization| {
-                match materialization {
-                    Use::UsedFromCxxBridgeWithAlias(alias) => {
-                        Self::generate_cxx_use_stmt(name, Some(alias))
+                let item = match materialization {
+                    Use::UsedFromCxxBridgeWithAlias(alias) => Self::generate_cxx_use_stmt(
+                        name,
+                        Some(&strip(alias).unwrap_or_else(|| alias.clone())),
+                    ),
+                    Use::UsedFromCxxBridge => {
+                        Self::generate_cxx_use_stmt(name, strip(&name.get_final_ident()).as_ref())
                     }
-                    Use::UsedFromCxxBridge => Self::generate_cxx_use_stmt(name, None),
-                    Use::UsedFromBindgen => Self::generate_bindgen_use_stmt(name),
+                    Use::UsedFromBindgen => Self::generate_bindgen_use_stmt(
+                        name,
+                        strip(&name.get_final_ident()).as_ref(),
+                    ),
                     Use::SpecificNameFromBindgen(id) => {
                         let name = QualifiedName::new(name.get_namespace(), id.clone());
-                        Self::generate_bindgen_use_stmt(&name)
+                        Self::generate_bindgen_use_stmt(&name, strip(id).as_ref())
                     }
                     Use::Custom(item) => *item.clone(),
-                }
+                };
+                Self::add_cfg_to_item(item, &codegen.cfg)
             }));
         }
         for (child_name, child_ns_entries) in ns_entries.children() {
@@ -345,6 +489,7 @@ impl<'a> RsCodeGenerator<'a> {
             Self::append_child_use_namespace(
                 child_ns_entries,
                 &mut new_mod.content.as_mut().unwrap().1,
+                config,
             );
             output_items.push(Item::Mod(new_mod));
         }
@@ -391,7 +536,10 @@ impl<'a> RsCodeGenerator<'a> {
                 impl_entries_by_type
                     .entry(impl_entry.ty.clone())
                     .or_default()
-                    .push(&impl_entry.item);
+                    .push(Self::add_cfg_to_impl_item(
+                        impl_entry.item.clone(),
+                        &item.1.cfg,
+                    ));
             }
             if let Some(trait_impl_entry) = &item.1.trait_impl_entry {
                 trait_impl_entries_by_trait_and_ty
@@ -400,14 +548,23 @@ impl<'a> RsCodeGenerator<'a> {
                     .push(&trait_impl_entry.item);
             }
         }
-        for (ty, entries) in impl_entries_by_type.into_iter() {
+        // Sort these too, so our output doesn't depend on hash ordering.
+        let mut impl_entries_by_type: Vec<_> = impl_entries_by_type.into_iter().collect();
+        impl_entries_by_type.sort_by_cached_key(|(ty, _)| ty.to_string());
+        let mut trait_impl_entries_by_trait_and_ty: Vec<_> =
+            trait_impl_entries_by_trait_and_ty.into_iter().collect();
+        trait_impl_entries_by_trait_and_ty.sort_by_cached_key(|(key, _)| {
+            let (trt, ty) = (&key.trait_signature, &key.ty);
+            quote! { #trt for #ty }.to_string()
+        });
+        for (ty, entries) in impl_entries_by_type {
             output_items.push(Item::Impl(parse_quote! {
                 impl #ty {
                     #(#entries)*
                 }
             }))
         }
-        for (key, entries) in trait_impl_entries_by_trait_and_ty.into_iter() {
+        for (key, entries) in trait_impl_entries_by_trait_and_ty {
             let unsafety = key.unsafety;
             let ty = key.ty;
             let trt = key.trait_signature;
@@ -435,6 +592,32 @@ impl<'a> RsCodeGenerator<'a> {
         }
     }
 
+    /// Attach any `generate_cfg!` condition to an item we're exposing to
+    /// the user. We deliberately don't do this within the `cxx::bridge`
+    /// mod, since `cxx_gen` can't evaluate `cfg` conditions; instead the
+    /// C++ item simply won't be found on platforms where it doesn't exist.
+    fn add_cfg_to_item(mut item: Item, cfg: &Option<Attribute>) -> Item {
+        if let Some(cfg) = cfg {
+            match &mut item {
+                Item::Use(ref mut u) => u.attrs.push(cfg.clone()),
+                Item::Struct(ref mut s) => s.attrs.push(cfg.clone()),
+                _ => {}
+            }
+        }
+        item
+    }
+
+    fn add_cfg_to_impl_item(mut item: ImplItem, cfg: &Option<Attribute>) -> ImplItem {
+        if let Some(cfg) = cfg {
+            match &mut item {
+                ImplItem::Method(ref mut m) => m.attrs.push(cfg.clone()),
+                ImplItem::Const(ref mut c) => c.attrs.push(cfg.clone()),
+                _ => {}
+            }
+        }
+        item
+    }
+
     fn id_to_expr(id: &Ident) -> Expr {
         parse_quote! { #id }
     }
@@ -456,6 +639,7 @@ impl<'a> RsCodeGenerator<'a> {
         api: Api<FnPhase>,
         associated_methods: &HashMap<QualifiedName, Vec<SuperclassMethod>>,
         subclasses_with_a_single_trivial_constructor: &HashSet<QualifiedName>,
+        pod_types: &HashSet<QualifiedName>,
     ) -> RsCodegenResult {
         let name = api.name().clone();
         let id = name.get_final_ident();
@@ -475,8 +659,30 @@ impl<'a> RsCodeGenerator<'a> {
                 }
             }
             Api::Function { fun, analysis, .. } => {
-                gen_function(name.get_namespace(), *fun, analysis, cpp_call_name)
+                let default_impl = Self::generate_pod_default_impl(&analysis, pod_types);
+                let mut gen = gen_function(
+                    name.get_namespace(),
+                    *fun,
+                    analysis,
+                    cpp_call_name,
+                    self.config.instrument_calls(),
+                );
+                if default_impl.is_some() {
+                    gen.trait_impl_entry = default_impl;
+                }
+                gen
             }
+            Api::Const {
+                const_item,
+                impl_for: Some(impl_for),
+                ..
+            } => RsCodegenResult {
+                impl_entry: Some(Box::new(ImplBlockDetails {
+                    item: parse_quote! { #const_item },
+                    ty: impl_for.get_final_ident(),
+                })),
+                ..Default::default()
+            },
             Api::Const { const_item, .. } => RsCodegenResult {
                 bindgen_mod_items: vec![Item::Const(const_item)],
                 materializations: vec![Use::UsedFromBindgen],
@@ -506,18 +712,45 @@ impl<'a> RsCodeGenerator<'a> {
                     layout,
                 )
             }
+            Api::FnPtrTypedef { item, .. } => {
+                // cxx can't pass an Option<fn> itself, so wrap it in a type
+                // which we can tell cxx is trivial.
+                let doc_attr = get_doc_attr(&item.attrs);
+                let fn_ptr = item.ty;
+                RsCodegenResult {
+                    global_items: self.generate_extern_type_impl(TypeKind::Pod, &name),
+                    extern_c_mod_items: vec![self.generate_cxxbridge_type(&name, true, None)],
+                    bindgen_mod_items: vec![parse_quote! {
+                        #doc_attr
+                        #[repr(transparent)]
+                        #[derive(Clone, Copy)]
+                        pub struct #id(pub #fn_ptr);
+                    }],
+                    materializations: vec![Use::UsedFromCxxBridge],
+                    ..Default::default()
+                }
+            }
             Api::Enum { item, .. } => {
                 let doc_attr = get_doc_attr(&item.attrs);
-                self.generate_type(
+                let (item, conversions) = if self.config.is_bitflags(&name.to_cpp_name()) {
+                    let conversions = Self::generate_bitflags_conversions(&item);
+                    (Self::generate_bitflags_type(item), conversions)
+                } else {
+                    let conversions = Self::generate_enum_conversions(&item);
+                    (Item::Enum(item), conversions)
+                };
+                let mut result = self.generate_type(
                     &name,
                     id,
                     TypeKind::Pod,
                     true,
                     true,
-                    || Some((Item::Enum(item), doc_attr)),
+                    || Some((item, doc_attr)),
                     associated_methods,
                     None,
-                )
+                );
+                result.bindgen_mod_items.push(conversions);
+                result
             }
             Api::ForwardDeclaration { .. } | Api::ConcreteType { .. } => self.generate_type(
                 &name,
@@ -529,10 +762,29 @@ impl<'a> RsCodeGenerator<'a> {
                 associated_methods,
                 None,
             ),
-            Api::CType { .. } => RsCodegenResult {
-                extern_c_mod_items: vec![ForeignItem::Verbatim(quote! {
-                    type #id = autocxx::#id;
-                })],
+            Api::CType { typename, .. } if known_types().is_external_type(&typename) => {
+                match Self::generate_external_type_alias(&id, &typename) {
+                    Ok(result) => result,
+                    Err(err) => Self::generate_error_entry(err, ErrorContext::new_for_item(id)),
+                }
+            }
+            Api::CType { typename, .. } => {
+                let path = known_types().known_type_type_path(&typename).unwrap();
+                RsCodegenResult {
+                    extern_c_mod_items: vec![ForeignItem::Verbatim(quote! {
+                        type #id = #path;
+                    })],
+                    ..Default::default()
+                }
+            }
+            Api::ExternCppType { path, .. } => RsCodegenResult {
+                // The other include_cpp! already implemented cxx::ExternType
+                // and any smart pointer traits, so just refer to its type.
+                bindgen_mod_items: vec![parse_quote! {
+                    pub use #path;
+                }],
+                extern_c_mod_items: vec![self.generate_cxxbridge_type(&name, true, None)],
+                materializations: vec![Use::UsedFromCxxBridge],
                 ..Default::default()
             },
             Api::RustType { path, .. } => RsCodegenResult {
@@ -553,6 +805,10 @@ impl<'a> RsCodeGenerator<'a> {
                 }],
                 ..Default::default()
             },
+            Api::RustSubclassFn { details, .. } if !details.is_virtual => {
+                // Rust can only call this method, via the supers trait.
+                RsCodegenResult::default()
+            }
             Api::RustSubclassFn {
                 details, subclass, ..
             } => Self::generate_subclass_fn(id, *details, subclass),
@@ -828,12 +1084,27 @@ impl<'a> RsCodeGenerator<'a> {
                         make_non_pod(s, layout);
                     } else {
                         // enum
-                        item = Item::Struct(new_non_pod_struct(id.clone()));
+                        let layout = match &item {
+                            Item::Enum(e) => enum_layout(e),
+                            _ => None,
+                        };
+                        item = Item::Struct(new_non_pod_struct(id.clone(), layout));
+                    }
+                }
+                if matches!(type_kind, TypeKind::Pod) {
+                    if let Item::Struct(ref mut s) = item {
+                        bindgen_mod_items.extend(generate_volatile_field_accessors(
+                            s,
+                            name,
+                            self.config,
+                        ));
                     }
                 }
                 bindgen_mod_items.push(item);
+                let mut global_items = self.generate_extern_type_impl(type_kind, name);
+                global_items.push(self.generate_autocxx_type_impl(name));
                 RsCodegenResult {
-                    global_items: self.generate_extern_type_impl(type_kind, name),
+                    global_items,
                     bridge_items: create_impl_items(&id, movable, destroyable, self.config),
                     extern_c_mod_items: vec![self.generate_cxxbridge_type(name, true, None)],
                     bindgen_mod_items,
@@ -848,6 +1119,7 @@ impl<'a> RsCodeGenerator<'a> {
                 bindgen_mod_items.push(Item::Use(parse_quote! { pub use cxxbridge::#id; }));
                 let doc_attr = orig_item.and_then(|maybe_item| maybe_item.1);
                 RsCodegenResult {
+                    global_items: vec![self.generate_autocxx_type_impl(name)],
                     extern_c_mod_items: vec![self.generate_cxxbridge_type(name, false, doc_attr)],
                     bindgen_mod_items,
                     materializations,
@@ -880,27 +1152,30 @@ impl<'a> RsCodeGenerator<'a> {
                     };
                     let ret_type = &method.ret_type;
                     let unsafe_token = method.requires_unsafe.wrapper_token();
-                    if method.is_pure_virtual {
+                    let super_fn: TraitItem = parse_quote!(
+                        #unsafe_token fn #super_id(#params) #ret_type;
+                    );
+                    if !method.is_virtual {
+                        (Some(super_fn), None)
+                    } else if method.is_pure_virtual {
                         (
                             None,
-                            parse_quote!(
+                            Some(parse_quote!(
                                 #unsafe_token fn #id(#params) #ret_type;
-                            ),
+                            )),
                         )
                     } else {
-                        let a: Option<TraitItem> = Some(parse_quote!(
-                            #unsafe_token fn #super_id(#params) #ret_type;
-                        ));
                         let b: TraitItem = parse_quote!(
                             #unsafe_token fn #id(#params) #ret_type {
                                 self.#super_id(#param_names)
                             }
                         );
-                        (a, b)
+                        (Some(super_fn), Some(b))
                     }
                 })
                 .unzip();
             let supers: Vec<_> = supers.into_iter().flatten().collect();
+            let mains: Vec<_> = mains.into_iter().flatten().collect();
             let supers_name = SubclassName::get_supers_trait_name(name).get_final_ident();
             let methods_name = SubclassName::get_methods_trait_name(name).get_final_ident();
             if !supers.is_empty() {
@@ -929,6 +1204,38 @@ impl<'a> RsCodeGenerator<'a> {
         }
     }
 
+    /// Declare a type registered by a [crate::KnownTypeExtension] to cxx.
+    /// The user is responsible for implementing `cxx::ExternType` for it.
+    fn generate_external_type_alias(
+        id: &Ident,
+        typename: &QualifiedName,
+    ) -> Result<RsCodegenResult, ConvertError> {
+        let unknown = || ConvertError::UnknownType(typename.to_cpp_name());
+        let cpp_name = QualifiedName::new_from_cpp_name(
+            &known_types()
+                .special_cpp_name(typename)
+                .ok_or_else(unknown)?,
+        );
+        let path = known_types()
+            .known_type_type_path(typename)
+            .ok_or_else(unknown)?;
+        let cpp_id = cpp_name.get_final_item();
+        let ns_attr = if cpp_name.get_namespace().is_empty() {
+            None
+        } else {
+            let ns = cpp_name.get_namespace().to_cpp_string();
+            Some(quote! { #[namespace = #ns] })
+        };
+        Ok(RsCodegenResult {
+            extern_c_mod_items: vec![ForeignItem::Verbatim(quote! {
+                #ns_attr
+                #[cxx_name = #cpp_id]
+                type #id = #path;
+            })],
+            ..Default::default()
+        })
+    }
+
     /// Generates something in the output mod that will carry a docstring
     /// explaining why a given type or function couldn't have bindings
     /// generated.
@@ -974,6 +1281,155 @@ impl<'a> RsCodeGenerator<'a> {
         }
     }
 
+    /// Generates a newtype to stand in for a C++ enum which the user has
+    /// asked us to treat as bitflags. Unlike a Rust enum, this can soundly
+    /// hold any combination of the enumerators' values.
+    /// The integer type underlying an enum, as bindgen told us.
+    fn enum_repr(item: &ItemEnum) -> Ident {
+        item.attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("repr"))
+            .and_then(|attr| attr.parse_args::<Ident>().ok())
+            .unwrap_or_else(|| make_ident("u32"))
+    }
+
+    /// Conversions between an enum and its underlying integer type. Not
+    /// every integer is a valid value of the enum, so conversion from the
+    /// integer is fallible, returning the integer as the error.
+    fn generate_enum_conversions(item: &ItemEnum) -> Item {
+        let id = &item.ident;
+        let repr = Self::enum_repr(item);
+        let variants = item.variants.iter().map(|variant| &variant.ident);
+        Item::Verbatim(quote! {
+            impl ::std::convert::From<#id> for #repr {
+                fn from(value: #id) -> Self {
+                    value as #repr
+                }
+            }
+            impl ::std::convert::TryFrom<#repr> for #id {
+                type Error = #repr;
+                fn try_from(value: #repr) -> ::std::result::Result<Self, #repr> {
+                    #(
+                        if value == Self::#variants as #repr {
+                            return Ok(Self::#variants);
+                        }
+                    )*
+                    Err(value)
+                }
+            }
+        })
+    }
+
+    /// Conversions between a bitflags newtype and its underlying integer
+    /// type. Any combination of bits is allowed, so these can't fail.
+    fn generate_bitflags_conversions(item: &ItemEnum) -> Item {
+        let id = &item.ident;
+        let repr = Self::enum_repr(item);
+        Item::Verbatim(quote! {
+            impl ::std::convert::From<#id> for #repr {
+                fn from(value: #id) -> Self {
+                    value.0
+                }
+            }
+            impl ::std::convert::From<#repr> for #id {
+                fn from(value: #repr) -> Self {
+                    Self(value)
+                }
+            }
+        })
+    }
+
+    fn generate_bitflags_type(item: ItemEnum) -> Item {
+        let id = &item.ident;
+        let repr = Self::enum_repr(&item);
+        let doc_attrs = item.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+        let consts = item.variants.iter().filter_map(|variant| {
+            let (_, value) = variant.discriminant.as_ref()?;
+            let variant_id = &variant.ident;
+            let variant_doc_attrs = variant
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("doc"));
+            Some(quote! {
+                #(#variant_doc_attrs)*
+                pub const #variant_id: Self = Self(#value);
+            })
+        });
+        Item::Verbatim(quote! {
+            #(#doc_attrs)*
+            #[repr(transparent)]
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+            pub struct #id(pub #repr);
+            #[allow(non_upper_case_globals)]
+            impl #id {
+                #(#consts)*
+                /// Whether all the flags set in `other` are also set in `self`.
+                pub fn contains(self, other: Self) -> bool {
+                    self.0 & other.0 == other.0
+                }
+            }
+            impl ::std::ops::BitOr for #id {
+                type Output = Self;
+                fn bitor(self, other: Self) -> Self {
+                    Self(self.0 | other.0)
+                }
+            }
+            impl ::std::ops::BitOrAssign for #id {
+                fn bitor_assign(&mut self, other: Self) {
+                    self.0 |= other.0;
+                }
+            }
+            impl ::std::ops::BitAnd for #id {
+                type Output = Self;
+                fn bitand(self, other: Self) -> Self {
+                    Self(self.0 & other.0)
+                }
+            }
+            impl ::std::ops::BitAndAssign for #id {
+                fn bitand_assign(&mut self, other: Self) {
+                    self.0 &= other.0;
+                }
+            }
+        })
+    }
+
+    /// POD types can be freely moved in Rust, so if one has a default
+    /// constructor, we can implement `Default` by calling it on some
+    /// uninitialized storage.
+    fn generate_pod_default_impl(
+        analysis: &FnAnalysis,
+        pod_types: &HashSet<QualifiedName>,
+    ) -> Option<Box<TraitImplBlockDetails>> {
+        match &analysis.kind {
+            FnKind::Method {
+                impl_for,
+                method_kind: MethodKind::Constructor { is_default: true },
+            } if pod_types.contains(impl_for)
+                && analysis.ignore_reason.is_ok()
+                && analysis.externally_callable =>
+            {
+                let cxxbridge_name = &analysis.cxxbridge_name;
+                Some(Box::new(TraitImplBlockDetails {
+                    item: parse_quote! {
+                        fn default() -> Self {
+                            let mut storage = ::std::mem::MaybeUninit::<Self>::uninit();
+                            unsafe {
+                                cxxbridge::#cxxbridge_name(storage.as_mut_ptr());
+                                storage.assume_init()
+                            }
+                        }
+                    },
+                    key: TraitImplSignature {
+                        ty: Type::Path(impl_for.to_type_path()),
+                        trait_signature: parse_quote! { Default },
+                        unsafety: None,
+                    },
+                }))
+            }
+            _ => None,
+        }
+    }
+
     fn generate_cxx_use_stmt(name: &QualifiedName, alias: Option<&Ident>) -> Item {
         let segs = Self::find_output_mod_root(name.get_namespace())
             .chain(std::iter::once(make_ident("cxxbridge")))
@@ -988,11 +1444,16 @@ impl<'a> RsCodeGenerator<'a> {
         })
     }
 
-    fn generate_bindgen_use_stmt(name: &QualifiedName) -> Item {
+    fn generate_bindgen_use_stmt(name: &QualifiedName, alias: Option<&Ident>) -> Item {
         let segs =
             Self::find_output_mod_root(name.get_namespace()).chain(name.get_bindgen_path_idents());
-        Item::Use(parse_quote! {
-            pub use #(#segs)::*;
+        Item::Use(match alias {
+            None => parse_quote! {
+                pub use #(#segs)::*;
+            },
+            Some(alias) => parse_quote! {
+                pub use #(#segs)::* as #alias;
+            },
         })
     }
 
@@ -1012,6 +1473,18 @@ impl<'a> RsCodeGenerator<'a> {
         })]
     }
 
+    fn generate_autocxx_type_impl(&self, tyname: &QualifiedName) -> Item {
+        let tynamestring = namespaced_name_using_original_name_map(tyname, &self.original_name_map);
+        let ns = tyname.get_namespace().to_cpp_string();
+        let fulltypath = tyname.get_bindgen_path_idents();
+        Item::Impl(parse_quote! {
+            impl autocxx::AutocxxType for #(#fulltypath)::* {
+                const CPP_NAME: &'static str = #tynamestring;
+                const CPP_NAMESPACE: &'static str = #ns;
+            }
+        })
+    }
+
     fn generate_cxxbridge_type(
         &self,
         name: &QualifiedName,
@@ -1024,12 +1497,12 @@ impl<'a> RsCodeGenerator<'a> {
         // If we have a nested class, B::C, within namespace A,
         // we actually have to tell cxx that we have nested class C
         // within namespace A.
-        let mut ns_components: Vec<_> = ns.iter().cloned().collect();
+        let mut ns_components: Vec<_> = ns.cpp_iter().map(str::to_string).collect();
         let mut cxx_name = None;
         if let Some(cpp_name) = self.original_name_map.get(name) {
             let cpp_name = QualifiedName::new_from_cpp_name(cpp_name);
             cxx_name = Some(cpp_name.get_final_item().to_string());
-            ns_components.extend(cpp_name.ns_segment_iter().cloned());
+            ns_components.extend(cpp_name.get_namespace().cpp_iter().map(str::to_string));
         };
 
         let mut for_extern_c_ts = if !ns_components.is_empty() {
@@ -1076,6 +1549,17 @@ impl<'a> RsCodeGenerator<'a> {
     }
 }
 
+fn find_pod_types(apis: &ApiVec<FnPhase>) -> HashSet<QualifiedName> {
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Struct { name, analysis, .. } if matches!(analysis.pod.kind, TypeKind::Pod) => {
+                Some(name.name.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 fn find_trivially_constructed_subclasses(apis: &ApiVec<FnPhase>) -> HashSet<QualifiedName> {
     let (simple_constructors, complex_constructors): (Vec<_>, Vec<_>) = apis
         .iter()
@@ -1123,4 +1607,7 @@ struct RsCodegenResult {
     impl_entry: Option<Box<ImplBlockDetails>>,
     trait_impl_entry: Option<Box<TraitImplBlockDetails>>,
     materializations: Vec<Use>,
+    /// Any `#[cfg(...)]` attribute requested using `generate_cfg!`, to be
+    /// attached to the user-facing Rust APIs for this item.
+    cfg: Option<Attribute>,
 }
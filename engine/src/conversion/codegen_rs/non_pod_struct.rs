@@ -12,17 +12,36 @@ use proc_macro2::{Ident, Span};
 use quote::quote;
 use syn::parse::Parser;
 use syn::punctuated::Punctuated;
-use syn::{parse_quote, Field, Fields, GenericParam, ItemStruct, LitInt};
+use syn::{parse_quote, Field, Fields, GenericParam, ItemEnum, ItemStruct, LitInt};
 
-pub(crate) fn new_non_pod_struct(id: Ident) -> ItemStruct {
+pub(crate) fn new_non_pod_struct(id: Ident, layout: Option<Layout>) -> ItemStruct {
     let mut s = parse_quote! {
         pub struct #id {
         }
     };
-    make_non_pod(&mut s, None);
+    make_non_pod(&mut s, layout);
     s
 }
 
+/// Work out the layout of an enum from its `#[repr]`, which bindgen
+/// always gives it, so that we can make an opaque version which is the
+/// same size and alignment.
+pub(crate) fn enum_layout(e: &ItemEnum) -> Option<Layout> {
+    let size = e
+        .attrs
+        .iter()
+        .filter(|a| a.path.is_ident("repr"))
+        .filter_map(|a| a.parse_args::<Ident>().ok())
+        .find_map(|repr| match repr.to_string().as_str() {
+            "u8" | "i8" => Some(1),
+            "u16" | "i16" => Some(2),
+            "u32" | "i32" => Some(4),
+            "u64" | "i64" => Some(8),
+            _ => None,
+        })?;
+    Some(Layout { size, align: size })
+}
+
 pub(crate) fn make_non_pod(s: &mut ItemStruct, layout: Option<Layout>) {
     // Make an opaque struct. If we have layout information, we pass
     // that through to Rust. We keep only doc attrs, plus add a #[repr(C)]
@@ -57,20 +76,19 @@ pub(crate) fn make_non_pod(s: &mut ItemStruct, layout: Option<Layout>) {
         .iter()
         .filter(|a| a.path.get_ident().iter().any(|p| *p == "doc"))
         .cloned();
+    // We don't use `packed` even for packed C++ types: our only data is
+    // a byte array, so there's no padding to remove, and `packed` would
+    // throw away the alignment (Rust won't let us combine it with `align`).
+    // Without layout information, we know nothing of the size, so the type
+    // is zero-sized and can only be used by reference.
     let repr_attr = if let Some(layout) = &layout {
         let align = make_lit_int(layout.align);
-        if layout.packed {
-            parse_quote! {
-                #[repr(C,align(#align),packed)]
-            }
-        } else {
-            parse_quote! {
-                #[repr(C,align(#align))]
-            }
+        parse_quote! {
+            #[repr(C, align(#align))]
         }
     } else {
         parse_quote! {
-            #[repr(C, packed)]
+            #[repr(C)]
         }
     };
     let attrs = doc_attr.chain(std::iter::once(repr_attr));
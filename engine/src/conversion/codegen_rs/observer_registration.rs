@@ -0,0 +1,144 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Registration guards for pairs of methods which add and remove a
+//! listener, as requested by `observer_registration!`.
+
+use std::collections::HashMap;
+
+use autocxx_parser::IncludeCppConfig;
+use quote::ToTokens;
+use syn::{parse_quote, Ident, Type, TypePtr};
+
+use super::{ImplBlockDetails, RsCodeGenerator, RsCodegenResult};
+use crate::{
+    conversion::{
+        analysis::fun::{FnAnalysis, FnKind, FnPhase, MethodKind, ReceiverMutability},
+        api::Api,
+        apivec::ApiVec,
+        convert_error::ErrorContext,
+        ConvertError,
+    },
+    types::{make_ident, QualifiedName},
+};
+
+/// Generate a method which registers a listener and returns an
+/// `autocxx::ObserverRegistration` for each pair of methods named in an
+/// `observer_registration!` directive. These go on the type which owns
+/// the methods.
+pub(super) fn generate_observer_registrations(
+    apis: &ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    let methods: HashMap<String, (&QualifiedName, &FnAnalysis)> = apis
+        .iter()
+        .filter_map(|api| match api {
+            Api::Function { analysis, .. } => match &analysis.kind {
+                FnKind::Method {
+                    impl_for,
+                    method_kind: MethodKind::Normal(_),
+                    ..
+                } => Some((
+                    format!("{}::{}", impl_for.to_cpp_name(), api.effective_cpp_name()),
+                    (impl_for, analysis),
+                )),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    config
+        .observer_registrations()
+        .filter_map(|(add, remove)| {
+            // If we didn't generate the method at all, there's no type to
+            // which we can attach an explanation.
+            let (impl_for, add_analysis) = methods.get(add)?;
+            let guard_name = make_ident(format!("{}_guarded", add_analysis.rust_name));
+            let name = QualifiedName::new(impl_for.get_namespace(), guard_name.clone());
+            let result = match methods.get(remove) {
+                Some((remove_impl_for, remove_analysis)) if remove_impl_for == impl_for => {
+                    generate_guard(&guard_name, impl_for, add_analysis, remove_analysis)
+                }
+                _ => None,
+            };
+            let result = result.unwrap_or_else(|| {
+                RsCodeGenerator::generate_error_entry(
+                    ConvertError::InvalidObserverRegistration(add.to_string(), remove.to_string()),
+                    ErrorContext::new_for_method(impl_for.get_final_ident(), guard_name),
+                )
+            });
+            Some((name, result))
+        })
+        .collect()
+}
+
+fn generate_guard(
+    guard_name: &Ident,
+    impl_for: &QualifiedName,
+    add: &FnAnalysis,
+    remove: &FnAnalysis,
+) -> Option<RsCodegenResult> {
+    let listener_ty = listener_type(add)?;
+    if listener_type(remove)?.to_token_stream().to_string()
+        != listener_ty.to_token_stream().to_string()
+    {
+        return None;
+    }
+    let add_name = make_ident(&add.rust_name);
+    let remove_name = make_ident(&remove.rust_name);
+    let doc = format!(
+        " Calls [`Self::{}`], returning a guard which calls [`Self::{}`] when dropped.",
+        add_name, remove_name
+    );
+    Some(RsCodegenResult {
+        impl_entry: Some(Box::new(ImplBlockDetails {
+            item: parse_quote! {
+                #[doc = #doc]
+                pub fn #guard_name<'a>(
+                    self: ::std::pin::Pin<&'a mut Self>,
+                    listener: ::std::pin::Pin<&'a mut #listener_ty>,
+                ) -> autocxx::ObserverRegistration<'a, Self, #listener_ty> {
+                    unsafe {
+                        autocxx::ObserverRegistration::new(
+                            self,
+                            listener,
+                            Self::#add_name,
+                            Self::#remove_name,
+                        )
+                    }
+                }
+            },
+            ty: impl_for.get_final_ident(),
+        })),
+        ..Default::default()
+    })
+}
+
+/// If this method takes a mutable `this` and a single non-const pointer,
+/// the type to which that pointer points.
+fn listener_type(analysis: &FnAnalysis) -> Option<&Type> {
+    if analysis.ignore_reason.is_err() || !analysis.externally_callable {
+        return None;
+    }
+    match analysis.param_details.as_slice() {
+        [this, listener]
+            if matches!(this.self_type, Some((_, ReceiverMutability::Mutable)))
+                && !listener.conversion.rust_work_needed() =>
+        {
+            match &listener.conversion.unwrapped_type {
+                Type::Ptr(TypePtr {
+                    elem,
+                    mutability: Some(_),
+                    ..
+                }) => Some(elem.as_ref()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `pin_mut` methods for POD types. Functions which take a POD type by
+//! mutable reference want a `Pin<&mut T>`, as do its mutable methods unless
+//! `unpin_pod_methods!` is used, and this makes one easy to get.
+
+use std::collections::HashSet;
+
+use syn::parse_quote;
+
+use super::RsCodegenResult;
+use crate::{
+    conversion::{
+        analysis::fun::{FnKind, FnPhase},
+        api::{Api, TypeKind},
+        apivec::ApiVec,
+    },
+    types::{make_ident, QualifiedName},
+};
+
+/// For each POD type, generate a `pin_mut` method, unless it already has
+/// a method of that name.
+pub(super) fn generate_pin_helpers(
+    apis: &ApiVec<FnPhase>,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    let clashes: HashSet<&QualifiedName> = apis
+        .iter()
+        .filter_map(|api| match api {
+            Api::Function { analysis, .. } if analysis.rust_name == "pin_mut" => {
+                match &analysis.kind {
+                    FnKind::Method { impl_for, .. } => Some(impl_for),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect();
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Struct { name, analysis, .. }
+                if matches!(analysis.pod.kind, TypeKind::Pod)
+                    && !analysis.pod.is_generic
+                    && !clashes.contains(&name.name) =>
+            {
+                Some(&name.name)
+            }
+            _ => None,
+        })
+        .map(|ty| {
+            let path = ty.to_type_path();
+            (
+                QualifiedName::new(
+                    ty.get_namespace(),
+                    make_ident(format!("{}_pin_helpers", ty.get_final_item())),
+                ),
+                RsCodegenResult {
+                    bindgen_mod_items: vec![parse_quote! {
+                        impl #path {
+                            /// Returns a pinned mutable reference, as needed by
+                            /// functions which take this type by mutable reference.
+                            /// POD types are `Unpin`, so this is always safe.
+                            pub fn pin_mut(&mut self) -> ::std::pin::Pin<&mut Self> {
+                                ::std::pin::Pin::new(self)
+                            }
+                        }
+                    }],
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,107 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Methods to connect Rust closures to Qt signals, as requested by
+//! `qt_signal!`. The C++ side of this is in `codegen_cpp`.
+
+use autocxx_parser::IncludeCppConfig;
+use quote::quote;
+use syn::{parse_quote, ForeignItem};
+
+use super::{ImplBlockDetails, RsCodeGenerator, RsCodegenResult};
+use crate::{
+    conversion::{
+        analysis::fun::FnPhase,
+        apivec::ApiVec,
+        convert_error::ErrorContext,
+        qt_signals::{find_qt_signals, qt_disconnect_fn_name},
+    },
+    types::{make_ident, QualifiedName},
+};
+
+/// Generate a `connect_` method for each signal named in a `qt_signal!`
+/// directive, along with the `cxx::bridge` declarations it needs. Returns
+/// whether any signals could be connected, in which case we'll need to
+/// include the C++ which we generate for them.
+pub(super) fn generate_qt_signals(
+    apis: &ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> (Vec<(QualifiedName, RsCodegenResult)>, bool) {
+    let mut any_valid = false;
+    let disconnect_fn_name = qt_disconnect_fn_name();
+    let mut results: Vec<_> = find_qt_signals(apis, config)
+        .into_iter()
+        .map(|signal| match signal {
+            Ok(signal) => {
+                any_valid = true;
+                let method_name = make_ident(format!("connect_{}", signal.rust_name));
+                let connect_fn_name = &signal.connect_fn_name;
+                let class_id = signal.class.get_final_ident();
+                let param_types = &signal.param_types;
+                let indices = 0..param_types.len();
+                let call = if param_types.is_empty() {
+                    quote! { handler() }
+                } else {
+                    quote! { unsafe { handler(#(args.get::<#param_types>(#indices)),*) } }
+                };
+                let doc = format!(
+                    " Calls `handler` whenever the `{}` signal is emitted, until the returned connection is dropped.",
+                    signal.cpp_name
+                );
+                let result = RsCodegenResult {
+                    extern_c_mod_items: vec![ForeignItem::Fn(parse_quote! {
+                        fn #connect_fn_name(sender: Pin<&mut #class_id>, handler: fn(usize, usize), context: usize) -> usize;
+                    })],
+                    impl_entry: Some(Box::new(ImplBlockDetails {
+                        item: parse_quote! {
+                            #[doc = #doc]
+                            pub fn #method_name(
+                                self: ::std::pin::Pin<&mut Self>,
+                                mut handler: impl FnMut(#(#param_types),*) + 'static,
+                            ) -> autocxx::qt::Connection {
+                                autocxx::qt::Connection::new(
+                                    move |args| #call,
+                                    |trampoline, context| cxxbridge::#connect_fn_name(self, trampoline, context),
+                                    cxxbridge::#disconnect_fn_name,
+                                )
+                            }
+                        },
+                        ty: class_id,
+                    })),
+                    ..Default::default()
+                };
+                (
+                    QualifiedName::new(signal.class.get_namespace(), method_name),
+                    result,
+                )
+            }
+            Err(invalid) => {
+                let method_name = make_ident(format!("connect_{}", invalid.rust_name));
+                (
+                    QualifiedName::new(invalid.class.get_namespace(), method_name.clone()),
+                    RsCodeGenerator::generate_error_entry(
+                        invalid.err,
+                        ErrorContext::new_for_method(invalid.class.get_final_ident(), method_name),
+                    ),
+                )
+            }
+        })
+        .collect();
+    if any_valid {
+        results.push((
+            QualifiedName::new_from_cpp_name(&disconnect_fn_name.to_string()),
+            RsCodegenResult {
+                extern_c_mod_items: vec![ForeignItem::Fn(parse_quote! {
+                    fn #disconnect_fn_name(connection: usize);
+                })],
+                ..Default::default()
+            },
+        ));
+    }
+    (results, any_valid)
+}
@@ -0,0 +1,53 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `cxx::bridge` declarations for the shims which an `autocxx::RcHandle`
+//! uses to add and release references to a `refcounted!` class. The C++
+//! side of this is in `codegen_cpp`.
+
+use syn::{parse_quote, ForeignItem};
+
+use super::{unqualify::unqualify_params, RsCodegenResult};
+use crate::{
+    conversion::{analysis::fun::FnPhase, apivec::ApiVec, refcounted::find_refcounted_types},
+    types::QualifiedName,
+};
+
+/// Declare the shims for each reference-counted class to which some
+/// function returns a pointer. Returns whether there were any, in which
+/// case we'll need to include the C++ which we generate for them.
+pub(super) fn generate_refcounted_shims(
+    apis: &ApiVec<FnPhase>,
+) -> (Vec<(QualifiedName, RsCodegenResult)>, bool) {
+    let results: Vec<_> = find_refcounted_types(apis)
+        .into_iter()
+        .map(|refcounted| {
+            let ty = &refcounted.ty;
+            let addref_fn_name = refcounted.addref_fn_name();
+            let release_fn_name = refcounted.release_fn_name();
+            let params = unqualify_params(parse_quote! { obj: *mut #ty });
+            let extern_c_mod_items: Vec<ForeignItem> = vec![
+                parse_quote! {
+                    unsafe fn #addref_fn_name(#params);
+                },
+                parse_quote! {
+                    unsafe fn #release_fn_name(#params);
+                },
+            ];
+            (
+                QualifiedName::new(refcounted.name.get_namespace(), addref_fn_name),
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+    let any = !results.is_empty();
+    (results, any)
+}
@@ -0,0 +1,205 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An `autocxx::reflection::Registry` describing the generated types and
+//! functions, as requested by `generate_registry!`.
+
+use std::collections::HashMap;
+
+use autocxx_parser::IncludeCppConfig;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{parse_quote, FnArg, Pat, ReturnType};
+
+use super::RsCodegenResult;
+use crate::{
+    conversion::{
+        analysis::fun::{FnAnalysis, FnKind, FnPhase, MethodKind, ReceiverMutability},
+        api::{Api, UnsafetyNeeded},
+        apivec::ApiVec,
+        codegen_cpp::type_to_cpp::{namespaced_name_using_original_name_map, CppNameMap},
+    },
+    types::{make_ident, Namespace, QualifiedName},
+};
+
+/// Generate `ffi::autocxx_registry()`, if the user asked for it.
+pub(super) fn generate_registry(
+    apis: &ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+    original_name_map: &CppNameMap,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    if !config.generate_registry() {
+        return Vec::new();
+    }
+    // Anything subject to a cfg might not exist, so we can't refer to it
+    // from a single static table.
+    let has_cfg = |name: &QualifiedName| config.cfg_for(&name.to_cpp_name()).is_some();
+    let mut methods: HashMap<&QualifiedName, Vec<(&str, &FnAnalysis)>> = HashMap::new();
+    let mut functions = Vec::new();
+    for api in apis.iter() {
+        if let Api::Function { analysis, .. } = api {
+            if analysis.ignore_reason.is_err()
+                || !analysis.externally_callable
+                || has_cfg(api.name())
+            {
+                continue;
+            }
+            match &analysis.kind {
+                FnKind::Function => {
+                    let rust_name = QualifiedName::new(
+                        api.name().get_namespace(),
+                        make_ident(&analysis.rust_name),
+                    );
+                    let cpp_name = QualifiedName::new(
+                        api.name().get_namespace(),
+                        make_ident(api.effective_cpp_name()),
+                    );
+                    functions.push(function_info(
+                        &rust_name.to_string(),
+                        &cpp_name.to_cpp_name(),
+                        analysis,
+                    ));
+                }
+                FnKind::Method { impl_for, .. } => {
+                    methods
+                        .entry(impl_for)
+                        .or_default()
+                        .push((api.effective_cpp_name(), analysis));
+                }
+                FnKind::TraitMethod { .. } => {}
+            }
+        }
+    }
+    let types = apis.iter().filter_map(|api| match api {
+        Api::Struct { name, .. } | Api::Enum { name, .. } if !has_cfg(&name.name) => {
+            let name = &name.name;
+            let cpp_name = namespaced_name_using_original_name_map(name, original_name_map);
+            let type_methods = methods.remove(name).unwrap_or_default();
+            Some(type_info(name, &cpp_name, &type_methods))
+        }
+        _ => None,
+    });
+    let types: Vec<_> = types.collect();
+    let name = QualifiedName::new(&Namespace::new(), make_ident("autocxx_registry"));
+    vec![(
+        name,
+        RsCodegenResult {
+            global_items: vec![parse_quote! {
+                /// Describes the types and functions generated by this
+                /// `include_cpp!`.
+                pub fn autocxx_registry() -> &'static autocxx::reflection::Registry {
+                    static REGISTRY: autocxx::reflection::Registry =
+                        autocxx::reflection::Registry::new(&[#(#types),*], &[#(#functions),*]);
+                    &REGISTRY
+                }
+            }],
+            ..Default::default()
+        },
+    )]
+}
+
+fn type_info(name: &QualifiedName, cpp_name: &str, methods: &[(&str, &FnAnalysis)]) -> TokenStream {
+    let rust_name = name.to_string();
+    let fulltypath = name.get_bindgen_path_idents();
+    let path = quote! { #(#fulltypath)::* };
+    let make_unique = methods
+        .iter()
+        .map(|(_, m)| m)
+        .find(|m| {
+            matches!(
+                m.kind,
+                FnKind::Method {
+                    method_kind: MethodKind::MakeUnique,
+                    ..
+                }
+            ) && m.params.is_empty()
+                && !matches!(m.requires_unsafe, UnsafetyNeeded::Always)
+        })
+        .map(|m| {
+            let make_unique = make_ident(&m.rust_name);
+            quote! {
+                Some(|| Box::new(#path::#make_unique()) as Box<dyn ::std::any::Any>)
+            }
+        })
+        .unwrap_or_else(|| quote! { None });
+    let methods = methods.iter().map(|(method_cpp_name, m)| {
+        function_info(
+            &m.rust_name,
+            &format!("{}::{}", cpp_name, method_cpp_name),
+            m,
+        )
+    });
+    quote! {
+        autocxx::reflection::TypeInfo {
+            rust_name: #rust_name,
+            cpp_name: #cpp_name,
+            type_id: <#path as autocxx::AutocxxType>::type_id,
+            make_unique: #make_unique,
+            methods: &[#(#methods),*],
+        }
+    }
+}
+
+fn function_info(rust_name: &str, cpp_name: &str, analysis: &FnAnalysis) -> TokenStream {
+    let (kind, skip) = match &analysis.kind {
+        FnKind::Method { method_kind, .. } => match method_kind {
+            MethodKind::Normal(ReceiverMutability::Const)
+            | MethodKind::Virtual(ReceiverMutability::Const)
+            | MethodKind::PureVirtual(ReceiverMutability::Const) => ("ConstMethod", 0),
+            MethodKind::Normal(ReceiverMutability::Mutable)
+            | MethodKind::Virtual(ReceiverMutability::Mutable)
+            | MethodKind::PureVirtual(ReceiverMutability::Mutable) => ("MutMethod", 0),
+            MethodKind::Static => ("StaticMethod", 0),
+            // The first parameter is the memory into which to construct.
+            MethodKind::Constructor { .. } => ("Constructor", 1),
+            MethodKind::MakeUnique => ("Constructor", 0),
+        },
+        _ => ("Function", 0),
+    };
+    let kind = make_ident(kind);
+    let params = analysis
+        .params
+        .iter()
+        .skip(skip)
+        .filter_map(|param| match param {
+            FnArg::Typed(pt) => match pt.pat.as_ref() {
+                Pat::Ident(pp) if pp.ident == "self" => None,
+                pat => {
+                    let name = pat.to_token_stream().to_string();
+                    let ty = pt.ty.to_token_stream().to_string();
+                    Some(quote! {
+                        autocxx::reflection::ParamInfo { name: #name, ty: #ty }
+                    })
+                }
+            },
+            FnArg::Receiver(_) => None,
+        });
+    let return_type = match (&analysis.kind, &analysis.ret_type) {
+        (
+            FnKind::Method {
+                method_kind: MethodKind::Constructor { .. },
+                ..
+            },
+            _,
+        ) => quote! { Some("impl autocxx::moveit::new::New<Output = Self>") },
+        (_, ReturnType::Type(_, ty)) => {
+            let ty = ty.to_token_stream().to_string();
+            quote! { Some(#ty) }
+        }
+        (_, ReturnType::Default) => quote! { None },
+    };
+    quote! {
+        autocxx::reflection::FunctionInfo {
+            rust_name: #rust_name,
+            cpp_name: #cpp_name,
+            kind: autocxx::reflection::FunctionKind::#kind,
+            params: &[#(#params),*],
+            return_type: #return_type,
+        }
+    }
+}
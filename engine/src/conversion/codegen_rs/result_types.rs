@@ -0,0 +1,76 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `cxx::bridge` declarations for the shims which let us turn a returned
+//! `absl::Status`, `absl::StatusOr<T>` or `result_type!` into a `Result`.
+//! The C++ side of this is in `codegen_cpp`, and the conversion itself is
+//! done by each function's Rust wrapper.
+
+use syn::{parse_quote, ForeignItem};
+
+use super::{unqualify::unqualify_ret_type, RsCodegenResult};
+use crate::{
+    conversion::{
+        analysis::fun::FnPhase,
+        apivec::ApiVec,
+        result_types::{find_result_types, ResultReturnKind},
+    },
+    types::QualifiedName,
+};
+
+/// Declare the shims which inspect each result type returned by some
+/// function: whether it holds a value, and the value or error within.
+pub(super) fn generate_result_shims(
+    apis: &ApiVec<FnPhase>,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    find_result_types(apis)
+        .into_iter()
+        .map(|result_return| {
+            let ty = result_return.result_type.get_final_ident();
+            let ok_fn_name = result_return.ok_fn_name();
+            let mut extern_c_mod_items: Vec<ForeignItem> = vec![parse_quote! {
+                fn #ok_fn_name(result: &#ty) -> bool;
+            }];
+            if let Some(value) = &result_return.value {
+                let value_fn_name = result_return.value_fn_name();
+                let value_ty = value.unconverted_rust_type();
+                let ret_type = unqualify_ret_type(parse_quote! { -> #value_ty });
+                extern_c_mod_items.push(parse_quote! {
+                    fn #value_fn_name(result: Pin<&mut #ty>) #ret_type;
+                });
+            }
+            match &result_return.kind {
+                ResultReturnKind::Abseil => {
+                    let code_fn_name = result_return.code_fn_name();
+                    let message_fn_name = result_return.message_fn_name();
+                    extern_c_mod_items.push(parse_quote! {
+                        fn #code_fn_name(result: &#ty) -> i32;
+                    });
+                    extern_c_mod_items.push(parse_quote! {
+                        fn #message_fn_name(result: &#ty) -> String;
+                    });
+                }
+                ResultReturnKind::Custom { error, .. } => {
+                    let error_fn_name = result_return.error_fn_name();
+                    let error_ty = error.unconverted_rust_type();
+                    let ret_type = unqualify_ret_type(parse_quote! { -> #error_ty });
+                    extern_c_mod_items.push(parse_quote! {
+                        fn #error_fn_name(result: Pin<&mut #ty>) #ret_type;
+                    });
+                }
+            }
+            (
+                QualifiedName::new(result_return.result_type.get_namespace(), ok_fn_name),
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
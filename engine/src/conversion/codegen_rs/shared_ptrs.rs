@@ -0,0 +1,96 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `shared_from_this` methods for classes deriving from
+//! `std::enable_shared_from_this`, and `autocxx::UpcastSharedPtr`
+//! implementations for classes with base classes. The C++ side of this is
+//! in `codegen_cpp`.
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{parse_quote, ForeignItem};
+
+use super::{ImplBlockDetails, RsCodegenResult};
+use crate::{
+    conversion::{
+        analysis::fun::FnPhase,
+        apivec::ApiVec,
+        shared_ptrs::{
+            find_shared_from_this_types, find_shared_ptr_upcasts, shared_from_this_fn_name,
+            upcast_fn_name,
+        },
+    },
+    types::QualifiedName,
+};
+
+/// Generate the shims and the Rust APIs which use them. Returns whether
+/// there were any, in which case we'll need to include the C++ which we
+/// generate for them.
+pub(super) fn generate_shared_ptr_shims(
+    apis: &ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> (Vec<(QualifiedName, RsCodegenResult)>, bool) {
+    let shared_from_this = find_shared_from_this_types(apis, config)
+        .into_iter()
+        .map(|ty| {
+            let id = ty.get_final_ident();
+            let fn_name = shared_from_this_fn_name(ty);
+            let extern_c_mod_items: Vec<ForeignItem> = vec![parse_quote! {
+                fn #fn_name(obj: &#id) -> SharedPtr<#id>;
+            }];
+            (
+                QualifiedName::new(ty.get_namespace(), fn_name.clone()),
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    impl_entry: Some(Box::new(ImplBlockDetails {
+                        item: parse_quote! {
+                            /// Returns a `SharedPtr` which shares ownership of
+                            /// this object with those which already own it. As
+                            /// in C++, this object must already be owned by a
+                            /// `std::shared_ptr`.
+                            pub fn shared_from_this(&self) -> cxx::SharedPtr<Self> {
+                                cxxbridge::#fn_name(self)
+                            }
+                        },
+                        ty: id,
+                    })),
+                    ..Default::default()
+                },
+            )
+        });
+    let upcasts = find_shared_ptr_upcasts(apis, config)
+        .into_iter()
+        .map(|(derived, base)| {
+            let derived_id = derived.get_final_ident();
+            let base_id = base.get_final_ident();
+            let derived_path = derived.to_type_path();
+            let base_path = base.to_type_path();
+            let fn_name = upcast_fn_name(derived, base);
+            let extern_c_mod_items: Vec<ForeignItem> = vec![parse_quote! {
+                fn #fn_name(ptr: &SharedPtr<#derived_id>) -> SharedPtr<#base_id>;
+            }];
+            (
+                QualifiedName::new(derived.get_namespace(), fn_name.clone()),
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    bindgen_mod_items: vec![parse_quote! {
+                        impl autocxx::UpcastSharedPtr<#base_path> for #derived_path {
+                            fn upcast_shared_ptr(
+                                ptr: &cxx::SharedPtr<Self>,
+                            ) -> cxx::SharedPtr<#base_path> {
+                                cxxbridge::#fn_name(ptr)
+                            }
+                        }
+                    }],
+                    ..Default::default()
+                },
+            )
+        });
+    let results: Vec<_> = shared_from_this.chain(upcasts).collect();
+    let any = !results.is_empty();
+    (results, any)
+}
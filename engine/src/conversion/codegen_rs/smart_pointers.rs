@@ -0,0 +1,53 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `cxx::bridge` declarations for the shims which an `autocxx::SmartPtr`
+//! uses to dereference and copy a `smart_pointer!`. The C++ side of this is
+//! in `codegen_cpp`.
+
+use syn::{parse_quote, ForeignItem};
+
+use super::{unqualify::unqualify_ret_type, RsCodegenResult};
+use crate::{
+    conversion::{
+        analysis::fun::FnPhase, apivec::ApiVec, smart_pointers::find_smart_pointer_types,
+    },
+    types::QualifiedName,
+};
+
+/// Declare the shims which dereference and copy each smart pointer type
+/// returned by some function.
+pub(super) fn generate_smart_pointer_shims(
+    apis: &ApiVec<FnPhase>,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    find_smart_pointer_types(apis)
+        .into_iter()
+        .map(|smart_pointer| {
+            let ty = smart_pointer.ptr_name.get_final_ident();
+            let target = &smart_pointer.target;
+            let get_fn_name = smart_pointer.get_fn_name();
+            let clone_fn_name = smart_pointer.clone_fn_name();
+            let get_ret_type = unqualify_ret_type(parse_quote! { -> *const #target });
+            let extern_c_mod_items: Vec<ForeignItem> = vec![
+                parse_quote! {
+                    fn #get_fn_name(ptr: &#ty) #get_ret_type;
+                },
+                parse_quote! {
+                    fn #clone_fn_name(ptr: &#ty) -> UniquePtr<#ty>;
+                },
+            ];
+            (
+                QualifiedName::new(smart_pointer.ptr_name.get_namespace(), get_fn_name),
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
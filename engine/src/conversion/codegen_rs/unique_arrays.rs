@@ -0,0 +1,47 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `cxx::bridge` declarations for the shims which an `autocxx::UniqueArray`
+//! uses to free the array returned as a `std::unique_ptr<T[]>`. The C++
+//! side of this is in `codegen_cpp`.
+
+use syn::{parse_quote, ForeignItem};
+
+use super::{unqualify::unqualify_params, RsCodegenResult};
+use crate::{
+    conversion::{
+        analysis::fun::FnPhase, apivec::ApiVec, unique_arrays::find_unique_array_returns,
+    },
+    types::{Namespace, QualifiedName},
+};
+
+/// Declare the shim which frees the array returned by each function
+/// returning a `std::unique_ptr<T[]>`, so that an `autocxx::UniqueArray`
+/// can release it.
+pub(super) fn generate_unique_array_shims(
+    apis: &ApiVec<FnPhase>,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    find_unique_array_returns(apis)
+        .into_iter()
+        .map(|unique_array| {
+            let elem = &unique_array.elem;
+            let delete_fn_name = &unique_array.delete_fn_name;
+            let params = unqualify_params(parse_quote! { ptr: *mut #elem });
+            let extern_c_mod_items: Vec<ForeignItem> = vec![parse_quote! {
+                unsafe fn #delete_fn_name(#params);
+            }];
+            (
+                QualifiedName::new(&Namespace::new(), delete_fn_name.clone()),
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
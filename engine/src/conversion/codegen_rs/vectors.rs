@@ -0,0 +1,72 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `autocxx::UniquePtrVectorElement` implementations for non-POD classes
+//! found in `std::vector`s. The C++ side of this is in `codegen_cpp`.
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{parse_quote, ForeignItem};
+
+use super::RsCodegenResult;
+use crate::{
+    conversion::{
+        analysis::fun::FnPhase,
+        apivec::ApiVec,
+        vectors::{find_vector_element_types, vector_get_copy_fn_name, vector_push_fn_name},
+    },
+    types::QualifiedName,
+};
+
+/// Declare the shims which copy out of and push into a `std::vector` of
+/// each non-POD class, and implement `autocxx::UniquePtrVectorElement`
+/// using them.
+pub(super) fn generate_vector_shims(
+    apis: &ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> Vec<(QualifiedName, RsCodegenResult)> {
+    find_vector_element_types(apis, config)
+        .into_iter()
+        .map(|ty| {
+            let id = ty.get_final_ident();
+            let path = ty.to_type_path();
+            let get_copy_fn_name = vector_get_copy_fn_name(ty);
+            let push_fn_name = vector_push_fn_name(ty);
+            let extern_c_mod_items: Vec<ForeignItem> = vec![
+                parse_quote! {
+                    fn #get_copy_fn_name(vec: &CxxVector<#id>, pos: usize) -> UniquePtr<#id>;
+                },
+                parse_quote! {
+                    fn #push_fn_name(vec: Pin<&mut CxxVector<#id>>, value: UniquePtr<#id>);
+                },
+            ];
+            (
+                QualifiedName::new(ty.get_namespace(), get_copy_fn_name.clone()),
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    bindgen_mod_items: vec![parse_quote! {
+                        impl autocxx::UniquePtrVectorElement for #path {
+                            fn vector_get_copy(
+                                vec: &cxx::CxxVector<Self>,
+                                pos: usize,
+                            ) -> cxx::UniquePtr<Self> {
+                                cxxbridge::#get_copy_fn_name(vec, pos)
+                            }
+                            fn vector_push(
+                                vec: ::std::pin::Pin<&mut cxx::CxxVector<Self>>,
+                                value: cxx::UniquePtr<Self>,
+                            ) {
+                                cxxbridge::#push_fn_name(vec, value)
+                            }
+                        }
+                    }],
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
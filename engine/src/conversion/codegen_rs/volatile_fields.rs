@@ -0,0 +1,61 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Accessors for the fields of POD types named in `volatile_field!`
+//! directives. bindgen discards `volatile`, so without these, Rust code
+//! would read and write such fields - perhaps memory-mapped registers - as
+//! if they were ordinary memory.
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{parse_quote, Fields, ImplItem, Item, ItemStruct, Visibility};
+
+use crate::types::{make_ident, QualifiedName};
+
+/// Hide each `volatile` field of this struct, and return an `impl` block
+/// with methods to read and write it using volatile operations instead.
+pub(super) fn generate_volatile_field_accessors(
+    s: &mut ItemStruct,
+    name: &QualifiedName,
+    config: &IncludeCppConfig,
+) -> Option<Item> {
+    let cpp_name = name.to_cpp_name();
+    let volatile_fields: Vec<_> = config.volatile_fields(&cpp_name).collect();
+    let fields = match &mut s.fields {
+        Fields::Named(fields) if !volatile_fields.is_empty() => fields,
+        _ => return None,
+    };
+    let mut methods: Vec<ImplItem> = Vec::new();
+    for field in fields.named.iter_mut() {
+        let id = field.ident.as_ref().unwrap();
+        if !volatile_fields.contains(&id.to_string().as_str()) {
+            continue;
+        }
+        field.vis = Visibility::Inherited;
+        let ty = &field.ty;
+        let read = make_ident(format!("read_{}", id));
+        let write = make_ident(format!("write_{}", id));
+        methods.push(parse_quote! {
+            /// Reads this `volatile` field.
+            pub fn #read(&self) -> #ty {
+                unsafe { ::std::ptr::read_volatile(&self.#id) }
+            }
+        });
+        methods.push(parse_quote! {
+            /// Writes this `volatile` field.
+            pub fn #write(&mut self, value: #ty) {
+                unsafe { ::std::ptr::write_volatile(&mut self.#id, value) }
+            }
+        });
+    }
+    let id = &s.ident;
+    Some(parse_quote! {
+        impl #id {
+            #(#methods)*
+        }
+    })
+}
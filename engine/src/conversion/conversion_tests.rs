@@ -13,7 +13,7 @@ use syn::ItemMod;
 
 use crate::CppCodegenOptions;
 
-use super::BridgeConverter;
+use super::{out_of_line::FunctionSymbols, BridgeConverter};
 
 // This mod is for tests which take bindgen output directly.
 // This should be avoided where possible, since these tests will
@@ -29,13 +29,15 @@ fn do_test(input: ItemMod) {
     let tc = parse_quote! {};
     let bc = BridgeConverter::new(&[], &tc);
     let inclusions = "".into();
-    bc.convert(
-        input,
-        UnsafePolicy::AllFunctionsSafe,
-        inclusions,
-        &CppCodegenOptions::default(),
-    )
-    .unwrap();
+    let analysis = bc
+        .analyze(
+            input,
+            UnsafePolicy::AllFunctionsSafe,
+            FunctionSymbols::default(),
+        )
+        .unwrap();
+    bc.generate(analysis, inclusions, &CppCodegenOptions::default())
+        .unwrap();
 }
 
 // How to add a test here
@@ -37,7 +37,7 @@ pub enum ConvertError {
     UnexpectedUseStatement(Option<Ident>),
     TemplatedTypeContainingNonPathArg(QualifiedName),
     InvalidPointee,
-    DidNotGenerateAnything(String),
+    DidNotGenerateAnything(String, Option<String>),
     TypeContainingForwardDeclaration(QualifiedName),
     Blocked(QualifiedName),
     UnusedTemplateParam,
@@ -53,14 +53,32 @@ pub enum ConvertError {
     NonPublicNestedType,
     RValueParam,
     RValueReturn,
+    RValueQualifiedVirtual,
+    InitializerListReturn,
+    ArrayReturn,
+    UnrepresentableAbi(String),
+    ArrayOfNonPodType,
     PrivateMethod,
     AssignmentOperator,
     Deleted,
+    HiddenVisibility,
     RValueReferenceField,
     MethodOfNonAllowlistedType,
     MethodOfGenericType,
     DuplicateItemsFoundInParsing,
     ConstructorWithOnlyOneParam,
+    PrefixStrippingCollision(QualifiedName, QualifiedName, String),
+    InvalidOutParam(usize),
+    InvalidBorrowsFrom(usize),
+    UniqueArrayWithoutLength,
+    InvalidLastError(String),
+    InvalidAsyncWrapper,
+    InvalidUniqueArray,
+    RefcountedConstructor,
+    InvalidSliceParams(usize, usize),
+    InvalidObserverRegistration(String, String),
+    MocGeneratedMember,
+    InvalidQtSignal(String),
 }
 
 fn format_maybe_identifier(id: &Option<Ident>) -> String {
@@ -83,17 +101,18 @@ impl Display for ConvertError {
             ConvertError::UnsupportedBuiltInType(ty) => write!(f, "autocxx does not yet know how to support the built-in C++ type {} - please raise an issue on github", ty.to_cpp_name())?,
             ConvertError::ConflictingTemplatedArgsWithTypedef(tn) => write!(f, "Type {} has templated arguments and so does the typedef to which it points", tn)?,
             ConvertError::UnacceptableParam(fn_name) => write!(f, "Function {} has a parameter or return type which is either on the blocklist or a forward declaration", fn_name)?,
-            ConvertError::NotOneInputReference(fn_name) => write!(f, "Function {} has a return reference parameter, but 0 or >1 input reference parameters, so the lifetime of the output reference cannot be deduced.", fn_name)?,
+            ConvertError::NotOneInputReference(fn_name) => write!(f, "Function {} has a return reference parameter, but 0 or >1 input reference parameters, so the lifetime of the output reference cannot be deduced. Use borrows_from! to say which parameter it borrows from.", fn_name)?,
             ConvertError::UnsupportedType(ty_desc) => write!(f, "Encountered type not yet supported by autocxx: {}", ty_desc)?,
             ConvertError::UnknownType(ty_desc) => write!(f, "Encountered type not yet known by autocxx: {}", ty_desc)?,
-            ConvertError::StaticData(ty_desc) => write!(f, "Encountered mutable static data, not yet supported: {}", ty_desc)?,
+            ConvertError::StaticData(ty_desc) => write!(f, "Encountered a global variable whose C++ name couldn't be determined, perhaps because it's a member of a templated class, so no accessor was generated: {}", ty_desc)?,
             ConvertError::InfinitelyRecursiveTypedef(tn) => write!(f, "Encountered typedef to itself - this is a known bindgen bug: {}", tn.to_cpp_name())?,
             ConvertError::UnexpectedUseStatement(maybe_ident) => write!(f, "Unexpected 'use' statement encountered: {}", format_maybe_identifier(maybe_ident))?,
             ConvertError::TemplatedTypeContainingNonPathArg(tn) => write!(f, "Type {} was parameterized over something complex which we don't yet support", tn)?,
             ConvertError::InvalidPointee => write!(f, "Pointer pointed to something unsupported")?,
-            ConvertError::DidNotGenerateAnything(directive) => write!(f, "The 'generate' or 'generate_pod' directive for '{}' did not result in any code being generated. Perhaps this was mis-spelled or you didn't qualify the name with any namespaces? Otherwise please report a bug.", directive)?,
-            ConvertError::TypeContainingForwardDeclaration(tn) => write!(f, "Found an attempt at using a forward declaration ({}) inside a templated cxx type such as UniquePtr or CxxVector", tn.to_cpp_name())?,
-            ConvertError::Blocked(tn) => write!(f, "Found an attempt at using a type marked as blocked! ({})", tn.to_cpp_name())?,
+            ConvertError::DidNotGenerateAnything(directive, None) => write!(f, "The 'generate' or 'generate_pod' directive for '{}' did not result in any code being generated. Perhaps this was mis-spelled or you didn't qualify the name with any namespaces? Otherwise please report a bug.", directive)?,
+            ConvertError::DidNotGenerateAnything(directive, Some(suggestion)) => write!(f, "The 'generate' or 'generate_pod' directive for '{}' did not result in any code being generated, because nothing of that name exists in the headers. Did you mean '{}'?", directive, suggestion)?,
+            ConvertError::TypeContainingForwardDeclaration(tn) => write!(f, "Found an attempt at using a forward declaration ({}) inside a templated cxx type such as UniquePtr or CxxVector, which need the complete type", tn.to_cpp_name())?,
+            ConvertError::Blocked(tn) => write!(f, "This uses {}, which is a type marked as blocked by block!, block_ns! or a pattern, so it can't be generated", tn.to_cpp_name())?,
             ConvertError::UnusedTemplateParam => write!(f, "This function or method uses a type where one of the template parameters was incomprehensible to bindgen/autocxx - probably because it uses template specialization.")?,
             ConvertError::TooManyUnderscores => write!(f, "Names containing __ are reserved by C++ so not acceptable to cxx")?,
             ConvertError::UnknownDependentType(qn) => write!(f, "This item relies on a type not known to autocxx ({})", qn.to_cpp_name())?,
@@ -107,14 +126,32 @@ impl Display for ConvertError {
             ConvertError::NonPublicNestedType => write!(f, "This type is nested within another struct/class with protected or private visibility.")?,
             ConvertError::RValueParam => write!(f, "This function takes an rvalue reference parameter (&&) which is not yet supported.")?,
             ConvertError::RValueReturn => write!(f, "This function returns an rvalue reference (&&) which is not yet supported.")?,
+            ConvertError::RValueQualifiedVirtual => write!(f, "This virtual method may only be called on an rvalue (it's &&-qualified). Such methods can't yet be overridden by subclasses, so autocxx doesn't generate bindings for them.")?,
+            ConvertError::InitializerListReturn => write!(f, "This function returns a std::initializer_list, whose elements wouldn't outlive the call.")?,
+            ConvertError::ArrayReturn => write!(f, "This function returns a reference or pointer to an array, which is not yet supported.")?,
+            ConvertError::UnrepresentableAbi(construct) => write!(f, "This function {}, which can't be represented in a cxx::bridge or passed through a C++ wrapper, so it was skipped.", construct)?,
+            ConvertError::ArrayOfNonPodType => write!(f, "This function takes a reference or pointer to an array whose elements aren't POD types or primitives, so can't be represented in Rust.")?,
             ConvertError::PrivateMethod => write!(f, "This method is private")?,
             ConvertError::AssignmentOperator => write!(f, "autocxx does not know how to generate bindings to operator=")?,
             ConvertError::Deleted => write!(f, "This function was marked =delete")?,
+            ConvertError::HiddenVisibility => write!(f, "This function isn't marked with any of the macros given in export_macro!, so the library which defines it doesn't export it and it can't be linked. If the generated C++ is built into that library, add it to force_wrapper! to call it through a generated wrapper instead.")?,
             ConvertError::RValueReferenceField => write!(f, "This structure has an rvalue reference field (&&) which is not yet supported.")?,
             ConvertError::MethodOfNonAllowlistedType => write!(f, "This type was not on the allowlist, so we are not generating methods for it.")?,
             ConvertError::MethodOfGenericType => write!(f, "This type is templated, so we can't generate bindings. We will instead generate bindings for each instantiation.")?,
             ConvertError::DuplicateItemsFoundInParsing => write!(f, "bindgen generated multiple different APIs (functions/types) with this name. autocxx doesn't know how to diambiguate them, so we won't generate bindings for any of them.")?,
             ConvertError::ConstructorWithOnlyOneParam => write!(f, "bindgen generated a move or copy constructor with an unexpected number of parameters.")?,
+            ConvertError::PrefixStrippingCollision(a, b, name) => write!(f, "After applying strip_prefix!, both {} and {} would be named {}. Remove the strip_prefix! directive or block one of these items.", a, b, name)?,
+            ConvertError::InvalidOutParam(idx) => write!(f, "An out_param! directive was given for parameter {}, but that parameter isn't a non-const pointer to a POD type or primitive on a free function or non-virtual method.", idx)?,
+            ConvertError::InvalidBorrowsFrom(idx) => write!(f, "A borrows_from! directive was given for parameter {}, but that parameter isn't a reference.", idx)?,
+            ConvertError::InvalidLastError(fetch) => write!(f, "A last_error! directive was given for this function, but it isn't a free function or non-virtual method returning a primitive, pointer or POD type, or {} isn't a free function taking no parameters and returning a primitive or enum.", fetch)?,
+            ConvertError::InvalidAsyncWrapper => write!(f, "An async_wrapper! directive was given for this function, but it isn't a free function, or its parameters or return value can't be moved to another thread. Only primitives, enums, POD structs and strings returned as String can.")?,
+            ConvertError::UniqueArrayWithoutLength => write!(f, "This function returns a std::unique_ptr<T[]>. Use unique_array! to say where to find its length.")?,
+            ConvertError::InvalidUniqueArray => write!(f, "A unique_array! directive was given for this function, but it isn't a free function or non-virtual method returning a std::unique_ptr<T[]> of POD types or primitives, or its length parameter isn't an integer.")?,
+            ConvertError::RefcountedConstructor => write!(f, "This constructs a class named in a refcounted! directive. The object would be owned by a UniquePtr rather than by its reference count, so instead use a function which returns a pointer to a new object.")?,
+            ConvertError::InvalidSliceParams(ptr_idx, len_idx) => write!(f, "A slice_params! directive was given for parameters {} and {}, but they aren't a pointer to a POD type or primitive followed by an integer length, on a free function or non-virtual method.", ptr_idx, len_idx)?,
+            ConvertError::InvalidObserverRegistration(add, remove) => write!(f, "An observer_registration! directive was given for {} and {}, but they aren't both non-const, non-virtual methods of the same class, each taking a single non-const pointer to the same listener type.", add, remove)?,
+            ConvertError::MocGeneratedMember => write!(f, "This member is declared by Q_OBJECT for the use of Qt's meta-object system, so qt_mode! hides it.")?,
+            ConvertError::InvalidQtSignal(signal) => write!(f, "A qt_signal! directive was given for {}, but it isn't a non-const, non-virtual method whose parameters are all primitives or enums passed by value.", signal)?,
         }
         Ok(())
     }
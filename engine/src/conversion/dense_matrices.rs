@@ -0,0 +1,102 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for dense matrix types, such as `Eigen::MatrixXd`, named in
+//! `dense_matrix!` directives. For each, we generate C++ shims to build one
+//! from a slice of its elements and to view its elements as a slice, and
+//! Rust methods which call them. Both the C++ and Rust code generators need
+//! to agree on what those shims are.
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{Ident, Type};
+
+use super::{
+    analysis::fun::FnPhase,
+    api::{Api, TypedefKind},
+    apivec::ApiVec,
+};
+use crate::types::{make_ident, QualifiedName};
+
+/// A dense matrix type named in a `dense_matrix!` directive.
+pub(crate) struct DenseMatrix<'a> {
+    /// The name of the type as given in the directive, e.g. a typedef.
+    pub(crate) cpp_name: &'a str,
+    /// The type which cxx knows about: the target of the typedef, if
+    /// it's one we could represent, or else the type itself.
+    pub(crate) name: QualifiedName,
+    /// The Rust type of its elements.
+    pub(crate) element: Ident,
+}
+
+impl<'a> DenseMatrix<'a> {
+    /// The name of the shim which builds a matrix from a slice.
+    pub(crate) fn from_slice_fn_name(&self) -> Ident {
+        self.shim_name("from_slice")
+    }
+
+    /// The name of the shim which views the elements as a slice.
+    pub(crate) fn data_fn_name(&self) -> Ident {
+        self.shim_name("data")
+    }
+
+    /// The name of the shim which views the elements as a mutable slice.
+    pub(crate) fn data_mut_fn_name(&self) -> Ident {
+        self.shim_name("data_mut")
+    }
+
+    /// The name of the shim which returns the number of rows.
+    pub(crate) fn rows_fn_name(&self) -> Ident {
+        self.shim_name("rows")
+    }
+
+    /// The name of the shim which returns the number of columns.
+    pub(crate) fn cols_fn_name(&self) -> Ident {
+        self.shim_name("cols")
+    }
+
+    fn shim_name(&self, operation: &str) -> Ident {
+        make_ident(format!(
+            "{}_autocxx_matrix_{}",
+            self.name.get_final_item(),
+            operation
+        ))
+    }
+}
+
+/// Find the types named in `dense_matrix!` directives. Any which we
+/// haven't generated, for instance because they're not in the headers,
+/// are skipped.
+pub(crate) fn find_dense_matrices<'a>(
+    apis: &ApiVec<FnPhase>,
+    config: &'a IncludeCppConfig,
+) -> Vec<DenseMatrix<'a>> {
+    config
+        .dense_matrices()
+        .filter_map(|(cpp_name, element)| {
+            let name = apis.iter().find_map(|api| match api {
+                _ if api.name().to_cpp_name() != cpp_name => None,
+                Api::Typedef { analysis, .. } => match &analysis.kind {
+                    TypedefKind::Type(ity) => match ity.ty.as_ref() {
+                        Type::Path(typ) => Some(QualifiedName::from_type_path(typ)),
+                        _ => None,
+                    },
+                    TypedefKind::Use(_) => None,
+                },
+                Api::Struct { name, .. }
+                | Api::ForwardDeclaration { name }
+                | Api::ConcreteType { name, .. } => Some(name.name.clone()),
+                _ => None,
+            })?;
+            Some(DenseMatrix {
+                cpp_name,
+                name,
+                element: make_ident(element),
+            })
+        })
+        .collect()
+}
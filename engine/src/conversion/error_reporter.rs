@@ -106,15 +106,31 @@ pub(crate) fn convert_apis<FF, SF, EF, TF, A, B: 'static>(
             Api::StringConstructor { name } => {
                 Ok(Box::new(std::iter::once(Api::StringConstructor { name })))
             }
-            Api::Const { name, const_item } => {
-                Ok(Box::new(std::iter::once(Api::Const { name, const_item })))
-            }
+            Api::Const {
+                name,
+                const_item,
+                impl_for,
+            } => Ok(Box::new(std::iter::once(Api::Const {
+                name,
+                const_item,
+                impl_for,
+            }))),
             Api::CType { name, typename } => {
                 Ok(Box::new(std::iter::once(Api::CType { name, typename })))
             }
             Api::RustType { name, path } => {
                 Ok(Box::new(std::iter::once(Api::RustType { name, path })))
             }
+            Api::FnPtrTypedef { name, item, deps } => {
+                Ok(Box::new(std::iter::once(Api::FnPtrTypedef {
+                    name,
+                    item,
+                    deps,
+                })))
+            }
+            Api::ExternCppType { name, path } => {
+                Ok(Box::new(std::iter::once(Api::ExternCppType { name, path })))
+            }
             Api::RustFn { name, sig, path } => {
                 Ok(Box::new(std::iter::once(Api::RustFn { name, sig, path })))
             }
@@ -0,0 +1,134 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for iterating over C++ containers from Rust. For each type with
+//! `begin()` and `end()` methods, we generate C++ shims to start iterating
+//! and to fetch the next element using the iterator's `operator*` and
+//! `operator++`, and a Rust `iter` method which calls them. Both the C++ and
+//! Rust code generators need to agree on what those shims are.
+
+use std::collections::HashMap;
+
+use quote::ToTokens;
+use syn::{Ident, ReturnType, Type, TypePtr};
+
+use super::{
+    analysis::fun::{FnAnalysis, FnKind, FnPhase, MethodKind, ReceiverMutability},
+    api::Api,
+    apivec::ApiVec,
+};
+use crate::types::{make_ident, QualifiedName};
+
+/// A type which we can iterate over.
+pub(crate) struct IterableType<'a> {
+    pub(crate) name: &'a QualifiedName,
+    /// The return type of `begin()` and `end()`, which is a pointer to
+    /// a const element. That's the only sort of iterator we support,
+    /// because it's the only sort for which we know the element type.
+    pub(crate) iterator: &'a ReturnType,
+}
+
+impl<'a> IterableType<'a> {
+    /// The element type, as bindgen names it.
+    pub(crate) fn element(&self) -> &'a Type {
+        match self.iterator {
+            ReturnType::Type(_, ty) => match ty.as_ref() {
+                Type::Ptr(TypePtr { elem, .. }) => elem,
+                _ => unreachable!("iterators are always pointers"),
+            },
+            ReturnType::Default => unreachable!("iterators are always pointers"),
+        }
+    }
+
+    /// The name of the C++ struct which holds the current and end
+    /// iterators.
+    pub(crate) fn iterator_type_name(&self) -> Ident {
+        make_ident(format!("{}_AutocxxIter", self.name.get_final_item()))
+    }
+
+    /// The name of the shim which starts iterating.
+    pub(crate) fn begin_fn_name(&self) -> Ident {
+        self.shim_name("begin")
+    }
+
+    /// The name of the shim which returns the next element, or null.
+    pub(crate) fn next_fn_name(&self) -> Ident {
+        self.shim_name("next")
+    }
+
+    fn shim_name(&self, operation: &str) -> Ident {
+        make_ident(format!(
+            "{}_autocxx_iter_{}",
+            self.name.get_final_item(),
+            operation
+        ))
+    }
+}
+
+/// Find the types which have const `begin()` and `end()` methods, taking
+/// no parameters and returning the same type of pointer to const. We skip
+/// any which already have a method called `iter`.
+pub(crate) fn find_iterable_types(apis: &ApiVec<FnPhase>) -> Vec<IterableType> {
+    let mut begins: HashMap<&QualifiedName, &ReturnType> = HashMap::new();
+    let mut ends: HashMap<&QualifiedName, &ReturnType> = HashMap::new();
+    let mut has_iter = Vec::new();
+    for api in apis.iter() {
+        if let Api::Function { analysis, .. } = api {
+            if let FnKind::Method { impl_for, .. } = &analysis.kind {
+                if analysis.rust_name == "iter" {
+                    has_iter.push(impl_for);
+                }
+                if !is_iterator_accessor(analysis) {
+                    continue;
+                }
+                match api.effective_cpp_name() {
+                    "begin" => begins.insert(impl_for, &analysis.ret_type),
+                    "end" => ends.insert(impl_for, &analysis.ret_type),
+                    _ => None,
+                };
+            }
+        }
+    }
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Struct { name, analysis, .. }
+                if !analysis.pod.is_generic && !has_iter.contains(&&name.name) =>
+            {
+                let begin = begins.get(&name.name)?;
+                let end = ends.get(&name.name)?;
+                if begin.to_token_stream().to_string() != end.to_token_stream().to_string() {
+                    return None;
+                }
+                Some(IterableType {
+                    name: &name.name,
+                    iterator: begin,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_iterator_accessor(analysis: &FnAnalysis) -> bool {
+    analysis.ignore_reason.is_ok()
+        && analysis.params.len() == 1
+        && matches!(
+            analysis.kind,
+            FnKind::Method {
+                method_kind: MethodKind::Normal(ReceiverMutability::Const),
+                ..
+            }
+        )
+        && matches!(
+            &analysis.ret_type,
+            ReturnType::Type(_, ty) if matches!(
+                ty.as_ref(),
+                Type::Ptr(TypePtr { mutability: None, .. })
+            )
+        )
+}
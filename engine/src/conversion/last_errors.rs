@@ -0,0 +1,70 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for C APIs which report errors errno-style, paired with the
+//! function which fetches the error by a `last_error!` directive. The C++
+//! wrapper for each such function checks for failure and fetches the error
+//! straight after the call, before anything else can overwrite it, and
+//! stores it in a thread-local slot. The Rust wrapper then takes it from
+//! that slot to return a `Result`. Both the C++ and Rust code generators
+//! need to agree on the names of the slot and of the shim which takes the
+//! error from it.
+
+use std::collections::HashSet;
+
+use syn::{Ident, Type};
+
+use super::{analysis::fun::FnPhase, api::Api, apivec::ApiVec};
+use crate::types::{make_ident, QualifiedName};
+
+/// How to capture the error reported by a function named in a
+/// `last_error!` directive.
+#[derive(Clone)]
+pub(crate) struct LastError {
+    /// The function which fetches the error.
+    pub(crate) fetch_fn: QualifiedName,
+    /// The C++ expression to which the function's return value is equal
+    /// when it fails.
+    pub(crate) failure: String,
+    /// The type of the error.
+    pub(crate) error_ty: Type,
+}
+
+impl LastError {
+    /// The name of the C++ function returning the thread-local slot in which
+    /// the error is stored.
+    pub(crate) fn slot_fn_name(&self) -> String {
+        format!("{}_autocxx_last_error", self.fetch_fn.get_final_item())
+    }
+
+    /// The name of the shim which reports whether the last call failed, and
+    /// if so copies out its error.
+    pub(crate) fn take_fn_name(&self) -> Ident {
+        make_ident(format!(
+            "{}_autocxx_take_last_error",
+            self.fetch_fn.get_final_item()
+        ))
+    }
+}
+
+/// Find each distinct error-fetching function used by some function which
+/// we're going to convert. Each gets one slot and one shim.
+pub(crate) fn find_last_error_fetchers(apis: &ApiVec<FnPhase>) -> Vec<&LastError> {
+    let mut seen = HashSet::new();
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Function { analysis, .. }
+                if analysis.ignore_reason.is_ok() && analysis.externally_callable =>
+            {
+                analysis.last_error.as_ref()
+            }
+            _ => None,
+        })
+        .filter(|last_error| seen.insert(&last_error.fetch_fn))
+        .collect()
+}
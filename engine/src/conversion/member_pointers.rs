@@ -0,0 +1,115 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for C++ member pointers, such as `int Foo::*` or
+//! `void (Foo::*)(int)`. bindgen represents these as plain pointers to the
+//! member's type, which would be wrong, so instead each member pointer type
+//! becomes an opaque token type: C++ defines it as a typedef of the member
+//! pointer type, and Rust can hold it in a `UniquePtr` and pass it back to
+//! C++. For pointers to data members, we also generate a C++ shim which
+//! applies the pointer to an object, and a Rust `apply` method which calls
+//! it. Both the C++ and Rust code generators need to agree on what those
+//! shims are.
+
+use std::collections::HashSet;
+
+use syn::{Ident, Type};
+
+use super::{analysis::fun::FnPhase, api::Api, apivec::ApiVec};
+use crate::{
+    known_types::known_types,
+    types::{make_ident, QualifiedName},
+};
+
+/// A pointer to a data member, which a function takes or returns.
+#[derive(Clone)]
+pub(crate) struct DataMemberPointer {
+    /// The opaque token type representing the member pointer.
+    pub(crate) token: QualifiedName,
+    /// The class of which it's a member.
+    pub(crate) class: QualifiedName,
+    /// The type of the member.
+    pub(crate) field: Type,
+    /// Every type named by `field`.
+    pub(crate) field_deps: HashSet<QualifiedName>,
+}
+
+impl DataMemberPointer {
+    /// The name of the shim which returns a pointer to the member of a
+    /// given object.
+    pub(crate) fn apply_fn_name(&self) -> Ident {
+        make_ident(format!("{}_autocxx_apply", self.token.get_final_item()))
+    }
+}
+
+/// The class of which this member pointer type points to a data member, or
+/// `None` if it points to a member function, or we can't tell. C++ spells
+/// data member pointer types as `T Class::*`.
+pub(crate) fn data_member_pointer_class(cpp_definition: &str) -> Option<QualifiedName> {
+    if cpp_definition.contains('(') {
+        return None;
+    }
+    let (_, class) = cpp_definition.strip_suffix("::*")?.rsplit_once(' ')?;
+    if class.is_empty()
+        || !class
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    {
+        return None;
+    }
+    Some(QualifiedName::new_from_cpp_name(class))
+}
+
+/// Find each pointer to a data member of some non-templated class which is
+/// taken or returned by a function we're going to convert, and whose type
+/// we can represent. Each token type gets one `apply` shim.
+pub(crate) fn find_data_member_pointers(apis: &ApiVec<FnPhase>) -> Vec<&DataMemberPointer> {
+    let classes: HashSet<_> = apis
+        .iter()
+        .filter_map(|api| match api {
+            Api::Struct { name, analysis, .. } if !analysis.pod.is_generic => Some(&name.name),
+            _ => None,
+        })
+        .collect();
+    let all_names: HashSet<_> = apis.iter().map(|api| api.name()).collect();
+    let mut tokens_found = HashSet::new();
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Function { analysis, .. } if analysis.ignore_reason.is_ok() => {
+                Some(analysis.data_member_pointers.iter())
+            }
+            _ => None,
+        })
+        .flatten()
+        .filter(|member_pointer| {
+            classes.contains(&member_pointer.class)
+                && member_pointer
+                    .field_deps
+                    .iter()
+                    .all(|dep| known_types().is_known_type(dep) || all_names.contains(dep))
+                && tokens_found.insert(&member_pointer.token)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::data_member_pointer_class;
+
+    #[test]
+    fn test_data_member_pointer_class() {
+        let class = |cpp_definition| {
+            data_member_pointer_class(cpp_definition).map(|class| class.to_cpp_name())
+        };
+        assert_eq!(class("int ns::Foo::*").as_deref(), Some("ns::Foo"));
+        assert_eq!(class("const char * Foo::*").as_deref(), Some("Foo"));
+        assert_eq!(class("void (Foo::*)(int)"), None);
+        assert_eq!(class("int Foo<int>::*"), None);
+        assert_eq!(class("int *"), None);
+    }
+}
@@ -6,20 +6,41 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+mod abi_self_tests;
 mod analysis;
 mod api;
+mod api_summary;
 mod apivec;
 mod codegen_cpp;
 mod codegen_rs;
 #[cfg(test)]
 mod conversion_tests;
 mod convert_error;
+mod dense_matrices;
 mod doc_attr;
 mod error_reporter;
+mod iterators;
+mod last_errors;
+mod member_pointers;
+pub(crate) mod out_of_line;
 mod parse;
+mod qt_signals;
+mod refcounted;
+mod result_types;
+mod shared_ptrs;
+mod smart_pointers;
+mod stable_hash;
+pub(crate) mod suggestions;
+mod unique_arrays;
 mod utilities;
+mod vectors;
 
 use analysis::fun::FnAnalyzer;
+#[cfg(feature = "manifest")]
+pub use api_summary::generated_items_to_json;
+pub use api_summary::{
+    diff_generated_items, GeneratedItem, GeneratedItemChange, GeneratedItemKind,
+};
 use autocxx_parser::IncludeCppConfig;
 pub(crate) use codegen_cpp::CppCodeGenerator;
 pub(crate) use convert_error::ConvertError;
@@ -35,17 +56,21 @@ use self::{
         abstract_types::{discard_ignored_functions, mark_types_abstract},
         allocators::create_alloc_and_frees,
         casts::add_casts,
-        check_names,
+        check_names, check_prefix_stripped_names,
         constructor_deps::decorate_types_with_constructor_deps,
+        field_accessors::add_field_accessors,
         fun::FnPhase,
         gc::filter_apis_by_following_edges_from_allowlist,
         pod::analyze_pod_apis,
         remove_ignored::filter_apis_by_ignored_dependents,
+        resolve_fn_name_collisions,
         tdef::convert_typedef_targets,
     },
     api::AnalysisPhase,
+    api_summary::summarize_apis,
     apivec::ApiVec,
     codegen_rs::RsCodeGenerator,
+    out_of_line::FunctionSymbols,
     parse::ParseBindgen,
 };
 
@@ -68,10 +93,28 @@ pub(crate) struct BridgeConverter<'a> {
     config: &'a IncludeCppConfig,
 }
 
+/// The results of analyzing the bindgen output: every API which we
+/// might generate, annotated with everything the code generators need to
+/// know about it. Neither code generator changes which APIs exist, so
+/// this can also be summarized to describe what will be generated.
+pub(crate) struct ApiAnalysis {
+    apis: ApiVec<FnPhase>,
+    /// The bindgen mod, now emptied of the items which became `apis`.
+    bindgen_mod: ItemMod,
+}
+
+impl ApiAnalysis {
+    /// A description of each of the analyzed APIs.
+    pub(crate) fn items(&self) -> Vec<GeneratedItem> {
+        summarize_apis(&self.apis)
+    }
+}
+
 /// C++ and Rust code generation output.
 pub(crate) struct CodegenResults {
     pub(crate) rs: Vec<Item>,
-    pub(crate) cpp: Option<CppFilePair>,
+    pub(crate) cpp: Vec<CppFilePair>,
+    pub(crate) items: Vec<GeneratedItem>,
 }
 
 impl<'a> BridgeConverter<'a> {
@@ -108,97 +151,125 @@ impl<'a> BridgeConverter<'a> {
         }
     }
 
-    /// Convert a TokenStream of bindgen-generated bindings to a form
-    /// suitable for cxx.
+    /// Analyze a TokenStream of bindgen-generated bindings, working out
+    /// which APIs can be generated and how.
     ///
     /// This is really the heart of autocxx. It parses the output of `bindgen`
     /// (although really by "parse" we mean to interpret the structures already built
-    /// up by the `syn` crate).
-    pub(crate) fn convert(
+    /// up by the `syn` crate). No code is generated; see [`Self::generate`].
+    /// `function_symbols` says which functions can be found in the C++
+    /// library; see [`out_of_line`].
+    pub(crate) fn analyze(
         &self,
         mut bindgen_mod: ItemMod,
         unsafe_policy: UnsafePolicy,
+        function_symbols: FunctionSymbols,
+    ) -> Result<ApiAnalysis, ConvertError> {
+        let items_to_process = match &mut bindgen_mod.content {
+            None => return Err(ConvertError::NoContent),
+            Some((_, items)) => items.drain(..).collect(),
+        };
+        // Parse the bindgen mod.
+        let parser = ParseBindgen::new(self.config);
+        let apis = parser.parse_items(items_to_process)?;
+        Self::dump_apis("parsing", &apis);
+        // Inside parse_results, we now have a list of APIs.
+        // We now enter various analysis phases.
+        // Next, convert any typedefs.
+        // "Convert" means replacing bindgen-style type targets
+        // (e.g. root::std::unique_ptr) with cxx-style targets (e.g. UniquePtr).
+        let apis = convert_typedef_targets(self.config, apis);
+        Self::dump_apis("typedefs", &apis);
+        // Now analyze which of them can be POD (i.e. trivial, movable, pass-by-value
+        // versus which need to be opaque).
+        // Specifically, let's confirm that the items requested by the user to be
+        // POD really are POD, and duly mark any dependent types.
+        // This returns a new list of `Api`s, which will be parameterized with
+        // the analysis results. It also returns an object which can be used
+        // by subsequent phases to work out which objects are POD.
+        let analyzed_apis = analyze_pod_apis(apis, self.config)?;
+        Self::dump_apis("pod analysis", &analyzed_apis);
+        let analyzed_apis = add_casts(analyzed_apis);
+        let analyzed_apis = add_field_accessors(analyzed_apis, self.config);
+        let analyzed_apis = create_alloc_and_frees(analyzed_apis);
+        // Next, figure out how we materialize different functions.
+        // Some will be simple entries in the cxx::bridge module; others will
+        // require C++ wrapper functions. This is probably the most complex
+        // part of `autocxx`. Again, this returns a new set of `Api`s, but
+        // parameterized by a richer set of metadata.
+        Self::dump_apis("adding casts", &analyzed_apis);
+        let analyzed_apis = FnAnalyzer::analyze_functions(
+            analyzed_apis,
+            unsafe_policy,
+            self.config,
+            function_symbols,
+        );
+        // If any of those functions turned out to be pure virtual, don't attempt
+        // to generate UniquePtr implementations for the type, since it can't
+        // be instantiated.
+        Self::dump_apis("analyze fns", &analyzed_apis);
+        let analyzed_apis = mark_types_abstract(analyzed_apis);
+        Self::dump_apis("marking abstract", &analyzed_apis);
+        // Annotate structs with a note of any copy/move constructors which
+        // we may want to retain to avoid garbage collecting them later.
+        let analyzed_apis = decorate_types_with_constructor_deps(analyzed_apis);
+        Self::dump_apis_with_deps("adding constructor deps", &analyzed_apis);
+        let analyzed_apis = discard_ignored_functions(analyzed_apis);
+        Self::dump_apis_with_deps("ignoring ignorable fns", &analyzed_apis);
+        // Rename any functions which would otherwise have the same Rust
+        // name as another.
+        let analyzed_apis = resolve_fn_name_collisions(analyzed_apis);
+        // Remove any APIs whose names are not compatible with cxx.
+        let analyzed_apis = check_names(analyzed_apis);
+        // Reject any items which would have the same name as others
+        // once prefixes are stripped.
+        let analyzed_apis = check_prefix_stripped_names(analyzed_apis, self.config);
+        // During parsing or subsequent processing we might have encountered
+        // items which we couldn't process due to as-yet-unsupported features.
+        // There might be other items depending on such things. Let's remove them
+        // too.
+        let analyzed_apis = filter_apis_by_ignored_dependents(analyzed_apis, self.config);
+        Self::dump_apis_with_deps("removing ignored dependents", &analyzed_apis);
+
+        // We now garbage collect the ones we don't need...
+        let mut analyzed_apis =
+            filter_apis_by_following_edges_from_allowlist(analyzed_apis, self.config);
+        // Determine what variably-sized C types (e.g. int) we need to include
+        analysis::ctypes::append_ctype_information(&mut analyzed_apis);
+        // Generate everything in order of namespace and name, rather than
+        // the order in which things appear in the headers, so that our
+        // output doesn't change when headers are merely rearranged.
+        analyzed_apis.sort_by_name();
+        Self::dump_apis_with_deps("GC", &analyzed_apis);
+        Ok(ApiAnalysis {
+            apis: analyzed_apis,
+            bindgen_mod,
+        })
+    }
+
+    /// Generate Rust and C++ code from the results of [`Self::analyze`],
+    /// in a form suitable for cxx to consume.
+    pub(crate) fn generate(
+        &self,
+        analysis: ApiAnalysis,
         inclusions: String,
         cpp_codegen_options: &CppCodegenOptions,
     ) -> Result<CodegenResults, ConvertError> {
-        match &mut bindgen_mod.content {
-            None => Err(ConvertError::NoContent),
-            Some((_, items)) => {
-                // Parse the bindgen mod.
-                let items_to_process = items.drain(..).collect();
-                let parser = ParseBindgen::new(self.config);
-                let apis = parser.parse_items(items_to_process)?;
-                Self::dump_apis("parsing", &apis);
-                // Inside parse_results, we now have a list of APIs.
-                // We now enter various analysis phases.
-                // Next, convert any typedefs.
-                // "Convert" means replacing bindgen-style type targets
-                // (e.g. root::std::unique_ptr) with cxx-style targets (e.g. UniquePtr).
-                let apis = convert_typedef_targets(self.config, apis);
-                Self::dump_apis("typedefs", &apis);
-                // Now analyze which of them can be POD (i.e. trivial, movable, pass-by-value
-                // versus which need to be opaque).
-                // Specifically, let's confirm that the items requested by the user to be
-                // POD really are POD, and duly mark any dependent types.
-                // This returns a new list of `Api`s, which will be parameterized with
-                // the analysis results. It also returns an object which can be used
-                // by subsequent phases to work out which objects are POD.
-                let analyzed_apis = analyze_pod_apis(apis, self.config)?;
-                Self::dump_apis("pod analysis", &analyzed_apis);
-                let analyzed_apis = add_casts(analyzed_apis);
-                let analyzed_apis = create_alloc_and_frees(analyzed_apis);
-                // Next, figure out how we materialize different functions.
-                // Some will be simple entries in the cxx::bridge module; others will
-                // require C++ wrapper functions. This is probably the most complex
-                // part of `autocxx`. Again, this returns a new set of `Api`s, but
-                // parameterized by a richer set of metadata.
-                Self::dump_apis("adding casts", &analyzed_apis);
-                let analyzed_apis =
-                    FnAnalyzer::analyze_functions(analyzed_apis, unsafe_policy, self.config);
-                // If any of those functions turned out to be pure virtual, don't attempt
-                // to generate UniquePtr implementations for the type, since it can't
-                // be instantiated.
-                Self::dump_apis("analyze fns", &analyzed_apis);
-                let analyzed_apis = mark_types_abstract(analyzed_apis);
-                Self::dump_apis("marking abstract", &analyzed_apis);
-                // Annotate structs with a note of any copy/move constructors which
-                // we may want to retain to avoid garbage collecting them later.
-                let analyzed_apis = decorate_types_with_constructor_deps(analyzed_apis);
-                Self::dump_apis_with_deps("adding constructor deps", &analyzed_apis);
-                let analyzed_apis = discard_ignored_functions(analyzed_apis);
-                Self::dump_apis_with_deps("ignoring ignorable fns", &analyzed_apis);
-                // Remove any APIs whose names are not compatible with cxx.
-                let analyzed_apis = check_names(analyzed_apis);
-                // During parsing or subsequent processing we might have encountered
-                // items which we couldn't process due to as-yet-unsupported features.
-                // There might be other items depending on such things. Let's remove them
-                // too.
-                let analyzed_apis = filter_apis_by_ignored_dependents(analyzed_apis);
-                Self::dump_apis_with_deps("removing ignored dependents", &analyzed_apis);
-
-                // We now garbage collect the ones we don't need...
-                let mut analyzed_apis =
-                    filter_apis_by_following_edges_from_allowlist(analyzed_apis, self.config);
-                // Determine what variably-sized C types (e.g. int) we need to include
-                analysis::ctypes::append_ctype_information(&mut analyzed_apis);
-                Self::dump_apis_with_deps("GC", &analyzed_apis);
-                // And finally pass them to the code gen phases, which outputs
-                // code suitable for cxx to consume.
-                let cpp = CppCodeGenerator::generate_cpp_code(
-                    inclusions,
-                    &analyzed_apis,
-                    self.config,
-                    cpp_codegen_options,
-                )?;
-                let rs = RsCodeGenerator::generate_rs_code(
-                    analyzed_apis,
-                    self.include_list,
-                    bindgen_mod,
-                    self.config,
-                    cpp.as_ref().map(|file_pair| file_pair.header_name.clone()),
-                );
-                Ok(CodegenResults { rs, cpp })
-            }
-        }
+        let items = analysis.items();
+        let cpp = CppCodeGenerator::generate_cpp_code(
+            inclusions,
+            &analysis.apis,
+            self.config,
+            cpp_codegen_options,
+        )?;
+        let rs = RsCodeGenerator::generate_rs_code(
+            analysis.apis,
+            self.include_list,
+            !cpp_codegen_options.module_imports.is_empty(),
+            analysis.bindgen_mod,
+            self.config,
+            cpp.as_ref().map(|file_pair| file_pair.header_name.clone()),
+        );
+        Ok(CodegenResults { rs, cpp, items })
     }
 }
@@ -0,0 +1,74 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Finding out which functions are defined out-of-line, for
+//! `dynamic_library!`. An inline function needn't be exported from the
+//! library, so we can't look it up there, and must call it directly
+//! instead. bindgen doesn't tell us which functions are inline, but it
+//! can be asked to leave them out, so we ask it again without them and
+//! see which functions remain.
+//!
+//! Likewise, for `export_macro!`, finding out which out-of-line functions
+//! the library doesn't export. bindgen leaves out functions with hidden
+//! visibility, so we ask it yet again as though the library were being
+//! built, with `-fvisibility=hidden` and each export macro giving default
+//! visibility, and see which functions go missing.
+
+use std::collections::HashSet;
+
+use syn::{ForeignItem, Item, ItemMod};
+
+use super::parse::{get_link_name, strip_link_name_prefix};
+
+/// What asking bindgen again told us about which functions can be found
+/// in the C++ library.
+#[derive(Default)]
+pub(crate) struct FunctionSymbols {
+    /// The functions defined out-of-line, if we needed to know.
+    pub(crate) out_of_line: Option<HashSet<String>>,
+    /// Functions defined out-of-line which the library doesn't export.
+    pub(crate) hidden: HashSet<String>,
+}
+
+/// The name by which we know a function in bindgen output: its symbol,
+/// if bindgen tells us, and otherwise (for an `extern "C"` function)
+/// its identifier.
+pub(crate) fn function_key(link_name: Option<&str>, ident: &syn::Ident) -> String {
+    link_name
+        .map(|link_name| strip_link_name_prefix(link_name).to_string())
+        .unwrap_or_else(|| ident.to_string())
+}
+
+/// The keys, as given by [`function_key`], of each function in some
+/// bindgen output.
+pub(crate) fn declared_functions(bindings: &ItemMod) -> HashSet<String> {
+    let mut functions = HashSet::new();
+    add_declared_functions(bindings, &mut functions);
+    functions
+}
+
+fn add_declared_functions(itm: &ItemMod, functions: &mut HashSet<String>) {
+    let items = match &itm.content {
+        Some((_, items)) => items,
+        None => return,
+    };
+    for item in items {
+        match item {
+            Item::ForeignMod(fm) => {
+                for fi in &fm.items {
+                    if let ForeignItem::Fn(f) = fi {
+                        let link_name = get_link_name(&f.attrs);
+                        functions.insert(function_key(link_name.as_deref(), &f.sig.ident));
+                    }
+                }
+            }
+            Item::Mod(inner) => add_declared_functions(inner, functions),
+            _ => {}
+        }
+    }
+}
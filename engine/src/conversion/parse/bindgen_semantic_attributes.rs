@@ -111,7 +111,7 @@ impl BindgenSemanticAttributes {
     }
 
     /// The original C++ name, which bindgen may have changed.
-    pub(super) fn get_original_name(&self) -> Option<String> {
+    pub(crate) fn get_original_name(&self) -> Option<String> {
         self.string_if_present("original_name")
     }
 
@@ -146,12 +146,43 @@ impl BindgenSemanticAttributes {
                 if let Ok(ls) = r {
                     results.rvalue_ref_params.insert(ls);
                 }
+            } else if a.is_ident("arg_type_member_pointer") {
+                let r: Result<MemberPointerParam, syn::Error> = a.parse_args();
+                if let Ok(mp) = r {
+                    results
+                        .member_pointer_params
+                        .insert(mp.param, mp.cpp_definition.value());
+                }
+            } else if a.is_ident("ret_type_member_pointer") {
+                let r: Result<LitStr, syn::Error> = a.parse_args();
+                if let Ok(ls) = r {
+                    results.member_pointer_return = Some(ls.value());
+                }
             }
         }
         results
     }
 }
 
+/// The body of an `arg_type_member_pointer` annotation: the parameter
+/// name, and the C++ spelling of its member pointer type.
+struct MemberPointerParam {
+    param: Ident,
+    cpp_definition: LitStr,
+}
+
+impl Parse for MemberPointerParam {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let param = input.parse()?;
+        input.parse::<syn::token::Comma>()?;
+        let cpp_definition = input.parse()?;
+        Ok(Self {
+            param,
+            cpp_definition,
+        })
+    }
+}
+
 #[derive(Debug)]
 struct BindgenSemanticAttribute {
     annotation_name: Ident,
@@ -0,0 +1,95 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! bindgen only tells us the offset of each field of a struct within the
+//! layout test it generates for that struct, which looks like this:
+//!
+//! ```ignore
+//! #[test]
+//! fn bindgen_test_layout_Point() {
+//!     assert_eq!(::std::mem::size_of::<Point>(), 16usize, concat!("Size of: ", stringify!(Point)));
+//!     assert_eq!(
+//!         unsafe { &(*(::std::ptr::null::<Point>())).y as *const _ as usize },
+//!         8usize,
+//!         concat!("Offset of field: ", stringify!(Point), "::", stringify!(y))
+//!     );
+//! }
+//! ```
+
+use syn::{
+    parse::Parser, punctuated::Punctuated, token::Comma, Expr, ExprLit, Ident, Item, ItemFn, Lit,
+    Macro, Stmt,
+};
+
+use crate::types::make_ident;
+
+const LAYOUT_TEST_PREFIX: &str = "bindgen_test_layout_";
+
+/// Whether this is one of the tests bindgen generates to check the layout
+/// of a type.
+pub(super) fn is_layout_test(f: &ItemFn) -> bool {
+    f.attrs.iter().any(|attr| attr.path.is_ident("test"))
+}
+
+/// If this is the layout test for a struct, returns the name of the struct
+/// and the offset in bytes of each of its fields.
+pub(super) fn field_offsets_from_layout_test(f: &ItemFn) -> Option<(Ident, Vec<(Ident, usize)>)> {
+    if !is_layout_test(f) {
+        return None;
+    }
+    let ty = f.sig.ident.to_string();
+    let ty = make_ident(ty.strip_prefix(LAYOUT_TEST_PREFIX)?);
+    let offsets = f
+        .block
+        .stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Item(Item::Macro(m)) => Some(&m.mac),
+            Stmt::Semi(Expr::Macro(m), _) | Stmt::Expr(Expr::Macro(m)) => Some(&m.mac),
+            _ => None,
+        })
+        .filter_map(field_offset_from_assertion)
+        .collect();
+    Some((ty, offsets))
+}
+
+fn field_offset_from_assertion(mac: &Macro) -> Option<(Ident, usize)> {
+    if !mac.path.is_ident("assert_eq") {
+        return None;
+    }
+    let args = macro_args(mac)?;
+    let offset = match args.get(1)? {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(offset),
+            ..
+        }) => offset.base10_parse().ok()?,
+        _ => return None,
+    };
+    let description = match args.get(2)? {
+        Expr::Macro(m) if m.mac.path.is_ident("concat") => macro_args(&m.mac)?,
+        _ => return None,
+    };
+    match description.first()? {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(s), ..
+        }) if s.value() == "Offset of field: " => {}
+        _ => return None,
+    }
+    let field = match description.last()? {
+        Expr::Macro(m) if m.mac.path.is_ident("stringify") => m.mac.parse_body::<Ident>().ok()?,
+        _ => return None,
+    };
+    Some((field, offset))
+}
+
+fn macro_args(mac: &Macro) -> Option<Vec<Expr>> {
+    Punctuated::<Expr, Comma>::parse_terminated
+        .parse2(mac.tokens.clone())
+        .ok()
+        .map(|args| args.into_iter().collect())
+}
@@ -7,8 +7,10 @@
 // except according to those terms.
 
 mod bindgen_semantic_attributes;
+mod layout_tests;
 mod parse_bindgen;
 mod parse_foreign_mod;
 
 pub(crate) use bindgen_semantic_attributes::BindgenSemanticAttributes;
 pub(crate) use parse_bindgen::ParseBindgen;
+pub(crate) use parse_foreign_mod::{get_link_name, strip_link_name_prefix};
@@ -6,37 +6,45 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     conversion::{
-        api::{Api, ApiName, NullPhase, StructDetails, SubclassName, TypedefKind, UnanalyzedApi},
+        api::{
+            Api, ApiName, CppVisibility, NullPhase, StructDetails, SubclassName, TypedefKind,
+            UnanalyzedApi,
+        },
         apivec::ApiVec,
         ConvertError,
     },
-    types::Namespace,
-    types::QualifiedName,
+    types::{make_ident, Namespace, QualifiedName},
 };
 use crate::{
     conversion::{
         convert_error::{ConvertErrorWithContext, ErrorContext},
         error_reporter::report_any_error,
     },
-    types::validate_ident_ok_for_cxx,
+    types::{validate_ident_ok_for_cxx, validate_ident_ok_for_rust},
 };
-use autocxx_parser::IncludeCppConfig;
-use syn::{parse_quote, Fields, Ident, Item, TypePath, UseTree};
+use autocxx_parser::{IncludeCppConfig, RustPath};
+use syn::{parse_quote, Expr, Fields, Ident, Item, ItemEnum, ItemStruct, Type, TypePath, UseTree};
 
 use super::{
     super::utilities::generate_utilities, bindgen_semantic_attributes::BindgenSemanticAttributes,
 };
 
+use super::layout_tests::{field_offsets_from_layout_test, is_layout_test};
 use super::parse_foreign_mod::ParseForeignMod;
 
+/// bindgen names each anonymous enum `_bindgen_ty_N`, or
+/// `Outer__bindgen_ty_N` if it's nested within a class.
+const ANONYMOUS_ENUM_MARKER: &str = "_bindgen_ty_";
+
 /// Parses a bindgen mod in order to understand the APIs within it.
 pub(crate) struct ParseBindgen<'a> {
     config: &'a IncludeCppConfig,
     apis: ApiVec<NullPhase>,
+    field_offsets: HashMap<QualifiedName, Vec<(Ident, usize)>>,
 }
 
 fn api_name(ns: &Namespace, id: Ident, attrs: &BindgenSemanticAttributes) -> ApiName {
@@ -57,11 +65,55 @@ pub(crate) fn api_name_qualified(
     }
 }
 
+/// bindgen names a `static constexpr` data member `kMax` of a class `Foo`
+/// `Foo_kMax`, and tells us its original name, which is either `Foo::kMax`
+/// or just `kMax`. If this is such a member, returns the names of the class
+/// and of the member.
+fn static_member_owner(id: &Ident, cpp_name: Option<&String>) -> Option<(Ident, Ident)> {
+    let cpp_name = cpp_name?;
+    let (owner, member) = match cpp_name.rsplit_once("::") {
+        Some((owner, member)) => (owner.replace("::", "_"), member),
+        None => {
+            let owner = id
+                .to_string()
+                .strip_suffix(cpp_name.as_str())?
+                .strip_suffix('_')?
+                .to_string();
+            (owner, cpp_name.as_str())
+        }
+    };
+    if owner.is_empty() || validate_ident_ok_for_rust(member).is_err() {
+        return None;
+    }
+    Some((make_ident(owner), make_ident(member)))
+}
+
+/// `offsetof` is only reliably supported for standard-layout types, and
+/// can't name private fields, so we only want to check the offsets of
+/// public fields, and only if there are no base classes or vtable.
+fn checkable_field_offsets(s: &ItemStruct, offsets: Vec<(Ident, usize)>) -> Vec<(Ident, usize)> {
+    let mut field_names = s.fields.iter().filter_map(|f| f.ident.as_ref());
+    if field_names.any(|id| id == "vtable_" || id.to_string().starts_with("_base")) {
+        return Vec::new();
+    }
+    offsets
+        .into_iter()
+        .filter(|(field, _)| {
+            s.fields.iter().any(|f| {
+                f.ident.as_ref() == Some(field)
+                    && BindgenSemanticAttributes::new(&f.attrs).get_cpp_visibility()
+                        == CppVisibility::Public
+            })
+        })
+        .collect()
+}
+
 impl<'a> ParseBindgen<'a> {
     pub(crate) fn new(config: &'a IncludeCppConfig) -> Self {
         ParseBindgen {
             config,
             apis: ApiVec::new(),
+            field_offsets: HashMap::new(),
         }
     }
 
@@ -108,6 +160,64 @@ impl<'a> ParseBindgen<'a> {
         }));
     }
 
+    /// If this type's namespace was named in an `extern_cpp_ns!` directive,
+    /// the path to the Rust type generated for it by the other
+    /// `include_cpp!`. That mirrors the C++ namespaces just as ours does.
+    fn extern_cpp_type_path(&self, ns: &Namespace, id: &Ident) -> Option<RustPath> {
+        let mod_path = self.config.extern_cpp_ns_path(&ns.to_cpp_string())?;
+        Some(
+            ns.iter()
+                .map(make_ident)
+                .chain(std::iter::once(id.clone()))
+                .fold(mod_path.clone(), |path, id| path.append(id)),
+        )
+    }
+
+    /// An anonymous enum, such as `enum { FLAG_A = 1, FLAG_B = 2 };`, is
+    /// typically just a bag of constants, and there's no type which Rust
+    /// code could name anyway. So make each variant a constant of the
+    /// enum's underlying integer type instead. For an anonymous enum nested
+    /// in a class, the constants are prefixed by the class name, just as
+    /// bindgen names nested types.
+    fn parse_anonymous_enum(&mut self, e: ItemEnum, ns: &Namespace) {
+        let enum_name = e.ident.to_string();
+        let prefix = enum_name
+            .split(ANONYMOUS_ENUM_MARKER)
+            .next()
+            .unwrap_or_default();
+        let repr: Type = e
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("repr"))
+            .and_then(|attr| attr.parse_args().ok())
+            .unwrap_or_else(|| parse_quote! { u32 });
+        let mut next_value: Expr = parse_quote! { 0 };
+        for variant in e.variants {
+            let value = match variant.discriminant {
+                Some((_, value)) => value,
+                None => next_value,
+            };
+            next_value = parse_quote! { (#value) + 1 };
+            let id = make_ident(format!("{}{}", prefix, variant.ident));
+            let name = ApiName::new(ns, id.clone());
+            if self.config.is_on_blocklist(&name.name.to_cpp_name()) {
+                continue;
+            }
+            let doc_attrs = variant
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("doc"));
+            self.apis.push(UnanalyzedApi::Const {
+                name,
+                const_item: parse_quote! {
+                    #(#doc_attrs)*
+                    pub const #id: #repr = #value;
+                },
+                impl_for: None,
+            });
+        }
+    }
+
     fn find_items_in_root(items: Vec<Item>) -> Result<Vec<Item>, ConvertError> {
         for item in items {
             match item {
@@ -133,6 +243,16 @@ impl<'a> ParseBindgen<'a> {
         // this particular mod.
         let mut mod_converter = ParseForeignMod::new(ns.clone());
         let mut more_apis = ApiVec::new();
+        // Each struct's layout test follows it, so find them first.
+        for item in &items {
+            if let Some((ty, offsets)) = match item {
+                Item::Fn(f) => field_offsets_from_layout_test(f),
+                _ => None,
+            } {
+                self.field_offsets
+                    .insert(QualifiedName::new(&ns, ty), offsets);
+            }
+        }
         for item in items {
             report_any_error(&ns, &mut more_apis, || {
                 self.parse_item(item, &mut mod_converter, &ns)
@@ -150,7 +270,15 @@ impl<'a> ParseBindgen<'a> {
     ) -> Result<(), ConvertErrorWithContext> {
         match item {
             Item::ForeignMod(fm) => {
-                mod_converter.convert_foreign_mod_items(fm.items);
+                // Functions in a namespace generated by another include_cpp!,
+                // including methods of its types, are left to that one.
+                if self
+                    .config
+                    .extern_cpp_ns_path(&ns.to_cpp_string())
+                    .is_none()
+                {
+                    mod_converter.convert_foreign_mod_items(fm.items, &fm.abi);
+                }
                 Ok(())
             }
             Item::Struct(s) => {
@@ -164,6 +292,8 @@ impl<'a> ParseBindgen<'a> {
                 let name = api_name_qualified(ns, s.ident.clone(), &annotations)?;
                 let api = if ns.is_empty() && self.config.is_rust_type(&s.ident) {
                     None
+                } else if let Some(path) = self.extern_cpp_type_path(ns, &s.ident) {
+                    Some(UnanalyzedApi::ExternCppType { name, path })
                 } else if is_forward_declaration {
                     Some(UnanalyzedApi::ForwardDeclaration { name })
                 } else {
@@ -175,6 +305,12 @@ impl<'a> ParseBindgen<'a> {
                         details: Box::new(StructDetails {
                             vis: annotations.get_cpp_visibility(),
                             layout: annotations.get_layout(),
+                            field_offsets: checkable_field_offsets(
+                                &s,
+                                self.field_offsets
+                                    .remove(&QualifiedName::new(ns, s.ident.clone()))
+                                    .unwrap_or_default(),
+                            ),
                             item: s,
                             has_rvalue_reference_fields,
                         }),
@@ -188,11 +324,16 @@ impl<'a> ParseBindgen<'a> {
                 }
                 Ok(())
             }
+            Item::Enum(e) if e.ident.to_string().contains(ANONYMOUS_ENUM_MARKER) => {
+                self.parse_anonymous_enum(e, ns);
+                Ok(())
+            }
             Item::Enum(e) => {
                 let annotations = BindgenSemanticAttributes::new(&e.attrs);
-                let api = UnanalyzedApi::Enum {
-                    name: api_name_qualified(ns, e.ident.clone(), &annotations)?,
-                    item: e,
+                let name = api_name_qualified(ns, e.ident.clone(), &annotations)?;
+                let api = match self.extern_cpp_type_path(ns, &e.ident) {
+                    Some(path) => UnanalyzedApi::ExternCppType { name, path },
+                    None => UnanalyzedApi::Enum { name, item: e },
                 };
                 if !self.config.is_on_blocklist(&api.name().to_cpp_name()) {
                     self.apis.push(api);
@@ -206,7 +347,10 @@ impl<'a> ParseBindgen<'a> {
                 // synthetic impl blocks.
                 // We do however record which methods were spotted, since
                 // we have no other way of working out which functions are
-                // static methods vs plain functions.
+                // static methods vs plain functions, and which functions
+                // are constructors. We also keep any associated constants,
+                // such as those bindgen generates for enumerators which
+                // share a value with an earlier one.
                 mod_converter.convert_impl_items(imp);
                 Ok(())
             }
@@ -232,15 +376,29 @@ impl<'a> ParseBindgen<'a> {
                             let old_id = &urn.ident;
                             let new_id = &urn.rename;
                             let new_tyname = QualifiedName::new(ns, new_id.clone());
-                            assert!(segs.remove(0) == "self", "Path didn't start with self");
-                            assert!(
-                                segs.remove(0) == "super",
-                                "Path didn't start with self::super"
-                            );
-                            // This is similar to the path encountered within 'tree'
-                            // but without the self::super prefix which is unhelpful
-                            // in our output mod, because we prefer relative paths
-                            // (we're nested in another mod)
+                            // bindgen refers to the target relative to this mod,
+                            // going up one 'super' for each level of namespace,
+                            // e.g. self::super::super::root::base::IdType.
+                            // We strip off the self::super... prefix which is
+                            // unhelpful in our output mod, because we prefer
+                            // relative paths (we're nested in another mod), leaving
+                            // a path from 'root' which means the same thing in any
+                            // namespace.
+                            let supers = segs
+                                .iter()
+                                .skip(1)
+                                .take_while(|seg| *seg == "super")
+                                .count();
+                            if segs.first().map_or(true, |seg| seg != "self")
+                                || supers == 0
+                                || segs.get(supers + 1).map_or(true, |seg| seg != "root")
+                            {
+                                return Err(ConvertErrorWithContext(
+                                    ConvertError::UnexpectedUseStatement(Some(new_id.clone())),
+                                    Some(ErrorContext::new_for_item(new_id.clone())),
+                                ));
+                            }
+                            segs.drain(..supers + 1);
                             let old_path: TypePath = parse_quote! {
                                 #(#segs)::* :: #old_id
                             };
@@ -272,14 +430,25 @@ impl<'a> ParseBindgen<'a> {
                 }
                 Ok(())
             }
-            Item::Const(const_item) => {
+            Item::Const(mut const_item) => {
                 let annotations = BindgenSemanticAttributes::new(&const_item.attrs);
+                let name = api_name(ns, const_item.ident.clone(), &annotations);
+                // A `static constexpr` member of a class becomes an
+                // associated constant of the type.
+                let impl_for = static_member_owner(&const_item.ident, name.cpp_name_if_present())
+                    .map(|(owner, member)| {
+                        const_item.ident = member;
+                        QualifiedName::new(ns, owner)
+                    });
                 self.apis.push(UnanalyzedApi::Const {
-                    name: api_name(ns, const_item.ident.clone(), &annotations),
+                    name,
                     const_item,
+                    impl_for,
                 });
                 Ok(())
             }
+            // Any layout tests were dealt with in parse_mod_items.
+            Item::Fn(f) if is_layout_test(&f) => Ok(()),
             Item::Type(ity) => {
                 let annotations = BindgenSemanticAttributes::new(&ity.attrs);
                 // It's known that sometimes bindgen will give us duplicate typedefs with the
@@ -313,7 +482,10 @@ impl<'a> ParseBindgen<'a> {
             .collect();
         for generate_directive in self.config.must_generate_list() {
             if !api_names.contains(&generate_directive) {
-                return Err(ConvertError::DidNotGenerateAnything(generate_directive));
+                return Err(ConvertError::DidNotGenerateAnything(
+                    generate_directive,
+                    None,
+                ));
             }
         }
         Ok(())
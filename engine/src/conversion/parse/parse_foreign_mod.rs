@@ -6,7 +6,12 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::conversion::api::{ApiName, NullPhase, Provenance};
+use crate::conversion::analysis::fun::function_wrapper::{
+    CallingConvention, CppFunctionBody, CppFunctionKind,
+};
+use crate::conversion::api::{
+    ApiName, CppVisibility, NullPhase, Provenance, References, SpecialMemberKind, Virtualness,
+};
 use crate::conversion::apivec::ApiVec;
 use crate::conversion::doc_attr::get_doc_attr;
 use crate::conversion::error_reporter::report_any_error;
@@ -17,10 +22,14 @@ use crate::conversion::{
 };
 use crate::{
     conversion::ConvertError,
-    types::{Namespace, QualifiedName},
+    types::{make_ident, Namespace, QualifiedName},
+};
+use std::collections::{HashMap, HashSet};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_quote, Attribute, Block, Expr, ExprCall, ExprMethodCall, ForeignItem, Ident, ImplItem,
+    ImplItemConst, ItemConst, ItemImpl, Lit, Meta, MetaNameValue, Stmt, Type,
 };
-use std::collections::HashMap;
-use syn::{Block, Expr, ExprCall, ForeignItem, Ident, ImplItem, ItemImpl, Stmt, Type};
 
 use super::bindgen_semantic_attributes::BindgenSemanticAttributes;
 
@@ -39,6 +48,13 @@ pub(crate) struct ParseForeignMod {
     // may actually be methods (static or otherwise). Mapping from
     // function name to type name.
     method_receivers: HashMap<Ident, QualifiedName>,
+    // Functions which 'impl' blocks show to be constructors. We can't
+    // reliably tell from their names, since a method might happen to start
+    // with the name of its class.
+    constructors: HashSet<Ident>,
+    // Associated constants from 'impl' blocks, and the types to which
+    // they belong.
+    associated_consts: Vec<(QualifiedName, ItemConst)>,
     ignored_apis: ApiVec<NullPhase>,
 }
 
@@ -48,27 +64,46 @@ impl ParseForeignMod {
             ns,
             funcs_to_convert: Vec::new(),
             method_receivers: HashMap::new(),
+            constructors: HashSet::new(),
+            associated_consts: Vec::new(),
             ignored_apis: ApiVec::new(),
         }
     }
 
     /// Record information from foreign mod items encountered
     /// in bindgen output.
-    pub(crate) fn convert_foreign_mod_items(&mut self, foreign_mod_items: Vec<ForeignItem>) {
+    pub(crate) fn convert_foreign_mod_items(
+        &mut self,
+        foreign_mod_items: Vec<ForeignItem>,
+        abi: &syn::Abi,
+    ) {
+        let calling_convention = CallingConvention::from_abi(abi);
         let mut extra_apis = ApiVec::new();
         for i in foreign_mod_items {
             report_any_error(&self.ns.clone(), &mut extra_apis, || {
-                self.parse_foreign_item(i)
+                self.parse_foreign_item(i, calling_convention)
             });
         }
         self.ignored_apis.append(&mut extra_apis);
     }
 
-    fn parse_foreign_item(&mut self, i: ForeignItem) -> Result<(), ConvertErrorWithContext> {
+    fn parse_foreign_item(
+        &mut self,
+        i: ForeignItem,
+        calling_convention: Option<CallingConvention>,
+    ) -> Result<(), ConvertErrorWithContext> {
         match i {
             ForeignItem::Fn(item) => {
                 let annotations = BindgenSemanticAttributes::new(&item.attrs);
                 let doc_attr = get_doc_attr(&item.attrs);
+                let link_name = get_link_name(&item.attrs)
+                    .map(|link_name| strip_link_name_prefix(&link_name).to_string());
+                let mut references = annotations.get_reference_parameters_and_return();
+                // bindgen doesn't tell us about ref-qualifiers, but the
+                // mangled name does.
+                references.rvalue_ref_this = link_name
+                    .as_ref()
+                    .map_or(false, |link_name| is_rvalue_ref_qualified(link_name));
                 self.funcs_to_convert.push(FuncToConvert {
                     provenance: Provenance::Bindgen,
                     self_ty: None,
@@ -82,19 +117,63 @@ impl ParseForeignMod {
                     special_member: annotations.special_member_kind(),
                     unused_template_param: annotations
                         .has_attr("unused_template_param_in_arg_or_return"),
-                    references: annotations.get_reference_parameters_and_return(),
+                    references,
                     original_name: annotations.get_original_name(),
                     synthesized_this_type: None,
                     add_to_trait: None,
                     is_deleted: annotations.has_attr("deleted"),
+                    calling_convention,
+                    link_name,
                     synthetic_cpp: None,
                 });
                 Ok(())
             }
-            ForeignItem::Static(item) => Err(ConvertErrorWithContext(
-                ConvertError::StaticData(item.ident.to_string()),
-                Some(ErrorContext::new_for_item(item.ident)),
-            )),
+            ForeignItem::Static(item) => {
+                // Rust code can't safely refer to C++ globals directly - some
+                // may be thread_local, for instance - so instead we generate
+                // a C++ function returning the address of the variable, as
+                // seen by the calling thread.
+                let cpp_name = match get_link_name(&item.attrs) {
+                    Some(link_name) if is_mangled(&link_name) => demangle_global_name(&link_name),
+                    _ => Some(QualifiedName::new(&self.ns, item.ident.clone())),
+                };
+                let cpp_name = cpp_name.ok_or_else(|| {
+                    ConvertErrorWithContext(
+                        ConvertError::StaticData(item.ident.to_string()),
+                        Some(ErrorContext::new_for_item(item.ident.clone())),
+                    )
+                })?;
+                let ty = item.ty;
+                let output = match item.mutability {
+                    Some(_) => parse_quote! { -> *mut #ty },
+                    None => parse_quote! { -> *const #ty },
+                };
+                self.funcs_to_convert.push(FuncToConvert {
+                    provenance: Provenance::SynthesizedOther,
+                    self_ty: None,
+                    ident: item.ident,
+                    doc_attr: get_doc_attr(&item.attrs),
+                    inputs: Punctuated::new(),
+                    output,
+                    vis: item.vis,
+                    virtualness: Virtualness::None,
+                    cpp_vis: CppVisibility::Public,
+                    special_member: None,
+                    unused_template_param: false,
+                    references: References::default(),
+                    original_name: None,
+                    synthesized_this_type: None,
+                    add_to_trait: None,
+                    is_deleted: false,
+                    calling_convention: None,
+                    link_name: None,
+                    synthetic_cpp: Some((
+                        CppFunctionBody::AddressOfGlobal(cpp_name),
+                        CppFunctionKind::Function,
+                    )),
+                });
+                Ok(())
+            }
             _ => Err(ConvertErrorWithContext(
                 ConvertError::UnexpectedForeignItem,
                 None,
@@ -110,7 +189,18 @@ impl ParseForeignMod {
             _ => return,
         };
         for i in imp.items {
+            if let ImplItem::Const(itc) = &i {
+                if is_of_type(&itc.ty, &ty_id) {
+                    self.associated_consts.push((
+                        QualifiedName::new(&self.ns, ty_id.clone()),
+                        to_item_const(itc),
+                    ));
+                }
+            }
             if let ImplItem::Method(itm) = i {
+                if let Some(constructor) = get_called_constructor(&itm.block) {
+                    self.constructors.insert(constructor.clone());
+                }
                 let effective_fun_name = match get_called_function(&itm.block) {
                     Some(id) => id.clone(),
                     None => itm.sig.ident,
@@ -128,9 +218,20 @@ impl ParseForeignMod {
     /// the resulting APIs.
     pub(crate) fn finished(mut self, apis: &mut ApiVec<NullPhase>) {
         apis.append(&mut self.ignored_apis);
+        for (ty, const_item) in self.associated_consts.drain(..) {
+            let id = make_ident(format!("{}_{}", ty.get_final_item(), const_item.ident));
+            apis.push(UnanalyzedApi::Const {
+                name: ApiName::new(&self.ns, id),
+                const_item,
+                impl_for: Some(ty),
+            });
+        }
         while !self.funcs_to_convert.is_empty() {
             let mut fun = self.funcs_to_convert.remove(0);
             fun.self_ty = self.method_receivers.get(&fun.ident).cloned();
+            if fun.special_member.is_none() && self.constructors.contains(&fun.ident) {
+                fun.special_member = Some(SpecialMemberKind::Constructor);
+            }
             apis.push(UnanalyzedApi::Function {
                 name: ApiName::new_with_cpp_name(
                     &self.ns,
@@ -158,12 +259,201 @@ fn get_called_function(block: &Block) -> Option<&Ident> {
     }
 }
 
+/// Whether this is the type in whose 'impl' block it appears. We only
+/// keep associated constants of that type, which bindgen generates for
+/// enumerators that share their value with an earlier enumerator: Rust
+/// enums can't have two variants with the same value.
+fn is_of_type(ty: &Type, ty_id: &Ident) -> bool {
+    matches!(ty, Type::Path(typ) if typ.qself.is_none()
+        && (typ.path.is_ident(ty_id) || typ.path.is_ident("Self")))
+}
+
+fn to_item_const(itc: &ImplItemConst) -> ItemConst {
+    let ImplItemConst {
+        attrs,
+        vis,
+        ident,
+        ty,
+        expr,
+        ..
+    } = itc;
+    parse_quote! {
+        #(#attrs)*
+        #vis const #ident: #ty = #expr;
+    }
+}
+
+/// bindgen generates a `new` function in an 'impl' block for each
+/// constructor, which calls the constructor on uninitialized memory:
+/// ```ignore
+/// pub unsafe fn new(a: c_int) -> Self {
+///     let mut __bindgen_tmp = ::std::mem::MaybeUninit::uninit();
+///     A_A(__bindgen_tmp.as_mut_ptr(), a);
+///     __bindgen_tmp.assume_init()
+/// }
+/// ```
+/// If this is the body of such a function, returns the name of the
+/// constructor.
+fn get_called_constructor(block: &Block) -> Option<&Ident> {
+    block.stmts.iter().find_map(|stmt| match stmt {
+        Stmt::Semi(Expr::Call(ExprCall { func, args, .. }), _) => match (&**func, args.first()) {
+            (
+                Expr::Path(exp),
+                Some(Expr::MethodCall(ExprMethodCall {
+                    receiver, method, ..
+                })),
+            ) if method == "as_mut_ptr"
+                && matches!(&**receiver, Expr::Path(recv) if recv.path.is_ident("__bindgen_tmp")) =>
+            {
+                exp.path.get_ident()
+            }
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// The symbol name which bindgen gives a global variable, if it's
+/// different from the Rust name.
+pub(crate) fn get_link_name(attrs: &[Attribute]) -> Option<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("link_name"))
+        .find_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(name),
+                ..
+            })) => Some(name.value()),
+            _ => None,
+        })
+}
+
+/// bindgen prefixes symbol names with `\u{1}` to stop the linker mangling
+/// them further.
+pub(crate) fn strip_link_name_prefix(link_name: &str) -> &str {
+    link_name.trim_start_matches('\u{1}')
+}
+
+/// Whether a symbol name is a mangled C++ name, rather than a plain name in
+/// the global namespace.
+fn is_mangled(link_name: &str) -> bool {
+    let link_name = strip_link_name_prefix(link_name);
+    link_name.starts_with("_Z") || link_name.starts_with("__Z") || link_name.starts_with('?')
+}
+
+/// Whether a mangled name is that of a `&&`-qualified method. Only the
+/// Itanium mangling scheme is supported, in which the qualifier follows
+/// any cv-qualifiers at the start of the nested name.
+fn is_rvalue_ref_qualified(link_name: &str) -> bool {
+    let link_name = strip_link_name_prefix(link_name);
+    link_name
+        .strip_prefix("_ZN")
+        .or_else(|| link_name.strip_prefix("__ZN"))
+        .map_or(false, |nested_name| {
+            nested_name
+                .trim_start_matches(|c| matches!(c, 'r' | 'V' | 'K'))
+                .starts_with('O')
+        })
+}
+
+/// bindgen names a global variable by concatenating its enclosing classes
+/// with its name, so to refer to it from C++ we need to recover its
+/// fully-qualified name from the mangled symbol. Only simple names - with no
+/// templates and the like - are supported, in either the Itanium or MSVC
+/// mangling scheme. Variables with internal linkage, such as non-`inline`
+/// `constexpr` variables at namespace scope, are supported too: they're
+/// defined in the header, so our generated C++ can refer to them.
+fn demangle_global_name(link_name: &str) -> Option<QualifiedName> {
+    let link_name = strip_link_name_prefix(link_name);
+    let components = if let Some(remaining) = link_name
+        .strip_prefix("_ZL")
+        .or_else(|| link_name.strip_prefix("__ZL"))
+    {
+        match take_source_name(remaining)? {
+            (component, "") => vec![component],
+            _ => return None,
+        }
+    } else if let Some(mut remaining) = link_name
+        .strip_prefix("_ZN")
+        .or_else(|| link_name.strip_prefix("__ZN"))
+    {
+        let mut components = Vec::new();
+        while !remaining.starts_with('E') {
+            // Within a nested name, an `L` marks internal linkage.
+            let (component, rest) =
+                take_source_name(remaining.strip_prefix('L').unwrap_or(remaining))?;
+            components.push(component);
+            remaining = rest;
+        }
+        components
+    } else if let Some(remaining) = link_name.strip_prefix('?') {
+        let (scoped_name, _) = remaining.split_once("@@")?;
+        scoped_name.split('@').rev().collect()
+    } else {
+        return None;
+    };
+    if components.is_empty()
+        || components.iter().any(|component| {
+            component.is_empty()
+                || !component
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+    {
+        return None;
+    }
+    Some(QualifiedName::new_from_cpp_name(&components.join("::")))
+}
+
+/// Splits a length-prefixed name, as in an Itanium mangled symbol, from
+/// whatever follows it.
+fn take_source_name(mangled: &str) -> Option<(&str, &str)> {
+    let len_len = mangled.find(|c: char| !c.is_ascii_digit())?;
+    let len: usize = mangled[..len_len].parse().ok()?;
+    let name = mangled.get(len_len..len_len + len)?;
+    Some((name, &mangled[len_len + len..]))
+}
+
 #[cfg(test)]
 mod test {
-    use super::get_called_function;
+    use super::{
+        demangle_global_name, get_called_constructor, get_called_function, is_rvalue_ref_qualified,
+    };
     use syn::parse_quote;
     use syn::Block;
 
+    #[test]
+    fn test_demangle_global_name() {
+        let demangle = |link_name| demangle_global_name(link_name).map(|qn| qn.to_cpp_name());
+        assert_eq!(
+            demangle("\u{1}_ZN2ns7counterE").as_deref(),
+            Some("ns::counter")
+        );
+        assert_eq!(
+            demangle("__ZN2ns3Foo5countE").as_deref(),
+            Some("ns::Foo::count")
+        );
+        assert_eq!(
+            demangle("?count@Foo@ns@@2HA").as_deref(),
+            Some("ns::Foo::count")
+        );
+        assert_eq!(demangle("_ZL6origin").as_deref(), Some("origin"));
+        assert_eq!(demangle("_ZN2nsL6originE").as_deref(), Some("ns::origin"));
+        assert_eq!(demangle("_ZL6origin_"), None);
+        assert_eq!(demangle("_ZN2ns3FooIiE5countE"), None);
+        assert_eq!(demangle("counter"), None);
+    }
+
+    #[test]
+    fn test_is_rvalue_ref_qualified() {
+        assert!(is_rvalue_ref_qualified("\u{1}_ZNO3Foo4takeEv"));
+        assert!(is_rvalue_ref_qualified("__ZNKO2ns3Foo5valueEv"));
+        assert!(!is_rvalue_ref_qualified("_ZNR3Foo4takeEv"));
+        assert!(!is_rvalue_ref_qualified("_ZNK3Foo5valueEv"));
+        assert!(!is_rvalue_ref_qualified("_Z4takev"));
+        assert!(!is_rvalue_ref_qualified("?take@Foo@@QEAAXXZ"));
+    }
+
     #[test]
     fn test_get_called_function() {
         let b: Block = parse_quote! {
@@ -173,4 +463,25 @@ mod test {
         };
         assert_eq!(get_called_function(&b).unwrap().to_string(), "call_foo");
     }
+
+    #[test]
+    fn test_get_called_constructor() {
+        let b: Block = parse_quote! {
+            {
+                let mut __bindgen_tmp = ::std::mem::MaybeUninit::uninit();
+                Foo_Bar_Foo_Bar(__bindgen_tmp.as_mut_ptr(), a);
+                __bindgen_tmp.assume_init()
+            }
+        };
+        assert_eq!(
+            get_called_constructor(&b).unwrap().to_string(),
+            "Foo_Bar_Foo_Bar"
+        );
+        let b: Block = parse_quote! {
+            {
+                Foo_FooBar(self, a)
+            }
+        };
+        assert!(get_called_constructor(&b).is_none());
+    }
 }
@@ -0,0 +1,150 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Finding the Qt signals named in `qt_signal!` directives. Both the C++
+//! and Rust code generators need to agree on which of these we can
+//! support.
+
+use std::collections::HashSet;
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{Ident, Type};
+
+use super::{
+    analysis::fun::{FnAnalysis, FnKind, FnPhase, MethodKind, ReceiverMutability},
+    api::Api,
+    apivec::ApiVec,
+    ConvertError,
+};
+use crate::{
+    known_types::known_types,
+    types::{make_ident, QualifiedName},
+};
+
+/// Rust primitive types which can be copied out of a signal's arguments.
+const PRIMITIVES: &[&str] = &[
+    "bool", "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize", "f32", "f64",
+];
+
+/// A Qt signal to which we can connect a Rust closure.
+pub(crate) struct QtSignal<'a> {
+    /// The class declaring the signal.
+    pub(crate) class: &'a QualifiedName,
+    /// The C++ name of the signal.
+    pub(crate) cpp_name: &'a str,
+    /// The Rust name of the method which emits the signal.
+    pub(crate) rust_name: &'a str,
+    /// The types of the signal's parameters, as used in Rust.
+    pub(crate) param_types: Vec<&'a Type>,
+    /// The name of the C++ function which connects to this signal.
+    pub(crate) connect_fn_name: Ident,
+}
+
+/// A `qt_signal!` directive which we couldn't honor.
+pub(crate) struct InvalidQtSignal<'a> {
+    pub(crate) class: &'a QualifiedName,
+    pub(crate) rust_name: &'a str,
+    pub(crate) err: ConvertError,
+}
+
+/// The name of the C++ function which deletes a connection made by any of
+/// the functions named by [`QtSignal::connect_fn_name`].
+pub(crate) fn qt_disconnect_fn_name() -> Ident {
+    make_ident("autocxx_qt_disconnect")
+}
+
+/// Find each signal named in a `qt_signal!` directive. Signals which weren't
+/// generated at all are omitted, since there's nowhere to report the problem.
+pub(crate) fn find_qt_signals<'a>(
+    apis: &'a ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> Vec<Result<QtSignal<'a>, InvalidQtSignal<'a>>> {
+    let wanted: HashSet<&str> = config.qt_signals().collect();
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+    let copyable_types: HashSet<&QualifiedName> = apis
+        .iter()
+        .filter_map(|api| match api {
+            Api::Enum { name, .. } => Some(&name.name),
+            _ => None,
+        })
+        .collect();
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Function { analysis, .. } => match &analysis.kind {
+                FnKind::Method {
+                    impl_for,
+                    method_kind,
+                    ..
+                } => {
+                    let cpp_name = api.effective_cpp_name();
+                    let full_name = format!("{}::{}", impl_for.to_cpp_name(), cpp_name);
+                    if !wanted.contains(full_name.as_str()) {
+                        return None;
+                    }
+                    let param_types = match method_kind {
+                        MethodKind::Normal(ReceiverMutability::Mutable) => {
+                            signal_param_types(analysis, &copyable_types)
+                        }
+                        _ => None,
+                    };
+                    Some(match param_types {
+                        Some(param_types) => Ok(QtSignal {
+                            class: impl_for,
+                            cpp_name,
+                            rust_name: &analysis.rust_name,
+                            param_types,
+                            connect_fn_name: make_ident(format!(
+                                "{}_autocxx_qt_connect",
+                                analysis.cxxbridge_name
+                            )),
+                        }),
+                        None => Err(InvalidQtSignal {
+                            class: impl_for,
+                            rust_name: &analysis.rust_name,
+                            err: ConvertError::InvalidQtSignal(full_name),
+                        }),
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn signal_param_types<'a>(
+    analysis: &'a FnAnalysis,
+    copyable_types: &HashSet<&QualifiedName>,
+) -> Option<Vec<&'a Type>> {
+    if analysis.ignore_reason.is_err() || !analysis.externally_callable {
+        return None;
+    }
+    analysis
+        .param_details
+        .iter()
+        .filter(|pd| pd.self_type.is_none())
+        .map(|pd| {
+            if pd.conversion.cpp_work_needed() || pd.conversion.rust_work_needed() {
+                return None;
+            }
+            let ty = &pd.conversion.unwrapped_type;
+            match ty {
+                Type::Path(typ) => {
+                    let tn = QualifiedName::from_type_path(typ);
+                    let copyable = PRIMITIVES.contains(&tn.to_cpp_name().as_str())
+                        || known_types().is_ctype(&tn)
+                        || copyable_types.contains(&tn);
+                    copyable.then(|| ty)
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,74 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for reference-counted classes named in `refcounted!` directives.
+//! Functions returning a pointer to one return an `autocxx::RcHandle`
+//! instead, which needs C++ shims to add and release a reference using the
+//! functions named in the directive. Both the C++ and Rust code generators
+//! need to agree on what those shims are.
+
+use std::collections::HashSet;
+
+use syn::{Ident, Type};
+
+use super::{analysis::fun::FnPhase, api::Api, apivec::ApiVec};
+use crate::types::{make_ident, QualifiedName};
+
+/// How to turn a returned pointer to a reference-counted class into an
+/// `autocxx::RcHandle`.
+#[derive(Clone)]
+pub(crate) struct RefcountedReturn {
+    /// The reference-counted class.
+    pub(crate) name: QualifiedName,
+    /// The same, as a Rust type.
+    pub(crate) ty: Type,
+    /// Whether the function returns a pointer to const, which the Rust
+    /// wrapper must cast.
+    pub(crate) is_const: bool,
+    /// The C++ function which adds a reference.
+    pub(crate) addref: String,
+    /// The C++ function which releases a reference.
+    pub(crate) release: String,
+}
+
+impl RefcountedReturn {
+    /// The name of the shim which adds a reference.
+    pub(crate) fn addref_fn_name(&self) -> Ident {
+        self.shim_name("addref")
+    }
+
+    /// The name of the shim which releases a reference.
+    pub(crate) fn release_fn_name(&self) -> Ident {
+        self.shim_name("release")
+    }
+
+    fn shim_name(&self, operation: &str) -> Ident {
+        make_ident(format!(
+            "{}_autocxx_{}",
+            self.name.get_final_item(),
+            operation
+        ))
+    }
+}
+
+/// Find each distinct reference-counted class to which a pointer is
+/// returned from a function which we're going to convert.
+pub(crate) fn find_refcounted_types(apis: &ApiVec<FnPhase>) -> Vec<&RefcountedReturn> {
+    let mut seen = HashSet::new();
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Function { analysis, .. }
+                if analysis.ignore_reason.is_ok() && analysis.externally_callable =>
+            {
+                analysis.refcounted_return.as_ref()
+            }
+            _ => None,
+        })
+        .filter(|refcounted| seen.insert(&refcounted.name))
+        .collect()
+}
@@ -0,0 +1,184 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for C++ types which hold either a value or an error, which are
+//! returned to Rust as a `Result`. Abseil's `absl::Status` and
+//! `absl::StatusOr<T>` are always supported; other templates, such as
+//! `tl::expected<T, E>`, can be named in `result_type!` directives. For each
+//! such type returned by some function, we generate a few C++ shims to find
+//! out whether it holds a value and to extract its value or error. Both the
+//! C++ and Rust code generators need to agree on what those shims are.
+
+use std::collections::HashSet;
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{GenericArgument, Ident, PathArguments, Type, TypePath};
+
+use super::{
+    analysis::fun::{function_wrapper::TypeConversionPolicy, FnPhase},
+    api::Api,
+    apivec::ApiVec,
+};
+use crate::types::{make_ident, QualifiedName};
+
+/// How a type returned by C++ can be turned into a `Result`.
+pub(crate) enum ResultTypeKind<'a> {
+    /// It's an `absl::Status`.
+    Status,
+    /// It's an `absl::StatusOr<T>`, with the given (unconverted) `T`.
+    StatusOr(&'a Type),
+    /// It's an instantiation of a template named in a `result_type!`
+    /// directive, with the given (unconverted) value and error types. There's
+    /// no value type if it's `void`.
+    Custom {
+        value: Option<&'a Type>,
+        error: &'a Type,
+        value_accessor: &'a str,
+        error_accessor: &'a str,
+    },
+}
+
+/// Determine whether a type, as given to us by bindgen, is `absl::Status`,
+/// `absl::StatusOr<T>`, or an instantiation of a template named in a
+/// `result_type!` directive.
+pub(crate) fn result_type_kind<'a>(
+    ty: &'a Type,
+    config: &'a IncludeCppConfig,
+) -> Option<ResultTypeKind<'a>> {
+    let typ = match ty {
+        Type::Path(typ) => typ,
+        _ => return None,
+    };
+    let cpp_name = QualifiedName::from_type_path(typ).to_cpp_name();
+    match cpp_name.as_str() {
+        "absl::Status" => Some(ResultTypeKind::Status),
+        "absl::StatusOr" => match template_args(typ).as_slice() {
+            [payload] => Some(ResultTypeKind::StatusOr(*payload)),
+            _ => None,
+        },
+        _ => {
+            let (value_accessor, error_accessor) = config.result_type_accessors(&cpp_name)?;
+            // Some such templates, e.g. `boost::outcome_v2::result`, have
+            // further parameters, which we don't care about.
+            match template_args(typ).as_slice() {
+                [value, error, ..] => Some(ResultTypeKind::Custom {
+                    value: Some(*value).filter(|value| !is_void(value)),
+                    error: *error,
+                    value_accessor,
+                    error_accessor,
+                }),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn template_args(typ: &TypePath) -> Vec<&Type> {
+    match typ.path.segments.last().map(|seg| &seg.arguments) {
+        Some(PathArguments::AngleBracketed(ab)) => ab
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_void(ty: &Type) -> bool {
+    match ty {
+        Type::Tuple(tuple) => tuple.elems.is_empty(),
+        Type::Path(typ) => typ
+            .path
+            .segments
+            .last()
+            .map(|seg| seg.ident == "c_void")
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+/// How to turn a returned `absl::Status`, `absl::StatusOr<T>` or
+/// `result_type!` into a `Result`.
+#[derive(Clone)]
+pub(crate) struct ResultReturn {
+    /// The returned type as known to cxx: `absl::Status`, or the concrete
+    /// type we generated for this instantiation of some template.
+    pub(crate) result_type: QualifiedName,
+    /// How to return the value, unless there isn't one.
+    pub(crate) value: Option<TypeConversionPolicy>,
+    pub(crate) kind: ResultReturnKind,
+}
+
+#[derive(Clone)]
+pub(crate) enum ResultReturnKind {
+    /// `absl::Status` or `absl::StatusOr<T>`, whose error we copy into an
+    /// `autocxx::AbslStatus`.
+    Abseil,
+    /// A type named in a `result_type!` directive, whose value and error
+    /// are moved out using the given methods.
+    Custom {
+        error: TypeConversionPolicy,
+        value_accessor: String,
+        error_accessor: String,
+    },
+}
+
+impl ResultReturn {
+    /// The name of the shim which returns whether this holds a value.
+    pub(crate) fn ok_fn_name(&self) -> Ident {
+        self.shim_name("ok")
+    }
+
+    /// The name of the shim which returns the `absl::Status` error code.
+    pub(crate) fn code_fn_name(&self) -> Ident {
+        self.shim_name("code")
+    }
+
+    /// The name of the shim which returns the `absl::Status` error message.
+    pub(crate) fn message_fn_name(&self) -> Ident {
+        self.shim_name("message")
+    }
+
+    /// The name of the shim which moves out the value.
+    pub(crate) fn value_fn_name(&self) -> Ident {
+        self.shim_name("value")
+    }
+
+    /// The name of the shim which moves out the error of a `result_type!`.
+    pub(crate) fn error_fn_name(&self) -> Ident {
+        self.shim_name("error")
+    }
+
+    fn shim_name(&self, accessor: &str) -> Ident {
+        make_ident(format!(
+            "{}_autocxx_result_{}",
+            self.result_type.get_final_item(),
+            accessor
+        ))
+    }
+}
+
+/// Find each distinct type which is returned from a function which we're
+/// going to convert into a `Result`.
+pub(crate) fn find_result_types(apis: &ApiVec<FnPhase>) -> Vec<&ResultReturn> {
+    let mut seen = HashSet::new();
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Function { analysis, .. }
+                if analysis.ignore_reason.is_ok() && analysis.externally_callable =>
+            {
+                analysis.result_return.as_ref()
+            }
+            _ => None,
+        })
+        .filter(|result_return| seen.insert(&result_return.result_type))
+        .collect()
+}
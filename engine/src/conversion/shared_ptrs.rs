@@ -0,0 +1,101 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for `std::shared_ptr`s of classes with base classes. Classes
+//! deriving from `std::enable_shared_from_this` get a `shared_from_this`
+//! method, and a `SharedPtr` of any class can be upcast to a `SharedPtr`
+//! of one of its bases, using `autocxx::UpcastSharedPtr`. Each needs a C++
+//! shim, and both the C++ and Rust code generators need to agree on what
+//! those shims are.
+
+use std::collections::HashSet;
+
+use autocxx_parser::IncludeCppConfig;
+use itertools::Itertools;
+use syn::Ident;
+
+use super::{
+    analysis::fun::{FnPhase, PodAndDepAnalysis},
+    api::Api,
+    apivec::ApiVec,
+};
+use crate::types::{make_ident, QualifiedName};
+
+/// The name of the shim which calls `shared_from_this` on a `ty`.
+pub(crate) fn shared_from_this_fn_name(ty: &QualifiedName) -> Ident {
+    make_ident(format!("{}_autocxx_shared_from_this", ty.get_final_item()))
+}
+
+/// The name of the shim which upcasts a `std::shared_ptr<derived>` to a
+/// `std::shared_ptr<base>`.
+pub(crate) fn upcast_fn_name(derived: &QualifiedName, base: &QualifiedName) -> Ident {
+    make_ident(format!(
+        "{}_autocxx_upcast_shared_ptr_to_{}",
+        derived.get_final_item(),
+        base.get_final_item()
+    ))
+}
+
+/// Whether we can put this type in a `cxx::SharedPtr`. We only tell cxx
+/// that's possible for types with destructors, unless told not to at all
+/// by `exclude_impls!`.
+fn in_shared_ptr(analysis: &PodAndDepAnalysis, config: &IncludeCppConfig) -> bool {
+    !config.exclude_impls && !analysis.pod.is_generic && analysis.constructors.destructor
+}
+
+/// Find each class which derives from `std::enable_shared_from_this`.
+pub(crate) fn find_shared_from_this_types<'a>(
+    apis: &'a ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> Vec<&'a QualifiedName> {
+    let enable_shared_from_this = QualifiedName::new_from_cpp_name("std::enable_shared_from_this");
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Struct { name, analysis, .. }
+                if in_shared_ptr(analysis, config)
+                    && analysis.pod.bases.contains(&enable_shared_from_this) =>
+            {
+                Some(&name.name)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Find each (derived, base) pair of classes for which we'll generate an
+/// upcast. These are the same bases to which we can cast references.
+pub(crate) fn find_shared_ptr_upcasts<'a>(
+    apis: &'a ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> Vec<(&'a QualifiedName, &'a QualifiedName)> {
+    let shareable: HashSet<_> = apis
+        .iter()
+        .filter_map(|api| match api {
+            Api::Struct { name, analysis, .. } if in_shared_ptr(analysis, config) => {
+                Some(&name.name)
+            }
+            _ => None,
+        })
+        .collect();
+    apis.iter()
+        .filter_map(|api| match api {
+            // Sort the bases so that we generate the same code each time.
+            Api::Struct { name, analysis, .. } if shareable.contains(&name.name) => Some(
+                analysis
+                    .pod
+                    .castable_bases
+                    .iter()
+                    .filter(|base| shareable.contains(base))
+                    .sorted_by_key(|base| base.to_cpp_name())
+                    .map(move |base| (&name.name, base)),
+            ),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
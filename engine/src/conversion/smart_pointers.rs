@@ -0,0 +1,96 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for user-defined C++ smart pointers, such as an intrusively
+//! reference-counted `RefPtr<T>`, named in `smart_pointer!` directives.
+//! Functions returning one by value return an `autocxx::SmartPtr` instead,
+//! which needs a C++ shim to get at the raw pointer and another to copy the
+//! smart pointer. Both the C++ and Rust code generators need to agree on
+//! what those shims are.
+
+use std::collections::HashSet;
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{GenericArgument, Ident, PathArguments, Type};
+
+use super::{analysis::fun::FnPhase, api::Api, apivec::ApiVec};
+use crate::types::{make_ident, QualifiedName};
+
+/// Determine whether a type, as given to us by bindgen, is an instantiation
+/// of a template named in a `smart_pointer!` directive. If so, returns the
+/// (unconverted) type it points to, and the name of the method which
+/// returns the raw pointer.
+pub(crate) fn smart_pointer_target<'a>(
+    ty: &'a Type,
+    config: &'a IncludeCppConfig,
+) -> Option<(&'a Type, &'a str)> {
+    let typ = match ty {
+        Type::Path(typ) => typ,
+        _ => return None,
+    };
+    let deref_accessor =
+        config.smart_pointer_deref(&QualifiedName::from_type_path(typ).to_cpp_name())?;
+    match &typ.path.segments.last()?.arguments {
+        PathArguments::AngleBracketed(ab) => match ab.args.first() {
+            Some(GenericArgument::Type(target)) => Some((target, deref_accessor)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// How to turn a returned smart pointer into an `autocxx::SmartPtr`.
+#[derive(Clone)]
+pub(crate) struct SmartPointerReturn {
+    /// The concrete type we generated for this instantiation of the
+    /// smart pointer template.
+    pub(crate) ptr_name: QualifiedName,
+    /// The same, as a Rust type.
+    pub(crate) ptr_type: Type,
+    /// The type it points to.
+    pub(crate) target: Type,
+    /// The method which returns the raw pointer.
+    pub(crate) deref_accessor: String,
+}
+
+impl SmartPointerReturn {
+    /// The name of the shim which returns the raw pointer.
+    pub(crate) fn get_fn_name(&self) -> Ident {
+        self.shim_name("get")
+    }
+
+    /// The name of the shim which copies the smart pointer.
+    pub(crate) fn clone_fn_name(&self) -> Ident {
+        self.shim_name("clone")
+    }
+
+    fn shim_name(&self, operation: &str) -> Ident {
+        make_ident(format!(
+            "{}_autocxx_smart_ptr_{}",
+            self.ptr_name.get_final_item(),
+            operation
+        ))
+    }
+}
+
+/// Find each distinct smart pointer type which is returned from a function
+/// which we're going to convert.
+pub(crate) fn find_smart_pointer_types(apis: &ApiVec<FnPhase>) -> Vec<&SmartPointerReturn> {
+    let mut seen = HashSet::new();
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Function { analysis, .. }
+                if analysis.ignore_reason.is_ok() && analysis.externally_callable =>
+            {
+                analysis.smart_pointer_return.as_ref()
+            }
+            _ => None,
+        })
+        .filter(|smart_pointer| seen.insert(&smart_pointer.ptr_name))
+        .collect()
+}
@@ -0,0 +1,159 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Suggestions for `generate!` directives which didn't match anything.
+//! bindgen only tells us about the items on the allowlist, so to find
+//! out what the user might have meant we have to ask it again, with a
+//! broader allowlist, and then look for the nearest name.
+
+use syn::{Item, ItemMod};
+
+use super::parse::BindgenSemanticAttributes;
+use crate::types::{make_ident, Namespace, QualifiedName};
+
+/// The allowlist for a bindgen run to find candidate names for a
+/// directive: everything in the same namespace, and anything of the same
+/// name in any namespace.
+pub(crate) fn discovery_allowlist(directive: &str) -> Vec<String> {
+    let name = QualifiedName::new_from_cpp_name(directive);
+    let ns = name.get_namespace();
+    let same_ns = if ns.is_empty() {
+        "[^:]*".to_string()
+    } else {
+        format!("{}::.*", ns.to_cpp_string())
+    };
+    vec![same_ns, format!(".*::{}", name.get_final_item())]
+}
+
+/// The C++ names of each type, function and constant in some bindgen
+/// output.
+pub(crate) fn declared_names(bindings: &ItemMod) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Some((_, items)) = &bindings.content {
+        for item in items {
+            // As in parse_bindgen, everything's within a mod called 'root'.
+            if let Item::Mod(root_mod) = item {
+                add_declared_names(root_mod, &Namespace::new(), &mut names);
+            }
+        }
+    }
+    names
+}
+
+fn add_declared_names(itm: &ItemMod, ns: &Namespace, names: &mut Vec<String>) {
+    let items = match &itm.content {
+        Some((_, items)) => items,
+        None => return,
+    };
+    let mut add = |id| names.push(QualifiedName::new(ns, id).to_cpp_name());
+    for item in items {
+        match item {
+            Item::Struct(s) => add(s.ident.clone()),
+            Item::Enum(e) => add(e.ident.clone()),
+            Item::Type(ity) => add(ity.ident.clone()),
+            Item::Const(c) => add(c.ident.clone()),
+            Item::ForeignMod(fm) => {
+                for fi in &fm.items {
+                    if let syn::ForeignItem::Fn(f) = fi {
+                        // Overloads are renamed by bindgen, so use the
+                        // original name if there is one.
+                        let id = BindgenSemanticAttributes::new(&f.attrs)
+                            .get_original_name()
+                            .map(make_ident)
+                            .unwrap_or_else(|| f.sig.ident.clone());
+                        add(id);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for item in items {
+        if let Item::Mod(inner) = item {
+            add_declared_names(inner, &ns.push(inner.ident.to_string()), names);
+        }
+    }
+}
+
+/// The name which the user most likely meant. We prefer an item with the
+/// same name in a different namespace, since forgetting to qualify a name
+/// is the commonest mistake; otherwise we look for a name with only a few
+/// characters different.
+pub(crate) fn nearest_name(directive: &str, names: &[String]) -> Option<String> {
+    // If the item does exist, it must have been omitted for some other
+    // reason, so there's nothing to suggest.
+    if names.iter().any(|name| name == directive) {
+        return None;
+    }
+    let final_item = QualifiedName::new_from_cpp_name(directive)
+        .get_final_item()
+        .to_string();
+    let mut names: Vec<_> = names.iter().collect();
+    names.sort();
+    names.dedup();
+    if let Some(name) = names
+        .iter()
+        .find(|name| QualifiedName::new_from_cpp_name(name).get_final_item() == final_item)
+    {
+        return Some(name.to_string());
+    }
+    let max_distance = final_item.len() / 3 + 1;
+    names
+        .into_iter()
+        .map(|name| (edit_distance(directive, name), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name.to_string())
+}
+
+/// The number of single character insertions, deletions or substitutions
+/// needed to turn one string into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev_row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev_row[j] + if ca == *cb { 0 } else { 1 };
+            row.push(substitution.min(prev_row[j + 1] + 1).min(row[j] + 1));
+        }
+        prev_row = row;
+    }
+    prev_row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, nearest_name};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_nearest_name() {
+        let names = vec![
+            "mylib::Widget".to_string(),
+            "mylib::make_widget".to_string(),
+            "mylib::Gadget".to_string(),
+        ];
+        assert_eq!(
+            nearest_name("Widget", &names),
+            Some("mylib::Widget".to_string())
+        );
+        assert_eq!(
+            nearest_name("mylib::Widgte", &names),
+            Some("mylib::Widget".to_string())
+        );
+        assert_eq!(nearest_name("mylib::Sprocket", &names), None);
+        assert_eq!(nearest_name("mylib::Gadget", &names), None);
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for functions returning a `std::unique_ptr<T[]>`, whose length
+//! is given by a `unique_array!` directive. cxx can't represent these, so
+//! the C++ wrapper releases the raw pointer and the Rust wrapper returns an
+//! `autocxx::UniqueArray` holding it, which needs a C++ shim to `delete[]`
+//! it again. Both the C++ and Rust code generators need to agree on what
+//! those shims are.
+
+use syn::{GenericArgument, Ident, PathArguments, Type, TypeArray};
+
+use super::{analysis::fun::FnPhase, api::Api, apivec::ApiVec};
+use crate::types::{make_ident, QualifiedName};
+
+/// Determine whether a type, as given to us by bindgen, is a
+/// `std::unique_ptr<T[]>`. If so, returns the (unconverted) element type.
+/// bindgen represents the array of unknown bound as a zero-length array.
+pub(crate) fn unique_array_element(ty: &Type) -> Option<&Type> {
+    let typ = match ty {
+        Type::Path(typ) => typ,
+        _ => return None,
+    };
+    if QualifiedName::from_type_path(typ).to_cpp_name() != "std::unique_ptr" {
+        return None;
+    }
+    match &typ.path.segments.last()?.arguments {
+        PathArguments::AngleBracketed(ab) => match ab.args.first() {
+            Some(GenericArgument::Type(Type::Array(TypeArray { elem, .. }))) => Some(elem.as_ref()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Where the Rust wrapper finds the length of a returned array.
+#[derive(Clone)]
+pub(crate) enum UniqueArrayLen {
+    /// The named integer parameter, which is an `autocxx::c_int` or
+    /// similar if `is_ctype`.
+    Param { name: Ident, is_ctype: bool },
+    /// A method of the receiver.
+    Method(Ident),
+    /// A static method of the same class.
+    StaticMethod(Ident),
+    /// A free function in the same namespace.
+    Function(Ident),
+}
+
+/// How to turn a returned `std::unique_ptr<T[]>` into an
+/// `autocxx::UniqueArray`.
+#[derive(Clone)]
+pub(crate) struct UniqueArrayReturn {
+    /// The type of each element.
+    pub(crate) elem: Type,
+    /// Where to find the length.
+    pub(crate) len: UniqueArrayLen,
+    /// The name of the shim which `delete[]`s the array.
+    pub(crate) delete_fn_name: Ident,
+}
+
+impl UniqueArrayReturn {
+    pub(crate) fn new(elem: Type, len: UniqueArrayLen, cxxbridge_name: &Ident) -> Self {
+        Self {
+            elem,
+            len,
+            delete_fn_name: make_ident(format!("{}_autocxx_delete_array", cxxbridge_name)),
+        }
+    }
+}
+
+/// Find each function returning a `std::unique_ptr<T[]>` which we're going
+/// to convert. Each needs its own shim.
+pub(crate) fn find_unique_array_returns(apis: &ApiVec<FnPhase>) -> Vec<&UniqueArrayReturn> {
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Function { analysis, .. }
+                if analysis.ignore_reason.is_ok() && analysis.externally_callable =>
+            {
+                analysis.unique_array_return.as_ref()
+            }
+            _ => None,
+        })
+        .collect()
+}
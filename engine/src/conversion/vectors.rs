@@ -0,0 +1,109 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for `std::vector`s of non-POD classes. cxx can only move
+//! elements of trivial types in and out of a `CxxVector`, so for each
+//! non-POD class which is found in a `std::vector` we generate C++ shims to
+//! copy an element into a `UniquePtr` and to move one onto the end, and an
+//! `autocxx::UniquePtrVectorElement` implementation which calls them. Both
+//! the C++ and Rust code generators need to agree on what those shims are.
+
+use std::collections::HashSet;
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{FnArg, GenericArgument, Ident, PathArguments, ReturnType, Type};
+
+use super::{
+    analysis::fun::FnPhase,
+    api::{Api, TypeKind},
+    apivec::ApiVec,
+};
+use crate::types::{make_ident, QualifiedName};
+
+/// The name of the shim which copies an element out of a vector.
+pub(crate) fn vector_get_copy_fn_name(ty: &QualifiedName) -> Ident {
+    make_ident(format!("{}_autocxx_vector_get_copy", ty.get_final_item()))
+}
+
+/// The name of the shim which moves an element onto the end of a vector.
+pub(crate) fn vector_push_fn_name(ty: &QualifiedName) -> Ident {
+    make_ident(format!("{}_autocxx_vector_push", ty.get_final_item()))
+}
+
+/// Find each non-POD class which is the element type of a `std::vector`
+/// taken or returned by some function, and which can be copied, moved and
+/// destroyed.
+pub(crate) fn find_vector_element_types<'a>(
+    apis: &'a ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> Vec<&'a QualifiedName> {
+    if config.exclude_impls {
+        return Vec::new();
+    }
+    let mut elements = HashSet::new();
+    for api in apis.iter() {
+        if let Api::Function { analysis, .. } = api {
+            if analysis.ignore_reason.is_err() {
+                continue;
+            }
+            let params = analysis.params.iter().filter_map(|param| match param {
+                FnArg::Typed(pt) => Some(pt.ty.as_ref()),
+                FnArg::Receiver(_) => None,
+            });
+            let ret = match &analysis.ret_type {
+                ReturnType::Type(_, ty) => Some(ty.as_ref()),
+                ReturnType::Default => None,
+            };
+            for ty in params.chain(ret) {
+                find_vector_elements(ty, &mut elements);
+            }
+        }
+    }
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Struct { name, analysis, .. }
+                if matches!(analysis.pod.kind, TypeKind::NonPod)
+                    && !analysis.pod.is_generic
+                    && analysis.constructors.copy_constructor
+                    && analysis.constructors.move_constructor
+                    && analysis.constructors.destructor
+                    && elements.contains(&name.name) =>
+            {
+                Some(&name.name)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Adds the element type of any `CxxVector` within this type.
+fn find_vector_elements(ty: &Type, elements: &mut HashSet<QualifiedName>) {
+    match ty {
+        Type::Reference(r) => find_vector_elements(&r.elem, elements),
+        Type::Ptr(p) => find_vector_elements(&p.elem, elements),
+        Type::Path(typ) => {
+            let last_seg = match typ.path.segments.last() {
+                Some(last_seg) => last_seg,
+                None => return,
+            };
+            if let PathArguments::AngleBracketed(ab) = &last_seg.arguments {
+                for arg in &ab.args {
+                    if let GenericArgument::Type(arg) = arg {
+                        match arg {
+                            Type::Path(arg_typ) if last_seg.ident == "CxxVector" => {
+                                elements.insert(QualifiedName::from_type_path(arg_typ));
+                            }
+                            _ => find_vector_elements(arg, elements),
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
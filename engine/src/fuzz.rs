@@ -0,0 +1,65 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An entry point for fuzzing the whole of autocxx with arbitrary headers.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use proc_macro2::{Punct, Spacing};
+use quote::quote;
+
+use crate::{CppCodegenOptions, Error, IncludeCppEngine};
+
+/// The result of [`fuzz_header`].
+#[derive(Debug)]
+pub enum FuzzOutcome {
+    /// Bindings were generated.
+    Generated,
+    /// autocxx rejected the header with an error. This is fine: most
+    /// arbitrary input isn't valid C++, and some valid C++ isn't
+    /// supported.
+    Rejected(Error),
+    /// autocxx panicked, with this message. This is a bug.
+    Panicked(String),
+}
+
+/// Run the whole of autocxx over a header with the given contents, asking
+/// for bindings to everything within it, and report whether it generated
+/// bindings, rejected the header cleanly, or panicked. This is intended to
+/// be called by fuzzers, which should treat [`FuzzOutcome::Panicked`] as a
+/// crash. Everything happens in-process, including parsing the header with
+/// libclang, so this needs the same environment as a normal build.
+pub fn fuzz_header(header: &str) -> FuzzOutcome {
+    let tdir = tempfile::tempdir().expect("Unable to create temporary directory");
+    std::fs::write(tdir.path().join("input.h"), header).expect("Unable to write header");
+    let hexathorpe = Punct::new('#', Spacing::Alone);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let mut engine = IncludeCppEngine::new_from_tokens(quote! {
+            #hexathorpe include "input.h"
+            safety!(unsafe_ffi)
+            generate_all!()
+        })?;
+        engine.generate_bindings(
+            vec![tdir.path().to_path_buf()],
+            &[],
+            None,
+            &CppCodegenOptions::default(),
+        )
+    }));
+    match result {
+        Ok(Ok(_)) => FuzzOutcome::Generated,
+        Ok(Err(err)) => FuzzOutcome::Rejected(err),
+        Err(payload) => FuzzOutcome::Panicked(
+            payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "(non-string panic payload)".to_string()),
+        ),
+    }
+}
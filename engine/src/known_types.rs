@@ -11,8 +11,13 @@ use crate::{
     types::{make_ident, QualifiedName},
 };
 use indoc::indoc;
+use itertools::Itertools;
 use once_cell::sync::OnceCell;
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    sync::Mutex,
+};
 use syn::{parse_quote, Type, TypePath, TypePtr};
 
 //// The behavior of the type.
@@ -26,8 +31,11 @@ enum Behavior {
     RustByValue,
     CByValue,
     CVariableLengthByValue,
+    CSimdByValue,
     CVoid,
     RustContainerByValueSafe,
+    ExternalByValueSafe,
+    ExternalNotByValueSafe,
 }
 
 /// Details about known special types, mostly primitives.
@@ -76,6 +84,12 @@ impl TypeDetails {
                 let tn = QualifiedName::new_from_cpp_name(&self.rs_name);
                 let cxx_name = tn.get_final_item();
                 let (templating, payload) = match self.behavior {
+                    // bindgen only tells us about template parameters which
+                    // are used, so mention the deleter to find out whether
+                    // it's the default.
+                    _ if self.cpp_name == "std::unique_ptr" => {
+                        ("template<typename T, typename D> ", "T* ptr; D* deleter")
+                    }
                     Behavior::CxxContainerByValueSafe
                     | Behavior::CxxContainerNotByValueSafe
                     | Behavior::RustContainerByValueSafe => ("template<typename T> ", "T* ptr"),
@@ -128,17 +142,163 @@ impl TypeDetails {
     }
 }
 
+/// The Rust reference type used for a C++ view type such as
+/// `absl::string_view`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ViewKind {
+    /// A view onto a string, which becomes `&str`.
+    Str,
+    /// A view onto an array of `const T`, which becomes `&[T]`.
+    Slice,
+    /// A `std::initializer_list<T>`, which also becomes `&[T]` but can only
+    /// be constructed from a braced list of elements.
+    InitializerList,
+}
+
 /// Database of known types.
 #[derive(Default)]
 pub(crate) struct TypeDatabase {
     by_rs_name: HashMap<QualifiedName, TypeDetails>,
     canonical_names: HashMap<QualifiedName, QualifiedName>,
+    views: HashMap<QualifiedName, ViewKind>,
 }
 
+static KNOWN_TYPES: OnceCell<TypeDatabase> = OnceCell::new();
+
 /// Returns a database of known types.
 pub(crate) fn known_types() -> &'static TypeDatabase {
-    static KNOWN_TYPES: OnceCell<TypeDatabase> = OnceCell::new();
-    KNOWN_TYPES.get_or_init(create_type_database)
+    KNOWN_TYPES.get_or_init(|| {
+        let mut db = create_type_database();
+        // Taking the registered types, while holding the lock, means that
+        // any later attempt to register more will fail rather than being
+        // silently ignored.
+        let registered = external_types().lock().unwrap().take();
+        for mapping in registered.into_iter().flatten() {
+            db.insert(mapping.into_type_details());
+        }
+        db
+    })
+}
+
+/// Types registered using [`register_known_type_extension`], or `None`
+/// once they've been added to the database of known types.
+fn external_types() -> &'static Mutex<Option<Vec<ExternalTypeMapping>>> {
+    static EXTERNAL_TYPES: OnceCell<Mutex<Option<Vec<ExternalTypeMapping>>>> = OnceCell::new();
+    EXTERNAL_TYPES.get_or_init(|| Mutex::new(Some(Vec::new())))
+}
+
+/// A C++ type which should be represented in Rust by some type supplied
+/// by the user of autocxx, rather than a type generated by autocxx.
+/// For example, you might map `folly::StringPiece` to a Rust type of your
+/// own which knows how to convert to and from `&str`.
+///
+/// The Rust type must implement `cxx::ExternType` with a
+/// `cxx::ExternType::Id` matching `cpp_name`.
+#[derive(Debug, Clone)]
+pub struct ExternalTypeMapping {
+    /// The fully-qualified C++ name, e.g. `folly::StringPiece`.
+    pub cpp_name: String,
+    /// The path to the Rust type, e.g. `crate::StringPiece`. This must be
+    /// resolvable from within the mod generated by `include_cpp!`.
+    pub rust_path: String,
+    /// Whether this type may be held and passed by value in Rust,
+    /// i.e. whether its `cxx::ExternType::Kind` is `cxx::kind::Trivial`.
+    pub trivial: bool,
+}
+
+impl ExternalTypeMapping {
+    fn into_type_details(self) -> TypeDetails {
+        TypeDetails::new(
+            self.rust_path,
+            self.cpp_name,
+            if self.trivial {
+                Behavior::ExternalByValueSafe
+            } else {
+                Behavior::ExternalNotByValueSafe
+            },
+            None,
+            self.trivial,
+            true,
+        )
+    }
+}
+
+/// Implement this to teach autocxx about extra C++ types which should
+/// map to existing Rust types, over and above those which autocxx knows
+/// about intrinsically (such as `std::string` or `std::unique_ptr`).
+/// Register your extension from your `build.rs` using
+/// [`register_known_type_extension`] before building.
+pub trait KnownTypeExtension {
+    /// Return the extra types which autocxx should know about.
+    fn external_types(&self) -> Vec<ExternalTypeMapping>;
+}
+
+/// Errors which may occur when registering a [`KnownTypeExtension`].
+#[derive(Debug)]
+pub enum KnownTypeRegistrationError {
+    /// The database of known types has already been used for code generation,
+    /// so it's too late to add more types.
+    AlreadyInUse,
+    /// This C++ or Rust type name is already known to autocxx.
+    ConflictsWithBuiltInType(String),
+}
+
+impl Display for KnownTypeRegistrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KnownTypeRegistrationError::AlreadyInUse => write!(
+                f,
+                "Extra known types must be registered before any bindings are generated."
+            )?,
+            KnownTypeRegistrationError::ConflictsWithBuiltInType(ty) => {
+                write!(f, "The type {} is already known to autocxx.", ty)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Register some extra C++ types which should be mapped to existing Rust
+/// types. This must be called before any bindings are generated, typically
+/// at the start of your `build.rs`.
+pub fn register_known_type_extension(
+    extension: &dyn KnownTypeExtension,
+) -> Result<(), KnownTypeRegistrationError> {
+    let mut registered = external_types().lock().unwrap();
+    let registered = registered
+        .as_mut()
+        .ok_or(KnownTypeRegistrationError::AlreadyInUse)?;
+    let mappings = extension.external_types();
+    check_no_conflicts(&create_type_database(), registered, &mappings)?;
+    registered.extend(mappings);
+    Ok(())
+}
+
+/// Checks that none of `mappings` names a type which is already known,
+/// either built in or registered, or which another of them names. We
+/// check them all before registering any, so that an extension is either
+/// registered entirely or not at all.
+fn check_no_conflicts(
+    builtins: &TypeDatabase,
+    registered: &[ExternalTypeMapping],
+    mappings: &[ExternalTypeMapping],
+) -> Result<(), KnownTypeRegistrationError> {
+    let mut seen: HashSet<&String> = registered
+        .iter()
+        .flat_map(|m| [&m.cpp_name, &m.rust_path])
+        .collect();
+    for mapping in mappings {
+        let names = [&mapping.cpp_name, &mapping.rust_path];
+        if let Some(name) = names.into_iter().find(|name| {
+            builtins.is_known_type(&QualifiedName::new_from_cpp_name(name)) || seen.contains(name)
+        }) {
+            return Err(KnownTypeRegistrationError::ConflictsWithBuiltInType(
+                name.clone(),
+            ));
+        }
+        seen.extend(names);
+    }
+    Ok(())
 }
 
 /// The type of payload that a cxx generic can contain.
@@ -180,7 +340,8 @@ impl TypeDatabase {
         itertools::join(
             self.by_rs_name
                 .values()
-                .filter_map(|t| t.get_prelude_entry()),
+                .filter_map(|t| t.get_prelude_entry())
+                .sorted(),
             "",
         )
     }
@@ -205,10 +366,13 @@ impl TypeDatabase {
                         | Behavior::RustByValue
                         | Behavior::CByValue
                         | Behavior::CVariableLengthByValue
-                        | Behavior::RustContainerByValueSafe => true,
+                        | Behavior::CSimdByValue
+                        | Behavior::RustContainerByValueSafe
+                        | Behavior::ExternalByValueSafe => true,
                         Behavior::CxxString
                         | Behavior::CxxContainerNotByValueSafe
-                        | Behavior::CVoid => false,
+                        | Behavior::CVoid
+                        | Behavior::ExternalNotByValueSafe => false,
                     },
                 )
             })
@@ -273,7 +437,16 @@ impl TypeDatabase {
     pub(crate) fn get_initial_blocklist(&self) -> impl Iterator<Item = &str> + '_ {
         self.by_rs_name
             .iter()
-            .filter_map(|(_, td)| td.get_prelude_entry().map(|_| td.cpp_name.as_str()))
+            .filter(|(_, td)| {
+                td.get_prelude_entry().is_some()
+                    || matches!(
+                        td.behavior,
+                        Behavior::CSimdByValue
+                            | Behavior::ExternalByValueSafe
+                            | Behavior::ExternalNotByValueSafe
+                    )
+            })
+            .map(|(_, td)| td.cpp_name.as_str())
     }
 
     /// Whether this is one of the ctypes (mostly variable length integers)
@@ -289,6 +462,43 @@ impl TypeDatabase {
             .unwrap_or(false)
     }
 
+    /// Whether this is one of the SIMD vector types used by intrinsics,
+    /// such as `__m128`, which we need to wrap.
+    pub(crate) fn is_simd_type(&self, ty: &QualifiedName) -> bool {
+        self.get(ty)
+            .map(|td| matches!(td.behavior, Behavior::CSimdByValue))
+            .unwrap_or(false)
+    }
+
+    /// Whether values of this type can be moved to another thread, as
+    /// needed for the `_async` variants generated by `async_wrapper!`.
+    pub(crate) fn is_send_by_value(&self, ty: &QualifiedName) -> bool {
+        self.get(ty)
+            .map(|td| {
+                matches!(
+                    td.behavior,
+                    Behavior::CByValue
+                        | Behavior::CVariableLengthByValue
+                        | Behavior::CSimdByValue
+                        | Behavior::RustString
+                )
+            })
+            .unwrap_or(false)
+    }
+
+    /// Whether this is a type registered by a [`KnownTypeExtension`],
+    /// which we need to declare to cxx as a type alias.
+    pub(crate) fn is_external_type(&self, ty: &QualifiedName) -> bool {
+        self.get(ty)
+            .map(|td| {
+                matches!(
+                    td.behavior,
+                    Behavior::ExternalByValueSafe | Behavior::ExternalNotByValueSafe
+                )
+            })
+            .unwrap_or(false)
+    }
+
     /// Whether this is a generic type acceptable to cxx. Otherwise,
     /// if we encounter a generic, we'll replace it with a synthesized concrete
     /// type.
@@ -298,6 +508,31 @@ impl TypeDatabase {
             .unwrap_or(CxxGenericType::Not)
     }
 
+    /// Whether this is a generic type whose type argument may be a
+    /// forward declaration. `std::shared_ptr` captures how to delete its
+    /// contents when it's created, so (unlike `std::unique_ptr`) it can be
+    /// passed around and dropped without the complete type.
+    pub(crate) fn permits_incomplete_type_argument(&self, ty: &QualifiedName) -> bool {
+        self.get(ty)
+            .map(|td| matches!(td.cpp_name.as_str(), "std::shared_ptr" | "std::weak_ptr"))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is `std::unique_ptr`, whose second template argument
+    /// is the deleter.
+    pub(crate) fn is_unique_ptr(&self, ty: &QualifiedName) -> bool {
+        self.get(ty)
+            .map(|td| td.cpp_name == "std::unique_ptr")
+            .unwrap_or(false)
+    }
+
+    /// Whether this is `std::default_delete`, the deleter of a
+    /// `std::unique_ptr` which is just a `cxx::UniquePtr`. We omit it from
+    /// types, since it's the default.
+    pub(crate) fn is_default_deleter(&self, ty: &QualifiedName) -> bool {
+        ty.to_cpp_name() == "std::default_delete"
+    }
+
     pub(crate) fn is_cxx_acceptable_receiver(&self, ty: &QualifiedName) -> bool {
         self.get(ty).is_none() // at present, none of our known types can have
                                // methods attached.
@@ -307,6 +542,13 @@ impl TypeDatabase {
         self.get(ty).is_some()
     }
 
+    /// Whether this is a C++ type which provides a view onto borrowed data,
+    /// and which we therefore pass by value as a Rust reference. Such types
+    /// are otherwise treated like any other C++ type.
+    pub(crate) fn view_kind(&self, ty: &QualifiedName) -> Option<ViewKind> {
+        self.views.get(ty).copied()
+    }
+
     pub(crate) fn convertible_from_strs(&self, ty: &QualifiedName) -> bool {
         self.get(ty)
             .map(|x| matches!(x.behavior, Behavior::CxxString))
@@ -327,6 +569,11 @@ impl TypeDatabase {
         );
         self.by_rs_name.insert(rs_name, td);
     }
+
+    fn insert_view(&mut self, cpp_name: &str, view_kind: ViewKind) {
+        self.views
+            .insert(QualifiedName::new_from_cpp_name(cpp_name), view_kind);
+    }
 }
 
 fn create_type_database() -> TypeDatabase {
@@ -388,7 +635,7 @@ fn create_type_database() -> TypeDatabase {
         true,
     ));
     db.insert(TypeDetails::new(
-        "std::boxed::Box",
+        "::std::boxed::Box",
         "rust::Box",
         Behavior::RustContainerByValueSafe,
         None,
@@ -436,7 +683,7 @@ fn create_type_database() -> TypeDatabase {
     ));
 
     db.insert(TypeDetails::new(
-        "std::pin::Pin",
+        "::std::pin::Pin",
         "Pin",
         Behavior::RustByValue, // because this is actually Pin<&something>
         None,
@@ -493,6 +740,46 @@ fn create_type_database() -> TypeDatabase {
         true,
         true,
     ));
+    // Vector types used by intrinsics. These are only defined when
+    // compiling for the relevant architecture, so it does no harm to
+    // know about all of them. See `autocxx::simd`.
+    let x86_simd_types = ["m128", "m128d", "m128i", "m256", "m256d", "m256i"]
+        .iter()
+        .map(|rs_name| (rs_name.to_string(), format!("__{}", rs_name)));
+    let arm_simd_types = [
+        "int8x8_t",
+        "int8x16_t",
+        "uint8x8_t",
+        "uint8x16_t",
+        "int16x4_t",
+        "int16x8_t",
+        "uint16x4_t",
+        "uint16x8_t",
+        "int32x2_t",
+        "int32x4_t",
+        "uint32x2_t",
+        "uint32x4_t",
+        "int64x1_t",
+        "int64x2_t",
+        "uint64x1_t",
+        "uint64x2_t",
+        "float32x2_t",
+        "float32x4_t",
+        "float64x1_t",
+        "float64x2_t",
+    ]
+    .iter()
+    .map(|name| (name.to_string(), name.to_string()));
+    for (rs_name, cpp_name) in x86_simd_types.chain(arm_simd_types) {
+        db.insert(TypeDetails::new(
+            format!("autocxx::simd::{}", rs_name),
+            cpp_name,
+            Behavior::CSimdByValue,
+            None,
+            true,
+            true,
+        ));
+    }
     db.insert(TypeDetails::new(
         "autocxx::c_void",
         "void",
@@ -501,6 +788,9 @@ fn create_type_database() -> TypeDatabase {
         false,
         false,
     ));
+    db.insert_view("absl::string_view", ViewKind::Str);
+    db.insert_view("absl::Span", ViewKind::Slice);
+    db.insert_view("std::initializer_list", ViewKind::InitializerList);
     db
 }
 
@@ -510,3 +800,67 @@ pub(crate) fn ensure_pointee_is_valid(ptr: &TypePtr) -> Result<(), ConvertError>
         _ => Err(ConvertError::InvalidPointee),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_no_conflicts, create_type_database, known_types, register_known_type_extension,
+        ExternalTypeMapping, KnownTypeExtension, KnownTypeRegistrationError,
+    };
+
+    fn mapping(cpp_name: &str, rust_path: &str) -> ExternalTypeMapping {
+        ExternalTypeMapping {
+            cpp_name: cpp_name.to_string(),
+            rust_path: rust_path.to_string(),
+            trivial: false,
+        }
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let builtins = create_type_database();
+        let registered = [mapping("folly::StringPiece", "crate::StringPiece")];
+        assert!(check_no_conflicts(
+            &builtins,
+            &registered,
+            &[mapping("folly::Range", "crate::Range")]
+        )
+        .is_ok());
+        assert!(matches!(
+            check_no_conflicts(&builtins, &registered, &[mapping("std::string", "crate::S")]),
+            Err(KnownTypeRegistrationError::ConflictsWithBuiltInType(name)) if name == "std::string"
+        ));
+        assert!(matches!(
+            check_no_conflicts(
+                &builtins,
+                &registered,
+                &[mapping("other::StringPiece", "crate::StringPiece")]
+            ),
+            Err(KnownTypeRegistrationError::ConflictsWithBuiltInType(name)) if name == "crate::StringPiece"
+        ));
+        // Mappings in the same extension mustn't conflict with each other.
+        assert!(check_no_conflicts(
+            &builtins,
+            &[],
+            &[mapping("a::A", "crate::A"), mapping("b::A", "crate::A")]
+        )
+        .is_err());
+    }
+
+    struct Extension;
+
+    impl KnownTypeExtension for Extension {
+        fn external_types(&self) -> Vec<ExternalTypeMapping> {
+            vec![mapping("folly::StringPiece", "crate::StringPiece")]
+        }
+    }
+
+    #[test]
+    fn test_register_after_use() {
+        known_types();
+        assert!(matches!(
+            register_known_type_extension(&Extension),
+            Err(KnownTypeRegistrationError::AlreadyInUse)
+        ));
+    }
+}
@@ -15,24 +15,36 @@
 #![cfg_attr(feature = "nightly", feature(doc_cfg))]
 #![forbid(unsafe_code)]
 
+mod allowlist_file;
 mod ast_discoverer;
 mod conversion;
 mod cxxbridge;
+mod fuzz;
 mod known_types;
 mod parse_callbacks;
 mod parse_file;
+mod project_config;
 mod rust_pretty_printer;
+mod snapshot;
 mod types;
 
 #[cfg(any(test, feature = "build"))]
 mod builder;
+#[cfg(any(test, feature = "build"))]
+mod rust_api_header;
 
 use autocxx_parser::{IncludeCppConfig, UnsafePolicy};
-use conversion::BridgeConverter;
+use conversion::{out_of_line, suggestions, BridgeConverter};
 use parse_callbacks::AutocxxParseCallbacks;
 use parse_file::CppBuildable;
 use proc_macro2::TokenStream as TokenStream2;
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::PathBuf,
+    rc::Rc,
+};
 use std::{
     fs::File,
     io::prelude::*,
@@ -45,10 +57,10 @@ use quote::ToTokens;
 use syn::Result as ParseResult;
 use syn::{
     parse::{Parse, ParseStream},
-    parse_quote, ItemMod, Macro,
+    parse_quote, Item, ItemMod, Macro,
 };
 
-use itertools::{join, Itertools};
+use itertools::Itertools;
 use known_types::known_types;
 use log::info;
 
@@ -56,14 +68,27 @@ use log::info;
 /// We hope to unfork.
 use autocxx_bindgen as bindgen;
 
+pub use allowlist_file::AllowlistFileError;
 #[cfg(any(test, feature = "build"))]
 pub use builder::{
-    Builder, BuilderBuild, BuilderContext, BuilderError, BuilderResult, BuilderSuccess,
+    Builder, BuilderBuild, BuilderContext, BuilderError, BuilderResult, BuilderSuccess, Sanitizer,
+};
+#[cfg(feature = "manifest")]
+pub use conversion::generated_items_to_json;
+pub use conversion::{diff_generated_items, GeneratedItem, GeneratedItemChange, GeneratedItemKind};
+pub use fuzz::{fuzz_header, FuzzOutcome};
+pub use known_types::{
+    register_known_type_extension, ExternalTypeMapping, KnownTypeExtension,
+    KnownTypeRegistrationError,
 };
 pub use parse_file::{parse_file, ParseError, ParsedFile};
+pub use project_config::ProjectConfigError;
 
 pub use cxx_gen::HEADER;
 
+/// bindgen's callbacks, for use with [`CppCodegenOptions::bindgen_callbacks`].
+pub use bindgen::callbacks as bindgen_callbacks;
+
 #[derive(Clone)]
 /// Some C++ content which should be written to disk and built.
 pub struct CppFilePair {
@@ -79,6 +104,21 @@ pub struct CppFilePair {
 /// All generated C++ content which should be written to disk.
 pub struct GeneratedCpp(pub Vec<CppFilePair>);
 
+/// Everything generated for a single `include_cpp` configuration, as
+/// returned by [`IncludeCppEngine::generate_bindings`]. This is the
+/// form most convenient for tools which use autocxx as a library
+/// rather than via `autocxx_build` or the `include_cpp!` macro.
+pub struct GeneratedBindings {
+    /// The Rust code, formatted with `rustfmt` if it's available.
+    /// This is the code that `include_cpp!` would expand to.
+    pub rs: String,
+    /// The C++ header and implementation files.
+    pub cpp: GeneratedCpp,
+    /// A description of each item for which bindings were (or could
+    /// not be) generated.
+    pub items: Vec<GeneratedItem>,
+}
+
 /// Errors which may occur in generating bindings for these C++
 /// functions.
 #[derive(Debug)]
@@ -93,6 +133,12 @@ pub enum Error {
     /// Some error occcurred in converting the bindgen-style
     /// bindings to safe cxx bindings.
     Conversion(conversion::ConvertError),
+    /// The `cxx` C++ code generator failed to process the Rust
+    /// bindings which we generated.
+    CxxGen(cxx_gen::Error),
+    /// A per-header clang argument (see
+    /// [`CppCodegenOptions::header_clang_args`]) wasn't a `-D` or `-U`.
+    UnsupportedHeaderClangArg(String, String),
 }
 
 impl Display for Error {
@@ -102,6 +148,8 @@ impl Display for Error {
             Error::Parsing(err) => write!(f, "The Rust file could not be parsed: {}", err)?,
             Error::NoAutoCxxInc => write!(f, "No C++ include directory was provided.")?,
             Error::Conversion(err) => write!(f, "autocxx could not generate the requested bindings. {}", err)?,
+            Error::CxxGen(err) => write!(f, "cxx could not generate C++ code for the bindings. {}", err)?,
+            Error::UnsupportedHeaderClangArg(header, arg) => write!(f, "The clang argument '{}' for header {} can't be applied to a single header; only -D and -U are supported.", arg, header)?,
         }
         Ok(())
     }
@@ -112,8 +160,9 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 struct GenerationResults {
     item_mod: ItemMod,
-    cpp: Option<CppFilePair>,
+    cpp: Vec<CppFilePair>,
     inc_dirs: Vec<PathBuf>,
+    items: Vec<GeneratedItem>,
 }
 enum State {
     NotGenerated,
@@ -123,6 +172,39 @@ enum State {
 
 const AUTOCXX_CLANG_ARGS: &[&str; 4] = &["-x", "c++", "-std=c++14", "-DBINDGEN"];
 
+/// What autocxx sees when it examines the headers for an `include_cpp!`,
+/// as reported by [`IncludeCppEngine::explain`].
+#[derive(Debug)]
+pub struct Explanation {
+    /// The arguments given to clang, apart from any which bindgen adds
+    /// itself.
+    pub clang_args: Vec<String>,
+    /// The patterns with which we ask bindgen for items, or `None` if we
+    /// ask for everything.
+    pub allowlist: Option<Vec<String>>,
+    /// Every header which was included, directly or indirectly, in the
+    /// order in which clang first reached each.
+    pub headers: Vec<String>,
+    /// The items which would be generated (or ignored, with reasons), or
+    /// the error which would stop any bindings being generated.
+    pub items: Result<Vec<GeneratedItem>>,
+}
+
+/// Gathers the headers which bindgen reports, for
+/// [`IncludeCppEngine::explain`].
+#[derive(Debug, Default, Clone)]
+struct HeaderCollector(Rc<RefCell<Vec<String>>>);
+
+impl RebuildDependencyRecorder for HeaderCollector {
+    fn record_header_file_dependency(&self, filename: &str) {
+        self.0.borrow_mut().push(filename.to_string());
+    }
+
+    fn record_file_dependency(&self, _filename: &str) {
+        // Configuration files aren't headers.
+    }
+}
+
 /// Implement to learn of header files which get included
 /// by this build process, such that your build system can choose
 /// to rerun the build process if any such file changes in future.
@@ -130,6 +212,13 @@ pub trait RebuildDependencyRecorder: std::fmt::Debug {
     /// Records that this autocxx build depends on the given
     /// header file. Full paths will be provided.
     fn record_header_file_dependency(&self, filename: &str);
+
+    /// Records that this autocxx build depends on some file other than
+    /// a header, such as a file named in `allowlist_file!`. Full paths
+    /// will be provided.
+    fn record_file_dependency(&self, filename: &str) {
+        self.record_header_file_dependency(filename)
+    }
 }
 
 #[cfg_attr(doc, aquamarine::aquamarine)]
@@ -233,6 +322,12 @@ pub trait RebuildDependencyRecorder: std::fmt::Debug {
 pub struct IncludeCppEngine {
     config: IncludeCppConfig,
     state: State,
+    /// Resolved paths of any files from which we read configuration.
+    config_files: Vec<PathBuf>,
+    /// Include directories from the project's `autocxx.toml`.
+    project_inc_dirs: Vec<PathBuf>,
+    /// Clang arguments from the project's `autocxx.toml`.
+    project_clang_args: Vec<String>,
 }
 
 impl Parse for IncludeCppEngine {
@@ -243,7 +338,13 @@ impl Parse for IncludeCppEngine {
         } else {
             State::NotGenerated
         };
-        Ok(Self { config, state })
+        Ok(Self {
+            config,
+            state,
+            config_files: Vec::new(),
+            project_inc_dirs: Vec::new(),
+            project_clang_args: Vec::new(),
+        })
     }
 }
 
@@ -252,6 +353,14 @@ impl IncludeCppEngine {
         mac.parse_body::<IncludeCppEngine>().map_err(Error::Parsing)
     }
 
+    /// Create an engine from the contents of an `include_cpp!` macro,
+    /// i.e. a series of `#include` statements and directives such as
+    /// `generate!`. This is the usual entry point for tools which wish
+    /// to drive autocxx directly rather than from a `.rs` file.
+    pub fn new_from_tokens(tokens: TokenStream2) -> Result<Self> {
+        syn::parse2::<IncludeCppEngine>(tokens).map_err(Error::Parsing)
+    }
+
     pub fn config_mut(&mut self) -> &mut IncludeCppConfig {
         assert!(
             matches!(self.state, State::NotGenerated),
@@ -260,20 +369,34 @@ impl IncludeCppEngine {
         &mut self.config
     }
 
-    fn build_header(&self) -> String {
-        join(
-            self.config
-                .inclusions
-                .iter()
-                .map(|path| format!("#include \"{}\"\n", path)),
-            "",
-        )
+    fn build_header(&self, cpp_codegen_options: &CppCodegenOptions) -> Result<String> {
+        // Imports come first, so that the headers can use what they export.
+        let mut header: String = cpp_codegen_options
+            .module_imports
+            .iter()
+            .map(ModuleImport::import_declaration)
+            .collect();
+        for path in self.inclusions(cpp_codegen_options) {
+            match cpp_codegen_options.header_clang_args.get(path) {
+                None => header.push_str(&format!("#include \"{}\"\n", path)),
+                Some(args) => header.push_str(&wrap_include_with_defines(path, args)?),
+            }
+        }
+        // Name each instantiation requested by `concrete!`, so that bindgen
+        // tells us about it.
+        for (definition, rust_id) in self.config.concretes() {
+            header.push_str(&format!("typedef {} {};\n", definition, rust_id));
+        }
+        Ok(header)
     }
 
     fn make_bindgen_builder(
         &self,
         inc_dirs: &[PathBuf],
         extra_clang_args: &[&str],
+        discovery_allowlist: Option<Vec<String>>,
+        inline_functions: bool,
+        cpp_codegen_options: &CppCodegenOptions,
     ) -> bindgen::Builder {
         let mut builder = bindgen::builder()
             .clang_args(make_clang_args(inc_dirs, extra_clang_args))
@@ -283,19 +406,28 @@ impl IncludeCppEngine {
                 non_exhaustive: false,
             })
             .enable_cxx_namespaces()
-            .generate_inline_functions(true)
+            .generate_inline_functions(inline_functions)
             .respect_cxx_access_specs(true)
             .use_specific_virtual_function_receiver(true)
             .cpp_semantic_attributes(true)
             .represent_cxx_operators(true)
-            .layout_tests(false); // TODO revisit later
+            // We don't use these tests, but they tell us the offset of
+            // each field, for use in our own layout assertions.
+            .layout_tests(true);
         for item in known_types().get_initial_blocklist() {
             builder = builder.blocklist_item(item);
         }
+        for item in &cpp_codegen_options.bindgen_blocklist {
+            builder = builder.blocklist_item(item);
+        }
 
         // 3. Passes allowlist and other options to the bindgen::Builder equivalent
         //    to --output-style=cxx --allowlist=<as passed in>
-        if let Some(allowlist) = self.config.bindgen_allowlist() {
+        let allowlist: Option<Box<dyn Iterator<Item = String>>> = match discovery_allowlist {
+            Some(discovery_allowlist) => Some(Box::new(discovery_allowlist.into_iter())),
+            None => self.config.bindgen_allowlist(),
+        };
+        if let Some(allowlist) = allowlist {
             for a in allowlist {
                 // TODO - allowlist type/functions/separately
                 builder = builder
@@ -329,6 +461,24 @@ impl IncludeCppEngine {
         }
     }
 
+    /// A description of each item which was considered for generation.
+    /// Call `generate` first.
+    pub fn generated_items(&self) -> &[GeneratedItem] {
+        match &self.state {
+            State::NotGenerated => panic!("Generate first"),
+            State::Generated(gen_results) => &gen_results.items,
+            State::ParseOnly => &[],
+        }
+    }
+
+    /// This `include_cpp!`'s part of the header written by
+    /// `Builder::rust_api_header`, if it makes any Rust items available
+    /// to C++.
+    #[cfg(any(test, feature = "build"))]
+    pub(crate) fn generate_rust_api_section(&self) -> Option<String> {
+        rust_api_header::render_rust_api_section(&self.config)
+    }
+
     /// Returns the name of the mod which this `include_cpp!` will generate.
     /// Can and should be used to ensure multiple mods in a file don't conflict.
     pub fn get_mod_name(&self) -> String {
@@ -368,30 +518,40 @@ impl IncludeCppEngine {
             State::Generated(_) => panic!("Only call generate once"),
         }
 
-        let mod_name = self.config.get_mod_name();
-        let mut builder = self.make_bindgen_builder(&inc_dirs, extra_clang_args);
-        if let Some(dep_recorder) = dep_recorder {
-            builder = builder.parse_callbacks(Box::new(AutocxxParseCallbacks(dep_recorder)));
-        }
-        let header_contents = self.build_header();
-        self.dump_header_if_so_configured(&header_contents, &inc_dirs, extra_clang_args);
-        let header_and_prelude = format!("{}\n\n{}", known_types().get_prelude(), header_contents);
-        log::info!("Header and prelude for bindgen:\n{}", header_and_prelude);
-        builder = builder.header_contents("example.hpp", &header_and_prelude);
-
-        let bindings = builder.generate().map_err(Error::Bindgen)?;
-        let bindings = self.parse_bindings(bindings)?;
-
-        let converter = BridgeConverter::new(&self.config.inclusions, &self.config);
-
-        let conversion = converter
-            .convert(
+        let discovery_inc_dirs = inc_dirs.clone();
+        let (bindings, header_contents, inc_dirs) = self.run_bindgen(
+            inc_dirs,
+            extra_clang_args,
+            dep_recorder,
+            cpp_codegen_options,
+            None,
+            true,
+        )?;
+        let function_symbols = self.find_function_symbols(
+            discovery_inc_dirs.clone(),
+            extra_clang_args,
+            cpp_codegen_options,
+        )?;
+        let include_list = self.include_list(cpp_codegen_options);
+        let converter = BridgeConverter::new(&include_list, &self.config);
+        let analysis = converter
+            .analyze(
                 bindings,
                 self.config.unsafe_policy.clone(),
-                header_contents,
-                cpp_codegen_options,
+                function_symbols,
             )
+            .map_err(|err| {
+                self.suggest_for_missing_item(
+                    err,
+                    discovery_inc_dirs,
+                    extra_clang_args,
+                    cpp_codegen_options,
+                )
+            })?;
+        let conversion = converter
+            .generate(analysis, header_contents, cpp_codegen_options)
             .map_err(Error::Conversion)?;
+        let mod_name = self.config.get_mod_name();
         let mut items = conversion.rs;
         let mut new_bindings: ItemMod = parse_quote! {
             #[allow(non_snake_case)]
@@ -410,10 +570,318 @@ impl IncludeCppEngine {
             item_mod: new_bindings,
             cpp: conversion.cpp,
             inc_dirs,
+            items: conversion.items,
         }));
         Ok(())
     }
 
+    /// Examine the headers, as [`Self::generate`] would, and describe
+    /// what bindings would be generated, without generating any code.
+    /// This is useful for tools and tests which want to know the results
+    /// of autocxx's analysis. Unlike `generate`, this may be called any
+    /// number of times, and doesn't affect later calls to `generate`.
+    pub fn analyze(
+        &self,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> Result<Vec<GeneratedItem>> {
+        if matches!(self.state, State::ParseOnly) {
+            return Ok(Vec::new());
+        }
+        let (bindings, _, _) = self.run_bindgen(
+            inc_dirs.clone(),
+            extra_clang_args,
+            None,
+            cpp_codegen_options,
+            None,
+            true,
+        )?;
+        self.analyze_bindings(bindings, inc_dirs, extra_clang_args, cpp_codegen_options)
+    }
+
+    /// Describe everything which goes into generating the bindings: the
+    /// arguments we give clang, every header which gets included, and
+    /// the items which [`Self::analyze`] would report. This is for working
+    /// out why some C++ item doesn't show up in the bindings. Like
+    /// `analyze`, this doesn't affect later calls to `generate`.
+    pub fn explain(
+        &self,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> Result<Explanation> {
+        let (all_inc_dirs, all_extra_clang_args) =
+            self.effective_clang_inputs(inc_dirs.clone(), extra_clang_args, cpp_codegen_options);
+        let all_extra_clang_args: Vec<_> =
+            all_extra_clang_args.iter().map(String::as_str).collect();
+        let clang_args = make_clang_args(&all_inc_dirs, &all_extra_clang_args).collect();
+        let allowlist = self.config.bindgen_allowlist().map(|a| a.collect());
+        if matches!(self.state, State::ParseOnly) {
+            return Ok(Explanation {
+                clang_args,
+                allowlist,
+                headers: Vec::new(),
+                items: Ok(Vec::new()),
+            });
+        }
+        let headers = HeaderCollector::default();
+        let (bindings, _, _) = self.run_bindgen(
+            inc_dirs.clone(),
+            extra_clang_args,
+            Some(Box::new(headers.clone())),
+            cpp_codegen_options,
+            None,
+            true,
+        )?;
+        let headers = headers.0.borrow().iter().unique().cloned().collect();
+        Ok(Explanation {
+            clang_args,
+            allowlist,
+            headers,
+            items: self.analyze_bindings(bindings, inc_dirs, extra_clang_args, cpp_codegen_options),
+        })
+    }
+
+    fn analyze_bindings(
+        &self,
+        bindings: ItemMod,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> Result<Vec<GeneratedItem>> {
+        let function_symbols =
+            self.find_function_symbols(inc_dirs.clone(), extra_clang_args, cpp_codegen_options)?;
+        let include_list = self.include_list(cpp_codegen_options);
+        BridgeConverter::new(&include_list, &self.config)
+            .analyze(
+                bindings,
+                self.config.unsafe_policy.clone(),
+                function_symbols,
+            )
+            .map(|analysis| analysis.items())
+            .map_err(|err| {
+                self.suggest_for_missing_item(err, inc_dirs, extra_clang_args, cpp_codegen_options)
+            })
+    }
+
+    /// If we're to look functions up in a shared library, or the library
+    /// only exports some of its functions, find out which can be found
+    /// there by asking bindgen again without inline functions, and then
+    /// again with only exported functions visible. Otherwise, we needn't
+    /// know.
+    fn find_function_symbols(
+        &self,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> Result<out_of_line::FunctionSymbols> {
+        let export_macros = self.config.export_macros();
+        if self.config.dynamic_library().is_none() && export_macros.is_empty() {
+            return Ok(out_of_line::FunctionSymbols::default());
+        }
+        let (bindings, _, _) = self.run_bindgen(
+            inc_dirs.clone(),
+            extra_clang_args,
+            None,
+            cpp_codegen_options,
+            None,
+            false,
+        )?;
+        let out_of_line = out_of_line::declared_functions(&bindings);
+        let hidden = if export_macros.is_empty() {
+            HashSet::new()
+        } else {
+            let visibility_args: Vec<_> = std::iter::once("-fvisibility=hidden".to_string())
+                .chain(export_macros.iter().map(|export_macro| {
+                    format!(
+                        "-D{}=__attribute__((visibility(\"default\")))",
+                        export_macro
+                    )
+                }))
+                .collect();
+            let extra_clang_args: Vec<_> = extra_clang_args
+                .iter()
+                .copied()
+                .chain(visibility_args.iter().map(String::as_str))
+                .collect();
+            let (bindings, _, _) = self.run_bindgen(
+                inc_dirs,
+                &extra_clang_args,
+                None,
+                cpp_codegen_options,
+                None,
+                false,
+            )?;
+            let exported = out_of_line::declared_functions(&bindings);
+            out_of_line.difference(&exported).cloned().collect()
+        };
+        Ok(out_of_line::FunctionSymbols {
+            out_of_line: Some(out_of_line),
+            hidden,
+        })
+    }
+
+    /// If a `generate!` directive didn't match anything, ask bindgen about
+    /// other items in the same namespace to see if we can suggest what the
+    /// user meant. This is slow, but only happens when we're failing anyway.
+    fn suggest_for_missing_item(
+        &self,
+        err: conversion::ConvertError,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> Error {
+        match err {
+            conversion::ConvertError::DidNotGenerateAnything(directive, None) => {
+                let discovery_allowlist = suggestions::discovery_allowlist(&directive);
+                let suggestion = self
+                    .run_bindgen(
+                        inc_dirs,
+                        extra_clang_args,
+                        None,
+                        cpp_codegen_options,
+                        Some(discovery_allowlist),
+                        true,
+                    )
+                    .ok()
+                    .and_then(|(bindings, _, _)| {
+                        suggestions::nearest_name(
+                            &directive,
+                            &suggestions::declared_names(&bindings),
+                        )
+                    });
+                Error::Conversion(conversion::ConvertError::DidNotGenerateAnything(
+                    directive, suggestion,
+                ))
+            }
+            _ => Error::Conversion(err),
+        }
+    }
+
+    /// Run bindgen over our headers, returning its output along with
+    /// the header we gave it and the include directories we used. A
+    /// `discovery_allowlist`, if given, replaces the allowlist from our
+    /// configuration. Unless `inline_functions` is set, bindgen leaves
+    /// out functions which are defined inline.
+    fn run_bindgen(
+        &self,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
+        cpp_codegen_options: &CppCodegenOptions,
+        discovery_allowlist: Option<Vec<String>>,
+        inline_functions: bool,
+    ) -> Result<(ItemMod, String, Vec<PathBuf>)> {
+        let (inc_dirs, extra_clang_args) =
+            self.effective_clang_inputs(inc_dirs, extra_clang_args, cpp_codegen_options);
+        let extra_clang_args: Vec<_> = extra_clang_args.iter().map(String::as_str).collect();
+        let extra_clang_args = &extra_clang_args[..];
+
+        let mut builder = self.make_bindgen_builder(
+            &inc_dirs,
+            extra_clang_args,
+            discovery_allowlist,
+            inline_functions,
+            cpp_codegen_options,
+        );
+        if let Some(dep_recorder) = &dep_recorder {
+            for config_file in &self.config_files {
+                dep_recorder.record_file_dependency(&config_file.to_string_lossy());
+            }
+        }
+        let user_callbacks = cpp_codegen_options.bindgen_callbacks.clone();
+        if dep_recorder.is_some() || user_callbacks.is_some() {
+            builder = builder.parse_callbacks(Box::new(AutocxxParseCallbacks {
+                dep_recorder,
+                user_callbacks,
+            }));
+        }
+        let header_contents = self.build_header(cpp_codegen_options)?;
+        self.dump_header_if_so_configured(&header_contents, &inc_dirs, extra_clang_args);
+        let header_and_prelude = format!("{}\n\n{}", known_types().get_prelude(), header_contents);
+        log::info!("Header and prelude for bindgen:\n{}", header_and_prelude);
+        builder = builder.header_contents("example.hpp", &header_and_prelude);
+
+        let bindings = builder.generate().map_err(Error::Bindgen)?;
+        let bindings = self.parse_bindings(bindings)?;
+        Ok((bindings, header_contents, inc_dirs))
+    }
+
+    /// The include directories and extra clang arguments which we really
+    /// use, including those from the project configuration and any
+    /// module imports.
+    fn effective_clang_inputs(
+        &self,
+        mut inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> (Vec<PathBuf>, Vec<String>) {
+        inc_dirs.extend(self.project_inc_dirs.iter().cloned());
+        // Module arguments come first so that the user can override the
+        // `-std` they imply.
+        let extra_clang_args = module_clang_args(&cpp_codegen_options.module_imports)
+            .into_iter()
+            .chain(self.project_clang_args.iter().cloned())
+            .chain(extra_clang_args.iter().map(|arg| arg.to_string()))
+            .collect();
+        (inc_dirs, extra_clang_args)
+    }
+
+    /// The headers named in `#include` directives, sorted if the user
+    /// asked for that.
+    fn inclusions<'a>(
+        &'a self,
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> impl Iterator<Item = &'a String> {
+        let mut inclusions: Vec<_> = self.config.inclusions.iter().collect();
+        if cpp_codegen_options.sort_inclusions {
+            inclusions.sort();
+        }
+        inclusions.into_iter()
+    }
+
+    /// The headers which the `cxx::bridge` should include directly.
+    fn include_list(&self, cpp_codegen_options: &CppCodegenOptions) -> Vec<String> {
+        // Headers with their own clang arguments can only be included via
+        // our generated header, which wraps them in the right definitions.
+        // Likewise, if there are modules to import, all the headers are
+        // included by our generated header after the imports.
+        self.inclusions(cpp_codegen_options)
+            .filter(|inc| {
+                cpp_codegen_options.module_imports.is_empty()
+                    && !cpp_codegen_options.header_clang_args.contains_key(*inc)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Generate the Rust and C++ bindings in one step, returning them
+    /// in memory rather than writing them to disk. This calls `generate`
+    /// and so likewise may only be called once.
+    pub fn generate_bindings(
+        &mut self,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> Result<GeneratedBindings> {
+        self.generate(
+            inc_dirs,
+            extra_clang_args,
+            dep_recorder,
+            cpp_codegen_options,
+        )?;
+        let cpp = self
+            .generate_h_and_cxx(cpp_codegen_options)
+            .map_err(Error::CxxGen)?;
+        Ok(GeneratedBindings {
+            rs: rust_pretty_printer::pretty_print(&self.generate_rs()),
+            cpp,
+            items: self.generated_items().to_vec(),
+        })
+    }
+
     /// Return the include directories used for this include_cpp invocation.
     fn include_dirs(&self) -> impl Iterator<Item = &PathBuf> {
         match &self.state {
@@ -557,17 +1025,122 @@ impl CppBuildable for IncludeCppEngine {
             State::ParseOnly => panic!("Cannot generate C++ in parse-only mode"),
             State::NotGenerated => panic!("Call generate() first"),
             State::Generated(gen_results) => {
-                let rs = gen_results.item_mod.to_token_stream();
                 if !cpp_codegen_options.skip_cxx_gen {
-                    files.push(do_cxx_cpp_generation(rs, cpp_codegen_options)?);
-                }
-                if let Some(cpp_file_pair) = &gen_results.cpp {
-                    files.push(cpp_file_pair.clone());
+                    files.extend(do_cxx_cpp_generation_for_mod(
+                        &gen_results.item_mod,
+                        cpp_codegen_options,
+                    )?);
                 }
+                files.extend(gen_results.cpp.iter().cloned());
             }
         };
         Ok(GeneratedCpp(files))
     }
+
+    /// Render everything generated for this `include_cpp!` as a snapshot:
+    /// deterministic text which can be checked into source control, so that
+    /// changes to the generated bindings (for example between versions of
+    /// autocxx) show up as a diff. Call `generate` first.
+    ///
+    /// Items appear in a canonical order rather than the order in which
+    /// they were generated, so the snapshot isn't itself valid Rust.
+    pub fn generate_snapshot(
+        &self,
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> Result<String, cxx_gen::Error> {
+        match &self.state {
+            State::ParseOnly => Ok(String::new()),
+            State::NotGenerated => panic!("Call generate() first"),
+            State::Generated(gen_results) => snapshot::render_snapshot(
+                &self.get_mod_name(),
+                &gen_results.item_mod,
+                &gen_results.cpp,
+                cpp_codegen_options,
+            ),
+        }
+    }
+}
+
+/// Run `cxx_gen` over all the `#[cxx::bridge]` mods in some generated code.
+fn do_cxx_cpp_generation_for_mod(
+    item_mod: &ItemMod,
+    cpp_codegen_options: &CppCodegenOptions,
+) -> Result<Vec<CppFilePair>, cxx_gen::Error> {
+    let bridge_mods = find_bridge_mods(item_mod);
+    if bridge_mods.len() > 1 {
+        // cxx_gen would consider all the bridges as one, and
+        // complain about types redeclared within each shard.
+        bridge_mods
+            .into_iter()
+            .enumerate()
+            .map(|(idx, bridge_mod)| {
+                let mut file_pair =
+                    do_cxx_cpp_generation(bridge_mod.to_token_stream(), cpp_codegen_options)?;
+                if idx > 0 {
+                    file_pair.header_name = format!("cxxgen{}.h", idx);
+                }
+                Ok(file_pair)
+            })
+            .collect()
+    } else {
+        let rs = item_mod.to_token_stream();
+        Ok(vec![do_cxx_cpp_generation(rs, cpp_codegen_options)?])
+    }
+}
+
+/// The `#[cxx::bridge]` mods within some generated code. There's more than
+/// one if `bridge_shards!` was used.
+fn find_bridge_mods(item_mod: &ItemMod) -> Vec<&ItemMod> {
+    item_mod
+        .content
+        .iter()
+        .flat_map(|(_, items)| items.iter())
+        .filter_map(|item| match item {
+            Item::Mod(bridge_mod)
+                if bridge_mod.attrs.iter().any(|attr| {
+                    attr.path
+                        .segments
+                        .iter()
+                        .map(|seg| seg.ident.to_string())
+                        .eq(["cxx", "bridge"])
+                }) =>
+            {
+                Some(bridge_mod)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// An `#include` of `path`, surrounded by the equivalent of the given `-D`
+/// and `-U` arguments. Any prior definition of each macro is restored
+/// afterwards, so other headers are unaffected.
+fn wrap_include_with_defines(path: &str, args: &[String]) -> Result<String> {
+    let mut before = String::new();
+    let mut after = String::new();
+    for arg in args {
+        let unsupported = || Error::UnsupportedHeaderClangArg(path.to_string(), arg.to_string());
+        let (name, directive) = if let Some(define) = arg.strip_prefix("-D") {
+            let (name, value) = define.split_once('=').unwrap_or((define, "1"));
+            (name, format!("#define {} {}\n", name, value))
+        } else if let Some(name) = arg.strip_prefix("-U") {
+            (name, format!("#undef {}\n", name))
+        } else {
+            return Err(unsupported());
+        };
+        // Function-like macros are pushed and popped by name alone.
+        let name = name.split('(').next().unwrap();
+        if name.is_empty() {
+            return Err(unsupported());
+        }
+        before.push_str(&format!(
+            "#pragma push_macro(\"{}\")\n#undef {}\n",
+            name, name
+        ));
+        before.push_str(&directive);
+        after.push_str(&format!("#pragma pop_macro(\"{}\")\n", name));
+    }
+    Ok(format!("{}#include \"{}\"\n{}", before, path, after))
 }
 
 /// Get clang args as if we were operating clang the same way as we operate
@@ -633,6 +1206,50 @@ impl HeaderNamer<'_> {
     }
 }
 
+/// A C++20 named module or header unit to be `import`ed, both when
+/// autocxx reads the headers and in the generated C++. Clang can only
+/// import these from a prebuilt module interface (a `.pcm` file), so the
+/// path to that is needed too.
+#[derive(Clone, Debug)]
+pub enum ModuleImport {
+    /// A named module, imported as `import name;`.
+    Module { name: String, bmi: PathBuf },
+    /// A header unit, imported as `import "header";`.
+    HeaderUnit { header: String, bmi: PathBuf },
+}
+
+impl ModuleImport {
+    fn import_declaration(&self) -> String {
+        match self {
+            ModuleImport::Module { name, .. } => format!("import {};\n", name),
+            ModuleImport::HeaderUnit { header, .. } => format!("import \"{}\";\n", header),
+        }
+    }
+
+    /// The clang argument which tells clang where to find the prebuilt
+    /// module interface.
+    pub fn clang_arg(&self) -> String {
+        match self {
+            ModuleImport::Module { name, bmi } => {
+                format!("-fmodule-file={}={}", name, bmi.to_str().unwrap())
+            }
+            ModuleImport::HeaderUnit { bmi, .. } => {
+                format!("-fmodule-file={}", bmi.to_str().unwrap())
+            }
+        }
+    }
+}
+
+/// Clang arguments needed to import the given modules and header units.
+pub fn module_clang_args(module_imports: &[ModuleImport]) -> Vec<String> {
+    if module_imports.is_empty() {
+        return Vec::new();
+    }
+    std::iter::once("-std=c++20".to_string())
+        .chain(module_imports.iter().map(ModuleImport::clang_arg))
+        .collect()
+}
+
 /// Options for C++ codegen
 #[derive(Default)]
 pub struct CppCodegenOptions<'a> {
@@ -652,9 +1269,44 @@ pub struct CppCodegenOptions<'a> {
     /// configured via `name`. These will be unique.
     pub header_namer: HeaderNamer<'a>,
     /// An annotation optionally to include on each C++ function.
-    /// For example to export the symbol from a library.
+    /// For example to export the symbol from a library. This is also
+    /// applied to C++ classes generated for subclasses.
     pub cxx_impl_annotations: Option<String>,
     /// Whether to skip using [`cxx_gen`] to generate the C++ code,
     /// so that some other process can handle that.
     pub skip_cxx_gen: bool,
+    /// Extra clang arguments which apply only to particular headers, keyed
+    /// by the name as given in `#include`. All headers are parsed together,
+    /// so only `-D` and `-U` are supported: they're turned into `#define`s
+    /// and `#undef`s around the `#include` of that header, both when
+    /// autocxx reads it and in the generated C++.
+    pub header_clang_args: HashMap<String, Vec<String>>,
+    /// C++20 modules and header units to import. This requires a compiler
+    /// which can consume clang's prebuilt module interfaces, which in
+    /// practice means clang.
+    pub module_imports: Vec<ModuleImport>,
+    /// Whether to generate C++ suitable for coverage instrumentation. Each
+    /// wrapper function is preceded by a comment naming the Rust function
+    /// which calls it, and wrappers aren't shared between functions with
+    /// identical signatures, so that each is counted separately.
+    pub coverage: bool,
+    /// Whether to sort the `#include`s in `include_cpp!` by name, both
+    /// when reading the headers and in the generated code, so that the
+    /// output is unaffected by the order in which they're listed. This is
+    /// only safe if the headers don't depend on the order of inclusion.
+    pub sort_inclusions: bool,
+    /// Into how many implementation files to split the C++ functions which
+    /// autocxx generates, so that a large binding can be compiled in
+    /// parallel. If this is more than one, the functions are defined out
+    /// of line rather than in the generated header, and each further file
+    /// comes with its own [`CppFilePair`]. Zero or one means a single file.
+    pub cpp_shards: usize,
+    /// Regular expressions matching extra items which bindgen should
+    /// ignore, in addition to those which autocxx itself blocks. This can
+    /// be used to avoid C++ items which bindgen can't handle.
+    pub bindgen_blocklist: Vec<String>,
+    /// Callbacks for bindgen to make as it parses the headers, for instance
+    /// to ignore problematic macros. Callbacks which rename items aren't
+    /// called, since autocxx relies on the names matching those in C++.
+    pub bindgen_callbacks: Option<Rc<dyn bindgen_callbacks::ParseCallbacks>>,
 }
@@ -6,18 +6,86 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::panic::UnwindSafe;
+use std::{panic::UnwindSafe, rc::Rc};
 
 use crate::RebuildDependencyRecorder;
-use autocxx_bindgen::callbacks::ParseCallbacks;
+use autocxx_bindgen::callbacks::{
+    DeriveTrait, EnumVariantCustomBehavior, EnumVariantValue, ImplementsTrait, IntKind,
+    MacroParsingBehavior, ParseCallbacks,
+};
 
+/// The callbacks we give bindgen: we record which headers are included,
+/// and pass most things on to any callbacks supplied by the user. We
+/// don't let them rename items, since the generated code relies on the
+/// Rust names matching the C++ names.
 #[derive(Debug)]
-pub(crate) struct AutocxxParseCallbacks(pub(crate) Box<dyn RebuildDependencyRecorder>);
+pub(crate) struct AutocxxParseCallbacks {
+    pub(crate) dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
+    pub(crate) user_callbacks: Option<Rc<dyn ParseCallbacks>>,
+}
 
 impl UnwindSafe for AutocxxParseCallbacks {}
 
 impl ParseCallbacks for AutocxxParseCallbacks {
+    fn will_parse_macro(&self, name: &str) -> MacroParsingBehavior {
+        self.user_callbacks
+            .as_ref()
+            .map(|cb| cb.will_parse_macro(name))
+            .unwrap_or(MacroParsingBehavior::Default)
+    }
+
+    fn int_macro(&self, name: &str, value: i64) -> Option<IntKind> {
+        self.user_callbacks.as_ref()?.int_macro(name, value)
+    }
+
+    fn str_macro(&self, name: &str, value: &[u8]) {
+        if let Some(cb) = &self.user_callbacks {
+            cb.str_macro(name, value)
+        }
+    }
+
+    fn func_macro(&self, name: &str, value: &[&[u8]]) {
+        if let Some(cb) = &self.user_callbacks {
+            cb.func_macro(name, value)
+        }
+    }
+
+    fn enum_variant_behavior(
+        &self,
+        enum_name: Option<&str>,
+        original_variant_name: &str,
+        variant_value: EnumVariantValue,
+    ) -> Option<EnumVariantCustomBehavior> {
+        self.user_callbacks.as_ref()?.enum_variant_behavior(
+            enum_name,
+            original_variant_name,
+            variant_value,
+        )
+    }
+
     fn include_file(&self, filename: &str) {
-        self.0.record_header_file_dependency(filename);
+        if let Some(dep_recorder) = &self.dep_recorder {
+            dep_recorder.record_header_file_dependency(filename);
+        }
+        if let Some(cb) = &self.user_callbacks {
+            cb.include_file(filename)
+        }
+    }
+
+    fn blocklisted_type_implements_trait(
+        &self,
+        name: &str,
+        derive_trait: DeriveTrait,
+    ) -> Option<ImplementsTrait> {
+        self.user_callbacks
+            .as_ref()?
+            .blocklisted_type_implements_trait(name, derive_trait)
+    }
+
+    fn add_derives(&self, name: &str) -> Vec<String> {
+        self.user_callbacks
+            .as_ref()
+            .map(|cb| cb.add_derives(name))
+            .unwrap_or_default()
     }
 }
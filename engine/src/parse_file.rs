@@ -6,7 +6,9 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::allowlist_file::{read_allowlist_file, AllowlistFileError};
 use crate::ast_discoverer::Discoveries;
+use crate::project_config::{find_project_config, ProjectConfig, ProjectConfigError};
 use crate::CppCodegenOptions;
 use crate::{
     cxxbridge::CxxBridge, Error as EngineError, GeneratedCpp, IncludeCppEngine,
@@ -43,6 +45,10 @@ pub enum ParseError {
     ZeroModsForDynamicDiscovery,
     MultipleModsForDynamicDiscovery,
     DiscoveredRustItemsWhenNotInAutoDiscover,
+    /// A file named in an `allowlist_file!` directive couldn't be used.
+    AllowlistFile(PathBuf, AllowlistFileError),
+    /// The project's `autocxx.toml` couldn't be used.
+    ProjectConfig(ProjectConfigError),
 }
 
 impl Display for ParseError {
@@ -61,6 +67,10 @@ impl Display for ParseError {
                 write!(f, "This file contains extra information to append to an include_cpp! but multiple such include_cpp! declarations were found in this file.")?,
             ParseError::DiscoveredRustItemsWhenNotInAutoDiscover =>
                 write!(f, "This file contains extra information to append to an \"extern Rust\" but auto-discover was switched off.")?,
+            ParseError::AllowlistFile(path, err) =>
+                write!(f, "Unable to use allowlist file {}: {}", path.to_string_lossy(), err)?,
+            ParseError::ProjectConfig(err) =>
+                write!(f, "Unable to use autocxx.toml: {}", err)?,
         }
         Ok(())
     }
@@ -72,15 +82,27 @@ pub fn parse_file<P1: AsRef<Path>>(
     auto_allowlist: bool,
 ) -> Result<ParsedFile, ParseError> {
     let mut source = String::new();
-    let mut file = std::fs::File::open(rs_file).map_err(ParseError::FileOpen)?;
+    let mut file = std::fs::File::open(&rs_file).map_err(ParseError::FileOpen)?;
     file.read_to_string(&mut source)
         .map_err(ParseError::FileRead)?;
     proc_macro2::fallback::force();
     let source = syn::parse_file(&source).map_err(ParseError::Syntax)?;
-    parse_file_contents(source, auto_allowlist)
+    let base_dir = rs_file.as_ref().parent();
+    let project_config = find_project_config(
+        base_dir
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new(".")),
+    )
+    .map_err(ParseError::ProjectConfig)?;
+    parse_file_contents(source, auto_allowlist, base_dir, project_config.as_ref())
 }
 
-fn parse_file_contents(source: syn::File, auto_allowlist: bool) -> Result<ParsedFile, ParseError> {
+fn parse_file_contents(
+    source: syn::File,
+    auto_allowlist: bool,
+    base_dir: Option<&Path>,
+    project_config: Option<&ProjectConfig>,
+) -> Result<ParsedFile, ParseError> {
     #[derive(Default)]
     struct State {
         auto_allowlist: bool,
@@ -236,6 +258,10 @@ fn parse_file_contents(source: syn::File, auto_allowlist: bool) -> Result<Parsed
         _ => None,
     });
     for seg in autocxx_seg_iterator {
+        if let Some(project_config) = project_config {
+            apply_project_config(seg, project_config);
+        }
+        load_allowlist_files(seg, base_dir)?;
         seg.config
             .confirm_complete(auto_allowlist)
             .map_err(ParseError::Syntax)?;
@@ -243,6 +269,41 @@ fn parse_file_contents(source: syn::File, auto_allowlist: bool) -> Result<Parsed
     Ok(ParsedFile(results))
 }
 
+fn apply_project_config(engine: &mut IncludeCppEngine, project_config: &ProjectConfig) {
+    engine
+        .config
+        .apply_project_defaults(project_config.defaults.clone());
+    engine
+        .project_inc_dirs
+        .extend(project_config.include_dirs.iter().cloned());
+    engine
+        .project_clang_args
+        .extend(project_config.clang_args.iter().cloned());
+    engine.config_files.push(project_config.path.clone());
+}
+
+/// Merge in the contents of any `allowlist_file!`s, which are found
+/// relative to the directory containing the .rs file.
+fn load_allowlist_files(
+    engine: &mut IncludeCppEngine,
+    base_dir: Option<&Path>,
+) -> Result<(), ParseError> {
+    for filename in engine.config.allowlist_files().to_vec() {
+        let path = match base_dir {
+            Some(base_dir) => base_dir.join(filename),
+            None => PathBuf::from(filename),
+        };
+        let external = read_allowlist_file(&path)
+            .map_err(|err| ParseError::AllowlistFile(path.clone(), err))?;
+        engine
+            .config
+            .add_external_allowlist(external)
+            .map_err(ParseError::Syntax)?;
+        engine.config_files.push(path);
+    }
+    Ok(())
+}
+
 /// A Rust file parsed by autocxx. May contain zero or more autocxx 'engines',
 /// i.e. the `IncludeCpp` class, corresponding to zero or more include_cpp
 /// macros within this file. Also contains `syn::Item` structures for all
@@ -304,7 +365,9 @@ impl ParsedFile {
         do_get_cpp_buildables(&self.0)
     }
 
-    fn get_autocxxes_mut(&mut self) -> impl Iterator<Item = &mut IncludeCppEngine> {
+    /// Get all the autocxxes in this parsed file, such that their
+    /// configuration can be altered before [`Self::resolve_all`].
+    pub fn get_autocxxes_mut(&mut self) -> impl Iterator<Item = &mut IncludeCppEngine> {
         fn do_get_autocxxes_mut(
             segments: &mut [Segment],
         ) -> impl Iterator<Item = &mut IncludeCppEngine> {
@@ -422,4 +485,8 @@ impl RebuildDependencyRecorder for CompositeDepRecorder {
     fn record_header_file_dependency(&self, filename: &str) {
         self.0.record_header_file_dependency(filename);
     }
+
+    fn record_file_dependency(&self, filename: &str) {
+        self.0.record_file_dependency(filename);
+    }
 }
@@ -0,0 +1,182 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use autocxx_parser::{NamingPolicy, ProjectDefaults, UnsafePolicy};
+
+/// The name of the file in which project-wide settings are found.
+pub(crate) const PROJECT_CONFIG_FILENAME: &str = "autocxx.toml";
+
+/// Problems reading an `autocxx.toml` file.
+#[derive(Debug)]
+pub enum ProjectConfigError {
+    /// The file couldn't be read.
+    Io(std::io::Error),
+    /// The file wasn't valid TOML.
+    Toml(toml::de::Error),
+    /// The file contained a key we don't understand.
+    UnexpectedKey(String),
+    /// A key's value was of the wrong form.
+    InvalidValue(String),
+}
+
+impl Display for ProjectConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectConfigError::Io(err) => write!(f, "{}", err)?,
+            ProjectConfigError::Toml(err) => write!(f, "{}", err)?,
+            ProjectConfigError::UnexpectedKey(key) => write!(
+                f,
+                "unexpected key '{}'; expected include_dirs, clang_args, safety, naming_policy or block_ns",
+                key
+            )?,
+            ProjectConfigError::InvalidValue(key) => {
+                write!(f, "'{}' has a value of the wrong form", key)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Settings shared by every `include_cpp!` in a project, read from an
+/// `autocxx.toml` file of the form
+/// ```toml
+/// include_dirs = [ "include" ]
+/// clang_args = [ "-DUSE_FOO=1" ]
+/// safety = "unsafe_ffi"
+/// naming_policy = "snake_case"
+/// block_ns = [ "mylib::internal" ]
+/// ```
+/// where every key is optional.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ProjectConfig {
+    /// Where this configuration was found.
+    pub(crate) path: PathBuf,
+    /// Include directories, already resolved relative to the directory
+    /// containing the file.
+    pub(crate) include_dirs: Vec<PathBuf>,
+    pub(crate) clang_args: Vec<String>,
+    pub(crate) defaults: ProjectDefaults,
+}
+
+/// Look for an `autocxx.toml` in `start_dir` or any of its ancestors,
+/// so that one file can serve a whole workspace. The nearest wins.
+pub(crate) fn find_project_config(
+    start_dir: &Path,
+) -> Result<Option<ProjectConfig>, ProjectConfigError> {
+    let start_dir = start_dir
+        .canonicalize()
+        .unwrap_or_else(|_| start_dir.to_path_buf());
+    for dir in start_dir.ancestors() {
+        let path = dir.join(PROJECT_CONFIG_FILENAME);
+        if path.is_file() {
+            let contents = std::fs::read_to_string(&path).map_err(ProjectConfigError::Io)?;
+            let mut config = parse_project_config(&contents, dir)?;
+            config.path = path;
+            return Ok(Some(config));
+        }
+    }
+    Ok(None)
+}
+
+fn parse_project_config(contents: &str, dir: &Path) -> Result<ProjectConfig, ProjectConfigError> {
+    let table: toml::value::Table = toml::from_str(contents).map_err(ProjectConfigError::Toml)?;
+    let mut config = ProjectConfig::default();
+    for (key, value) in table {
+        match key.as_str() {
+            "include_dirs" => {
+                config.include_dirs = string_list(&key, &value)?
+                    .into_iter()
+                    .map(|inc| dir.join(inc))
+                    .collect()
+            }
+            "clang_args" => config.clang_args = string_list(&key, &value)?,
+            "safety" => {
+                config.defaults.unsafe_policy = Some(parse_setting::<UnsafePolicy>(&key, &value)?)
+            }
+            "naming_policy" => {
+                config.defaults.naming_policy = Some(parse_setting::<NamingPolicy>(&key, &value)?)
+            }
+            "block_ns" => config.defaults.blocked_namespaces = string_list(&key, &value)?,
+            _ => return Err(ProjectConfigError::UnexpectedKey(key)),
+        }
+    }
+    Ok(config)
+}
+
+fn string_list(key: &str, value: &toml::Value) -> Result<Vec<String>, ProjectConfigError> {
+    value
+        .as_array()
+        .and_then(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or_else(|| ProjectConfigError::InvalidValue(key.to_string()))
+}
+
+/// Interpret a string setting exactly as we would the argument of the
+/// directive of the same name.
+fn parse_setting<T: syn::parse::Parse>(
+    key: &str,
+    value: &toml::Value,
+) -> Result<T, ProjectConfigError> {
+    value
+        .as_str()
+        .and_then(|value| syn::parse_str(value).ok())
+        .ok_or_else(|| ProjectConfigError::InvalidValue(key.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use autocxx_parser::{NamingPolicy, UnsafePolicy};
+
+    use super::{parse_project_config, ProjectConfigError};
+
+    #[test]
+    fn test_parse_project_config() {
+        let config = parse_project_config(
+            r#"
+            include_dirs = [ "include" ]
+            clang_args = [ "-DFOO" ]
+            safety = "unsafe_ffi"
+            naming_policy = "snake_case"
+            block_ns = [ "a::internal" ]
+            "#,
+            Path::new("/project"),
+        )
+        .unwrap();
+        assert_eq!(config.include_dirs, [PathBuf::from("/project/include")]);
+        assert_eq!(config.clang_args, ["-DFOO"]);
+        assert_eq!(
+            config.defaults.unsafe_policy,
+            Some(UnsafePolicy::AllFunctionsSafe)
+        );
+        assert_eq!(config.defaults.naming_policy, Some(NamingPolicy::SnakeCase));
+        assert_eq!(config.defaults.blocked_namespaces, ["a::internal"]);
+    }
+
+    #[test]
+    fn test_parse_project_config_rejects_bad_values() {
+        assert!(matches!(
+            parse_project_config(r#"naming_policy = "kebab""#, Path::new("/")),
+            Err(ProjectConfigError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            parse_project_config(r#"generate = [ "Foo" ]"#, Path::new("/")),
+            Err(ProjectConfigError::UnexpectedKey(_))
+        ));
+    }
+}
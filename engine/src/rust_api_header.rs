@@ -0,0 +1,156 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A header through which C++ can use the Rust types and functions made
+//! available by `extern_rust_type` and `extern_rust_function`.
+//!
+//! `cxx` declares these in `cxxgen.h`, alongside everything else in the
+//! bridge, and the name and layout of that file are details of how the
+//! bindings happen to be built. This header is meant to be installed or
+//! checked in alongside the C++ which uses it: it has an include guard,
+//! it documents each item, and it puts them in a namespace named after
+//! the `include_cpp!` mod, so C++ code doesn't need to know anything
+//! about `cxx`.
+
+use autocxx_parser::IncludeCppConfig;
+use itertools::Itertools;
+use quote::ToTokens;
+use syn::{FnArg, ReturnType, Signature};
+
+use crate::CppCodegenOptions;
+
+/// The part of the header for a single `include_cpp!`, or `None` if it
+/// doesn't expose anything from Rust.
+pub(crate) fn render_rust_api_section(config: &IncludeCppConfig) -> Option<String> {
+    if config.rust_types.is_empty() && config.extern_rust_funs.is_empty() {
+        return None;
+    }
+    let mod_name = config.get_mod_name();
+    let mut out = format!("namespace {} {{\n", mod_name);
+    for path in &config.rust_types {
+        let id = path.get_final_ident();
+        out.push_str(&format!(
+            "\n/// The Rust type `{}`. C++ can't know its size or layout, so can\n/// only refer to it by pointer or reference, or within a `rust::Box`.\nusing {} = ::{};\n",
+            to_string(path),
+            id,
+            id
+        ));
+    }
+    for fun in &config.extern_rust_funs {
+        out.push_str(&format!(
+            "\n/// Calls the Rust function `{}`, which is declared as:\n/// `{}`\nusing ::{};\n",
+            to_string(&fun.path),
+            signature_to_string(&fun.sig),
+            fun.sig.ident
+        ));
+    }
+    out.push_str(&format!("\n}} // namespace {}\n", mod_name));
+    Some(out)
+}
+
+/// The whole header, given the sections for each `include_cpp!`.
+pub(crate) fn render_rust_api_header(
+    header_name: &str,
+    sections: &[String],
+    cpp_codegen_options: &CppCodegenOptions,
+) -> String {
+    let guard = format!(
+        "AUTOCXX_{}",
+        header_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            })
+            .collect::<String>()
+    );
+    let path_to_cxxgen_h = cpp_codegen_options
+        .path_to_cxxgen_h
+        .as_deref()
+        .unwrap_or_default();
+    format!(
+        "// Rust APIs available to C++. Generated by autocxx: do not edit.\n\n#ifndef {guard}\n#define {guard}\n\n#include \"{prefix}cxxgen.h\"\n\n{sections}\n#endif // {guard}\n",
+        guard = guard,
+        prefix = path_to_cxxgen_h,
+        sections = sections.join("\n")
+    )
+}
+
+fn signature_to_string(sig: &Signature) -> String {
+    let args = sig
+        .inputs
+        .iter()
+        .map(|arg| match arg {
+            FnArg::Typed(pt) => format!("{}: {}", to_string(&pt.pat), to_string(&pt.ty)),
+            FnArg::Receiver(_) => to_string(arg),
+        })
+        .join(", ");
+    let ret = match &sig.output {
+        ReturnType::Default => String::new(),
+        ReturnType::Type(_, ty) => format!(" -> {}", to_string(ty)),
+    };
+    format!("fn {}({}){}", sig.ident, args, ret)
+}
+
+/// Tokens as a string, with spaces only where they're needed, i.e.
+/// between two words.
+fn to_string(tokens: &impl ToTokens) -> String {
+    let text = tokens.to_token_stream().to_string();
+    let is_word = |c: Option<char>| c.map(|c| c.is_alphanumeric() || c == '_') == Some(true);
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ' ' || (is_word(out.chars().last()) && is_word(chars.peek().copied())) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use autocxx_parser::IncludeCppConfig;
+    use syn::parse_quote;
+
+    use super::{render_rust_api_header, render_rust_api_section};
+    use crate::CppCodegenOptions;
+
+    #[test]
+    fn test_rust_api_header() {
+        let config: IncludeCppConfig = parse_quote! {
+            safety!(unsafe_ffi)
+            extern_rust_type!(Counter)
+            extern_rust_fun!(counters::make_counter, fn make_counter(start: u32) -> Box<Counter>)
+        };
+        let section = render_rust_api_section(&config).unwrap();
+        assert!(section.starts_with("namespace ffi {\n"));
+        assert!(section.contains("/// The Rust type `Counter`."));
+        assert!(section.contains("using Counter = ::Counter;"));
+        assert!(section.contains("/// Calls the Rust function `counters::make_counter`"));
+        assert!(section.contains("/// `fn make_counter(start: u32) -> Box<Counter>`"));
+        assert!(section.contains("using ::make_counter;"));
+        let header = render_rust_api_header(
+            "my-lib/rust_api.h",
+            &[section],
+            &CppCodegenOptions::default(),
+        );
+        assert!(header.contains("#ifndef AUTOCXX_MY_LIB_RUST_API_H\n"));
+        assert!(header.contains("#include \"cxxgen.h\""));
+        assert!(header.ends_with("#endif // AUTOCXX_MY_LIB_RUST_API_H\n"));
+    }
+
+    #[test]
+    fn test_rust_api_header_without_rust_items() {
+        let config: IncludeCppConfig = parse_quote! {
+            safety!(unsafe_ffi)
+            generate!("foo")
+        };
+        assert!(render_rust_api_section(&config).is_none());
+    }
+}
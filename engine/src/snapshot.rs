@@ -0,0 +1,172 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rendering of generated bindings as snapshots for golden testing.
+//!
+//! The order in which we generate items isn't stable (much of it
+//! comes from iterating over hash maps) and `rustfmt` may or may not be
+//! available, so rather than pretty-printing the Rust we sort it into a
+//! canonical order and print one item per line, expanding mods, extern
+//! blocks and impls so that a diff shows which item changed. The `cxx`
+//! C++ is then generated from the sorted Rust, so it's stable too.
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{ForeignItem, Item, ItemForeignMod, ItemImpl, ItemMod, ItemTrait};
+
+use crate::{do_cxx_cpp_generation_for_mod, CppCodegenOptions, CppFilePair};
+
+const INDENT: &str = "    ";
+
+pub(crate) fn render_snapshot(
+    mod_name: &str,
+    item_mod: &ItemMod,
+    autocxx_cpp: &[CppFilePair],
+    cpp_codegen_options: &CppCodegenOptions,
+) -> Result<String, cxx_gen::Error> {
+    let mut item_mod = item_mod.clone();
+    if let Some((_, items)) = &mut item_mod.content {
+        sort_items(items);
+    }
+    let mut out = format!("// autocxx snapshot of mod {}\n\n// Rust\n", mod_name);
+    render_item(&Item::Mod(item_mod.clone()), 0, &mut out);
+    let mut cpp_files = if cpp_codegen_options.skip_cxx_gen {
+        Vec::new()
+    } else {
+        do_cxx_cpp_generation_for_mod(&item_mod, cpp_codegen_options)?
+    };
+    cpp_files.extend(autocxx_cpp.iter().cloned());
+    for file_pair in cpp_files {
+        render_cpp(&file_pair.header_name, &file_pair.header, &mut out);
+        if let Some(implementation) = &file_pair.implementation {
+            let label = format!("implementation for {}", file_pair.header_name);
+            render_cpp(&label, implementation, &mut out);
+        }
+    }
+    Ok(out)
+}
+
+fn sort_items(items: &mut Vec<Item>) {
+    for item in items.iter_mut() {
+        match item {
+            Item::Mod(ItemMod {
+                content: Some((_, items)),
+                ..
+            }) => sort_items(items),
+            // The order of `include!`s matters, so leave them first and in
+            // their original order.
+            Item::ForeignMod(fm) => fm.items.sort_by_cached_key(|item| match item {
+                ForeignItem::Macro(_) => (false, String::new()),
+                _ => (true, to_string(item)),
+            }),
+            Item::Impl(imp) => imp.items.sort_by_cached_key(to_string),
+            Item::Trait(trt) => trt.items.sort_by_cached_key(to_string),
+            _ => {}
+        }
+    }
+    items.sort_by_cached_key(to_string);
+}
+
+fn to_string(tokens: &impl ToTokens) -> String {
+    tokens.to_token_stream().to_string()
+}
+
+fn render_item(item: &Item, depth: usize, out: &mut String) {
+    match item {
+        Item::Mod(ItemMod {
+            attrs,
+            vis,
+            ident,
+            content: Some((_, items)),
+            ..
+        }) => {
+            open_block(quote! { #(#attrs)* #vis mod #ident }, depth, out);
+            for item in items {
+                render_item(item, depth + 1, out);
+            }
+            close_block(depth, out);
+        }
+        Item::ForeignMod(ItemForeignMod {
+            attrs, abi, items, ..
+        }) => render_block(quote! { #(#attrs)* #abi }, items, depth, out),
+        Item::Impl(ItemImpl {
+            attrs,
+            unsafety,
+            generics,
+            trait_,
+            self_ty,
+            items,
+            ..
+        }) => {
+            let trait_ = trait_
+                .as_ref()
+                .map(|(bang, path, for_token)| quote! { #bang #path #for_token });
+            let where_clause = &generics.where_clause;
+            render_block(
+                quote! { #(#attrs)* #unsafety impl #generics #trait_ #self_ty #where_clause },
+                items,
+                depth,
+                out,
+            )
+        }
+        Item::Trait(ItemTrait {
+            attrs,
+            vis,
+            unsafety,
+            ident,
+            generics,
+            colon_token,
+            supertraits,
+            items,
+            ..
+        }) => {
+            let where_clause = &generics.where_clause;
+            render_block(
+                quote! { #(#attrs)* #vis #unsafety trait #ident #generics #colon_token #supertraits #where_clause },
+                items,
+                depth,
+                out,
+            )
+        }
+        _ => render_line(item, depth, out),
+    }
+}
+
+/// Render a block with one line for each item within it.
+fn render_block(header: TokenStream, items: &[impl ToTokens], depth: usize, out: &mut String) {
+    open_block(header, depth, out);
+    for item in items {
+        render_line(item, depth + 1, out);
+    }
+    close_block(depth, out);
+}
+
+fn open_block(header: TokenStream, depth: usize, out: &mut String) {
+    out.push_str(&INDENT.repeat(depth));
+    out.push_str(&header.to_string());
+    out.push_str(" {\n");
+}
+
+fn close_block(depth: usize, out: &mut String) {
+    out.push_str(&INDENT.repeat(depth));
+    out.push_str("}\n");
+}
+
+fn render_line(tokens: &impl ToTokens, depth: usize, out: &mut String) {
+    out.push_str(&INDENT.repeat(depth));
+    out.push_str(&to_string(tokens));
+    out.push('\n');
+}
+
+fn render_cpp(label: &str, contents: &[u8], out: &mut String) {
+    out.push_str(&format!("\n// C++: {}\n", label));
+    out.push_str(&String::from_utf8_lossy(contents));
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
@@ -20,7 +20,7 @@ pub(crate) fn make_ident<S: AsRef<str>>(id: S) -> Ident {
 }
 
 /// Newtype wrapper for a C++ namespace.
-#[derive(Debug, PartialEq, PartialOrd, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Clone)]
 #[allow(clippy::rc_buffer)]
 pub struct Namespace(Arc<Vec<String>>);
 
@@ -44,6 +44,19 @@ impl Namespace {
         self.0.iter()
     }
 
+    /// Iterate over the segments of this namespace as they're spelled in
+    /// C++. Segments are stored as they're spelled in the bindgen mod,
+    /// where a namespace called `async` becomes `async_`.
+    pub(crate) fn cpp_iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|seg| cpp_name_for_rust_ident(seg))
+    }
+
+    /// The namespace as it should be written in C++ code or in a
+    /// `#[namespace]` attribute.
+    pub(crate) fn to_cpp_string(&self) -> String {
+        self.cpp_iter().join("::")
+    }
+
     #[cfg(test)]
     pub(crate) fn from_user_input(input: &str) -> Self {
         Self(Arc::new(input.split("::").map(|x| x.to_string()).collect()))
@@ -88,7 +101,7 @@ impl<'a> IntoIterator for &'a Namespace {
 /// either. It doesn't directly have functionality to convert
 /// from one to the other; `replace_type_path_without_arguments`
 /// does that.
-#[derive(Debug, PartialEq, PartialOrd, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Clone)]
 pub struct QualifiedName(Namespace, String);
 
 impl QualifiedName {
@@ -125,13 +138,15 @@ impl QualifiedName {
     }
 
     /// Create from user input, e.g. a name in an AllowPOD directive.
+    /// Namespaces which are Rust keywords are renamed to match bindgen's
+    /// mods.
     pub(crate) fn new_from_cpp_name(id: &str) -> Self {
         let mut seg_iter = id.split("::").peekable();
         let mut ns = Namespace::new();
         while let Some(seg) = seg_iter.next() {
             if seg_iter.peek().is_some() {
                 if !seg.to_string().is_empty() {
-                    ns = ns.push(seg.to_string());
+                    ns = ns.push(rust_name_for_cpp_ident(seg));
                 }
             } else {
                 return Self(ns, seg.to_string());
@@ -176,7 +191,11 @@ impl QualifiedName {
         let special_cpp_name = known_types().special_cpp_name(self);
         match special_cpp_name {
             Some(name) => name,
-            None => self.0.iter().chain(std::iter::once(&self.1)).join("::"),
+            None => self
+                .0
+                .cpp_iter()
+                .chain(std::iter::once(self.1.as_str()))
+                .join("::"),
         }
     }
 
@@ -247,9 +266,35 @@ pub fn validate_ident_ok_for_rust(label: &str) -> Result<(), ConvertError> {
         .map(|_| ())
 }
 
+/// Whether this name is a Rust keyword, and so can't be used as an
+/// identifier in Rust without renaming.
+pub(crate) fn is_rust_keyword(name: &str) -> bool {
+    syn::parse_str::<Ident>(name).is_err() && syn::parse_str::<Ident>(&format!("{}_", name)).is_ok()
+}
+
+/// The name to use in Rust for a C++ identifier. C++ names which are Rust
+/// keywords, such as `move` or `type`, get an underscore appended, just as
+/// bindgen does.
+pub(crate) fn rust_name_for_cpp_ident(cpp_name: &str) -> String {
+    if is_rust_keyword(cpp_name) {
+        format!("{}_", cpp_name)
+    } else {
+        cpp_name.to_string()
+    }
+}
+
+/// Undo [`rust_name_for_cpp_ident`] (or bindgen's equivalent renaming) to
+/// find the C++ spelling of a name.
+pub(crate) fn cpp_name_for_rust_ident(rust_name: &str) -> &str {
+    match rust_name.strip_suffix('_') {
+        Some(cpp_name) if is_rust_keyword(cpp_name) => cpp_name,
+        _ => rust_name,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::QualifiedName;
+    use super::{cpp_name_for_rust_ident, rust_name_for_cpp_ident, Namespace, QualifiedName};
 
     #[test]
     fn test_ints() {
@@ -262,4 +307,20 @@ mod tests {
             "uint64_t"
         );
     }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(rust_name_for_cpp_ident("move"), "move_");
+        assert_eq!(rust_name_for_cpp_ident("mover"), "mover");
+        assert_eq!(cpp_name_for_rust_ident("type_"), "type");
+        assert_eq!(cpp_name_for_rust_ident("types_"), "types_");
+        assert_eq!(
+            Namespace::from_user_input("a::async_::b").to_cpp_string(),
+            "a::async::b"
+        );
+        assert_eq!(
+            QualifiedName::new_from_cpp_name("self::Foo").to_cpp_name(),
+            "self::Foo"
+        );
+    }
 }
@@ -8,6 +8,10 @@
 
 #![forbid(unsafe_code)]
 
+pub use autocxx_engine::{
+    bindgen_callbacks, register_known_type_extension, ExternalTypeMapping, KnownTypeExtension,
+    KnownTypeRegistrationError, Sanitizer,
+};
 use autocxx_engine::{BuilderBuild, BuilderContext, BuilderError, RebuildDependencyRecorder};
 use std::{collections::HashSet, io::Write, sync::Mutex};
 use std::{ffi::OsStr, path::Path};
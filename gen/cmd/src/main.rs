@@ -8,13 +8,24 @@
 
 #![forbid(unsafe_code)]
 
-use autocxx_engine::{parse_file, HeaderNamer};
-use clap::{crate_authors, crate_version, App, Arg, ArgGroup};
-use proc_macro2::TokenStream;
+use autocxx_engine::{
+    diff_generated_items, parse_file, GeneratedItem, GeneratedItemChange, HeaderNamer,
+    ModuleImport, ParsedFile, RebuildDependencyRecorder,
+};
+use clap::{crate_authors, crate_version, App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand};
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::ToTokens;
 use std::io::{Read, Write};
 use std::path::PathBuf;
-use std::{cell::Cell, fs::File, path::Path};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fs::{create_dir_all, File},
+    panic::AssertUnwindSafe,
+    path::Path,
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
 
 pub(crate) static BLANK: &str = "// Blank autocxx placeholder";
 
@@ -70,14 +81,55 @@ which `.include.rs` file to use, so the only way to get fixed output paths is
 with `--gen-rs-complete`. There are always multiple `.cc` files (even with just
 a single `include_cpp!` section), and we always generate the same number of each
 type of file.
+
+Bindings for a very large C++ project may be split into several crates, one per
+C++ namespace, using `--split-ns NAMESPACE=CRATE` once for each. Everything for
+each is generated into a subdirectory of the output directory named after its
+crate, as though its `include_cpp!` asked only for that namespace. Each crate
+refers to types from the namespaces listed before it (via `extern_cpp_ns!`) rather
+than generating them again, so list them in dependency order, and make each
+crate depend on those before it.
+
+Finally, `autocxx-gen diff` can help you upgrade a C++ dependency. Give it
+the Rust file, and the include paths for the old and new versions of the
+headers, and it'll list the functions and types whose bindings would be
+added, removed or changed. Like diff(1), it exits with status 1 if there are
+any differences.
+
+If a C++ item you expected doesn't show up in the bindings, `autocxx-gen
+explain` may help to work out why. Give it the Rust file and the same include
+paths and clang arguments as usual, and for each `include_cpp!` it'll print
+the arguments given to clang, the patterns used to ask bindgen for items,
+every header which gets included, and every item autocxx found, along with
+the reason for any which it couldn't generate.
+
+If you check generated bindings into your repository, `--watch` gives a quick
+editing loop: after generating everything, autocxx-gen keeps watching the
+Rust file, every header it includes and any other configuration files, and
+regenerates whenever one changes. It carries on watching if generation fails,
+so you can fix the problem and save again. Stop it with Ctrl-C.
 ";
 
+/// How often `--watch` checks whether anything has changed.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Gathers every file on which the bindings depend, for `--watch`.
+#[derive(Debug, Default, Clone)]
+struct DependencyCollector(Rc<RefCell<Vec<PathBuf>>>);
+
+impl RebuildDependencyRecorder for DependencyCollector {
+    fn record_header_file_dependency(&self, filename: &str) {
+        self.0.borrow_mut().push(PathBuf::from(filename));
+    }
+}
+
 fn main() {
     let matches = App::new("autocxx-gen")
         .version(crate_version!())
         .author(crate_authors!())
         .about("Generates bindings files from Rust files that contain include_cpp! macros")
         .long_about(LONG_HELP)
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("INPUT")
                 .help("Sets the input .rs file to use")
@@ -133,6 +185,11 @@ fn main() {
             .arg("gen-rs-complete")
             .arg("gen-rs-include")
         )
+        .arg(
+            Arg::with_name("watch")
+                .long("watch")
+                .help("after generating, watch the input file and everything it depends upon, and regenerate whenever any of them change")
+        )
         .arg(
             Arg::with_name("skip-cxx-gen")
                 .long("skip-cxx-gen")
@@ -162,6 +219,35 @@ fn main() {
                 .long("suppress-system-headers")
                 .help("Do not refer to any system headers from generated code. May be useful for minimization.")
         )
+        .arg(
+            Arg::with_name("coverage")
+                .long("coverage")
+                .help("Generate C++ suitable for coverage instrumentation, with each wrapper function annotated with the Rust function which calls it. Only applies for --gen-cpp")
+                .requires("gen-cpp")
+        )
+        .arg(
+            Arg::with_name("sort-includes")
+                .long("sort-includes")
+                .help("Sort the #includes in include_cpp! by name, so that reordering them doesn't change the generated code. Only safe if the headers don't depend on the order of inclusion.")
+        )
+        .arg(
+            Arg::with_name("bindgen-blocklist")
+                .long("bindgen-blocklist")
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("REGEX")
+                .help("Ask bindgen to ignore C++ items matching this regular expression, to work around items which it can't handle")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("cpp-shards")
+                .long("cpp-shards")
+                .value_name("NUM")
+                .help("Split the C++ functions generated by autocxx for each include_cpp! between NUM .cc files, so that they can be compiled in parallel. Each extra file also has a header, which counts towards --generate-exact. Only applies for --gen-cpp")
+                .takes_value(true)
+                .validator(validate_cpp_shards)
+                .requires("gen-cpp")
+        )
         .arg(
             Arg::with_name("cxx-impl-annotations")
                 .long("cxx-impl-annotations")
@@ -183,20 +269,357 @@ fn main() {
                 .help("prefix for path to cxxgen.h (which we generate into the output directory) within #include statements. Must end in /")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("header-clang-arg")
+                .long("header-clang-arg")
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("HEADER=ARG")
+                .help("a -D or -U argument to apply only to the given header, e.g. foo.h=-DSPECIAL_BUILD")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("import-module")
+                .long("import-module")
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("MODULE=BMI")
+                .help("a C++20 module to import, and the path to its prebuilt module interface, e.g. foo.bar=foo.bar.pcm")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("import-header-unit")
+                .long("import-header-unit")
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("HEADER=BMI")
+                .help("a C++20 header unit to import, and the path to its prebuilt module interface, e.g. foo.h=foo.pcm")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("split-ns")
+                .long("split-ns")
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("NAMESPACE=CRATE")
+                .help("generate bindings for just this C++ namespace into a subdirectory of the output directory named after CRATE, referring to types from any namespace given earlier in the crate named for it, e.g. mylib::core=mylib_core_sys")
+                .takes_value(true)
+                .validator(|arg| parse_split_ns(&arg).map(|_| ())),
+        )
         .arg(
             Arg::with_name("clang-args")
                 .last(true)
                 .multiple(true)
                 .help("Extra arguments to pass to Clang"),
         )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Lists the differences between the bindings for two versions of some C++ headers")
+                .arg(
+                    Arg::with_name("INPUT")
+                        .help("Sets the input .rs file to use")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("old-inc")
+                        .long("old-inc")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("INCLUDE DIRS")
+                        .help("include path for the old version of the headers")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("new-inc")
+                        .long("new-inc")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("INCLUDE DIRS")
+                        .help("include path for the new version of the headers")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("clang-args")
+                        .last(true)
+                        .multiple(true)
+                        .help("Extra arguments to pass to Clang"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("Prints the clang arguments, included headers and discovered items for each include_cpp!")
+                .arg(
+                    Arg::with_name("INPUT")
+                        .help("Sets the input .rs file to use")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("inc")
+                        .short("I")
+                        .long("inc")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("INCLUDE DIRS")
+                        .help("include path")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("clang-args")
+                        .last(true)
+                        .multiple(true)
+                        .help("Extra arguments to pass to Clang"),
+                ),
+        )
         .get_matches();
 
     env_logger::builder().init();
-    let mut parsed_file = parse_file(
+    if let Some(diff_matches) = matches.subcommand_matches("diff") {
+        let any_changes = diff(diff_matches);
+        std::process::exit(if any_changes { 1 } else { 0 });
+    }
+    if let Some(explain_matches) = matches.subcommand_matches("explain") {
+        explain(explain_matches);
+        return;
+    }
+    let outdir: PathBuf = matches.value_of_os("outdir").unwrap().into();
+    if matches.is_present("watch") {
+        watch(&matches, &outdir);
+    }
+    generate_all(&matches, &outdir, &DependencyCollector::default());
+}
+
+/// Checks that `--cpp-shards` is given a positive number.
+fn validate_cpp_shards(arg: String) -> Result<(), String> {
+    match arg.parse::<usize>() {
+        Ok(0) => Err("there must be at least one shard".to_string()),
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("'{}' isn't a number", arg)),
+    }
+}
+
+/// Splits a `--split-ns` argument into the namespace, the crate name and
+/// the identifier by which Rust code refers to that crate, which has
+/// underscores in place of any hyphens in its name.
+fn parse_split_ns(arg: &str) -> Result<(&str, &str, Ident), String> {
+    let (ns, crate_name) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("'{}' isn't of the form NAMESPACE=CRATE", arg))?;
+    if ns.is_empty() {
+        return Err(format!("'{}' doesn't give a namespace", arg));
+    }
+    let ident = crate_name.replace('-', "_");
+    let is_valid = ident
+        .chars()
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && ident.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && ident != "_";
+    if !is_valid {
+        return Err(format!("'{}' isn't a valid crate name", crate_name));
+    }
+    Ok((ns, crate_name, Ident::new(&ident, Span::call_site())))
+}
+
+/// Generate everything asked for on the command line, telling `deps`
+/// about each file which the results depend upon.
+fn generate_all(matches: &ArgMatches, outdir: &Path, deps: &DependencyCollector) {
+    let splits: Vec<_> = matches
+        .values_of("split-ns")
+        .unwrap_or_default()
+        .map(|split| parse_split_ns(split).unwrap()) // checked by the validator
+        .collect();
+    if splits.is_empty() {
+        generate(matches, parse_input(matches), outdir, deps);
+        return;
+    }
+    // Each namespace gets generated separately, as though its crate had
+    // its own include_cpp! with generate_ns!, and each may use types from
+    // the crates before it.
+    let mut earlier_splits: Vec<(&str, Ident)> = Vec::new();
+    for (ns, crate_name, crate_ident) in splits {
+        let mut parsed_file = parse_input(matches);
+        for include_cpp in parsed_file.get_autocxxes_mut() {
+            let config = include_cpp.config_mut();
+            for (earlier_ns, earlier_crate) in &earlier_splits {
+                config.add_extern_cpp_ns(earlier_ns.to_string(), earlier_crate.clone());
+            }
+            config.restrict_to_namespace(ns.to_string());
+        }
+        let crate_outdir = outdir.join(crate_name);
+        create_dir_all(&crate_outdir).expect("Unable to create output directory");
+        generate(matches, parsed_file, &crate_outdir, deps);
+        earlier_splits.push((ns, crate_ident));
+    }
+}
+
+/// Generate everything, then regenerate whenever the input file or
+/// anything it depends upon changes. Never returns.
+fn watch(matches: &ArgMatches, outdir: &Path) -> ! {
+    let input = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let mut files = Vec::new();
+    loop {
+        let deps = DependencyCollector::default();
+        let succeeded =
+            std::panic::catch_unwind(AssertUnwindSafe(|| generate_all(matches, outdir, &deps)))
+                .is_ok();
+        let mut new_files = deps.0.take();
+        if succeeded {
+            eprintln!("autocxx-gen: generated bindings; watching for changes");
+        } else {
+            eprintln!("autocxx-gen: unable to generate bindings; watching for changes");
+            // We may not have got as far as reading everything, so keep
+            // watching what we knew about before.
+            new_files.extend(files);
+        }
+        new_files.push(input.clone());
+        new_files.sort();
+        new_files.dedup();
+        files = new_files;
+        wait_for_change(&files);
+    }
+}
+
+/// Wait until any of these files is modified, created or deleted.
+fn wait_for_change(files: &[PathBuf]) {
+    let modification_times = || -> Vec<Option<SystemTime>> {
+        files
+            .iter()
+            .map(|file| std::fs::metadata(file).and_then(|m| m.modified()).ok())
+            .collect()
+    };
+    let original = modification_times();
+    while modification_times() == original {
+        std::thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+fn parse_input(matches: &ArgMatches) -> ParsedFile {
+    parse_file(
         matches.value_of("INPUT").unwrap(),
         matches.is_present("auto-allowlist"),
     )
-    .expect("Unable to parse Rust file and interpret autocxx macro");
+    .expect("Unable to parse Rust file and interpret autocxx macro")
+}
+
+/// Print the differences between the bindings for the old and new
+/// headers, returning whether there were any.
+fn diff(matches: &ArgMatches) -> bool {
+    let parsed_file = parse_input(matches);
+    let old_incs: Vec<PathBuf> = matches
+        .values_of("old-inc")
+        .unwrap_or_default()
+        .map(PathBuf::from)
+        .collect();
+    let new_incs: Vec<PathBuf> = matches
+        .values_of("new-inc")
+        .unwrap_or_default()
+        .map(PathBuf::from)
+        .collect();
+    let extra_clang_args: Vec<_> = matches
+        .values_of("clang-args")
+        .unwrap_or_default()
+        .collect();
+    let cpp_codegen_options = autocxx_engine::CppCodegenOptions::default();
+    let include_cpps: Vec<_> = parsed_file.get_rs_buildables().collect();
+    let mut any_changes = false;
+    for include_cpp in &include_cpps {
+        let analyze = |incs: &Vec<PathBuf>| {
+            include_cpp
+                .analyze(incs.clone(), &extra_clang_args, &cpp_codegen_options)
+                .expect("Unable to analyze headers")
+        };
+        let changes = diff_generated_items(&analyze(&old_incs), &analyze(&new_incs));
+        if include_cpps.len() > 1 && !changes.is_empty() {
+            println!("In mod {}:", include_cpp.get_mod_name());
+        }
+        for change in &changes {
+            match change {
+                GeneratedItemChange::Added(item) => println!("+ {}", describe_item(item)),
+                GeneratedItemChange::Removed(item) => println!("- {}", describe_item(item)),
+                GeneratedItemChange::Changed { old, new } => {
+                    println!("~ {}", new.rust_name);
+                    println!("    - {}", describe_item(old));
+                    println!("    + {}", describe_item(new));
+                }
+            }
+        }
+        any_changes |= !changes.is_empty();
+    }
+    any_changes
+}
+
+/// Print everything which goes into generating the bindings for each
+/// `include_cpp!`, to help work out why some item is missing.
+fn explain(matches: &ArgMatches) {
+    let parsed_file = parse_input(matches);
+    let incs: Vec<PathBuf> = matches
+        .values_of("inc")
+        .unwrap_or_default()
+        .map(PathBuf::from)
+        .collect();
+    let extra_clang_args: Vec<_> = matches
+        .values_of("clang-args")
+        .unwrap_or_default()
+        .collect();
+    let cpp_codegen_options = autocxx_engine::CppCodegenOptions::default();
+    for include_cpp in parsed_file.get_rs_buildables() {
+        println!("In mod {}:", include_cpp.get_mod_name());
+        let explanation = include_cpp
+            .explain(incs.clone(), &extra_clang_args, &cpp_codegen_options)
+            .expect("Unable to run bindgen");
+        println!("  Clang arguments:");
+        for arg in &explanation.clang_args {
+            println!("    {}", arg);
+        }
+        match &explanation.allowlist {
+            None => println!("  Allowlist: everything"),
+            Some(allowlist) => {
+                println!("  Allowlist:");
+                for pattern in allowlist {
+                    println!("    {}", pattern);
+                }
+            }
+        }
+        println!("  Headers:");
+        for header in &explanation.headers {
+            println!("    {}", header);
+        }
+        match &explanation.items {
+            Ok(items) => {
+                println!("  Items:");
+                for item in items {
+                    println!("    {}", describe_item(item));
+                }
+            }
+            Err(err) => println!("  No bindings could be generated: {}", err),
+        }
+    }
+}
+
+fn describe_item(item: &GeneratedItem) -> String {
+    let mut description = format!("{:?} {}", item.kind, item.rust_name);
+    if let Some(impl_for) = &item.impl_for {
+        description.push_str(&format!(" on {}", impl_for));
+    }
+    if let Some(signature) = &item.signature {
+        description.push_str(&format!(": {}", signature));
+    }
+    if let Some(reason) = &item.ignored_reason {
+        description.push_str(&format!(" ({})", reason));
+    }
+    description
+}
+
+fn generate(
+    matches: &ArgMatches,
+    mut parsed_file: ParsedFile,
+    outdir: &Path,
+    deps: &DependencyCollector,
+) {
     let incs = matches
         .values_of("inc")
         .unwrap_or_default()
@@ -206,6 +629,35 @@ fn main() {
         .values_of("clang-args")
         .unwrap_or_default()
         .collect();
+    let mut header_clang_args: HashMap<String, Vec<String>> = HashMap::new();
+    for header_arg in matches.values_of("header-clang-arg").unwrap_or_default() {
+        let (header, arg) = header_arg
+            .split_once('=')
+            .expect("--header-clang-arg should be of the form HEADER=ARG");
+        header_clang_args
+            .entry(header.to_string())
+            .or_default()
+            .push(arg.to_string());
+    }
+    let mut module_imports = Vec::new();
+    for module_arg in matches.values_of("import-module").unwrap_or_default() {
+        let (name, bmi) = module_arg
+            .split_once('=')
+            .expect("--import-module should be of the form MODULE=BMI");
+        module_imports.push(ModuleImport::Module {
+            name: name.to_string(),
+            bmi: PathBuf::from(bmi),
+        });
+    }
+    for header_unit_arg in matches.values_of("import-header-unit").unwrap_or_default() {
+        let (header, bmi) = header_unit_arg
+            .split_once('=')
+            .expect("--import-header-unit should be of the form HEADER=BMI");
+        module_imports.push(ModuleImport::HeaderUnit {
+            header: header.to_string(),
+            bmi: PathBuf::from(bmi),
+        });
+    }
     let suppress_system_headers = matches.is_present("suppress-system-headers");
     let desired_number = matches
         .value_of("generate-exact")
@@ -222,19 +674,37 @@ fn main() {
     };
     let cpp_codegen_options = autocxx_engine::CppCodegenOptions {
         suppress_system_headers,
-        cxx_impl_annotations: get_option_string("cxx-impl-annotations", &matches),
-        path_to_cxx_h: get_option_string("cxx-h-path", &matches),
-        path_to_cxxgen_h: get_option_string("cxxgen-h-path", &matches),
+        cxx_impl_annotations: get_option_string("cxx-impl-annotations", matches),
+        path_to_cxx_h: get_option_string("cxx-h-path", matches),
+        path_to_cxxgen_h: get_option_string("cxxgen-h-path", matches),
         skip_cxx_gen: matches.is_present("skip-cxx-gen"),
         header_namer,
+        header_clang_args,
+        module_imports,
+        coverage: matches.is_present("coverage"),
+        sort_inclusions: matches.is_present("sort-includes"),
+        cpp_shards: matches
+            .value_of("cpp-shards")
+            .map(|s| s.parse::<usize>().unwrap()) // checked by validate_cpp_shards
+            .unwrap_or_default(),
+        bindgen_blocklist: matches
+            .values_of("bindgen-blocklist")
+            .unwrap_or_default()
+            .map(|s| s.to_string())
+            .collect(),
+        bindgen_callbacks: None,
     };
     // In future, we should provide an option to write a .d file here
-    // by passing a callback into the dep_recorder parameter here.
+    // using these dependencies too.
     // https://github.com/google/autocxx/issues/56
     parsed_file
-        .resolve_all(incs, &extra_clang_args, None, &cpp_codegen_options)
+        .resolve_all(
+            incs,
+            &extra_clang_args,
+            Some(Box::new(deps.clone())),
+            &cpp_codegen_options,
+        )
         .expect("Unable to resolve macro");
-    let outdir: PathBuf = matches.value_of_os("outdir").unwrap().into();
     if matches.is_present("gen-cpp") {
         let cpp = matches.value_of("cpp-extension").unwrap();
         let mut counter = 0usize;
@@ -244,20 +714,20 @@ fn main() {
                 .expect("Unable to generate header and C++ code");
             for pair in generations.0 {
                 let cppname = format!("gen{}.{}", counter, cpp);
-                write_to_file(&outdir, cppname, &pair.implementation.unwrap_or_default());
-                write_to_file(&outdir, pair.header_name, &pair.header);
+                write_to_file(outdir, cppname, &pair.implementation.unwrap_or_default());
+                write_to_file(outdir, pair.header_name, &pair.header);
                 counter += 1;
             }
         }
-        write_placeholders(&outdir, counter, desired_number, cpp);
+        write_placeholders(outdir, counter, desired_number, cpp);
     }
     drop(cpp_codegen_options);
-    write_placeholders(&outdir, header_counter.into_inner(), desired_number, "h");
+    write_placeholders(outdir, header_counter.into_inner(), desired_number, "h");
     if matches.is_present("gen-rs-complete") {
         let mut ts = TokenStream::new();
         parsed_file.to_tokens(&mut ts);
         write_to_file(
-            &outdir,
+            outdir,
             "gen.complete.rs".to_string(),
             ts.to_string().as_bytes(),
         );
@@ -272,11 +742,11 @@ fn main() {
             } else {
                 include_cxx.get_rs_filename()
             };
-            write_to_file(&outdir, fname, ts.to_string().as_bytes());
+            write_to_file(outdir, fname, ts.to_string().as_bytes());
             counter += 1;
         }
         if matches.is_present("fix-rs-include-name") {
-            write_placeholders(&outdir, counter, desired_number, "include.rs");
+            write_placeholders(outdir, counter, desired_number, "include.rs");
         }
     }
 }
@@ -6,7 +6,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::{convert::TryInto, fs::File, io::Write, path::Path};
+use std::{convert::TryInto, fs::File, io::Write, path::Path, time::Duration};
 
 use assert_cmd::Command;
 use tempdir::TempDir;
@@ -160,6 +160,290 @@ fn test_skip_cxx_gen() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_cpp_shards_rejects_bad_values() -> Result<(), Box<dyn std::error::Error>> {
+    for (value, message) in [("0", "at least one shard"), ("two", "isn't a number")] {
+        let tmp_dir = TempDir::new("example")?;
+        write_to_file(tmp_dir.path(), "main.rs", MAIN_RS.as_bytes());
+        let output = Command::cargo_bin("autocxx-gen")?
+            .arg(tmp_dir.path().join("main.rs"))
+            .arg("--outdir")
+            .arg(tmp_dir.path().to_str().unwrap())
+            .arg("--gen-cpp")
+            .arg("--cpp-shards")
+            .arg(value)
+            .output()?;
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains(message));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_split_ns() -> Result<(), Box<dyn std::error::Error>> {
+    do_split_ns_test("mylib_core_sys")
+}
+
+#[test]
+fn test_split_ns_hyphenated_crate() -> Result<(), Box<dyn std::error::Error>> {
+    do_split_ns_test("mylib-core-sys")
+}
+
+#[test]
+fn test_split_ns_rejects_bad_values() -> Result<(), Box<dyn std::error::Error>> {
+    for (value, message) in [
+        ("mylib::core", "isn't of the form NAMESPACE=CRATE"),
+        ("=mylib_core_sys", "doesn't give a namespace"),
+        ("mylib::core=1core", "isn't a valid crate name"),
+        ("mylib::core=my.lib", "isn't a valid crate name"),
+    ] {
+        let tmp_dir = TempDir::new("example")?;
+        write_to_file(tmp_dir.path(), "main.rs", MAIN_RS.as_bytes());
+        let output = Command::cargo_bin("autocxx-gen")?
+            .arg(tmp_dir.path().join("main.rs"))
+            .arg("--outdir")
+            .arg(tmp_dir.path().to_str().unwrap())
+            .arg("--gen-cpp")
+            .arg("--split-ns")
+            .arg(value)
+            .output()?;
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains(message));
+    }
+    Ok(())
+}
+
+fn do_split_ns_test(core_crate: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = TempDir::new("example")?;
+    let code_dir = tmp_dir.path().join("code");
+    std::fs::create_dir(&code_dir).unwrap();
+    write_to_file(
+        &code_dir,
+        "input.h",
+        br#"
+        #pragma once
+        namespace mylib {
+        namespace core {
+        struct Widget {
+            int size;
+        };
+        inline int widget_size(const Widget& w) { return w.size; }
+        }
+        namespace gui {
+        inline void draw(const core::Widget&) {}
+        }
+        }
+        "#,
+    );
+    write_to_file(
+        &code_dir,
+        "main.rs",
+        br#"
+        use autocxx::prelude::*;
+        include_cpp! {
+            #include "input.h"
+            safety!(unsafe_ffi)
+            generate_ns!("mylib")
+        }
+        fn main() {}
+        "#,
+    );
+    let mut cmd = Command::cargo_bin("autocxx-gen")?;
+    cmd.arg("--inc")
+        .arg(code_dir.to_str().unwrap())
+        .arg(code_dir.join("main.rs"))
+        .arg("--outdir")
+        .arg(tmp_dir.path().to_str().unwrap())
+        .arg("--gen-cpp")
+        .arg("--gen-rs-include")
+        .arg("--fix-rs-include-name")
+        .arg("--split-ns")
+        .arg(format!("mylib::core={}", core_crate))
+        .arg("--split-ns")
+        .arg("mylib::gui=mylib_gui_sys");
+    cmd.assert().success();
+    assert_contentful(&tmp_dir, &format!("{}/gen0.h", core_crate));
+    assert_contains(
+        &tmp_dir,
+        &format!("{}/gen0.include.rs", core_crate),
+        "widget_size",
+    );
+    assert_contentful(&tmp_dir, "mylib_gui_sys/gen0.h");
+    assert_contains(&tmp_dir, "mylib_gui_sys/gen0.include.rs", "draw");
+    assert_contains(
+        &tmp_dir,
+        "mylib_gui_sys/gen0.include.rs",
+        "mylib_core_sys :: ffi :: mylib :: core :: Widget",
+    );
+    Ok(())
+}
+
+#[test]
+fn test_diff() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = TempDir::new("example")?;
+    let old_dir = tmp_dir.path().join("old");
+    let new_dir = tmp_dir.path().join("new");
+    std::fs::create_dir(&old_dir).unwrap();
+    std::fs::create_dir(&new_dir).unwrap();
+    write_to_file(
+        &old_dir,
+        "input.h",
+        br#"
+        #pragma once
+        #include <cstdint>
+        inline uint32_t unchanged() { return 1; }
+        inline uint32_t widened(uint32_t a) { return a; }
+        inline void dropped() {}
+        "#,
+    );
+    write_to_file(
+        &new_dir,
+        "input.h",
+        br#"
+        #pragma once
+        #include <cstdint>
+        inline uint32_t unchanged() { return 1; }
+        inline uint64_t widened(uint64_t a) { return a; }
+        inline void introduced() {}
+        "#,
+    );
+    write_to_file(
+        tmp_dir.path(),
+        "main.rs",
+        br#"
+        use autocxx::prelude::*;
+        include_cpp! {
+            #include "input.h"
+            safety!(unsafe_ffi)
+            generate!("unchanged")
+            generate!("widened")
+            generate!("dropped")
+            generate!("introduced")
+        }
+        fn main() {}
+        "#,
+    );
+    let mut cmd = Command::cargo_bin("autocxx-gen")?;
+    cmd.arg("diff")
+        .arg(tmp_dir.path().join("main.rs"))
+        .arg("--old-inc")
+        .arg(old_dir.to_str().unwrap())
+        .arg("--new-inc")
+        .arg(new_dir.to_str().unwrap());
+    let output = cmd.output()?;
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("+ Function introduced"));
+    assert!(stdout.contains("- Function dropped"));
+    assert!(stdout.contains("~ widened"));
+    assert!(!stdout.contains("unchanged"));
+    Ok(())
+}
+
+#[test]
+fn test_explain() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = TempDir::new("example")?;
+    let inc_dir = tmp_dir.path().join("include");
+    std::fs::create_dir(&inc_dir).unwrap();
+    write_to_file(
+        &inc_dir,
+        "input.h",
+        br#"
+        #pragma once
+        #include "detail.h"
+        inline uint32_t found() { return 1; }
+        "#,
+    );
+    write_to_file(
+        &inc_dir,
+        "detail.h",
+        br#"
+        #pragma once
+        #include <cstdint>
+        inline uint32_t&& unwanted(uint32_t& a) { return static_cast<uint32_t&&>(a); }
+        "#,
+    );
+    write_to_file(
+        tmp_dir.path(),
+        "main.rs",
+        br#"
+        use autocxx::prelude::*;
+        include_cpp! {
+            #include "input.h"
+            safety!(unsafe_ffi)
+            generate!("found")
+            generate!("unwanted")
+        }
+        fn main() {}
+        "#,
+    );
+    let mut cmd = Command::cargo_bin("autocxx-gen")?;
+    cmd.arg("explain")
+        .arg(tmp_dir.path().join("main.rs"))
+        .arg("--inc")
+        .arg(inc_dir.to_str().unwrap())
+        .arg("--")
+        .arg("-DEXPLAIN_TEST");
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains(&format!("-I{}", inc_dir.to_str().unwrap())));
+    assert!(stdout.contains("-DEXPLAIN_TEST"));
+    assert!(stdout.contains("detail.h"));
+    assert!(stdout.contains("Function found"));
+    assert!(stdout.contains("Ignored unwanted"));
+    Ok(())
+}
+
+#[test]
+fn test_watch() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = TempDir::new("example")?;
+    let demo_code_dir = tmp_dir.path().join("demo");
+    std::fs::create_dir(&demo_code_dir).unwrap();
+    write_to_file(&demo_code_dir, "input.h", INPUT_H.as_bytes());
+    write_to_file(&demo_code_dir, "main.rs", MAIN_RS.as_bytes());
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("autocxx-gen"))
+        .arg("--inc")
+        .arg(demo_code_dir.to_str().unwrap())
+        .arg(demo_code_dir.join("main.rs"))
+        .arg("--outdir")
+        .arg(tmp_dir.path().to_str().unwrap())
+        .arg("--gen-rs-include")
+        .arg("--watch")
+        .spawn()?;
+    let wait_for_contents = |pattern: &str| {
+        for _ in 0..240 {
+            if let Ok(content) = std::fs::read_to_string(tmp_dir.path().join("gen0.include.rs")) {
+                if content.contains(pattern) {
+                    return true;
+                }
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+        false
+    };
+    let first_generated = wait_for_contents("add_a_horn");
+    // Make sure the modification time will differ even on filesystems
+    // which only record it to the second.
+    std::thread::sleep(Duration::from_secs(1));
+    write_to_file(
+        &demo_code_dir,
+        "input.h",
+        INPUT_H
+            .replace(
+                "void add_a_horn();",
+                "void add_a_horn();\n    void remove_a_horn() {}",
+            )
+            .as_bytes(),
+    );
+    let regenerated = first_generated && wait_for_contents("remove_a_horn");
+    child.kill()?;
+    child.wait()?;
+    assert!(first_generated, "Bindings were never generated");
+    assert!(regenerated, "Bindings weren't regenerated after a change");
+    Ok(())
+}
+
 fn write_to_file(dir: &Path, filename: &str, content: &[u8]) {
     let path = dir.join(filename);
     let mut f = File::create(&path).expect("Unable to create file");
@@ -6,7 +6,10 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use autocxx_engine::Builder;
+use autocxx_engine::{
+    bindgen_callbacks::{IntKind, ParseCallbacks},
+    Builder,
+};
 
 use autocxx_integration_tests::{BuilderModifier, BuilderModifierFns, TestBuilderContext};
 
@@ -66,3 +69,92 @@ impl BuilderModifierFns for SkipCxxGen {
         builder.skip_cxx_gen(true)
     }
 }
+
+pub(crate) struct EnableCudaCompatibility;
+
+impl BuilderModifierFns for EnableCudaCompatibility {
+    fn modify_autocxx_builder<'a>(
+        &self,
+        builder: Builder<'a, TestBuilderContext>,
+    ) -> Builder<'a, TestBuilderContext> {
+        builder.cuda_compatibility(true)
+    }
+}
+
+pub(crate) struct SetCxxImplAnnotations(pub(crate) &'static str);
+
+impl BuilderModifierFns for SetCxxImplAnnotations {
+    fn modify_autocxx_builder<'a>(
+        &self,
+        builder: Builder<'a, TestBuilderContext>,
+    ) -> Builder<'a, TestBuilderContext> {
+        builder.cxx_impl_annotations(Some(self.0.to_string()))
+    }
+}
+
+pub(crate) struct SetHeaderClangArgs(pub(crate) &'static str, pub(crate) &'static [&'static str]);
+
+impl BuilderModifierFns for SetHeaderClangArgs {
+    fn modify_autocxx_builder<'a>(
+        &self,
+        builder: Builder<'a, TestBuilderContext>,
+    ) -> Builder<'a, TestBuilderContext> {
+        builder.header_clang_args(self.0, self.1)
+    }
+}
+
+/// Generates the C++ as for coverage, without the compiler flags, which
+/// need clang.
+pub(crate) struct EnableCoverageCodegen;
+
+impl BuilderModifierFns for EnableCoverageCodegen {
+    fn modify_autocxx_builder<'a>(
+        &self,
+        builder: Builder<'a, TestBuilderContext>,
+    ) -> Builder<'a, TestBuilderContext> {
+        builder.cpp_codegen_options(|options| options.coverage = true)
+    }
+}
+
+pub(crate) struct SetCppShards(pub(crate) usize);
+
+impl BuilderModifierFns for SetCppShards {
+    fn modify_autocxx_builder<'a>(
+        &self,
+        builder: Builder<'a, TestBuilderContext>,
+    ) -> Builder<'a, TestBuilderContext> {
+        builder.cpp_shards(self.0)
+    }
+}
+
+pub(crate) struct SetBindgenBlocklist(pub(crate) &'static str);
+
+impl BuilderModifierFns for SetBindgenBlocklist {
+    fn modify_autocxx_builder<'a>(
+        &self,
+        builder: Builder<'a, TestBuilderContext>,
+    ) -> Builder<'a, TestBuilderContext> {
+        builder.bindgen_blocklist(self.0)
+    }
+}
+
+/// bindgen callbacks which make the named integer macros `i64`s.
+#[derive(Debug)]
+struct I64MacroCallbacks(&'static [&'static str]);
+
+impl ParseCallbacks for I64MacroCallbacks {
+    fn int_macro(&self, name: &str, _value: i64) -> Option<IntKind> {
+        self.0.contains(&name).then(|| IntKind::I64)
+    }
+}
+
+pub(crate) struct SetI64Macros(pub(crate) &'static [&'static str]);
+
+impl BuilderModifierFns for SetI64Macros {
+    fn modify_autocxx_builder<'a>(
+        &self,
+        builder: Builder<'a, TestBuilderContext>,
+    ) -> Builder<'a, TestBuilderContext> {
+        builder.bindgen_callbacks(I64MacroCallbacks(self.0))
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Extra known types have to be registered before any bindings are
+//! generated in the process, just as a build script would, so these tests
+//! can't live alongside the other integration tests.
+
+use autocxx_engine::{register_known_type_extension, ExternalTypeMapping, KnownTypeExtension};
+use autocxx_integration_tests::run_test_ex;
+use indoc::indoc;
+use quote::quote;
+
+struct Extension;
+
+impl KnownTypeExtension for Extension {
+    fn external_types(&self) -> Vec<ExternalTypeMapping> {
+        vec![ExternalTypeMapping {
+            cpp_name: "mylib::Duration".into(),
+            rust_path: "crate::Duration".into(),
+            trivial: true,
+        }]
+    }
+}
+
+#[test]
+fn test_external_type_in_signature() {
+    register_known_type_extension(&Extension).unwrap();
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace mylib {
+        struct Duration {
+            uint64_t nanos;
+        };
+        }
+        inline mylib::Duration twice(mylib::Duration d) {
+            return mylib::Duration{d.nanos * 2};
+        }
+        inline uint64_t nanos_of(const mylib::Duration& d) { return d.nanos; }
+    "};
+    let rs = quote! {
+        let d = ffi::twice(Duration { nanos: 3 });
+        assert_eq!(d.nanos, 6);
+        assert_eq!(ffi::nanos_of(&d), 6);
+    };
+    let extra_rust = quote! {
+        #[repr(C)]
+        pub struct Duration {
+            pub nanos: u64,
+        }
+
+        unsafe impl cxx::ExternType for Duration {
+            type Id = cxx::type_id!("mylib::Duration");
+            type Kind = cxx::kind::Trivial;
+        }
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("twice")
+            generate!("nanos_of")
+        },
+        None,
+        None,
+        Some(extra_rust),
+    );
+}
@@ -8,12 +8,17 @@
 
 use crate::{
     builder_modifiers::{
-        make_clang_arg_adder, EnableAutodiscover, SetSuppressSystemHeaders, SkipCxxGen,
+        make_clang_arg_adder, EnableAutodiscover, EnableCoverageCodegen, EnableCudaCompatibility,
+        SetBindgenBlocklist, SetCppShards, SetCxxImplAnnotations, SetHeaderClangArgs, SetI64Macros,
+        SetSuppressSystemHeaders, SkipCxxGen,
     },
     code_checkers::{
         make_error_finder, make_string_finder, CppCounter, CppMatcher, NoSystemHeadersChecker,
     },
 };
+use autocxx_engine::{
+    fuzz_header, CppCodegenOptions, FuzzOutcome, GeneratedItemKind, IncludeCppEngine,
+};
 use autocxx_integration_tests::{
     directives_from_lists, do_run_test, do_run_test_manual, run_test, run_test_ex,
     run_test_expect_fail, run_test_expect_fail_ex, TestError,
@@ -9874,6 +9879,3793 @@ fn test_tricky_destructors() {
     );
 }
 
+#[test]
+fn test_engine_generate_bindings() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct A {
+            uint32_t a;
+        };
+        inline uint32_t give_int() {
+            return 5;
+        }
+    "};
+    let tdir = tempfile::tempdir().unwrap();
+    std::fs::write(tdir.path().join("input.h"), hdr).unwrap();
+    let hexathorpe = Token![#](Span::call_site());
+    let mut engine = IncludeCppEngine::new_from_tokens(quote! {
+        #hexathorpe include "input.h"
+        safety!(unsafe_ffi)
+        generate!("give_int")
+        generate_pod!("A")
+    })
+    .unwrap();
+    let bindings = engine
+        .generate_bindings(
+            vec![tdir.path().to_path_buf()],
+            &[],
+            None,
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    assert!(bindings.rs.contains("give_int"));
+    assert!(!bindings.cpp.0.is_empty());
+    let find_item = |name: &str| {
+        bindings
+            .items
+            .iter()
+            .find(|item| item.rust_name == name)
+            .unwrap()
+    };
+    assert_eq!(find_item("give_int").kind, GeneratedItemKind::Function);
+    assert_eq!(find_item("A").kind, GeneratedItemKind::PodType);
+}
+
+#[test]
+fn test_naming_policy_snake_case() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class A {
+        public:
+            A() {}
+            uint32_t getValue() const { return 4; }
+            uint32_t HTTPStatus() const { return 200; }
+        };
+    "};
+    let rs = quote! {
+        let a = ffi::A::make_unique();
+        assert_eq!(a.get_value(), 4);
+        assert_eq!(a.http_status(), 200);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            naming_policy!(snake_case)
+            generate!("A")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_strip_prefix() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct mylib_ctx {
+            uint32_t a;
+        };
+        inline uint32_t mylib_get(const mylib_ctx& ctx) {
+            return ctx.a;
+        }
+    "};
+    let rs = quote! {
+        let ctx = ffi::ctx { a: 3 };
+        assert_eq!(ffi::get(&ctx), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            strip_prefix!("mylib_")
+            generate_pod!("mylib_ctx")
+            generate!("mylib_get")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_strip_prefix_collision() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t mylib_get() {
+            return 3;
+        }
+        inline uint32_t get() {
+            return 4;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get(), 4);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            strip_prefix!("mylib_")
+            generate!("mylib_get")
+            generate!("get")
+        },
+        None,
+        Some(make_error_finder("mylib_get")),
+        None,
+    );
+}
+
+#[test]
+fn test_generate_cfg() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #ifdef _WIN32
+        inline uint32_t win_only() {
+            return 3;
+        }
+        #else
+        inline uint32_t not_win() {
+            return 4;
+        }
+        #endif
+    "};
+    let rs = quote! {
+        #[cfg(not(windows))]
+        assert_eq!(ffi::not_win(), 4);
+        #[cfg(windows)]
+        assert_eq!(ffi::win_only(), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_cfg!("win_only", r#"cfg(windows)"#)
+            generate_cfg!("not_win", r#"cfg(not(windows))"#)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_generate_cfg_off() {
+    // The bridge declaration and C++ are still generated and built for an
+    // item whose condition is false, but its Rust API isn't available.
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t hidden() {
+            return 3;
+        }
+        inline uint32_t shown() {
+            return 4;
+        }
+    "};
+    let directives = quote! {
+        generate!("shown")
+        generate_cfg!("hidden", r#"cfg(any())"#)
+    };
+    let rs = quote! {
+        assert_eq!(ffi::shown(), 4);
+    };
+    run_test_ex("", hdr, rs, directives.clone(), None, None, None);
+    let rs = quote! {
+        ffi::hidden();
+    };
+    run_test_expect_fail_ex("", hdr, rs, directives, None, None, None);
+}
+
+#[test]
+fn test_out_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace ns {
+            struct Point {
+                uint32_t x;
+                uint32_t y;
+            };
+            inline bool get(uint32_t which, Point* out) {
+                if (which == 0) {
+                    return false;
+                }
+                out->x = which;
+                out->y = which * 2;
+                return true;
+            }
+            inline void get_x(uint32_t* out) {
+                *out = 7;
+            }
+            inline uint32_t get_y(uint32_t* out) {
+                *out = 8;
+                return 9;
+            }
+        }
+    "};
+    let rs = quote! {
+        assert!(ffi::ns::get(0).is_none());
+        let pt = ffi::ns::get(3).unwrap();
+        assert_eq!(pt.x, 3);
+        assert_eq!(pt.y, 6);
+        assert_eq!(ffi::ns::get_x(), 7);
+        assert_eq!(ffi::ns::get_y(), (9, 8));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("ns::Point")
+            generate!("ns::get")
+            generate!("ns::get_x")
+            generate!("ns::get_y")
+            out_param!("ns::get", 1)
+            out_param!("ns::get_x", 0)
+            out_param!("ns::get_y", 0)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_out_param_method() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Counter {
+        public:
+            Counter() : count(4) {}
+            bool current(uint32_t* out) const {
+                *out = count;
+                return true;
+            }
+        private:
+            uint32_t count;
+        };
+    "};
+    let rs = quote! {
+        let c = ffi::Counter::make_unique();
+        assert_eq!(c.current(), Some(4));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Counter")
+            out_param!("Counter::current", 0)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_error_code() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        enum class Status {
+            Ok,
+            NotFound,
+        };
+        inline Status check(bool present) {
+            return present ? Status::Ok : Status::NotFound;
+        }
+        inline Status lookup(uint32_t key, uint32_t* out) {
+            if (key == 0) {
+                return Status::NotFound;
+            }
+            *out = key * 10;
+            return Status::Ok;
+        }
+    "};
+    let rs = quote! {
+        assert!(ffi::check(true).is_ok());
+        assert!(matches!(ffi::check(false), Err(ffi::Status::NotFound)));
+        assert_eq!(ffi::lookup(2).ok(), Some(20));
+        assert!(ffi::lookup(0).is_err());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Status")
+            generate!("check")
+            generate!("lookup")
+            error_code!("Status", "Ok")
+            out_param!("lookup", 1)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_pair_accessors() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Sheep {
+        public:
+            Sheep() : wool(3) {}
+            uint32_t getWool() const { return wool; }
+            void setWool(uint32_t w) { wool = w; }
+            uint32_t getLegs() const { return 4; }
+        private:
+            uint32_t wool;
+        };
+    "};
+    let rs = quote! {
+        let mut sheep = ffi::Sheep::make_unique();
+        assert_eq!(sheep.wool(), 3);
+        sheep.pin_mut().set_wool(5);
+        assert_eq!(sheep.wool(), 5);
+        // No setter, so this isn't renamed.
+        assert_eq!(sheep.getLegs(), 4);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Sheep")
+            pair_accessors!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_pod_default() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Config {
+            Config() : retries(3), verbose(false) {}
+            uint32_t retries;
+            bool verbose;
+        };
+        struct Plain {
+            uint32_t a;
+        };
+    "};
+    let rs = quote! {
+        let c = ffi::Config::default();
+        assert_eq!(c.retries, 3);
+        assert!(!c.verbose);
+        let p = ffi::Plain::default();
+        assert_eq!(p.a, 0);
+    };
+    run_test("", hdr, rs, &[], &["Config", "Plain"]);
+}
+
+#[test]
+fn test_constructor_naming_by_parameters() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Rect {
+            Rect() : width(1), height(1) {}
+            Rect(uint32_t side) : width(side), height(side) {}
+            Rect(uint32_t width, uint32_t height) : width(width), height(height) {}
+            uint32_t width;
+            uint32_t height;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::Rect::make_unique().width, 1);
+        assert_eq!(ffi::Rect::make_unique_with_side(2).height, 2);
+        let r = ffi::Rect::make_unique_with_width_and_height(3, 4);
+        assert_eq!(r.width, 3);
+        assert_eq!(r.height, 4);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Rect")
+            constructor_naming!(by_parameters)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_assignment_operators() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct A {
+            A() : a(0) {}
+            A(const A& other) : a(other.a) {}
+            A(A&& other) : a(other.a) { other.a = 0; }
+            A& operator=(const A& other) { a = other.a; return *this; }
+            A& operator=(A&& other) { a = other.a; other.a = 0; return *this; }
+            void set(uint32_t val) { a = val; }
+            uint32_t get() const { return a; }
+            uint32_t a;
+            std::string so_we_are_non_trivial;
+        };
+    "};
+    let rs = quote! {
+        let mut a = ffi::A::make_unique();
+        let mut b = ffi::A::make_unique();
+        b.pin_mut().set(42);
+        a.pin_mut().assign(&b);
+        assert_eq!(a.get(), 42);
+        assert_eq!(b.get(), 42);
+        moveit! {
+            let mut c = ffi::A::new();
+        }
+        c.as_mut().set(17);
+        a.pin_mut().assign_move(c);
+        assert_eq!(a.get(), 17);
+    };
+    run_test("", hdr, rs, &["A"], &[]);
+}
+
+#[test]
+fn test_bitflags() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            enum Flags : uint32_t {
+                FLAG_A = 1,
+                FLAG_B = 2,
+                FLAG_C = 4,
+            };
+        }
+        inline uint32_t flags_value(a::Flags flags) {
+            return static_cast<uint32_t>(flags);
+        }
+        inline a::Flags all_flags() {
+            return static_cast<a::Flags>(a::FLAG_A | a::FLAG_B | a::FLAG_C);
+        }
+    "};
+    let rs = quote! {
+        let mut flags = ffi::a::Flags::FLAG_A | ffi::a::Flags::FLAG_C;
+        assert_eq!(ffi::flags_value(flags), 5);
+        assert!(flags.contains(ffi::a::Flags::FLAG_C));
+        assert!(!flags.contains(ffi::a::Flags::FLAG_B));
+        flags &= ffi::a::Flags::FLAG_C;
+        assert_eq!(flags, ffi::a::Flags::FLAG_C);
+        assert_eq!(ffi::all_flags().0, 7);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("a::Flags")
+            generate!("flags_value")
+            generate!("all_flags")
+            bitflags!("a::Flags")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_pod_layout_assertions() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            uint32_t x;
+            uint64_t y;
+        };
+        inline uint64_t sum(Point p) { return p.x + p.y; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::sum(ffi::Point { x: 1, y: 2 }), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Point")
+            generate!("sum")
+        },
+        None,
+        Some(Box::new(CppMatcher::new(
+            &[
+                "static_assert(sizeof(Point) == 16",
+                "static_assert(alignof(Point) == 8",
+                "static_assert(offsetof(Point, x) == 0",
+                "static_assert(offsetof(Point, y) == 8",
+            ],
+            &[],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_instrument_calls() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            inline uint32_t double_it(uint32_t x) { return x * 2; }
+        }
+        struct Counter {
+            Counter() : count(0) {}
+            void increment() { count++; }
+            uint32_t get() const { return count; }
+            uint32_t count;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::a::double_it(21), 42);
+        let mut c = ffi::Counter::make_unique();
+        c.pin_mut().increment();
+        assert_eq!(c.get(), 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("a::double_it")
+            generate!("Counter")
+            instrument_calls!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_allowlist_file() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+        inline uint32_t sum(Point p) { return p.x + p.y; }
+        inline uint32_t not_allowed() { return 3; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::sum(ffi::Point { x: 1, y: 2 }), 3);
+    };
+    let allowlist_dir = tempfile::tempdir().unwrap();
+    let allowlist_path = allowlist_dir.path().join("autocxx_allow.toml");
+    std::fs::write(
+        &allowlist_path,
+        indoc! {r#"
+            generate = [ "sum" ]
+            generate_pod = [ "Point" ]
+        "#},
+    )
+    .unwrap();
+    let allowlist_path = allowlist_path.to_str().unwrap();
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            allowlist_file!(#allowlist_path)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_block_ns() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            namespace internal {
+                struct Detail {
+                    uint32_t a;
+                };
+            }
+            inline uint32_t get_value() { return 3; }
+            inline uint32_t get_detail(internal::Detail d) { return d.a; }
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::a::get_value(), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_ns!("a")
+            block_ns!("a::internal")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_cuda_compatibility() {
+    // With generate_all!, bindings for the kernel would fail to link,
+    // since it's never defined.
+    let hdr = indoc! {"
+        __host__ __device__ inline int add(int a, int b) {
+            return a + b;
+        }
+        __global__ void add_kernel(int* a, const int* b);
+        __device__ __forceinline__ int twice(int a) {
+            return a * 2;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::add(1, 2), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_all!()
+        },
+        Some(Box::new(EnableCudaCompatibility)),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_subclass_cxx_impl_annotations() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    #define AUTOCXX_TEST_EXPORT
+
+    class Observer {
+    public:
+        Observer() {}
+        virtual void foo() = 0;
+        virtual ~Observer() {}
+    };
+    inline void bar() {}
+    "};
+    run_test_ex(
+        "",
+        hdr,
+        quote! {
+            MyObserver::new_rust_owned(MyObserver { a: 3, cpp_peer: Default::default() });
+        },
+        quote! {
+            generate!("bar")
+            subclass!("Observer",MyObserver)
+        },
+        Some(Box::new(SetCxxImplAnnotations("AUTOCXX_TEST_EXPORT"))),
+        Some(Box::new(CppMatcher::new(
+            &["class AUTOCXX_TEST_EXPORT MyObserverCpp"],
+            &[],
+        ))),
+        Some(quote! {
+            use autocxx::subclass::CppSubclass;
+            use ffi::Observer_methods;
+            #[autocxx::subclass::subclass]
+            pub struct MyObserver {
+                a: u32
+            }
+            impl Observer_methods for MyObserver {
+                fn foo(&mut self) {
+                }
+            }
+        }),
+    );
+}
+
+#[test]
+fn test_bridge_shards() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        #include <string>
+        namespace a {
+            struct Counter {
+                uint32_t count;
+                void increment() { count++; }
+                uint32_t get() const { return count; }
+            };
+            inline std::unique_ptr<Counter> make_counter() {
+                return std::make_unique<Counter>(Counter { 0 });
+            }
+            inline uint32_t add(uint32_t a, uint32_t b) { return a + b; }
+            inline uint32_t sub(uint32_t a, uint32_t b) { return a - b; }
+            inline uint32_t mul(uint32_t a, uint32_t b) { return a * b; }
+            inline uint32_t count_of(const Counter& c) { return c.count; }
+            inline size_t len(const std::string& s) { return s.size(); }
+        }
+    "};
+    let rs = quote! {
+        let mut counter = ffi::a::make_counter();
+        counter.pin_mut().increment();
+        assert_eq!(counter.get(), 1);
+        assert_eq!(ffi::a::count_of(&counter), 1);
+        assert_eq!(ffi::a::add(2, 3), 5);
+        assert_eq!(ffi::a::sub(3, 2), 1);
+        assert_eq!(ffi::a::mul(2, 3), 6);
+        assert_eq!(ffi::a::len("hello"), 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_ns!("a")
+            bridge_shards!(3)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_opaque_typedef_dependency() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        typedef uint32_t (*Transform)(uint32_t);
+        inline uint32_t twice(uint32_t a) { return a * 2; }
+        class Widget {
+        public:
+            Widget() : t(twice) {}
+            const Transform& get_transform() const { return t; }
+            uint32_t apply(const Transform& transform, uint32_t v) const {
+                return transform(v);
+            }
+        private:
+            Transform t;
+        };
+    "};
+    let rs = quote! {
+        let w = ffi::Widget::make_unique();
+        assert_eq!(w.apply(w.get_transform(), 3), 6);
+    };
+    run_test("", hdr, rs, &["Widget"], &[]);
+}
+
+#[test]
+fn test_typedef_of_blocked_type_stays_blocked() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Secret {
+            uint32_t a;
+        };
+        typedef Secret Alias;
+        inline uint32_t read_secret(const Alias& s) { return s.a; }
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Alias")
+            generate!("read_secret")
+            block!("Secret")
+            safety!(unsafe_ffi)
+        },
+        None,
+        Some(make_error_finder("Alias")),
+        None,
+    );
+}
+
+#[test]
+fn test_header_clang_args() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    #ifdef SPECIAL_BUILD
+    inline uint32_t special() { return SPECIAL_BUILD; }
+    #endif
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::special(), 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("special")
+        },
+        Some(Box::new(SetHeaderClangArgs(
+            "input.h",
+            &["-DSPECIAL_BUILD=5"],
+        ))),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_generate_std_type() {
+    let hdr = indoc! {"
+    #include <cstdint>
+    #include <memory>
+    #include <mutex>
+    struct Guarded {
+        Guarded() {}
+        uint32_t get() const { return 3; }
+    };
+    inline std::unique_ptr<std::mutex> make_mutex() { return std::make_unique<std::mutex>(); }
+    "};
+    let rs = quote! {
+        let mut m = ffi::make_mutex();
+        assert!(m.pin_mut().try_lock());
+        m.pin_mut().unlock();
+        let g = ffi::Guarded::make_unique();
+        assert_eq!(g.get(), 3);
+    };
+    run_test("", hdr, rs, &["std::mutex", "make_mutex", "Guarded"], &[]);
+}
+
+#[test]
+fn test_take_platform_string() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cstring>
+        #include <cwchar>
+        #ifdef _WIN32
+        inline uint32_t path_length(const wchar_t* path) { return wcslen(path); }
+        #else
+        inline uint32_t path_length(const char* path) { return strlen(path); }
+        #endif
+    "};
+    let rs = quote! {
+        let path = autocxx::CppOsString::new(std::path::Path::new("foo/bar.txt")).unwrap();
+        assert_eq!(path.len(), 11);
+        assert_eq!(unsafe { ffi::path_length(path.as_ptr().cast()) }, 11);
+        assert!(autocxx::CppOsString::new("a\0b").is_err());
+    };
+    run_test("", hdr, rs, &["path_length"], &[]);
+}
+
+#[test]
+fn test_c_string_params() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cstring>
+        inline uint32_t c_strlen(const char* s) { return strlen(s); }
+        class Greeter {
+        public:
+            Greeter() {}
+            uint32_t count_matching(const char* a, const char* b) const {
+                return strcmp(a, b) == 0 ? 2 : 0;
+            }
+        };
+    "};
+    let rs = quote! {
+        let s = std::ffi::CString::new("hello").unwrap();
+        assert_eq!(ffi::c_strlen(&s), 5);
+        let g = ffi::Greeter::make_unique();
+        assert_eq!(g.count_matching(&s, &s), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("c_strlen")
+            generate!("Greeter")
+            c_string_params!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_slice_params() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cstddef>
+        inline uint32_t sum(const uint8_t* data, size_t len) {
+            uint32_t total = 0;
+            for (size_t i = 0; i < len; i++) {
+                total += data[i];
+            }
+            return total;
+        }
+        inline void fill(int count, uint32_t* out, uint32_t val) {
+            for (int i = 0; i < count; i++) {
+                out[i] = val;
+            }
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::sum(&[1, 2, 3]), 6);
+        let mut buf = [0u32; 4];
+        ffi::fill(&mut buf, 7);
+        assert_eq!(buf, [7; 4]);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("sum")
+            generate!("fill")
+            slice_params!("sum", 0, 1)
+            slice_params!("fill", 1, 0)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_string_returns() {
+    let hdr = indoc! {"
+        #include <string>
+        inline std::string get_name() { return \"Bob\"; }
+        inline std::string get_other_name() { return \"Alice\"; }
+        class Person {
+        public:
+            Person() {}
+            std::string describe() const { return \"tall\"; }
+        };
+    "};
+    let rs = quote! {
+        let name: String = ffi::get_name();
+        assert_eq!(name, "Bob");
+        assert_eq!(ffi::get_other_name().to_str().unwrap(), "Alice");
+        let p = ffi::Person::make_unique();
+        let desc: String = p.describe();
+        assert_eq!(desc, "tall");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("get_name")
+            generate!("get_other_name")
+            generate!("Person")
+            string_returns!("get_name", "Person::describe")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_observer_registration() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Listener {
+        public:
+            Listener() : calls(0) {}
+            uint32_t get_calls() const { return calls; }
+            uint32_t calls;
+        };
+        class Subject {
+        public:
+            Subject() : listener(nullptr) {}
+            void addListener(Listener* l) { listener = l; }
+            void removeListener(Listener* l) {
+                if (listener == l) {
+                    listener = nullptr;
+                }
+            }
+            void notify() {
+                if (listener) {
+                    listener->calls++;
+                }
+            }
+        private:
+            Listener* listener;
+        };
+    "};
+    let rs = quote! {
+        let mut subject = ffi::Subject::make_unique();
+        let mut listener = ffi::Listener::make_unique();
+        {
+            let mut registration = subject
+                .pin_mut()
+                .addListener_guarded(listener.pin_mut());
+            registration.subject().notify();
+            registration.subject().notify();
+        }
+        subject.pin_mut().notify();
+        assert_eq!(listener.get_calls(), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Subject")
+            generate!("Listener")
+            observer_registration!("Subject::addListener", "Subject::removeListener")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_qt_signal() {
+    // A very small imitation of the parts of Qt which we use.
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <functional>
+        #include <memory>
+        #include <vector>
+        class QMetaObject {
+        public:
+            struct Connection {
+                std::shared_ptr<bool> connected;
+            };
+        };
+        typedef std::vector<std::pair<std::shared_ptr<bool>, std::function<void(uint32_t)>>> Slots;
+        inline Slots& all_slots() {
+            static Slots slots;
+            return slots;
+        }
+        class QObject {
+        public:
+            template <typename Sender, typename Signal, typename F>
+            static QMetaObject::Connection connect(const Sender*, Signal, F f) {
+                auto connected = std::make_shared<bool>(true);
+                all_slots().push_back(std::make_pair(connected, std::function<void(uint32_t)>(f)));
+                return QMetaObject::Connection { connected };
+            }
+            static bool disconnect(const QMetaObject::Connection& c) {
+                *c.connected = false;
+                return true;
+            }
+        };
+        class Counter : public QObject {
+        public:
+            Counter() : value(0) {}
+            const QMetaObject* metaObject() const { return nullptr; }
+            void setValue(uint32_t v) {
+                value = v;
+                valueChanged(v);
+            }
+            void valueChanged(uint32_t v) {
+                for (auto& slot : all_slots()) {
+                    if (*slot.first) {
+                        slot.second(v);
+                    }
+                }
+            }
+        private:
+            uint32_t value;
+        };
+    "};
+    let rs = quote! {
+        let total = std::rc::Rc::new(std::cell::Cell::new(0u32));
+        let mut counter = ffi::Counter::make_unique();
+        let t = total.clone();
+        let connection = counter
+            .pin_mut()
+            .connect_valueChanged(move |v| t.set(t.get() + v));
+        counter.pin_mut().setValue(3);
+        counter.pin_mut().setValue(4);
+        drop(connection);
+        counter.pin_mut().setValue(5);
+        assert_eq!(total.get(), 7);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Counter")
+            qt_mode!()
+            qt_signal!("Counter::valueChanged")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_absl_status() {
+    // A very small imitation of the parts of Abseil which we use.
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        #include <utility>
+        namespace absl {
+        enum class StatusCode : int { kOk = 0, kInvalidArgument = 3 };
+        class Status {
+        public:
+            Status() : code_(StatusCode::kOk) {}
+            Status(StatusCode code, std::string message)
+                : code_(code), message_(std::move(message)) {}
+            bool ok() const { return code_ == StatusCode::kOk; }
+            StatusCode code() const { return code_; }
+            const std::string& message() const { return message_; }
+        private:
+            StatusCode code_;
+            std::string message_;
+        };
+        template <typename T> class StatusOr {
+        public:
+            StatusOr(T value) : status_(), value_(std::move(value)) {}
+            StatusOr(Status status) : status_(std::move(status)), value_() {}
+            bool ok() const { return status_.ok(); }
+            const Status& status() const { return status_; }
+            T value() && { return std::move(value_); }
+        private:
+            Status status_;
+            T value_;
+        };
+        }
+        inline absl::Status check(uint32_t x) {
+            if (x > 10) {
+                return absl::Status(absl::StatusCode::kInvalidArgument, \"too big\");
+            }
+            return absl::Status();
+        }
+        inline absl::StatusOr<uint32_t> halve(uint32_t x) {
+            if (x % 2) {
+                return absl::Status(absl::StatusCode::kInvalidArgument, \"odd\");
+            }
+            return x / 2;
+        }
+    "};
+    let rs = quote! {
+        assert!(ffi::check(3).is_ok());
+        let err = ffi::check(11).unwrap_err();
+        assert_eq!(err.code(), 3);
+        assert_eq!(err.message(), "too big");
+        assert_eq!(ffi::halve(4).unwrap(), 2);
+        assert_eq!(ffi::halve(3).unwrap_err().message(), "odd");
+    };
+    run_test("", hdr, rs, &["check", "halve"], &[]);
+}
+
+#[test]
+fn test_absl_string_view_and_span() {
+    // A very small imitation of the parts of Abseil which we use.
+    let hdr = indoc! {"
+        #include <cstddef>
+        #include <cstdint>
+        #include <string>
+        namespace absl {
+        class string_view {
+        public:
+            string_view(const char* data, size_t len) : data_(data), len_(len) {}
+            const char* data() const { return data_; }
+            size_t size() const { return len_; }
+        private:
+            const char* data_;
+            size_t len_;
+        };
+        template <typename T> class Span {
+        public:
+            Span(T* data, size_t len) : data_(data), len_(len) {}
+            T* data() const { return data_; }
+            size_t size() const { return len_; }
+        private:
+            T* data_;
+            size_t len_;
+        };
+        }
+        inline uint32_t count_a(absl::string_view s) {
+            uint32_t count = 0;
+            for (size_t i = 0; i < s.size(); i++) {
+                if (s.data()[i] == 'a') {
+                    count++;
+                }
+            }
+            return count;
+        }
+        inline uint32_t sum(absl::Span<const uint32_t> values) {
+            uint32_t total = 0;
+            for (size_t i = 0; i < values.size(); i++) {
+                total += values.data()[i];
+            }
+            return total;
+        }
+        class Name {
+        public:
+            Name() : name(\"Bob\") {}
+            absl::string_view get() const { return absl::string_view(name.data(), name.size()); }
+        private:
+            std::string name;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::count_a("banana"), 3);
+        assert_eq!(ffi::sum(&[1, 2, 3]), 6);
+        let name = ffi::Name::make_unique();
+        assert_eq!(name.get(), "Bob");
+    };
+    run_test("", hdr, rs, &["count_a", "sum", "Name"], &[]);
+}
+
+#[test]
+fn test_result_type() {
+    // A very small imitation of tl::expected.
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <utility>
+        namespace tl {
+        template <typename E> struct unexpected {
+            E e;
+        };
+        template <typename T, typename E> class expected {
+        public:
+            expected(T value) : has_value_(true), value_(std::move(value)), error_() {}
+            expected(unexpected<E> error) : has_value_(false), value_(), error_(error.e) {}
+            bool has_value() const { return has_value_; }
+            T value() && { return std::move(value_); }
+            E error() && { return std::move(error_); }
+        private:
+            bool has_value_;
+            T value_;
+            E error_;
+        };
+        }
+        enum class ParseError { Empty, TooLong };
+        inline tl::expected<uint32_t, ParseError> parse_len(uint32_t len) {
+            if (len == 0) {
+                return tl::unexpected<ParseError>{ParseError::Empty};
+            }
+            if (len > 10) {
+                return tl::unexpected<ParseError>{ParseError::TooLong};
+            }
+            return len * 2;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::parse_len(3).unwrap(), 6);
+        assert!(ffi::parse_len(0).unwrap_err() == ffi::ParseError::Empty);
+        assert!(ffi::parse_len(11).unwrap_err() == ffi::ParseError::TooLong);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("parse_len")
+            generate!("ParseError")
+            result_type!("tl::expected", "value", "error")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_smart_pointer() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t& live_widgets() {
+            static uint32_t count = 0;
+            return count;
+        }
+        class Widget {
+        public:
+            Widget(uint32_t value) : value_(value), refs_(0) { live_widgets()++; }
+            ~Widget() { live_widgets()--; }
+            uint32_t get_value() const { return value_; }
+            void add_ref() { refs_++; }
+            void release() {
+                if (--refs_ == 0) {
+                    delete this;
+                }
+            }
+        private:
+            uint32_t value_;
+            uint32_t refs_;
+        };
+        template <typename T> class RefPtr {
+        public:
+            explicit RefPtr(T* ptr) : ptr_(ptr) { ptr_->add_ref(); }
+            RefPtr(const RefPtr& other) : ptr_(other.ptr_) { ptr_->add_ref(); }
+            RefPtr& operator=(const RefPtr&) = delete;
+            ~RefPtr() { ptr_->release(); }
+            T* get() const { return ptr_; }
+        private:
+            T* ptr_;
+        };
+        inline RefPtr<Widget> make_widget(uint32_t value) {
+            return RefPtr<Widget>(new Widget(value));
+        }
+        inline uint32_t widget_value(RefPtr<Widget> widget) {
+            return widget.get()->get_value();
+        }
+        inline uint32_t live_widget_count() {
+            return live_widgets();
+        }
+    "};
+    let rs = quote! {
+        let widget = ffi::make_widget(3);
+        assert_eq!(widget.get_value(), 3);
+        let other = widget.clone();
+        drop(widget);
+        assert_eq!(ffi::live_widget_count(), 1);
+        assert_eq!(ffi::widget_value(&other), 3);
+        drop(other);
+        assert_eq!(ffi::live_widget_count(), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Widget")
+            generate!("make_widget")
+            generate!("widget_value")
+            generate!("live_widget_count")
+            smart_pointer!("RefPtr", "get")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_dense_matrix() {
+    // A very small imitation of the parts of Eigen which we use.
+    let hdr = indoc! {"
+        #include <cstddef>
+        namespace Eigen {
+        template <typename S> class Matrix {
+        public:
+            typedef S Scalar;
+            Matrix() : rows_(0), cols_(0) {}
+            void resize(ptrdiff_t rows, ptrdiff_t cols) {
+                if (rows * cols <= 16) {
+                    rows_ = rows;
+                    cols_ = cols;
+                }
+            }
+            ptrdiff_t rows() const { return rows_; }
+            ptrdiff_t cols() const { return cols_; }
+            ptrdiff_t size() const { return rows_ * cols_; }
+            S* data() { return data_; }
+            const S* data() const { return data_; }
+            S operator()(ptrdiff_t row, ptrdiff_t col) const { return data_[col * rows_ + row]; }
+        private:
+            ptrdiff_t rows_;
+            ptrdiff_t cols_;
+            S data_[16];
+        };
+        typedef Matrix<double> MatrixXd;
+        }
+        inline double trace(const Eigen::MatrixXd& m) {
+            double total = 0;
+            for (ptrdiff_t i = 0; i < m.rows() && i < m.cols(); i++) {
+                total += m(i, i);
+            }
+            return total;
+        }
+        inline Eigen::MatrixXd identity(ptrdiff_t n) {
+            Eigen::MatrixXd m;
+            m.resize(n, n);
+            for (ptrdiff_t i = 0; i < n * n; i++) {
+                m.data()[i] = (i % (n + 1)) ? 0 : 1;
+            }
+            return m;
+        }
+    "};
+    let rs = quote! {
+        let m = ffi::Eigen::MatrixXd::from_slice(&[1.0, 2.0, 3.0, 4.0], 2, 2);
+        assert_eq!(m.dims(), (2, 2));
+        assert_eq!(ffi::trace(&m), 5.0);
+        let mut id = ffi::identity(3);
+        assert_eq!(id.as_slice(), &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+        id.pin_mut().as_mut_slice()[0] = 2.0;
+        assert_eq!(ffi::trace(&id), 4.0);
+        assert!(std::panic::catch_unwind(|| {
+            ffi::Eigen::MatrixXd::from_slice(&[0.0; 25], 5, 5)
+        })
+        .is_err());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("trace")
+            generate!("identity")
+            dense_matrix!("Eigen::MatrixXd", "f64")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_initializer_list_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <initializer_list>
+        inline uint32_t sum(std::initializer_list<uint32_t> values) {
+            uint32_t total = 0;
+            for (uint32_t value : values) {
+                total += value;
+            }
+            return total;
+        }
+        struct Bag {
+            Bag(std::initializer_list<uint32_t> values) : count(values.size()) {}
+            uint32_t add_all(std::initializer_list<uint32_t> a, std::initializer_list<uint32_t> b) const {
+                return count + sum(a) + sum(b);
+            }
+            uint32_t count;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::sum(&[]), 0);
+        assert_eq!(ffi::sum(&[1, 2, 3]), 6);
+        let bag = ffi::Bag::make_unique(&[5, 6]);
+        assert_eq!(bag.count, 2);
+        assert_eq!(bag.add_all(&[1], &[2, 3]), 8);
+        let result = std::panic::catch_unwind(|| ffi::sum(&[1; 17]));
+        assert!(result.is_err());
+    };
+    run_test("", hdr, rs, &["sum"], &["Bag"]);
+}
+
+#[test]
+fn test_array_params() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t sum4(const uint32_t (&values)[4]) {
+            return values[0] + values[1] + values[2] + values[3];
+        }
+        inline void fill4(uint32_t (&values)[4], uint32_t value) {
+            for (auto& v : values) {
+                v = value;
+            }
+        }
+        inline double trace(const double (&m)[3][3]) {
+            return m[0][0] + m[1][1] + m[2][2];
+        }
+        inline double first_column_sum(double m[2][3]) {
+            return m[0][0] + m[1][0];
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::sum4(&[1, 2, 3, 4]), 10);
+        let mut values = [0u32; 4];
+        ffi::fill4(&mut values, 7);
+        assert_eq!(values, [7; 4]);
+        let m = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]];
+        assert_eq!(ffi::trace(&m), 15.0);
+        let mut rows = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        assert_eq!(unsafe { ffi::first_column_sum(rows.as_mut_ptr()) }, 5.0);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["sum4", "fill4", "trace", "first_column_sum"],
+        &[],
+    );
+}
+
+#[test]
+fn test_global_variables() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace ns {
+            extern uint32_t counter;
+            extern const uint32_t limit;
+            extern thread_local uint32_t per_thread;
+            struct Totals {
+                static uint32_t total;
+            };
+        }
+    "};
+    let cxx = indoc! {"
+        uint32_t ns::counter = 1;
+        const uint32_t ns::limit = 10;
+        thread_local uint32_t ns::per_thread = 0;
+        uint32_t ns::Totals::total = 3;
+    "};
+    let rs = quote! {
+        unsafe {
+            *ffi::ns::counter() += 1;
+            assert_eq!(*ffi::ns::counter(), 2);
+            assert_eq!(*ffi::ns::limit(), 10);
+            *ffi::ns::per_thread() = 5;
+            std::thread::spawn(|| assert_eq!(*ffi::ns::per_thread(), 0))
+                .join()
+                .unwrap();
+            assert_eq!(*ffi::ns::per_thread(), 5);
+            assert_eq!(*ffi::ns::Totals_total(), 3);
+        }
+    };
+    run_test(
+        cxx,
+        hdr,
+        rs,
+        &["ns::counter", "ns::limit", "ns::per_thread", "ns::Totals"],
+        &[],
+    );
+}
+
+#[test]
+fn test_volatile_field() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Regs {
+            volatile uint32_t status;
+            uint32_t other;
+        };
+        inline Regs make_regs() {
+            Regs r;
+            r.status = 3;
+            r.other = 4;
+            return r;
+        }
+        inline uint32_t get_status(const Regs& r) { return r.status; }
+    "};
+    let rs = quote! {
+        let mut r = ffi::make_regs();
+        assert_eq!(r.read_status(), 3);
+        assert_eq!(r.other, 4);
+        r.write_status(5);
+        assert_eq!(ffi::get_status(&r), 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("make_regs")
+            generate!("get_status")
+            generate_pod!("Regs")
+            volatile_field!("Regs", "status")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_non_pod_packed_layout() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #pragma pack(push, 2)
+        struct Packed {
+            Packed() : a(1), b(2) {}
+            ~Packed() {}
+            uint32_t a;
+            uint64_t b;
+        };
+        #pragma pack(pop)
+        inline uint64_t get_b(const Packed& p) { return p.b; }
+    "};
+    let rs = quote! {
+        assert_eq!(std::mem::size_of::<ffi::Packed>(), 12);
+        assert_eq!(std::mem::align_of::<ffi::Packed>(), 2);
+        let p = ffi::Packed::make_unique();
+        assert_eq!(ffi::get_b(&p), 2);
+    };
+    run_test("", hdr, rs, &["Packed", "get_b"], &[]);
+}
+
+#[test]
+fn test_field_accessors() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct Inner {
+            uint32_t a;
+        };
+        struct Person {
+            Person() : age(3) {}
+            uint32_t age;
+            std::string name;
+            Inner inner;
+        private:
+            uint32_t secret;
+        };
+        inline uint32_t get_age(const Person& p) { return p.age; }
+    "};
+    let rs = quote! {
+        let mut p = ffi::Person::make_unique();
+        assert_eq!(*p.age(), 3);
+        p.pin_mut().set_age(4);
+        assert_eq!(ffi::get_age(&p), 4);
+        p.pin_mut().set_name("Bob");
+        assert_eq!(p.name().to_str().unwrap(), "Bob");
+        p.pin_mut().set_inner(ffi::Inner { a: 5 });
+        assert_eq!(p.inner().a, 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Person")
+            generate!("get_age")
+            generate_pod!("Inner")
+            field_accessors!("Person")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_deref_to_base() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Animal {
+        public:
+            Animal() : legs(4) {}
+            uint32_t get_legs() const { return legs; }
+            virtual ~Animal() {}
+        private:
+            uint32_t legs;
+        };
+        class Dog : public Animal {
+        public:
+            uint32_t bark() const { return 3; }
+        };
+    "};
+    let rs = quote! {
+        let dog = ffi::Dog::make_unique();
+        assert_eq!(dog.bark(), 3);
+        assert_eq!(dog.get_legs(), 4);
+        let animal: &ffi::Animal = &dog;
+        assert_eq!(animal.get_legs(), 4);
+    };
+    run_test("", hdr, rs, &["Animal", "Dog"], &[]);
+}
+
+#[test]
+fn test_subclass_calls_protected_method() {
+    let hdr = indoc! {"
+    #include <cstdint>
+
+    class Observer {
+    public:
+        Observer() {}
+        virtual uint32_t foo() const = 0;
+        uint32_t call_foo() const { return foo(); }
+        virtual ~Observer() {}
+    protected:
+        uint32_t scale(uint32_t x) const { return x * 2; }
+    };
+    inline void bar() {}
+    "};
+    run_test_ex(
+        "",
+        hdr,
+        quote! {
+            let obs = MyObserver::new_rust_owned(MyObserver { a: 3, cpp_peer: Default::default() });
+            assert_eq!(obs.borrow().as_ref().call_foo(), 6);
+        },
+        quote! {
+            generate!("bar")
+            subclass!("Observer",MyObserver)
+        },
+        None,
+        None,
+        Some(quote! {
+            use autocxx::subclass::CppSubclass;
+            use ffi::Observer_methods;
+            use ffi::Observer_supers;
+            #[autocxx::subclass::subclass]
+            pub struct MyObserver {
+                a: u32
+            }
+            impl Observer_methods for MyObserver {
+                fn foo(&self) -> u32 {
+                    self.scale_super(self.a)
+                }
+            }
+        }),
+    );
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn test_simd_m128() {
+    let hdr = indoc! {"
+        #include <xmmintrin.h>
+        inline __m128 double_it(__m128 a) {
+            return _mm_add_ps(a, a);
+        }
+    "};
+    let rs = quote! {
+        use std::arch::x86_64::{_mm_set_ps, _mm_storeu_ps};
+        let input = autocxx::simd::m128(unsafe { _mm_set_ps(4.0, 3.0, 2.0, 1.0) });
+        let output: std::arch::x86_64::__m128 = ffi::double_it(input).into();
+        let mut result = [0f32; 4];
+        unsafe { _mm_storeu_ps(result.as_mut_ptr(), output) };
+        assert_eq!(result, [2.0, 4.0, 6.0, 8.0]);
+    };
+    run_test("", hdr, rs, &["double_it"], &[]);
+}
+
+#[test]
+fn test_engine_analyze() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct A {
+            uint32_t a;
+        };
+        inline uint32_t give_int() {
+            return 5;
+        }
+        inline std::string give_str() {
+            return \"hello\";
+        }
+    "};
+    let tdir = tempfile::tempdir().unwrap();
+    std::fs::write(tdir.path().join("input.h"), hdr).unwrap();
+    let hexathorpe = Token![#](Span::call_site());
+    let engine = IncludeCppEngine::new_from_tokens(quote! {
+        #hexathorpe include "input.h"
+        safety!(unsafe_ffi)
+        generate!("give_int")
+        generate!("give_str")
+        generate_pod!("A")
+    })
+    .unwrap();
+    let items = engine
+        .analyze(
+            vec![tdir.path().to_path_buf()],
+            &[],
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let find_item = |name: &str| items.iter().find(|item| item.rust_name == name).unwrap();
+    assert_eq!(find_item("give_int").kind, GeneratedItemKind::Function);
+    assert!(!find_item("give_int").has_cpp_wrapper);
+    // Returning a std::string by value needs a C++ wrapper.
+    assert!(find_item("give_str").has_cpp_wrapper);
+    assert_eq!(find_item("A").kind, GeneratedItemKind::PodType);
+}
+
+#[test]
+fn test_engine_generate_snapshot() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct A {
+            uint32_t a;
+        };
+        class B {
+        public:
+            std::string describe() const { return \"B\"; }
+        };
+        inline uint32_t give_int() {
+            return 5;
+        }
+    "};
+    let tdir = tempfile::tempdir().unwrap();
+    std::fs::write(tdir.path().join("input.h"), hdr).unwrap();
+    let make_snapshot = || {
+        let hexathorpe = Token![#](Span::call_site());
+        let mut engine = IncludeCppEngine::new_from_tokens(quote! {
+            #hexathorpe include "input.h"
+            safety!(unsafe_ffi)
+            generate!("give_int")
+            generate!("B")
+            generate_pod!("A")
+        })
+        .unwrap();
+        engine
+            .generate(
+                vec![tdir.path().to_path_buf()],
+                &[],
+                None,
+                &CppCodegenOptions::default(),
+            )
+            .unwrap();
+        engine
+            .generate_snapshot(&CppCodegenOptions::default())
+            .unwrap()
+    };
+    let snapshot = make_snapshot();
+    assert_eq!(snapshot, make_snapshot());
+    assert!(snapshot.contains("give_int"));
+    assert!(snapshot.contains("describe"));
+    assert!(snapshot.contains("// C++: cxxgen.h"));
+}
+
+#[test]
+fn test_fuzz_header() {
+    assert!(matches!(
+        fuzz_header("inline int give_int() { return 5; }"),
+        FuzzOutcome::Generated
+    ));
+    assert!(matches!(
+        fuzz_header("struct A { int a; } }"),
+        FuzzOutcome::Rejected(_)
+    ));
+}
+
+#[test]
+fn test_keyword_namespace() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace async {
+            struct A {
+                uint32_t a;
+            };
+            inline uint32_t get(A a) {
+                return a.a;
+            }
+        }
+    "};
+    let rs = quote! {
+        let a = ffi::async_::A { a: 12 };
+        assert_eq!(ffi::async_::get(a), 12);
+    };
+    run_test("", hdr, rs, &["async::get"], &["async::A"]);
+}
+
+#[test]
+fn test_keyword_renames_reported() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace type {
+            inline uint32_t move() {
+                return 5;
+            }
+        }
+    "};
+    let tdir = tempfile::tempdir().unwrap();
+    std::fs::write(tdir.path().join("input.h"), hdr).unwrap();
+    let hexathorpe = Token![#](Span::call_site());
+    let engine = IncludeCppEngine::new_from_tokens(quote! {
+        #hexathorpe include "input.h"
+        safety!(unsafe_ffi)
+        generate!("type::move")
+    })
+    .unwrap();
+    let items = engine
+        .analyze(
+            vec![tdir.path().to_path_buf()],
+            &[],
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let item = items
+        .iter()
+        .find(|item| item.kind == GeneratedItemKind::Function)
+        .unwrap();
+    assert_eq!(item.keyword_renames, vec!["type", "move"]);
+}
+
+#[test]
+fn test_forward_declaration_in_shared_ptr() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        class Widget;
+        std::shared_ptr<Widget> make_widget();
+        uint32_t get_value(const Widget& w);
+        void set_value(Widget* w, uint32_t value);
+    "};
+    let cpp = indoc! {"
+        class Widget {
+        public:
+            uint32_t value = 3;
+        };
+        std::shared_ptr<Widget> make_widget() {
+            return std::make_shared<Widget>();
+        }
+        uint32_t get_value(const Widget& w) {
+            return w.value;
+        }
+        void set_value(Widget* w, uint32_t value) {
+            w->value = value;
+        }
+    "};
+    let rs = quote! {
+        let w = ffi::make_widget();
+        assert_eq!(ffi::get_value(w.as_ref().unwrap()), 3);
+        let weak = w.downgrade();
+        drop(weak);
+        assert_eq!(ffi::get_value(w.as_ref().unwrap()), 3);
+    };
+    run_test(
+        cpp,
+        hdr,
+        rs,
+        &["make_widget", "get_value", "set_value"],
+        &[],
+    );
+}
+
+#[test]
+fn test_method_returning_reference_borrows_self() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Selector {
+            bool first;
+        };
+        struct Pair {
+            uint32_t a;
+            uint32_t b;
+            const uint32_t& pick(const Selector& s) const {
+                return s.first ? a : b;
+            }
+        };
+    "};
+    let rs = quote! {
+        let p = ffi::Pair { a: 1, b: 2 };
+        let picked = {
+            let s = ffi::Selector { first: false };
+            p.pick(&s)
+        };
+        assert_eq!(*picked, 2);
+    };
+    run_test("", hdr, rs, &[], &["Pair", "Selector"]);
+}
+
+#[test]
+fn test_borrows_from() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Pair {
+            uint32_t a;
+            uint32_t b;
+        };
+        inline const uint32_t& pick(const Pair& p, const bool& first) {
+            return first ? p.a : p.b;
+        }
+    "};
+    let rs = quote! {
+        let p = ffi::Pair { a: 1, b: 2 };
+        let picked = {
+            let first = true;
+            ffi::pick(&p, &first)
+        };
+        assert_eq!(*picked, 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Pair")
+            generate!("pick")
+            borrows_from!("pick", 0)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_unique_array() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cstddef>
+        #include <memory>
+        inline std::unique_ptr<uint32_t[]> make_buffer(size_t len) {
+            std::unique_ptr<uint32_t[]> buffer(new uint32_t[len]);
+            for (size_t i = 0; i < len; i++) {
+                buffer[i] = i * 2;
+            }
+            return buffer;
+        }
+        class Image {
+        public:
+            Image(uint32_t width, uint32_t height) : width_(width), height_(height) {}
+            uint32_t pixel_count() const { return width_ * height_; }
+            std::unique_ptr<uint8_t[]> pixels() const {
+                std::unique_ptr<uint8_t[]> pixels(new uint8_t[pixel_count()]);
+                for (uint32_t i = 0; i < pixel_count(); i++) {
+                    pixels[i] = 7;
+                }
+                return pixels;
+            }
+        private:
+            uint32_t width_;
+            uint32_t height_;
+        };
+    "};
+    let rs = quote! {
+        let mut buffer = ffi::make_buffer(4);
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(&*buffer, &[0, 2, 4, 6]);
+        buffer[0] = 9;
+        assert_eq!(buffer[0], 9);
+        let image = ffi::Image::make_unique(2, 3);
+        let pixels = image.pixels();
+        assert_eq!(pixels.len(), 6);
+        assert!(pixels.iter().all(|p| *p == 7));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("make_buffer")
+            generate!("Image")
+            unique_array!("make_buffer", 0)
+            unique_array!("Image::pixels", "pixel_count")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_unique_ptr_custom_deleter() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        inline uint32_t& open_handles() {
+            static uint32_t count = 0;
+            return count;
+        }
+        struct Handle {
+            uint32_t id;
+        };
+        struct HandleCloser {
+            void operator()(Handle* handle) const {
+                open_handles()--;
+                delete handle;
+            }
+        };
+        inline std::unique_ptr<Handle, HandleCloser> open_handle(uint32_t id) {
+            open_handles()++;
+            return std::unique_ptr<Handle, HandleCloser>(new Handle{id});
+        }
+        inline uint32_t handle_id(const std::unique_ptr<Handle, HandleCloser>& handle) {
+            return handle->id;
+        }
+        inline uint32_t open_handle_count() {
+            return open_handles();
+        }
+    "};
+    let rs = quote! {
+        let handle = ffi::open_handle(3);
+        assert_eq!(ffi::open_handle_count(), 1);
+        assert_eq!(ffi::handle_id(handle.as_ref().unwrap()), 3);
+        drop(handle);
+        assert_eq!(ffi::open_handle_count(), 0);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["open_handle", "handle_id", "open_handle_count"],
+        &[],
+    );
+}
+
+#[test]
+fn test_shared_from_this_and_upcast() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        class Base {
+        public:
+            virtual ~Base() {}
+            virtual uint32_t value() const { return 1; }
+        };
+        class Derived : public Base, public std::enable_shared_from_this<Derived> {
+        public:
+            uint32_t value() const override { return 2; }
+        };
+        inline std::shared_ptr<Derived> make_derived() {
+            return std::make_shared<Derived>();
+        }
+        inline uint32_t base_value(std::shared_ptr<Base> base) {
+            return base->value();
+        }
+    "};
+    let rs = quote! {
+        use autocxx::SharedPtrUpcast;
+        let derived = ffi::make_derived();
+        let again = derived.shared_from_this();
+        assert!(std::ptr::eq(derived.as_ref().unwrap(), again.as_ref().unwrap()));
+        let base = derived.upcast::<ffi::Base>();
+        assert_eq!(ffi::base_value(base), 2);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["Base", "Derived", "make_derived", "base_value"],
+        &[],
+    );
+}
+
+#[test]
+fn test_function_pointer_typedef() {
+    let hdr = indoc! {"
+        typedef int (*Callback)(int);
+        inline int call_it(Callback cb, int value) {
+            return cb(value);
+        }
+        inline int doubler(int value) {
+            return value * 2;
+        }
+        inline Callback get_doubler() {
+            return doubler;
+        }
+    "};
+    let rs = quote! {
+        extern "C" fn triple(value: std::os::raw::c_int) -> std::os::raw::c_int {
+            value * 3
+        }
+        assert_eq!(
+            ffi::call_it(ffi::Callback(Some(triple)), autocxx::c_int(2)),
+            autocxx::c_int(6)
+        );
+        let doubler = ffi::get_doubler();
+        assert_eq!(unsafe { doubler.0.unwrap()(4) }, 8);
+        assert_eq!(ffi::call_it(doubler, autocxx::c_int(5)), autocxx::c_int(10));
+    };
+    run_test("", hdr, rs, &["call_it", "get_doubler"], &[]);
+}
+
+#[test]
+fn test_anonymous_enum_constants() {
+    let hdr = indoc! {"
+        enum { FLAG_A = 1, FLAG_B = 2 };
+        struct Holder {
+            enum { CAPACITY = 16 };
+            int x;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::FLAG_A | ffi::FLAG_B, 3);
+        assert_eq!(ffi::Holder_CAPACITY, 16);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["FLAG_A", "FLAG_B", "Holder", "Holder_CAPACITY"],
+        &[],
+    );
+}
+
+#[test]
+fn test_enum_integer_conversions() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        enum class Color : uint32_t {
+            Red = 1,
+            Green = 2,
+            Blue = 4,
+        };
+        enum class Flags : uint8_t {
+            A = 1,
+            B = 2,
+        };
+    "};
+    let rs = quote! {
+        use std::convert::TryFrom;
+        assert_eq!(u32::from(ffi::Color::Green), 2);
+        assert!(matches!(ffi::Color::try_from(4u32), Ok(ffi::Color::Blue)));
+        assert!(matches!(ffi::Color::try_from(3u32), Err(3)));
+        let flags = ffi::Flags::from(3u8);
+        assert!(flags.contains(ffi::Flags::B));
+        assert_eq!(u8::from(ffi::Flags::A), 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Color")
+            generate!("Flags")
+            bitflags!("Flags")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_generate_and_block_patterns() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace mylib {
+        namespace net {
+            inline uint32_t connect() { return 1; }
+            inline uint32_t reset_internal() { return 2; }
+        }
+        inline uint32_t unrelated() { return 3; }
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::mylib::net::connect(), 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("mylib::net::*")
+            block!("*_internal")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_misspelled_generate_suggestion() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace mylib {
+        inline uint32_t give_int() {
+            return 5;
+        }
+        struct Widget {
+            uint32_t a;
+        };
+        }
+    "};
+    let tdir = tempfile::tempdir().unwrap();
+    std::fs::write(tdir.path().join("input.h"), hdr).unwrap();
+    let hexathorpe = Token![#](Span::call_site());
+    let analyze = |directive: &str| {
+        IncludeCppEngine::new_from_tokens(quote! {
+            #hexathorpe include "input.h"
+            safety!(unsafe_ffi)
+            generate!(#directive)
+        })
+        .unwrap()
+        .analyze(
+            vec![tdir.path().to_path_buf()],
+            &[],
+            &CppCodegenOptions::default(),
+        )
+        .err()
+        .unwrap()
+        .to_string()
+    };
+    assert!(analyze("mylib::give_itn").contains("Did you mean 'mylib::give_int'?"));
+    assert!(analyze("Widget").contains("Did you mean 'mylib::Widget'?"));
+    assert!(!analyze("mylib::Sprocket").contains("Did you mean"));
+}
+
+#[test]
+fn test_copy_pod_returns() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+        struct Big {
+            uint32_t data[16];
+        };
+        class Shape {
+        public:
+            Shape() : pos{1, 2} {}
+            const Point& position() const { return pos; }
+            const Big& big() const { return b; }
+        private:
+            Point pos;
+            Big b;
+        };
+        inline const Point& origin() {
+            static Point p{0, 0};
+            return p;
+        }
+    "};
+    let rs = quote! {
+        let shape = ffi::Shape::make_unique();
+        // Point is small enough to be returned by value, so it outlives the
+        // Shape from which it came.
+        let pos: ffi::Point = shape.position();
+        let _big: &ffi::Big = shape.big();
+        drop(shape);
+        assert_eq!(pos.x, 1);
+        assert_eq!(pos.y, 2);
+        let o: ffi::Point = ffi::origin();
+        assert_eq!(o.x, 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Shape")
+            generate!("origin")
+            generate_pod!("Point")
+            generate_pod!("Big")
+            copy_pod_returns!(16)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_rvalue_ref_qualified_method() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        class Builder {
+        public:
+            Builder() : name(\"hello\") {}
+            uint32_t len() const & { return name.size(); }
+            std::string take() && { return std::move(name); }
+            virtual uint32_t finish() && { return 3; }
+            virtual ~Builder() {}
+        private:
+            std::string name;
+        };
+    "};
+    let rs = quote! {
+        let mut b = ffi::Builder::make_unique();
+        assert_eq!(b.len(), 5);
+        assert_eq!(b.pin_mut().take().to_str().unwrap(), "hello");
+        // The string has now been moved out.
+        assert_eq!(b.len(), 0);
+    };
+    run_test("", hdr, rs, &["Builder"], &[]);
+}
+
+#[test]
+fn test_autocxx_type_trait() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            struct Pod {
+                uint32_t x;
+            };
+            class Outer {
+            public:
+                class Inner {
+                public:
+                    uint32_t y;
+                };
+            };
+            enum class Colour { Red, Green };
+        }
+        class Abstract {
+        public:
+            virtual void act() = 0;
+            virtual ~Abstract() {}
+        };
+    "};
+    let rs = quote! {
+        use autocxx::AutocxxType;
+        fn name<T: AutocxxType>() -> (&'static str, &'static str) {
+            (T::CPP_NAME, T::CPP_NAMESPACE)
+        }
+        assert_eq!(name::<ffi::a::Pod>(), ("a::Pod", "a"));
+        assert_eq!(name::<ffi::a::Outer_Inner>(), ("a::Outer::Inner", "a"));
+        assert_eq!(name::<ffi::a::Colour>(), ("a::Colour", "a"));
+        assert_eq!(name::<ffi::Abstract>(), ("Abstract", ""));
+        assert_ne!(ffi::a::Pod::type_id(), ffi::a::Colour::type_id());
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["a::Outer_Inner", "a::Colour", "Abstract"],
+        &["a::Pod"],
+    );
+}
+
+#[test]
+fn test_generate_registry() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            inline uint32_t double_it(uint32_t x) { return x * 2; }
+            class Counter {
+            public:
+                Counter() : count(0) {}
+                void increment() { count++; }
+                uint32_t get() const { return count; }
+                static uint32_t zero() { return 0; }
+            private:
+                uint32_t count;
+            };
+        }
+    "};
+    let rs = quote! {
+        use autocxx::reflection::FunctionKind;
+        let registry = ffi::autocxx_registry();
+        let f = registry.find_function("a::double_it").unwrap();
+        assert_eq!(f.cpp_name, "a::double_it");
+        assert_eq!(f.kind, FunctionKind::Function);
+        assert_eq!(f.params.len(), 1);
+        assert_eq!(f.params[0].name, "x");
+        let counter = registry.find_type("a::Counter").unwrap();
+        assert_eq!(counter.rust_name, "a::Counter");
+        assert_eq!((counter.type_id)(), std::any::TypeId::of::<ffi::a::Counter>());
+        assert!(registry
+            .find_type_by_id(std::any::TypeId::of::<ffi::a::Counter>())
+            .is_some());
+        assert_eq!(counter.find_method("get").unwrap().kind, FunctionKind::ConstMethod);
+        assert_eq!(counter.find_method("increment").unwrap().kind, FunctionKind::MutMethod);
+        let zero = counter.find_method("zero").unwrap();
+        assert_eq!(zero.kind, FunctionKind::StaticMethod);
+        assert_eq!(zero.cpp_name, "a::Counter::zero");
+        let made = (counter.make_unique.unwrap())();
+        let made = made
+            .downcast::<cxx::UniquePtr<ffi::a::Counter>>()
+            .unwrap();
+        assert_eq!(made.get(), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("a::double_it")
+            generate!("a::Counter")
+            generate_registry!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+#[cfg_attr(skip_windows_gnu_failing_tests, ignore)]
+#[cfg_attr(skip_windows_msvc_failing_tests, ignore)]
+fn test_dynamic_library() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        extern \"C\" uint32_t plugin_version();
+        namespace plugin {
+            uint32_t triple(uint32_t x);
+            class Widget {
+            public:
+                uint32_t size() const;
+            };
+        }
+    "};
+    let cpp = indoc! {"
+        uint32_t plugin_version() { return 1; }
+        uint32_t plugin::triple(uint32_t x) { return x * 3; }
+        uint32_t plugin::Widget::size() const { return 4; }
+    "};
+    // The library doesn't exist, so all we can do is check that we'd look
+    // functions up in it, rather than calling them directly.
+    let rs = quote! {
+        let _ = ffi::plugin_version;
+        let _ = ffi::plugin::triple;
+        let _ = ffi::plugin::Widget::size;
+    };
+    run_test_ex(
+        cpp,
+        hdr,
+        rs,
+        quote! {
+            generate!("plugin_version")
+            generate!("plugin::triple")
+            generate!("plugin::Widget")
+            dynamic_library!("libplugin.so")
+        },
+        None,
+        Some(Box::new(CppMatcher::new(
+            &[
+                "autocxx_dl_resolve(\"libplugin.so\", \"plugin_version\")",
+                "autocxx_dl_resolve(\"libplugin.so\", \"_ZN6plugin6tripleEj\")",
+                "autocxx_dl_resolve(\"libplugin.so\", \"_ZNK6plugin6Widget4sizeEv\")",
+            ],
+            &["plugin::triple(arg0)"],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_dynamic_library_inline_function() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace plugin {
+            uint32_t triple(uint32_t x);
+            inline uint32_t twice(uint32_t x) { return x * 2; }
+        }
+    "};
+    let cpp = indoc! {"
+        uint32_t plugin::triple(uint32_t x) { return x * 3; }
+    "};
+    // The library doesn't exist, so this only works if the inline function
+    // is called directly.
+    let rs = quote! {
+        let _ = ffi::plugin::triple;
+        assert_eq!(ffi::plugin::twice(2), 4);
+    };
+    run_test_ex(
+        cpp,
+        hdr,
+        rs,
+        quote! {
+            generate!("plugin::triple")
+            generate!("plugin::twice")
+            dynamic_library!("libplugin.so")
+        },
+        None,
+        Some(Box::new(CppMatcher::new(
+            &["autocxx_dl_resolve(\"libplugin.so\", \"_ZN6plugin6tripleEj\")"],
+            &["_ZN6plugin5twiceEj"],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_force_wrapper() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            uint8_t x;
+            uint8_t y;
+        };
+        namespace geo {
+            inline Point get_point() { return Point { 1, 2 }; }
+        }
+        inline uint32_t unwrapped() { return 3; }
+    "};
+    let rs = quote! {
+        let p = ffi::geo::get_point();
+        assert_eq!(p.x, 1);
+        assert_eq!(p.y, 2);
+        assert_eq!(ffi::unwrapped(), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Point")
+            generate!("geo::get_point")
+            generate!("unwrapped")
+            force_wrapper!("geo::get_point")
+        },
+        None,
+        Some(Box::new(CppMatcher::new(
+            &["get_point_autocxx_wrapper"],
+            &["unwrapped_autocxx_wrapper"],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_abi_self_test() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Reading {
+            uint8_t channel;
+            double value;
+            bool valid;
+            int type;
+        };
+        struct Handle {
+            const char* name;
+        };
+        inline Reading make_reading() { return Reading { 1, 2.5, true, 3 }; }
+    "};
+    let rs = quote! {
+        let r = ffi::make_reading();
+        assert_eq!(r.channel, 1);
+        assert_eq!(r.type_, 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Reading")
+            generate_pod!("Handle")
+            generate!("make_reading")
+            abi_self_test!()
+        },
+        None,
+        Some(make_string_finder(vec![
+            "fn autocxx_abi_self_test_Reading",
+            "Reading_autocxx_abi_roundtrip",
+        ])),
+        None,
+    );
+}
+
+#[test]
+fn test_coverage_codegen() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        class Goat {
+        public:
+            uint32_t horns() const { return 2; }
+            std::string bleat() const { return \"baa\"; }
+        };
+        inline std::string describe() { return \"goat\"; }
+        inline std::string name() { return \"Gerald\"; }
+    "};
+    let rs = quote! {
+        let goat = ffi::Goat::make_unique();
+        assert_eq!(goat.horns(), 2);
+        assert_eq!(goat.bleat().to_str().unwrap(), "baa");
+        assert_eq!(ffi::describe().to_str().unwrap(), "goat");
+        assert_eq!(ffi::name().to_str().unwrap(), "Gerald");
+    };
+    // Without coverage, describe and name would share a wrapper.
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Goat", "describe", "name"], &[], None),
+        Some(Box::new(EnableCoverageCodegen)),
+        Some(Box::new(CppMatcher::new(
+            &[
+                "// Called from Rust by ffi::Goat::bleat",
+                "// Called from Rust by ffi::describe",
+            ],
+            &["autocxx_shared_wrapper_"],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_cpp_shards() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        class Goat {
+        public:
+            uint32_t horns() const { return 2; }
+            std::string bleat() const { return \"baa\"; }
+        };
+        class Sheep {
+        public:
+            std::string bleat() const { return \"meh\"; }
+        };
+        inline std::string describe() { return \"goat\"; }
+        inline uint32_t add(uint32_t a, uint32_t b) { return a + b; }
+    "};
+    let rs = quote! {
+        let goat = ffi::Goat::make_unique();
+        assert_eq!(goat.horns(), 2);
+        assert_eq!(goat.bleat().to_str().unwrap(), "baa");
+        assert_eq!(ffi::Sheep::make_unique().bleat().to_str().unwrap(), "meh");
+        assert_eq!(ffi::describe().to_str().unwrap(), "goat");
+        assert_eq!(ffi::add(2, 3), 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Goat", "Sheep", "describe", "add"], &[], None),
+        Some(Box::new(SetCppShards(3))),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_bindgen_blocklist() {
+    let hdr = indoc! {"
+        namespace ns {
+            inline int good() { return 1; }
+            inline int bad() { return 2; }
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::ns::good(), autocxx::c_int(1));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_ns!("ns")
+            safety!(unsafe_ffi)
+        },
+        Some(Box::new(SetBindgenBlocklist("ns::bad"))),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_bindgen_callbacks() {
+    let hdr = indoc! {"
+        #define BOB 3
+    "};
+    // Without the callbacks, BOB would be a u32.
+    let rs = quote! {
+        let bob: i64 = ffi::BOB;
+        assert_eq!(bob, 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["BOB"], &[], None),
+        Some(Box::new(SetI64Macros(&["BOB"]))),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_block_through_typedef() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Problem {
+            uint32_t a;
+        };
+        typedef Problem ProblemAlias;
+        inline uint32_t get_value() { return 3; }
+        inline uint32_t get_problem(const ProblemAlias& p) { return p.a; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_value(), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("get_value")
+            generate!("get_problem")
+            block!("Problem")
+            safety!(unsafe_ffi)
+        },
+        None,
+        Some(make_error_finder("get_problem")),
+        None,
+    );
+}
+
+#[test]
+fn test_block_base_class() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace zoo {
+            class Problem {
+            public:
+                uint32_t a() const { return 1; }
+            };
+            class Derived : public Problem {
+            public:
+                uint32_t b() const { return 2; }
+            };
+        }
+    "};
+    // Derived is still generated, though we can't cast it to its base.
+    let rs = quote! {
+        assert_eq!(ffi::zoo::Derived::make_unique().b(), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("zoo::*")
+            block!("zoo::Problem")
+            safety!(unsafe_ffi)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_overload_suffix_collision() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            uint32_t a;
+            uint32_t get1() const { return 1; }
+            uint32_t get(uint32_t b) const { return a + b; }
+            uint32_t get(uint8_t b) const { return a * b; }
+        };
+        inline uint32_t make1() { return 1; }
+        inline uint32_t make(uint32_t b) { return b; }
+        inline uint32_t make(uint8_t b) { return b * 2; }
+    "};
+    let rs = quote! {
+        let a = ffi::Bob { a: 12 };
+        assert_eq!(a.get1(), 1);
+        assert_eq!(a.get(2u32), 14);
+        assert_eq!(a.get2(2u8), 24);
+        assert_eq!(ffi::make1(), 1);
+        assert_eq!(ffi::make(3u32), 3);
+        assert_eq!(ffi::make2(3u8), 6);
+    };
+    run_test("", hdr, rs, &["make", "make1"], &["Bob"]);
+}
+
+#[test]
+fn test_method_starting_with_type_name() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Foo {
+        public:
+            Foo() : a(3) {}
+            Foo(uint32_t b) : a(b) {}
+            uint32_t FooCount() const { return a; }
+        private:
+            uint32_t a;
+        };
+        struct Foo_Bar {
+            Foo_Bar(uint32_t b) : a(b) {}
+            uint32_t Foo_BarValue() const { return a; }
+            uint32_t a;
+        };
+    "};
+    let rs = quote! {
+        let a = ffi::Foo::make_unique1(4);
+        assert_eq!(a.FooCount(), 4);
+        let b = ffi::Foo_Bar::make_unique(5);
+        assert_eq!(b.Foo_BarValue(), 5);
+    };
+    run_test("", hdr, rs, &["Foo", "Foo_Bar"], &[]);
+}
+
+#[test]
+fn test_enum_with_aliased_enumerators() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        enum class Colour {
+            Red,
+            Green,
+            Blue,
+            Rouge = Red,
+            Vert = Green,
+        };
+        inline uint32_t colour_value(Colour c) {
+            return static_cast<uint32_t>(c);
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::colour_value(ffi::Colour::Rouge), 0);
+        assert_eq!(ffi::colour_value(ffi::Colour::Vert), 1);
+    };
+    run_test("", hdr, rs, &["Colour", "colour_value"], &[]);
+}
+
+#[test]
+fn test_static_constexpr_member() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Foo {
+        public:
+            static constexpr uint32_t kMax = 10;
+            uint32_t a;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::Foo::kMax, 10);
+    };
+    run_test("", hdr, rs, &[], &["Foo"]);
+}
+
+#[test]
+fn test_typedef_across_nested_namespaces() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace base {
+        namespace internal {
+            struct IdType {
+                uint32_t id;
+            };
+        }
+        }
+        namespace app {
+        namespace model {
+            using Id = base::internal::IdType;
+            using OtherId = Id;
+            inline uint32_t get_id(const OtherId& i) {
+                return i.id;
+            }
+        }
+        }
+    "};
+    let rs = quote! {
+        let id = ffi::base::internal::IdType { id: 4 };
+        assert_eq!(ffi::app::model::get_id(&id), 4);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["app::model::get_id"],
+        &["base::internal::IdType"],
+    );
+}
+
+#[test]
+fn test_concrete_alias_template() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        #include <string>
+        template <typename K, typename V> struct Pair {
+            K first;
+            V second;
+        };
+        template <typename T> using StringPair = Pair<std::string, T>;
+        inline std::unique_ptr<Pair<std::string, uint32_t>> make_string_pair(uint32_t second) {
+            return std::make_unique<Pair<std::string, uint32_t>>(
+                Pair<std::string, uint32_t>{\"first\", second});
+        }
+        inline uint32_t get_second(const Pair<std::string, uint32_t>& pair) {
+            return pair.second;
+        }
+    "};
+    let rs = quote! {
+        let pair: cxx::UniquePtr<ffi::IntStringPair> = ffi::make_string_pair(42);
+        assert_eq!(ffi::get_second(&pair), 42);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("make_string_pair")
+            generate!("get_second")
+            concrete!("StringPair<uint32_t>", IntStringPair)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_begin_end_iterator() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Item {
+            uint32_t value;
+        };
+        class Bag {
+        public:
+            Bag() {
+                for (uint32_t i = 0; i < 3; i++) {
+                    items[i].value = i + 1;
+                }
+            }
+            const Item* begin() const { return items; }
+            const Item* end() const { return items + 3; }
+        private:
+            Item items[3];
+        };
+    "};
+    let rs = quote! {
+        let bag = ffi::Bag::make_unique();
+        let values: Vec<u32> = bag.iter().map(|item| item.value).collect();
+        assert_eq!(values, [1, 2, 3]);
+    };
+    run_test("", hdr, rs, &["Bag"], &["Item"]);
+}
+
+#[test]
+fn test_vector_of_non_pod() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        #include <string>
+        #include <vector>
+        class Named {
+        public:
+            Named(std::string name) : name(name) {}
+            const std::string& get_name() const { return name; }
+        private:
+            std::string name;
+        };
+        inline std::unique_ptr<std::vector<Named>> make_names() {
+            auto names = std::make_unique<std::vector<Named>>();
+            names->push_back(Named(\"a\"));
+            return names;
+        }
+        inline uint32_t count_names(const std::vector<Named>& names) {
+            return names.size();
+        }
+    "};
+    let rs = quote! {
+        use autocxx::CxxVectorUniquePtr;
+        let mut names = ffi::make_names();
+        let first = names.get_copy(0).unwrap();
+        assert_eq!(first.get_name().to_str().unwrap(), "a");
+        assert!(names.get_copy(1).is_none());
+        names.pin_mut().push_unique(first);
+        assert_eq!(ffi::count_names(&names), 2);
+    };
+    run_test("", hdr, rs, &["Named", "make_names", "count_names"], &[]);
+}
+
+#[test]
+fn test_auto_pod() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct Point {
+            int32_t x;
+            int32_t y;
+        };
+        struct Segment {
+            Point start;
+            Point end;
+        };
+        struct Named {
+            std::string name;
+        };
+        struct Tracked {
+            int32_t id;
+            ~Tracked() {}
+        };
+        struct Opaque {
+            int32_t secret;
+        };
+        inline Segment make_segment() { return Segment { { 1, 2 }, { 3, 4 } }; }
+        inline int32_t get_secret(const Opaque& o) { return o.secret; }
+    "};
+    let rs = quote! {
+        let s = ffi::make_segment();
+        assert_eq!(s.start.x, 1);
+        assert_eq!(s.end.y, 4);
+        let p = ffi::Point { x: 5, y: 6 };
+        assert_eq!(p.x + p.y, 11);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            auto_pod!()
+            non_pod!("Opaque")
+            generate!("make_segment")
+            generate!("get_secret")
+            generate!("Named")
+            generate!("Tracked")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_opaque_pod_pointers() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Args {
+            int32_t argc;
+            char** argv;
+            const int32_t* lengths;
+        };
+        inline Args make_args() {
+            static char a[] = \"a\";
+            static char* argv[] = { a };
+            static const int32_t lengths[] = { 1 };
+            return Args { 1, argv, lengths };
+        }
+    "};
+    let rs = quote! {
+        let args = ffi::make_args();
+        assert_eq!(args.argc, 1);
+        let argv: *mut std::os::raw::c_void = args.argv;
+        assert!(!argv.is_null());
+        assert_eq!(unsafe { *args.lengths }, 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Args")
+            generate!("make_args")
+            opaque_pod_pointers!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_pod_mut_receiver_and_pin_helpers() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Counter {
+            uint32_t count;
+            uint32_t inc() { return ++count; }
+        };
+        struct Widget {
+            Widget() : count(0) {}
+            ~Widget() {}
+            uint32_t count;
+            uint32_t inc() { return ++count; }
+        };
+        inline void reset(Counter& c) { c.count = 0; }
+    "};
+    let rs = quote! {
+        let mut c = ffi::Counter { count: 3 };
+        assert_eq!(c.inc(), 4);
+        let r: &mut ffi::Counter = &mut c;
+        assert_eq!(r.inc(), 5);
+        ffi::reset(c.pin_mut());
+        assert_eq!(c.count, 0);
+        let mut pinned = Box::pin(ffi::Counter { count: 1 });
+        assert_eq!(pinned.as_mut().inc(), 2);
+        let mut w = ffi::Widget::make_unique();
+        assert_eq!(w.pin_mut().inc(), 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["reset", "Widget"],
+            &["Counter"],
+            Some(quote! { unpin_pod_methods!() }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_pod_mut_receiver_pinned_by_default() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Counter {
+            uint32_t count;
+            uint32_t inc() { return ++count; }
+        };
+        inline void reset(Counter& c) { c.count = 0; }
+    "};
+    let rs = quote! {
+        let mut c = ffi::Counter { count: 3 };
+        assert_eq!(c.pin_mut().inc(), 4);
+        ffi::reset(c.pin_mut());
+        assert_eq!(c.count, 0);
+    };
+    run_test("", hdr, rs, &["reset"], &["Counter"]);
+}
+
+#[test]
+fn test_inline_and_weak_variables() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace ns {
+            struct Point {
+                uint32_t x;
+                uint32_t y;
+            };
+            inline constexpr Point unit{1, 1};
+            constexpr Point origin{0, 0};
+            inline uint32_t hits = 2;
+            __attribute__((weak)) uint32_t weak_limit = 4;
+            struct Shape {
+                static constexpr Point corner{3, 4};
+            };
+        }
+    "};
+    let rs = quote! {
+        unsafe {
+            assert_eq!((*ffi::ns::unit()).x, 1);
+            assert_eq!((*ffi::ns::origin()).y, 0);
+            *ffi::ns::hits() += 1;
+            assert_eq!(*ffi::ns::hits(), 3);
+            assert_eq!(*ffi::ns::weak_limit(), 4);
+            assert_eq!((*ffi::ns::Shape_corner()).y, 4);
+        }
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &[
+            "ns::unit",
+            "ns::origin",
+            "ns::hits",
+            "ns::weak_limit",
+            "ns::Shape",
+        ],
+        &["ns::Point"],
+    );
+}
+
+#[test]
+fn test_refcounted() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t& live_widgets() {
+            static uint32_t count = 0;
+            return count;
+        }
+        class Widget {
+        public:
+            Widget(uint32_t value) : value_(value), refs_(1) { live_widgets()++; }
+            ~Widget() { live_widgets()--; }
+            uint32_t get_value() const { return value_; }
+            void add_ref() { refs_++; }
+            void release() {
+                if (--refs_ == 0) {
+                    delete this;
+                }
+            }
+        private:
+            uint32_t value_;
+            uint32_t refs_;
+        };
+        inline void widget_ref(Widget* widget) { widget->add_ref(); }
+        inline void widget_unref(Widget* widget) { widget->release(); }
+        inline Widget* make_widget(uint32_t value) {
+            return new Widget(value);
+        }
+        inline uint32_t widget_value(const Widget& widget) {
+            return widget.get_value();
+        }
+        inline uint32_t live_widget_count() {
+            return live_widgets();
+        }
+    "};
+    let rs = quote! {
+        let widget = ffi::make_widget(3);
+        assert_eq!(widget.get_value(), 3);
+        let other = widget.clone();
+        drop(widget);
+        assert_eq!(ffi::live_widget_count(), 1);
+        assert_eq!(ffi::widget_value(&other), 3);
+        drop(other);
+        assert_eq!(ffi::live_widget_count(), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Widget")
+            generate!("make_widget")
+            generate!("widget_value")
+            generate!("live_widget_count")
+            refcounted!("Widget", "widget_ref", "widget_unref")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_error_generated_for_array_return() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t (&get_row())[3] {
+            static uint32_t row[3] = {1, 2, 3};
+            return row;
+        }
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate!("get_row") },
+        None,
+        Some(make_error_finder("get_row")),
+        None,
+    );
+}
+
+#[test]
+fn test_error_generated_for_array_of_unknown_bound() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t first(const uint32_t (&values)[]) {
+            return values[0];
+        }
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate!("first") },
+        None,
+        Some(make_error_finder("first")),
+        None,
+    );
+}
+
+#[test]
+fn test_last_error() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t& last_error_slot() {
+            static uint32_t error = 0;
+            return error;
+        }
+        inline uint32_t get_error() {
+            return last_error_slot();
+        }
+        inline int32_t open_channel(uint32_t id) {
+            if (id == 0) {
+                last_error_slot() = 22;
+                return -1;
+            }
+            last_error_slot() = 0;
+            return static_cast<int32_t>(id) + 100;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::open_channel(3), Ok(103));
+        assert_eq!(ffi::open_channel(0), Err(22));
+        assert_eq!(ffi::get_error(), 22);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("open_channel")
+            last_error!("open_channel", "get_error", "-1")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_async_wrapper() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <thread>
+        inline uint32_t slow_multiply(uint32_t a, uint32_t b) {
+            std::this_thread::sleep_for(std::chrono::milliseconds(10));
+            return a * b;
+        }
+    "};
+    let rs = quote! {
+        use std::future::Future;
+        struct ThreadWaker(std::thread::Thread);
+        impl std::task::Wake for ThreadWaker {
+            fn wake(self: std::sync::Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+        let waker = std::task::Waker::from(std::sync::Arc::new(ThreadWaker(
+            std::thread::current(),
+        )));
+        let mut cx = std::task::Context::from_waker(&waker);
+        let mut future = Box::pin(ffi::slow_multiply_async(7, 5));
+        let result = loop {
+            match future.as_mut().poll(&mut cx) {
+                std::task::Poll::Ready(result) => break result,
+                std::task::Poll::Pending => std::thread::park(),
+            }
+        };
+        assert_eq!(result, 35);
+        assert_eq!(ffi::slow_multiply(2, 3), 6);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("slow_multiply")
+            async_wrapper!("slow_multiply")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_error_generated_for_async_wrapper_taking_reference() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t read_value(const uint32_t& value) {
+            return value;
+        }
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("read_value")
+            async_wrapper!("read_value")
+        },
+        None,
+        Some(make_error_finder("read_value")),
+        None,
+    );
+}
+
+#[test]
+fn test_hidden_visibility_function() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        __attribute__((visibility(\"hidden\"))) uint32_t internal_helper();
+        inline uint32_t public_api() { return 7; }
+    "};
+    let cpp = indoc! {"
+        uint32_t internal_helper() { return 3; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::public_api(), 7);
+    };
+    run_test_ex(
+        cpp,
+        hdr,
+        rs,
+        quote! {
+            generate_all!()
+        },
+        None,
+        Some(Box::new(CppMatcher::new(
+            &["public_api"],
+            &["internal_helper"],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_shared_wrappers() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        inline uint32_t length_of(std::string a) { return a.length(); }
+        inline uint32_t spaces_in(std::string a) {
+            uint32_t spaces = 0;
+            for (char c : a) { if (c == ' ') { spaces++; } }
+            return spaces;
+        }
+        class Counter {
+        public:
+            uint32_t count(std::string a) const { return length_of(a) + 1; }
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::length_of(ffi::make_string("a b c")), 5);
+        assert_eq!(ffi::spaces_in(ffi::make_string("a b c")), 2);
+        let counter = ffi::Counter::make_unique();
+        assert_eq!(counter.count(ffi::make_string("a b c")), 6);
+    };
+    // The conversion of each std::string is done in shared wrappers, one
+    // for functions and one for methods, rather than in each wrapper.
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["length_of", "spaces_in", "Counter"], &[], None),
+        None,
+        Some(Box::new(CppMatcher::new(
+            &[
+                "autocxx_shared_wrapper_",
+                "autocxx_callee(std::move(*arg0))",
+            ],
+            &[
+                "length_of(std::move(*arg0))",
+                "spaces_in(std::move(*arg0))",
+                "count(std::move(*arg1))",
+            ],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_constructor_naming_by_parameters_collision() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Scale {
+            Scale(uint32_t factor) : value(factor) {}
+            Scale(double factor) : value(static_cast<uint32_t>(factor * 10)) {}
+            Scale(uint32_t a, uint32_t b) : value(a * b) {}
+            uint32_t value;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::Scale::make_unique(2).value, 2);
+        assert_eq!(ffi::Scale::make_unique1(2.5).value, 25);
+        assert_eq!(ffi::Scale::make_unique_with_a_and_b(3, 4).value, 12);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Scale")
+            constructor_naming!(by_parameters)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_instrument_calls_hook() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            inline uint32_t double_it(uint32_t x) { return x * 2; }
+        }
+        struct Counter {
+            Counter() : count(0) {}
+            void increment() { count++; }
+            uint32_t count;
+        };
+    "};
+    let rs = quote! {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static DOUBLE_IT_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static INCREMENT_CALLS: AtomicUsize = AtomicUsize::new(0);
+        fn hook(name: &'static str, _: std::time::Duration) {
+            match name {
+                "a::double_it" => DOUBLE_IT_CALLS.fetch_add(1, Ordering::SeqCst),
+                "Counter::increment" => INCREMENT_CALLS.fetch_add(1, Ordering::SeqCst),
+                _ => 0,
+            };
+        }
+        autocxx::instrumentation::set_call_hook(hook);
+        assert_eq!(ffi::a::double_it(21), 42);
+        let mut c = ffi::Counter::make_unique();
+        c.pin_mut().increment();
+        c.pin_mut().increment();
+        autocxx::instrumentation::clear_call_hook();
+        assert_eq!(ffi::a::double_it(1), 2);
+        assert_eq!(DOUBLE_IT_CALLS.load(Ordering::SeqCst), 1);
+        assert_eq!(INCREMENT_CALLS.load(Ordering::SeqCst), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("a::double_it")
+            generate!("Counter")
+            instrument_calls!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+// `__stdcall` only means anything on 32-bit Windows; elsewhere, clang
+// ignores it and bindgen reports the default calling convention.
+#[cfg(all(target_os = "windows", target_arch = "x86"))]
+#[test]
+fn test_subclass_stdcall_override() {
+    let hdr = indoc! {"
+    #include <cstdint>
+
+    class Observer {
+    public:
+        Observer() {}
+        virtual uint32_t __stdcall foo(uint32_t a) = 0;
+        virtual ~Observer() {}
+    };
+    inline uint32_t call_foo(Observer& obs) { return obs.foo(3); }
+    "};
+    run_test_ex(
+        "",
+        hdr,
+        quote! {
+            let obs = MyObserver::new_rust_owned(MyObserver { a: 1, cpp_peer: Default::default() });
+            assert_eq!(ffi::call_foo(obs.as_ref().borrow_mut().pin_mut()), 4);
+        },
+        quote! {
+            generate!("call_foo")
+            subclass!("Observer",MyObserver)
+        },
+        None,
+        Some(Box::new(CppMatcher::new(&["__stdcall foo("], &[]))),
+        Some(quote! {
+            use autocxx::subclass::CppSubclass;
+            use ffi::Observer_methods;
+            #[autocxx::subclass::subclass]
+            pub struct MyObserver {
+                a: u32
+            }
+            impl Observer_methods for MyObserver {
+                fn foo(&mut self, a: u32) -> u32 {
+                    a + self.a
+                }
+            }
+        }),
+    );
+}
+
+#[test]
+fn test_engine_output_independent_of_order() {
+    let shapes = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct Shape {
+            virtual ~Shape() {}
+            virtual uint32_t sides() const { return 0; }
+        };
+        struct Square : public Shape {
+            uint32_t sides() const override { return 4; }
+            std::string name;
+        };
+        inline uint32_t count_sides(const Shape& s) { return s.sides(); }
+    "};
+    let points = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+        struct Label {
+            std::string text;
+        };
+        inline Point origin() { return Point { 0, 0 }; }
+    "};
+    let options = CppCodegenOptions {
+        sort_inclusions: true,
+        ..Default::default()
+    };
+    let tdir = tempfile::tempdir().unwrap();
+    std::fs::write(tdir.path().join("shapes.h"), shapes).unwrap();
+    std::fs::write(tdir.path().join("points.h"), points).unwrap();
+    let make_snapshot = |first: &str, second: &str, directives: proc_macro2::TokenStream| {
+        let hexathorpe = Token![#](Span::call_site());
+        let mut engine = IncludeCppEngine::new_from_tokens(quote! {
+            #hexathorpe include #first
+            #hexathorpe include #second
+            safety!(unsafe_ffi)
+            #directives
+        })
+        .unwrap();
+        engine
+            .generate(vec![tdir.path().to_path_buf()], &[], None, &options)
+            .unwrap();
+        engine.generate_snapshot(&options).unwrap()
+    };
+    let snapshot = make_snapshot(
+        "shapes.h",
+        "points.h",
+        quote! {
+            generate!("Shape")
+            generate!("Square")
+            generate!("count_sides")
+            generate_pod!("Point")
+            generate!("Label")
+            generate!("origin")
+        },
+    );
+    let shuffled = make_snapshot(
+        "points.h",
+        "shapes.h",
+        quote! {
+            generate!("origin")
+            generate!("Label")
+            generate!("count_sides")
+            generate_pod!("Point")
+            generate!("Square")
+            generate!("Shape")
+        },
+    );
+    assert_eq!(snapshot, shuffled);
+    assert!(snapshot.contains("count_sides"));
+    assert!(snapshot.contains("origin"));
+}
+
+#[test]
+fn test_error_generated_for_async_wrapper_returning_pod_with_pointer() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Opaque;
+        struct Handle {
+            Opaque* target;
+            uint32_t id;
+        };
+        inline Handle make_handle() {
+            return Handle { nullptr, 1 };
+        }
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Handle")
+            generate!("make_handle")
+            opaque_pod_pointers!()
+            async_wrapper!("make_handle")
+        },
+        None,
+        Some(make_error_finder("make_handle")),
+        None,
+    );
+}
+
+#[test]
+fn test_export_macro() {
+    // Unlike an explicit visibility attribute, which bindgen spots itself,
+    // this only matters when the library is built with -fvisibility=hidden.
+    let hdr = indoc! {"
+        #include <cstdint>
+        #ifndef MYLIB_EXPORT
+        #define MYLIB_EXPORT
+        #endif
+        MYLIB_EXPORT uint32_t exported();
+        uint32_t internal();
+        inline uint32_t inline_helper() { return 5; }
+    "};
+    let cpp = indoc! {"
+        uint32_t exported() { return 3; }
+        uint32_t internal() { return 4; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::exported(), 3);
+        assert_eq!(ffi::inline_helper(), 5);
+    };
+    run_test_ex(
+        cpp,
+        hdr,
+        rs,
+        quote! {
+            generate!("exported")
+            generate!("internal")
+            generate!("inline_helper")
+            export_macro!("MYLIB_EXPORT")
+        },
+        None,
+        Some(make_error_finder("internal")),
+        None,
+    );
+}
+
 // Yet to test:
 // - Ifdef
 // - Out param pointers
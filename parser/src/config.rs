@@ -8,7 +8,7 @@
 
 use std::{borrow::Cow, collections::HashSet};
 
-use proc_macro2::Span;
+use proc_macro2::{Span, TokenStream};
 use quote::ToTokens;
 use syn::{
     parse::{Parse, ParseStream},
@@ -24,6 +24,11 @@ use crate::{
 #[cfg(feature = "reproduction_case")]
 use quote::quote;
 
+/// The Rust element types which `dense_matrix!` supports.
+const DENSE_MATRIX_ELEMENT_TYPES: &[&str] = &[
+    "f32", "f64", "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64",
+];
+
 #[derive(PartialEq, Clone, Debug, Hash)]
 pub enum UnsafePolicy {
     AllFunctionsSafe,
@@ -64,22 +69,233 @@ impl ToTokens for UnsafePolicy {
     }
 }
 
+/// How to name generated Rust methods.
+#[derive(PartialEq, Clone, Copy, Debug, Hash)]
+pub enum NamingPolicy {
+    /// Use the same names as C++.
+    Preserve,
+    /// Convert method names to `snake_case`, e.g. `getValue` becomes
+    /// `get_value`. The C++ name is still used for linkage.
+    SnakeCase,
+}
+
+#[allow(clippy::derivable_impls)] // nightly-only
+impl Default for NamingPolicy {
+    fn default() -> Self {
+        NamingPolicy::Preserve
+    }
+}
+
+impl Parse for NamingPolicy {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let id: syn::Ident = input.parse()?;
+        if id == "snake_case" {
+            Ok(NamingPolicy::SnakeCase)
+        } else if id == "preserve" {
+            Ok(NamingPolicy::Preserve)
+        } else {
+            Err(syn::Error::new(
+                id.span(),
+                "expected snake_case or preserve",
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "reproduction_case")]
+impl ToTokens for NamingPolicy {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            NamingPolicy::Preserve => tokens.extend(quote! { preserve }),
+            NamingPolicy::SnakeCase => tokens.extend(quote! { snake_case }),
+        }
+    }
+}
+
+/// How to name generated constructors, where there's more than one.
+#[derive(PartialEq, Clone, Copy, Debug, Hash)]
+pub enum ConstructorNaming {
+    /// Number them in the order they're found: `new`, `new1`, `new2`...
+    /// and likewise `make_unique`, `make_unique1`...
+    Numbered,
+    /// Name constructors which take parameters after those parameters,
+    /// e.g. `Foo(int width, int height)` becomes `new_with_width_and_height`
+    /// and `make_unique_with_width_and_height`.
+    ByParameters,
+}
+
+#[allow(clippy::derivable_impls)] // nightly-only
+impl Default for ConstructorNaming {
+    fn default() -> Self {
+        ConstructorNaming::Numbered
+    }
+}
+
+impl Parse for ConstructorNaming {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let id: syn::Ident = input.parse()?;
+        if id == "by_parameters" {
+            Ok(ConstructorNaming::ByParameters)
+        } else if id == "numbered" {
+            Ok(ConstructorNaming::Numbered)
+        } else {
+            Err(syn::Error::new(
+                id.span(),
+                "expected by_parameters or numbered",
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "reproduction_case")]
+impl ToTokens for ConstructorNaming {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            ConstructorNaming::Numbered => tokens.extend(quote! { numbered }),
+            ConstructorNaming::ByParameters => tokens.extend(quote! { by_parameters }),
+        }
+    }
+}
+
+/// Where to find the length of an array returned as a
+/// `std::unique_ptr<T[]>`, as given in a `unique_array!` directive.
+#[derive(PartialEq, Clone, Debug, Hash)]
+pub enum UniqueArrayLength {
+    /// The parameter at this index (not counting any `this` parameter).
+    Param(usize),
+    /// The result of calling this function: for a method, another const
+    /// method of the same class taking no parameters; for a free function,
+    /// a free function in the same namespace taking no parameters.
+    Function(String),
+}
+
+impl Parse for UniqueArrayLength {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        if input.peek(syn::LitInt) {
+            let idx: syn::LitInt = input.parse()?;
+            Ok(UniqueArrayLength::Param(idx.base10_parse()?))
+        } else {
+            let fun: syn::LitStr = input.parse()?;
+            if syn::parse_str::<Ident>(&fun.value()).is_err() {
+                return Err(syn::Error::new(
+                    fun.span(),
+                    "expected a parameter index or the name of a function returning the length",
+                ));
+            }
+            Ok(UniqueArrayLength::Function(fun.value()))
+        }
+    }
+}
+
+#[cfg(feature = "reproduction_case")]
+impl ToTokens for UniqueArrayLength {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            UniqueArrayLength::Param(idx) => {
+                syn::LitInt::new(&idx.to_string(), Span::call_site()).to_tokens(tokens)
+            }
+            UniqueArrayLength::Function(fun) => fun.to_tokens(tokens),
+        }
+    }
+}
+
 /// An entry in the allowlist.
 #[derive(Hash, Debug)]
 pub enum AllowlistEntry {
     Item(String),
     Namespace(String),
+    /// A wildcard pattern such as `mylib::net::*`. See [`matches_pattern`].
+    Pattern(String),
 }
 
 impl AllowlistEntry {
+    /// The entry for a `generate!` directive, which may give either a
+    /// single item or a pattern.
+    fn for_generate(name: String) -> Self {
+        if is_pattern(&name) {
+            AllowlistEntry::Pattern(name)
+        } else {
+            AllowlistEntry::Item(name)
+        }
+    }
+
     fn to_bindgen_item(&self) -> String {
         match self {
             AllowlistEntry::Item(i) => i.clone(),
             AllowlistEntry::Namespace(ns) => format!("{}::.*", ns),
+            AllowlistEntry::Pattern(pattern) => pattern_to_regex(pattern),
         }
     }
 }
 
+/// Whether a name given to `generate!` or `block!` is actually a wildcard
+/// pattern.
+fn is_pattern(name: &str) -> bool {
+    name.contains('*')
+}
+
+/// Whether a C++ name matches a pattern in which each `*` stands for any
+/// sequence of characters, including `::`. So `mylib::net::*` matches
+/// everything within `mylib::net` and any namespaces within it, and
+/// `*_internal` matches anything whose name ends in `_internal`.
+fn matches_pattern(pattern: &str, cpp_name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap();
+    let mut rest = match cpp_name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let mut parts: Vec<_> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// The regular expression, as understood by bindgen, equivalent to a
+/// wildcard pattern.
+fn pattern_to_regex(pattern: &str) -> String {
+    pattern
+        .split('*')
+        .map(|part| {
+            part.chars()
+                .flat_map(|c| {
+                    let escape = "\\.+*?()|[]{}^$".contains(c).then_some('\\');
+                    escape.into_iter().chain(std::iter::once(c))
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(".*")
+}
+
+/// Allowlist entries read from an external file rather than from
+/// directives within the `include_cpp!` macro. Each list has the same
+/// meaning as the directive of the same name.
+#[derive(Debug, Default, Clone)]
+pub struct ExternalAllowlist {
+    pub generate: Vec<String>,
+    pub generate_pod: Vec<String>,
+    pub generate_ns: Vec<String>,
+    pub block: Vec<String>,
+}
+
+/// Settings which may be shared by every `include_cpp!` in a project,
+/// rather than being given by directives within each one.
+#[derive(Debug, Default, Clone)]
+pub struct ProjectDefaults {
+    pub unsafe_policy: Option<UnsafePolicy>,
+    pub naming_policy: Option<NamingPolicy>,
+    pub blocked_namespaces: Vec<String>,
+}
+
 /// Allowlist configuration.
 #[derive(Hash, Debug)]
 pub enum Allowlist {
@@ -163,6 +379,49 @@ pub struct IncludeCppConfig {
     pub rust_types: Vec<RustPath>,
     pub subclasses: Vec<Subclass>,
     pub extern_rust_funs: Vec<RustFun>,
+    naming_policy: NamingPolicy,
+    strip_prefixes: Vec<String>,
+    generate_cfgs: Vec<(String, TokenStream)>,
+    out_params: Vec<(String, usize)>,
+    borrows_from: Vec<(String, usize)>,
+    slice_params: Vec<(String, usize, usize)>,
+    error_codes: Vec<(String, String)>,
+    last_errors: Vec<(String, String, String)>,
+    observer_registrations: Vec<(String, String)>,
+    result_types: Vec<(String, String, String)>,
+    smart_pointers: Vec<(String, String)>,
+    refcounted_types: Vec<(String, String, String)>,
+    unique_arrays: Vec<(String, UniqueArrayLength)>,
+    dense_matrices: Vec<(String, String)>,
+    concretes: Vec<(String, Ident)>,
+    volatile_fields: Vec<(String, String)>,
+    field_accessors: Vec<String>,
+    qt_mode: bool,
+    qt_signals: Vec<String>,
+    pair_accessors: bool,
+    instrument_calls: bool,
+    c_string_params: bool,
+    all_string_returns: bool,
+    string_returns: Vec<String>,
+    constructor_naming: ConstructorNaming,
+    bitflags: Vec<String>,
+    allowlist_files: Vec<String>,
+    blocked_namespaces: Vec<String>,
+    unsafe_policy_specified: bool,
+    naming_policy_specified: bool,
+    bridge_shards: usize,
+    extern_cpp_namespaces: Vec<(String, RustPath)>,
+    copy_pod_returns: Option<usize>,
+    generate_registry: bool,
+    dynamic_library: Option<String>,
+    force_wrappers: Vec<String>,
+    export_macros: Vec<String>,
+    async_wrappers: Vec<String>,
+    abi_self_test: bool,
+    auto_pod: bool,
+    non_pods: Vec<String>,
+    opaque_pod_pointers: bool,
+    unpin_pod_methods: bool,
 }
 
 impl Parse for IncludeCppConfig {
@@ -185,6 +444,49 @@ impl Parse for IncludeCppConfig {
         let mut mod_name = None;
         let mut subclasses = Vec::new();
         let mut extern_rust_funs = Vec::new();
+        let mut naming_policy = NamingPolicy::default();
+        let mut strip_prefixes = Vec::new();
+        let mut generate_cfgs = Vec::new();
+        let mut out_params = Vec::new();
+        let mut borrows_from = Vec::new();
+        let mut slice_params = Vec::new();
+        let mut error_codes = Vec::new();
+        let mut last_errors = Vec::new();
+        let mut observer_registrations = Vec::new();
+        let mut result_types = Vec::new();
+        let mut smart_pointers = Vec::new();
+        let mut refcounted_types = Vec::new();
+        let mut unique_arrays = Vec::new();
+        let mut volatile_fields = Vec::new();
+        let mut field_accessors = Vec::new();
+        let mut dense_matrices = Vec::new();
+        let mut concretes = Vec::new();
+        let mut qt_mode = false;
+        let mut qt_signals = Vec::new();
+        let mut pair_accessors = false;
+        let mut instrument_calls = false;
+        let mut c_string_params = false;
+        let mut all_string_returns = false;
+        let mut string_returns = Vec::new();
+        let mut constructor_naming = ConstructorNaming::default();
+        let mut bitflags = Vec::new();
+        let mut allowlist_files = Vec::new();
+        let mut blocked_namespaces = Vec::new();
+        let mut unsafe_policy_specified = false;
+        let mut naming_policy_specified = false;
+        let mut bridge_shards = 1;
+        let mut extern_cpp_namespaces = Vec::new();
+        let mut copy_pod_returns = None;
+        let mut generate_registry = false;
+        let mut dynamic_library = None;
+        let mut force_wrappers = Vec::new();
+        let mut export_macros = Vec::new();
+        let mut async_wrappers = Vec::new();
+        let mut abi_self_test = false;
+        let mut auto_pod = false;
+        let mut non_pods = Vec::new();
+        let mut opaque_pod_pointers = false;
+        let mut unpin_pod_methods = false;
 
         while !input.is_empty() {
             let has_hexathorpe = input.parse::<Option<syn::token::Pound>>()?.is_some();
@@ -201,7 +503,25 @@ impl Parse for IncludeCppConfig {
                     let args;
                     syn::parenthesized!(args in input);
                     let generate: syn::LitStr = args.parse()?;
+                    allowlist.push(
+                        AllowlistEntry::for_generate(generate.value()),
+                        generate.span(),
+                    )?;
+                } else if ident == "generate_cfg" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let generate: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let cfg: syn::LitStr = args.parse()?;
+                    let cfg_meta: syn::Meta = cfg.parse()?;
+                    if !matches!(&cfg_meta, syn::Meta::List(list) if list.path.is_ident("cfg")) {
+                        return Err(syn::Error::new(
+                            cfg.span(),
+                            "expected a condition of the form cfg(...)",
+                        ));
+                    }
                     allowlist.push(AllowlistEntry::Item(generate.value()), generate.span())?;
+                    generate_cfgs.push((generate.value(), cfg_meta.into_token_stream()));
                 } else if ident == "generate_ns" {
                     let args;
                     syn::parenthesized!(args in input);
@@ -224,11 +544,26 @@ impl Parse for IncludeCppConfig {
                     syn::parenthesized!(args in input);
                     let pod: syn::LitStr = args.parse()?;
                     pod_requests.push(pod.value());
+                } else if ident == "bitflags" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let flags: syn::LitStr = args.parse()?;
+                    bitflags.push(flags.value());
+                } else if ident == "allowlist_file" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let path: syn::LitStr = args.parse()?;
+                    allowlist_files.push(path.value());
                 } else if ident == "block" {
                     let args;
                     syn::parenthesized!(args in input);
                     let generate: syn::LitStr = args.parse()?;
                     blocklist.push(generate.value());
+                } else if ident == "block_ns" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ns: syn::LitStr = args.parse()?;
+                    blocked_namespaces.push(ns.value());
                 } else if ident == "block_constructors" {
                     let args;
                     syn::parenthesized!(args in input);
@@ -263,6 +598,36 @@ impl Parse for IncludeCppConfig {
                     syn::parenthesized!(args in input);
                     let ident: syn::Ident = args.parse()?;
                     mod_name = Some(ident);
+                } else if ident == "unpin_pod_methods" {
+                    unpin_pod_methods = true;
+                    swallow_parentheses(&input, &ident)?;
+                } else if ident == "pair_accessors" {
+                    pair_accessors = true;
+                    swallow_parentheses(&input, &ident)?;
+                } else if ident == "instrument_calls" {
+                    instrument_calls = true;
+                    swallow_parentheses(&input, &ident)?;
+                } else if ident == "qt_mode" {
+                    qt_mode = true;
+                    swallow_parentheses(&input, &ident)?;
+                } else if ident == "qt_signal" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let signal: syn::LitStr = args.parse()?;
+                    qt_signals.push(signal.value());
+                } else if ident == "c_string_params" {
+                    c_string_params = true;
+                    swallow_parentheses(&input, &ident)?;
+                } else if ident == "string_returns" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let funs: syn::punctuated::Punctuated<syn::LitStr, syn::token::Comma> =
+                        args.parse_terminated(|input| input.parse())?;
+                    if funs.is_empty() {
+                        all_string_returns = true;
+                    } else {
+                        string_returns.extend(funs.iter().map(|fun| fun.value()));
+                    }
                 } else if ident == "exclude_utilities" {
                     exclude_utilities = true;
                     swallow_parentheses(&input, &ident)?;
@@ -270,6 +635,7 @@ impl Parse for IncludeCppConfig {
                     let args;
                     syn::parenthesized!(args in input);
                     unsafe_policy = args.parse()?;
+                    unsafe_policy_specified = true;
                 } else if ident == "extern_rust_fun" {
                     let args;
                     syn::parenthesized!(args in input);
@@ -277,10 +643,258 @@ impl Parse for IncludeCppConfig {
                     args.parse::<syn::token::Comma>()?;
                     let sig: syn::Signature = args.parse()?;
                     extern_rust_funs.push(RustFun { path, sig });
+                } else if ident == "naming_policy" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    naming_policy = args.parse()?;
+                    naming_policy_specified = true;
+                } else if ident == "constructor_naming" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    constructor_naming = args.parse()?;
+                } else if ident == "strip_prefix" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let prefix: syn::LitStr = args.parse()?;
+                    strip_prefixes.push(prefix.value());
+                } else if ident == "out_param" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let fun: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let idx: syn::LitInt = args.parse()?;
+                    out_params.push((fun.value(), idx.base10_parse()?));
+                } else if ident == "borrows_from" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let fun: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let idx: syn::LitInt = args.parse()?;
+                    borrows_from.push((fun.value(), idx.base10_parse()?));
+                } else if ident == "slice_params" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let fun: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let ptr_idx: syn::LitInt = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let len_idx: syn::LitInt = args.parse()?;
+                    let (ptr_idx, len_idx) = (ptr_idx.base10_parse()?, len_idx.base10_parse()?);
+                    if ptr_idx == len_idx {
+                        return Err(syn::Error::new(
+                            fun.span(),
+                            "expected different pointer and length parameters",
+                        ));
+                    }
+                    slice_params.push((fun.value(), ptr_idx, len_idx));
+                } else if ident == "error_code" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let error_type: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let success: syn::LitStr = args.parse()?;
+                    if syn::parse_str::<Ident>(&success.value()).is_err() {
+                        return Err(syn::Error::new(
+                            success.span(),
+                            "expected the name of the enum variant representing success",
+                        ));
+                    }
+                    error_codes.push((error_type.value(), success.value()));
+                } else if ident == "last_error" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let fun: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let fetch: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let failure: syn::LitStr = args.parse()?;
+                    if fetch
+                        .value()
+                        .split("::")
+                        .any(|segment| syn::parse_str::<Ident>(segment).is_err())
+                    {
+                        return Err(syn::Error::new(
+                            fetch.span(),
+                            "expected the name of a C++ function returning the error",
+                        ));
+                    }
+                    last_errors.push((fun.value(), fetch.value(), failure.value()));
+                } else if ident == "observer_registration" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let add: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let remove: syn::LitStr = args.parse()?;
+                    observer_registrations.push((add.value(), remove.value()));
+                } else if ident == "result_type" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let template: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let value: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let error: syn::LitStr = args.parse()?;
+                    for accessor in [&value, &error] {
+                        if syn::parse_str::<Ident>(&accessor.value()).is_err() {
+                            return Err(syn::Error::new(
+                                accessor.span(),
+                                "expected the name of an accessor method",
+                            ));
+                        }
+                    }
+                    result_types.push((template.value(), value.value(), error.value()));
+                } else if ident == "smart_pointer" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let template: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let deref: syn::LitStr = args.parse()?;
+                    if syn::parse_str::<Ident>(&deref.value()).is_err() {
+                        return Err(syn::Error::new(
+                            deref.span(),
+                            "expected the name of the method returning the raw pointer",
+                        ));
+                    }
+                    smart_pointers.push((template.value(), deref.value()));
+                } else if ident == "refcounted" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let addref: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let release: syn::LitStr = args.parse()?;
+                    for fun in [&addref, &release] {
+                        if fun
+                            .value()
+                            .split("::")
+                            .any(|segment| syn::parse_str::<Ident>(segment).is_err())
+                        {
+                            return Err(syn::Error::new(
+                                fun.span(),
+                                "expected the name of a C++ function",
+                            ));
+                        }
+                    }
+                    refcounted_types.push((ty.value(), addref.value(), release.value()));
+                } else if ident == "unique_array" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let fun: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let len: UniqueArrayLength = args.parse()?;
+                    unique_arrays.push((fun.value(), len));
+                } else if ident == "dense_matrix" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let matrix: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let element: syn::LitStr = args.parse()?;
+                    if !DENSE_MATRIX_ELEMENT_TYPES.contains(&element.value().as_str()) {
+                        return Err(syn::Error::new(
+                            element.span(),
+                            "expected a Rust numeric type such as f64",
+                        ));
+                    }
+                    dense_matrices.push((matrix.value(), element.value()));
+                } else if ident == "concrete" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let definition: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let rust_id: Ident = args.parse()?;
+                    concretes.push((definition.value(), rust_id));
+                } else if ident == "volatile_field" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let field: syn::LitStr = args.parse()?;
+                    if syn::parse_str::<Ident>(&field.value()).is_err() {
+                        return Err(syn::Error::new(
+                            field.span(),
+                            "expected the name of a field",
+                        ));
+                    }
+                    volatile_fields.push((ty.value(), field.value()));
+                } else if ident == "field_accessors" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ty: syn::LitStr = args.parse()?;
+                    field_accessors.push(ty.value());
+                } else if ident == "bridge_shards" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let shards: syn::LitInt = args.parse()?;
+                    bridge_shards = shards.base10_parse()?;
+                    if bridge_shards == 0 {
+                        return Err(syn::Error::new(
+                            shards.span(),
+                            "expected at least one bridge shard",
+                        ));
+                    }
+                } else if ident == "copy_pod_returns" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let max_size: syn::LitInt = args.parse()?;
+                    copy_pod_returns = Some(max_size.base10_parse()?);
+                } else if ident == "generate_registry" {
+                    generate_registry = true;
+                    swallow_parentheses(&input, &ident)?;
+                } else if ident == "dynamic_library" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let library: syn::LitStr = args.parse()?;
+                    dynamic_library = Some(library.value());
+                } else if ident == "force_wrapper" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let funs: syn::punctuated::Punctuated<syn::LitStr, syn::token::Comma> =
+                        args.parse_terminated(|input| input.parse())?;
+                    force_wrappers.extend(funs.iter().map(|fun| fun.value()));
+                } else if ident == "export_macro" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let macros: syn::punctuated::Punctuated<syn::Ident, syn::token::Comma> =
+                        args.parse_terminated(|input| {
+                            let lit: syn::LitStr = input.parse()?;
+                            lit.parse()
+                        })?;
+                    export_macros.extend(macros.iter().map(|m| m.to_string()));
+                } else if ident == "async_wrapper" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let funs: syn::punctuated::Punctuated<syn::LitStr, syn::token::Comma> =
+                        args.parse_terminated(|input| input.parse())?;
+                    async_wrappers.extend(funs.iter().map(|fun| fun.value()));
+                } else if ident == "abi_self_test" {
+                    abi_self_test = true;
+                    swallow_parentheses(&input, &ident)?;
+                } else if ident == "auto_pod" {
+                    auto_pod = true;
+                    swallow_parentheses(&input, &ident)?;
+                } else if ident == "non_pod" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let non_pod: syn::LitStr = args.parse()?;
+                    non_pods.push(non_pod.value());
+                } else if ident == "opaque_pod_pointers" {
+                    opaque_pod_pointers = true;
+                    swallow_parentheses(&input, &ident)?;
+                } else if ident == "extern_cpp_ns" {
+                    let args;
+                    syn::parenthesized!(args in input);
+                    let ns: syn::LitStr = args.parse()?;
+                    args.parse::<syn::token::Comma>()?;
+                    let path: RustPath = args.parse()?;
+                    extern_cpp_namespaces.push((ns.value(), path));
                 } else {
                     return Err(syn::Error::new(
                         ident.span(),
-                        "expected generate, generate_pod, nested_type, safety or exclude_utilities",
+                        format!(
+                            "unknown directive {}; see https://docs.rs/autocxx/latest/autocxx/#macros for the directives which may be used within include_cpp!",
+                            ident
+                        ),
                     ));
                 }
             }
@@ -303,6 +917,49 @@ impl Parse for IncludeCppConfig {
             mod_name,
             subclasses,
             extern_rust_funs,
+            naming_policy,
+            strip_prefixes,
+            generate_cfgs,
+            out_params,
+            borrows_from,
+            slice_params,
+            error_codes,
+            last_errors,
+            observer_registrations,
+            result_types,
+            smart_pointers,
+            refcounted_types,
+            unique_arrays,
+            dense_matrices,
+            concretes,
+            volatile_fields,
+            field_accessors,
+            qt_mode,
+            qt_signals,
+            pair_accessors,
+            instrument_calls,
+            c_string_params,
+            all_string_returns,
+            string_returns,
+            constructor_naming,
+            bitflags,
+            allowlist_files,
+            blocked_namespaces,
+            unsafe_policy_specified,
+            naming_policy_specified,
+            bridge_shards,
+            extern_cpp_namespaces,
+            copy_pod_returns,
+            generate_registry,
+            dynamic_library,
+            force_wrappers,
+            export_macros,
+            async_wrappers,
+            abi_self_test,
+            auto_pod,
+            non_pods,
+            opaque_pod_pointers,
+            unpin_pod_methods,
         })
     }
 }
@@ -338,8 +995,327 @@ impl IncludeCppConfig {
         self.exclude_utilities
     }
 
+    /// How the user would like generated Rust methods to be named.
+    pub fn naming_policy(&self) -> NamingPolicy {
+        self.naming_policy
+    }
+
+    /// How the user would like overloaded constructors to be named.
+    pub fn constructor_naming(&self) -> ConstructorNaming {
+        self.constructor_naming
+    }
+
+    /// Whether mutable methods of POD types should take `&mut self`
+    /// rather than `self: Pin<&mut Self>`.
+    pub fn unpin_pod_methods(&self) -> bool {
+        self.unpin_pod_methods
+    }
+
+    /// Whether to rename matching `getFoo`/`setFoo` methods to
+    /// `foo`/`set_foo`.
+    pub fn pair_accessors(&self) -> bool {
+        self.pair_accessors
+    }
+
+    /// Whether to route every call into C++ through
+    /// `autocxx::instrumentation`, so that it can be traced or timed.
+    pub fn instrument_calls(&self) -> bool {
+        self.instrument_calls
+    }
+
+    /// Whether `const char*` parameters should accept a `&CStr` rather
+    /// than a raw pointer.
+    pub fn c_string_params(&self) -> bool {
+        self.c_string_params
+    }
+
+    /// Pairs of methods which register and deregister a listener, for which
+    /// a registration guard should be generated. Each is given by its full
+    /// C++ name.
+    pub fn observer_registrations(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.observer_registrations
+            .iter()
+            .map(|(add, remove)| (add.as_str(), remove.as_str()))
+    }
+
+    /// Whether to expect classes processed by Qt's moc, and so hide the
+    /// members which `Q_OBJECT` declares for moc's own use.
+    pub fn qt_mode(&self) -> bool {
+        self.qt_mode
+    }
+
+    /// The full C++ names of Qt signals for which we should generate a
+    /// means of connecting a Rust closure.
+    pub fn qt_signals(&self) -> impl Iterator<Item = &str> {
+        self.qt_signals.iter().map(String::as_str)
+    }
+
+    /// Whether this function, if it returns a `std::string` by value,
+    /// should return a Rust `String` instead.
+    pub fn string_return_for(&self, cpp_name: &str) -> bool {
+        self.all_string_returns || self.string_returns.iter().any(|fun| fun == cpp_name)
+    }
+
+    /// How many `cxx::bridge` mods the generated function bindings should
+    /// be spread across. More shards means less work for each expansion
+    /// of the `cxx::bridge` macro.
+    pub fn bridge_shards(&self) -> usize {
+        self.bridge_shards
+    }
+
+    /// The largest POD type, in bytes, which functions returning a
+    /// `const` reference to it should instead return by value, if the
+    /// user asked for that with `copy_pod_returns!`.
+    pub fn copy_pod_returns_max_size(&self) -> Option<usize> {
+        self.copy_pod_returns
+    }
+
+    /// Whether to generate an `autocxx::reflection::Registry` describing
+    /// the generated types and functions.
+    pub fn generate_registry(&self) -> bool {
+        self.generate_registry
+    }
+
+    /// The shared library from which functions should be looked up at
+    /// runtime, rather than being linked, if the user asked for that with
+    /// `dynamic_library!`.
+    pub fn dynamic_library(&self) -> Option<&str> {
+        self.dynamic_library.as_deref()
+    }
+
+    /// Whether this function should always be called through a C++
+    /// wrapper, even if autocxx would otherwise let cxx call it directly.
+    pub fn force_wrapper_for(&self, cpp_name: &str) -> bool {
+        self.force_wrappers.iter().any(|fun| fun == cpp_name)
+    }
+
+    /// The macros with which the C++ library marks the functions it
+    /// exports, if the user told us with `export_macro!` that it's built
+    /// with `-fvisibility=hidden`.
+    pub fn export_macros(&self) -> &[String] {
+        &self.export_macros
+    }
+
+    /// Whether this function should also get an `_async` variant which
+    /// makes the call on a thread suitable for blocking work.
+    pub fn async_wrapper_for(&self, cpp_name: &str) -> bool {
+        self.async_wrappers.iter().any(|fun| fun == cpp_name)
+    }
+
+    /// Whether to generate tests which check that Rust and C++ agree on
+    /// the layout of each POD type.
+    pub fn abi_self_test(&self) -> bool {
+        self.abi_self_test
+    }
+
+    /// Whether to make every type POD which we can prove is safe to be
+    /// held by value in Rust, as well as those named in `generate_pod!`.
+    pub fn auto_pod(&self) -> bool {
+        self.auto_pod
+    }
+
+    /// Whether the user has asked that this type shouldn't be made POD
+    /// automatically, even if it could be.
+    pub fn is_non_pod(&self, cpp_name: &str) -> bool {
+        self.non_pods.iter().any(|ty| ty == cpp_name)
+    }
+
+    /// Whether POD types may have pointer fields whose pointee we can't
+    /// represent, in which case such fields become `c_void` pointers.
+    pub fn opaque_pod_pointers(&self) -> bool {
+        self.opaque_pod_pointers
+    }
+
+    /// If types in this C++ namespace are generated by some other
+    /// `include_cpp!`, typically in another crate, the path to the mod
+    /// which that generated. Nested namespaces count too.
+    pub fn extern_cpp_ns_path(&self, ns: &str) -> Option<&RustPath> {
+        self.extern_cpp_namespaces
+            .iter()
+            .find(|(extern_ns, _)| {
+                ns == extern_ns
+                    || ns
+                        .strip_prefix(extern_ns.as_str())
+                        .map(|rest| rest.starts_with("::"))
+                        .unwrap_or(false)
+            })
+            .map(|(_, path)| path)
+    }
+
+    /// Note that types in this C++ namespace are generated by an identical
+    /// `include_cpp!` within the crate `crate_name`, as `extern_cpp_ns!`
+    /// would.
+    pub fn add_extern_cpp_ns(&mut self, ns: String, crate_name: Ident) {
+        let path = RustPath::new_from_ident(crate_name).append(self.get_mod_name());
+        self.extern_cpp_namespaces.push((ns, path));
+    }
+
+    /// Generate only what's needed from this C++ namespace, instead of
+    /// whatever the allowlist said before.
+    pub fn restrict_to_namespace(&mut self, ns: String) {
+        self.allowlist = Allowlist::Specific(vec![AllowlistEntry::Namespace(ns)]);
+    }
+
+    /// If this name starts with one of the prefixes which the user asked
+    /// us to strip, return the remainder. Names are left alone if the
+    /// remainder wouldn't be a valid Rust identifier.
+    pub fn strip_prefix<'a>(&self, name: &'a str) -> Option<&'a str> {
+        self.strip_prefixes
+            .iter()
+            .filter_map(|prefix| name.strip_prefix(prefix.as_str()))
+            .find(|remainder| syn::parse_str::<Ident>(remainder).is_ok())
+    }
+
+    /// Any `cfg(...)` condition attached to this item using `generate_cfg!`.
+    /// Items are generated only if they're present in the C++ headers on the
+    /// current platform, but the condition is then attached to the Rust APIs
+    /// exposed for them.
+    pub fn cfg_for(&self, cpp_name: &str) -> Option<&TokenStream> {
+        self.generate_cfgs
+            .iter()
+            .find(|(item, _)| item == cpp_name)
+            .map(|(_, cfg)| cfg)
+    }
+
+    /// The index of the parameter which the user has asked to be treated
+    /// as an out parameter for this function, if any. The index excludes
+    /// any `this` parameter.
+    pub fn out_param_for(&self, cpp_name: &str) -> Option<usize> {
+        self.out_params
+            .iter()
+            .find(|(fun, _)| fun == cpp_name)
+            .map(|(_, idx)| *idx)
+    }
+
+    /// The index of the parameter from which the user has asked for this
+    /// function's returned reference to borrow, if any. The index excludes
+    /// any `this` parameter.
+    pub fn borrowed_param_for(&self, cpp_name: &str) -> Option<usize> {
+        self.borrows_from
+            .iter()
+            .find(|(fun, _)| fun == cpp_name)
+            .map(|(_, idx)| *idx)
+    }
+
+    /// The (pointer, length) parameter index pairs which the user has asked
+    /// to be combined into a single slice parameter for this function. The
+    /// indices exclude any `this` parameter.
+    pub fn slice_params_for<'a>(
+        &'a self,
+        cpp_name: &'a str,
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.slice_params
+            .iter()
+            .filter(move |(fun, _, _)| fun == cpp_name)
+            .map(|(_, ptr_idx, len_idx)| (*ptr_idx, *len_idx))
+    }
+
+    /// If the user has asked for functions returning instantiations of this
+    /// template to return a `Result` instead, the names of the methods which
+    /// access its value and its error.
+    pub fn result_type_accessors(&self, template: &str) -> Option<(&str, &str)> {
+        self.result_types
+            .iter()
+            .find(|(name, _, _)| name == template)
+            .map(|(_, value, error)| (value.as_str(), error.as_str()))
+    }
+
+    /// If the user has told us that this template is a smart pointer, the
+    /// name of the method which returns the raw pointer it holds.
+    pub fn smart_pointer_deref(&self, template: &str) -> Option<&str> {
+        self.smart_pointers
+            .iter()
+            .find(|(name, _)| name == template)
+            .map(|(_, deref)| deref.as_str())
+    }
+
+    /// If the user has told us that this class is reference-counted, the
+    /// names of the functions which add and release a reference.
+    pub fn refcounted_functions(&self, cpp_name: &str) -> Option<(&str, &str)> {
+        self.refcounted_types
+            .iter()
+            .find(|(ty, _, _)| ty == cpp_name)
+            .map(|(_, addref, release)| (addref.as_str(), release.as_str()))
+    }
+
+    /// Where to find the length of the array returned by this function as
+    /// a `std::unique_ptr<T[]>`, if the user has told us.
+    pub fn unique_array_length_for(&self, cpp_name: &str) -> Option<&UniqueArrayLength> {
+        self.unique_arrays
+            .iter()
+            .find(|(fun, _)| fun == cpp_name)
+            .map(|(_, len)| len)
+    }
+
+    /// The dense matrix types for which conversions to and from slices
+    /// should be generated, along with the Rust type of their elements.
+    pub fn dense_matrices(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.dense_matrices
+            .iter()
+            .map(|(matrix, element)| (matrix.as_str(), element.as_str()))
+    }
+
+    /// The template instantiations which the user has asked us to name,
+    /// along with the name each should have in Rust.
+    pub fn concretes(&self) -> impl Iterator<Item = (&str, &Ident)> {
+        self.concretes
+            .iter()
+            .map(|(definition, rust_id)| (definition.as_str(), rust_id))
+    }
+
+    /// The fields of this POD type which the user has told us are
+    /// `volatile`, so must only be accessed using volatile reads and writes.
+    pub fn volatile_fields<'a>(&'a self, cpp_name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.volatile_fields
+            .iter()
+            .filter(move |(ty, _)| ty == cpp_name)
+            .map(|(_, field)| field.as_str())
+    }
+
+    /// Whether we should generate methods to get and set the public fields
+    /// of this non-POD type.
+    pub fn field_accessors_for(&self, cpp_name: &str) -> bool {
+        self.field_accessors.iter().any(|ty| ty == cpp_name)
+    }
+
+    /// If the user has asked for functions returning this enum to return
+    /// a `Result` instead, the name of the variant which represents success.
+    pub fn error_code_success_value(&self, cpp_name: &str) -> Option<&str> {
+        self.error_codes
+            .iter()
+            .find(|(error_type, _)| error_type == cpp_name)
+            .map(|(_, success)| success.as_str())
+    }
+
+    /// If the user has asked for this function to return a `Result`
+    /// holding the error from an errno-style function, the name of that
+    /// function and the C++ expression to which a failed call's return
+    /// value is equal.
+    pub fn last_error_for(&self, cpp_name: &str) -> Option<(&str, &str)> {
+        self.last_errors
+            .iter()
+            .find(|(fun, _, _)| fun == cpp_name)
+            .map(|(_, fetch, failure)| (fetch.as_str(), failure.as_str()))
+    }
+
+    /// Whether this function returns the error for some other function
+    /// named in a `last_error!` directive.
+    pub fn is_last_error_fetcher(&self, cpp_name: &str) -> bool {
+        self.last_errors
+            .iter()
+            .any(|(_, fetch, _)| fetch == cpp_name)
+    }
+
+    /// Whether the user has asked for this enum to be represented as a
+    /// set of bitflags rather than as a Rust enum.
+    pub fn is_bitflags(&self, cpp_name: &str) -> bool {
+        self.bitflags.iter().any(|item| item == cpp_name)
+    }
+
     /// Items which the user has explicitly asked us to generate;
     /// we should raise an error if we weren't able to do so.
+    /// Items subject to a `generate_cfg!` condition may legitimately be
+    /// absent on this platform, so they're not included.
     pub fn must_generate_list(&self) -> Box<dyn Iterator<Item = String> + '_> {
         if let Allowlist::Specific(items) = &self.allowlist {
             Box::new(
@@ -347,8 +1323,9 @@ impl IncludeCppConfig {
                     .iter()
                     .filter_map(|i| match i {
                         AllowlistEntry::Item(i) => Some(i),
-                        AllowlistEntry::Namespace(_) => None,
+                        AllowlistEntry::Namespace(_) | AllowlistEntry::Pattern(_) => None,
                     })
+                    .filter(|i| self.cfg_for(i).is_none())
                     .chain(self.pod_requests.iter())
                     .cloned(),
             )
@@ -366,6 +1343,13 @@ impl IncludeCppConfig {
                     .iter()
                     .map(AllowlistEntry::to_bindgen_item)
                     .chain(self.pod_requests.iter().cloned())
+                    .chain(self.dense_matrices.iter().map(|(matrix, _)| matrix.clone()))
+                    .chain(self.last_errors.iter().map(|(_, fetch, _)| fetch.clone()))
+                    .chain(
+                        self.concretes
+                            .iter()
+                            .map(|(_, rust_id)| rust_id.to_string()),
+                    )
                     .chain(self.active_utilities())
                     .chain(self.subclasses.iter().flat_map(|sc| {
                         [
@@ -413,26 +1397,50 @@ impl IncludeCppConfig {
             || self.is_subclass_holder(cpp_name)
             || self.is_subclass_cpp(cpp_name)
             || self.is_rust_fun(cpp_name)
+            || self
+                .dense_matrices
+                .iter()
+                .any(|(matrix, _)| matrix == cpp_name)
+            || self.is_last_error_fetcher(cpp_name)
+            || self
+                .concretes
+                .iter()
+                .any(|(_, rust_id)| rust_id == cpp_name)
             || match &self.allowlist {
                 Allowlist::Unspecified(_) => panic!("Eek no allowlist yet"),
                 Allowlist::All => true,
                 Allowlist::Specific(items) => items.iter().any(|entry| match entry {
                     AllowlistEntry::Item(i) => i == cpp_name,
                     AllowlistEntry::Namespace(ns) => cpp_name.starts_with(ns),
+                    AllowlistEntry::Pattern(pattern) => matches_pattern(pattern, cpp_name),
                 }),
             }
     }
 
     pub fn is_on_blocklist(&self, cpp_name: &str) -> bool {
-        self.blocklist.contains(&cpp_name.to_string())
+        self.blocklist.iter().any(|item| {
+            if is_pattern(item) {
+                matches_pattern(item, cpp_name)
+            } else {
+                item == cpp_name
+            }
+        }) || self.blocked_namespaces.iter().any(|ns| {
+            cpp_name
+                .strip_prefix(ns.as_str())
+                .map(|rest| rest.starts_with("::"))
+                .unwrap_or(false)
+        })
     }
 
     pub fn is_on_constructor_blocklist(&self, cpp_name: &str) -> bool {
         self.constructor_blocklist.contains(&cpp_name.to_string())
     }
 
+    /// The items named by `block!` directives. Patterns aren't included,
+    /// since they can only be checked against particular names, using
+    /// [`Self::is_on_blocklist`].
     pub fn get_blocklist(&self) -> impl Iterator<Item = &String> {
-        self.blocklist.iter()
+        self.blocklist.iter().filter(|item| !is_pattern(item))
     }
 
     /// In case there are multiple sets of ffi mods in a single binary,
@@ -492,6 +1500,52 @@ impl IncludeCppConfig {
         )
     }
 
+    /// Files, named in `allowlist_file!` directives, from which further
+    /// allowlist entries should be read.
+    pub fn allowlist_files(&self) -> &[String] {
+        &self.allowlist_files
+    }
+
+    /// Add the contents of an external allowlist file. Once this has been
+    /// called we consider the allowlist to be specified, even if the file
+    /// turned out to be empty.
+    pub fn add_external_allowlist(&mut self, external: ExternalAllowlist) -> ParseResult<()> {
+        for item in external.generate {
+            self.allowlist
+                .push(AllowlistEntry::for_generate(item), Span::call_site())?;
+        }
+        for item in external.generate_pod {
+            self.pod_requests.push(item.clone());
+            self.allowlist
+                .push(AllowlistEntry::Item(item), Span::call_site())?;
+        }
+        for ns in external.generate_ns {
+            self.allowlist
+                .push(AllowlistEntry::Namespace(ns), Span::call_site())?;
+        }
+        self.blocklist.extend(external.block);
+        if let Allowlist::Unspecified(items) = &mut self.allowlist {
+            self.allowlist = Allowlist::Specific(std::mem::take(items));
+        }
+        Ok(())
+    }
+
+    /// Apply settings shared by every `include_cpp!` in a project. Settings
+    /// given explicitly within this `include_cpp!` take precedence.
+    pub fn apply_project_defaults(&mut self, defaults: ProjectDefaults) {
+        if !self.unsafe_policy_specified {
+            if let Some(unsafe_policy) = defaults.unsafe_policy {
+                self.unsafe_policy = unsafe_policy;
+            }
+        }
+        if !self.naming_policy_specified {
+            if let Some(naming_policy) = defaults.naming_policy {
+                self.naming_policy = naming_policy;
+            }
+        }
+        self.blocked_namespaces.extend(defaults.blocked_namespaces);
+    }
+
     pub fn confirm_complete(&mut self, auto_allowlist: bool) -> ParseResult<()> {
         if matches!(self.allowlist, Allowlist::Unspecified(_)) {
             if auto_allowlist {
@@ -538,35 +1592,175 @@ impl ToTokens for IncludeCppConfig {
         if self.exclude_utilities {
             tokens.extend(quote! { exclude_utilities!() });
         }
+        if self.pair_accessors {
+            tokens.extend(quote! { pair_accessors!() });
+        }
+        if self.instrument_calls {
+            tokens.extend(quote! { instrument_calls!() });
+        }
+        if self.c_string_params {
+            tokens.extend(quote! { c_string_params!() });
+        }
+        if self.qt_mode {
+            tokens.extend(quote! { qt_mode!() });
+        }
+        for signal in &self.qt_signals {
+            tokens.extend(quote! { qt_signal!(#signal) });
+        }
+        if self.all_string_returns {
+            tokens.extend(quote! { string_returns!() });
+        }
+        if !self.string_returns.is_empty() {
+            let funs = &self.string_returns;
+            tokens.extend(quote! { string_returns!(#(#funs),*) });
+        }
         for i in &self.pod_requests {
             tokens.extend(quote! { pod!(#i) });
         }
+        for i in &self.bitflags {
+            tokens.extend(quote! { bitflags!(#i) });
+        }
         for i in &self.blocklist {
             tokens.extend(quote! { block!(#i) });
         }
+        for ns in &self.blocked_namespaces {
+            tokens.extend(quote! { block_ns!(#ns) });
+        }
         for i in &self.constructor_blocklist {
             tokens.extend(quote! { block_constructors!(#i) });
         }
         for path in &self.rust_types {
             tokens.extend(quote! { rust_type!(#path) });
         }
+        // allowlist_file! is deliberately not reproduced: by now its contents
+        // have been merged into the allowlist, pod requests and blocklist.
         match &self.allowlist {
             Allowlist::All => tokens.extend(quote! { generate_all!() }),
             Allowlist::Specific(items) => {
                 for i in items {
                     match i {
-                        AllowlistEntry::Item(i) => tokens.extend(quote! { generate!(#i) }),
+                        AllowlistEntry::Item(i) => match self.cfg_for(i) {
+                            Some(cfg) => {
+                                let cfg = cfg.to_string();
+                                tokens.extend(quote! { generate_cfg!(#i, #cfg) })
+                            }
+                            None => tokens.extend(quote! { generate!(#i) }),
+                        },
                         AllowlistEntry::Namespace(ns) => {
                             tokens.extend(quote! { generate_ns!(#ns) })
                         }
+                        AllowlistEntry::Pattern(pattern) => {
+                            tokens.extend(quote! { generate!(#pattern) })
+                        }
                     }
                 }
             }
             Allowlist::Unspecified(_) => panic!("Allowlist mode not yet determined"),
         }
+        if self.naming_policy != NamingPolicy::Preserve {
+            let naming_policy = &self.naming_policy;
+            tokens.extend(quote! { naming_policy!(#naming_policy) });
+        }
+        if self.constructor_naming != ConstructorNaming::Numbered {
+            let constructor_naming = &self.constructor_naming;
+            tokens.extend(quote! { constructor_naming!(#constructor_naming) });
+        }
+        for i in &self.strip_prefixes {
+            tokens.extend(quote! { strip_prefix!(#i) });
+        }
+        for (fun, idx) in &self.out_params {
+            let idx = syn::LitInt::new(&idx.to_string(), Span::call_site());
+            tokens.extend(quote! { out_param!(#fun, #idx) });
+        }
+        for (fun, idx) in &self.borrows_from {
+            let idx = syn::LitInt::new(&idx.to_string(), Span::call_site());
+            tokens.extend(quote! { borrows_from!(#fun, #idx) });
+        }
+        for (fun, ptr_idx, len_idx) in &self.slice_params {
+            let ptr_idx = syn::LitInt::new(&ptr_idx.to_string(), Span::call_site());
+            let len_idx = syn::LitInt::new(&len_idx.to_string(), Span::call_site());
+            tokens.extend(quote! { slice_params!(#fun, #ptr_idx, #len_idx) });
+        }
+        for (error_type, success) in &self.error_codes {
+            tokens.extend(quote! { error_code!(#error_type, #success) });
+        }
+        for (fun, fetch, failure) in &self.last_errors {
+            tokens.extend(quote! { last_error!(#fun, #fetch, #failure) });
+        }
+        for (add, remove) in &self.observer_registrations {
+            tokens.extend(quote! { observer_registration!(#add, #remove) });
+        }
+        for (template, value, error) in &self.result_types {
+            tokens.extend(quote! { result_type!(#template, #value, #error) });
+        }
+        for (template, deref) in &self.smart_pointers {
+            tokens.extend(quote! { smart_pointer!(#template, #deref) });
+        }
+        for (ty, addref, release) in &self.refcounted_types {
+            tokens.extend(quote! { refcounted!(#ty, #addref, #release) });
+        }
+        for (fun, len) in &self.unique_arrays {
+            tokens.extend(quote! { unique_array!(#fun, #len) });
+        }
+        for (matrix, element) in &self.dense_matrices {
+            tokens.extend(quote! { dense_matrix!(#matrix, #element) });
+        }
+        for (definition, rust_id) in &self.concretes {
+            tokens.extend(quote! { concrete!(#definition, #rust_id) });
+        }
+        for (ty, field) in &self.volatile_fields {
+            tokens.extend(quote! { volatile_field!(#ty, #field) });
+        }
+        for ty in &self.field_accessors {
+            tokens.extend(quote! { field_accessors!(#ty) });
+        }
         if let Some(mod_name) = &self.mod_name {
             tokens.extend(quote! { mod_name!(#mod_name) });
         }
+        if self.bridge_shards != 1 {
+            let shards = syn::LitInt::new(&self.bridge_shards.to_string(), Span::call_site());
+            tokens.extend(quote! { bridge_shards!(#shards) });
+        }
+        for (ns, path) in &self.extern_cpp_namespaces {
+            tokens.extend(quote! { extern_cpp_ns!(#ns, #path) });
+        }
+        if let Some(max_size) = self.copy_pod_returns {
+            let max_size = syn::LitInt::new(&max_size.to_string(), Span::call_site());
+            tokens.extend(quote! { copy_pod_returns!(#max_size) });
+        }
+        if self.generate_registry {
+            tokens.extend(quote! { generate_registry!() });
+        }
+        if let Some(library) = &self.dynamic_library {
+            tokens.extend(quote! { dynamic_library!(#library) });
+        }
+        if !self.force_wrappers.is_empty() {
+            let funs = &self.force_wrappers;
+            tokens.extend(quote! { force_wrapper!(#(#funs),*) });
+        }
+        if !self.export_macros.is_empty() {
+            let macros = &self.export_macros;
+            tokens.extend(quote! { export_macro!(#(#macros),*) });
+        }
+        if !self.async_wrappers.is_empty() {
+            let funs = &self.async_wrappers;
+            tokens.extend(quote! { async_wrapper!(#(#funs),*) });
+        }
+        if self.abi_self_test {
+            tokens.extend(quote! { abi_self_test!() });
+        }
+        if self.auto_pod {
+            tokens.extend(quote! { auto_pod!() });
+        }
+        for ty in &self.non_pods {
+            tokens.extend(quote! { non_pod!(#ty) });
+        }
+        if self.unpin_pod_methods {
+            tokens.extend(quote! { unpin_pod_methods!() });
+        }
+        if self.opaque_pod_pointers {
+            tokens.extend(quote! { opaque_pod_pointers!() });
+        }
         for i in &self.extern_rust_funs {
             let p = &i.path;
             let s = &i.sig;
@@ -582,7 +1776,10 @@ impl ToTokens for IncludeCppConfig {
 
 #[cfg(test)]
 mod parse_tests {
-    use crate::config::UnsafePolicy;
+    use crate::config::{
+        matches_pattern, pattern_to_regex, ConstructorNaming, ExternalAllowlist, IncludeCppConfig,
+        NamingPolicy, ProjectDefaults, UniqueArrayLength, UnsafePolicy,
+    };
     use syn::parse_quote;
     #[test]
     fn test_safety_unsafe() {
@@ -605,4 +1802,513 @@ mod parse_tests {
         let us: UnsafePolicy = parse_quote! {};
         assert_eq!(us, UnsafePolicy::AllFunctionsUnsafe)
     }
+
+    #[test]
+    fn test_generate_cfg() {
+        let config: IncludeCppConfig = parse_quote! {
+            generate_cfg!("WinOnlyThing", r#"cfg(windows)"#)
+            generate!("Everywhere")
+        };
+        assert_eq!(
+            config.cfg_for("WinOnlyThing").unwrap().to_string(),
+            "cfg (windows)"
+        );
+        assert!(config.cfg_for("Everywhere").is_none());
+        assert_eq!(
+            config.must_generate_list().collect::<Vec<_>>(),
+            ["Everywhere"]
+        );
+    }
+
+    #[test]
+    fn test_generate_cfg_rejects_other_attributes() {
+        let config: syn::Result<IncludeCppConfig> =
+            syn::parse_str(r#"generate_cfg!("WinOnlyThing", "doc(hidden)")"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_unknown_directive() {
+        let err = syn::parse_str::<IncludeCppConfig>(r#"generate_podd!("Foo")"#).unwrap_err();
+        assert!(err.to_string().contains("unknown directive generate_podd"));
+    }
+
+    #[test]
+    fn test_out_param() {
+        let config: IncludeCppConfig = parse_quote! {
+            out_param!("ns::get", 1)
+        };
+        assert_eq!(config.out_param_for("ns::get"), Some(1));
+        assert_eq!(config.out_param_for("get"), None);
+    }
+
+    #[test]
+    fn test_borrows_from() {
+        let config: IncludeCppConfig = parse_quote! {
+            borrows_from!("ns::pick", 1)
+        };
+        assert_eq!(config.borrowed_param_for("ns::pick"), Some(1));
+        assert_eq!(config.borrowed_param_for("pick"), None);
+    }
+
+    #[test]
+    fn test_unique_array() {
+        let config: IncludeCppConfig = parse_quote! {
+            unique_array!("make_buffer", 0)
+            unique_array!("Image::pixels", "pixel_count")
+        };
+        assert_eq!(
+            config.unique_array_length_for("make_buffer"),
+            Some(&UniqueArrayLength::Param(0))
+        );
+        assert_eq!(
+            config.unique_array_length_for("Image::pixels"),
+            Some(&UniqueArrayLength::Function("pixel_count".into()))
+        );
+        assert_eq!(config.unique_array_length_for("pixels"), None);
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"unique_array!("make_buffer", "not a function")"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_c_string_params() {
+        let config: IncludeCppConfig = parse_quote! {
+            c_string_params!()
+        };
+        assert!(config.c_string_params());
+        let config: IncludeCppConfig = parse_quote! {
+            generate!("foo")
+        };
+        assert!(!config.c_string_params());
+    }
+
+    #[test]
+    fn test_string_returns() {
+        let config: IncludeCppConfig = parse_quote! {
+            string_returns!("ns::name", "ns::Foo::describe")
+        };
+        assert!(config.string_return_for("ns::name"));
+        assert!(config.string_return_for("ns::Foo::describe"));
+        assert!(!config.string_return_for("ns::other"));
+        let config: IncludeCppConfig = parse_quote! {
+            string_returns!()
+        };
+        assert!(config.string_return_for("ns::other"));
+    }
+
+    #[test]
+    fn test_slice_params() {
+        let config: IncludeCppConfig = parse_quote! {
+            slice_params!("ns::write", 0, 1)
+            slice_params!("ns::write", 2, 3)
+        };
+        assert_eq!(
+            config.slice_params_for("ns::write").collect::<Vec<_>>(),
+            [(0, 1), (2, 3)]
+        );
+        assert_eq!(config.slice_params_for("write").count(), 0);
+        let config: syn::Result<IncludeCppConfig> =
+            syn::parse_str(r#"slice_params!("ns::write", 1, 1)"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_observer_registration() {
+        let config: IncludeCppConfig = parse_quote! {
+            observer_registration!("ns::Subject::addListener", "ns::Subject::removeListener")
+        };
+        assert_eq!(
+            config.observer_registrations().collect::<Vec<_>>(),
+            [("ns::Subject::addListener", "ns::Subject::removeListener")]
+        );
+    }
+
+    #[test]
+    fn test_qt() {
+        let config: IncludeCppConfig = parse_quote! {
+            qt_mode!()
+            qt_signal!("ns::Counter::valueChanged")
+        };
+        assert!(config.qt_mode());
+        assert_eq!(
+            config.qt_signals().collect::<Vec<_>>(),
+            ["ns::Counter::valueChanged"]
+        );
+    }
+
+    #[test]
+    fn test_result_type() {
+        let config: IncludeCppConfig = parse_quote! {
+            result_type!("tl::expected", "value", "error")
+        };
+        assert_eq!(
+            config.result_type_accessors("tl::expected"),
+            Some(("value", "error"))
+        );
+        assert_eq!(config.result_type_accessors("std::optional"), None);
+    }
+
+    #[test]
+    fn test_smart_pointer() {
+        let config: IncludeCppConfig = parse_quote! {
+            smart_pointer!("RefPtr", "get")
+        };
+        assert_eq!(config.smart_pointer_deref("RefPtr"), Some("get"));
+        assert_eq!(config.smart_pointer_deref("std::shared_ptr"), None);
+    }
+
+    #[test]
+    fn test_refcounted() {
+        let config: IncludeCppConfig = parse_quote! {
+            refcounted!("SkData", "SkSafeRef", "SkSafeUnref")
+        };
+        assert_eq!(
+            config.refcounted_functions("SkData"),
+            Some(("SkSafeRef", "SkSafeUnref"))
+        );
+        assert_eq!(config.refcounted_functions("SkString"), None);
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"refcounted!("SkData", "SkData::ref()", "SkSafeUnref")"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_dense_matrix() {
+        let mut config: IncludeCppConfig = parse_quote! {
+            generate!("solve")
+            dense_matrix!("Eigen::MatrixXd", "f64")
+        };
+        config.confirm_complete(false).unwrap();
+        assert_eq!(
+            config.dense_matrices().collect::<Vec<_>>(),
+            [("Eigen::MatrixXd", "f64")]
+        );
+        assert!(config.is_on_allowlist("Eigen::MatrixXd"));
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"dense_matrix!("Eigen::MatrixXd", "double")"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_concrete() {
+        let mut config: IncludeCppConfig = parse_quote! {
+            generate!("take_map")
+            concrete!("StringMap<int>", IntStringMap)
+        };
+        config.confirm_complete(false).unwrap();
+        assert_eq!(
+            config
+                .concretes()
+                .map(|(definition, rust_id)| (definition, rust_id.to_string()))
+                .collect::<Vec<_>>(),
+            [("StringMap<int>", "IntStringMap".to_string())]
+        );
+        assert!(config.is_on_allowlist("IntStringMap"));
+        assert!(config
+            .bindgen_allowlist()
+            .unwrap()
+            .any(|item| item == "IntStringMap"));
+        assert!(syn::parse_str::<IncludeCppConfig>(
+            r#"concrete!("StringMap<int>", "IntStringMap")"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_volatile_field() {
+        let config: IncludeCppConfig = parse_quote! {
+            volatile_field!("Regs", "status")
+            volatile_field!("Regs", "control")
+        };
+        assert_eq!(
+            config.volatile_fields("Regs").collect::<Vec<_>>(),
+            ["status", "control"]
+        );
+        assert_eq!(config.volatile_fields("Other").count(), 0);
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"volatile_field!("Regs", "a.b")"#).is_err());
+    }
+
+    #[test]
+    fn test_field_accessors() {
+        let config: IncludeCppConfig = parse_quote! {
+            field_accessors!("ns::Config")
+        };
+        assert!(config.field_accessors_for("ns::Config"));
+        assert!(!config.field_accessors_for("ns::Other"));
+    }
+
+    #[test]
+    fn test_error_code() {
+        let config: IncludeCppConfig = parse_quote! {
+            error_code!("VkResult", "VK_SUCCESS")
+        };
+        assert_eq!(
+            config.error_code_success_value("VkResult"),
+            Some("VK_SUCCESS")
+        );
+        assert_eq!(config.error_code_success_value("VkOther"), None);
+    }
+
+    #[test]
+    fn test_last_error() {
+        let mut config: IncludeCppConfig = parse_quote! {
+            generate!("open")
+            last_error!("open", "get_errno", "-1")
+        };
+        config.confirm_complete(false).unwrap();
+        assert_eq!(config.last_error_for("open"), Some(("get_errno", "-1")));
+        assert_eq!(config.last_error_for("close"), None);
+        assert!(config.is_last_error_fetcher("get_errno"));
+        assert!(config.is_on_allowlist("get_errno"));
+        assert!(
+            syn::parse_str::<IncludeCppConfig>(r#"last_error!("open", "errno()", "-1")"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_bitflags() {
+        let config: IncludeCppConfig = parse_quote! {
+            generate!("ns::Flags")
+            bitflags!("ns::Flags")
+        };
+        assert!(config.is_bitflags("ns::Flags"));
+        assert!(!config.is_bitflags("Flags"));
+    }
+
+    #[test]
+    fn test_block_ns() {
+        let mut config: IncludeCppConfig = parse_quote! {
+            block_ns!("a::internal")
+        };
+        assert!(config.is_on_blocklist("a::internal::Foo"));
+        assert!(!config.is_on_blocklist("a::internal_api::Foo"));
+        assert!(!config.is_on_blocklist("a::Foo"));
+        config.apply_project_defaults(ProjectDefaults {
+            blocked_namespaces: vec!["b".into()],
+            ..Default::default()
+        });
+        assert!(config.is_on_blocklist("b::Foo"));
+    }
+
+    #[test]
+    fn test_wildcard_patterns() {
+        let config: IncludeCppConfig = parse_quote! {
+            generate!("mylib::net::*")
+            generate!("mylib::Widget")
+            block!("*_internal")
+        };
+        assert!(config.is_on_allowlist("mylib::net::Socket"));
+        assert!(config.is_on_allowlist("mylib::net::tcp::connect"));
+        assert!(config.is_on_allowlist("mylib::Widget"));
+        assert!(!config.is_on_allowlist("mylib::network"));
+        assert!(!config.is_on_allowlist("mylib::Gadget"));
+        assert!(config.is_on_blocklist("mylib::net::reset_internal"));
+        assert!(!config.is_on_blocklist("mylib::net::internal_reset"));
+        assert_eq!(config.get_blocklist().count(), 0);
+        assert_eq!(
+            config.must_generate_list().collect::<Vec<_>>(),
+            vec!["mylib::Widget".to_string()]
+        );
+        assert_eq!(
+            config.bindgen_allowlist().unwrap().next().unwrap(),
+            "mylib::net::.*"
+        );
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("a*b*c", "abc"));
+        assert!(matches_pattern("a*b*c", "a::xbyb::c"));
+        assert!(!matches_pattern("a*b*c", "a::c"));
+        assert!(matches_pattern("*", "anything"));
+        assert!(!matches_pattern("a*a", "a"));
+        assert_eq!(pattern_to_regex("ns::operator()*"), "ns::operator\\(\\).*");
+    }
+
+    #[test]
+    fn test_copy_pod_returns() {
+        let config: IncludeCppConfig = parse_quote! {
+            copy_pod_returns!(16)
+        };
+        assert_eq!(config.copy_pod_returns_max_size(), Some(16));
+        let config: IncludeCppConfig = parse_quote! {};
+        assert_eq!(config.copy_pod_returns_max_size(), None);
+    }
+
+    #[test]
+    fn test_generate_registry() {
+        let config: IncludeCppConfig = parse_quote! {
+            generate_registry!()
+        };
+        assert!(config.generate_registry());
+        let config: IncludeCppConfig = parse_quote! {};
+        assert!(!config.generate_registry());
+    }
+
+    #[test]
+    fn test_dynamic_library() {
+        let config: IncludeCppConfig = parse_quote! {
+            dynamic_library!("libplugin.so")
+        };
+        assert_eq!(config.dynamic_library(), Some("libplugin.so"));
+        let config: IncludeCppConfig = parse_quote! {};
+        assert_eq!(config.dynamic_library(), None);
+    }
+
+    #[test]
+    fn test_force_wrapper() {
+        let config: IncludeCppConfig = parse_quote! {
+            force_wrapper!("ns::get_point", "Shape::origin")
+        };
+        assert!(config.force_wrapper_for("ns::get_point"));
+        assert!(config.force_wrapper_for("Shape::origin"));
+        assert!(!config.force_wrapper_for("get_point"));
+    }
+
+    #[test]
+    fn test_export_macro() {
+        let config: IncludeCppConfig = parse_quote! {
+            export_macro!("MYLIB_EXPORT", "MYLIB_API")
+        };
+        assert_eq!(config.export_macros(), ["MYLIB_EXPORT", "MYLIB_API"]);
+        let config: IncludeCppConfig = parse_quote! {};
+        assert!(config.export_macros().is_empty());
+        assert!(syn::parse_str::<IncludeCppConfig>(r#"export_macro!("MYLIB EXPORT")"#).is_err());
+    }
+
+    #[test]
+    fn test_async_wrapper() {
+        let config: IncludeCppConfig = parse_quote! {
+            async_wrapper!("compress", "ns::checksum")
+        };
+        assert!(config.async_wrapper_for("compress"));
+        assert!(config.async_wrapper_for("ns::checksum"));
+        assert!(!config.async_wrapper_for("checksum"));
+        let config: IncludeCppConfig = parse_quote! {
+            async_wrapper!("compress")
+            async_wrapper!("decompress")
+        };
+        assert!(config.async_wrapper_for("decompress"));
+    }
+
+    #[test]
+    fn test_abi_self_test() {
+        let config: IncludeCppConfig = parse_quote! {
+            abi_self_test!()
+        };
+        assert!(config.abi_self_test());
+        let config: IncludeCppConfig = parse_quote! {};
+        assert!(!config.abi_self_test());
+    }
+
+    #[test]
+    fn test_auto_pod() {
+        let config: IncludeCppConfig = parse_quote! {
+            auto_pod!()
+            non_pod!("ns::Handle")
+        };
+        assert!(config.auto_pod());
+        assert!(config.is_non_pod("ns::Handle"));
+        assert!(!config.is_non_pod("ns::Point"));
+        let config: IncludeCppConfig = parse_quote! {};
+        assert!(!config.auto_pod());
+    }
+
+    #[test]
+    fn test_unpin_pod_methods() {
+        let config: IncludeCppConfig = parse_quote! {
+            unpin_pod_methods!()
+        };
+        assert!(config.unpin_pod_methods());
+        let config: IncludeCppConfig = parse_quote! {};
+        assert!(!config.unpin_pod_methods());
+    }
+
+    #[test]
+    fn test_opaque_pod_pointers() {
+        let config: IncludeCppConfig = parse_quote! {
+            opaque_pod_pointers!()
+        };
+        assert!(config.opaque_pod_pointers());
+        let config: IncludeCppConfig = parse_quote! {};
+        assert!(!config.opaque_pod_pointers());
+    }
+
+    #[test]
+    fn test_bridge_shards() {
+        let config: IncludeCppConfig = parse_quote! {
+            bridge_shards!(4)
+        };
+        assert_eq!(config.bridge_shards(), 4);
+        let config: IncludeCppConfig = parse_quote! {};
+        assert_eq!(config.bridge_shards(), 1);
+        assert!(syn::parse_str::<IncludeCppConfig>("bridge_shards!(0)").is_err());
+    }
+
+    #[test]
+    fn test_extern_cpp_ns() {
+        let config: IncludeCppConfig = parse_quote! {
+            extern_cpp_ns!("mylib::core", mylib_core_sys::ffi)
+        };
+        let path = config.extern_cpp_ns_path("mylib::core").unwrap();
+        assert_eq!(path.get_final_ident(), "ffi");
+        assert!(config.extern_cpp_ns_path("mylib::core::detail").is_some());
+        assert!(config.extern_cpp_ns_path("mylib::core_extra").is_none());
+        assert!(config.extern_cpp_ns_path("mylib").is_none());
+    }
+
+    #[test]
+    fn test_project_defaults_dont_override_directives() {
+        let mut config: IncludeCppConfig = parse_quote! {
+            safety!(unsafe_ffi)
+        };
+        config.apply_project_defaults(ProjectDefaults {
+            unsafe_policy: Some(UnsafePolicy::AllFunctionsUnsafe),
+            naming_policy: Some(NamingPolicy::SnakeCase),
+            ..Default::default()
+        });
+        assert_eq!(config.unsafe_policy, UnsafePolicy::AllFunctionsSafe);
+        assert_eq!(config.naming_policy(), NamingPolicy::SnakeCase);
+    }
+
+    #[test]
+    fn test_allowlist_file() {
+        let mut config: IncludeCppConfig = parse_quote! {
+            allowlist_file!("autocxx_allow.toml")
+        };
+        assert_eq!(config.allowlist_files(), ["autocxx_allow.toml"]);
+        config
+            .add_external_allowlist(ExternalAllowlist {
+                generate: vec!["Foo".into()],
+                generate_pod: vec!["Bar".into()],
+                block: vec!["Baz".into()],
+                ..Default::default()
+            })
+            .unwrap();
+        config.confirm_complete(false).unwrap();
+        assert!(config.is_on_allowlist("Foo"));
+        assert!(config.is_on_allowlist("Bar"));
+        assert_eq!(config.get_pod_requests(), ["Bar"]);
+        assert!(config.is_on_blocklist("Baz"));
+    }
+
+    #[test]
+    fn test_constructor_naming_by_parameters() {
+        let cn: ConstructorNaming = parse_quote! {
+            by_parameters
+        };
+        assert_eq!(cn, ConstructorNaming::ByParameters)
+    }
+
+    #[test]
+    fn test_naming_policy_snake_case() {
+        let np: NamingPolicy = parse_quote! {
+            snake_case
+        };
+        assert_eq!(np, NamingPolicy::SnakeCase)
+    }
 }
@@ -0,0 +1,74 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt::Display;
+
+/// The names of the canonical `absl::StatusCode`s, indexed by code.
+const CODE_NAMES: &[&str] = &[
+    "OK",
+    "CANCELLED",
+    "UNKNOWN",
+    "INVALID_ARGUMENT",
+    "DEADLINE_EXCEEDED",
+    "NOT_FOUND",
+    "ALREADY_EXISTS",
+    "PERMISSION_DENIED",
+    "RESOURCE_EXHAUSTED",
+    "FAILED_PRECONDITION",
+    "ABORTED",
+    "OUT_OF_RANGE",
+    "UNIMPLEMENTED",
+    "INTERNAL",
+    "UNAVAILABLE",
+    "DATA_LOSS",
+    "UNAUTHENTICATED",
+];
+
+/// An error returned by a C++ function which returns `absl::Status` or
+/// `absl::StatusOr<T>`. autocxx turns such functions into Rust functions
+/// returning `Result<(), AbslStatus>` or `Result<T, AbslStatus>`
+/// respectively. This is a copy of the code and message of the original
+/// `absl::Status`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AbslStatus {
+    code: i32,
+    message: String,
+}
+
+impl AbslStatus {
+    /// Creates a status from its parts. This is called by generated code;
+    /// you shouldn't need to call it yourself.
+    #[doc(hidden)]
+    pub fn new(code: i32, message: String) -> Self {
+        Self { code, message }
+    }
+
+    /// The `absl::StatusCode`, as an integer.
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// The error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for AbslStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match usize::try_from(self.code)
+            .ok()
+            .and_then(|code| CODE_NAMES.get(code))
+        {
+            Some(name) => write!(f, "{}: {}", name, self.message),
+            None => write!(f, "Unknown code({}): {}", self.code, self.message),
+        }
+    }
+}
+
+impl std::error::Error for AbslStatus {}
@@ -0,0 +1,158 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for calling blocking C++ functions from async Rust.
+//!
+//! If you name a function in the `async_wrapper!` directive within
+//! [`crate::include_cpp`], then as well as `foo` you get `foo_async`,
+//! which returns a future. The call itself is made on a thread suitable
+//! for blocking work, so that it doesn't hold up your async executor. By
+//! default that's a new thread for each call, but you'll probably want to
+//! use your executor's own pool for blocking work instead, by registering
+//! a hook using [`set_spawn_blocking_hook`]. For example, with `tokio`:
+//!
+//! ```rust,ignore
+//! autocxx::blocking::set_spawn_blocking_hook(|job| {
+//!     tokio::task::spawn_blocking(job);
+//! });
+//! ```
+//!
+//! This doesn't depend upon any particular async runtime.
+
+use std::{
+    future::Future,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+/// A job to be run on a thread suitable for blocking work.
+pub type BlockingJob = Box<dyn FnOnce() + Send + 'static>;
+
+/// A function which arranges for a [`BlockingJob`] to be run on a thread
+/// suitable for blocking work, such as `tokio::task::spawn_blocking`.
+pub type SpawnBlockingHook = fn(BlockingJob);
+
+static SPAWN_BLOCKING_HOOK: AtomicPtr<()> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Registers a hook to run each blocking call made by an `_async`
+/// wrapper, replacing any hook previously registered.
+pub fn set_spawn_blocking_hook(hook: SpawnBlockingHook) {
+    SPAWN_BLOCKING_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Unregisters any hook registered using [`set_spawn_blocking_hook`], so
+/// that each blocking call is once again made on a new thread.
+pub fn clear_spawn_blocking_hook() {
+    SPAWN_BLOCKING_HOOK.store(std::ptr::null_mut(), Ordering::Release);
+}
+
+enum State<R> {
+    Pending(Option<Waker>),
+    Done(thread::Result<R>),
+    Abandoned,
+    Taken,
+}
+
+/// A future which resolves to the result of a call made on a thread
+/// suitable for blocking work. Returned by the `_async` wrapper functions
+/// generated for `async_wrapper!`.
+///
+/// If the call panics, so does polling this future. Dropping this future
+/// doesn't stop the call, which will run to completion and then have its
+/// result discarded.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct BlockingCall<R> {
+    state: Arc<Mutex<State<R>>>,
+}
+
+impl<R> Future for BlockingCall<R> {
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        let mut state = self.state.lock().unwrap();
+        match std::mem::replace(&mut *state, State::Taken) {
+            State::Pending(_) => {
+                *state = State::Pending(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+            State::Done(Ok(ret)) => Poll::Ready(ret),
+            State::Done(Err(payload)) => {
+                drop(state);
+                resume_unwind(payload)
+            }
+            State::Abandoned => panic!("the spawn_blocking hook dropped a call without running it"),
+            State::Taken => panic!("BlockingCall polled after completion"),
+        }
+    }
+}
+
+/// Owned by the job, so that if the hook drops the job without running it,
+/// the future finds out rather than waiting forever.
+struct Completion<R> {
+    state: Arc<Mutex<State<R>>>,
+    completed: bool,
+}
+
+impl<R> Completion<R> {
+    fn complete(mut self, result: thread::Result<R>) {
+        self.finish(State::Done(result));
+        self.completed = true;
+    }
+
+    fn finish(&self, result: State<R>) {
+        let waker = match std::mem::replace(&mut *self.state.lock().unwrap(), result) {
+            State::Pending(waker) => waker,
+            _ => None,
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<R> Drop for Completion<R> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.finish(State::Abandoned);
+        }
+    }
+}
+
+/// Makes a call on a thread suitable for blocking work, using any hook
+/// registered with [`set_spawn_blocking_hook`], and returns a future which
+/// resolves to its result.
+pub fn spawn_blocking<R, F>(call: F) -> BlockingCall<R>
+where
+    R: Send + 'static,
+    F: FnOnce() -> R + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(State::Pending(None)));
+    let completion = Completion {
+        state: state.clone(),
+        completed: false,
+    };
+    let job: BlockingJob = Box::new(move || {
+        let result = catch_unwind(AssertUnwindSafe(call));
+        completion.complete(result);
+    });
+    let hook = SPAWN_BLOCKING_HOOK.load(Ordering::Acquire);
+    if hook.is_null() {
+        thread::spawn(job);
+    } else {
+        // Safety: the only non-null values ever stored are `SpawnBlockingHook`s.
+        let hook = unsafe { std::mem::transmute::<*mut (), SpawnBlockingHook>(hook) };
+        hook(job);
+    }
+    BlockingCall { state }
+}
@@ -0,0 +1,64 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for observing calls from Rust into C++.
+//!
+//! If you use the `instrument_calls!` directive within [`crate::include_cpp`],
+//! every call into C++ made through the generated bindings is routed
+//! through this module. Unless the `instrumentation` feature of this crate
+//! is enabled, that does nothing at all. If it is enabled:
+//! * each call is made within a [`tracing`](https://docs.rs/tracing) span
+//!   named `autocxx_ffi_call`, at `TRACE` level, with a `function` field;
+//! * any hook registered using [`set_call_hook`] is told the name of the
+//!   C++ function and how long the call took.
+
+/// A function to be told about each instrumented call into C++: the name
+/// of the C++ function (e.g. `ns::Type::method`) and how long it took.
+pub type CallHook = fn(&'static str, std::time::Duration);
+
+#[cfg(feature = "instrumentation")]
+static CALL_HOOK: std::sync::atomic::AtomicPtr<()> =
+    std::sync::atomic::AtomicPtr::new(std::ptr::null_mut());
+
+/// Registers a hook to be called after each instrumented call into C++,
+/// replacing any hook previously registered.
+#[cfg(feature = "instrumentation")]
+pub fn set_call_hook(hook: CallHook) {
+    CALL_HOOK.store(hook as *mut (), std::sync::atomic::Ordering::Release);
+}
+
+/// Unregisters any hook registered using [`set_call_hook`].
+#[cfg(feature = "instrumentation")]
+pub fn clear_call_hook() {
+    CALL_HOOK.store(std::ptr::null_mut(), std::sync::atomic::Ordering::Release);
+}
+
+/// Makes a call into C++ on behalf of generated code. Not intended for
+/// direct use.
+#[doc(hidden)]
+#[inline]
+pub fn instrumented<R>(name: &'static str, call: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "instrumentation")]
+    {
+        let _span = tracing::trace_span!("autocxx_ffi_call", function = name).entered();
+        let start = std::time::Instant::now();
+        let ret = call();
+        let hook = CALL_HOOK.load(std::sync::atomic::Ordering::Acquire);
+        if !hook.is_null() {
+            // Safety: the only non-null values ever stored are `CallHook`s.
+            let hook = unsafe { std::mem::transmute::<*mut (), CallHook>(hook) };
+            hook(name, start.elapsed());
+        }
+        ret
+    }
+    #[cfg(not(feature = "instrumentation"))]
+    {
+        let _ = name;
+        call()
+    }
+}
@@ -14,8 +14,22 @@
 // do anything - all the magic is handled entirely by
 // autocxx_macro::include_cpp_impl.
 
+mod absl_status;
+pub mod blocking;
+pub mod instrumentation;
+mod observer;
+mod os_string;
+pub mod qt;
+mod rc_handle;
+#[cfg(feature = "reflection")]
+pub mod reflection;
+mod shared_ptr;
+pub mod simd;
+mod smart_ptr;
 pub mod subclass;
+mod unique_array;
 mod value_param;
+mod vector;
 
 #[cfg_attr(doc, aquamarine::aquamarine)]
 /// Include some C++ headers in your Rust project.
@@ -116,6 +130,8 @@ macro_rules! include {
 }
 
 /// Generate Rust bindings for the given C++ type or function.
+/// The name may instead be a pattern, in which each `*` matches any
+/// sequence of characters including `::`, such as `generate!("mylib::net::*")`.
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 /// See also [generate_pod].
@@ -180,6 +196,8 @@ macro_rules! exclude_utilities {
 /// otherwise generated.
 /// This is 'greedy' in the sense that any functions/methods
 /// which take or return such a type will _also_ be blocked.
+/// As with [generate], the name may be a pattern containing `*`,
+/// such as `block!("*_internal")`.
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
@@ -260,6 +278,678 @@ macro_rules! exclude_impls {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
+/// Choose how generated Rust methods are named. By default, methods
+/// retain their C++ names. Specify `naming_policy!(snake_case)` to
+/// convert `camelCase` or `PascalCase` method names to `snake_case`
+/// instead, such that they're idiomatic Rust. The original C++ name
+/// is still used to call the method.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! naming_policy {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Strip a prefix from the names of C++ functions and types, as seen
+/// from Rust. For instance, if all your C APIs begin with `mylib_`,
+/// then `strip_prefix!("mylib_")` would allow you to call
+/// `ffi::init()` instead of `ffi::mylib_init()`. The C++ names are
+/// unaffected. If stripping a prefix would cause two items to have
+/// the same name, the item whose prefix was stripped is not generated,
+/// and an error is reported.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! strip_prefix {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate Rust bindings for the given C++ item, as [`generate`], but
+/// attach a `cfg` condition to the resulting Rust APIs. This is useful
+/// where a header only declares certain items on some platforms.
+/// For example,
+/// `generate_cfg!("WinOnlyThing", r#"cfg(windows)"#)`.
+/// Unlike [`generate`], it's not an error if the item can't be
+/// found, because it's assumed that it's absent on this platform.
+///
+/// The condition only applies to the Rust APIs within `ffi`. If the item
+/// is found in the headers, the `cxx::bridge` declarations and any C++
+/// wrappers for it are generated and built regardless, so the condition
+/// should match whatever decides whether the headers declare it. It
+/// can't be used to leave out an item which the headers always declare
+/// but which can't be built in some configurations.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! generate_cfg {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Treat one of a C++ function's pointer parameters as an additional
+/// return value. For example, given `bool get(Foo* out)`,
+/// `out_param!("get", 0)` would produce a Rust function
+/// `fn get() -> Option<Foo>`. The rules are:
+/// * If the C++ function returns `bool`, the Rust function returns
+///   `Option<T>`, which is `Some` when the C++ function returned `true`.
+/// * If the C++ function returns `void`, the Rust function returns `T`.
+/// * Otherwise the Rust function returns a tuple of the C++ return value
+///   and `T`.
+///
+/// The parameter index doesn't count `this`. The parameter must be a non-const
+/// pointer to a POD type (see [`generate_pod`]) or a primitive, and the
+/// function must be a free function or a non-virtual method, given with
+/// its full C++ name (e.g. `"ns::get"` or `"ns::Type::get"`).
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! out_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Say which parameter a reference returned by a C++ function borrows from.
+/// For example, given `const Item& pick(const List& list, const Filter& f)`,
+/// `borrows_from!("pick", 0)` would produce a Rust function
+/// `fn pick<'a>(list: &'a List, f: &Filter) -> &'a Item`. Without this,
+/// functions which return a reference are only generated if they have
+/// exactly one reference parameter, or if they're methods, in which case
+/// the returned reference borrows from `self`.
+///
+/// The parameter index doesn't count `this`, and the parameter must be a
+/// reference. The function must be given with its full C++ name (e.g.
+/// `"ns::pick"` or `"ns::Type::pick"`).
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! borrows_from {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Make functions which return a `std::string` by value return a Rust
+/// `String` instead of a `UniquePtr<CxxString>`. This copies the string,
+/// replacing any invalid UTF-8 with `U+FFFD`. Use `string_returns!()` to do
+/// this for every such function, or give the full C++ names of specific
+/// functions or methods, e.g.
+/// `string_returns!("ns::get_name", "ns::Person::describe")`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! string_returns {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Combine a C++ function's pointer and length parameters into a single
+/// Rust slice parameter. For example, given
+/// `uint32_t write(const uint8_t* data, size_t len)`,
+/// `slice_params!("write", 0, 1)` would produce a Rust function
+/// `fn write(data: &[u8]) -> u32`. A non-const pointer becomes a `&mut`
+/// slice. The Rust function panics if the slice's length doesn't fit in the
+/// length parameter's type.
+///
+/// The parameter indices don't count `this`. The pointer must point to a POD
+/// type (see [`generate_pod`]) or a primitive, the length must be an integer,
+/// and the function must be a free function or a non-virtual method, given
+/// with its full C++ name (e.g. `"ns::write"` or `"ns::Type::write"`). The
+/// directive may be given more than once for the same function.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! slice_params {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Treat a C++ enum as an error code. Any function which returns this
+/// enum will instead return a `Result` in Rust: `Ok` if the function
+/// returned the given success value, `Err` containing the enum otherwise.
+/// For example, `error_code!("VkResult", "VK_SUCCESS")`.
+///
+/// This composes with [`out_param`]: a function
+/// `VkResult create(Thing* out)` with an out parameter would return
+/// `Result<Thing, VkResult>`. Otherwise, the `Ok` type is `()`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! error_code {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Treat a C++ template which holds either a value or an error, such as
+/// `tl::expected<T, E>`, like a Rust `Result`. Any function which returns
+/// an instantiation of this template by value will instead return
+/// `Result<T, E>`. The arguments are the full name of the template, then the
+/// names of the methods which move out its value and its error. For
+/// example, `result_type!("tl::expected", "value", "error")`.
+///
+/// The template must have a `has_value()` method, and its first two
+/// template parameters must be the value and error types. A `void` value
+/// becomes `()`. As with other return values, values and errors which
+/// aren't [POD](generate_pod) types or primitives are returned as
+/// `UniquePtr`s. `absl::Status` and `absl::StatusOr<T>` are handled in a
+/// similar way without this directive; see [`AbslStatus`].
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! result_type {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate a safe way to register a listener (or observer) with a C++
+/// object, given the full C++ names of a pair of methods which add and
+/// remove listeners. For example, given a class `Subject` with methods
+/// `void addListener(Listener* l)` and `void removeListener(Listener* l)`,
+/// `observer_registration!("Subject::addListener", "Subject::removeListener")`
+/// generates a method
+/// `fn addListener_guarded<'a>(self: Pin<&'a mut Subject>, listener: Pin<&'a mut Listener>)`
+/// returning an [`ObserverRegistration`]. That calls `addListener`
+/// immediately and `removeListener` when it's dropped, and borrows both the
+/// subject and the listener in the meantime.
+///
+/// Both methods must be non-const, non-virtual methods of the same class,
+/// each taking a single non-const pointer to the listener type.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! observer_registration {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Describe a C++ smart pointer template, such as an intrusively
+/// reference-counted `RefPtr<T>`, given its full name and the method which
+/// returns the raw pointer it holds. For example,
+/// `smart_pointer!("RefPtr", "get")`. Any function which returns an
+/// instantiation of this template by value will instead return a
+/// [`SmartPtr`], which dereferences to the object pointed to.
+///
+/// Cloning and dropping a [`SmartPtr`] use the smart pointer's copy
+/// constructor and destructor, which must therefore be what manage the
+/// reference count or other ownership. The pointee type must be the
+/// template's first parameter.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! smart_pointer {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Describe a reference-counted C++ class which must only be managed
+/// through its own functions to add and release a reference, as in ICU or
+/// Skia. The arguments are the full name of the class and the names of
+/// those two functions, each of which takes a pointer to the object, for
+/// example `refcounted!("SkData", "SkSafeRef", "SkSafeUnref")`.
+///
+/// Any function or method which returns a pointer to the class will
+/// instead return an [`RcHandle`], which takes over the reference which
+/// the function returned. Cloning the handle adds a reference and dropping
+/// it releases one. The class's constructors aren't generated, since the
+/// objects they made would be owned by a `UniquePtr` rather than by their
+/// reference count.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! refcounted {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Describe a C API which reports errors errno-style: the function
+/// returns a particular value to indicate failure, and the actual error
+/// must then be fetched from elsewhere. The arguments are the name of the
+/// function, the name of a free function taking no parameters which
+/// fetches the error (such as `GetLastError`, or a one-line C++ wrapper
+/// returning `errno`), and a C++ expression for the failure value, for
+/// example `last_error!("open", "get_errno", "-1")`.
+///
+/// The function then returns a `Result` whose `Ok` value is its return
+/// value and whose `Err` value is the error. The error is fetched within
+/// the generated C++ wrapper straight after the call, before anything else
+/// can overwrite it.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! last_error {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Describe where to find the length of the array returned by a function
+/// which returns a `std::unique_ptr<T[]>`, so that it can instead return a
+/// [`UniqueArray`]. The first argument is the C++ name of the function or
+/// method, e.g. `"make_buffer"` or `"Image::pixels"`. The second is either
+/// the index of an integer parameter holding the length, not counting any
+/// `this` parameter, or the Rust name of a function taking no parameters
+/// which returns it. For a method, that's another method of the same
+/// object; for a free function, another free function in the same
+/// namespace. For example,
+/// `unique_array!("make_buffer", 0)` or
+/// `unique_array!("Image::pixels", "pixel_count")`.
+///
+/// The elements must be POD types or primitives, and the length function
+/// must return a primitive integer.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! unique_array {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate conversions between a dense matrix type, such as
+/// `Eigen::MatrixXd`, and Rust slices. The arguments are the full name of
+/// the C++ type and the Rust type of its elements, for example
+/// `dense_matrix!("Eigen::MatrixXd", "f64")`. The type gains methods
+/// `from_slice(data: &[f64], rows: usize, cols: usize) -> UniquePtr<Self>`,
+/// `as_slice`, `as_mut_slice` and `dims`. Elements are in column-major
+/// order, as Eigen stores them by default.
+///
+/// The type needs a `Scalar` typedef, plus `resize(rows, cols)`, `data()`,
+/// `size()`, `rows()` and `cols()` methods, as Eigen's dense types have.
+/// It's generated automatically, so needn't also be named in [`generate`].
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! dense_matrix {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Give a name to a particular instantiation of a C++ template, for example
+/// `concrete!("StringMap<int>", IntStringMap)`. The instantiation becomes
+/// an opaque Rust type of that name, which C++ functions may take and
+/// return by reference or within a `UniquePtr`.
+///
+/// The template may be an alias template, such as
+/// `template <typename T> using StringMap = std::map<std::string, T>;`.
+/// In that case the alias is resolved, so C++ sees the instantiation of the
+/// underlying template, here `std::map<std::string, int>`.
+///
+/// The type has no methods: bindgen doesn't tell us about the members of
+/// class templates, so we can't generate any for an instantiation.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! concrete {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate methods to get and set the public fields of a non-POD type,
+/// for example `field_accessors!("ns::Person")`. Rust can't see the fields
+/// of non-POD types, so for each field `name`, the type gains a method
+/// `name(&self)` returning a reference to it, and `set_name(self: Pin<&mut Self>, value)`
+/// which assigns to it.
+///
+/// Fields which are pointers, references or arrays are skipped. Every other
+/// public field must be assignable, so don't use this for types with `const`
+/// fields.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! field_accessors {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Declare that a field of a POD type is `volatile`, for example
+/// `volatile_field!("Regs", "status")`. bindgen doesn't tell autocxx
+/// about `volatile`, so without this the field would be an ordinary
+/// public field, which Rust may read and write as it pleases. Instead,
+/// the field is made private and the type gains `read_status` and
+/// `write_status` methods which use [`std::ptr::read_volatile`] and
+/// [`std::ptr::write_volatile`].
+///
+/// The type must be [`generate_pod`]ed. Pointers and references to
+/// `volatile` types aren't affected.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! volatile_field {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Adapt the bindings to classes processed by Qt's `moc`. The members
+/// which `moc` generates for each `Q_OBJECT` class (`metaObject`,
+/// `qt_metacast`, `qt_metacall`, `tr` and so on) are omitted, since
+/// they're neither useful nor safe to call from Rust. Slots and
+/// `Q_INVOKABLE` methods are ordinary methods, so they're available
+/// as usual if their class is [`generate`]d.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! qt_mode {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Allow Rust closures to be connected to a Qt signal, given its full C++
+/// name. For example, `qt_signal!("Counter::valueChanged")` generates a
+/// method `fn connect_valueChanged(self: Pin<&mut Counter>, handler: impl FnMut(u32) + 'static)`
+/// returning a [`qt::Connection`]. The closure is called each time the
+/// signal is emitted, until the connection is dropped.
+///
+/// The signal must be a non-const, non-virtual method whose parameters
+/// are all primitives or enums passed by value. Qt calls the closure on
+/// the thread which emits the signal, which must be the thread which
+/// made the connection. This is normally combined with [`qt_mode`].
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! qt_signal {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Give mutable methods of POD types a `&mut self` receiver rather than
+/// `self: Pin<&mut Self>`. POD types are `Unpin`, so pinning them achieves
+/// nothing. Without this directive, call such methods using the `pin_mut()`
+/// method which every POD type has, e.g. `point.pin_mut().normalize()`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! unpin_pod_methods {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Rename matching pairs of getters and setters to follow Rust
+/// conventions. If a class has both `getFoo()` and `setFoo(x)` methods
+/// (or `GetFoo`/`SetFoo`, or `get_foo`/`set_foo`), they'll be available
+/// in Rust as `foo()` and `set_foo(x)`, and their documentation will
+/// refer to each other. Methods without a partner are left alone.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! pair_accessors {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Choose how to name constructors. By default, overloaded constructors
+/// are numbered: `make_unique`, `make_unique1`, etc. (and likewise
+/// `new`, `new1`). With `constructor_naming!(by_parameters)`, constructors
+/// which take parameters are instead named after them, so
+/// `Rect(int width, int height)` becomes `make_unique_with_width_and_height`.
+/// Constructors whose parameters have the same names as another's are
+/// still numbered.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! constructor_naming {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate a C++ enum as a set of bitflags: a newtype with an associated
+/// constant per enumerator, supporting `|` and `&`, rather than a Rust enum
+/// which couldn't soundly represent combined values.
+/// The enum must also be generated using [generate] or similar.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! bitflags {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Route every call into C++ through [`instrumentation`], so that
+/// calls can be traced or timed when this crate's `instrumentation`
+/// feature is enabled. This makes every function and method use a
+/// wrapper, so it's not recommended unless you need it.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! instrument_calls {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate a function `ffi::autocxx_registry()` which returns a
+/// description of every generated type and function, including a way to
+/// construct each type which has a default constructor. This is intended
+/// for exposing the bound C++ API to scripting languages. It requires
+/// this crate's `reflection` feature; see `autocxx::reflection`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! generate_registry {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Look up generated functions at runtime in a shared library, e.g.
+/// `dynamic_library!("libplugin.so")`, rather than linking against them.
+/// This is intended for plugin hosts which load C++ libraries dynamically.
+/// The library is loaded using `dlopen` when the first such function is
+/// called, and each function is found using `dlsym` on its first call. If
+/// the library or a symbol can't be found, a C++ exception is thrown,
+/// which terminates the program unless the function returns a `Result`.
+///
+/// This applies to free functions and to static and non-virtual methods.
+/// Constructors, destructors and virtual methods are still linked in
+/// the normal way, as are inline functions. It relies on the Itanium C++
+/// ABI, so it's not supported on Windows. On some platforms you'll need
+/// to link against `libdl`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! dynamic_library {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Always call the named functions or methods through a generated C++
+/// wrapper function, e.g. `force_wrapper!("ns::get_point", "Shape::origin")`.
+/// Normally autocxx lets cxx call C++ functions directly where it can;
+/// this is a workaround if that goes wrong for some function, for instance
+/// because of ABI subtleties when returning small structs by value.
+/// It's also the way to call a function which [export_macro] finds isn't
+/// exported: the wrapper is inline in the generated header, so it works if
+/// the generated C++ is built into the same library.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! force_wrapper {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Say that the C++ library is built with `-fvisibility=hidden`, so it
+/// exports only functions marked with the named macros, e.g.
+/// `export_macro!("MYLIB_EXPORT")`. autocxx reads the headers again as
+/// though building the library, with each macro giving default visibility,
+/// and skips any out-of-line function which isn't exported, saying why,
+/// rather than generating bindings which fail to link. Inline functions
+/// are unaffected. If your headers define the macros themselves, they must
+/// leave them alone when they're already defined.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! export_macro {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Also generate an async variant of each of the named free functions,
+/// e.g. `async_wrapper!("compress", "ns::checksum")`. As well as
+/// `compress`, you'll get `compress_async`, which takes the same
+/// parameters and returns a future, so that async code can call a
+/// blocking C++ function without holding up its executor. The call is
+/// made on a thread chosen by the hook registered with
+/// [`blocking::set_spawn_blocking_hook`] - see [`blocking`].
+///
+/// The parameters and return value must be able to move between threads,
+/// so they must be primitives, enums, POD structs without pointer fields,
+/// or strings returned as `String` (see [string_returns]).
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! async_wrapper {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate a `#[test]` for each POD type whose fields are all numbers or
+/// `bool`s. Each test fills in the fields from Rust, checks that C++ reads
+/// back the same values, then has C++ return a copy by value and checks
+/// that again. These run as part of `cargo test` for the crate containing
+/// the [include_cpp], so they catch Rust and C++ disagreeing about the
+/// layout of a type - for instance because a header has changed but the
+/// bindings haven't been regenerated.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! abi_self_test {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Make every type "plain old data" which autocxx can prove is safe to be
+/// held by value in Rust, as if each had been named in [generate_pod]. A
+/// type qualifies if it has no virtual functions, no user-declared
+/// destructor, copy or move constructor or assignment operator, and all its
+/// fields qualify too. Other types are generated as usual. Each decision,
+/// and the reason for it, is logged at `info` level. Use [non_pod] for
+/// types which should stay opaque regardless.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! auto_pod {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Don't make the given type "plain old data" under [auto_pod], for
+/// example because it holds a handle which mustn't be copied, even though
+/// C++ would allow that.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! non_pod {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Allow types to be "plain old data" even if they have pointer fields
+/// which autocxx can't otherwise represent, such as pointers to pointers
+/// or to types which aren't generated. Such fields become
+/// `*mut c_void` or `*const c_void`, which have the same layout, so you
+/// can still pass the pointer around but will need to cast it to use it.
+/// Pointer fields which autocxx can represent keep their types.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! opaque_pod_pointers {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Read further allowlist entries from a TOML file, which is found
+/// relative to the directory containing the `.rs` file. This is useful
+/// when bindings cover so many APIs that listing them all with [generate]
+/// becomes unwieldy. The file may contain lists named `generate`,
+/// `generate_pod`, `generate_ns` and `block`, each of which has the same
+/// meaning as the directive of the same name:
+/// ```toml
+/// generate = [ "Foo", "ns::bar" ]
+/// generate_pod = [ "Point" ]
+/// ```
+/// `autocxx_build` will arrange for bindings to be regenerated whenever
+/// this file changes.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! allowlist_file {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Entirely block everything within some namespace, in the same way as
+/// [block]. This is useful for namespaces holding implementation details,
+/// such as `mylib::internal`, when using [generate_ns] or [generate_all].
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! block_ns {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Use the types from some C++ namespace which were generated by another
+/// [include_cpp], typically in another crate, instead of generating them
+/// again, e.g.
+/// `extern_cpp_ns!("mylib::core", mylib_core_sys::ffi)`. The second
+/// argument is the path to the mod generated by that other [include_cpp].
+///
+/// This lets bindings for a large C++ project be split into several
+/// crates, one per namespace, which can be built separately. Functions
+/// in this namespace, including methods of its types, are left to the
+/// other [include_cpp] too. `autocxx-gen --split-ns` can generate such a
+/// set of crates from a single [include_cpp].
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! extern_cpp_ns {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Return small plain-old-data types by value from functions and methods
+/// which return a `const` reference to them, e.g. `copy_pod_returns!(16)`
+/// for types of up to 16 bytes. The value is copied in C++ before it's
+/// returned, so the Rust caller gets an owned value rather than a
+/// reference which borrows from one of the parameters. This is often
+/// simpler, and no slower, for small accessors such as
+/// `const Point& position() const`.
+///
+/// Only types which are POD (see [generate_pod]) are copied, and only
+/// plain functions and non-virtual methods are affected.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! copy_pod_returns {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Spread the generated bindings across several `#[cxx::bridge]` mods,
+/// e.g. `bridge_shards!(8)`. For very large sets of bindings, expanding
+/// one enormous bridge can dominate build times; smaller bridges are
+/// quicker for `cxx` and `rustc` to process. The generated API is the
+/// same whatever the number of shards.
+///
+/// Only plain functions and methods are moved into the extra shards,
+/// and each of those shards produces its own generated C++ file.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! bridge_shards {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Accept a `&CStr` for every `const char*` parameter, rather than a raw
+/// pointer. Since a `CStr` is always a valid NUL-terminated string, this
+/// allows many C-style functions to be called without `unsafe`. It applies
+/// to free functions and non-virtual methods; use a `CString` if you need
+/// to build the string at runtime.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! c_string_params {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
 /// Deprecated - use [`extern_rust_type`] instead.
 #[macro_export]
 #[deprecated]
@@ -421,11 +1111,50 @@ pub trait PinMut<T>: AsRef<T> {
     fn pin_mut(&mut self) -> std::pin::Pin<&mut T>;
 }
 
+/// Implemented by every C++ type for which autocxx generates bindings -
+/// structs, classes and enums, whether POD or not - so that generic code
+/// can find out about the C++ type behind a Rust type without relying
+/// on naming conventions.
+/// ```
+/// # use autocxx::AutocxxType;
+/// fn describe<T: AutocxxType>() -> String {
+///     format!("{} (in namespace '{}')", T::CPP_NAME, T::CPP_NAMESPACE)
+/// }
+/// ```
+pub trait AutocxxType: 'static {
+    /// The fully-qualified C++ name of this type, e.g. `ns::Outer::Inner`.
+    /// This is the same name as is given to [`cxx::type_id`] for this
+    /// type.
+    const CPP_NAME: &'static str;
+
+    /// The C++ namespace containing this type, e.g. `ns`, or an empty
+    /// string if it's in the global namespace.
+    const CPP_NAMESPACE: &'static str;
+
+    /// A [`std::any::TypeId`] for this type, for use as a key in
+    /// registries of C++ types.
+    fn type_id() -> std::any::TypeId {
+        std::any::TypeId::of::<Self>()
+    }
+}
+
+pub use absl_status::AbslStatus;
+pub use observer::ObserverRegistration;
+pub use os_string::CppOsString;
+pub use os_string::InteriorNulError;
+pub use os_string::PlatformChar;
+pub use rc_handle::RcHandle;
+pub use shared_ptr::SharedPtrUpcast;
+pub use shared_ptr::UpcastSharedPtr;
+pub use smart_ptr::SmartPtr;
+pub use unique_array::UniqueArray;
 pub use value_param::as_copy;
 pub use value_param::as_mov;
 pub use value_param::as_new;
 pub use value_param::ValueParam;
 pub use value_param::ValueParamHandler;
+pub use vector::CxxVectorUniquePtr;
+pub use vector::UniquePtrVectorElement;
 
 /// Imports which you're likely to want to use.
 pub mod prelude {
@@ -444,7 +1173,10 @@ pub mod prelude {
     pub use crate::c_void;
     pub use crate::cpp_semantics;
     pub use crate::include_cpp;
+    pub use crate::CppOsString;
+    pub use crate::CxxVectorUniquePtr;
     pub use crate::PinMut;
+    pub use crate::SharedPtrUpcast;
     pub use crate::ValueParam;
     pub use moveit::moveit;
     pub use moveit::new::New;
@@ -0,0 +1,64 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{marker::PhantomData, pin::Pin};
+
+/// A registration of some listener (or observer, or callback object) with
+/// a C++ subject, as created by the methods which autocxx generates for
+/// each [`observer_registration`](crate::observer_registration) directive.
+/// The listener is deregistered when this is dropped.
+///
+/// Both the subject and the listener remain mutably borrowed for as long as
+/// the registration exists, so neither can be moved or destroyed while the
+/// subject might still call the listener. If you need to call methods on the
+/// subject in the meantime, use [`ObserverRegistration::subject`].
+pub struct ObserverRegistration<'a, S, L> {
+    subject: Pin<&'a mut S>,
+    listener: *mut L,
+    remove: unsafe fn(Pin<&mut S>, *mut L),
+    phantom: PhantomData<Pin<&'a mut L>>,
+}
+
+impl<'a, S, L> ObserverRegistration<'a, S, L> {
+    /// Registers `listener` with `subject` using `add`, arranging for it to
+    /// be deregistered using `remove` on drop. This is called by generated
+    /// code; you shouldn't need to call it yourself.
+    ///
+    /// # Safety
+    ///
+    /// `add` and `remove` must be a matching pair of C++ methods which
+    /// register and deregister a listener, such that the subject won't
+    /// use the listener after `remove` returns.
+    #[doc(hidden)]
+    pub unsafe fn new(
+        mut subject: Pin<&'a mut S>,
+        listener: Pin<&'a mut L>,
+        add: unsafe fn(Pin<&mut S>, *mut L),
+        remove: unsafe fn(Pin<&mut S>, *mut L),
+    ) -> Self {
+        let listener = Pin::into_inner_unchecked(listener) as *mut L;
+        add(subject.as_mut(), listener);
+        Self {
+            subject,
+            listener,
+            remove,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Access the subject while the listener remains registered.
+    pub fn subject(&mut self) -> Pin<&mut S> {
+        self.subject.as_mut()
+    }
+}
+
+impl<'a, S, L> Drop for ObserverRegistration<'a, S, L> {
+    fn drop(&mut self) {
+        unsafe { (self.remove)(self.subject.as_mut(), self.listener) }
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{ffi::OsStr, fmt::Display};
+
+/// The character type which C++ APIs conventionally use for paths and
+/// other platform strings: `char` everywhere except Windows, where it's
+/// `wchar_t`.
+#[cfg(not(windows))]
+pub type PlatformChar = ::std::os::raw::c_char;
+
+/// The character type which C++ APIs conventionally use for paths and
+/// other platform strings: `char` everywhere except Windows, where it's
+/// `wchar_t`.
+#[cfg(windows)]
+pub type PlatformChar = u16;
+
+/// A platform string (such as a path) converted into the NUL-terminated
+/// form expected by C++ APIs which take a `const char*` on Unix or a
+/// `const wchar_t*` on Windows. The conversion is lossless: on Unix the
+/// bytes are passed through unchanged, and on Windows the string is
+/// encoded as UTF-16 exactly as the Windows API would expect.
+///
+/// ```
+/// # use autocxx::CppOsString;
+/// let path = std::path::Path::new("/tmp/foo.txt");
+/// let cpp_path = CppOsString::new(path).unwrap();
+/// let ptr = cpp_path.as_ptr();
+/// // Now pass `ptr` to a C++ function taking a `const char*`
+/// // or `const wchar_t*`, so long as `cpp_path` outlives the call.
+/// # let _ = ptr;
+/// ```
+///
+/// If the bindings represent the parameter type differently, for instance
+/// `wchar_t` as some C integer newtype, use [`pointer::cast`] on the
+/// result of [`CppOsString::as_ptr`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CppOsString(Vec<PlatformChar>);
+
+/// A string couldn't be converted to a [`CppOsString`] because it
+/// contained a NUL, which C++ would take to be the end of the string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InteriorNulError {
+    position: usize,
+}
+
+impl InteriorNulError {
+    /// The index (in platform characters) of the NUL.
+    pub fn nul_position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Display for InteriorNulError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "platform string contains a NUL at position {}",
+            self.position
+        )
+    }
+}
+
+impl std::error::Error for InteriorNulError {}
+
+impl CppOsString {
+    /// Convert a platform string into a form suitable for passing to C++.
+    pub fn new(s: impl AsRef<OsStr>) -> Result<Self, InteriorNulError> {
+        let mut chars = encode(s.as_ref());
+        if let Some(position) = chars.iter().position(|c| *c == 0) {
+            return Err(InteriorNulError { position });
+        }
+        chars.push(0);
+        Ok(Self(chars))
+    }
+
+    /// A pointer to the NUL-terminated string, valid for as long as this
+    /// object exists.
+    pub fn as_ptr(&self) -> *const PlatformChar {
+        self.0.as_ptr()
+    }
+
+    /// The number of platform characters, excluding the terminating NUL.
+    pub fn len(&self) -> usize {
+        self.0.len() - 1
+    }
+
+    /// Whether the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(unix)]
+fn encode(s: &OsStr) -> Vec<PlatformChar> {
+    use std::os::unix::ffi::OsStrExt;
+    s.as_bytes().iter().map(|b| *b as PlatformChar).collect()
+}
+
+#[cfg(windows)]
+fn encode(s: &OsStr) -> Vec<PlatformChar> {
+    use std::os::windows::ffi::OsStrExt;
+    s.encode_wide().collect()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn encode(s: &OsStr) -> Vec<PlatformChar> {
+    // No lossless way to get at the underlying representation.
+    s.to_string_lossy()
+        .bytes()
+        .map(|b| b as PlatformChar)
+        .collect()
+}
@@ -0,0 +1,89 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for connecting Rust closures to Qt signals, as requested by
+//! the [`qt_signal`](crate::qt_signal) directive.
+
+use std::{cell::RefCell, ffi::c_void};
+
+type Handler = RefCell<Box<dyn FnMut(SignalArgs)>>;
+
+/// A connection between a Qt signal and a Rust closure, as returned by the
+/// `connect_` methods which autocxx generates for each
+/// [`qt_signal`](crate::qt_signal) directive. The closure is disconnected
+/// and dropped when this is dropped.
+///
+/// Qt calls the closure on whichever thread emits the signal, so signals
+/// which are connected in this way must only be emitted on the thread which
+/// made the connection. (This type isn't `Send`, so the connection can't
+/// move elsewhere.)
+///
+/// The connection doesn't borrow the object which emits the signal. It's
+/// fine to destroy that object first; Qt will already have removed the
+/// connection, and dropping this does nothing more than free the closure.
+#[must_use = "the closure is disconnected as soon as the Connection is dropped"]
+pub struct Connection {
+    handle: usize,
+    disconnect: fn(usize),
+    handler: *mut Handler,
+}
+
+impl Connection {
+    /// Connects `handler` to a signal using `connect`, which must return a
+    /// handle which can later be passed to `disconnect`. This is called by
+    /// generated code; you shouldn't need to call it yourself.
+    #[doc(hidden)]
+    pub fn new(
+        handler: impl FnMut(SignalArgs) + 'static,
+        connect: impl FnOnce(fn(usize, usize), usize) -> usize,
+        disconnect: fn(usize),
+    ) -> Self {
+        let handler: Box<Handler> = Box::new(RefCell::new(Box::new(handler)));
+        let handler = Box::into_raw(handler);
+        let handle = connect(Self::trampoline, handler as usize);
+        Self {
+            handle,
+            disconnect,
+            handler,
+        }
+    }
+
+    fn trampoline(context: usize, args: usize) {
+        let handler = unsafe { &*(context as *const Handler) };
+        let mut handler = handler
+            .try_borrow_mut()
+            .expect("Qt signal emitted again from within its own Rust handler");
+        handler(SignalArgs(args as *const *const c_void));
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        (self.disconnect)(self.handle);
+        // Once disconnected, Qt will never call the trampoline again.
+        unsafe { drop(Box::from_raw(self.handler)) }
+    }
+}
+
+/// The arguments with which a Qt signal was emitted. This is used by
+/// generated code; you shouldn't need to use it yourself.
+#[doc(hidden)]
+pub struct SignalArgs(*const *const c_void);
+
+impl SignalArgs {
+    /// Copies out the argument at `idx`.
+    ///
+    /// # Safety
+    ///
+    /// The signal must have at least `idx + 1` parameters, and the parameter
+    /// at `idx` must be of type `T`.
+    pub unsafe fn get<T: Copy>(&self, idx: usize) -> T {
+        // The first element is always null.
+        *(*self.0.add(idx + 1) as *const T)
+    }
+}
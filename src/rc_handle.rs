@@ -0,0 +1,97 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::ops::Deref;
+
+/// A reference to an object of a reference-counted C++ class named in a
+/// [`refcounted`](crate::refcounted) directive. Functions returning a
+/// pointer to such a class return one of these.
+///
+/// Cloning this adds a reference, and dropping it releases one, using the
+/// functions named in the directive. It dereferences to the object.
+pub struct RcHandle<T> {
+    ptr: *mut T,
+    addref: unsafe fn(*mut T),
+    release: unsafe fn(*mut T),
+}
+
+impl<T> RcHandle<T> {
+    /// Takes over a reference to an object, given shims which add and
+    /// release a reference. This is called by generated code; you shouldn't
+    /// need to call it yourself.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or point to a live object, one of whose references
+    /// nothing else will release.
+    #[doc(hidden)]
+    pub unsafe fn from_raw(
+        ptr: *mut T,
+        addref: unsafe fn(*mut T),
+        release: unsafe fn(*mut T),
+    ) -> Self {
+        Self {
+            ptr,
+            addref,
+            release,
+        }
+    }
+
+    /// The object, unless C++ returned a null pointer.
+    pub fn as_ref(&self) -> Option<&T> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Whether C++ returned a null pointer.
+    pub fn is_null(&self) -> bool {
+        self.ptr.is_null()
+    }
+
+    /// The raw pointer, e.g. to pass back to C++. The reference remains
+    /// owned by this `RcHandle`.
+    pub fn as_ptr(&self) -> *mut T {
+        self.ptr
+    }
+
+    /// Gives up the reference without releasing it, e.g. to pass to a C++
+    /// function which takes it over.
+    pub fn into_raw(self) -> *mut T {
+        let ptr = self.ptr;
+        std::mem::forget(self);
+        ptr
+    }
+}
+
+impl<T> Deref for RcHandle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.as_ref().expect("called deref on a null RcHandle")
+    }
+}
+
+impl<T> Clone for RcHandle<T> {
+    fn clone(&self) -> Self {
+        if !self.ptr.is_null() {
+            unsafe { (self.addref)(self.ptr) }
+        }
+        Self {
+            ptr: self.ptr,
+            addref: self.addref,
+            release: self.release,
+        }
+    }
+}
+
+impl<T> Drop for RcHandle<T> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { (self.release)(self.ptr) }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Descriptions of generated bindings which can be inspected at runtime.
+//!
+//! If you use the `generate_registry!()` directive within
+//! [`crate::include_cpp`], and enable this crate's `reflection` feature,
+//! the generated `ffi` mod contains a function `autocxx_registry()`
+//! which returns a [`Registry`]. This lists each generated type and
+//! function, so that (for example) a scripting language bridge can
+//! expose the bound C++ API without parsing the C++ headers a second
+//! time.
+//!
+//! Types and signatures are described as they're declared in the
+//! `cxx::bridge`, as strings of Rust tokens.
+
+use std::any::{Any, TypeId};
+
+/// A description of all the types and free functions generated by one
+/// `include_cpp!`.
+#[derive(Debug)]
+pub struct Registry {
+    types: &'static [TypeInfo],
+    functions: &'static [FunctionInfo],
+}
+
+impl Registry {
+    /// Creates a registry. Not intended for direct use: it's called by
+    /// generated code.
+    #[doc(hidden)]
+    pub const fn new(types: &'static [TypeInfo], functions: &'static [FunctionInfo]) -> Self {
+        Self { types, functions }
+    }
+
+    /// All the generated types.
+    pub fn types(&self) -> &'static [TypeInfo] {
+        self.types
+    }
+
+    /// All the generated free functions, i.e. those which aren't methods.
+    pub fn functions(&self) -> &'static [FunctionInfo] {
+        self.functions
+    }
+
+    /// Finds a type by its fully-qualified C++ name, e.g. `ns::Foo`.
+    pub fn find_type(&self, cpp_name: &str) -> Option<&'static TypeInfo> {
+        self.types.iter().find(|ty| ty.cpp_name == cpp_name)
+    }
+
+    /// Finds a type by its Rust [`TypeId`].
+    pub fn find_type_by_id(&self, type_id: TypeId) -> Option<&'static TypeInfo> {
+        self.types.iter().find(|ty| (ty.type_id)() == type_id)
+    }
+
+    /// Finds a free function by its fully-qualified Rust name, e.g.
+    /// `ns::do_thing`.
+    pub fn find_function(&self, rust_name: &str) -> Option<&'static FunctionInfo> {
+        self.functions.iter().find(|f| f.rust_name == rust_name)
+    }
+}
+
+/// A description of a generated type.
+#[derive(Debug)]
+pub struct TypeInfo {
+    /// The fully-qualified Rust name, relative to the `ffi` mod, e.g.
+    /// `ns::Outer_Inner`.
+    pub rust_name: &'static str,
+    /// The fully-qualified C++ name, e.g. `ns::Outer::Inner`.
+    pub cpp_name: &'static str,
+    /// Returns the [`TypeId`] of the Rust type.
+    pub type_id: fn() -> TypeId,
+    /// If the type has an accessible default constructor, a function which
+    /// makes a new instance in a `cxx::UniquePtr<T>`, boxed as an [`Any`].
+    pub make_unique: Option<fn() -> Box<dyn Any>>,
+    /// The methods of this type, including static methods and constructors.
+    pub methods: &'static [FunctionInfo],
+}
+
+impl TypeInfo {
+    /// Finds a method by its Rust name.
+    pub fn find_method(&self, rust_name: &str) -> Option<&'static FunctionInfo> {
+        self.methods.iter().find(|m| m.rust_name == rust_name)
+    }
+}
+
+/// What sort of function a [`FunctionInfo`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionKind {
+    /// A free function.
+    Function,
+    /// A method which takes `&self`.
+    ConstMethod,
+    /// A method which takes `self: Pin<&mut Self>`.
+    MutMethod,
+    /// A static method.
+    StaticMethod,
+    /// A constructor, which returns `impl New<Output = Self>`, or a
+    /// `make_unique` function which returns a `cxx::UniquePtr<Self>`.
+    Constructor,
+}
+
+/// A description of a generated function or method.
+#[derive(Debug)]
+pub struct FunctionInfo {
+    /// For a free function, the fully-qualified Rust name relative to the
+    /// `ffi` mod; for a method, just its name.
+    pub rust_name: &'static str,
+    /// The fully-qualified C++ name, e.g. `ns::Foo::bar`.
+    pub cpp_name: &'static str,
+    /// What sort of function this is.
+    pub kind: FunctionKind,
+    /// The parameters, not including any `self`.
+    pub params: &'static [ParamInfo],
+    /// The return type, if any.
+    pub return_type: Option<&'static str>,
+}
+
+/// A description of a parameter to a generated function.
+#[derive(Debug)]
+pub struct ParamInfo {
+    /// The parameter name.
+    pub name: &'static str,
+    /// The Rust type of the parameter.
+    pub ty: &'static str,
+}
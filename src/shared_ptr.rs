@@ -0,0 +1,36 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use cxx::{memory::SharedPtrTarget, SharedPtr};
+
+/// Implemented for each C++ class `T` with a base class `B`, so that a
+/// `SharedPtr<T>` can be converted to a `SharedPtr<B>`, e.g. to pass to a
+/// function taking a `std::shared_ptr<B>`. Usually you'll want to call
+/// [`SharedPtrUpcast::upcast`] instead.
+pub trait UpcastSharedPtr<B: SharedPtrTarget>: SharedPtrTarget {
+    /// Returns a `SharedPtr<B>` which shares ownership with `ptr`.
+    fn upcast_shared_ptr(ptr: &SharedPtr<Self>) -> SharedPtr<B>;
+}
+
+/// Adds [`upcast`](SharedPtrUpcast::upcast) to `SharedPtr`.
+pub trait SharedPtrUpcast<T: SharedPtrTarget> {
+    /// Returns a `SharedPtr` to a base class of `T`, which shares
+    /// ownership with this one.
+    fn upcast<B: SharedPtrTarget>(&self) -> SharedPtr<B>
+    where
+        T: UpcastSharedPtr<B>;
+}
+
+impl<T: SharedPtrTarget> SharedPtrUpcast<T> for SharedPtr<T> {
+    fn upcast<B: SharedPtrTarget>(&self) -> SharedPtr<B>
+    where
+        T: UpcastSharedPtr<B>,
+    {
+        T::upcast_shared_ptr(self)
+    }
+}
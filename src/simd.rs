@@ -0,0 +1,94 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Wrappers for the SIMD vector types used by C++ intrinsics, such as
+//! `__m128` and `float32x4_t`. Functions which take or return these
+//! use these wrappers instead, which can be converted to and from the
+//! equivalent type in [`core::arch`] using `.0` or `From`. The x86 types
+//! lose their leading underscores, since `cxx` doesn't allow names
+//! containing `__`; the Arm types keep the same names.
+//!
+//! Only the types for the target architecture exist, just as the C++
+//! types are only available when compiling for that architecture.
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86 as arch;
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64 as arch;
+
+#[cfg(target_arch = "aarch64")]
+use core::arch::aarch64 as arch;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+macro_rules! simd_wrapper {
+    ($($r:ident = $c:ident),*) => {
+        $(
+            #[doc=concat!("Newtype wrapper for a `", stringify!($c), "`")]
+            #[derive(Debug, Copy, Clone)]
+            #[allow(non_camel_case_types)]
+            #[repr(transparent)]
+            pub struct $r(pub arch::$c);
+
+            /// # Safety
+            ///
+            /// We assert that the namespace and type ID refer to a C++
+            /// type which is equivalent to this Rust type.
+            unsafe impl cxx::ExternType for $r {
+                type Id = cxx::type_id!($r);
+                type Kind = cxx::kind::Trivial;
+            }
+
+            impl From<arch::$c> for $r {
+                fn from(val: arch::$c) -> Self {
+                    Self(val)
+                }
+            }
+
+            impl From<$r> for arch::$c {
+                fn from(val: $r) -> Self {
+                    val.0
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+simd_wrapper!(
+    m128 = __m128,
+    m128d = __m128d,
+    m128i = __m128i,
+    m256 = __m256,
+    m256d = __m256d,
+    m256i = __m256i
+);
+
+#[cfg(target_arch = "aarch64")]
+simd_wrapper!(
+    int8x8_t = int8x8_t,
+    int8x16_t = int8x16_t,
+    uint8x8_t = uint8x8_t,
+    uint8x16_t = uint8x16_t,
+    int16x4_t = int16x4_t,
+    int16x8_t = int16x8_t,
+    uint16x4_t = uint16x4_t,
+    uint16x8_t = uint16x8_t,
+    int32x2_t = int32x2_t,
+    int32x4_t = int32x4_t,
+    uint32x2_t = uint32x2_t,
+    uint32x4_t = uint32x4_t,
+    int64x1_t = int64x1_t,
+    int64x2_t = int64x2_t,
+    uint64x1_t = uint64x1_t,
+    uint64x2_t = uint64x2_t,
+    float32x2_t = float32x2_t,
+    float32x4_t = float32x4_t,
+    float64x1_t = float64x1_t,
+    float64x2_t = float64x2_t
+);
@@ -0,0 +1,98 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{ops::Deref, pin::Pin};
+
+use cxx::{memory::UniquePtrTarget, UniquePtr};
+
+use crate::ValueParam;
+
+/// A C++ smart pointer of type `P`, such as an intrusively
+/// reference-counted `RefPtr<T>`, pointing to a `T`. Functions returning a
+/// smart pointer named in a [`smart_pointer`](crate::smart_pointer)
+/// directive return one of these.
+///
+/// Cloning this copies the C++ smart pointer, and dropping it destroys
+/// that copy, so for a reference-counted pointer these add and release a
+/// reference. It dereferences to the `T`, and can be passed to C++
+/// functions which take the smart pointer by value.
+pub struct SmartPtr<P: UniquePtrTarget, T> {
+    ptr: UniquePtr<P>,
+    get: fn(&P) -> *const T,
+    clone: fn(&P) -> UniquePtr<P>,
+}
+
+impl<P: UniquePtrTarget, T> SmartPtr<P, T> {
+    /// Wraps a smart pointer, given shims which return its raw pointer and
+    /// copy it. This is called by generated code; you shouldn't need to call
+    /// it yourself.
+    #[doc(hidden)]
+    pub fn new(ptr: UniquePtr<P>, get: fn(&P) -> *const T, clone: fn(&P) -> UniquePtr<P>) -> Self {
+        Self { ptr, get, clone }
+    }
+
+    /// The object pointed to, unless this is null.
+    pub fn as_ref(&self) -> Option<&T> {
+        unsafe { (self.get)(self.as_cpp()).as_ref() }
+    }
+
+    /// The C++ smart pointer itself, e.g. to pass to a function which takes
+    /// it by reference.
+    pub fn as_cpp(&self) -> &P {
+        self.ptr
+            .as_ref()
+            .expect("SmartPtr always holds a smart pointer")
+    }
+
+    /// Unwraps the C++ smart pointer.
+    pub fn into_cpp(self) -> UniquePtr<P> {
+        self.ptr
+    }
+}
+
+impl<P: UniquePtrTarget, T> Deref for SmartPtr<P, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.as_ref().expect("called deref on a null SmartPtr")
+    }
+}
+
+impl<P: UniquePtrTarget, T> Clone for SmartPtr<P, T> {
+    fn clone(&self) -> Self {
+        Self {
+            ptr: (self.clone)(self.as_cpp()),
+            get: self.get,
+            clone: self.clone,
+        }
+    }
+}
+
+unsafe impl<P: UniquePtrTarget, T> ValueParam<P> for SmartPtr<P, T> {
+    type StackStorage = UniquePtr<P>;
+
+    unsafe fn populate_stack_space(self, stack: Pin<&mut Option<Self::StackStorage>>) {
+        self.ptr.populate_stack_space(stack)
+    }
+
+    fn get_ptr(stack: Pin<&mut Self::StackStorage>) -> *mut P {
+        <UniquePtr<P> as ValueParam<P>>::get_ptr(stack)
+    }
+}
+
+unsafe impl<'a, P: UniquePtrTarget, T> ValueParam<P> for &'a SmartPtr<P, T> {
+    type StackStorage = UniquePtr<P>;
+
+    unsafe fn populate_stack_space(self, stack: Pin<&mut Option<Self::StackStorage>>) {
+        (self.clone)(self.as_cpp()).populate_stack_space(stack)
+    }
+
+    fn get_ptr(stack: Pin<&mut Self::StackStorage>) -> *mut P {
+        <UniquePtr<P> as ValueParam<P>>::get_ptr(stack)
+    }
+}
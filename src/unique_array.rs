@@ -0,0 +1,101 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::ops::{Deref, DerefMut};
+
+/// An array allocated by C++ with `new[]`, returned as a
+/// `std::unique_ptr<T[]>` from a function named in a
+/// [`unique_array`](crate::unique_array) directive.
+///
+/// A `std::unique_ptr<T[]>` doesn't know its own length, so the directive
+/// says where to find it. This dereferences to a slice of that length, and
+/// `delete[]`s the array when dropped.
+pub struct UniqueArray<T> {
+    ptr: *mut T,
+    len: usize,
+    delete: unsafe fn(*mut T),
+}
+
+impl<T> UniqueArray<T> {
+    /// Takes ownership of an array, given a shim which `delete[]`s it. This
+    /// is called by generated code; you shouldn't need to call it yourself.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or point to `len` initialized elements allocated
+    /// with `new[]`, which nothing else owns.
+    #[doc(hidden)]
+    pub unsafe fn from_raw(ptr: *mut T, len: usize, delete: unsafe fn(*mut T)) -> Self {
+        Self { ptr, len, delete }
+    }
+
+    /// The number of elements, or zero if this is null.
+    pub fn len(&self) -> usize {
+        if self.ptr.is_null() {
+            0
+        } else {
+            self.len
+        }
+    }
+
+    /// Whether there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether C++ returned a null pointer.
+    pub fn is_null(&self) -> bool {
+        self.ptr.is_null()
+    }
+
+    /// The elements, as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        if self.ptr.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+        }
+    }
+
+    /// The elements, as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        if self.ptr.is_null() {
+            &mut []
+        } else {
+            unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+        }
+    }
+
+    /// The raw pointer to the first element, e.g. to pass back to C++.
+    /// This remains owned by the `UniqueArray`.
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+}
+
+impl<T> Deref for UniqueArray<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> DerefMut for UniqueArray<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T> Drop for UniqueArray<T> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { (self.delete)(self.ptr) }
+        }
+    }
+}
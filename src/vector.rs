@@ -0,0 +1,56 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::pin::Pin;
+
+use cxx::{memory::UniquePtrTarget, vector::VectorElement, CxxVector, UniquePtr};
+
+/// Implemented for each non-POD C++ class `T` which is found in a
+/// `std::vector<T>`, so that elements can be copied out of the vector and
+/// moved into it. cxx can only do that for trivial types. Usually you'll
+/// want to call the methods of [`CxxVectorUniquePtr`] instead.
+pub trait UniquePtrVectorElement: VectorElement + UniquePtrTarget {
+    /// Returns a copy of the element at `pos`, or a null `UniquePtr` if
+    /// there's no such element.
+    fn vector_get_copy(vec: &CxxVector<Self>, pos: usize) -> UniquePtr<Self>;
+
+    /// Moves the object owned by `value` onto the end of the vector,
+    /// leaving a moved-from object behind for `value` to destroy.
+    fn vector_push(vec: Pin<&mut CxxVector<Self>>, value: UniquePtr<Self>);
+}
+
+/// Adds [`get_copy`](CxxVectorUniquePtr::get_copy) and
+/// [`push_unique`](CxxVectorUniquePtr::push_unique) to `CxxVector`.
+pub trait CxxVectorUniquePtr<T: UniquePtrVectorElement> {
+    /// Returns a copy of the element at `pos`, or `None` if it's out of
+    /// bounds.
+    fn get_copy(&self, pos: usize) -> Option<UniquePtr<T>>;
+
+    /// Moves an object onto the end of the vector. Panics if `value` is
+    /// null.
+    fn push_unique(self: Pin<&mut Self>, value: UniquePtr<T>);
+}
+
+impl<T: UniquePtrVectorElement> CxxVectorUniquePtr<T> for CxxVector<T> {
+    fn get_copy(&self, pos: usize) -> Option<UniquePtr<T>> {
+        let value = T::vector_get_copy(self, pos);
+        if value.is_null() {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    fn push_unique(self: Pin<&mut Self>, value: UniquePtr<T>) {
+        assert!(
+            !value.is_null(),
+            "can't push a null UniquePtr onto a vector"
+        );
+        T::vector_push(self, value)
+    }
+}